@@ -0,0 +1,288 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::geometry::Rect;
+
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+static ANIMATIONS_ENABLED: AtomicBool = AtomicBool::new(true);
+
+// Mirrors the OS "show animations" preference. When disabled every transition jumps straight
+// to its target value.
+pub fn animations_enabled() -> bool {
+    ANIMATIONS_ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn set_animations_enabled(enabled: bool) {
+    ANIMATIONS_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+pub trait Clock {
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemClock {
+    epoch: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+#[derive(Default)]
+pub struct VirtualClock {
+    now: Cell<Duration>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+
+    pub fn set(&self, now: Duration) {
+        self.now.set(now);
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // CSS-style cubic-bezier(x1, y1, x2, y2)
+    CubicBezier(f32, f32, f32, f32),
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::CubicBezier(x1, y1, x2, y2) => cubic_bezier(x1, y1, x2, y2, t),
+        }
+    }
+}
+
+fn bezier_component(a: f32, b: f32, s: f32) -> f32 {
+    let inv = 1.0 - s;
+    3.0 * inv * inv * s * a + 3.0 * inv * s * s * b + s * s * s
+}
+
+fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32, t: f32) -> f32 {
+    // The curve is monotonic in x for x1, x2 in [0, 1], so bisect for the parameter that gives
+    // the requested x.
+    let (mut low, mut high) = (0.0f32, 1.0f32);
+    let mut s = t;
+
+    for _ in 0..32 {
+        let x = bezier_component(x1, x2, s);
+        if (x - t).abs() < 1e-5 {
+            break;
+        }
+
+        if x < t {
+            low = s;
+        } else {
+            high = s;
+        }
+        s = (low + high) / 2.0;
+    }
+
+    bezier_component(y1, y2, s)
+}
+
+pub trait Lerp: Copy {
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+impl Lerp for i32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        (self as f32).lerp(to as f32, t).round() as _
+    }
+}
+
+impl Lerp for Rect {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Rect {
+            left: self.left.lerp(to.left, t),
+            top: self.top.lerp(to.top, t),
+            right: self.right.lerp(to.right, t),
+            bottom: self.bottom.lerp(to.bottom, t),
+        }
+    }
+}
+
+pub struct Animated<T: Lerp> {
+    from: T,
+    to: T,
+    start: Option<Duration>,
+    duration: Duration,
+    easing: Easing,
+}
+
+impl<T: Lerp> Animated<T> {
+    pub fn new(value: T, duration: Duration, easing: Easing) -> Self {
+        Self {
+            from: value,
+            to: value,
+            start: None,
+            duration,
+            easing,
+        }
+    }
+
+    pub fn target(&self) -> T {
+        self.to
+    }
+
+    pub fn progress(&self, now: Duration) -> f32 {
+        let start = match self.start {
+            Some(start) if !self.duration.is_zero() && now < start + self.duration => start,
+            _ => return 1.0,
+        };
+
+        now.saturating_sub(start).as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    pub fn value(&self, now: Duration) -> T {
+        let progress = self.progress(now);
+        if progress >= 1.0 {
+            return self.to;
+        }
+
+        self.from.lerp(self.to, self.easing.apply(progress))
+    }
+
+    pub fn is_animating(&self, now: Duration) -> bool {
+        self.progress(now) < 1.0
+    }
+
+    // Starts a transition from wherever the value currently is, so interrupting a running
+    // animation never makes it jump.
+    pub fn animate_to(&mut self, target: T, now: Duration) {
+        if !animations_enabled() {
+            self.set(target);
+            return;
+        }
+
+        self.from = self.value(now);
+        self.to = target;
+        self.start = Some(now);
+    }
+
+    pub fn animate_from_to(&mut self, from: T, target: T, now: Duration) {
+        self.set(from);
+        self.animate_to(target, now);
+    }
+
+    pub fn set(&mut self, value: T) {
+        self.from = value;
+        self.to = value;
+        self.start = None;
+    }
+}
+
+// Keeps track of whether the frame timer of a component needs to be running. The component
+// owns the actual timer and just asks the scheduler what to do with it.
+pub struct FrameScheduler {
+    clock: Rc<dyn Clock>,
+    interval: Duration,
+    running: bool,
+    last_frame: Option<Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimerAction {
+    None,
+    Start(Duration),
+    Stop,
+}
+
+impl FrameScheduler {
+    pub fn new(clock: Rc<dyn Clock>) -> Self {
+        Self {
+            clock,
+            interval: FRAME_INTERVAL,
+            running: false,
+            last_frame: None,
+        }
+    }
+
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn last_frame(&self) -> Option<Duration> {
+        self.last_frame
+    }
+
+    pub fn request_frames(&mut self) -> TimerAction {
+        if self.running {
+            return TimerAction::None;
+        }
+
+        self.running = true;
+        TimerAction::Start(self.interval)
+    }
+
+    // Called on every timer tick after the animations were stepped.
+    pub fn frame(&mut self, still_animating: bool) -> TimerAction {
+        self.last_frame = Some(self.now());
+
+        if self.running && !still_animating {
+            self.running = false;
+            return TimerAction::Stop;
+        }
+
+        TimerAction::None
+    }
+}
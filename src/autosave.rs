@@ -0,0 +1,147 @@
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::animation::{Clock, TimerAction};
+
+// Changes in a row are saved once they stop for this long, or at the latest this long after
+// the first of them, so a steady stream of changes can't keep the session from being saved.
+pub const AUTOSAVE_DELAY: Duration = Duration::from_secs(2);
+pub const AUTOSAVE_MAX_DELAY: Duration = Duration::from_secs(30);
+
+// Keeps track of when the session should be saved next. Like `FrameScheduler`, the component
+// owns the actual timer and just asks what to do with it.
+pub struct Autosave {
+    clock: Rc<dyn Clock>,
+    delay: Duration,
+    max_delay: Duration,
+    // When the oldest and the newest change that wasn't saved yet happened
+    pending: Option<(Duration, Duration)>,
+}
+
+impl Autosave {
+    pub fn new(clock: Rc<dyn Clock>, delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            clock,
+            delay,
+            max_delay: max_delay.max(delay),
+            pending: None,
+        }
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    // When the changes so far are due to be saved, on the clock's time.
+    pub fn due(&self) -> Option<Duration> {
+        self.pending
+            .map(|(first, last)| (last + self.delay).min(first + self.max_delay))
+    }
+
+    // Called on every change worth saving. The timer gets restarted to fire when it is due.
+    pub fn changed(&mut self) -> TimerAction {
+        let now = self.clock.now();
+        let first = self.pending.map_or(now, |(first, _)| first);
+        self.pending = Some((first, now));
+        self.timer_until_due(now)
+    }
+
+    // Called when the timer fires. Returns whether the session should be saved now, after
+    // which the changes count as saved.
+    pub fn tick(&mut self) -> (bool, TimerAction) {
+        let now = self.clock.now();
+        match self.due() {
+            Some(due) if due <= now => {
+                self.pending = None;
+                (true, TimerAction::Stop)
+            }
+            // Timers may fire a little early
+            Some(_) => (false, self.timer_until_due(now)),
+            None => (false, TimerAction::Stop),
+        }
+    }
+
+    // The session got saved some other way, e.g. because the window closes.
+    pub fn cancel(&mut self) -> TimerAction {
+        self.pending = None;
+        TimerAction::Stop
+    }
+
+    fn timer_until_due(&self, now: Duration) -> TimerAction {
+        match self.due() {
+            Some(due) => TimerAction::Start(due.saturating_sub(now)),
+            None => TimerAction::Stop,
+        }
+    }
+}
+
+// Exists next to the session file while the app runs. Finding one at start means the last run
+// crashed or got killed before it could remove it, and its session was only autosaved.
+pub struct RunMarker {
+    path: PathBuf,
+}
+
+impl RunMarker {
+    // Marks the session at `session` as in use. Also tells whether the last run left its
+    // marker behind.
+    pub fn acquire(session: &Path) -> io::Result<(Self, bool)> {
+        let path = Self::path_for(session);
+        let unclean = path.try_exists()?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, std::process::id().to_string())?;
+
+        Ok((Self { path }, unclean))
+    }
+
+    pub fn path_for(session: &Path) -> PathBuf {
+        sibling(session, ".running")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The app is exiting cleanly. Not done on drop, unwinding from a panic isn't clean.
+    pub fn release(self) -> io::Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        }
+    }
+}
+
+// Replaces the file at `path` with `contents` through a temporary file next to it, so a crash
+// halfway through leaves either the old file or the new one and never a part of it.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let temp = sibling(path, ".tmp");
+    let result = fs::File::create(&temp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+
+    match result.and_then(|_| fs::rename(&temp, path)) {
+        Ok(()) => Ok(()),
+        Err(err) => {
+            let _ = fs::remove_file(&temp);
+            Err(err)
+        }
+    }
+}
+
+// `path` with `suffix` appended to its file name, e.g. `session` and `session.tmp`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(suffix);
+    PathBuf::from(name)
+}
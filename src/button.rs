@@ -1,32 +1,23 @@
-use std::mem::MaybeUninit;
-use std::ptr::null_mut;
-use std::{io, mem};
-
-use winapi::shared::d3d9types::D3DCOLORVALUE;
-use winapi::shared::minwindef::{FALSE, HINSTANCE, LPARAM, LRESULT, TRUE, UINT, WPARAM};
-use winapi::shared::windef::HWND;
-use winapi::um::d2d1::{
-    ID2D1Factory, ID2D1HwndRenderTarget, ID2D1SolidColorBrush, D2D1_BRUSH_PROPERTIES, D2D1_COLOR_F,
-    D2D1_HWND_RENDER_TARGET_PROPERTIES, D2D1_RECT_F, D2D1_RENDER_TARGET_PROPERTIES, D2D1_SIZE_U,
-};
-use winapi::um::wingdi::{CreateSolidBrush, DeleteObject, RGB};
-use winapi::um::winuser::{
-    BeginPaint, CreateWindowExW, DefWindowProcW, DestroyWindow, EndPaint, FillRect, MoveWindow,
-    ReleaseCapture, SendMessageW, SetCapture, TrackMouseEvent, MK_LBUTTON, PAINTSTRUCT, TME_LEAVE,
-    TRACKMOUSEEVENT, WM_CREATE, WM_ERASEBKGND, WM_LBUTTONDOWN, WM_LBUTTONUP, WM_MOUSELEAVE,
-    WM_MOUSEMOVE, WM_PAINT, WM_SIZE, WM_USER, WS_CHILD, WS_VISIBLE,
-};
-
-use crate::component::Component;
-use crate::wutils::Error;
-use crate::{wnd_proc_gen, wpanic_ifeq, wpanic_ifne, wpanic_ifnull, wutils};
-
-const BUTTON_CLASS: &str = "CUSTOM_BTN";
-const TOGGLE_BUTTON_CLASS: &str = "CUSTOM_TBTN";
-const CM_CLICK: UINT = WM_USER + 1;
-const CM_PAINTLAST: UINT = WM_USER + 2;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::animation::{Animated, Easing};
+use crate::canvas::{Canvas, Color};
+use crate::dpi::Dpi;
+use crate::geometry::{PointF, Rect, RectF};
+use crate::image::ImageSet;
+use crate::units::{Dip, DipInsets};
+use crate::widget::{Context, InputRouter, Widget};
+
+const COLOR_FADE_DURATION: Duration = Duration::from_millis(120);
+const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+const SPINNER_DOTS: u32 = 8;
+const ICON_SIZE: Dip = Dip(16.0);
+const ICON_PADDING: Dip = Dip(8.0);
+const TOGGLE_INSETS: DipInsets = DipInsets::new(0.0, 0.0, 10.0, 10.0);
 
 type CbFn<T> = Box<dyn Fn(&T)>;
+type PaintFn<T> = Box<dyn Fn(&T, &mut dyn Canvas)>;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum State {
@@ -35,14 +26,21 @@ pub enum State {
     Down,
 }
 
+#[derive(Clone)]
+pub enum Icon {
+    Image(Rc<ImageSet>),
+    Loading,
+    Modified,
+}
+
 pub struct Colors {
-    default: D3DCOLORVALUE,
-    hover: D3DCOLORVALUE,
-    down: D3DCOLORVALUE,
+    default: Color,
+    hover: Color,
+    down: Color,
 }
 
 impl Colors {
-    pub fn new(default: D3DCOLORVALUE, hover: D3DCOLORVALUE, down: D3DCOLORVALUE) -> Self {
+    pub fn new(default: Color, hover: Color, down: Color) -> Self {
         Self {
             default,
             hover,
@@ -50,81 +48,148 @@ impl Colors {
         }
     }
 
-    pub fn default(&self) -> D3DCOLORVALUE {
+    pub fn default(&self) -> Color {
         self.default
     }
 
-    pub fn hover(&self) -> D3DCOLORVALUE {
+    pub fn hover(&self) -> Color {
         self.hover
     }
 
-    pub fn down(&self) -> D3DCOLORVALUE {
+    pub fn down(&self) -> Color {
         self.down
     }
 }
 
-pub trait BaseButton: Component {
+pub trait BaseButton: Widget {
     fn state(&self) -> State;
     fn colors(&self) -> &Colors;
     fn on_click(&mut self, cb: CbFn<Self>);
-    fn on_paint(&mut self, cb: CbFn<Self>);
-    fn on_paint_last(&mut self, cb: CbFn<Self>);
+    fn on_paint(&mut self, cb: PaintFn<Self>);
+    fn on_paint_last(&mut self, cb: PaintFn<Self>);
+    fn icon(&self) -> Option<&Icon>;
+    fn set_icon(&mut self, icon: Option<Icon>, ctx: &mut Context);
+    // Whether the button was clicked since the last call, for parents routing input to it.
+    fn take_click(&mut self) -> bool;
 }
 
-pub struct Button<'a> {
-    hwnd: HWND,
-    is_own_d2d: bool,
-    d2d_factory: &'a ID2D1Factory,
-    d2d_render_target: Option<&'a ID2D1HwndRenderTarget>,
-    d2d_brush: Option<&'a ID2D1SolidColorBrush>,
+pub struct Button {
+    bounds: Rect,
     state: State,
-    track_mouse_leave: bool,
     is_down: bool,
+    clicked: bool,
+    now: Duration,
     click_cb: Option<CbFn<Self>>,
-    paint_cb: Option<CbFn<Self>>,
-    paint_last_cb: Option<CbFn<Self>>,
+    paint_cb: Option<PaintFn<Self>>,
+    paint_last_cb: Option<PaintFn<Self>>,
     colors: Colors,
+    background: Animated<Color>,
+    icon: Option<Icon>,
+    spinner_phase: u32,
 }
 
-impl Drop for Button<'_> {
-    fn drop(&mut self) {
-        dbg!(("Drop button", self.hwnd));
+impl Widget for Button {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
 
-        if let Some(ref brush) = self.d2d_brush {
-            unsafe {
-                brush.Release();
-            }
+    fn paint(&self, canvas: &mut dyn Canvas) {
+        canvas.set_antialias(true);
+
+        if let Some(cb) = self.paint_cb.as_ref() {
+            cb(self, canvas);
+        } else {
+            canvas.fill_rect(self.bounds.into(), self.background());
         }
 
-        if let Some(ref render_target) = self.d2d_render_target {
-            unsafe {
-                render_target.Release();
-            }
+        self.paint_icon(canvas);
+
+        if let Some(cb) = self.paint_last_cb.as_ref() {
+            cb(self, canvas);
         }
+    }
+
+    fn on_mouse_move(&mut self, x: i32, y: i32, left_down: bool, ctx: &mut Context) {
+        let old_state = self.state;
 
-        if self.is_own_d2d {
-            unsafe {
-                self.d2d_factory.Release();
+        if self.bounds.contains_point(x, y) && self.is_down {
+            if left_down {
+                self.state = State::Down;
+            } else {
+                self.state = State::Hover;
             }
+        } else {
+            self.state = State::Hover;
         }
 
-        unsafe {
-            DestroyWindow(self.hwnd);
+        if old_state != self.state {
+            self.on_state_changed(ctx);
         }
     }
-}
 
-impl Component for Button<'_> {
-    fn hwnd(&self) -> HWND {
-        self.hwnd
+    fn on_mouse_leave(&mut self, ctx: &mut Context) {
+        self.state = State::None;
+        self.on_state_changed(ctx);
+    }
+
+    fn on_mouse_down(&mut self, _x: i32, _y: i32, ctx: &mut Context) -> bool {
+        self.state = State::Down;
+        self.is_down = true;
+        self.on_state_changed(ctx);
+        true
+    }
+
+    fn on_mouse_up(&mut self, x: i32, y: i32, ctx: &mut Context) {
+        let old_state = self.state;
+
+        if self.bounds.contains_point(x, y) {
+            self.state = State::Hover;
+
+            if self.is_down {
+                self.clicked = true;
+                if let Some(cb) = self.click_cb.as_ref() {
+                    cb(self);
+                }
+            }
+        } else {
+            self.state = State::None;
+        }
+
+        self.is_down = false;
+        if old_state != self.state {
+            self.on_state_changed(ctx);
+        }
     }
 
-    fn register_class(h_inst: HINSTANCE) -> Result<(), Error> {
-        wutils::register_class(h_inst, BUTTON_CLASS, wnd_proc_btn)
+    fn on_frame(&mut self, ctx: &mut Context) -> bool {
+        self.now = ctx.now();
+
+        let is_animating = self.background.is_animating(self.now);
+        if is_animating {
+            ctx.invalidate(self.bounds);
+        }
+
+        if let Some(Icon::Loading) = self.icon {
+            let phase = (self.now.as_millis() / SPINNER_INTERVAL.as_millis()) as u32 % SPINNER_DOTS;
+
+            if phase != self.spinner_phase {
+                self.spinner_phase = phase;
+                // Only the spinner itself changes, leave the rest of the button alone
+                ctx.invalidate(self.icon_rect(&Icon::Loading, ctx.dpi()).round_out());
+            }
+
+            return true;
+        }
+
+        is_animating
     }
 }
 
-impl BaseButton for Button<'_> {
+impl BaseButton for Button {
     fn state(&self) -> State {
         self.state
     }
@@ -137,320 +202,226 @@ impl BaseButton for Button<'_> {
         self.click_cb = Some(cb);
     }
 
-    fn on_paint(&mut self, cb: CbFn<Self>) {
+    fn on_paint(&mut self, cb: PaintFn<Self>) {
         self.paint_cb = Some(cb);
     }
 
-    fn on_paint_last(&mut self, cb: CbFn<Self>) {
+    fn on_paint_last(&mut self, cb: PaintFn<Self>) {
         self.paint_last_cb = Some(cb);
     }
-}
 
-impl<'a> Button<'a> {
-    pub fn new(
-        parent_hwnd: HWND,
-        h_inst: HINSTANCE,
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        colors: Option<Colors>,
-        d2d_factory: Option<&'a ID2D1Factory>,
-    ) -> Result<Box<Self>, Error> {
-        Self::register_class(h_inst)?;
+    fn icon(&self) -> Option<&Icon> {
+        self.icon.as_ref()
+    }
 
-        let mut is_own_d2d = false;
+    fn set_icon(&mut self, icon: Option<Icon>, ctx: &mut Context) {
+        if let Some(Icon::Loading) = icon {
+            ctx.request_frames();
+        }
 
-        let d2d_factory = match d2d_factory {
-            Some(some) => some,
-            None => {
-                is_own_d2d = true;
-                wutils::create_d2d_factory()?
-            }
-        };
+        self.icon = icon;
+        self.spinner_phase = 0;
+        ctx.invalidate(self.bounds);
+    }
+
+    fn take_click(&mut self) -> bool {
+        std::mem::take(&mut self.clicked)
+    }
+}
 
-        let mut me = Box::new(Self {
-            hwnd: null_mut(),
-            is_own_d2d,
-            d2d_factory,
-            d2d_render_target: None,
-            d2d_brush: None,
+impl Button {
+    pub fn new(x: i32, y: i32, width: i32, height: i32, colors: Option<Colors>) -> Self {
+        let colors = colors.unwrap_or(Colors {
+            default: Color::from_argb(0xff646464),
+            hover: Color::from_argb(0xff505050),
+            down: Color::from_argb(0xff3c3c3c),
+        });
+
+        Self {
+            bounds: Rect::from_size(x, y, width, height),
             state: State::None,
-            track_mouse_leave: false,
             is_down: false,
+            clicked: false,
+            now: Duration::ZERO,
             click_cb: None,
             paint_cb: None,
             paint_last_cb: None,
-            colors: colors.unwrap_or(Colors {
-                default: wutils::color_from_argb(0xff646464),
-                hover: wutils::color_from_argb(0xff505050),
-                down: wutils::color_from_argb(0xff3c3c3c),
-            }),
-        });
-
-        let hwnd = unsafe {
-            CreateWindowExW(
-                0,
-                wutils::wide_string(BUTTON_CLASS).as_ptr(),
-                wutils::wide_string("").as_ptr(),
-                WS_CHILD | WS_VISIBLE,
-                x,
-                y,
-                width,
-                height,
-                parent_hwnd,
-                null_mut(),
-                h_inst,
-                me.as_ref() as *const _ as _,
-            )
-        };
-
-        if hwnd.is_null() {
-            return Err(Error::WindowsInternal(io::Error::last_os_error()));
+            background: Animated::new(colors.default, COLOR_FADE_DURATION, Easing::EaseOut),
+            colors,
+            icon: None,
+            spinner_phase: 0,
         }
-
-        (*me).hwnd = hwnd;
-
-        Ok(me)
     }
 
-    pub fn set_colors(&mut self, colors: &Colors) {
+    pub fn set_colors(&mut self, colors: &Colors, ctx: &mut Context) {
         self.colors = Colors::new(colors.default, colors.hover, colors.down);
+        self.on_state_changed(ctx);
     }
 
-    fn init_d2d(&mut self) {
-        if let None = self.d2d_render_target {
-            let mut render_target = MaybeUninit::<*mut ID2D1HwndRenderTarget>::uninit();
-
-            wpanic_ifne!(
-                self.d2d_factory.CreateHwndRenderTarget(
-                    &D2D1_RENDER_TARGET_PROPERTIES::default(),
-                    &D2D1_HWND_RENDER_TARGET_PROPERTIES {
-                        hwnd: self.hwnd,
-                        ..Default::default()
-                    },
-                    render_target.as_mut_ptr() as _,
-                ),
-                0
-            );
-
-            self.d2d_render_target = Some(unsafe { &*render_target.assume_init() });
-        }
-
-        if let None = self.d2d_brush {
-            let mut brush = MaybeUninit::<*mut ID2D1SolidColorBrush>::uninit();
-            wpanic_ifne!(
-                self.d2d_render_target().CreateSolidColorBrush(
-                    &D2D1_COLOR_F::default(),
-                    &D2D1_BRUSH_PROPERTIES {
-                        opacity: 1.0,
-                        ..Default::default()
-                    },
-                    brush.as_mut_ptr() as _
-                ),
-                0
-            );
-
-            self.d2d_brush = Some(unsafe { &*brush.assume_init() });
-        }
-    }
-
-    pub fn d2d_factory(&self) -> &ID2D1Factory {
-        self.d2d_factory
-    }
-
-    pub fn d2d_render_target(&self) -> &ID2D1HwndRenderTarget {
-        self.d2d_render_target.as_deref().unwrap()
-    }
-
-    pub fn d2d_brush(&self) -> &ID2D1SolidColorBrush {
-        self.d2d_brush.as_deref().unwrap()
+    pub fn background(&self) -> Color {
+        self.background.value(self.now)
     }
 
-    fn paint(&mut self) {
-        self.init_d2d();
-
-        let mut ps = PAINTSTRUCT::default();
-        let hdc = wpanic_ifnull!(BeginPaint(self.hwnd, &mut ps));
-
-        let target = self.d2d_render_target();
-        unsafe {
-            target.BeginDraw();
-            target.Clear(null_mut());
-        }
-
-        if let Some(cb) = self.paint_cb.as_ref() {
-            cb(self);
-        } else {
-            let bg_color = match self.state {
-                State::None => self.colors.default,
-                State::Hover => self.colors.hover,
-                State::Down => self.colors.down,
-            };
-
-            let brush = self.d2d_brush();
-
-            unsafe {
-                brush.SetColor(&bg_color);
-
-                let size = target.GetSize();
-
-                target.FillRectangle(
-                    &D2D1_RECT_F {
-                        left: 0.0,
-                        top: 0.0,
-                        right: size.width,
-                        bottom: size.height,
-                    },
-                    brush as *const _ as _,
-                );
-            }
+    fn state_color(&self) -> Color {
+        match self.state {
+            State::None => self.colors.default,
+            State::Hover => self.colors.hover,
+            State::Down => self.colors.down,
         }
+    }
 
-        if let Some(cb) = self.paint_last_cb.as_ref() {
-            cb(self);
-        }
+    fn on_state_changed(&mut self, ctx: &mut Context) {
+        self.now = ctx.now();
+        self.background.animate_to(self.state_color(), self.now);
 
-        unsafe {
-            target.EndDraw(null_mut(), null_mut());
+        if self.background.is_animating(self.now) {
+            ctx.request_frames();
         }
 
-        wpanic_ifeq!(EndPaint(self.hwnd, &ps), FALSE);
+        ctx.invalidate(self.bounds);
     }
 
-    fn handle_message(&mut self, message: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-        match message {
-            WM_SIZE => {
-                let rect = self.get_client_rect();
-                let size = D2D1_SIZE_U {
-                    width: rect.right as _,
-                    height: rect.bottom as _,
-                };
-                self.init_d2d();
-                wpanic_ifne!(self.d2d_render_target().Resize(&size), 0);
-            }
-            WM_ERASEBKGND => {
-                return 1;
-            }
-            WM_PAINT => {
-                self.paint();
-            }
-            WM_MOUSELEAVE => {
-                self.track_mouse_leave = false;
-                self.state = State::None;
-                self.invalidate_rect();
-            }
-            WM_MOUSEMOVE => {
-                let old_state = self.state;
+    fn icon_rect(&self, icon: &Icon, dpi: Dpi) -> RectF {
+        let padding = dpi.px(ICON_PADDING).get() as f32;
+        let icon_size = match icon {
+            Icon::Image(ref images) => images.size_for_dpi(dpi) as f32,
+            _ => dpi.px(ICON_SIZE).get() as f32,
+        };
 
-                if !self.track_mouse_leave {
-                    self.track_mouse_leave = true;
+        let bounds = RectF::from(self.bounds);
+        let icon_rect = RectF::new(0.0, 0.0, icon_size, icon_size).centered_in(&bounds);
 
-                    let mut trk = TRACKMOUSEEVENT {
-                        cbSize: mem::size_of::<TRACKMOUSEEVENT>() as u32,
-                        dwFlags: TME_LEAVE,
-                        hwndTrack: self.hwnd,
-                        dwHoverTime: 0,
-                    };
+        // Bitmaps blur when they don't start on a whole pixel
+        RectF::new(
+            bounds.left + padding,
+            icon_rect.top,
+            bounds.left + padding + icon_size,
+            icon_rect.bottom,
+        )
+        .round()
+        .into()
+    }
 
-                    wpanic_ifeq!(TrackMouseEvent(&mut trk), FALSE);
-                }
+    fn paint_icon(&self, canvas: &mut dyn Canvas) {
+        let icon = match self.icon {
+            Some(ref icon) => icon,
+            None => return,
+        };
 
-                if self.is_mouse_over() && self.is_down {
-                    if wparam & MK_LBUTTON > 0 {
-                        self.state = State::Down;
-                    } else {
-                        self.state = State::Hover;
-                    }
-                } else {
-                    self.state = State::Hover;
-                }
+        let dpi = canvas.dpi();
+        let icon_rect = self.icon_rect(icon, dpi);
+        let icon_size = icon_rect.width();
+        let center = icon_rect.center();
+        let color = Color::from_argb(0xff212121);
 
-                if old_state != self.state {
-                    self.invalidate_rect();
-                }
+        match icon {
+            Icon::Image(ref images) => {
+                canvas.draw_image(images.for_dpi(dpi), icon_rect);
             }
-            WM_LBUTTONDOWN => {
-                self.state = State::Down;
-                self.is_down = true;
-                self.invalidate_rect();
-                unsafe { SetCapture(self.hwnd) };
-                return 1;
+            Icon::Modified => {
+                let radius = icon_size / 4.0;
+                canvas.fill_ellipse(center, radius, radius, color);
             }
-            WM_LBUTTONUP => {
-                let old_state = self.state;
-
-                if self.is_mouse_over() {
-                    self.state = State::Hover;
-
-                    if self.is_down {
-                        if let Some(cb) = self.click_cb.as_ref() {
-                            cb(self);
-                        }
-                    }
-                } else {
-                    self.state = State::None;
+            Icon::Loading => {
+                let ring_radius = icon_size / 2.0 - icon_size / 8.0;
+                let dot_radius = icon_size / 12.0;
+
+                for dot in 0..SPINNER_DOTS {
+                    // The dot at the current phase is the most opaque, trailing ones fade out.
+                    let age = (self.spinner_phase + SPINNER_DOTS - dot) % SPINNER_DOTS;
+                    let angle = dot as f32 / SPINNER_DOTS as f32 * std::f32::consts::TAU;
+
+                    canvas.fill_ellipse(
+                        PointF::new(
+                            center.x + ring_radius * angle.sin(),
+                            center.y - ring_radius * angle.cos(),
+                        ),
+                        dot_radius,
+                        dot_radius,
+                        color.with_alpha(1.0 - age as f32 / SPINNER_DOTS as f32),
+                    );
                 }
-
-                self.is_down = false;
-                if old_state != self.state {
-                    self.invalidate_rect();
-                }
-
-                wpanic_ifeq!(ReleaseCapture(), FALSE);
             }
-            _ => {}
         }
-
-        unsafe { DefWindowProcW(self.hwnd, message, wparam, lparam) }
     }
 }
 
-pub struct ToggleButton<'a> {
-    hwnd: HWND,
-    h_inst: HINSTANCE,
-    is_own_d2d: bool,
-    d2d_factory: &'a ID2D1Factory,
-    button: Option<Box<Button<'a>>>,
-    state: State,
+pub struct ToggleButton {
+    bounds: Rect,
+    dpi: Dpi,
+    button: Button,
+    router: InputRouter,
+    clicked: bool,
     click_cb: Option<CbFn<Self>>,
-    paint_cb: Option<CbFn<Self>>,
-    paint_last_cb: Option<CbFn<Self>>,
+    paint_cb: Option<PaintFn<Self>>,
+    paint_last_cb: Option<PaintFn<Self>>,
     is_toggled: bool,
     colors: Colors,
     toggled_colors: Colors,
 }
 
-impl Drop for ToggleButton<'_> {
-    fn drop(&mut self) {
-        self.button = None;
+impl Widget for ToggleButton {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
 
-        dbg!(("Drop t button", self.hwnd));
-        if self.is_own_d2d {
-            unsafe {
-                self.d2d_factory.Release();
-            }
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+        self.reposition_components();
+    }
+
+    fn paint(&self, canvas: &mut dyn Canvas) {
+        if let Some(cb) = self.paint_cb.as_ref() {
+            cb(self, canvas);
+        } else {
+            canvas.fill_rect(self.bounds.into(), Color::from_rgb(0xff, 0xdd, 0xdd));
         }
 
-        unsafe {
-            DestroyWindow(self.hwnd);
+        self.button.paint(canvas);
+
+        if let Some(cb) = self.paint_last_cb.as_ref() {
+            cb(self, canvas);
         }
     }
-}
 
-impl Component for ToggleButton<'_> {
-    fn hwnd(&self) -> HWND {
-        self.hwnd
+    fn on_dpi_changed(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+        self.reposition_components();
+    }
+
+    fn on_mouse_move(&mut self, x: i32, y: i32, left_down: bool, ctx: &mut Context) {
+        self.router
+            .mouse_move(&mut [&mut self.button], x, y, left_down, ctx);
+    }
+
+    fn on_mouse_leave(&mut self, ctx: &mut Context) {
+        self.router.mouse_leave(&mut [&mut self.button], ctx);
+    }
+
+    fn on_mouse_down(&mut self, x: i32, y: i32, ctx: &mut Context) -> bool {
+        self.router.mouse_down(&mut [&mut self.button], x, y, ctx)
+    }
+
+    fn on_mouse_up(&mut self, x: i32, y: i32, ctx: &mut Context) {
+        self.router.mouse_up(&mut [&mut self.button], x, y, ctx);
+
+        if self.button.take_click() {
+            self.toggle(ctx);
+            self.clicked = true;
+            if let Some(cb) = self.click_cb.as_ref() {
+                cb(self);
+            }
+        }
     }
 
-    fn register_class(h_inst: HINSTANCE) -> Result<(), Error> {
-        wutils::register_class(h_inst, TOGGLE_BUTTON_CLASS, wnd_proc_tbtn)
+    fn on_frame(&mut self, ctx: &mut Context) -> bool {
+        self.button.on_frame(ctx)
     }
 }
 
-impl BaseButton for ToggleButton<'_> {
+impl BaseButton for ToggleButton {
     fn state(&self) -> State {
-        self.state
+        self.button.state()
     }
 
     fn colors(&self) -> &Colors {
@@ -461,198 +432,87 @@ impl BaseButton for ToggleButton<'_> {
         self.click_cb = Some(cb);
     }
 
-    fn on_paint(&mut self, cb: CbFn<Self>) {
+    fn on_paint(&mut self, cb: PaintFn<Self>) {
         self.paint_cb = Some(cb);
     }
 
-    fn on_paint_last(&mut self, cb: CbFn<Self>) {
+    fn on_paint_last(&mut self, cb: PaintFn<Self>) {
         self.paint_last_cb = Some(cb);
     }
+
+    fn icon(&self) -> Option<&Icon> {
+        self.button.icon()
+    }
+
+    fn set_icon(&mut self, icon: Option<Icon>, ctx: &mut Context) {
+        self.button.set_icon(icon, ctx);
+    }
+
+    fn take_click(&mut self) -> bool {
+        std::mem::take(&mut self.clicked)
+    }
 }
 
-impl<'a> ToggleButton<'a> {
+impl ToggleButton {
     pub fn new(
-        parent_hwnd: HWND,
-        h_inst: HINSTANCE,
         x: i32,
         y: i32,
         width: i32,
         height: i32,
         colors: Option<Colors>,
         toggled_colors: Option<Colors>,
-        d2d_factory: Option<&'a ID2D1Factory>,
-    ) -> Result<Box<Self>, Error> {
-        Self::register_class(h_inst)?;
-
-        let mut is_own_d2d = false;
-
-        let d2d_factory = match d2d_factory {
-            Some(some) => some,
-            None => {
-                is_own_d2d = true;
-                wutils::create_d2d_factory()?
-            }
-        };
-
-        let mut me = Box::new(Self {
-            hwnd: null_mut(),
-            h_inst,
-            is_own_d2d,
-            d2d_factory,
-            button: None,
-            state: State::None,
+    ) -> Self {
+        let mut me = Self {
+            bounds: Rect::from_size(x, y, width, height),
+            dpi: Dpi::DEFAULT,
+            button: Button::new(0, 0, 0, 0, None),
+            router: InputRouter::new(),
+            clicked: false,
             click_cb: None,
             paint_cb: None,
             paint_last_cb: None,
             is_toggled: false,
             colors: colors.unwrap_or(Colors {
-                default: wutils::color_from_argb(0xff646464),
-                hover: wutils::color_from_argb(0xff505050),
-                down: wutils::color_from_argb(0xff3c3c3c),
+                default: Color::from_argb(0xff646464),
+                hover: Color::from_argb(0xff505050),
+                down: Color::from_argb(0xff3c3c3c),
             }),
             toggled_colors: toggled_colors.unwrap_or(Colors {
-                default: wutils::color_from_argb(0xff464646),
-                hover: wutils::color_from_argb(0xff3c3c3c),
-                down: wutils::color_from_argb(0xff323232),
+                default: Color::from_argb(0xff464646),
+                hover: Color::from_argb(0xff3c3c3c),
+                down: Color::from_argb(0xff323232),
             }),
-        });
-
-        let hwnd = unsafe {
-            CreateWindowExW(
-                0,
-                wutils::wide_string(TOGGLE_BUTTON_CLASS).as_ptr(),
-                wutils::wide_string("").as_ptr(),
-                WS_CHILD | WS_VISIBLE,
-                x,
-                y,
-                width,
-                height,
-                parent_hwnd,
-                null_mut(),
-                h_inst,
-                me.as_ref() as *const _ as _,
-            )
         };
 
-        if hwnd.is_null() {
-            return Err(Error::WindowsInternal(io::Error::last_os_error()));
-        }
-
-        (*me).hwnd = hwnd;
-
-        Ok(me)
-    }
-
-    fn reposition_components(&self) {
-        let rect = self.get_client_rect();
-
-        if let Some(ref button) = self.button {
-            wpanic_ifeq!(
-                MoveWindow(
-                    button.hwnd(),
-                    rect.left,
-                    rect.top,
-                    rect.right - rect.left - 10,
-                    rect.bottom - rect.top - 10,
-                    TRUE
-                ),
-                0
-            );
-        }
-    }
-
-    fn on_created(&mut self) {
-        let mut button = Button::new(
-            self.hwnd,
-            self.h_inst,
-            0,
-            0,
-            0,
-            0,
-            None,
-            Some(self.d2d_factory),
-        )
-        .unwrap();
-
-        let hwnd = self.hwnd;
-
-        button.on_click(Box::new(move |_| unsafe {
-            SendMessageW(hwnd, CM_CLICK, 0, 0);
-        }));
-
-        button.on_paint_last(Box::new(move |_| unsafe {
-            SendMessageW(hwnd, CM_PAINTLAST, 0, 0);
-        }));
-
-        self.button = Some(button);
-    }
-
-    pub fn d2d_render_target(&self) -> &ID2D1HwndRenderTarget {
-        self.button.as_ref().unwrap().d2d_render_target()
+        me.reposition_components();
+        me
     }
 
-    pub fn d2d_brush(&self) -> &ID2D1SolidColorBrush {
-        self.button.as_ref().unwrap().d2d_brush()
+    fn reposition_components(&mut self) {
+        self.button
+            .set_bounds(self.bounds.inset(&TOGGLE_INSETS.to_px(self.dpi)));
     }
 
     pub fn is_toggled(&self) -> bool {
         self.is_toggled
     }
 
-    pub fn toggle(&mut self) -> bool {
-        self.is_toggled = !self.is_toggled;
-
-        if let Some(ref mut button) = self.button {
-            if self.is_toggled {
-                button.set_colors(&self.toggled_colors);
-            } else {
-                button.set_colors(&self.colors);
-            }
-        }
-        self.invalidate_rect();
+    pub fn toggle(&mut self, ctx: &mut Context) -> bool {
+        self.set_toggled(!self.is_toggled, ctx);
         self.is_toggled
     }
 
-    fn paint(&mut self) {
-        let mut ps = PAINTSTRUCT::default();
-        let hdc = wpanic_ifnull!(BeginPaint(self.hwnd, &mut ps));
-
-        let bg_brush = wpanic_ifnull!(CreateSolidBrush(RGB(0xff, 0xdd, 0xdd)));
-        wpanic_ifeq!(FillRect(hdc, &ps.rcPaint, bg_brush), 0);
-        wpanic_ifeq!(DeleteObject(bg_brush as _), FALSE);
-
-        wpanic_ifeq!(EndPaint(self.hwnd, &ps), FALSE);
-    }
-
-    fn handle_message(&mut self, message: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-        match message {
-            WM_PAINT => {
-                self.paint();
-            }
-            WM_CREATE => {
-                self.on_created();
-                self.reposition_components();
-            }
-            WM_SIZE => {
-                self.reposition_components();
-            }
-            CM_CLICK => {
-                self.toggle();
-                if let Some(cb) = self.click_cb.as_ref() {
-                    cb(self);
-                }
-            }
-            CM_PAINTLAST => {
-                if let Some(cb) = self.paint_last_cb.as_ref() {
-                    cb(self);
-                }
-            }
-            _ => {}
+    pub fn set_toggled(&mut self, toggled: bool, ctx: &mut Context) {
+        if self.is_toggled == toggled {
+            return;
         }
+        self.is_toggled = toggled;
 
-        unsafe { DefWindowProcW(self.hwnd, message, wparam, lparam) }
+        if self.is_toggled {
+            self.button.set_colors(&self.toggled_colors, ctx);
+        } else {
+            self.button.set_colors(&self.colors, ctx);
+        }
+        ctx.invalidate(self.bounds);
     }
 }
-
-wnd_proc_gen!(Button, wnd_proc_btn);
-wnd_proc_gen!(ToggleButton, wnd_proc_tbtn);
@@ -0,0 +1,63 @@
+use crate::animation::Lerp;
+use crate::dpi::Dpi;
+use crate::geometry::{Point, PointF, RectF};
+use crate::image::Image;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub fn from_rgb(r: u8, g: u8, b: u8) -> Self {
+        Self::from_argb(0xff000000 | (r as u32) << 16 | (g as u32) << 8 | b as u32)
+    }
+
+    pub fn from_argb(color: u32) -> Self {
+        Self {
+            a: ((color >> 24) & 0xff) as f32 / 255.0,
+            r: ((color >> 16) & 0xff) as f32 / 255.0,
+            g: ((color >> 8) & 0xff) as f32 / 255.0,
+            b: (color & 0xff) as f32 / 255.0,
+        }
+    }
+
+    pub fn with_alpha(self, a: f32) -> Self {
+        Self { a, ..self }
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        Color {
+            r: self.r.lerp(to.r, t),
+            g: self.g.lerp(to.g, t),
+            b: self.b.lerp(to.b, t),
+            a: self.a.lerp(to.a, t),
+        }
+    }
+}
+
+// Everything a component can draw. Coordinates are in physical pixels relative to the client
+// area of the window that owns the canvas.
+pub trait Canvas {
+    fn dpi(&self) -> Dpi;
+    fn set_antialias(&mut self, enabled: bool);
+    fn clear(&mut self, color: Color);
+    fn fill_rect(&mut self, rect: RectF, color: Color);
+    fn stroke_rect(&mut self, rect: RectF, color: Color, width: f32);
+    fn draw_line(&mut self, from: PointF, to: PointF, color: Color, width: f32);
+    fn fill_ellipse(&mut self, center: PointF, radius_x: f32, radius_y: f32, color: Color);
+    fn draw_image(&mut self, image: &Image, rect: RectF);
+    // Draws `text` in the window's `Font` with its top left corner at `origin`.
+    fn draw_text(&mut self, text: &str, origin: Point, color: Color);
+    fn push_clip(&mut self, rect: RectF);
+    fn pop_clip(&mut self);
+}
@@ -0,0 +1,185 @@
+// Decides whether tabs and the window may close. Everyone involved is asked in turn, and can
+// answer right away or hold on to its `CloseReply` and answer later, e.g. once a prompt was
+// confirmed or the work was saved. Nothing closes until everyone agreed, and a single
+// cancel stops the whole round.
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::tab_model::TabId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Party {
+    Tab(TabId),
+    // The window's own close handler, asked after all of its tabs
+    Window,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CloseAnswer {
+    Close,
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CloseStep {
+    // Hand `party` a reply and wait for its answer
+    Ask(Party),
+    // Everyone agreed
+    Close { tabs: Vec<TabId>, window: bool },
+    Cancelled,
+}
+
+type Answers = Rc<RefCell<VecDeque<(u32, Party, CloseAnswer)>>>;
+
+// Lets one party answer once. Dropping it unanswered cancels, so a lost reply can't leave
+// the round hanging.
+pub struct CloseReply {
+    round: u32,
+    party: Party,
+    answers: Answers,
+    answered: bool,
+}
+
+impl CloseReply {
+    pub fn party(&self) -> Party {
+        self.party
+    }
+
+    pub fn close(mut self) {
+        self.answer(CloseAnswer::Close);
+    }
+
+    pub fn cancel(mut self) {
+        self.answer(CloseAnswer::Cancel);
+    }
+
+    fn answer(&mut self, answer: CloseAnswer) {
+        self.answered = true;
+        self.answers
+            .borrow_mut()
+            .push_back((self.round, self.party, answer));
+    }
+}
+
+impl Drop for CloseReply {
+    fn drop(&mut self) {
+        if !self.answered {
+            self.answer(CloseAnswer::Cancel);
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CloseProtocol {
+    round: u32,
+    waiting: Option<Party>,
+    // Still to be asked, in order
+    pending: VecDeque<Party>,
+    tabs: Vec<TabId>,
+    window: bool,
+    answers: Answers,
+}
+
+impl CloseProtocol {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_closing(&self) -> bool {
+        self.waiting.is_some()
+    }
+
+    // Asks `tabs` in order, the window stays. Ignored while another round is going on.
+    pub fn close_tabs(&mut self, tabs: &[TabId]) -> Option<CloseStep> {
+        self.start(tabs, false)
+    }
+
+    // Asks every tab in order and then the window itself.
+    pub fn close_window(&mut self, tabs: &[TabId]) -> Option<CloseStep> {
+        self.start(tabs, true)
+    }
+
+    fn start(&mut self, tabs: &[TabId], window: bool) -> Option<CloseStep> {
+        if self.is_closing() {
+            return None;
+        }
+
+        self.round += 1;
+        self.tabs = tabs.to_vec();
+        self.window = window;
+        self.pending = tabs.iter().copied().map(Party::Tab).collect();
+        if window {
+            self.pending.push_back(Party::Window);
+        }
+
+        Some(self.next())
+    }
+
+    pub fn reply(&self, party: Party) -> CloseReply {
+        CloseReply {
+            round: self.round,
+            party,
+            answers: self.answers.clone(),
+            answered: false,
+        }
+    }
+
+    // Feeds the answers given since the last call into the round. Answers from earlier
+    // rounds or from parties that weren't asked are dropped.
+    pub fn take_answers(&mut self) -> Vec<CloseStep> {
+        let mut steps = Vec::new();
+
+        loop {
+            let answer = self.answers.borrow_mut().pop_front();
+            let (round, party, answer) = match answer {
+                Some(answer) => answer,
+                None => break,
+            };
+
+            if let Some(step) = self.answer(round, party, answer) {
+                steps.push(step);
+            }
+        }
+
+        steps
+    }
+
+    fn answer(&mut self, round: u32, party: Party, answer: CloseAnswer) -> Option<CloseStep> {
+        if round != self.round || self.waiting != Some(party) {
+            return None;
+        }
+
+        match answer {
+            CloseAnswer::Close => Some(self.next()),
+            CloseAnswer::Cancel => {
+                self.reset();
+                Some(CloseStep::Cancelled)
+            }
+        }
+    }
+
+    fn next(&mut self) -> CloseStep {
+        match self.pending.pop_front() {
+            Some(party) => {
+                self.waiting = Some(party);
+                CloseStep::Ask(party)
+            }
+            None => {
+                let step = CloseStep::Close {
+                    tabs: std::mem::take(&mut self.tabs),
+                    window: self.window,
+                };
+                self.reset();
+                step
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.waiting = None;
+        self.pending.clear();
+        self.tabs.clear();
+        self.window = false;
+    }
+}
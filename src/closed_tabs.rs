@@ -0,0 +1,85 @@
+use crate::session::TabSession;
+
+// How many closed tabs are remembered, the oldest ones are forgotten first.
+pub const CLOSED_TABS_LIMIT: usize = 25;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClosedTab {
+    pub tab: TabSession,
+    // Where the tab was in its window's tab order when it closed
+    pub index: usize,
+    // The window it was in, its place among the app's windows as in `Session::windows`, or
+    // past them once that window is gone
+    pub window: usize,
+}
+
+// The tabs closed most recently, newest last, for reopening them in reverse order. Shared by
+// all windows of the app and kept in the session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClosedTabs {
+    tabs: Vec<ClosedTab>,
+    limit: usize,
+}
+
+impl Default for ClosedTabs {
+    fn default() -> Self {
+        Self::new(CLOSED_TABS_LIMIT)
+    }
+}
+
+impl ClosedTabs {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            tabs: Vec::new(),
+            limit,
+        }
+    }
+
+    // Starts out with `tabs`, e.g. from the last session, oldest first.
+    pub fn with_tabs(limit: usize, tabs: Vec<ClosedTab>) -> Self {
+        let mut me = Self::new(limit);
+        for tab in tabs {
+            me.push(tab);
+        }
+        me
+    }
+
+    pub fn tabs(&self) -> &[ClosedTab] {
+        &self.tabs
+    }
+
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    pub fn push(&mut self, tab: ClosedTab) {
+        if self.limit == 0 {
+            return;
+        }
+
+        if self.tabs.len() == self.limit {
+            self.tabs.remove(0);
+        }
+        self.tabs.push(tab);
+    }
+
+    // Takes the tab `window` closed last. Tabs of other windows only come back here once
+    // `window` has none left, as their windows may be gone.
+    pub fn pop(&mut self, window: usize) -> Option<ClosedTab> {
+        let index = self
+            .tabs
+            .iter()
+            .rposition(|tab| tab.window == window)
+            .or_else(|| self.tabs.len().checked_sub(1))?;
+
+        Some(self.tabs.remove(index))
+    }
+
+    pub fn clear(&mut self) {
+        self.tabs.clear();
+    }
+}
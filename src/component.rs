@@ -1,25 +1,344 @@
-use std::ptr::null;
+// Window handles are opaque to Rust code, they are only ever handed back to Win32
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
 
-use winapi::shared::minwindef::{FALSE, HINSTANCE};
-use winapi::shared::windef::{HWND, RECT};
-use winapi::um::winuser::InvalidateRect;
+use std::cell::Cell;
+use std::rc::Rc;
+use std::sync::OnceLock;
 
-use crate::wpanic_ifeq;
-use crate::wutils::{self, Error};
+use winapi::shared::minwindef::{FALSE, HIWORD, LOWORD, LPARAM, LRESULT, UINT, WPARAM};
+use winapi::shared::windef::{HWND, POINT, RECT};
+use winapi::um::wingdi::MAKEPOINTS;
+use winapi::um::winuser::*;
 
-pub trait Component {
-    fn hwnd(&self) -> HWND;
-    fn register_class(h_inst: HINSTANCE) -> Result<(), Error>;
+use crate::dpi::Dpi;
+use crate::error::{self, Error};
+use crate::event::{Event, EventHandler, HitZone, Key, MouseButton, MouseButtons, Reply};
+use crate::geometry::{Point, Size};
+use crate::host::{ComponentHost, Dispatch};
+use crate::macros::{werr_ifeq, werr_ifnull};
+use crate::platform::WindowId;
+use crate::{logging, wutils};
 
-    fn get_client_rect(&self) -> RECT {
-        wutils::get_client_rect(self.hwnd()).unwrap()
+// What a window keeps in its GWLP_USERDATA. Owns the window's reference to the host, which
+// is released in WM_NCDESTROY.
+pub struct WindowData<C: EventHandler> {
+    pub host: Rc<ComponentHost<C>>,
+    pub name: &'static str,
+    pub custom_frame: bool,
+    // The first half of a character typed in two WM_CHAR messages
+    pub high_surrogate: Cell<Option<u16>>,
+}
+
+// A registered message is unique in the whole system, unlike `WM_USER + n` ids.
+pub fn custom_event_message() -> UINT {
+    static MESSAGE: OnceLock<UINT> = OnceLock::new();
+
+    *MESSAGE.get_or_init(|| unsafe {
+        RegisterWindowMessageW(wutils::wide_string("testwindowtabs.CustomEvent").as_ptr())
+    })
+}
+
+// The window procedure of every component. Finds the component of the window, turns the
+// message into an `Event` and the reply back into what the system expects. Messages without
+// an event get the default handling.
+pub extern "system" fn wnd_proc<C: EventHandler>(
+    hwnd: HWND,
+    message: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_NCCREATE {
+        // Takes over the data `Win32Platform::create_window` made for the window
+        let cs = lparam as *const CREATESTRUCTW;
+        let data = unsafe { (*cs).lpCreateParams } as *mut WindowData<C>;
+        unsafe { SetWindowLongPtrW(hwnd, GWLP_USERDATA, data as _) };
+
+        if let Some(data) = unsafe { data.as_ref() } {
+            data.host
+                .with(|component| component.attach(WindowId(hwnd as _)));
+        }
+    }
+
+    let ptr = unsafe { GetWindowLongPtrW(hwnd, GWLP_USERDATA) } as *mut WindowData<C>;
+    let data = match unsafe { ptr.as_ref() } {
+        Some(data) => data,
+        None => {
+            // Translated anyway so custom events don't leak
+            drop(translate_message::<C::Custom>(
+                hwnd, message, wparam, lparam,
+            ));
+            return unsafe { DefWindowProcW(hwnd, message, wparam, lparam) };
+        }
+    };
+
+    let name = data.name;
+    logging::trace_message(name, message, wparam, lparam);
+
+    if message == WM_NCCALCSIZE && wparam == 1 && data.custom_frame {
+        return match extend_client_area(hwnd, lparam) {
+            Ok(()) => 0,
+            // Falls back to the standard frame, which is still a working window
+            Err(err) => {
+                error::report_error(&err.in_component(name));
+                unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+            }
+        };
+    }
+
+    if message == WM_NCDESTROY {
+        unsafe { SetWindowLongPtrW(hwnd, GWLP_USERDATA, 0) };
+        let data = unsafe { Box::from_raw(ptr) };
+        data.host.destroy();
+        drop(data);
+        return unsafe { DefWindowProcW(hwnd, message, wparam, lparam) };
+    }
+
+    // Keeps the host alive until this call is done, even if the window lets go of it in a
+    // nested WM_NCDESTROY
+    let host = data.host.clone();
+
+    let handle = |component: &mut C, event| match component.handle_event(event) {
+        Ok(reply) => reply,
+        Err(err) => {
+            // The message gets the default handling instead
+            error::report_error(&err.in_component(name));
+            Reply::Default
+        }
+    };
+
+    if message == WM_PAINT {
+        return paint(name, &host, hwnd, handle);
+    }
+
+    let event = match message {
+        WM_CHAR => typed_char(&data.high_surrogate, wparam).map(Event::Text),
+        _ => translate_message::<C::Custom>(hwnd, message, wparam, lparam),
+    };
+    if let Some(event) = event {
+        match host.dispatch(event, handle) {
+            Dispatch::Handled(Reply::Default)
+            | Dispatch::Deferred
+            | Dispatch::Busy
+            | Dispatch::Gone => {}
+            Dispatch::Handled(reply) => return reply_to_result(message, reply),
+        }
+    }
+
+    unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+}
+
+fn paint<C: EventHandler>(
+    name: &'static str,
+    host: &ComponentHost<C>,
+    hwnd: HWND,
+    handle: impl FnMut(&mut C, Event<C::Custom>) -> Reply,
+) -> LRESULT {
+    let mut ps = PAINTSTRUCT::default();
+    if let Err(err) = werr_ifnull!(BeginPaint(hwnd, &mut ps)) {
+        error::report_error(&err.in_component(name));
+        return 0;
+    }
+
+    let dirty = ps.rcPaint.into();
+    host.dispatch(Event::Paint { dirty }, handle);
+    unsafe { EndPaint(hwnd, &ps) };
+
+    0
+}
+
+// Extends the client area over the title bar, leaving only the resize borders to the system.
+fn extend_client_area(hwnd: HWND, lparam: LPARAM) -> Result<(), Error> {
+    let dpi = wutils::get_dpi_for_window(hwnd)?;
+
+    let frame_x = wutils::get_system_metrics_for_dpi(SM_CXFRAME, dpi)?;
+    let frame_y = wutils::get_system_metrics_for_dpi(SM_CYFRAME, dpi)?;
+    let padding = wutils::get_system_metrics_for_dpi(SM_CXPADDEDBORDER, dpi)?;
+    let is_maximized = wutils::window_is_maximized(hwnd)?;
+
+    let params = match unsafe { (lparam as *mut NCCALCSIZE_PARAMS).as_mut() } {
+        Some(params) => params,
+        None => return Err(Error::Generic(String::from("WM_NCCALCSIZE without params"))),
+    };
+
+    let requested_client_rect = &mut params.rgrc[0];
+
+    requested_client_rect.right -= frame_x + padding;
+    requested_client_rect.left += frame_x + padding;
+    requested_client_rect.bottom -= frame_y + padding;
+
+    if is_maximized {
+        requested_client_rect.top += padding;
+    }
+
+    Ok(())
+}
+
+fn translate_message<T: 'static>(
+    hwnd: HWND,
+    message: UINT,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> Option<Event<T>> {
+    if message == custom_event_message() {
+        let event = unsafe { Box::from_raw(lparam as *mut T) };
+        return Some(Event::Custom(*event));
+    }
+
+    let event = match message {
+        WM_CREATE => Event::Created,
+        WM_CLOSE => Event::CloseRequested,
+        WM_DESTROY => Event::Destroyed,
+        WM_ACTIVATE => Event::Activate(LOWORD(wparam as _) != WA_INACTIVE),
+        WM_SETFOCUS => Event::Focus(true),
+        WM_KILLFOCUS => Event::Focus(false),
+        WM_SIZE => Event::Resize(Size::new(
+            LOWORD(lparam as _) as _,
+            HIWORD(lparam as _) as _,
+        )),
+        WM_ERASEBKGND => Event::EraseBackground,
+        WM_NCHITTEST => {
+            let system = unsafe { DefWindowProcW(hwnd, message, wparam, lparam) };
+            let pos = point_from_lparam(lparam);
+            let mut pos = POINT { x: pos.x, y: pos.y };
+            werr_ifeq!(ScreenToClient(hwnd, &mut pos), FALSE).ok()?;
+
+            Event::HitTest {
+                pos: Point::new(pos.x, pos.y),
+                system: hit_zone(system),
+            }
+        }
+        WM_MOUSEMOVE => Event::MouseMove {
+            pos: point_from_lparam(lparam),
+            buttons: buttons_from_wparam(wparam),
+        },
+        WM_MOUSELEAVE => Event::MouseLeave,
+        WM_LBUTTONDOWN | WM_RBUTTONDOWN | WM_MBUTTONDOWN => Event::MouseDown {
+            pos: point_from_lparam(lparam),
+            button: button_from_message(message)?,
+            buttons: buttons_from_wparam(wparam),
+        },
+        WM_LBUTTONUP | WM_RBUTTONUP | WM_MBUTTONUP => Event::MouseUp {
+            pos: point_from_lparam(lparam),
+            button: button_from_message(message)?,
+            buttons: buttons_from_wparam(wparam),
+        },
+        WM_KEYDOWN => Event::KeyDown(key_from_wparam(wparam)),
+        WM_NCLBUTTONDOWN | WM_NCRBUTTONDOWN | WM_NCMBUTTONDOWN => Event::NonClientMouseDown {
+            pos: point_from_lparam(lparam),
+            button: button_from_message(message)?,
+        },
+        WM_TIMER => Event::Timer(wparam),
+        WM_DPICHANGED => {
+            let suggested = unsafe { (lparam as *const RECT).as_ref() }?;
+
+            // Both axes always have the same DPI
+            Event::Dpi {
+                dpi: Dpi::new(HIWORD(wparam as _) as _),
+                suggested: (*suggested).into(),
+            }
+        }
+        WM_SETTINGCHANGE => Event::SettingsChanged,
+        _ => return None,
+    };
+
+    Some(event)
+}
+
+// Characters past the Basic Multilingual Plane come as two UTF-16 halves, one per message.
+fn typed_char(high_surrogate: &Cell<Option<u16>>, wparam: WPARAM) -> Option<char> {
+    let unit = wparam as u16;
+    if (0xd800..0xdc00).contains(&unit) {
+        high_surrogate.set(Some(unit));
+        return None;
+    }
+
+    let units = match high_surrogate.take() {
+        Some(high) => vec![high, unit],
+        None => vec![unit],
+    };
+    char::decode_utf16(units).next()?.ok()
+}
+
+fn reply_to_result(message: UINT, reply: Reply) -> LRESULT {
+    match reply {
+        Reply::HitTest(zone) => hit_zone_to_result(zone),
+        // Erasing is the one message that reports being handled with a non-zero value
+        Reply::Handled if message == WM_ERASEBKGND => 1,
+        Reply::Handled | Reply::Default => 0,
+    }
+}
+
+fn point_from_lparam(lparam: LPARAM) -> Point {
+    let point = MAKEPOINTS(lparam as u32);
+    Point::new(point.x as _, point.y as _)
+}
+
+fn buttons_from_wparam(wparam: WPARAM) -> MouseButtons {
+    MouseButtons {
+        left: wparam & MK_LBUTTON != 0,
+        right: wparam & MK_RBUTTON != 0,
+        middle: wparam & MK_MBUTTON != 0,
     }
+}
 
-    fn invalidate_rect(&self) {
-        wpanic_ifeq!(InvalidateRect(self.hwnd(), null(), FALSE), FALSE);
+fn button_from_message(message: UINT) -> Option<MouseButton> {
+    match message {
+        WM_LBUTTONDOWN | WM_LBUTTONUP | WM_NCLBUTTONDOWN => Some(MouseButton::Left),
+        WM_RBUTTONDOWN | WM_RBUTTONUP | WM_NCRBUTTONDOWN => Some(MouseButton::Right),
+        WM_MBUTTONDOWN | WM_MBUTTONUP | WM_NCMBUTTONDOWN => Some(MouseButton::Middle),
+        _ => None,
     }
+}
+
+// Letters and digits have the virtual key codes of their upper case characters.
+fn key_from_wparam(wparam: WPARAM) -> Key {
+    match wparam as i32 {
+        VK_UP => Key::Up,
+        VK_DOWN => Key::Down,
+        VK_LEFT => Key::Left,
+        VK_RIGHT => Key::Right,
+        VK_HOME => Key::Home,
+        VK_END => Key::End,
+        VK_RETURN => Key::Enter,
+        VK_ESCAPE => Key::Escape,
+        VK_SPACE => Key::Space,
+        VK_TAB => Key::Tab,
+        VK_BACK => Key::Backspace,
+        code @ (0x30..=0x39 | 0x41..=0x5a) => Key::Char((code as u8 as char).to_ascii_lowercase()),
+        _ => Key::Other,
+    }
+}
+
+fn hit_zone(result: LRESULT) -> HitZone {
+    match result {
+        HTNOWHERE => HitZone::Nowhere,
+        HTCLIENT => HitZone::Client,
+        HTCAPTION => HitZone::Caption,
+        HTLEFT => HitZone::Left,
+        HTRIGHT => HitZone::Right,
+        HTTOP => HitZone::Top,
+        HTBOTTOM => HitZone::Bottom,
+        HTTOPLEFT => HitZone::TopLeft,
+        HTTOPRIGHT => HitZone::TopRight,
+        HTBOTTOMLEFT => HitZone::BottomLeft,
+        HTBOTTOMRIGHT => HitZone::BottomRight,
+        _ => HitZone::Other,
+    }
+}
 
-    fn is_mouse_over(&self) -> bool {
-        wutils::is_mouse_over(self.hwnd()).unwrap()
+fn hit_zone_to_result(zone: HitZone) -> LRESULT {
+    match zone {
+        HitZone::Nowhere => HTNOWHERE,
+        HitZone::Client => HTCLIENT,
+        HitZone::Caption => HTCAPTION,
+        HitZone::Left => HTLEFT,
+        HitZone::Right => HTRIGHT,
+        HitZone::Top => HTTOP,
+        HitZone::Bottom => HTBOTTOM,
+        HitZone::TopLeft => HTTOPLEFT,
+        HitZone::TopRight => HTTOPRIGHT,
+        HitZone::BottomLeft => HTBOTTOMLEFT,
+        HitZone::BottomRight => HTBOTTOMRIGHT,
+        HitZone::Other => HTCLIENT,
     }
 }
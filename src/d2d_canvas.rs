@@ -0,0 +1,442 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::mem::MaybeUninit;
+use std::ptr::null_mut;
+
+use winapi::shared::dxgiformat::DXGI_FORMAT_B8G8R8A8_UNORM;
+use winapi::shared::windef::HWND;
+use winapi::shared::winerror::{D2DERR_RECREATE_TARGET, HRESULT};
+use winapi::um::d2d1::{
+    ID2D1Bitmap, ID2D1Factory, ID2D1HwndRenderTarget, ID2D1SolidColorBrush,
+    D2D1_ANTIALIAS_MODE_ALIASED, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+    D2D1_BITMAP_INTERPOLATION_MODE_LINEAR, D2D1_BITMAP_PROPERTIES, D2D1_BRUSH_PROPERTIES,
+    D2D1_COLOR_F, D2D1_DRAW_TEXT_OPTIONS_NONE, D2D1_ELLIPSE, D2D1_HWND_RENDER_TARGET_PROPERTIES,
+    D2D1_POINT_2F, D2D1_PRESENT_OPTIONS_RETAIN_CONTENTS, D2D1_RECT_F,
+    D2D1_RENDER_TARGET_PROPERTIES, D2D1_SIZE_U,
+};
+use winapi::um::dcommon::{
+    D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_PIXEL_FORMAT, DWRITE_MEASURING_MODE_NATURAL,
+};
+use winapi::um::dwrite::{
+    IDWriteFactory, IDWriteTextFormat, IDWriteTextLayout, DWRITE_FONT_STRETCH_NORMAL,
+    DWRITE_FONT_STYLE_NORMAL, DWRITE_FONT_WEIGHT_NORMAL, DWRITE_TEXT_METRICS,
+    DWRITE_WORD_WRAPPING_NO_WRAP,
+};
+
+use crate::canvas::{Canvas, Color};
+use crate::dpi::Dpi;
+use crate::error::{report_error, Error};
+use crate::geometry::{Point, PointF, RectF, Size};
+use crate::image::Image;
+use crate::render_resources::{DeviceError, DeviceFactory, RenderDevice};
+use crate::units::Dip;
+use crate::wutils;
+
+// What `Font` is drawn in
+const FONT_FAMILY: &str = "Segoe UI";
+const FONT_SIZE: Dip = Dip(12.0);
+
+thread_local! {
+    // Like the Direct2D factory, these stay for the life of the app, with a text format for every
+    // DPI a window was on
+    static WRITE_FACTORY: Option<&'static IDWriteFactory> =
+        wutils::create_dwrite_factory().map_err(|err| report_error(&err)).ok();
+    static TEXT_FORMATS: RefCell<HashMap<Dpi, Option<&'static IDWriteTextFormat>>> =
+        RefCell::default();
+}
+
+impl From<Color> for D2D1_COLOR_F {
+    fn from(color: Color) -> Self {
+        D2D1_COLOR_F {
+            r: color.r,
+            g: color.g,
+            b: color.b,
+            a: color.a,
+        }
+    }
+}
+
+impl From<RectF> for D2D1_RECT_F {
+    fn from(rect: RectF) -> Self {
+        D2D1_RECT_F {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+impl From<PointF> for D2D1_POINT_2F {
+    fn from(point: PointF) -> Self {
+        D2D1_POINT_2F {
+            x: point.x,
+            y: point.y,
+        }
+    }
+}
+
+fn text_format(dpi: Dpi) -> Option<&'static IDWriteTextFormat> {
+    TEXT_FORMATS.with(|formats| {
+        *formats
+            .borrow_mut()
+            .entry(dpi)
+            .or_insert_with(|| create_text_format(dpi))
+    })
+}
+
+fn create_text_format(dpi: Dpi) -> Option<&'static IDWriteTextFormat> {
+    let factory = WRITE_FACTORY.with(|factory| *factory)?;
+    let family = wutils::wide_string(FONT_FAMILY);
+    let locale = wutils::wide_string("");
+    let mut format = MaybeUninit::<*mut IDWriteTextFormat>::uninit();
+
+    let hr = unsafe {
+        factory.CreateTextFormat(
+            family.as_ptr(),
+            null_mut(),
+            DWRITE_FONT_WEIGHT_NORMAL,
+            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_STRETCH_NORMAL,
+            FONT_SIZE.to_px_f32(dpi),
+            locale.as_ptr(),
+            format.as_mut_ptr(),
+        )
+    };
+    if hr < 0 {
+        report_error(&Error::Hresult {
+            api: "CreateTextFormat",
+            hr,
+        });
+        return None;
+    }
+
+    let format = unsafe { &*format.assume_init() };
+    unsafe { format.SetWordWrapping(DWRITE_WORD_WRAPPING_NO_WRAP) };
+    Some(format)
+}
+
+// Width and height of `text` in the font at `dpi`, None when DirectWrite isn't there.
+pub fn measure_text(text: &str, dpi: Dpi) -> Option<(f32, f32)> {
+    let format = text_format(dpi)?;
+    let factory = WRITE_FACTORY.with(|factory| *factory)?;
+    let text = text.encode_utf16().collect::<Vec<_>>();
+    let mut layout = MaybeUninit::<*mut IDWriteTextLayout>::uninit();
+
+    let hr = unsafe {
+        factory.CreateTextLayout(
+            text.as_ptr(),
+            text.len() as _,
+            format as *const _ as _,
+            f32::MAX,
+            f32::MAX,
+            layout.as_mut_ptr(),
+        )
+    };
+    if hr < 0 {
+        return None;
+    }
+
+    let layout = unsafe { &*layout.assume_init() };
+    let mut metrics = DWRITE_TEXT_METRICS::default();
+    let hr = unsafe { layout.GetMetrics(&mut metrics) };
+    unsafe { layout.Release() };
+    (hr >= 0).then_some((metrics.widthIncludingTrailingWhitespace, metrics.height))
+}
+
+fn device_result(hr: HRESULT) -> Result<(), DeviceError> {
+    match hr {
+        D2DERR_RECREATE_TARGET => Err(DeviceError::Lost),
+        hr if hr < 0 => Err(DeviceError::Failed(hr)),
+        _ => Ok(()),
+    }
+}
+
+// Creates the render target of a window, again whenever the previous one was lost.
+pub struct D2DDeviceFactory<'a> {
+    factory: &'a ID2D1Factory,
+    hwnd: HWND,
+}
+
+impl<'a> D2DDeviceFactory<'a> {
+    pub fn new(factory: &'a ID2D1Factory, hwnd: HWND) -> Self {
+        Self { factory, hwnd }
+    }
+}
+
+impl<'a> DeviceFactory for D2DDeviceFactory<'a> {
+    type Device = D2DCanvas<'a>;
+
+    fn create_device(&mut self, size: Size, dpi: Dpi) -> Result<Self::Device, DeviceError> {
+        D2DCanvas::new(self.factory, self.hwnd, size, dpi)
+    }
+}
+
+// The one render target of a window. Every widget of the window paints through it.
+pub struct D2DCanvas<'a> {
+    render_target: &'a ID2D1HwndRenderTarget,
+    brush: &'a ID2D1SolidColorBrush,
+    bitmaps: HashMap<u64, &'a ID2D1Bitmap>,
+    dpi: Dpi,
+    // The first error while drawing a frame, reported by `end_draw`.
+    error: Option<DeviceError>,
+}
+
+impl Drop for D2DCanvas<'_> {
+    fn drop(&mut self) {
+        self.release_bitmaps();
+
+        unsafe {
+            self.brush.Release();
+            self.render_target.Release();
+        }
+    }
+}
+
+impl<'a> D2DCanvas<'a> {
+    pub fn new(
+        factory: &ID2D1Factory,
+        hwnd: HWND,
+        size: Size,
+        dpi: Dpi,
+    ) -> Result<Self, DeviceError> {
+        let mut render_target = MaybeUninit::<*mut ID2D1HwndRenderTarget>::uninit();
+
+        device_result(unsafe {
+            factory.CreateHwndRenderTarget(
+                &D2D1_RENDER_TARGET_PROPERTIES::default(),
+                &D2D1_HWND_RENDER_TARGET_PROPERTIES {
+                    hwnd,
+                    pixelSize: D2D1_SIZE_U {
+                        width: size.width.max(0) as _,
+                        height: size.height.max(0) as _,
+                    },
+                    // Paints only cover the dirty region, the rest of the frame has to stay
+                    presentOptions: D2D1_PRESENT_OPTIONS_RETAIN_CONTENTS,
+                },
+                render_target.as_mut_ptr() as _,
+            )
+        })?;
+
+        let render_target = unsafe { &*render_target.assume_init() };
+        // Work in physical pixels, everything is scaled through `Dpi` already and D2D must not
+        // scale a second time.
+        let pixel_dpi = Dpi::DEFAULT.value() as f32;
+        unsafe { render_target.SetDpi(pixel_dpi, pixel_dpi) };
+
+        let mut brush = MaybeUninit::<*mut ID2D1SolidColorBrush>::uninit();
+        let res = device_result(unsafe {
+            render_target.CreateSolidColorBrush(
+                &D2D1_COLOR_F::default(),
+                &D2D1_BRUSH_PROPERTIES {
+                    opacity: 1.0,
+                    ..Default::default()
+                },
+                brush.as_mut_ptr() as _,
+            )
+        });
+
+        if let Err(err) = res {
+            unsafe { render_target.Release() };
+            return Err(err);
+        }
+
+        Ok(Self {
+            render_target,
+            brush: unsafe { &*brush.assume_init() },
+            bitmaps: HashMap::new(),
+            dpi,
+            error: None,
+        })
+    }
+
+    fn release_bitmaps(&mut self) {
+        for (_, bitmap) in self.bitmaps.drain() {
+            unsafe {
+                bitmap.Release();
+            }
+        }
+    }
+
+    fn brush(&self, color: Color) -> *mut ID2D1SolidColorBrush {
+        unsafe { self.brush.SetColor(&color.into()) };
+        self.brush as *const _ as _
+    }
+
+    fn bitmap(&mut self, image: &Image) -> Option<&'a ID2D1Bitmap> {
+        if let Some(bitmap) = self.bitmaps.get(&image.id()) {
+            return Some(bitmap);
+        }
+
+        let pixels = image.to_bgra();
+        let mut bitmap = MaybeUninit::<*mut ID2D1Bitmap>::uninit();
+
+        let res = device_result(unsafe {
+            self.render_target.CreateBitmap(
+                D2D1_SIZE_U {
+                    width: image.width(),
+                    height: image.height(),
+                },
+                pixels.as_ptr() as _,
+                image.stride(),
+                &D2D1_BITMAP_PROPERTIES {
+                    pixelFormat: D2D1_PIXEL_FORMAT {
+                        format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                        alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                    },
+                    dpiX: Dpi::DEFAULT.value() as f32,
+                    dpiY: Dpi::DEFAULT.value() as f32,
+                },
+                bitmap.as_mut_ptr() as _,
+            )
+        });
+
+        if let Err(err) = res {
+            self.error.get_or_insert(err);
+            return None;
+        }
+
+        let bitmap = unsafe { &*bitmap.assume_init() };
+        self.bitmaps.insert(image.id(), bitmap);
+        Some(bitmap)
+    }
+}
+
+impl RenderDevice for D2DCanvas<'_> {
+    fn begin_draw(&mut self) {
+        self.error = None;
+        unsafe { self.render_target.BeginDraw() };
+    }
+
+    fn end_draw(&mut self) -> Result<(), DeviceError> {
+        let res = device_result(unsafe { self.render_target.EndDraw(null_mut(), null_mut()) });
+
+        match self.error.take() {
+            Some(err) => Err(err),
+            None => res,
+        }
+    }
+
+    fn resize(&mut self, size: Size) -> Result<(), DeviceError> {
+        device_result(unsafe {
+            self.render_target.Resize(&D2D1_SIZE_U {
+                width: size.width.max(0) as _,
+                height: size.height.max(0) as _,
+            })
+        })
+    }
+
+    // Bitmaps were uploaded for the variants picked at the old DPI, so they are dropped and get
+    // recreated on demand.
+    fn set_dpi(&mut self, dpi: Dpi) {
+        if self.dpi != dpi {
+            self.dpi = dpi;
+            self.release_bitmaps();
+        }
+    }
+}
+
+impl Canvas for D2DCanvas<'_> {
+    fn dpi(&self) -> Dpi {
+        self.dpi
+    }
+
+    fn set_antialias(&mut self, enabled: bool) {
+        let mode = if enabled {
+            D2D1_ANTIALIAS_MODE_PER_PRIMITIVE
+        } else {
+            D2D1_ANTIALIAS_MODE_ALIASED
+        };
+
+        unsafe { self.render_target.SetAntialiasMode(mode) };
+    }
+
+    fn clear(&mut self, color: Color) {
+        unsafe { self.render_target.Clear(&color.into()) };
+    }
+
+    fn fill_rect(&mut self, rect: RectF, color: Color) {
+        let brush = self.brush(color);
+        unsafe { self.render_target.FillRectangle(&rect.into(), brush as _) };
+    }
+
+    fn stroke_rect(&mut self, rect: RectF, color: Color, width: f32) {
+        let brush = self.brush(color);
+        unsafe {
+            self.render_target
+                .DrawRectangle(&rect.into(), brush as _, width, null_mut())
+        };
+    }
+
+    fn draw_line(&mut self, from: PointF, to: PointF, color: Color, width: f32) {
+        let brush = self.brush(color);
+        unsafe {
+            self.render_target
+                .DrawLine(from.into(), to.into(), brush as _, width, null_mut())
+        };
+    }
+
+    fn fill_ellipse(&mut self, center: PointF, radius_x: f32, radius_y: f32, color: Color) {
+        let brush = self.brush(color);
+        unsafe {
+            self.render_target.FillEllipse(
+                &D2D1_ELLIPSE {
+                    point: center.into(),
+                    radiusX: radius_x,
+                    radiusY: radius_y,
+                },
+                brush as _,
+            )
+        };
+    }
+
+    fn draw_image(&mut self, image: &Image, rect: RectF) {
+        let bitmap = match self.bitmap(image) {
+            Some(bitmap) => bitmap,
+            None => return,
+        };
+
+        unsafe {
+            self.render_target.DrawBitmap(
+                bitmap as *const _ as _,
+                &rect.into(),
+                1.0,
+                D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                null_mut(),
+            );
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, origin: Point, color: Color) {
+        let format = match text_format(self.dpi) {
+            Some(format) => format,
+            None => return,
+        };
+
+        let brush = self.brush(color);
+        let text = text.encode_utf16().collect::<Vec<_>>();
+        let rect = RectF::new(origin.x as f32, origin.y as f32, f32::MAX, f32::MAX);
+        unsafe {
+            self.render_target.DrawText(
+                text.as_ptr(),
+                text.len() as _,
+                format as *const _ as _,
+                &rect.into(),
+                brush as _,
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
+                DWRITE_MEASURING_MODE_NATURAL,
+            )
+        };
+    }
+
+    fn push_clip(&mut self, rect: RectF) {
+        unsafe {
+            self.render_target
+                .PushAxisAlignedClip(&rect.into(), D2D1_ANTIALIAS_MODE_ALIASED)
+        };
+    }
+
+    fn pop_clip(&mut self) {
+        unsafe { self.render_target.PopAxisAlignedClip() };
+    }
+}
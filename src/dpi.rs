@@ -0,0 +1,52 @@
+use crate::units::{Dip, Px, Rounding};
+
+// The DPI of the monitor a window is on. Layout values are written in `Dip`s and turned into
+// physical pixels through this, so every widget agrees on the same rounding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Dpi(u32);
+
+impl Dpi {
+    pub const DEFAULT: Dpi = Dpi(96);
+
+    // 0, which is what some systems report when they don't know, would turn every size into
+    // 0 and every conversion back into a division by zero, so it stands for the default.
+    pub const fn new(dpi: u32) -> Self {
+        match dpi {
+            0 => Self::DEFAULT,
+            dpi => Self(dpi),
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+
+    pub fn factor(&self) -> f32 {
+        self.0 as f32 / Self::DEFAULT.0 as f32
+    }
+
+    pub fn px(&self, dip: Dip) -> Px {
+        dip.to_px(*self, Rounding::Round)
+    }
+
+    pub fn dip(&self, px: Px) -> Dip {
+        px.to_dip(*self)
+    }
+
+    // For plain DIP values coming from the system, e.g. theme part sizes.
+    pub fn scale(&self, value: i32) -> i32 {
+        self.px(Dip(value as f32)).get()
+    }
+}
+
+impl Default for Dpi {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl From<u32> for Dpi {
+    fn from(dpi: u32) -> Self {
+        Self::new(dpi)
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::Mutex;
+use std::{fmt, io};
+
+use crate::log_error;
+use crate::logging::Target;
+
+#[derive(Debug)]
+pub enum Error {
+    Generic(String),
+    // A Win32 call failed, `source` is what GetLastError had to say about it
+    Win32 {
+        api: &'static str,
+        source: io::Error,
+    },
+    Hresult {
+        api: &'static str,
+        hr: i32,
+    },
+    // A request to a display server failed, or the connection to it
+    Display {
+        api: &'static str,
+        message: String,
+    },
+    // An error that happened while a component was handling something
+    Component {
+        component: &'static str,
+        source: Box<Error>,
+    },
+    ComponentRegistryPoisoned,
+    ComponentAlreadyRegistered,
+    // The component is handling an event or its window is gone
+    ComponentUnavailable,
+}
+
+impl Error {
+    pub fn last_os_error(api: &'static str) -> Self {
+        Error::Win32 {
+            api,
+            source: io::Error::last_os_error(),
+        }
+    }
+
+    pub fn display(api: &'static str, err: impl fmt::Display) -> Self {
+        Error::Display {
+            api,
+            message: err.to_string(),
+        }
+    }
+
+    pub fn in_component(self, component: &'static str) -> Self {
+        Error::Component {
+            component,
+            source: Box::new(self),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Generic(message) => f.write_str(message),
+            Error::Win32 { api, source } => write!(f, "{} failed: {}", api, source),
+            Error::Hresult { api, hr } => write!(f, "{} failed with HRESULT {:#010x}", api, hr),
+            Error::Display { api, message } => write!(f, "{} failed: {}", api, message),
+            Error::Component { component, source } => write!(f, "{}: {}", component, source),
+            Error::ComponentRegistryPoisoned => f.write_str("the component registry is poisoned"),
+            Error::ComponentAlreadyRegistered => f.write_str("the component is already registered"),
+            Error::ComponentUnavailable => f.write_str("the component is busy or destroyed"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Win32 { source, .. } => Some(source),
+            Error::Component { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+pub trait ResultExt<T> {
+    fn in_component(self, component: &'static str) -> Result<T, Error>;
+}
+
+impl<T> ResultExt<T> for Result<T, Error> {
+    fn in_component(self, component: &'static str) -> Result<T, Error> {
+        self.map_err(|err| err.in_component(component))
+    }
+}
+
+// For the results of display server libraries, which bring their own error types.
+pub trait DisplayResultExt<T> {
+    fn or_display(self, api: &'static str) -> Result<T, Error>;
+}
+
+impl<T, E: fmt::Display> DisplayResultExt<T> for Result<T, E> {
+    fn or_display(self, api: &'static str) -> Result<T, Error> {
+        self.map_err(|err| Error::display(api, err))
+    }
+}
+
+pub type ErrorHook = Box<dyn Fn(&Error) + Send>;
+
+static ERROR_HOOK: Mutex<Option<ErrorHook>> = Mutex::new(None);
+
+// Window procedures can't hand errors to anyone, so they report them here and carry on with
+// the default handling of the message. Without a hook the error is logged.
+pub fn set_error_hook(hook: ErrorHook) {
+    if let Ok(mut guard) = ERROR_HOOK.lock() {
+        *guard = Some(hook);
+    }
+}
+
+pub fn report_error(err: &Error) {
+    let guard = ERROR_HOOK.lock();
+    match guard.as_ref().ok().and_then(|hook| hook.as_ref()) {
+        Some(hook) => hook(err),
+        None => log_error!(Target::Window, "{}", err),
+    }
+}
@@ -0,0 +1,217 @@
+use crate::dpi::Dpi;
+use crate::error::Error;
+use crate::geometry::{Point, Rect, Size};
+use crate::platform::WindowId;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+// The buttons held down while the event happened.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MouseButtons {
+    pub left: bool,
+    pub right: bool,
+    pub middle: bool,
+}
+
+impl MouseButtons {
+    pub fn is_down(&self, button: MouseButton) -> bool {
+        match button {
+            MouseButton::Left => self.left,
+            MouseButton::Right => self.right,
+            MouseButton::Middle => self.middle,
+        }
+    }
+}
+
+// Keys the window reacts to, any other key comes as `Key::Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    Home,
+    End,
+    Enter,
+    Escape,
+    Space,
+    Tab,
+    Backspace,
+    // A key that types a character, in lower case and as typed without modifiers
+    Char(char),
+    Other,
+}
+
+// What a point of the window is to the system, decides whether it moves, resizes or hands
+// the input to the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HitZone {
+    Nowhere,
+    Client,
+    Caption,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    // Anything else the system knows about, handled like the client area
+    Other,
+}
+
+impl HitZone {
+    pub fn is_border(&self) -> bool {
+        matches!(
+            self,
+            HitZone::Left
+                | HitZone::Right
+                | HitZone::Top
+                | HitZone::Bottom
+                | HitZone::TopLeft
+                | HitZone::TopRight
+                | HitZone::BottomLeft
+                | HitZone::BottomRight
+        )
+    }
+}
+
+// Everything a component gets told about, with positions in client coordinates. `T` is the
+// component's own event type, posted through `Platform::post_event`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event<T> {
+    Created,
+    // Someone asked the window to close, `Reply::Handled` keeps it open
+    CloseRequested,
+    Destroyed,
+    // The window got or lost activation
+    Activate(bool),
+    // The window got or lost the keyboard focus
+    Focus(bool),
+    Resize(Size),
+    Paint {
+        dirty: Rect,
+    },
+    EraseBackground,
+    // `system` is what the system would answer, it knows about the resize borders
+    HitTest {
+        pos: Point,
+        system: HitZone,
+    },
+    MouseMove {
+        pos: Point,
+        buttons: MouseButtons,
+    },
+    MouseLeave,
+    MouseDown {
+        pos: Point,
+        button: MouseButton,
+        buttons: MouseButtons,
+    },
+    MouseUp {
+        pos: Point,
+        button: MouseButton,
+        buttons: MouseButtons,
+    },
+    // A key was pressed while the window had the keyboard focus, repeats included
+    KeyDown(Key),
+    // What the key pressed last typed, as the keyboard layout and the modifiers held make it.
+    // Keys like Enter may type control characters, which are better told by their `KeyDown`.
+    Text(char),
+    // A press outside of the client area, e.g. on the resize borders. `pos` is in screen
+    // coordinates.
+    NonClientMouseDown {
+        pos: Point,
+        button: MouseButton,
+    },
+    Timer(usize),
+    // `suggested` is where the system wants the window to be at the new DPI, in screen
+    // coordinates
+    Dpi {
+        dpi: Dpi,
+        suggested: Rect,
+    },
+    SettingsChanged,
+    Custom(T),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    // Not handled, the system does what it usually does
+    Default,
+    Handled,
+    HitTest(HitZone),
+}
+
+impl<T> Event<T> {
+    // Whether the sender decides what happens from the reply. The rest are notices, their
+    // default handling doesn't depend on the component.
+    pub fn expects_reply(&self) -> bool {
+        matches!(
+            self,
+            Event::CloseRequested
+                | Event::EraseBackground
+                | Event::HitTest { .. }
+                | Event::NonClientMouseDown { .. }
+        )
+    }
+
+    // The same event for a component with a different custom event type. Only custom events
+    // can't be carried over, they are handed back.
+    pub fn cast<U>(self) -> Result<Event<U>, T> {
+        let event = match self {
+            Event::Created => Event::Created,
+            Event::CloseRequested => Event::CloseRequested,
+            Event::Destroyed => Event::Destroyed,
+            Event::Activate(active) => Event::Activate(active),
+            Event::Focus(focused) => Event::Focus(focused),
+            Event::Resize(size) => Event::Resize(size),
+            Event::Paint { dirty } => Event::Paint { dirty },
+            Event::EraseBackground => Event::EraseBackground,
+            Event::HitTest { pos, system } => Event::HitTest { pos, system },
+            Event::MouseMove { pos, buttons } => Event::MouseMove { pos, buttons },
+            Event::MouseLeave => Event::MouseLeave,
+            Event::MouseDown {
+                pos,
+                button,
+                buttons,
+            } => Event::MouseDown {
+                pos,
+                button,
+                buttons,
+            },
+            Event::MouseUp {
+                pos,
+                button,
+                buttons,
+            } => Event::MouseUp {
+                pos,
+                button,
+                buttons,
+            },
+            Event::KeyDown(key) => Event::KeyDown(key),
+            Event::Text(ch) => Event::Text(ch),
+            Event::NonClientMouseDown { pos, button } => Event::NonClientMouseDown { pos, button },
+            Event::Timer(id) => Event::Timer(id),
+            Event::Dpi { dpi, suggested } => Event::Dpi { dpi, suggested },
+            Event::SettingsChanged => Event::SettingsChanged,
+            Event::Custom(custom) => return Err(custom),
+        };
+
+        Ok(event)
+    }
+}
+
+pub trait EventHandler {
+    type Custom: 'static;
+
+    // Called with the new window before it gets any event
+    fn attach(&mut self, window: WindowId);
+    fn handle_event(&mut self, event: Event<Self::Custom>) -> Result<Reply, Error>;
+}
@@ -0,0 +1,218 @@
+#[cfg(not(windows))]
+use crate::canvas::{Canvas, Color};
+use crate::dpi::Dpi;
+#[cfg(not(windows))]
+use crate::geometry::{Point, Rect};
+
+const GLYPH_WIDTH: i32 = 5;
+const GLYPH_HEIGHT: i32 = 7;
+// Between two glyphs, in font pixels
+const GLYPH_SPACING: i32 = 1;
+#[cfg(not(windows))]
+const FIRST_GLYPH: char = ' ';
+// What characters outside of the font are drawn as
+#[cfg(not(windows))]
+const MISSING_GLYPH: [u8; 5] = [0x7f, 0x41, 0x41, 0x41, 0x7f];
+
+// Printable ASCII, one byte per column from left to right with the top row in the lowest bit.
+#[cfg(not(windows))]
+#[rustfmt::skip]
+const GLYPHS: [[u8; 5]; 95] = [
+    [0x00, 0x00, 0x00, 0x00, 0x00], // ' '
+    [0x00, 0x00, 0x5f, 0x00, 0x00], // !
+    [0x00, 0x07, 0x00, 0x07, 0x00], // "
+    [0x14, 0x7f, 0x14, 0x7f, 0x14], // #
+    [0x24, 0x2a, 0x7f, 0x2a, 0x12], // $
+    [0x23, 0x13, 0x08, 0x64, 0x62], // %
+    [0x36, 0x49, 0x56, 0x20, 0x50], // &
+    [0x00, 0x00, 0x07, 0x00, 0x00], // '
+    [0x00, 0x1c, 0x22, 0x41, 0x00], // (
+    [0x00, 0x41, 0x22, 0x1c, 0x00], // )
+    [0x14, 0x08, 0x3e, 0x08, 0x14], // *
+    [0x08, 0x08, 0x3e, 0x08, 0x08], // +
+    [0x00, 0x50, 0x30, 0x00, 0x00], // ,
+    [0x08, 0x08, 0x08, 0x08, 0x08], // -
+    [0x00, 0x60, 0x60, 0x00, 0x00], // .
+    [0x20, 0x10, 0x08, 0x04, 0x02], // /
+    [0x3e, 0x51, 0x49, 0x45, 0x3e], // 0
+    [0x00, 0x42, 0x7f, 0x40, 0x00], // 1
+    [0x42, 0x61, 0x51, 0x49, 0x46], // 2
+    [0x21, 0x41, 0x45, 0x4b, 0x31], // 3
+    [0x18, 0x14, 0x12, 0x7f, 0x10], // 4
+    [0x27, 0x45, 0x45, 0x45, 0x39], // 5
+    [0x3c, 0x4a, 0x49, 0x49, 0x30], // 6
+    [0x01, 0x71, 0x09, 0x05, 0x03], // 7
+    [0x36, 0x49, 0x49, 0x49, 0x36], // 8
+    [0x06, 0x49, 0x49, 0x29, 0x1e], // 9
+    [0x00, 0x36, 0x36, 0x00, 0x00], // :
+    [0x00, 0x56, 0x36, 0x00, 0x00], // ;
+    [0x08, 0x14, 0x22, 0x41, 0x00], // <
+    [0x14, 0x14, 0x14, 0x14, 0x14], // =
+    [0x00, 0x41, 0x22, 0x14, 0x08], // >
+    [0x02, 0x01, 0x51, 0x09, 0x06], // ?
+    [0x32, 0x49, 0x79, 0x41, 0x3e], // @
+    [0x7e, 0x11, 0x11, 0x11, 0x7e], // A
+    [0x7f, 0x49, 0x49, 0x49, 0x36], // B
+    [0x3e, 0x41, 0x41, 0x41, 0x22], // C
+    [0x7f, 0x41, 0x41, 0x22, 0x1c], // D
+    [0x7f, 0x49, 0x49, 0x49, 0x41], // E
+    [0x7f, 0x09, 0x09, 0x09, 0x01], // F
+    [0x3e, 0x41, 0x49, 0x49, 0x7a], // G
+    [0x7f, 0x08, 0x08, 0x08, 0x7f], // H
+    [0x00, 0x41, 0x7f, 0x41, 0x00], // I
+    [0x20, 0x40, 0x41, 0x3f, 0x01], // J
+    [0x7f, 0x08, 0x14, 0x22, 0x41], // K
+    [0x7f, 0x40, 0x40, 0x40, 0x40], // L
+    [0x7f, 0x02, 0x0c, 0x02, 0x7f], // M
+    [0x7f, 0x04, 0x08, 0x10, 0x7f], // N
+    [0x3e, 0x41, 0x41, 0x41, 0x3e], // O
+    [0x7f, 0x09, 0x09, 0x09, 0x06], // P
+    [0x3e, 0x41, 0x51, 0x21, 0x5e], // Q
+    [0x7f, 0x09, 0x19, 0x29, 0x46], // R
+    [0x46, 0x49, 0x49, 0x49, 0x31], // S
+    [0x01, 0x01, 0x7f, 0x01, 0x01], // T
+    [0x3f, 0x40, 0x40, 0x40, 0x3f], // U
+    [0x1f, 0x20, 0x40, 0x20, 0x1f], // V
+    [0x3f, 0x40, 0x38, 0x40, 0x3f], // W
+    [0x63, 0x14, 0x08, 0x14, 0x63], // X
+    [0x07, 0x08, 0x70, 0x08, 0x07], // Y
+    [0x61, 0x51, 0x49, 0x45, 0x43], // Z
+    [0x00, 0x7f, 0x41, 0x41, 0x00], // [
+    [0x02, 0x04, 0x08, 0x10, 0x20], // \
+    [0x00, 0x41, 0x41, 0x7f, 0x00], // ]
+    [0x04, 0x02, 0x01, 0x02, 0x04], // ^
+    [0x40, 0x40, 0x40, 0x40, 0x40], // _
+    [0x00, 0x01, 0x02, 0x04, 0x00], // `
+    [0x20, 0x54, 0x54, 0x54, 0x78], // a
+    [0x7f, 0x48, 0x44, 0x44, 0x38], // b
+    [0x38, 0x44, 0x44, 0x44, 0x20], // c
+    [0x38, 0x44, 0x44, 0x48, 0x7f], // d
+    [0x38, 0x54, 0x54, 0x54, 0x18], // e
+    [0x08, 0x7e, 0x09, 0x01, 0x02], // f
+    [0x0c, 0x52, 0x52, 0x52, 0x3e], // g
+    [0x7f, 0x08, 0x04, 0x04, 0x78], // h
+    [0x00, 0x44, 0x7d, 0x40, 0x00], // i
+    [0x20, 0x40, 0x44, 0x3d, 0x00], // j
+    [0x7f, 0x10, 0x28, 0x44, 0x00], // k
+    [0x00, 0x41, 0x7f, 0x40, 0x00], // l
+    [0x7c, 0x04, 0x18, 0x04, 0x78], // m
+    [0x7c, 0x08, 0x04, 0x04, 0x78], // n
+    [0x38, 0x44, 0x44, 0x44, 0x38], // o
+    [0x7c, 0x14, 0x14, 0x14, 0x08], // p
+    [0x08, 0x14, 0x14, 0x18, 0x7c], // q
+    [0x7c, 0x08, 0x04, 0x04, 0x08], // r
+    [0x48, 0x54, 0x54, 0x54, 0x20], // s
+    [0x04, 0x3f, 0x44, 0x40, 0x20], // t
+    [0x3c, 0x40, 0x40, 0x20, 0x7c], // u
+    [0x1c, 0x20, 0x40, 0x20, 0x1c], // v
+    [0x3c, 0x40, 0x30, 0x40, 0x3c], // w
+    [0x44, 0x28, 0x10, 0x28, 0x44], // x
+    [0x0c, 0x50, 0x50, 0x50, 0x3c], // y
+    [0x44, 0x64, 0x54, 0x4c, 0x44], // z
+    [0x00, 0x08, 0x36, 0x41, 0x00], // {
+    [0x00, 0x00, 0x7f, 0x00, 0x00], // |
+    [0x00, 0x41, 0x36, 0x08, 0x00], // }
+    [0x08, 0x04, 0x08, 0x10, 0x08], // ~
+];
+
+// The font of the bits of text the window draws itself, e.g. menu labels, and the room text
+// takes in it. Canvases draw it with `Canvas::draw_text`: Direct2D through DirectWrite, the
+// software canvas with the pixel font above, whose glyphs are scaled by whole pixels so they
+// stay sharp at any DPI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Font {
+    dpi: Dpi,
+}
+
+impl Font {
+    pub fn new(dpi: Dpi) -> Self {
+        Self { dpi }
+    }
+
+    // Thickness of lines drawn along text, e.g. underlines and the caret, and the size of a pixel
+    // of the pixel font
+    pub fn scale(&self) -> i32 {
+        (self.dpi.factor().round() as i32).max(1)
+    }
+
+    pub fn height(&self) -> i32 {
+        #[cfg(windows)]
+        if let Some((_, height)) = crate::d2d_canvas::measure_text("", self.dpi) {
+            return height.ceil() as i32;
+        }
+
+        GLYPH_HEIGHT * self.scale()
+    }
+
+    pub fn text_width(&self, text: &str) -> i32 {
+        #[cfg(windows)]
+        if let Some((width, _)) = crate::d2d_canvas::measure_text(text, self.dpi) {
+            return width.ceil() as i32;
+        }
+
+        match text.chars().count() as i32 {
+            0 => 0,
+            len => len * self.advance() - GLYPH_SPACING * self.scale(),
+        }
+    }
+
+    // Where the character at `index` is drawn, with the text starting at 0.
+    pub fn char_span(&self, text: &str, index: usize) -> (i32, i32) {
+        let offset = |index| {
+            text.char_indices()
+                .nth(index)
+                .map_or(text.len(), |(i, _)| i)
+        };
+        let (start, end) = (offset(index), offset(index + 1));
+        let right = self.text_width(&text[..end]);
+        (right - self.text_width(&text[start..end]), right)
+    }
+
+    // From the start of one glyph of the pixel font to the start of the next
+    fn advance(&self) -> i32 {
+        (GLYPH_WIDTH + GLYPH_SPACING) * self.scale()
+    }
+
+    // Draws `text` in the pixel font with its top left corner at `origin`, for canvases without
+    // text of their own.
+    #[cfg(not(windows))]
+    pub fn draw(&self, canvas: &mut dyn Canvas, text: &str, origin: Point, color: Color) {
+        let scale = self.scale();
+        for (index, ch) in text.chars().enumerate() {
+            let left = index as i32 * self.advance();
+
+            for (column, bits) in glyph(ch).iter().enumerate() {
+                let x = origin.x + left + column as i32 * scale;
+
+                // Each run of set pixels in a column is one rectangle
+                let mut row = 0;
+                while row < GLYPH_HEIGHT {
+                    if bits & (1 << row) == 0 {
+                        row += 1;
+                        continue;
+                    }
+
+                    let start = row;
+                    while row < GLYPH_HEIGHT && bits & (1 << row) != 0 {
+                        row += 1;
+                    }
+                    let rect = Rect::new(
+                        x,
+                        origin.y + start * scale,
+                        x + scale,
+                        origin.y + row * scale,
+                    );
+                    canvas.fill_rect(rect.into(), color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(windows))]
+fn glyph(ch: char) -> &'static [u8; 5] {
+    (ch as usize)
+        .checked_sub(FIRST_GLYPH as usize)
+        .and_then(|index| GLYPHS.get(index))
+        .unwrap_or(&MISSING_GLYPH)
+}
@@ -0,0 +1,307 @@
+#[cfg(windows)]
+use winapi::shared::windef::RECT;
+
+use crate::units::{self, Rounding};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rect {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Rect {
+    pub const fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub const fn from_size(left: i32, top: i32, width: i32, height: i32) -> Self {
+        Self::new(left, top, left + width, top + height)
+    }
+
+    pub fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width(), self.height())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.right <= self.left || self.bottom <= self.top
+    }
+
+    pub fn area(&self) -> i64 {
+        if self.is_empty() {
+            return 0;
+        }
+
+        self.width() as i64 * self.height() as i64
+    }
+
+    pub fn contains_point(&self, x: i32, y: i32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+
+    pub fn contains(&self, other: &Rect) -> bool {
+        other.is_empty()
+            || (other.left >= self.left
+                && other.top >= self.top
+                && other.right <= self.right
+                && other.bottom <= self.bottom)
+    }
+
+    pub fn intersects(&self, other: &Rect) -> bool {
+        !self.intersection(other).is_empty()
+    }
+
+    // Unlike `intersects`, rects that only share an edge count as touching.
+    pub fn touches(&self, other: &Rect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.left <= other.right
+            && other.left <= self.right
+            && self.top <= other.bottom
+            && other.top <= self.bottom
+    }
+
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        let rect = Rect::new(
+            self.left.max(other.left),
+            self.top.max(other.top),
+            self.right.min(other.right),
+            self.bottom.min(other.bottom),
+        );
+
+        if rect.is_empty() {
+            Rect::default()
+        } else {
+            rect
+        }
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+
+        Rect::new(
+            self.left.min(other.left),
+            self.top.min(other.top),
+            self.right.max(other.right),
+            self.bottom.max(other.bottom),
+        )
+    }
+
+    // Shrinks the rect by the insets, never past an empty rect.
+    pub fn inset(&self, insets: &Insets) -> Rect {
+        let left = self.left + insets.left;
+        let top = self.top + insets.top;
+
+        Rect::new(
+            left,
+            top,
+            (self.right - insets.right).max(left),
+            (self.bottom - insets.bottom).max(top),
+        )
+    }
+
+    pub fn offset(&self, dx: i32, dy: i32) -> Rect {
+        Rect::new(
+            self.left + dx,
+            self.top + dy,
+            self.right + dx,
+            self.bottom + dy,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub const fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Size {
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Size {
+    pub const fn new(width: i32, height: i32) -> Self {
+        Self { width, height }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Insets {
+    pub left: i32,
+    pub top: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl Insets {
+    pub const fn new(left: i32, top: i32, right: i32, bottom: i32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub const fn uniform(value: i32) -> Self {
+        Self::new(value, value, value, value)
+    }
+
+    pub fn horizontal(&self) -> i32 {
+        self.left + self.right
+    }
+
+    pub fn vertical(&self) -> i32 {
+        self.top + self.bottom
+    }
+}
+
+#[cfg(windows)]
+impl From<RECT> for Rect {
+    fn from(rect: RECT) -> Self {
+        Rect::new(rect.left, rect.top, rect.right, rect.bottom)
+    }
+}
+
+#[cfg(windows)]
+impl From<Rect> for RECT {
+    fn from(rect: Rect) -> Self {
+        RECT {
+            left: rect.left,
+            top: rect.top,
+            right: rect.right,
+            bottom: rect.bottom,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PointF {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl PointF {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn snap_to_pixel(&self, stroke_width: f32) -> PointF {
+        PointF::new(
+            units::snap_to_pixel(self.x, stroke_width),
+            units::snap_to_pixel(self.y, stroke_width),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RectF {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl RectF {
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.bottom - self.top
+    }
+
+    pub fn center(&self) -> PointF {
+        PointF::new(
+            (self.left + self.right) / 2.0,
+            (self.top + self.bottom) / 2.0,
+        )
+    }
+
+    pub fn centered_in(&self, outer: &RectF) -> RectF {
+        let left = outer.left + (outer.width() - self.width()) / 2.0;
+        let top = outer.top + (outer.height() - self.height()) / 2.0;
+        RectF::new(left, top, left + self.width(), top + self.height())
+    }
+
+    pub fn round(&self) -> Rect {
+        self.snap(Rounding::Round)
+    }
+
+    pub fn snap(&self, rounding: Rounding) -> Rect {
+        Rect::new(
+            rounding.apply(self.left),
+            rounding.apply(self.top),
+            rounding.apply(self.right),
+            rounding.apply(self.bottom),
+        )
+    }
+
+    // Moves the edges so a stroke of `stroke_width` pixels along them stays crisp.
+    pub fn snap_to_pixel(&self, stroke_width: f32) -> RectF {
+        RectF::new(
+            units::snap_to_pixel(self.left, stroke_width),
+            units::snap_to_pixel(self.top, stroke_width),
+            units::snap_to_pixel(self.right, stroke_width),
+            units::snap_to_pixel(self.bottom, stroke_width),
+        )
+    }
+
+    // Smallest integer rect covering this one.
+    pub fn round_out(&self) -> Rect {
+        Rect::new(
+            self.left.floor() as _,
+            self.top.floor() as _,
+            self.right.ceil() as _,
+            self.bottom.ceil() as _,
+        )
+    }
+}
+
+impl From<Rect> for RectF {
+    fn from(rect: Rect) -> Self {
+        RectF::new(
+            rect.left as _,
+            rect.top as _,
+            rect.right as _,
+            rect.bottom as _,
+        )
+    }
+}
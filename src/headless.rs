@@ -0,0 +1,836 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::animation::{Clock, VirtualClock};
+use crate::canvas::{Canvas, Color};
+use crate::dpi::Dpi;
+use crate::error::{self, Error};
+use crate::event::{Event, EventHandler, HitZone, Key, MouseButton, MouseButtons, Reply};
+use crate::geometry::{Point, PointF, Rect, RectF, Size};
+use crate::host::{Dispatch, Host, Hosted};
+use crate::image::Image;
+use crate::platform::{Platform, SystemCommand, WindowId, WindowParams};
+use crate::region::DirtyRegion;
+use crate::render_resources::{DeviceError, DeviceFactory, RenderDevice};
+
+const SCREEN: Rect = Rect::new(0, 0, 1920, 1080);
+const CAPTION_HEIGHT: i32 = 30;
+const RESIZE_BORDER: i32 = 8;
+
+type SendFn = Rc<dyn Fn(Event<()>) -> Option<Reply>>;
+
+// What the headless canvas was asked to draw, for tests to look at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawOp {
+    Clear(Color),
+    FillRect(RectF, Color),
+    StrokeRect(RectF, Color, f32),
+    Line(PointF, PointF, Color),
+    Ellipse(PointF, Color),
+    Image(u64, RectF),
+    Text(String, Point, Color),
+    PushClip(RectF),
+    PopClip,
+}
+
+pub type Frame = Vec<DrawOp>;
+
+enum Posted {
+    Command(WindowId, SystemCommand),
+    Custom(Box<dyn FnOnce()>),
+}
+
+struct HeadlessWindow {
+    // In screen coordinates. There is no frame, the client area covers all of it.
+    bounds: Rect,
+    restored: Rect,
+    maximized: bool,
+    minimized: bool,
+    dpi: Dpi,
+    invalid: DirtyRegion,
+    // Interval and when the timer fires next
+    timers: HashMap<usize, (Duration, Duration)>,
+    tracking_leave: bool,
+    owner: Option<WindowId>,
+    frames: Rc<RefCell<Vec<Frame>>>,
+    send: SendFn,
+    destroy: Rc<dyn Fn()>,
+}
+
+#[derive(Default)]
+struct State {
+    windows: HashMap<WindowId, HeadlessWindow>,
+    next_id: usize,
+    active: Option<WindowId>,
+    captured: Option<WindowId>,
+    cursor: Point,
+    buttons: MouseButtons,
+    posted: VecDeque<Posted>,
+    quit: bool,
+    // What `confirm` answers next, and what it was asked so far
+    answers: VecDeque<bool>,
+    questions: Vec<String>,
+    // Every system command run so far
+    commands: Vec<(WindowId, SystemCommand)>,
+}
+
+// A platform without a screen. Windows only exist in memory, time only moves when told to
+// and input is whatever the caller scripts, so the whole app can run in tests.
+pub struct HeadlessPlatform {
+    clock: Rc<VirtualClock>,
+    dpi: Dpi,
+    animations_enabled: bool,
+    state: Rc<RefCell<State>>,
+}
+
+impl Default for HeadlessPlatform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HeadlessPlatform {
+    pub fn new() -> Self {
+        Self {
+            clock: Rc::new(VirtualClock::new()),
+            dpi: Dpi::DEFAULT,
+            animations_enabled: true,
+            state: Rc::new(RefCell::new(State::default())),
+        }
+    }
+
+    // The DPI new windows start out with.
+    pub fn with_dpi(mut self, dpi: Dpi) -> Self {
+        self.dpi = dpi;
+        self
+    }
+
+    pub fn with_animations(mut self, enabled: bool) -> Self {
+        self.animations_enabled = enabled;
+        self
+    }
+
+    pub fn now(&self) -> Duration {
+        self.clock.now()
+    }
+
+    // Hands `event` to the window right away. `None` if the window is gone or busy.
+    pub fn send(&self, window: WindowId, event: Event<()>) -> Option<Reply> {
+        let send = self.state.borrow().windows.get(&window)?.send.clone();
+        send(event)
+    }
+
+    pub fn hit_test(&self, window: WindowId, pos: Point) -> HitZone {
+        let system = match self.client_rect(window) {
+            Ok(rect) if !rect.contains_point(pos.x, pos.y) => HitZone::Nowhere,
+            Ok(_) if pos.x < RESIZE_BORDER => HitZone::Left,
+            Ok(rect) if pos.x >= rect.right - RESIZE_BORDER => HitZone::Right,
+            Ok(rect) if pos.y >= rect.bottom - RESIZE_BORDER => HitZone::Bottom,
+            _ => HitZone::Client,
+        };
+
+        match self.send(window, Event::HitTest { pos, system }) {
+            Some(Reply::HitTest(zone)) => zone,
+            _ => system,
+        }
+    }
+
+    pub fn move_mouse(&self, window: WindowId, pos: Point) {
+        let cursor = self.client_to_screen(window, pos).unwrap_or(pos);
+        let buttons = {
+            let mut state = self.state.borrow_mut();
+            state.cursor = cursor;
+            state.buttons
+        };
+
+        let inside = self
+            .client_rect(window)
+            .map(|rect| rect.contains_point(pos.x, pos.y))
+            .unwrap_or(false);
+
+        if inside || self.is_captured(window) {
+            self.send(window, Event::MouseMove { pos, buttons });
+        } else {
+            self.leave(window);
+        }
+    }
+
+    // The mouse left the window for somewhere else.
+    pub fn leave(&self, window: WindowId) {
+        let tracking = match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => std::mem::replace(&mut data.tracking_leave, false),
+            None => false,
+        };
+
+        if tracking {
+            self.send(window, Event::MouseLeave);
+        }
+    }
+
+    pub fn press(&self, window: WindowId, pos: Point, button: MouseButton) {
+        let buttons = self.set_button(button, true);
+        self.send(
+            window,
+            Event::MouseDown {
+                pos,
+                button,
+                buttons,
+            },
+        );
+    }
+
+    pub fn release(&self, window: WindowId, pos: Point, button: MouseButton) {
+        let buttons = self.set_button(button, false);
+        self.send(
+            window,
+            Event::MouseUp {
+                pos,
+                button,
+                buttons,
+            },
+        );
+    }
+
+    pub fn press_key(&self, window: WindowId, key: Key) {
+        self.send(window, Event::KeyDown(key));
+    }
+
+    // Types `text` a character at a time, each with the press of a key that types it.
+    pub fn type_text(&self, window: WindowId, text: &str) {
+        for ch in text.chars() {
+            let key = match ch {
+                ' ' => Key::Space,
+                ch => ch.to_lowercase().next().map_or(Key::Other, Key::Char),
+            };
+            self.send(window, Event::KeyDown(key));
+            self.send(window, Event::Text(ch));
+        }
+    }
+
+    pub fn click(&self, window: WindowId, pos: Point) {
+        self.move_mouse(window, pos);
+        self.press(window, pos, MouseButton::Left);
+        self.release(window, pos, MouseButton::Left);
+    }
+
+    fn set_button(&self, button: MouseButton, down: bool) -> MouseButtons {
+        let mut state = self.state.borrow_mut();
+        match button {
+            MouseButton::Left => state.buttons.left = down,
+            MouseButton::Right => state.buttons.right = down,
+            MouseButton::Middle => state.buttons.middle = down,
+        }
+        state.buttons
+    }
+
+    pub fn set_active(&self, window: WindowId, active: bool) {
+        let previous = {
+            let mut state = self.state.borrow_mut();
+            let previous = state.active;
+            state.active = if active { Some(window) } else { None };
+            previous
+        };
+
+        if let Some(previous) = previous.filter(|previous| *previous != window) {
+            self.send(previous, Event::Activate(false));
+        }
+        self.send(window, Event::Activate(active));
+    }
+
+    // Moves the window to a monitor with another DPI, keeping its logical size.
+    pub fn change_dpi(&self, window: WindowId, dpi: Dpi) -> Result<(), Error> {
+        let bounds = self.window_bounds(window)?;
+        let factor = dpi.factor() / self.dpi(window)?.factor();
+        let suggested = Rect::from_size(
+            bounds.left,
+            bounds.top,
+            (bounds.width() as f32 * factor).round() as _,
+            (bounds.height() as f32 * factor).round() as _,
+        );
+
+        if let Some(data) = self.state.borrow_mut().windows.get_mut(&window) {
+            data.dpi = dpi;
+        }
+
+        self.send(window, Event::Dpi { dpi, suggested });
+        Ok(())
+    }
+
+    // Moves time forward, firing every timer that comes due on the way.
+    pub fn advance(&self, by: Duration) {
+        let end = self.clock.now() + by;
+
+        loop {
+            let next = self
+                .state
+                .borrow()
+                .windows
+                .iter()
+                .flat_map(|(window, data)| {
+                    data.timers
+                        .iter()
+                        .map(move |(id, (_, due))| (*due, *window, *id))
+                })
+                .filter(|(due, _, _)| *due <= end)
+                .min_by_key(|(due, window, id)| (*due, window.0, *id));
+
+            let (due, window, id) = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            self.clock.set(due.max(self.clock.now()));
+            if let Some(data) = self.state.borrow_mut().windows.get_mut(&window) {
+                if let Some((interval, next_due)) = data.timers.get_mut(&id) {
+                    *next_due = due + *interval;
+                }
+            }
+            self.send(window, Event::Timer(id));
+        }
+
+        self.clock.set(end);
+    }
+
+    // Delivers everything that was posted so far, including what gets posted on the way.
+    pub fn run_pending(&self) {
+        loop {
+            let posted = self.state.borrow_mut().posted.pop_front();
+            match posted {
+                Some(Posted::Command(window, command)) => self.run_command(window, command),
+                Some(Posted::Custom(deliver)) => deliver(),
+                None => break,
+            }
+        }
+    }
+
+    fn run_command(&self, window: WindowId, command: SystemCommand) {
+        self.state.borrow_mut().commands.push((window, command));
+        if command == SystemCommand::Close {
+            close_window(&self.state, window);
+            return;
+        }
+
+        let resized = {
+            let mut state = self.state.borrow_mut();
+            let data = match state.windows.get_mut(&window) {
+                Some(data) => data,
+                None => return,
+            };
+
+            match command {
+                SystemCommand::Minimize => {
+                    data.minimized = true;
+                    None
+                }
+                SystemCommand::Maximize if !data.maximized => {
+                    data.restored = data.bounds;
+                    data.bounds = SCREEN;
+                    data.maximized = true;
+                    Some(data.bounds.size())
+                }
+                SystemCommand::Restore if data.maximized || data.minimized => {
+                    data.minimized = false;
+                    if data.maximized {
+                        data.maximized = false;
+                        data.bounds = data.restored;
+                    }
+                    Some(data.bounds.size())
+                }
+                _ => None,
+            }
+        };
+
+        if let Some(size) = resized {
+            self.send(window, Event::Resize(size));
+            let _ = self.invalidate(window, Rect::from_size(0, 0, size.width, size.height));
+        }
+    }
+
+    // Paints whatever was invalidated, like the system does once the queue is empty.
+    pub fn paint(&self, window: WindowId) {
+        let dirty = match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) if !data.invalid.is_empty() => data.invalid.take().bounds(),
+            _ => return,
+        };
+
+        self.send(window, Event::Paint { dirty });
+    }
+
+    pub fn frames(&self, window: WindowId) -> Vec<Frame> {
+        match self.state.borrow().windows.get(&window) {
+            Some(data) => data.frames.borrow().clone(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn invalid_region(&self, window: WindowId) -> Option<DirtyRegion> {
+        let state = self.state.borrow();
+        let data = state.windows.get(&window)?;
+
+        let mut region = DirtyRegion::new();
+        region.add_region(&data.invalid);
+        Some(region)
+    }
+
+    // The windows that are open, the oldest first.
+    pub fn windows(&self) -> Vec<WindowId> {
+        let mut windows = self
+            .state
+            .borrow()
+            .windows
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        windows.sort_by_key(|window| window.0);
+        windows
+    }
+
+    // The popups open over `owner`, the oldest first.
+    pub fn popups(&self, owner: WindowId) -> Vec<WindowId> {
+        let state = self.state.borrow();
+        let mut popups = state
+            .windows
+            .iter()
+            .filter(|(_, data)| data.owner == Some(owner))
+            .map(|(window, _)| *window)
+            .collect::<Vec<_>>();
+        popups.sort_by_key(|window| window.0);
+        popups
+    }
+
+    pub fn is_open(&self, window: WindowId) -> bool {
+        self.state.borrow().windows.contains_key(&window)
+    }
+
+    pub fn is_minimized(&self, window: WindowId) -> bool {
+        let state = self.state.borrow();
+        state
+            .windows
+            .get(&window)
+            .is_some_and(|data| data.minimized)
+    }
+
+    pub fn is_captured(&self, window: WindowId) -> bool {
+        self.state.borrow().captured == Some(window)
+    }
+
+    pub fn timer_interval(&self, window: WindowId, id: usize) -> Option<Duration> {
+        let state = self.state.borrow();
+        let data = state.windows.get(&window)?;
+        data.timers.get(&id).map(|(interval, _)| *interval)
+    }
+
+    pub fn has_quit(&self) -> bool {
+        self.state.borrow().quit
+    }
+
+    // Scripts the answer to the next question. Questions nobody scripted can't be asked here,
+    // like where there is no dialog.
+    pub fn answer_next(&self, yes: bool) {
+        self.state.borrow_mut().answers.push_back(yes);
+    }
+
+    pub fn questions(&self) -> Vec<String> {
+        self.state.borrow().questions.clone()
+    }
+
+    // The system commands the window ran so far, also those there is nothing to do for
+    // without a screen.
+    pub fn commands(&self, window: WindowId) -> Vec<SystemCommand> {
+        let state = self.state.borrow();
+        state
+            .commands
+            .iter()
+            .filter(|(of, _)| *of == window)
+            .map(|(_, command)| *command)
+            .collect()
+    }
+
+    fn with_window<R>(
+        &self,
+        window: WindowId,
+        f: impl FnOnce(&mut HeadlessWindow) -> R,
+    ) -> Result<R, Error> {
+        match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => Ok(f(data)),
+            None => Err(Error::Generic(format!("no window {:?}", window))),
+        }
+    }
+}
+
+// Destroys the window unless its component wants to keep it open.
+fn close_window(state: &Rc<RefCell<State>>, window: WindowId) {
+    let send = match state.borrow().windows.get(&window) {
+        Some(data) => data.send.clone(),
+        None => return,
+    };
+
+    if send(Event::CloseRequested) != Some(Reply::Handled) {
+        destroy_window(state, window);
+    }
+}
+
+// Forgets the window, which makes it drop its component after telling it.
+fn destroy_window(state: &Rc<RefCell<State>>, window: WindowId) {
+    let data = match state.borrow_mut().windows.remove(&window) {
+        Some(data) => data,
+        None => return,
+    };
+
+    {
+        let mut state = state.borrow_mut();
+        if state.captured == Some(window) {
+            state.captured = None;
+        }
+        if state.active == Some(window) {
+            state.active = None;
+        }
+    }
+
+    (data.send)(Event::Destroyed);
+    (data.destroy)();
+}
+
+impl Platform for HeadlessPlatform {
+    type DeviceFactory = RecordingDeviceFactory;
+
+    fn create_window<C: EventHandler + 'static>(
+        &self,
+        params: &WindowParams,
+        component: C,
+    ) -> Result<Hosted<C>, Error> {
+        let host = Rc::new(Host::new(component));
+        let name = params.class_name;
+
+        let window = {
+            let mut state = self.state.borrow_mut();
+            state.next_id += 1;
+            WindowId(state.next_id)
+        };
+        host.with(|component| component.attach(window));
+
+        let send: SendFn = {
+            let host = host.clone();
+            Rc::new(move |event| {
+                let event = event.cast().ok()?;
+                let handle = |component: &mut C, event| match component.handle_event(event) {
+                    Ok(reply) => reply,
+                    Err(err) => {
+                        error::report_error(&err.in_component(name));
+                        Reply::Default
+                    }
+                };
+
+                match host.dispatch(event, handle) {
+                    Dispatch::Handled(reply) => Some(reply),
+                    Dispatch::Deferred | Dispatch::Busy | Dispatch::Gone => None,
+                }
+            })
+        };
+
+        let destroy: Rc<dyn Fn()> = {
+            let host = host.clone();
+            Rc::new(move || host.destroy())
+        };
+
+        let bounds = match params.popup {
+            Some(popup) => {
+                let origin = self.client_to_screen(
+                    popup.owner,
+                    Point::new(popup.bounds.left, popup.bounds.top),
+                )?;
+                Rect::from_size(
+                    origin.x,
+                    origin.y,
+                    popup.bounds.width(),
+                    popup.bounds.height(),
+                )
+            }
+            None => Rect::from_size(0, 0, params.size.width, params.size.height),
+        };
+        self.state.borrow_mut().windows.insert(
+            window,
+            HeadlessWindow {
+                bounds,
+                restored: bounds,
+                maximized: false,
+                minimized: false,
+                dpi: self.dpi,
+                invalid: DirtyRegion::new(),
+                timers: HashMap::new(),
+                tracking_leave: false,
+                owner: params.popup.map(|popup| popup.owner),
+                frames: Rc::new(RefCell::new(Vec::new())),
+                send: send.clone(),
+                destroy,
+            },
+        );
+
+        send(Event::Created);
+        send(Event::Resize(self.client_rect(window)?.size()));
+        self.invalidate(window, self.client_rect(window)?)?;
+
+        let state = self.state.clone();
+        Ok(Hosted::new(host, window, move || {
+            destroy_window(&state, window)
+        }))
+    }
+
+    fn post_event<C: EventHandler + 'static>(
+        &self,
+        window: &Hosted<C>,
+        event: C::Custom,
+    ) -> Result<(), Error> {
+        let host = window.host().clone();
+        let deliver = move || {
+            host.dispatch(Event::Custom(event), |component, event| {
+                if let Err(err) = component.handle_event(event) {
+                    error::report_error(&err);
+                }
+            });
+        };
+
+        let mut state = self.state.borrow_mut();
+        state.posted.push_back(Posted::Custom(Box::new(deliver)));
+        Ok(())
+    }
+
+    fn post_command(&self, window: WindowId, command: SystemCommand) -> Result<(), Error> {
+        let mut state = self.state.borrow_mut();
+        state.posted.push_back(Posted::Command(window, command));
+        Ok(())
+    }
+
+    fn quit(&self) {
+        self.state.borrow_mut().quit = true;
+    }
+
+    fn device_factory(&self, window: WindowId) -> RecordingDeviceFactory {
+        let frames = match self.state.borrow().windows.get(&window) {
+            Some(data) => data.frames.clone(),
+            None => Rc::new(RefCell::new(Vec::new())),
+        };
+
+        RecordingDeviceFactory { frames }
+    }
+
+    fn clock(&self) -> Rc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    fn dpi(&self, window: WindowId) -> Result<Dpi, Error> {
+        self.with_window(window, |data| data.dpi)
+    }
+
+    fn client_rect(&self, window: WindowId) -> Result<Rect, Error> {
+        self.with_window(window, |data| {
+            Rect::from_size(0, 0, data.bounds.width(), data.bounds.height())
+        })
+    }
+
+    fn window_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        self.with_window(window, |data| data.bounds)
+    }
+
+    fn set_window_bounds(&self, window: WindowId, bounds: Rect) -> Result<(), Error> {
+        let resized = self.with_window(window, |data| {
+            let resized = data.bounds.size() != bounds.size();
+            data.bounds = bounds;
+            resized
+        })?;
+
+        if resized {
+            self.send(window, Event::Resize(bounds.size()));
+        }
+        Ok(())
+    }
+
+    fn resize_window(&self, window: WindowId, size: Size) -> Result<(), Error> {
+        let bounds = self.window_bounds(window)?;
+        self.set_window_bounds(
+            window,
+            Rect::from_size(bounds.left, bounds.top, size.width, size.height),
+        )
+    }
+
+    fn restored_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        self.with_window(window, |data| {
+            if data.maximized {
+                data.restored
+            } else {
+                data.bounds
+            }
+        })
+    }
+
+    fn is_maximized(&self, window: WindowId) -> Result<bool, Error> {
+        self.with_window(window, |data| data.maximized)
+    }
+
+    fn is_active(&self, window: WindowId) -> bool {
+        self.state.borrow().active == Some(window)
+    }
+
+    fn caption_height(&self, _window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        Ok(dpi.scale(CAPTION_HEIGHT))
+    }
+
+    fn resize_border(&self, _window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        Ok(dpi.scale(RESIZE_BORDER))
+    }
+
+    fn invalidate(&self, window: WindowId, rect: Rect) -> Result<(), Error> {
+        self.with_window(window, |data| data.invalid.add(rect))
+    }
+
+    fn set_capture(&self, window: WindowId) {
+        self.state.borrow_mut().captured = Some(window);
+    }
+
+    fn release_capture(&self, window: WindowId) -> Result<(), Error> {
+        let mut state = self.state.borrow_mut();
+        if state.captured == Some(window) {
+            state.captured = None;
+        }
+        Ok(())
+    }
+
+    fn track_mouse_leave(&self, window: WindowId) -> Result<(), Error> {
+        self.with_window(window, |data| data.tracking_leave = true)
+    }
+
+    fn set_timer(&self, window: WindowId, id: usize, interval: Duration) -> Result<(), Error> {
+        let now = self.clock.now();
+        self.with_window(window, |data| {
+            data.timers.insert(id, (interval, now + interval));
+        })
+    }
+
+    fn kill_timer(&self, window: WindowId, id: usize) {
+        let _ = self.with_window(window, |data| data.timers.remove(&id));
+    }
+
+    fn cursor_position(&self) -> Result<Point, Error> {
+        Ok(self.state.borrow().cursor)
+    }
+
+    fn client_to_screen(&self, window: WindowId, point: Point) -> Result<Point, Error> {
+        let bounds = self.window_bounds(window)?;
+        Ok(Point::new(bounds.left + point.x, bounds.top + point.y))
+    }
+
+    fn animations_enabled(&self) -> bool {
+        self.animations_enabled
+    }
+
+    fn monitors(&self) -> Result<Vec<Rect>, Error> {
+        Ok(vec![SCREEN])
+    }
+
+    fn monitor(&self, _window: WindowId) -> Result<Option<Rect>, Error> {
+        Ok(Some(SCREEN))
+    }
+
+    fn confirm(
+        &self,
+        window: WindowId,
+        _title: &str,
+        question: &str,
+    ) -> Result<Option<bool>, Error> {
+        self.with_window(window, |_| ())?;
+
+        let mut state = self.state.borrow_mut();
+        state.questions.push(String::from(question));
+        Ok(state.answers.pop_front())
+    }
+}
+
+pub struct RecordingDeviceFactory {
+    frames: Rc<RefCell<Vec<Frame>>>,
+}
+
+impl DeviceFactory for RecordingDeviceFactory {
+    type Device = RecordingDevice;
+
+    fn create_device(&mut self, _size: Size, dpi: Dpi) -> Result<RecordingDevice, DeviceError> {
+        Ok(RecordingDevice {
+            frames: self.frames.clone(),
+            current: Vec::new(),
+            dpi,
+        })
+    }
+}
+
+// Records every draw call of a frame and keeps the finished frames.
+pub struct RecordingDevice {
+    frames: Rc<RefCell<Vec<Frame>>>,
+    current: Frame,
+    dpi: Dpi,
+}
+
+impl Canvas for RecordingDevice {
+    fn dpi(&self) -> Dpi {
+        self.dpi
+    }
+
+    fn set_antialias(&mut self, _enabled: bool) {}
+
+    fn clear(&mut self, color: Color) {
+        self.current.push(DrawOp::Clear(color));
+    }
+
+    fn fill_rect(&mut self, rect: RectF, color: Color) {
+        self.current.push(DrawOp::FillRect(rect, color));
+    }
+
+    fn stroke_rect(&mut self, rect: RectF, color: Color, width: f32) {
+        self.current.push(DrawOp::StrokeRect(rect, color, width));
+    }
+
+    fn draw_line(&mut self, from: PointF, to: PointF, color: Color, _width: f32) {
+        self.current.push(DrawOp::Line(from, to, color));
+    }
+
+    fn fill_ellipse(&mut self, center: PointF, _radius_x: f32, _radius_y: f32, color: Color) {
+        self.current.push(DrawOp::Ellipse(center, color));
+    }
+
+    fn draw_image(&mut self, image: &Image, rect: RectF) {
+        self.current.push(DrawOp::Image(image.id(), rect));
+    }
+
+    fn draw_text(&mut self, text: &str, origin: Point, color: Color) {
+        self.current
+            .push(DrawOp::Text(text.to_string(), origin, color));
+    }
+
+    fn push_clip(&mut self, rect: RectF) {
+        self.current.push(DrawOp::PushClip(rect));
+    }
+
+    fn pop_clip(&mut self) {
+        self.current.push(DrawOp::PopClip);
+    }
+}
+
+impl RenderDevice for RecordingDevice {
+    fn begin_draw(&mut self) {
+        self.current.clear();
+    }
+
+    fn end_draw(&mut self) -> Result<(), DeviceError> {
+        let frame = std::mem::take(&mut self.current);
+        self.frames.borrow_mut().push(frame);
+        Ok(())
+    }
+
+    fn resize(&mut self, _size: Size) -> Result<(), DeviceError> {
+        Ok(())
+    }
+
+    fn set_dpi(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+    }
+}
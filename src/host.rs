@@ -0,0 +1,174 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+use crate::error::Error;
+use crate::event::{Event, EventHandler};
+use crate::platform::WindowId;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Dispatch<R> {
+    Handled(R),
+    // The component was busy with another event, this one runs once that is done
+    Deferred,
+    // The component was busy and the sender waits for the reply, which nobody would take
+    // once the event got its turn. It is dropped, the sender handles it the default way.
+    Busy,
+    // The component is destroyed
+    Gone,
+}
+
+// Events a busy host may queue for later.
+pub trait Deferrable {
+    fn can_defer(&self) -> bool;
+}
+
+// Only notices, the answer to a question has to be there when the sender asks.
+impl<T> Deferrable for Event<T> {
+    fn can_defer(&self) -> bool {
+        !self.expects_reply()
+    }
+}
+
+// Owns a component on behalf of its window and hands out `&mut` access one event at a time.
+//
+// Window procedures are re-entered whenever a handler does something that sends a message
+// synchronously (e.g. `SetWindowPos`, `DestroyWindow`). Such nested events are queued and
+// run after the handler returns instead of aliasing the component, unless their sender waits
+// for the reply. Destroying the component
+// while a handler runs only marks it, it is dropped once nothing uses it anymore.
+pub struct Host<C, E> {
+    component: RefCell<Option<C>>,
+    pending: RefCell<VecDeque<E>>,
+    destroyed: Cell<bool>,
+}
+
+impl<C, E> Host<C, E> {
+    pub fn new(component: C) -> Self {
+        Self {
+            component: RefCell::new(Some(component)),
+            pending: RefCell::new(VecDeque::new()),
+            destroyed: Cell::new(false),
+        }
+    }
+
+    pub fn is_destroyed(&self) -> bool {
+        self.destroyed.get()
+    }
+
+    // Whether a handler is running right now.
+    pub fn is_busy(&self) -> bool {
+        self.component.try_borrow_mut().is_err()
+    }
+
+    pub fn dispatch<R>(&self, event: E, mut handle: impl FnMut(&mut C, E) -> R) -> Dispatch<R>
+    where
+        E: Deferrable,
+    {
+        if self.is_destroyed() {
+            return Dispatch::Gone;
+        }
+
+        let mut guard = match self.component.try_borrow_mut() {
+            Ok(guard) => guard,
+            Err(_) if event.can_defer() => {
+                self.pending.borrow_mut().push_back(event);
+                return Dispatch::Deferred;
+            }
+            Err(_) => return Dispatch::Busy,
+        };
+
+        let component = match guard.as_mut() {
+            Some(component) => component,
+            None => return Dispatch::Gone,
+        };
+
+        let result = handle(component, event);
+
+        // Whatever came in while the handler ran, in order. Handlers of these can queue
+        // more events again. Only notices get queued, so their replies are of no use.
+        while !self.is_destroyed() {
+            let event = match self.pending.borrow_mut().pop_front() {
+                Some(event) => event,
+                None => break,
+            };
+            handle(component, event);
+        }
+
+        if self.is_destroyed() {
+            self.pending.borrow_mut().clear();
+            *guard = None;
+        }
+
+        Dispatch::Handled(result)
+    }
+
+    // Gives access to the component outside of event handling. `None` if a handler is
+    // running or the component is destroyed.
+    pub fn with<R>(&self, f: impl FnOnce(&mut C) -> R) -> Option<R> {
+        if self.is_destroyed() {
+            return None;
+        }
+
+        let mut guard = self.component.try_borrow_mut().ok()?;
+        guard.as_mut().map(f)
+    }
+
+    // Drops the component right away, or after the running handler returns.
+    pub fn destroy(&self) {
+        self.destroyed.set(true);
+
+        if let Ok(mut guard) = self.component.try_borrow_mut() {
+            self.pending.borrow_mut().clear();
+            *guard = None;
+        }
+    }
+}
+
+pub type ComponentHost<C> = Host<C, Event<<C as EventHandler>::Custom>>;
+
+// The owner's handle to a component and its window. The platform keeps a reference to the
+// host as well and releases it once the window is gone, so whichever goes first the host
+// stays valid.
+pub struct Hosted<C: EventHandler> {
+    host: Rc<ComponentHost<C>>,
+    window: WindowId,
+    destroy_window: Option<Box<dyn FnOnce()>>,
+}
+
+impl<C: EventHandler> Hosted<C> {
+    // `destroy_window` runs when the handle is dropped while the window is still around.
+    pub fn new(
+        host: Rc<ComponentHost<C>>,
+        window: WindowId,
+        destroy_window: impl FnOnce() + 'static,
+    ) -> Self {
+        Self {
+            host,
+            window,
+            destroy_window: Some(Box::new(destroy_window)),
+        }
+    }
+
+    pub fn window(&self) -> WindowId {
+        self.window
+    }
+
+    pub fn host(&self) -> &Rc<ComponentHost<C>> {
+        &self.host
+    }
+
+    pub fn with<R>(&self, f: impl FnOnce(&mut C) -> R) -> Result<R, Error> {
+        self.host.with(f).ok_or(Error::ComponentUnavailable)
+    }
+}
+
+impl<C: EventHandler> Drop for Hosted<C> {
+    fn drop(&mut self) {
+        if let Some(destroy_window) = self.destroy_window.take() {
+            if !self.host.is_destroyed() {
+                destroy_window();
+            }
+        }
+    }
+}
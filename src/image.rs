@@ -0,0 +1,408 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::dpi::Dpi;
+use crate::units::Dip;
+
+const PNG_SIGNATURE: &[u8] = &[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+const ICO_HEADER_SIZE: usize = 6;
+const ICO_ENTRY_SIZE: usize = 16;
+const BITMAPINFOHEADER_SIZE: usize = 40;
+
+#[derive(Debug)]
+pub enum Error {
+    Png(png::DecodingError),
+    Truncated,
+    UnknownFormat,
+    Unsupported(String),
+    InvalidDimensions(u32, u32),
+    Empty,
+}
+
+impl From<png::DecodingError> for Error {
+    fn from(err: png::DecodingError) -> Self {
+        Error::Png(err)
+    }
+}
+
+static NEXT_IMAGE_ID: AtomicU64 = AtomicU64::new(1);
+
+// Pixels are stored as RGBA8 with the color channels premultiplied by alpha, which is what
+// Direct2D expects for D2D1_ALPHA_MODE_PREMULTIPLIED bitmaps. The id lets canvases cache the
+// device bitmap they upload for an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    id: u64,
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn from_rgba(width: u32, height: u32, mut pixels: Vec<u8>) -> Result<Self, Error> {
+        premultiply(&mut pixels);
+        Self::from_premultiplied_rgba(width, height, pixels)
+    }
+
+    pub fn from_premultiplied_rgba(
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> Result<Self, Error> {
+        if width == 0 || height == 0 || pixels.len() != width as usize * height as usize * 4 {
+            return Err(Error::InvalidDimensions(width, height));
+        }
+
+        Ok(Self {
+            id: NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed),
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    pub fn from_png(bytes: &[u8]) -> Result<Self, Error> {
+        let mut decoder = png::Decoder::new(io::Cursor::new(bytes));
+        decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+        let mut reader = decoder.read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+        buffer.truncate(info.buffer_size());
+
+        let pixel_count = info.width as usize * info.height as usize;
+        let mut pixels = Vec::with_capacity(pixel_count * 4);
+
+        match info.color_type {
+            png::ColorType::Rgba => pixels = buffer,
+            png::ColorType::Rgb => {
+                for rgb in buffer.chunks_exact(3) {
+                    pixels.extend_from_slice(&[rgb[0], rgb[1], rgb[2], 0xff]);
+                }
+            }
+            png::ColorType::GrayscaleAlpha => {
+                for ga in buffer.chunks_exact(2) {
+                    pixels.extend_from_slice(&[ga[0], ga[0], ga[0], ga[1]]);
+                }
+            }
+            png::ColorType::Grayscale => {
+                for &g in &buffer {
+                    pixels.extend_from_slice(&[g, g, g, 0xff]);
+                }
+            }
+            png::ColorType::Indexed => {
+                return Err(Error::Unsupported(String::from("unexpanded indexed PNG")));
+            }
+        }
+
+        Self::from_rgba(info.width, info.height, pixels)
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn stride(&self) -> u32 {
+        self.width * 4
+    }
+
+    // Direct2D bitmaps on HWND render targets want BGRA.
+    pub fn to_bgra(&self) -> Vec<u8> {
+        let mut bgra = self.pixels.clone();
+        for pixel in bgra.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        bgra
+    }
+}
+
+pub fn premultiply(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        let alpha = pixel[3] as u32;
+        if alpha == 0xff {
+            continue;
+        }
+
+        for channel in &mut pixel[..3] {
+            *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+        }
+    }
+}
+
+pub fn is_png(bytes: &[u8]) -> bool {
+    bytes.starts_with(PNG_SIGNATURE)
+}
+
+pub fn is_ico(bytes: &[u8]) -> bool {
+    bytes.len() >= ICO_HEADER_SIZE && bytes[..4] == [0, 0, 1, 0]
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, Error> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|b| u16::from_le_bytes([b[0], b[1]]))
+        .ok_or(Error::Truncated)
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, Error> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+        .ok_or(Error::Truncated)
+}
+
+pub fn decode_ico(bytes: &[u8]) -> Result<Vec<Image>, Error> {
+    if !is_ico(bytes) {
+        return Err(Error::UnknownFormat);
+    }
+
+    let count = read_u16(bytes, 4)? as usize;
+    let mut images = Vec::with_capacity(count);
+
+    for idx in 0..count {
+        let entry = ICO_HEADER_SIZE + idx * ICO_ENTRY_SIZE;
+        let size = read_u32(bytes, entry + 8)? as usize;
+        let offset = read_u32(bytes, entry + 12)? as usize;
+
+        let data = offset
+            .checked_add(size)
+            .and_then(|end| bytes.get(offset..end))
+            .ok_or(Error::Truncated)?;
+
+        let image = if is_png(data) {
+            Image::from_png(data)?
+        } else {
+            decode_dib(data)?
+        };
+
+        images.push(image);
+    }
+
+    if images.is_empty() {
+        return Err(Error::Empty);
+    }
+
+    Ok(images)
+}
+
+// ICO entries that aren't PNG are a BITMAPINFOHEADER followed by the palette, the bottom-up
+// XOR (color) bitmap and the 1bpp AND (transparency) mask. The header height covers both.
+fn decode_dib(data: &[u8]) -> Result<Image, Error> {
+    let header_size = read_u32(data, 0)? as usize;
+    if header_size < BITMAPINFOHEADER_SIZE {
+        return Err(Error::Unsupported(format!(
+            "DIB header of {} bytes",
+            header_size
+        )));
+    }
+
+    let width = read_u32(data, 4)? as i32;
+    let height = read_u32(data, 8)? as i32 / 2;
+    let bit_count = read_u16(data, 14)? as u32;
+    let compression = read_u32(data, 16)?;
+    let colors_used = read_u32(data, 32)? as usize;
+
+    if width <= 0 || height <= 0 {
+        return Err(Error::InvalidDimensions(width as u32, height as u32));
+    }
+    if compression != 0 {
+        return Err(Error::Unsupported(format!(
+            "DIB compression {}",
+            compression
+        )));
+    }
+
+    let (width, height) = (width as usize, height as usize);
+
+    let palette_len = match bit_count {
+        1 | 4 | 8 if colors_used == 0 => 1 << bit_count,
+        1 | 4 | 8 => colors_used,
+        24 | 32 => 0,
+        _ => return Err(Error::Unsupported(format!("{} bits per pixel", bit_count))),
+    };
+
+    // Sizes come from the file, anything that doesn't fit a usize can't be in it either
+    let palette = palette_len
+        .checked_mul(4)
+        .and_then(|len| header_size.checked_add(len))
+        .and_then(|end| data.get(header_size..end))
+        .ok_or(Error::Truncated)?;
+
+    // Rows are padded to 4 bytes.
+    let xor_stride = width
+        .checked_mul(bit_count as usize)
+        .map(|bits| bits.div_ceil(32) * 4)
+        .ok_or(Error::Truncated)?;
+    let and_stride = width.div_ceil(32) * 4;
+    let xor_offset = header_size + palette.len();
+    let and_offset = xor_stride
+        .checked_mul(height)
+        .and_then(|len| xor_offset.checked_add(len))
+        .ok_or(Error::Truncated)?;
+
+    let xor = data.get(xor_offset..and_offset).ok_or(Error::Truncated)?;
+    // Some 32bpp icons omit the mask entirely and rely on the alpha channel.
+    let and = and_stride
+        .checked_mul(height)
+        .and_then(|len| and_offset.checked_add(len))
+        .and_then(|end| data.get(and_offset..end));
+
+    let pixel_len = width
+        .checked_mul(height)
+        .and_then(|len| len.checked_mul(4))
+        .ok_or(Error::Truncated)?;
+    let mut pixels = vec![0u8; pixel_len];
+    let mut has_alpha = false;
+
+    for y in 0..height {
+        let row = &xor[(height - 1 - y) * xor_stride..][..xor_stride];
+
+        for x in 0..width {
+            let bgra = match bit_count {
+                32 => [row[x * 4], row[x * 4 + 1], row[x * 4 + 2], row[x * 4 + 3]],
+                24 => [row[x * 3], row[x * 3 + 1], row[x * 3 + 2], 0xff],
+                _ => {
+                    let bits = bit_count as usize;
+                    let bit_offset = x * bits;
+                    let byte = row[bit_offset / 8];
+                    let shift = 8 - bits - bit_offset % 8;
+                    let index = ((byte >> shift) & ((1 << bits) - 1) as u8) as usize;
+
+                    let color = palette
+                        .get(index * 4..index * 4 + 3)
+                        .ok_or(Error::Truncated)?;
+                    [color[0], color[1], color[2], 0xff]
+                }
+            };
+
+            if bit_count == 32 && bgra[3] != 0 {
+                has_alpha = true;
+            }
+
+            let pixel = &mut pixels[(y * width + x) * 4..][..4];
+            pixel.copy_from_slice(&[bgra[2], bgra[1], bgra[0], bgra[3]]);
+        }
+    }
+
+    if !has_alpha {
+        if let Some(and) = and {
+            for y in 0..height {
+                let row = &and[(height - 1 - y) * and_stride..][..and_stride];
+
+                for x in 0..width {
+                    let transparent = row[x / 8] & (0x80 >> (x % 8)) != 0;
+                    pixels[(y * width + x) * 4 + 3] = if transparent { 0 } else { 0xff };
+                }
+            }
+        } else {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel[3] = 0xff;
+            }
+        }
+    }
+
+    Image::from_rgba(width as u32, height as u32, pixels)
+}
+
+// An icon with all the resolutions it was provided in, so the best one for each DPI can be
+// picked instead of scaling a single bitmap.
+#[derive(Debug)]
+pub struct ImageSet {
+    variants: Vec<Image>,
+    logical_size: u32,
+    dpi_cache: RefCell<HashMap<Dpi, usize>>,
+}
+
+impl ImageSet {
+    pub fn new(mut variants: Vec<Image>, logical_size: u32) -> Result<Self, Error> {
+        if variants.is_empty() {
+            return Err(Error::Empty);
+        }
+
+        variants.sort_by_key(|image| image.width.max(image.height));
+
+        Ok(Self {
+            variants,
+            logical_size,
+            dpi_cache: RefCell::new(HashMap::with_capacity(2)),
+        })
+    }
+
+    pub fn decode(bytes: &[u8], logical_size: u32) -> Result<Self, Error> {
+        if is_png(bytes) {
+            Self::new(vec![Image::from_png(bytes)?], logical_size)
+        } else if is_ico(bytes) {
+            Self::new(decode_ico(bytes)?, logical_size)
+        } else {
+            Err(Error::UnknownFormat)
+        }
+    }
+
+    pub fn variants(&self) -> &[Image] {
+        &self.variants
+    }
+
+    pub fn logical_size(&self) -> u32 {
+        self.logical_size
+    }
+
+    pub fn size_for_dpi(&self, dpi: Dpi) -> u32 {
+        dpi.px(Dip(self.logical_size as f32)).get() as _
+    }
+
+    pub fn variant_index_for_dpi(&self, dpi: Dpi) -> usize {
+        if let Some(idx) = self.dpi_cache.borrow().get(&dpi) {
+            return *idx;
+        }
+
+        let idx = select_variant(&self.variants, self.size_for_dpi(dpi));
+        self.dpi_cache.borrow_mut().insert(dpi, idx);
+        idx
+    }
+
+    pub fn for_dpi(&self, dpi: Dpi) -> &Image {
+        &self.variants[self.variant_index_for_dpi(dpi)]
+    }
+}
+
+// Prefers the smallest variant that is at least `target` pixels so it only ever gets scaled
+// down, falling back to the largest one available.
+pub fn select_variant(variants: &[Image], target: u32) -> usize {
+    let mut best = None;
+
+    for (idx, image) in variants.iter().enumerate() {
+        let size = image.width.max(image.height);
+        if size < target {
+            continue;
+        }
+
+        match best {
+            Some((_, best_size)) if best_size <= size => {}
+            _ => best = Some((idx, size)),
+        }
+    }
+
+    match best {
+        Some((idx, _)) => idx,
+        None => variants
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, image)| image.width.max(image.height))
+            .map(|(idx, _)| idx)
+            .unwrap_or(0),
+    }
+}
@@ -0,0 +1,412 @@
+use std::collections::HashMap;
+
+use crate::event::Key;
+use crate::keysyms::{KEYS, KEYSYMS};
+
+// Modifier bits as in the state of X11 key events, and as the real modifiers of an xkb
+// keymap, which come first and in this order.
+pub const SHIFT: u32 = 1 << 0;
+pub const CAPS_LOCK: u32 = 1 << 1;
+pub const CONTROL: u32 = 1 << 2;
+pub const ALT: u32 = 1 << 3;
+pub const NUM_LOCK: u32 = 1 << 4;
+// AltGr, on the layouts that have it
+pub const LEVEL3: u32 = 1 << 7;
+
+pub const NO_SYMBOL: u32 = 0;
+
+// The keysyms of a key by group, and in each group by shift level.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeySymbols {
+    groups: Vec<Vec<u32>>,
+}
+
+impl KeySymbols {
+    pub fn new(groups: Vec<Vec<u32>>) -> Self {
+        Self { groups }
+    }
+
+    // What the key is without modifiers in the first group, which menus and shortcuts go by.
+    pub fn key(&self) -> Key {
+        key_from_keysym(self.keysym(0, 0))
+    }
+
+    // The keysym at the level `mods` select in `group`. A group the key doesn't have falls
+    // back to the first one, and a level past those it has to the first of the group.
+    pub fn keysym(&self, group: usize, mods: u32) -> u32 {
+        let levels = match self
+            .groups
+            .get(group)
+            .filter(|levels| !levels.is_empty())
+            .or(self.groups.first())
+        {
+            Some(levels) => levels,
+            None => return NO_SYMBOL,
+        };
+        let at = |level: usize| levels.get(level).copied().filter(|sym| *sym != NO_SYMBOL);
+
+        let mut shifted = mods & SHIFT != 0;
+        // Num Lock turns the keypad to digits, and Caps Lock only shifts letters
+        if at(1).is_some_and(is_keypad_digit) {
+            shifted ^= mods & NUM_LOCK != 0;
+        } else if at(0)
+            .and_then(keysym_to_char)
+            .is_some_and(char::is_lowercase)
+        {
+            shifted ^= mods & CAPS_LOCK != 0;
+        }
+
+        let base = if mods & LEVEL3 != 0 && levels.len() > 2 {
+            2
+        } else {
+            0
+        };
+        let level = base + shifted as usize;
+        levels
+            .get(level)
+            .or_else(|| levels.get(base))
+            .copied()
+            .unwrap_or(NO_SYMBOL)
+    }
+
+    // The character the key types with `mods`. Keys held with Control or Alt are shortcuts
+    // and type nothing, and neither do keys like Enter that only type control characters.
+    pub fn text(&self, group: usize, mods: u32) -> Option<char> {
+        if mods & (CONTROL | ALT) != 0 {
+            return None;
+        }
+
+        keysym_to_char(self.keysym(group, mods)).filter(|ch| !ch.is_control())
+    }
+}
+
+// The keysyms of every key of a keyboard, by keycode.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Keymap {
+    keys: HashMap<u32, KeySymbols>,
+}
+
+impl Keymap {
+    // The X server's core keyboard mapping, `keysyms_per_keycode` keysyms for every keycode
+    // from `min_keycode` on. The first group has its levels at 0, 1, 4 and 5 and the second
+    // group at 2 and 3, and a letter listed in lower case only is upper case when shifted.
+    pub fn from_x11(min_keycode: u8, keysyms_per_keycode: u8, keysyms: &[u32]) -> Self {
+        let keys = keysyms
+            .chunks(keysyms_per_keycode.max(1) as usize)
+            .enumerate()
+            .map(|(offset, row)| {
+                let at = |index: usize| row.get(index).copied().unwrap_or(NO_SYMBOL);
+                let groups = [vec![at(0), at(1), at(4), at(5)], vec![at(2), at(3)]]
+                    .into_iter()
+                    .map(|mut levels| {
+                        if levels[1] == NO_SYMBOL {
+                            levels[1] = upper_case(levels[0]);
+                        }
+                        while levels.last() == Some(&NO_SYMBOL) {
+                            levels.pop();
+                        }
+                        levels
+                    })
+                    .collect();
+
+                (min_keycode as u32 + offset as u32, KeySymbols::new(groups))
+            })
+            .collect();
+
+        Self { keys }
+    }
+
+    // A keymap in the xkb text format, as Wayland compositors hand it out, of which only
+    // the keycodes and the symbols are read. `None` if it has no keys.
+    pub fn parse(text: &str) -> Option<Self> {
+        let tokens = tokenize(text);
+        let mut codes = HashMap::new();
+        let mut aliases = HashMap::new();
+        let mut symbols = Vec::new();
+
+        let mut at = 0;
+        while at < tokens.len() {
+            let section = match &tokens[at] {
+                Token::Word(word) if word == "xkb_keycodes" || word == "xkb_symbols" => {
+                    word.clone()
+                }
+                _ => {
+                    at += 1;
+                    continue;
+                }
+            };
+
+            let open = at + tokens[at..].iter().position(|token| token.is('{'))?;
+            let close = open + matching(&tokens[open..])?;
+            for statement in split(&tokens[open + 1..close], ';') {
+                match (section.as_str(), statement) {
+                    ("xkb_keycodes", [Token::Name(name), eq, Token::Word(code)]) if eq.is('=') => {
+                        if let Ok(code) = code.parse::<u32>() {
+                            codes.insert(name.clone(), code);
+                        }
+                    }
+                    (
+                        "xkb_keycodes",
+                        [Token::Word(alias), Token::Name(name), eq, Token::Name(real)],
+                    ) if alias == "alias" && eq.is('=') => {
+                        aliases.insert(name.clone(), real.clone());
+                    }
+                    ("xkb_symbols", [Token::Word(key), Token::Name(name), body @ ..])
+                        if key == "key" =>
+                    {
+                        symbols.push((name.clone(), parse_key(body)));
+                    }
+                    _ => {}
+                }
+            }
+            at = close + 1;
+        }
+
+        let keys = symbols
+            .into_iter()
+            .filter_map(|(name, symbols)| {
+                let code = codes
+                    .get(&name)
+                    .or_else(|| codes.get(aliases.get(&name)?))?;
+                Some((*code, symbols))
+            })
+            .collect::<HashMap<_, _>>();
+
+        (!keys.is_empty()).then_some(Self { keys })
+    }
+
+    pub fn symbols(&self, keycode: u32) -> Option<&KeySymbols> {
+        self.keys.get(&keycode)
+    }
+}
+
+// The character a keysym types, if any.
+pub fn keysym_to_char(keysym: u32) -> Option<char> {
+    match keysym {
+        // Latin-1 keysyms are the characters themselves
+        0x20..=0x7e | 0xa0..=0xff => char::from_u32(keysym),
+        0x1000000..=0x110ffff => char::from_u32(keysym - 0x1000000),
+        // The keypad's space, operators and digits, as laid out in ASCII
+        0xff80 | 0xffaa..=0xffb9 => char::from_u32(keysym - 0xff80),
+        0xffbd => Some('='),
+        _ => KEYSYMS
+            .iter()
+            .find(|(_, sym, _)| *sym == keysym)
+            .map(|(_, _, ch)| *ch),
+    }
+}
+
+// The keysym a keymap names `name`, also as `U20AC` for a Unicode character or as a number.
+pub fn keysym_from_name(name: &str) -> Option<u32> {
+    if let Some(hex) = name.strip_prefix("0x") {
+        return u32::from_str_radix(hex, 16).ok();
+    }
+    if let Some(code) = name
+        .strip_prefix('U')
+        .filter(|hex| hex.len() >= 4)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+    {
+        return match code {
+            0x20..=0x7e | 0xa0..=0xff => Some(code),
+            code => char::from_u32(code).map(|_| 0x1000000 + code),
+        };
+    }
+
+    KEYSYMS
+        .iter()
+        .map(|(known, sym, _)| (*known, *sym))
+        .chain(KEYS)
+        .find(|(known, _)| *known == name)
+        .map(|(_, sym)| sym)
+}
+
+pub fn key_from_keysym(keysym: u32) -> Key {
+    match keysym {
+        0xff52 | 0xff97 => Key::Up,
+        0xff54 | 0xff99 => Key::Down,
+        0xff51 | 0xff96 => Key::Left,
+        0xff53 | 0xff98 => Key::Right,
+        0xff50 | 0xff95 => Key::Home,
+        0xff57 | 0xff9c => Key::End,
+        // Return and the keypad's Enter
+        0xff0d | 0xff8d => Key::Enter,
+        0xff1b => Key::Escape,
+        0xff09 | 0xfe20 => Key::Tab,
+        0xff08 => Key::Backspace,
+        0x20 => Key::Space,
+        keysym => keysym_to_char(keysym)
+            .filter(|ch| !ch.is_control() && !ch.is_whitespace())
+            .and_then(|ch| ch.to_lowercase().next())
+            .map_or(Key::Other, Key::Char),
+    }
+}
+
+fn is_keypad_digit(keysym: u32) -> bool {
+    matches!(keysym, 0xffae | 0xffb0..=0xffb9)
+}
+
+// The keysym of the upper case of the letter `keysym` is, if it has one.
+fn upper_case(keysym: u32) -> u32 {
+    let upper = keysym_to_char(keysym)
+        .filter(|ch| ch.is_lowercase())
+        .map(|ch| ch.to_uppercase().collect::<Vec<_>>());
+    match upper.as_deref() {
+        Some([upper]) => keysym_from_char(*upper),
+        _ => NO_SYMBOL,
+    }
+}
+
+fn keysym_from_char(ch: char) -> u32 {
+    match ch as u32 {
+        code @ (0x20..=0x7e | 0xa0..=0xff) => code,
+        code => KEYSYMS
+            .iter()
+            .find(|(_, _, known)| *known == ch)
+            .map_or(0x1000000 + code, |(_, sym, _)| *sym),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    // Keywords, keysym names and numbers
+    Word(String),
+    // A key name, `<AC01>` without the brackets
+    Name(String),
+    Text(String),
+    Punct(char),
+}
+
+impl Token {
+    fn is(&self, punct: char) -> bool {
+        *self == Token::Punct(punct)
+    }
+}
+
+// Comments start with `//` or `#` and go to the end of the line.
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '/' if chars.peek() == Some(&'/') => {
+                chars.by_ref().find(|ch| *ch == '\n');
+            }
+            '#' => {
+                chars.by_ref().find(|ch| *ch == '\n');
+            }
+            '<' => {
+                let name = chars.by_ref().take_while(|ch| *ch != '>').collect();
+                tokens.push(Token::Name(name));
+            }
+            '"' => {
+                let text = chars.by_ref().take_while(|ch| *ch != '"').collect();
+                tokens.push(Token::Text(text));
+            }
+            ch if ch.is_alphanumeric() || ch == '_' => {
+                let mut word = String::from(ch);
+                while let Some(ch) = chars.next_if(|ch| ch.is_alphanumeric() || *ch == '_') {
+                    word.push(ch);
+                }
+                tokens.push(Token::Word(word));
+            }
+            ch if ch.is_whitespace() => {}
+            ch => tokens.push(Token::Punct(ch)),
+        }
+    }
+
+    tokens
+}
+
+// How far the bracket `tokens` start with is closed, `None` if it never is.
+fn matching(tokens: &[Token]) -> Option<usize> {
+    let mut depth = 0usize;
+    for (at, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Punct('{' | '[' | '(') => depth += 1,
+            Token::Punct('}' | ']' | ')') => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(at);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Splits `tokens` at every `separator` that isn't inside brackets.
+fn split(tokens: &[Token], separator: char) -> Vec<&[Token]> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (at, token) in tokens.iter().enumerate() {
+        match token {
+            Token::Punct('{' | '[' | '(') => depth += 1,
+            Token::Punct('}' | ']' | ')') => depth = depth.saturating_sub(1),
+            Token::Punct(ch) if *ch == separator && depth == 0 => {
+                parts.push(&tokens[start..at]);
+                start = at + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&tokens[start..]);
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+// The body of `key <NAME> { ... }`, with its groups either as `symbols[Group2] = [ ... ]`
+// or as plain lists one after the other. Anything else, like the key's type, is skipped.
+fn parse_key(body: &[Token]) -> KeySymbols {
+    let inner = match body {
+        [open, inner @ .., close] if open.is('{') && close.is('}') => inner,
+        _ => return KeySymbols::default(),
+    };
+
+    let mut groups: Vec<Vec<u32>> = Vec::new();
+    let mut next = 0;
+    for item in split(inner, ',') {
+        let (group, list) = match item {
+            [open, .., close] if open.is('[') && close.is(']') => {
+                next += 1;
+                (next - 1, item)
+            }
+            [Token::Word(symbols), open, Token::Word(group), close, eq, list @ ..]
+                if symbols == "symbols" && open.is('[') && close.is(']') && eq.is('=') =>
+            {
+                let group = group
+                    .to_ascii_lowercase()
+                    .strip_prefix("group")
+                    .and_then(|number| number.parse::<usize>().ok())
+                    .and_then(|number| number.checked_sub(1));
+                match group {
+                    Some(group) => (group, list),
+                    None => continue,
+                }
+            }
+            _ => continue,
+        };
+
+        let levels = match list {
+            [open, levels @ .., close] if open.is('[') && close.is(']') => levels,
+            _ => continue,
+        };
+        // A level with several keysyms in braces types more than a character, left out
+        let levels = split(levels, ',')
+            .into_iter()
+            .map(|level| match level {
+                [Token::Word(name)] => keysym_from_name(name).unwrap_or(NO_SYMBOL),
+                _ => NO_SYMBOL,
+            })
+            .collect();
+
+        if groups.len() <= group {
+            groups.resize(group + 1, Vec::new());
+        }
+        groups[group] = levels;
+    }
+
+    KeySymbols::new(groups)
+}
@@ -0,0 +1,982 @@
+// Keysym names and the characters they type, as X.Org's keysymdef.h lists them. Keysyms
+// from 0x1000000 on are the Unicode characters `keysym - 0x1000000` instead.
+pub static KEYSYMS: [(&str, u32, char); 917] = [
+    ("space", 0x0020, '\u{20}'),
+    ("exclam", 0x0021, '\u{21}'),
+    ("quotedbl", 0x0022, '\u{22}'),
+    ("numbersign", 0x0023, '\u{23}'),
+    ("dollar", 0x0024, '\u{24}'),
+    ("percent", 0x0025, '\u{25}'),
+    ("ampersand", 0x0026, '\u{26}'),
+    ("apostrophe", 0x0027, '\u{27}'),
+    ("parenleft", 0x0028, '\u{28}'),
+    ("parenright", 0x0029, '\u{29}'),
+    ("asterisk", 0x002a, '\u{2a}'),
+    ("plus", 0x002b, '\u{2b}'),
+    ("comma", 0x002c, '\u{2c}'),
+    ("minus", 0x002d, '\u{2d}'),
+    ("period", 0x002e, '\u{2e}'),
+    ("slash", 0x002f, '\u{2f}'),
+    ("0", 0x0030, '\u{30}'),
+    ("1", 0x0031, '\u{31}'),
+    ("2", 0x0032, '\u{32}'),
+    ("3", 0x0033, '\u{33}'),
+    ("4", 0x0034, '\u{34}'),
+    ("5", 0x0035, '\u{35}'),
+    ("6", 0x0036, '\u{36}'),
+    ("7", 0x0037, '\u{37}'),
+    ("8", 0x0038, '\u{38}'),
+    ("9", 0x0039, '\u{39}'),
+    ("colon", 0x003a, '\u{3a}'),
+    ("semicolon", 0x003b, '\u{3b}'),
+    ("less", 0x003c, '\u{3c}'),
+    ("equal", 0x003d, '\u{3d}'),
+    ("greater", 0x003e, '\u{3e}'),
+    ("question", 0x003f, '\u{3f}'),
+    ("at", 0x0040, '\u{40}'),
+    ("A", 0x0041, '\u{41}'),
+    ("B", 0x0042, '\u{42}'),
+    ("C", 0x0043, '\u{43}'),
+    ("D", 0x0044, '\u{44}'),
+    ("E", 0x0045, '\u{45}'),
+    ("F", 0x0046, '\u{46}'),
+    ("G", 0x0047, '\u{47}'),
+    ("H", 0x0048, '\u{48}'),
+    ("I", 0x0049, '\u{49}'),
+    ("J", 0x004a, '\u{4a}'),
+    ("K", 0x004b, '\u{4b}'),
+    ("L", 0x004c, '\u{4c}'),
+    ("M", 0x004d, '\u{4d}'),
+    ("N", 0x004e, '\u{4e}'),
+    ("O", 0x004f, '\u{4f}'),
+    ("P", 0x0050, '\u{50}'),
+    ("Q", 0x0051, '\u{51}'),
+    ("R", 0x0052, '\u{52}'),
+    ("S", 0x0053, '\u{53}'),
+    ("T", 0x0054, '\u{54}'),
+    ("U", 0x0055, '\u{55}'),
+    ("V", 0x0056, '\u{56}'),
+    ("W", 0x0057, '\u{57}'),
+    ("X", 0x0058, '\u{58}'),
+    ("Y", 0x0059, '\u{59}'),
+    ("Z", 0x005a, '\u{5a}'),
+    ("bracketleft", 0x005b, '\u{5b}'),
+    ("backslash", 0x005c, '\u{5c}'),
+    ("bracketright", 0x005d, '\u{5d}'),
+    ("asciicircum", 0x005e, '\u{5e}'),
+    ("underscore", 0x005f, '\u{5f}'),
+    ("grave", 0x0060, '\u{60}'),
+    ("a", 0x0061, '\u{61}'),
+    ("b", 0x0062, '\u{62}'),
+    ("c", 0x0063, '\u{63}'),
+    ("d", 0x0064, '\u{64}'),
+    ("e", 0x0065, '\u{65}'),
+    ("f", 0x0066, '\u{66}'),
+    ("g", 0x0067, '\u{67}'),
+    ("h", 0x0068, '\u{68}'),
+    ("i", 0x0069, '\u{69}'),
+    ("j", 0x006a, '\u{6a}'),
+    ("k", 0x006b, '\u{6b}'),
+    ("l", 0x006c, '\u{6c}'),
+    ("m", 0x006d, '\u{6d}'),
+    ("n", 0x006e, '\u{6e}'),
+    ("o", 0x006f, '\u{6f}'),
+    ("p", 0x0070, '\u{70}'),
+    ("q", 0x0071, '\u{71}'),
+    ("r", 0x0072, '\u{72}'),
+    ("s", 0x0073, '\u{73}'),
+    ("t", 0x0074, '\u{74}'),
+    ("u", 0x0075, '\u{75}'),
+    ("v", 0x0076, '\u{76}'),
+    ("w", 0x0077, '\u{77}'),
+    ("x", 0x0078, '\u{78}'),
+    ("y", 0x0079, '\u{79}'),
+    ("z", 0x007a, '\u{7a}'),
+    ("braceleft", 0x007b, '\u{7b}'),
+    ("bar", 0x007c, '\u{7c}'),
+    ("braceright", 0x007d, '\u{7d}'),
+    ("asciitilde", 0x007e, '\u{7e}'),
+    ("nobreakspace", 0x00a0, '\u{a0}'),
+    ("exclamdown", 0x00a1, '\u{a1}'),
+    ("cent", 0x00a2, '\u{a2}'),
+    ("sterling", 0x00a3, '\u{a3}'),
+    ("currency", 0x00a4, '\u{a4}'),
+    ("yen", 0x00a5, '\u{a5}'),
+    ("brokenbar", 0x00a6, '\u{a6}'),
+    ("section", 0x00a7, '\u{a7}'),
+    ("diaeresis", 0x00a8, '\u{a8}'),
+    ("copyright", 0x00a9, '\u{a9}'),
+    ("ordfeminine", 0x00aa, '\u{aa}'),
+    ("guillemotleft", 0x00ab, '\u{ab}'),
+    ("notsign", 0x00ac, '\u{ac}'),
+    ("hyphen", 0x00ad, '\u{ad}'),
+    ("registered", 0x00ae, '\u{ae}'),
+    ("macron", 0x00af, '\u{af}'),
+    ("degree", 0x00b0, '\u{b0}'),
+    ("plusminus", 0x00b1, '\u{b1}'),
+    ("twosuperior", 0x00b2, '\u{b2}'),
+    ("threesuperior", 0x00b3, '\u{b3}'),
+    ("acute", 0x00b4, '\u{b4}'),
+    ("mu", 0x00b5, '\u{b5}'),
+    ("paragraph", 0x00b6, '\u{b6}'),
+    ("periodcentered", 0x00b7, '\u{b7}'),
+    ("cedilla", 0x00b8, '\u{b8}'),
+    ("onesuperior", 0x00b9, '\u{b9}'),
+    ("masculine", 0x00ba, '\u{ba}'),
+    ("guillemotright", 0x00bb, '\u{bb}'),
+    ("onequarter", 0x00bc, '\u{bc}'),
+    ("onehalf", 0x00bd, '\u{bd}'),
+    ("threequarters", 0x00be, '\u{be}'),
+    ("questiondown", 0x00bf, '\u{bf}'),
+    ("Agrave", 0x00c0, '\u{c0}'),
+    ("Aacute", 0x00c1, '\u{c1}'),
+    ("Acircumflex", 0x00c2, '\u{c2}'),
+    ("Atilde", 0x00c3, '\u{c3}'),
+    ("Adiaeresis", 0x00c4, '\u{c4}'),
+    ("Aring", 0x00c5, '\u{c5}'),
+    ("AE", 0x00c6, '\u{c6}'),
+    ("Ccedilla", 0x00c7, '\u{c7}'),
+    ("Egrave", 0x00c8, '\u{c8}'),
+    ("Eacute", 0x00c9, '\u{c9}'),
+    ("Ecircumflex", 0x00ca, '\u{ca}'),
+    ("Ediaeresis", 0x00cb, '\u{cb}'),
+    ("Igrave", 0x00cc, '\u{cc}'),
+    ("Iacute", 0x00cd, '\u{cd}'),
+    ("Icircumflex", 0x00ce, '\u{ce}'),
+    ("Idiaeresis", 0x00cf, '\u{cf}'),
+    ("ETH", 0x00d0, '\u{d0}'),
+    ("Ntilde", 0x00d1, '\u{d1}'),
+    ("Ograve", 0x00d2, '\u{d2}'),
+    ("Oacute", 0x00d3, '\u{d3}'),
+    ("Ocircumflex", 0x00d4, '\u{d4}'),
+    ("Otilde", 0x00d5, '\u{d5}'),
+    ("Odiaeresis", 0x00d6, '\u{d6}'),
+    ("multiply", 0x00d7, '\u{d7}'),
+    ("Ooblique", 0x00d8, '\u{d8}'),
+    ("Oslash", 0x00d8, '\u{d8}'),
+    ("Ugrave", 0x00d9, '\u{d9}'),
+    ("Uacute", 0x00da, '\u{da}'),
+    ("Ucircumflex", 0x00db, '\u{db}'),
+    ("Udiaeresis", 0x00dc, '\u{dc}'),
+    ("Yacute", 0x00dd, '\u{dd}'),
+    ("THORN", 0x00de, '\u{de}'),
+    ("ssharp", 0x00df, '\u{df}'),
+    ("agrave", 0x00e0, '\u{e0}'),
+    ("aacute", 0x00e1, '\u{e1}'),
+    ("acircumflex", 0x00e2, '\u{e2}'),
+    ("atilde", 0x00e3, '\u{e3}'),
+    ("adiaeresis", 0x00e4, '\u{e4}'),
+    ("aring", 0x00e5, '\u{e5}'),
+    ("ae", 0x00e6, '\u{e6}'),
+    ("ccedilla", 0x00e7, '\u{e7}'),
+    ("egrave", 0x00e8, '\u{e8}'),
+    ("eacute", 0x00e9, '\u{e9}'),
+    ("ecircumflex", 0x00ea, '\u{ea}'),
+    ("ediaeresis", 0x00eb, '\u{eb}'),
+    ("igrave", 0x00ec, '\u{ec}'),
+    ("iacute", 0x00ed, '\u{ed}'),
+    ("icircumflex", 0x00ee, '\u{ee}'),
+    ("idiaeresis", 0x00ef, '\u{ef}'),
+    ("eth", 0x00f0, '\u{f0}'),
+    ("ntilde", 0x00f1, '\u{f1}'),
+    ("ograve", 0x00f2, '\u{f2}'),
+    ("oacute", 0x00f3, '\u{f3}'),
+    ("ocircumflex", 0x00f4, '\u{f4}'),
+    ("otilde", 0x00f5, '\u{f5}'),
+    ("odiaeresis", 0x00f6, '\u{f6}'),
+    ("division", 0x00f7, '\u{f7}'),
+    ("ooblique", 0x00f8, '\u{f8}'),
+    ("oslash", 0x00f8, '\u{f8}'),
+    ("ugrave", 0x00f9, '\u{f9}'),
+    ("uacute", 0x00fa, '\u{fa}'),
+    ("ucircumflex", 0x00fb, '\u{fb}'),
+    ("udiaeresis", 0x00fc, '\u{fc}'),
+    ("yacute", 0x00fd, '\u{fd}'),
+    ("thorn", 0x00fe, '\u{fe}'),
+    ("ydiaeresis", 0x00ff, '\u{ff}'),
+    ("Aogonek", 0x01a1, '\u{104}'),
+    ("breve", 0x01a2, '\u{2d8}'),
+    ("Lstroke", 0x01a3, '\u{141}'),
+    ("Lcaron", 0x01a5, '\u{13d}'),
+    ("Sacute", 0x01a6, '\u{15a}'),
+    ("Scaron", 0x01a9, '\u{160}'),
+    ("Scedilla", 0x01aa, '\u{15e}'),
+    ("Tcaron", 0x01ab, '\u{164}'),
+    ("Zacute", 0x01ac, '\u{179}'),
+    ("Zcaron", 0x01ae, '\u{17d}'),
+    ("Zabovedot", 0x01af, '\u{17b}'),
+    ("aogonek", 0x01b1, '\u{105}'),
+    ("ogonek", 0x01b2, '\u{2db}'),
+    ("lstroke", 0x01b3, '\u{142}'),
+    ("lcaron", 0x01b5, '\u{13e}'),
+    ("sacute", 0x01b6, '\u{15b}'),
+    ("caron", 0x01b7, '\u{2c7}'),
+    ("scaron", 0x01b9, '\u{161}'),
+    ("scedilla", 0x01ba, '\u{15f}'),
+    ("tcaron", 0x01bb, '\u{165}'),
+    ("zacute", 0x01bc, '\u{17a}'),
+    ("doubleacute", 0x01bd, '\u{2dd}'),
+    ("zcaron", 0x01be, '\u{17e}'),
+    ("zabovedot", 0x01bf, '\u{17c}'),
+    ("Racute", 0x01c0, '\u{154}'),
+    ("Abreve", 0x01c3, '\u{102}'),
+    ("Lacute", 0x01c5, '\u{139}'),
+    ("Cacute", 0x01c6, '\u{106}'),
+    ("Ccaron", 0x01c8, '\u{10c}'),
+    ("Eogonek", 0x01ca, '\u{118}'),
+    ("Ecaron", 0x01cc, '\u{11a}'),
+    ("Dcaron", 0x01cf, '\u{10e}'),
+    ("Dstroke", 0x01d0, '\u{110}'),
+    ("Nacute", 0x01d1, '\u{143}'),
+    ("Ncaron", 0x01d2, '\u{147}'),
+    ("Odoubleacute", 0x01d5, '\u{150}'),
+    ("Rcaron", 0x01d8, '\u{158}'),
+    ("Uring", 0x01d9, '\u{16e}'),
+    ("Udoubleacute", 0x01db, '\u{170}'),
+    ("Tcedilla", 0x01de, '\u{162}'),
+    ("racute", 0x01e0, '\u{155}'),
+    ("abreve", 0x01e3, '\u{103}'),
+    ("lacute", 0x01e5, '\u{13a}'),
+    ("cacute", 0x01e6, '\u{107}'),
+    ("ccaron", 0x01e8, '\u{10d}'),
+    ("eogonek", 0x01ea, '\u{119}'),
+    ("ecaron", 0x01ec, '\u{11b}'),
+    ("dcaron", 0x01ef, '\u{10f}'),
+    ("dstroke", 0x01f0, '\u{111}'),
+    ("nacute", 0x01f1, '\u{144}'),
+    ("ncaron", 0x01f2, '\u{148}'),
+    ("odoubleacute", 0x01f5, '\u{151}'),
+    ("rcaron", 0x01f8, '\u{159}'),
+    ("uring", 0x01f9, '\u{16f}'),
+    ("udoubleacute", 0x01fb, '\u{171}'),
+    ("tcedilla", 0x01fe, '\u{163}'),
+    ("abovedot", 0x01ff, '\u{2d9}'),
+    ("Hstroke", 0x02a1, '\u{126}'),
+    ("Hcircumflex", 0x02a6, '\u{124}'),
+    ("Iabovedot", 0x02a9, '\u{130}'),
+    ("Gbreve", 0x02ab, '\u{11e}'),
+    ("Jcircumflex", 0x02ac, '\u{134}'),
+    ("hstroke", 0x02b1, '\u{127}'),
+    ("hcircumflex", 0x02b6, '\u{125}'),
+    ("idotless", 0x02b9, '\u{131}'),
+    ("gbreve", 0x02bb, '\u{11f}'),
+    ("jcircumflex", 0x02bc, '\u{135}'),
+    ("Cabovedot", 0x02c5, '\u{10a}'),
+    ("Ccircumflex", 0x02c6, '\u{108}'),
+    ("Gabovedot", 0x02d5, '\u{120}'),
+    ("Gcircumflex", 0x02d8, '\u{11c}'),
+    ("Ubreve", 0x02dd, '\u{16c}'),
+    ("Scircumflex", 0x02de, '\u{15c}'),
+    ("cabovedot", 0x02e5, '\u{10b}'),
+    ("ccircumflex", 0x02e6, '\u{109}'),
+    ("gabovedot", 0x02f5, '\u{121}'),
+    ("gcircumflex", 0x02f8, '\u{11d}'),
+    ("ubreve", 0x02fd, '\u{16d}'),
+    ("scircumflex", 0x02fe, '\u{15d}'),
+    ("kra", 0x03a2, '\u{138}'),
+    ("Rcedilla", 0x03a3, '\u{156}'),
+    ("Itilde", 0x03a5, '\u{128}'),
+    ("Lcedilla", 0x03a6, '\u{13b}'),
+    ("Emacron", 0x03aa, '\u{112}'),
+    ("Gcedilla", 0x03ab, '\u{122}'),
+    ("Tslash", 0x03ac, '\u{166}'),
+    ("rcedilla", 0x03b3, '\u{157}'),
+    ("itilde", 0x03b5, '\u{129}'),
+    ("lcedilla", 0x03b6, '\u{13c}'),
+    ("emacron", 0x03ba, '\u{113}'),
+    ("gcedilla", 0x03bb, '\u{123}'),
+    ("tslash", 0x03bc, '\u{167}'),
+    ("ENG", 0x03bd, '\u{14a}'),
+    ("eng", 0x03bf, '\u{14b}'),
+    ("Amacron", 0x03c0, '\u{100}'),
+    ("Iogonek", 0x03c7, '\u{12e}'),
+    ("Eabovedot", 0x03cc, '\u{116}'),
+    ("Imacron", 0x03cf, '\u{12a}'),
+    ("Ncedilla", 0x03d1, '\u{145}'),
+    ("Omacron", 0x03d2, '\u{14c}'),
+    ("Kcedilla", 0x03d3, '\u{136}'),
+    ("Uogonek", 0x03d9, '\u{172}'),
+    ("Utilde", 0x03dd, '\u{168}'),
+    ("Umacron", 0x03de, '\u{16a}'),
+    ("amacron", 0x03e0, '\u{101}'),
+    ("iogonek", 0x03e7, '\u{12f}'),
+    ("eabovedot", 0x03ec, '\u{117}'),
+    ("imacron", 0x03ef, '\u{12b}'),
+    ("ncedilla", 0x03f1, '\u{146}'),
+    ("omacron", 0x03f2, '\u{14d}'),
+    ("kcedilla", 0x03f3, '\u{137}'),
+    ("uogonek", 0x03f9, '\u{173}'),
+    ("utilde", 0x03fd, '\u{169}'),
+    ("umacron", 0x03fe, '\u{16b}'),
+    ("overline", 0x047e, '\u{203e}'),
+    ("kana_fullstop", 0x04a1, '\u{3002}'),
+    ("kana_openingbracket", 0x04a2, '\u{300c}'),
+    ("kana_closingbracket", 0x04a3, '\u{300d}'),
+    ("kana_comma", 0x04a4, '\u{3001}'),
+    ("kana_conjunctive", 0x04a5, '\u{30fb}'),
+    ("kana_WO", 0x04a6, '\u{30f2}'),
+    ("kana_a", 0x04a7, '\u{30a1}'),
+    ("kana_i", 0x04a8, '\u{30a3}'),
+    ("kana_u", 0x04a9, '\u{30a5}'),
+    ("kana_e", 0x04aa, '\u{30a7}'),
+    ("kana_o", 0x04ab, '\u{30a9}'),
+    ("kana_ya", 0x04ac, '\u{30e3}'),
+    ("kana_yu", 0x04ad, '\u{30e5}'),
+    ("kana_yo", 0x04ae, '\u{30e7}'),
+    ("kana_tsu", 0x04af, '\u{30c3}'),
+    ("prolongedsound", 0x04b0, '\u{30fc}'),
+    ("kana_A", 0x04b1, '\u{30a2}'),
+    ("kana_I", 0x04b2, '\u{30a4}'),
+    ("kana_U", 0x04b3, '\u{30a6}'),
+    ("kana_E", 0x04b4, '\u{30a8}'),
+    ("kana_O", 0x04b5, '\u{30aa}'),
+    ("kana_KA", 0x04b6, '\u{30ab}'),
+    ("kana_KI", 0x04b7, '\u{30ad}'),
+    ("kana_KU", 0x04b8, '\u{30af}'),
+    ("kana_KE", 0x04b9, '\u{30b1}'),
+    ("kana_KO", 0x04ba, '\u{30b3}'),
+    ("kana_SA", 0x04bb, '\u{30b5}'),
+    ("kana_SHI", 0x04bc, '\u{30b7}'),
+    ("kana_SU", 0x04bd, '\u{30b9}'),
+    ("kana_SE", 0x04be, '\u{30bb}'),
+    ("kana_SO", 0x04bf, '\u{30bd}'),
+    ("kana_TA", 0x04c0, '\u{30bf}'),
+    ("kana_CHI", 0x04c1, '\u{30c1}'),
+    ("kana_TSU", 0x04c2, '\u{30c4}'),
+    ("kana_TE", 0x04c3, '\u{30c6}'),
+    ("kana_TO", 0x04c4, '\u{30c8}'),
+    ("kana_NA", 0x04c5, '\u{30ca}'),
+    ("kana_NI", 0x04c6, '\u{30cb}'),
+    ("kana_NU", 0x04c7, '\u{30cc}'),
+    ("kana_NE", 0x04c8, '\u{30cd}'),
+    ("kana_NO", 0x04c9, '\u{30ce}'),
+    ("kana_HA", 0x04ca, '\u{30cf}'),
+    ("kana_HI", 0x04cb, '\u{30d2}'),
+    ("kana_FU", 0x04cc, '\u{30d5}'),
+    ("kana_HE", 0x04cd, '\u{30d8}'),
+    ("kana_HO", 0x04ce, '\u{30db}'),
+    ("kana_MA", 0x04cf, '\u{30de}'),
+    ("kana_MI", 0x04d0, '\u{30df}'),
+    ("kana_MU", 0x04d1, '\u{30e0}'),
+    ("kana_ME", 0x04d2, '\u{30e1}'),
+    ("kana_MO", 0x04d3, '\u{30e2}'),
+    ("kana_YA", 0x04d4, '\u{30e4}'),
+    ("kana_YU", 0x04d5, '\u{30e6}'),
+    ("kana_YO", 0x04d6, '\u{30e8}'),
+    ("kana_RA", 0x04d7, '\u{30e9}'),
+    ("kana_RI", 0x04d8, '\u{30ea}'),
+    ("kana_RU", 0x04d9, '\u{30eb}'),
+    ("kana_RE", 0x04da, '\u{30ec}'),
+    ("kana_RO", 0x04db, '\u{30ed}'),
+    ("kana_WA", 0x04dc, '\u{30ef}'),
+    ("kana_N", 0x04dd, '\u{30f3}'),
+    ("voicedsound", 0x04de, '\u{309b}'),
+    ("semivoicedsound", 0x04df, '\u{309c}'),
+    ("Arabic_comma", 0x05ac, '\u{60c}'),
+    ("Arabic_semicolon", 0x05bb, '\u{61b}'),
+    ("Arabic_question_mark", 0x05bf, '\u{61f}'),
+    ("Arabic_hamza", 0x05c1, '\u{621}'),
+    ("Arabic_maddaonalef", 0x05c2, '\u{622}'),
+    ("Arabic_hamzaonalef", 0x05c3, '\u{623}'),
+    ("Arabic_hamzaonwaw", 0x05c4, '\u{624}'),
+    ("Arabic_hamzaunderalef", 0x05c5, '\u{625}'),
+    ("Arabic_hamzaonyeh", 0x05c6, '\u{626}'),
+    ("Arabic_alef", 0x05c7, '\u{627}'),
+    ("Arabic_beh", 0x05c8, '\u{628}'),
+    ("Arabic_tehmarbuta", 0x05c9, '\u{629}'),
+    ("Arabic_teh", 0x05ca, '\u{62a}'),
+    ("Arabic_theh", 0x05cb, '\u{62b}'),
+    ("Arabic_jeem", 0x05cc, '\u{62c}'),
+    ("Arabic_hah", 0x05cd, '\u{62d}'),
+    ("Arabic_khah", 0x05ce, '\u{62e}'),
+    ("Arabic_dal", 0x05cf, '\u{62f}'),
+    ("Arabic_thal", 0x05d0, '\u{630}'),
+    ("Arabic_ra", 0x05d1, '\u{631}'),
+    ("Arabic_zain", 0x05d2, '\u{632}'),
+    ("Arabic_seen", 0x05d3, '\u{633}'),
+    ("Arabic_sheen", 0x05d4, '\u{634}'),
+    ("Arabic_sad", 0x05d5, '\u{635}'),
+    ("Arabic_dad", 0x05d6, '\u{636}'),
+    ("Arabic_tah", 0x05d7, '\u{637}'),
+    ("Arabic_zah", 0x05d8, '\u{638}'),
+    ("Arabic_ain", 0x05d9, '\u{639}'),
+    ("Arabic_ghain", 0x05da, '\u{63a}'),
+    ("Arabic_tatweel", 0x05e0, '\u{640}'),
+    ("Arabic_feh", 0x05e1, '\u{641}'),
+    ("Arabic_qaf", 0x05e2, '\u{642}'),
+    ("Arabic_kaf", 0x05e3, '\u{643}'),
+    ("Arabic_lam", 0x05e4, '\u{644}'),
+    ("Arabic_meem", 0x05e5, '\u{645}'),
+    ("Arabic_noon", 0x05e6, '\u{646}'),
+    ("Arabic_ha", 0x05e7, '\u{647}'),
+    ("Arabic_waw", 0x05e8, '\u{648}'),
+    ("Arabic_alefmaksura", 0x05e9, '\u{649}'),
+    ("Arabic_yeh", 0x05ea, '\u{64a}'),
+    ("Arabic_fathatan", 0x05eb, '\u{64b}'),
+    ("Arabic_dammatan", 0x05ec, '\u{64c}'),
+    ("Arabic_kasratan", 0x05ed, '\u{64d}'),
+    ("Arabic_fatha", 0x05ee, '\u{64e}'),
+    ("Arabic_damma", 0x05ef, '\u{64f}'),
+    ("Arabic_kasra", 0x05f0, '\u{650}'),
+    ("Arabic_shadda", 0x05f1, '\u{651}'),
+    ("Arabic_sukun", 0x05f2, '\u{652}'),
+    ("Serbian_dje", 0x06a1, '\u{452}'),
+    ("Macedonia_gje", 0x06a2, '\u{453}'),
+    ("Cyrillic_io", 0x06a3, '\u{451}'),
+    ("Ukrainian_ie", 0x06a4, '\u{454}'),
+    ("Macedonia_dse", 0x06a5, '\u{455}'),
+    ("Ukrainian_i", 0x06a6, '\u{456}'),
+    ("Ukrainian_yi", 0x06a7, '\u{457}'),
+    ("Cyrillic_je", 0x06a8, '\u{458}'),
+    ("Cyrillic_lje", 0x06a9, '\u{459}'),
+    ("Cyrillic_nje", 0x06aa, '\u{45a}'),
+    ("Serbian_tshe", 0x06ab, '\u{45b}'),
+    ("Macedonia_kje", 0x06ac, '\u{45c}'),
+    ("Ukrainian_ghe_with_upturn", 0x06ad, '\u{491}'),
+    ("Byelorussian_shortu", 0x06ae, '\u{45e}'),
+    ("Cyrillic_dzhe", 0x06af, '\u{45f}'),
+    ("numerosign", 0x06b0, '\u{2116}'),
+    ("Serbian_DJE", 0x06b1, '\u{402}'),
+    ("Macedonia_GJE", 0x06b2, '\u{403}'),
+    ("Cyrillic_IO", 0x06b3, '\u{401}'),
+    ("Ukrainian_IE", 0x06b4, '\u{404}'),
+    ("Macedonia_DSE", 0x06b5, '\u{405}'),
+    ("Ukrainian_I", 0x06b6, '\u{406}'),
+    ("Ukrainian_YI", 0x06b7, '\u{407}'),
+    ("Cyrillic_JE", 0x06b8, '\u{408}'),
+    ("Cyrillic_LJE", 0x06b9, '\u{409}'),
+    ("Cyrillic_NJE", 0x06ba, '\u{40a}'),
+    ("Serbian_TSHE", 0x06bb, '\u{40b}'),
+    ("Macedonia_KJE", 0x06bc, '\u{40c}'),
+    ("Ukrainian_GHE_WITH_UPTURN", 0x06bd, '\u{490}'),
+    ("Byelorussian_SHORTU", 0x06be, '\u{40e}'),
+    ("Cyrillic_DZHE", 0x06bf, '\u{40f}'),
+    ("Cyrillic_yu", 0x06c0, '\u{44e}'),
+    ("Cyrillic_a", 0x06c1, '\u{430}'),
+    ("Cyrillic_be", 0x06c2, '\u{431}'),
+    ("Cyrillic_tse", 0x06c3, '\u{446}'),
+    ("Cyrillic_de", 0x06c4, '\u{434}'),
+    ("Cyrillic_ie", 0x06c5, '\u{435}'),
+    ("Cyrillic_ef", 0x06c6, '\u{444}'),
+    ("Cyrillic_ghe", 0x06c7, '\u{433}'),
+    ("Cyrillic_ha", 0x06c8, '\u{445}'),
+    ("Cyrillic_i", 0x06c9, '\u{438}'),
+    ("Cyrillic_shorti", 0x06ca, '\u{439}'),
+    ("Cyrillic_ka", 0x06cb, '\u{43a}'),
+    ("Cyrillic_el", 0x06cc, '\u{43b}'),
+    ("Cyrillic_em", 0x06cd, '\u{43c}'),
+    ("Cyrillic_en", 0x06ce, '\u{43d}'),
+    ("Cyrillic_o", 0x06cf, '\u{43e}'),
+    ("Cyrillic_pe", 0x06d0, '\u{43f}'),
+    ("Cyrillic_ya", 0x06d1, '\u{44f}'),
+    ("Cyrillic_er", 0x06d2, '\u{440}'),
+    ("Cyrillic_es", 0x06d3, '\u{441}'),
+    ("Cyrillic_te", 0x06d4, '\u{442}'),
+    ("Cyrillic_u", 0x06d5, '\u{443}'),
+    ("Cyrillic_zhe", 0x06d6, '\u{436}'),
+    ("Cyrillic_ve", 0x06d7, '\u{432}'),
+    ("Cyrillic_softsign", 0x06d8, '\u{44c}'),
+    ("Cyrillic_yeru", 0x06d9, '\u{44b}'),
+    ("Cyrillic_ze", 0x06da, '\u{437}'),
+    ("Cyrillic_sha", 0x06db, '\u{448}'),
+    ("Cyrillic_e", 0x06dc, '\u{44d}'),
+    ("Cyrillic_shcha", 0x06dd, '\u{449}'),
+    ("Cyrillic_che", 0x06de, '\u{447}'),
+    ("Cyrillic_hardsign", 0x06df, '\u{44a}'),
+    ("Cyrillic_YU", 0x06e0, '\u{42e}'),
+    ("Cyrillic_A", 0x06e1, '\u{410}'),
+    ("Cyrillic_BE", 0x06e2, '\u{411}'),
+    ("Cyrillic_TSE", 0x06e3, '\u{426}'),
+    ("Cyrillic_DE", 0x06e4, '\u{414}'),
+    ("Cyrillic_IE", 0x06e5, '\u{415}'),
+    ("Cyrillic_EF", 0x06e6, '\u{424}'),
+    ("Cyrillic_GHE", 0x06e7, '\u{413}'),
+    ("Cyrillic_HA", 0x06e8, '\u{425}'),
+    ("Cyrillic_I", 0x06e9, '\u{418}'),
+    ("Cyrillic_SHORTI", 0x06ea, '\u{419}'),
+    ("Cyrillic_KA", 0x06eb, '\u{41a}'),
+    ("Cyrillic_EL", 0x06ec, '\u{41b}'),
+    ("Cyrillic_EM", 0x06ed, '\u{41c}'),
+    ("Cyrillic_EN", 0x06ee, '\u{41d}'),
+    ("Cyrillic_O", 0x06ef, '\u{41e}'),
+    ("Cyrillic_PE", 0x06f0, '\u{41f}'),
+    ("Cyrillic_YA", 0x06f1, '\u{42f}'),
+    ("Cyrillic_ER", 0x06f2, '\u{420}'),
+    ("Cyrillic_ES", 0x06f3, '\u{421}'),
+    ("Cyrillic_TE", 0x06f4, '\u{422}'),
+    ("Cyrillic_U", 0x06f5, '\u{423}'),
+    ("Cyrillic_ZHE", 0x06f6, '\u{416}'),
+    ("Cyrillic_VE", 0x06f7, '\u{412}'),
+    ("Cyrillic_SOFTSIGN", 0x06f8, '\u{42c}'),
+    ("Cyrillic_YERU", 0x06f9, '\u{42b}'),
+    ("Cyrillic_ZE", 0x06fa, '\u{417}'),
+    ("Cyrillic_SHA", 0x06fb, '\u{428}'),
+    ("Cyrillic_E", 0x06fc, '\u{42d}'),
+    ("Cyrillic_SHCHA", 0x06fd, '\u{429}'),
+    ("Cyrillic_CHE", 0x06fe, '\u{427}'),
+    ("Cyrillic_HARDSIGN", 0x06ff, '\u{42a}'),
+    ("Greek_ALPHAaccent", 0x07a1, '\u{386}'),
+    ("Greek_EPSILONaccent", 0x07a2, '\u{388}'),
+    ("Greek_ETAaccent", 0x07a3, '\u{389}'),
+    ("Greek_IOTAaccent", 0x07a4, '\u{38a}'),
+    ("Greek_IOTAdieresis", 0x07a5, '\u{3aa}'),
+    ("Greek_OMICRONaccent", 0x07a7, '\u{38c}'),
+    ("Greek_UPSILONaccent", 0x07a8, '\u{38e}'),
+    ("Greek_UPSILONdieresis", 0x07a9, '\u{3ab}'),
+    ("Greek_OMEGAaccent", 0x07ab, '\u{38f}'),
+    ("Greek_accentdieresis", 0x07ae, '\u{385}'),
+    ("Greek_horizbar", 0x07af, '\u{2015}'),
+    ("Greek_alphaaccent", 0x07b1, '\u{3ac}'),
+    ("Greek_epsilonaccent", 0x07b2, '\u{3ad}'),
+    ("Greek_etaaccent", 0x07b3, '\u{3ae}'),
+    ("Greek_iotaaccent", 0x07b4, '\u{3af}'),
+    ("Greek_iotadieresis", 0x07b5, '\u{3ca}'),
+    ("Greek_iotaaccentdieresis", 0x07b6, '\u{390}'),
+    ("Greek_omicronaccent", 0x07b7, '\u{3cc}'),
+    ("Greek_upsilonaccent", 0x07b8, '\u{3cd}'),
+    ("Greek_upsilondieresis", 0x07b9, '\u{3cb}'),
+    ("Greek_upsilonaccentdieresis", 0x07ba, '\u{3b0}'),
+    ("Greek_omegaaccent", 0x07bb, '\u{3ce}'),
+    ("Greek_ALPHA", 0x07c1, '\u{391}'),
+    ("Greek_BETA", 0x07c2, '\u{392}'),
+    ("Greek_GAMMA", 0x07c3, '\u{393}'),
+    ("Greek_DELTA", 0x07c4, '\u{394}'),
+    ("Greek_EPSILON", 0x07c5, '\u{395}'),
+    ("Greek_ZETA", 0x07c6, '\u{396}'),
+    ("Greek_ETA", 0x07c7, '\u{397}'),
+    ("Greek_THETA", 0x07c8, '\u{398}'),
+    ("Greek_IOTA", 0x07c9, '\u{399}'),
+    ("Greek_KAPPA", 0x07ca, '\u{39a}'),
+    ("Greek_LAMBDA", 0x07cb, '\u{39b}'),
+    ("Greek_LAMDA", 0x07cb, '\u{39b}'),
+    ("Greek_MU", 0x07cc, '\u{39c}'),
+    ("Greek_NU", 0x07cd, '\u{39d}'),
+    ("Greek_XI", 0x07ce, '\u{39e}'),
+    ("Greek_OMICRON", 0x07cf, '\u{39f}'),
+    ("Greek_PI", 0x07d0, '\u{3a0}'),
+    ("Greek_RHO", 0x07d1, '\u{3a1}'),
+    ("Greek_SIGMA", 0x07d2, '\u{3a3}'),
+    ("Greek_TAU", 0x07d4, '\u{3a4}'),
+    ("Greek_UPSILON", 0x07d5, '\u{3a5}'),
+    ("Greek_PHI", 0x07d6, '\u{3a6}'),
+    ("Greek_CHI", 0x07d7, '\u{3a7}'),
+    ("Greek_PSI", 0x07d8, '\u{3a8}'),
+    ("Greek_OMEGA", 0x07d9, '\u{3a9}'),
+    ("Greek_alpha", 0x07e1, '\u{3b1}'),
+    ("Greek_beta", 0x07e2, '\u{3b2}'),
+    ("Greek_gamma", 0x07e3, '\u{3b3}'),
+    ("Greek_delta", 0x07e4, '\u{3b4}'),
+    ("Greek_epsilon", 0x07e5, '\u{3b5}'),
+    ("Greek_zeta", 0x07e6, '\u{3b6}'),
+    ("Greek_eta", 0x07e7, '\u{3b7}'),
+    ("Greek_theta", 0x07e8, '\u{3b8}'),
+    ("Greek_iota", 0x07e9, '\u{3b9}'),
+    ("Greek_kappa", 0x07ea, '\u{3ba}'),
+    ("Greek_lambda", 0x07eb, '\u{3bb}'),
+    ("Greek_lamda", 0x07eb, '\u{3bb}'),
+    ("Greek_mu", 0x07ec, '\u{3bc}'),
+    ("Greek_nu", 0x07ed, '\u{3bd}'),
+    ("Greek_xi", 0x07ee, '\u{3be}'),
+    ("Greek_omicron", 0x07ef, '\u{3bf}'),
+    ("Greek_pi", 0x07f0, '\u{3c0}'),
+    ("Greek_rho", 0x07f1, '\u{3c1}'),
+    ("Greek_sigma", 0x07f2, '\u{3c3}'),
+    ("Greek_finalsmallsigma", 0x07f3, '\u{3c2}'),
+    ("Greek_tau", 0x07f4, '\u{3c4}'),
+    ("Greek_upsilon", 0x07f5, '\u{3c5}'),
+    ("Greek_phi", 0x07f6, '\u{3c6}'),
+    ("Greek_chi", 0x07f7, '\u{3c7}'),
+    ("Greek_psi", 0x07f8, '\u{3c8}'),
+    ("Greek_omega", 0x07f9, '\u{3c9}'),
+    ("leftradical", 0x08a1, '\u{23b7}'),
+    ("topintegral", 0x08a4, '\u{2320}'),
+    ("botintegral", 0x08a5, '\u{2321}'),
+    ("topleftsqbracket", 0x08a7, '\u{23a1}'),
+    ("botleftsqbracket", 0x08a8, '\u{23a3}'),
+    ("toprightsqbracket", 0x08a9, '\u{23a4}'),
+    ("botrightsqbracket", 0x08aa, '\u{23a6}'),
+    ("topleftparens", 0x08ab, '\u{239b}'),
+    ("botleftparens", 0x08ac, '\u{239d}'),
+    ("toprightparens", 0x08ad, '\u{239e}'),
+    ("botrightparens", 0x08ae, '\u{23a0}'),
+    ("leftmiddlecurlybrace", 0x08af, '\u{23a8}'),
+    ("rightmiddlecurlybrace", 0x08b0, '\u{23ac}'),
+    ("lessthanequal", 0x08bc, '\u{2264}'),
+    ("notequal", 0x08bd, '\u{2260}'),
+    ("greaterthanequal", 0x08be, '\u{2265}'),
+    ("integral", 0x08bf, '\u{222b}'),
+    ("therefore", 0x08c0, '\u{2234}'),
+    ("variation", 0x08c1, '\u{221d}'),
+    ("infinity", 0x08c2, '\u{221e}'),
+    ("nabla", 0x08c5, '\u{2207}'),
+    ("approximate", 0x08c8, '\u{223c}'),
+    ("similarequal", 0x08c9, '\u{2243}'),
+    ("ifonlyif", 0x08cd, '\u{21d4}'),
+    ("implies", 0x08ce, '\u{21d2}'),
+    ("identical", 0x08cf, '\u{2261}'),
+    ("radical", 0x08d6, '\u{221a}'),
+    ("includedin", 0x08da, '\u{2282}'),
+    ("includes", 0x08db, '\u{2283}'),
+    ("intersection", 0x08dc, '\u{2229}'),
+    ("union", 0x08dd, '\u{222a}'),
+    ("logicaland", 0x08de, '\u{2227}'),
+    ("logicalor", 0x08df, '\u{2228}'),
+    ("partialderivative", 0x08ef, '\u{2202}'),
+    ("function", 0x08f6, '\u{192}'),
+    ("leftarrow", 0x08fb, '\u{2190}'),
+    ("uparrow", 0x08fc, '\u{2191}'),
+    ("rightarrow", 0x08fd, '\u{2192}'),
+    ("downarrow", 0x08fe, '\u{2193}'),
+    ("soliddiamond", 0x09e0, '\u{25c6}'),
+    ("checkerboard", 0x09e1, '\u{2592}'),
+    ("ht", 0x09e2, '\u{2409}'),
+    ("ff", 0x09e3, '\u{240c}'),
+    ("cr", 0x09e4, '\u{240d}'),
+    ("lf", 0x09e5, '\u{240a}'),
+    ("nl", 0x09e8, '\u{2424}'),
+    ("vt", 0x09e9, '\u{240b}'),
+    ("lowrightcorner", 0x09ea, '\u{2518}'),
+    ("uprightcorner", 0x09eb, '\u{2510}'),
+    ("upleftcorner", 0x09ec, '\u{250c}'),
+    ("lowleftcorner", 0x09ed, '\u{2514}'),
+    ("crossinglines", 0x09ee, '\u{253c}'),
+    ("horizlinescan1", 0x09ef, '\u{23ba}'),
+    ("horizlinescan3", 0x09f0, '\u{23bb}'),
+    ("horizlinescan5", 0x09f1, '\u{2500}'),
+    ("horizlinescan7", 0x09f2, '\u{23bc}'),
+    ("horizlinescan9", 0x09f3, '\u{23bd}'),
+    ("leftt", 0x09f4, '\u{251c}'),
+    ("rightt", 0x09f5, '\u{2524}'),
+    ("bott", 0x09f6, '\u{2534}'),
+    ("topt", 0x09f7, '\u{252c}'),
+    ("vertbar", 0x09f8, '\u{2502}'),
+    ("emspace", 0x0aa1, '\u{2003}'),
+    ("enspace", 0x0aa2, '\u{2002}'),
+    ("em3space", 0x0aa3, '\u{2004}'),
+    ("em4space", 0x0aa4, '\u{2005}'),
+    ("digitspace", 0x0aa5, '\u{2007}'),
+    ("punctspace", 0x0aa6, '\u{2008}'),
+    ("thinspace", 0x0aa7, '\u{2009}'),
+    ("hairspace", 0x0aa8, '\u{200a}'),
+    ("emdash", 0x0aa9, '\u{2014}'),
+    ("endash", 0x0aaa, '\u{2013}'),
+    ("ellipsis", 0x0aae, '\u{2026}'),
+    ("doubbaselinedot", 0x0aaf, '\u{2025}'),
+    ("onethird", 0x0ab0, '\u{2153}'),
+    ("twothirds", 0x0ab1, '\u{2154}'),
+    ("onefifth", 0x0ab2, '\u{2155}'),
+    ("twofifths", 0x0ab3, '\u{2156}'),
+    ("threefifths", 0x0ab4, '\u{2157}'),
+    ("fourfifths", 0x0ab5, '\u{2158}'),
+    ("onesixth", 0x0ab6, '\u{2159}'),
+    ("fivesixths", 0x0ab7, '\u{215a}'),
+    ("careof", 0x0ab8, '\u{2105}'),
+    ("figdash", 0x0abb, '\u{2012}'),
+    ("oneeighth", 0x0ac3, '\u{215b}'),
+    ("threeeighths", 0x0ac4, '\u{215c}'),
+    ("fiveeighths", 0x0ac5, '\u{215d}'),
+    ("seveneighths", 0x0ac6, '\u{215e}'),
+    ("trademark", 0x0ac9, '\u{2122}'),
+    ("leftsinglequotemark", 0x0ad0, '\u{2018}'),
+    ("rightsinglequotemark", 0x0ad1, '\u{2019}'),
+    ("leftdoublequotemark", 0x0ad2, '\u{201c}'),
+    ("rightdoublequotemark", 0x0ad3, '\u{201d}'),
+    ("prescription", 0x0ad4, '\u{211e}'),
+    ("permille", 0x0ad5, '\u{2030}'),
+    ("minutes", 0x0ad6, '\u{2032}'),
+    ("seconds", 0x0ad7, '\u{2033}'),
+    ("latincross", 0x0ad9, '\u{271d}'),
+    ("club", 0x0aec, '\u{2663}'),
+    ("diamond", 0x0aed, '\u{2666}'),
+    ("heart", 0x0aee, '\u{2665}'),
+    ("maltesecross", 0x0af0, '\u{2720}'),
+    ("dagger", 0x0af1, '\u{2020}'),
+    ("doubledagger", 0x0af2, '\u{2021}'),
+    ("checkmark", 0x0af3, '\u{2713}'),
+    ("ballotcross", 0x0af4, '\u{2717}'),
+    ("musicalsharp", 0x0af5, '\u{266f}'),
+    ("musicalflat", 0x0af6, '\u{266d}'),
+    ("malesymbol", 0x0af7, '\u{2642}'),
+    ("femalesymbol", 0x0af8, '\u{2640}'),
+    ("telephone", 0x0af9, '\u{260e}'),
+    ("telephonerecorder", 0x0afa, '\u{2315}'),
+    ("phonographcopyright", 0x0afb, '\u{2117}'),
+    ("caret", 0x0afc, '\u{2038}'),
+    ("singlelowquotemark", 0x0afd, '\u{201a}'),
+    ("doublelowquotemark", 0x0afe, '\u{201e}'),
+    ("downtack", 0x0bc2, '\u{22a4}'),
+    ("downstile", 0x0bc4, '\u{230a}'),
+    ("jot", 0x0bca, '\u{2218}'),
+    ("quad", 0x0bcc, '\u{2395}'),
+    ("uptack", 0x0bce, '\u{22a5}'),
+    ("circle", 0x0bcf, '\u{25cb}'),
+    ("upstile", 0x0bd3, '\u{2308}'),
+    ("lefttack", 0x0bdc, '\u{22a3}'),
+    ("righttack", 0x0bfc, '\u{22a2}'),
+    ("hebrew_doublelowline", 0x0cdf, '\u{2017}'),
+    ("hebrew_aleph", 0x0ce0, '\u{5d0}'),
+    ("hebrew_bet", 0x0ce1, '\u{5d1}'),
+    ("hebrew_gimel", 0x0ce2, '\u{5d2}'),
+    ("hebrew_dalet", 0x0ce3, '\u{5d3}'),
+    ("hebrew_he", 0x0ce4, '\u{5d4}'),
+    ("hebrew_waw", 0x0ce5, '\u{5d5}'),
+    ("hebrew_zain", 0x0ce6, '\u{5d6}'),
+    ("hebrew_chet", 0x0ce7, '\u{5d7}'),
+    ("hebrew_tet", 0x0ce8, '\u{5d8}'),
+    ("hebrew_yod", 0x0ce9, '\u{5d9}'),
+    ("hebrew_finalkaph", 0x0cea, '\u{5da}'),
+    ("hebrew_kaph", 0x0ceb, '\u{5db}'),
+    ("hebrew_lamed", 0x0cec, '\u{5dc}'),
+    ("hebrew_finalmem", 0x0ced, '\u{5dd}'),
+    ("hebrew_mem", 0x0cee, '\u{5de}'),
+    ("hebrew_finalnun", 0x0cef, '\u{5df}'),
+    ("hebrew_nun", 0x0cf0, '\u{5e0}'),
+    ("hebrew_samech", 0x0cf1, '\u{5e1}'),
+    ("hebrew_ayin", 0x0cf2, '\u{5e2}'),
+    ("hebrew_finalpe", 0x0cf3, '\u{5e3}'),
+    ("hebrew_pe", 0x0cf4, '\u{5e4}'),
+    ("hebrew_finalzade", 0x0cf5, '\u{5e5}'),
+    ("hebrew_zade", 0x0cf6, '\u{5e6}'),
+    ("hebrew_qoph", 0x0cf7, '\u{5e7}'),
+    ("hebrew_resh", 0x0cf8, '\u{5e8}'),
+    ("hebrew_shin", 0x0cf9, '\u{5e9}'),
+    ("hebrew_taw", 0x0cfa, '\u{5ea}'),
+    ("Thai_kokai", 0x0da1, '\u{e01}'),
+    ("Thai_khokhai", 0x0da2, '\u{e02}'),
+    ("Thai_khokhuat", 0x0da3, '\u{e03}'),
+    ("Thai_khokhwai", 0x0da4, '\u{e04}'),
+    ("Thai_khokhon", 0x0da5, '\u{e05}'),
+    ("Thai_khorakhang", 0x0da6, '\u{e06}'),
+    ("Thai_ngongu", 0x0da7, '\u{e07}'),
+    ("Thai_chochan", 0x0da8, '\u{e08}'),
+    ("Thai_choching", 0x0da9, '\u{e09}'),
+    ("Thai_chochang", 0x0daa, '\u{e0a}'),
+    ("Thai_soso", 0x0dab, '\u{e0b}'),
+    ("Thai_chochoe", 0x0dac, '\u{e0c}'),
+    ("Thai_yoying", 0x0dad, '\u{e0d}'),
+    ("Thai_dochada", 0x0dae, '\u{e0e}'),
+    ("Thai_topatak", 0x0daf, '\u{e0f}'),
+    ("Thai_thothan", 0x0db0, '\u{e10}'),
+    ("Thai_thonangmontho", 0x0db1, '\u{e11}'),
+    ("Thai_thophuthao", 0x0db2, '\u{e12}'),
+    ("Thai_nonen", 0x0db3, '\u{e13}'),
+    ("Thai_dodek", 0x0db4, '\u{e14}'),
+    ("Thai_totao", 0x0db5, '\u{e15}'),
+    ("Thai_thothung", 0x0db6, '\u{e16}'),
+    ("Thai_thothahan", 0x0db7, '\u{e17}'),
+    ("Thai_thothong", 0x0db8, '\u{e18}'),
+    ("Thai_nonu", 0x0db9, '\u{e19}'),
+    ("Thai_bobaimai", 0x0dba, '\u{e1a}'),
+    ("Thai_popla", 0x0dbb, '\u{e1b}'),
+    ("Thai_phophung", 0x0dbc, '\u{e1c}'),
+    ("Thai_fofa", 0x0dbd, '\u{e1d}'),
+    ("Thai_phophan", 0x0dbe, '\u{e1e}'),
+    ("Thai_fofan", 0x0dbf, '\u{e1f}'),
+    ("Thai_phosamphao", 0x0dc0, '\u{e20}'),
+    ("Thai_moma", 0x0dc1, '\u{e21}'),
+    ("Thai_yoyak", 0x0dc2, '\u{e22}'),
+    ("Thai_rorua", 0x0dc3, '\u{e23}'),
+    ("Thai_ru", 0x0dc4, '\u{e24}'),
+    ("Thai_loling", 0x0dc5, '\u{e25}'),
+    ("Thai_lu", 0x0dc6, '\u{e26}'),
+    ("Thai_wowaen", 0x0dc7, '\u{e27}'),
+    ("Thai_sosala", 0x0dc8, '\u{e28}'),
+    ("Thai_sorusi", 0x0dc9, '\u{e29}'),
+    ("Thai_sosua", 0x0dca, '\u{e2a}'),
+    ("Thai_hohip", 0x0dcb, '\u{e2b}'),
+    ("Thai_lochula", 0x0dcc, '\u{e2c}'),
+    ("Thai_oang", 0x0dcd, '\u{e2d}'),
+    ("Thai_honokhuk", 0x0dce, '\u{e2e}'),
+    ("Thai_paiyannoi", 0x0dcf, '\u{e2f}'),
+    ("Thai_saraa", 0x0dd0, '\u{e30}'),
+    ("Thai_maihanakat", 0x0dd1, '\u{e31}'),
+    ("Thai_saraaa", 0x0dd2, '\u{e32}'),
+    ("Thai_saraam", 0x0dd3, '\u{e33}'),
+    ("Thai_sarai", 0x0dd4, '\u{e34}'),
+    ("Thai_saraii", 0x0dd5, '\u{e35}'),
+    ("Thai_saraue", 0x0dd6, '\u{e36}'),
+    ("Thai_sarauee", 0x0dd7, '\u{e37}'),
+    ("Thai_sarau", 0x0dd8, '\u{e38}'),
+    ("Thai_sarauu", 0x0dd9, '\u{e39}'),
+    ("Thai_phinthu", 0x0dda, '\u{e3a}'),
+    ("Thai_baht", 0x0ddf, '\u{e3f}'),
+    ("Thai_sarae", 0x0de0, '\u{e40}'),
+    ("Thai_saraae", 0x0de1, '\u{e41}'),
+    ("Thai_sarao", 0x0de2, '\u{e42}'),
+    ("Thai_saraaimaimuan", 0x0de3, '\u{e43}'),
+    ("Thai_saraaimaimalai", 0x0de4, '\u{e44}'),
+    ("Thai_lakkhangyao", 0x0de5, '\u{e45}'),
+    ("Thai_maiyamok", 0x0de6, '\u{e46}'),
+    ("Thai_maitaikhu", 0x0de7, '\u{e47}'),
+    ("Thai_maiek", 0x0de8, '\u{e48}'),
+    ("Thai_maitho", 0x0de9, '\u{e49}'),
+    ("Thai_maitri", 0x0dea, '\u{e4a}'),
+    ("Thai_maichattawa", 0x0deb, '\u{e4b}'),
+    ("Thai_thanthakhat", 0x0dec, '\u{e4c}'),
+    ("Thai_nikhahit", 0x0ded, '\u{e4d}'),
+    ("Thai_leksun", 0x0df0, '\u{e50}'),
+    ("Thai_leknung", 0x0df1, '\u{e51}'),
+    ("Thai_leksong", 0x0df2, '\u{e52}'),
+    ("Thai_leksam", 0x0df3, '\u{e53}'),
+    ("Thai_leksi", 0x0df4, '\u{e54}'),
+    ("Thai_lekha", 0x0df5, '\u{e55}'),
+    ("Thai_lekhok", 0x0df6, '\u{e56}'),
+    ("Thai_lekchet", 0x0df7, '\u{e57}'),
+    ("Thai_lekpaet", 0x0df8, '\u{e58}'),
+    ("Thai_lekkao", 0x0df9, '\u{e59}'),
+    ("Hangul_Kiyeog", 0x0ea1, '\u{3131}'),
+    ("Hangul_SsangKiyeog", 0x0ea2, '\u{3132}'),
+    ("Hangul_KiyeogSios", 0x0ea3, '\u{3133}'),
+    ("Hangul_Nieun", 0x0ea4, '\u{3134}'),
+    ("Hangul_NieunJieuj", 0x0ea5, '\u{3135}'),
+    ("Hangul_NieunHieuh", 0x0ea6, '\u{3136}'),
+    ("Hangul_Dikeud", 0x0ea7, '\u{3137}'),
+    ("Hangul_SsangDikeud", 0x0ea8, '\u{3138}'),
+    ("Hangul_Rieul", 0x0ea9, '\u{3139}'),
+    ("Hangul_RieulKiyeog", 0x0eaa, '\u{313a}'),
+    ("Hangul_RieulMieum", 0x0eab, '\u{313b}'),
+    ("Hangul_RieulPieub", 0x0eac, '\u{313c}'),
+    ("Hangul_RieulSios", 0x0ead, '\u{313d}'),
+    ("Hangul_RieulTieut", 0x0eae, '\u{313e}'),
+    ("Hangul_RieulPhieuf", 0x0eaf, '\u{313f}'),
+    ("Hangul_RieulHieuh", 0x0eb0, '\u{3140}'),
+    ("Hangul_Mieum", 0x0eb1, '\u{3141}'),
+    ("Hangul_Pieub", 0x0eb2, '\u{3142}'),
+    ("Hangul_SsangPieub", 0x0eb3, '\u{3143}'),
+    ("Hangul_PieubSios", 0x0eb4, '\u{3144}'),
+    ("Hangul_Sios", 0x0eb5, '\u{3145}'),
+    ("Hangul_SsangSios", 0x0eb6, '\u{3146}'),
+    ("Hangul_Ieung", 0x0eb7, '\u{3147}'),
+    ("Hangul_Jieuj", 0x0eb8, '\u{3148}'),
+    ("Hangul_SsangJieuj", 0x0eb9, '\u{3149}'),
+    ("Hangul_Cieuc", 0x0eba, '\u{314a}'),
+    ("Hangul_Khieuq", 0x0ebb, '\u{314b}'),
+    ("Hangul_Tieut", 0x0ebc, '\u{314c}'),
+    ("Hangul_Phieuf", 0x0ebd, '\u{314d}'),
+    ("Hangul_Hieuh", 0x0ebe, '\u{314e}'),
+    ("Hangul_A", 0x0ebf, '\u{314f}'),
+    ("Hangul_AE", 0x0ec0, '\u{3150}'),
+    ("Hangul_YA", 0x0ec1, '\u{3151}'),
+    ("Hangul_YAE", 0x0ec2, '\u{3152}'),
+    ("Hangul_EO", 0x0ec3, '\u{3153}'),
+    ("Hangul_E", 0x0ec4, '\u{3154}'),
+    ("Hangul_YEO", 0x0ec5, '\u{3155}'),
+    ("Hangul_YE", 0x0ec6, '\u{3156}'),
+    ("Hangul_O", 0x0ec7, '\u{3157}'),
+    ("Hangul_WA", 0x0ec8, '\u{3158}'),
+    ("Hangul_WAE", 0x0ec9, '\u{3159}'),
+    ("Hangul_OE", 0x0eca, '\u{315a}'),
+    ("Hangul_YO", 0x0ecb, '\u{315b}'),
+    ("Hangul_U", 0x0ecc, '\u{315c}'),
+    ("Hangul_WEO", 0x0ecd, '\u{315d}'),
+    ("Hangul_WE", 0x0ece, '\u{315e}'),
+    ("Hangul_WI", 0x0ecf, '\u{315f}'),
+    ("Hangul_YU", 0x0ed0, '\u{3160}'),
+    ("Hangul_EU", 0x0ed1, '\u{3161}'),
+    ("Hangul_YI", 0x0ed2, '\u{3162}'),
+    ("Hangul_I", 0x0ed3, '\u{3163}'),
+    ("Hangul_J_Kiyeog", 0x0ed4, '\u{11a8}'),
+    ("Hangul_J_SsangKiyeog", 0x0ed5, '\u{11a9}'),
+    ("Hangul_J_KiyeogSios", 0x0ed6, '\u{11aa}'),
+    ("Hangul_J_Nieun", 0x0ed7, '\u{11ab}'),
+    ("Hangul_J_NieunJieuj", 0x0ed8, '\u{11ac}'),
+    ("Hangul_J_NieunHieuh", 0x0ed9, '\u{11ad}'),
+    ("Hangul_J_Dikeud", 0x0eda, '\u{11ae}'),
+    ("Hangul_J_Rieul", 0x0edb, '\u{11af}'),
+    ("Hangul_J_RieulKiyeog", 0x0edc, '\u{11b0}'),
+    ("Hangul_J_RieulMieum", 0x0edd, '\u{11b1}'),
+    ("Hangul_J_RieulPieub", 0x0ede, '\u{11b2}'),
+    ("Hangul_J_RieulSios", 0x0edf, '\u{11b3}'),
+    ("Hangul_J_RieulTieut", 0x0ee0, '\u{11b4}'),
+    ("Hangul_J_RieulPhieuf", 0x0ee1, '\u{11b5}'),
+    ("Hangul_J_RieulHieuh", 0x0ee2, '\u{11b6}'),
+    ("Hangul_J_Mieum", 0x0ee3, '\u{11b7}'),
+    ("Hangul_J_Pieub", 0x0ee4, '\u{11b8}'),
+    ("Hangul_J_PieubSios", 0x0ee5, '\u{11b9}'),
+    ("Hangul_J_Sios", 0x0ee6, '\u{11ba}'),
+    ("Hangul_J_SsangSios", 0x0ee7, '\u{11bb}'),
+    ("Hangul_J_Ieung", 0x0ee8, '\u{11bc}'),
+    ("Hangul_J_Jieuj", 0x0ee9, '\u{11bd}'),
+    ("Hangul_J_Cieuc", 0x0eea, '\u{11be}'),
+    ("Hangul_J_Khieuq", 0x0eeb, '\u{11bf}'),
+    ("Hangul_J_Tieut", 0x0eec, '\u{11c0}'),
+    ("Hangul_J_Phieuf", 0x0eed, '\u{11c1}'),
+    ("Hangul_J_Hieuh", 0x0eee, '\u{11c2}'),
+    ("Hangul_RieulYeorinHieuh", 0x0eef, '\u{316d}'),
+    ("Hangul_SunkyeongeumMieum", 0x0ef0, '\u{3171}'),
+    ("Hangul_SunkyeongeumPieub", 0x0ef1, '\u{3178}'),
+    ("Hangul_PanSios", 0x0ef2, '\u{317f}'),
+    ("Hangul_KkogjiDalrinIeung", 0x0ef3, '\u{3181}'),
+    ("Hangul_SunkyeongeumPhieuf", 0x0ef4, '\u{3184}'),
+    ("Hangul_YeorinHieuh", 0x0ef5, '\u{3186}'),
+    ("Hangul_AraeA", 0x0ef6, '\u{318d}'),
+    ("Hangul_AraeAE", 0x0ef7, '\u{318e}'),
+    ("Hangul_J_PanSios", 0x0ef8, '\u{11eb}'),
+    ("Hangul_J_KkogjiDalrinIeung", 0x0ef9, '\u{11f0}'),
+    ("Hangul_J_YeorinHieuh", 0x0efa, '\u{11f9}'),
+    ("OE", 0x13bc, '\u{152}'),
+    ("oe", 0x13bd, '\u{153}'),
+    ("Ydiaeresis", 0x13be, '\u{178}'),
+    ("EuroSign", 0x20ac, '\u{20ac}'),
+];
+
+// Keys that type nothing, or only control characters, that keymaps name.
+pub static KEYS: [(&str, u32); 57] = [
+    ("ISO_Level3_Shift", 0xfe03),
+    ("ISO_Left_Tab", 0xfe20),
+    ("BackSpace", 0xff08),
+    ("Tab", 0xff09),
+    ("Return", 0xff0d),
+    ("Escape", 0xff1b),
+    ("Home", 0xff50),
+    ("Left", 0xff51),
+    ("Up", 0xff52),
+    ("Right", 0xff53),
+    ("Down", 0xff54),
+    ("Prior", 0xff55),
+    ("Next", 0xff56),
+    ("End", 0xff57),
+    ("Insert", 0xff63),
+    ("Mode_switch", 0xff7e),
+    ("Num_Lock", 0xff7f),
+    ("KP_Space", 0xff80),
+    ("KP_Enter", 0xff8d),
+    ("KP_Home", 0xff95),
+    ("KP_Left", 0xff96),
+    ("KP_Up", 0xff97),
+    ("KP_Right", 0xff98),
+    ("KP_Down", 0xff99),
+    ("KP_Prior", 0xff9a),
+    ("KP_Next", 0xff9b),
+    ("KP_End", 0xff9c),
+    ("KP_Begin", 0xff9d),
+    ("KP_Insert", 0xff9e),
+    ("KP_Delete", 0xff9f),
+    ("KP_Multiply", 0xffaa),
+    ("KP_Add", 0xffab),
+    ("KP_Separator", 0xffac),
+    ("KP_Subtract", 0xffad),
+    ("KP_Decimal", 0xffae),
+    ("KP_Divide", 0xffaf),
+    ("KP_0", 0xffb0),
+    ("KP_1", 0xffb1),
+    ("KP_2", 0xffb2),
+    ("KP_3", 0xffb3),
+    ("KP_4", 0xffb4),
+    ("KP_5", 0xffb5),
+    ("KP_6", 0xffb6),
+    ("KP_7", 0xffb7),
+    ("KP_8", 0xffb8),
+    ("KP_9", 0xffb9),
+    ("KP_Equal", 0xffbd),
+    ("Shift_L", 0xffe1),
+    ("Shift_R", 0xffe2),
+    ("Control_L", 0xffe3),
+    ("Control_R", 0xffe4),
+    ("Caps_Lock", 0xffe5),
+    ("Alt_L", 0xffe9),
+    ("Alt_R", 0xffea),
+    ("Super_L", 0xffeb),
+    ("Super_R", 0xffec),
+    ("Delete", 0xffff),
+];
@@ -0,0 +1,542 @@
+use crate::canvas::Canvas;
+use crate::geometry::{Insets, Rect, Size};
+use crate::widget::Widget;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Row,
+    Column,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Start,
+    Center,
+    End,
+    Stretch,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Justify {
+    Start,
+    Center,
+    End,
+    SpaceBetween,
+}
+
+// How a child takes part in the layout of its parent. `size` is what the child measured,
+// `basis` overrides it along the main axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlexItem {
+    pub size: Size,
+    pub basis: Option<i32>,
+    pub grow: f32,
+    pub shrink: f32,
+    pub margin: Insets,
+    pub align: Option<Align>,
+}
+
+impl Default for FlexItem {
+    fn default() -> Self {
+        Self {
+            size: Size::default(),
+            basis: None,
+            grow: 0.0,
+            shrink: 1.0,
+            margin: Insets::default(),
+            align: None,
+        }
+    }
+}
+
+impl FlexItem {
+    pub fn new(size: Size) -> Self {
+        Self {
+            size,
+            ..Default::default()
+        }
+    }
+
+    // Always exactly `main` pixels along the main axis.
+    pub fn fixed(main: i32) -> Self {
+        Self {
+            basis: Some(main),
+            shrink: 0.0,
+            ..Default::default()
+        }
+    }
+
+    // Takes a `grow` share of whatever space is left.
+    pub fn flexible(grow: f32) -> Self {
+        Self {
+            basis: Some(0),
+            grow,
+            ..Default::default()
+        }
+    }
+
+    pub fn spacer() -> Self {
+        Self::flexible(1.0)
+    }
+
+    pub fn with_size(self, size: Size) -> Self {
+        Self { size, ..self }
+    }
+
+    pub fn with_basis(self, basis: i32) -> Self {
+        Self {
+            basis: Some(basis),
+            ..self
+        }
+    }
+
+    pub fn with_grow(self, grow: f32) -> Self {
+        Self { grow, ..self }
+    }
+
+    pub fn with_shrink(self, shrink: f32) -> Self {
+        Self { shrink, ..self }
+    }
+
+    pub fn with_margin(self, margin: Insets) -> Self {
+        Self { margin, ..self }
+    }
+
+    pub fn with_align(self, align: Align) -> Self {
+        Self {
+            align: Some(align),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Flex {
+    pub direction: Direction,
+    pub padding: Insets,
+    pub gap: i32,
+    pub align: Align,
+    pub justify: Justify,
+}
+
+impl Flex {
+    pub fn new(direction: Direction) -> Self {
+        Self {
+            direction,
+            padding: Insets::default(),
+            gap: 0,
+            align: Align::Stretch,
+            justify: Justify::Start,
+        }
+    }
+
+    pub fn row() -> Self {
+        Self::new(Direction::Row)
+    }
+
+    pub fn column() -> Self {
+        Self::new(Direction::Column)
+    }
+
+    pub fn with_padding(self, padding: Insets) -> Self {
+        Self { padding, ..self }
+    }
+
+    pub fn with_gap(self, gap: i32) -> Self {
+        Self { gap, ..self }
+    }
+
+    pub fn with_align(self, align: Align) -> Self {
+        Self { align, ..self }
+    }
+
+    pub fn with_justify(self, justify: Justify) -> Self {
+        Self { justify, ..self }
+    }
+
+    fn main(&self, size: Size) -> i32 {
+        match self.direction {
+            Direction::Row => size.width,
+            Direction::Column => size.height,
+        }
+    }
+
+    fn cross(&self, size: Size) -> i32 {
+        match self.direction {
+            Direction::Row => size.height,
+            Direction::Column => size.width,
+        }
+    }
+
+    // Margins before and after the item along the main and the cross axis.
+    fn margins(&self, margin: &Insets) -> ((i32, i32), (i32, i32)) {
+        match self.direction {
+            Direction::Row => ((margin.left, margin.right), (margin.top, margin.bottom)),
+            Direction::Column => ((margin.top, margin.bottom), (margin.left, margin.right)),
+        }
+    }
+
+    fn rect(&self, inner: &Rect, main: (i32, i32), cross: (i32, i32)) -> Rect {
+        match self.direction {
+            Direction::Row => Rect::new(
+                inner.left + main.0,
+                inner.top + cross.0,
+                inner.left + main.1,
+                inner.top + cross.1,
+            ),
+            Direction::Column => Rect::new(
+                inner.left + cross.0,
+                inner.top + main.0,
+                inner.left + cross.1,
+                inner.top + main.1,
+            ),
+        }
+    }
+
+    fn basis(&self, item: &FlexItem) -> i32 {
+        item.basis.unwrap_or_else(|| self.main(item.size)).max(0)
+    }
+
+    // The size the items need without growing or shrinking any of them.
+    pub fn measure(&self, items: &[FlexItem]) -> Size {
+        let mut main = 0;
+        let mut cross = 0;
+
+        for item in items {
+            let ((before, after), (cross_before, cross_after)) = self.margins(&item.margin);
+            main += before + self.basis(item) + after;
+            cross = cross.max(cross_before + self.cross(item.size) + cross_after);
+        }
+
+        main += self.gap * (items.len() as i32 - 1).max(0);
+
+        match self.direction {
+            Direction::Row => Size::new(
+                main + self.padding.horizontal(),
+                cross + self.padding.vertical(),
+            ),
+            Direction::Column => Size::new(
+                cross + self.padding.horizontal(),
+                main + self.padding.vertical(),
+            ),
+        }
+    }
+
+    pub fn layout(&self, bounds: Rect, items: &[FlexItem]) -> Vec<Rect> {
+        if items.is_empty() {
+            return Vec::new();
+        }
+
+        let inner = bounds.inset(&self.padding);
+        let inner_main = self.main(inner.size());
+        let inner_cross = self.cross(inner.size());
+
+        let margins: Vec<_> = items
+            .iter()
+            .map(|item| self.margins(&item.margin))
+            .collect();
+        let margin_sum: i32 = margins
+            .iter()
+            .map(|((before, after), _)| before + after)
+            .sum();
+        let available = (inner_main - self.gap * (items.len() as i32 - 1) - margin_sum) as f64;
+
+        let bases: Vec<f64> = items.iter().map(|item| self.basis(item) as f64).collect();
+        let free = available - bases.iter().sum::<f64>();
+
+        let total_grow: f64 = items.iter().map(|item| item.grow.max(0.0) as f64).sum();
+
+        let sizes: Vec<f64> = if free > 0.0 && total_grow > 0.0 {
+            items
+                .iter()
+                .zip(&bases)
+                .map(|(item, basis)| basis + free * item.grow.max(0.0) as f64 / total_grow)
+                .collect()
+        } else if free < 0.0 {
+            shrink(items, &bases, available)
+        } else {
+            bases
+        };
+
+        let leftover = (available - sizes.iter().sum::<f64>()).max(0.0);
+        let (mut position, spacing) = match self.justify {
+            Justify::Start => (0.0, 0.0),
+            Justify::Center => (leftover / 2.0, 0.0),
+            Justify::End => (leftover, 0.0),
+            Justify::SpaceBetween if items.len() > 1 => (0.0, leftover / (items.len() - 1) as f64),
+            Justify::SpaceBetween => (0.0, 0.0),
+        };
+
+        let mut rects = Vec::with_capacity(items.len());
+
+        for (idx, item) in items.iter().enumerate() {
+            let ((before, after), (cross_before, cross_after)) = margins[idx];
+
+            // Edges are rounded from the running float position so neighbours never overlap
+            // or leave a gap because of rounding.
+            position += before as f64;
+            let start = position.round() as i32;
+            position += sizes[idx];
+            let end = position.round() as i32;
+            position += (after + self.gap) as f64 + spacing;
+
+            let cross_available = (inner_cross - cross_before - cross_after).max(0);
+            let cross_size = self.cross(item.size).clamp(0, cross_available);
+            let cross = match item.align.unwrap_or(self.align) {
+                Align::Start => (cross_before, cross_before + cross_size),
+                Align::Center => {
+                    let top = cross_before + (cross_available - cross_size) / 2;
+                    (top, top + cross_size)
+                }
+                Align::End => (
+                    inner_cross - cross_after - cross_size,
+                    inner_cross - cross_after,
+                ),
+                Align::Stretch => (cross_before, cross_before + cross_available),
+            };
+
+            rects.push(self.rect(&inner, (start, end), cross));
+        }
+
+        rects
+    }
+}
+
+// Like CSS, bigger items give up more of their size. Items can't go below 0, so whatever an
+// item can't give up is taken from the others, until only items that can't shrink are left.
+// Those overflow the end of the container.
+fn shrink(items: &[FlexItem], bases: &[f64], available: f64) -> Vec<f64> {
+    let mut sizes = bases.to_vec();
+    let mut frozen: Vec<bool> = items
+        .iter()
+        .zip(bases)
+        .map(|(item, basis)| item.shrink <= 0.0 || *basis <= 0.0)
+        .collect();
+
+    loop {
+        let overflow = available - sizes.iter().sum::<f64>();
+        let total_shrink: f64 = items
+            .iter()
+            .zip(bases)
+            .zip(&frozen)
+            .filter(|(_, frozen)| !**frozen)
+            .map(|((item, basis), _)| item.shrink as f64 * basis)
+            .sum();
+
+        if overflow >= 0.0 || total_shrink <= 0.0 {
+            return sizes;
+        }
+
+        let shrunk: Vec<Option<f64>> = (0..items.len())
+            .map(|idx| {
+                let share = items[idx].shrink as f64 * bases[idx] / total_shrink;
+                (!frozen[idx]).then(|| sizes[idx] + overflow * share)
+            })
+            .collect();
+
+        // Items that would go below 0 stop at 0 and the rest is spread again without them
+        let mut clamped = false;
+        for (idx, size) in shrunk.iter().enumerate() {
+            if size.is_some_and(|size| size <= 0.0) {
+                sizes[idx] = 0.0;
+                frozen[idx] = true;
+                clamped = true;
+            }
+        }
+
+        if !clamped {
+            for (idx, size) in shrunk.into_iter().enumerate() {
+                if let Some(size) = size {
+                    sizes[idx] = size;
+                }
+            }
+            return sizes;
+        }
+    }
+}
+
+// Puts every item on top of each other inside `bounds`, aligned on both axes.
+pub fn stack(bounds: Rect, padding: Insets, align: Align, items: &[FlexItem]) -> Vec<Rect> {
+    let inner = bounds.inset(&padding);
+
+    let place = |start: i32, available: i32, size: i32, margin: (i32, i32), align| {
+        let available = (available - margin.0 - margin.1).max(0);
+        let size = size.clamp(0, available);
+
+        let start = start + margin.0;
+        match align {
+            Align::Start => (start, start + size),
+            Align::Center => {
+                let start = start + (available - size) / 2;
+                (start, start + size)
+            }
+            Align::End => (start + available - size, start + available),
+            Align::Stretch => (start, start + available),
+        }
+    };
+
+    items
+        .iter()
+        .map(|item| {
+            let align = item.align.unwrap_or(align);
+            let margin = item.margin;
+            let (left, right) = place(
+                inner.left,
+                inner.width(),
+                item.size.width,
+                (margin.left, margin.right),
+                align,
+            );
+            let (top, bottom) = place(
+                inner.top,
+                inner.height(),
+                item.size.height,
+                (margin.top, margin.bottom),
+                align,
+            );
+
+            Rect::new(left, top, right, bottom)
+        })
+        .collect()
+}
+
+// A row or column of widgets.
+pub struct FlexBox {
+    bounds: Rect,
+    flex: Flex,
+}
+
+impl FlexBox {
+    pub fn new(flex: Flex) -> Self {
+        Self {
+            bounds: Rect::default(),
+            flex,
+        }
+    }
+
+    pub fn flex(&self) -> &Flex {
+        &self.flex
+    }
+
+    pub fn set_flex(&mut self, flex: Flex) {
+        self.flex = flex;
+    }
+}
+
+impl Widget for FlexBox {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    fn paint(&self, _canvas: &mut dyn Canvas) {}
+
+    fn hit_test(&self, _x: i32, _y: i32) -> bool {
+        false
+    }
+
+    fn measure(&self, _available: Size, children: &[FlexItem]) -> Size {
+        self.flex.measure(children)
+    }
+
+    fn arrange(&self, bounds: Rect, children: &[FlexItem]) -> Option<Vec<Rect>> {
+        Some(self.flex.layout(bounds, children))
+    }
+}
+
+// Layers its children on top of each other.
+pub struct Stack {
+    bounds: Rect,
+    padding: Insets,
+    align: Align,
+}
+
+impl Stack {
+    pub fn new(padding: Insets, align: Align) -> Self {
+        Self {
+            bounds: Rect::default(),
+            padding,
+            align,
+        }
+    }
+}
+
+impl Widget for Stack {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    fn paint(&self, _canvas: &mut dyn Canvas) {}
+
+    fn hit_test(&self, _x: i32, _y: i32) -> bool {
+        false
+    }
+
+    fn measure(&self, _available: Size, children: &[FlexItem]) -> Size {
+        let width = children
+            .iter()
+            .map(|item| item.size.width + item.margin.horizontal())
+            .max()
+            .unwrap_or(0);
+        let height = children
+            .iter()
+            .map(|item| item.size.height + item.margin.vertical())
+            .max()
+            .unwrap_or(0);
+
+        Size::new(
+            width + self.padding.horizontal(),
+            height + self.padding.vertical(),
+        )
+    }
+
+    fn arrange(&self, bounds: Rect, children: &[FlexItem]) -> Option<Vec<Rect>> {
+        Some(stack(bounds, self.padding, self.align, children))
+    }
+}
+
+// Empty space in a layout. Give it a `FlexItem::spacer()` to soak up what is left.
+pub struct Spacer {
+    bounds: Rect,
+}
+
+impl Spacer {
+    pub fn new() -> Self {
+        Self {
+            bounds: Rect::default(),
+        }
+    }
+}
+
+impl Default for Spacer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Widget for Spacer {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    fn paint(&self, _canvas: &mut dyn Canvas) {}
+
+    fn hit_test(&self, _x: i32, _y: i32) -> bool {
+        false
+    }
+
+    fn measure(&self, _available: Size, _children: &[FlexItem]) -> Size {
+        Size::default()
+    }
+}
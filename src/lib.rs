@@ -0,0 +1,56 @@
+pub mod animation;
+pub mod autosave;
+pub mod button;
+pub mod canvas;
+pub mod close;
+pub mod closed_tabs;
+#[cfg(windows)]
+pub mod component;
+#[cfg(windows)]
+pub mod d2d_canvas;
+pub mod dpi;
+pub mod error;
+pub mod event;
+pub mod font;
+pub mod geometry;
+// Runs the app without a display, for scripted sessions and tests
+#[cfg(not(windows))]
+pub mod headless;
+pub mod host;
+pub mod image;
+// What the keys of X11 and Wayland keyboards type
+#[cfg(target_os = "linux")]
+pub mod keymap;
+#[cfg(target_os = "linux")]
+mod keysyms;
+pub mod layout;
+pub mod logging;
+#[cfg(windows)]
+mod macros;
+pub mod menu;
+pub mod menu_window;
+pub mod platform;
+pub mod popup_menu;
+pub mod region;
+pub mod render_resources;
+pub mod session;
+#[cfg(not(windows))]
+pub mod software_canvas;
+pub mod tab_bar;
+pub mod tab_content;
+pub mod tab_menu;
+pub mod tab_model;
+pub mod theme;
+pub mod title_editor;
+pub mod units;
+#[cfg(target_os = "linux")]
+pub mod wayland;
+pub mod widget;
+pub mod widget_tree;
+#[cfg(windows)]
+pub mod win32;
+pub mod window;
+#[cfg(windows)]
+pub mod wutils;
+#[cfg(target_os = "linux")]
+pub mod x11;
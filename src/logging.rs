@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Option<Level>> {
+        match s.to_ascii_lowercase().as_str() {
+            "off" => Some(None),
+            "error" => Some(Some(Level::Error)),
+            "warn" => Some(Some(Level::Warn)),
+            "info" => Some(Some(Level::Info)),
+            "debug" => Some(Some(Level::Debug)),
+            "trace" => Some(Some(Level::Trace)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    Button,
+    TabBar,
+    Window,
+    D2d,
+    // Every message a window procedure receives, see `trace_message`
+    Messages,
+}
+
+impl Target {
+    pub const ALL: [Target; 5] = [
+        Target::Button,
+        Target::TabBar,
+        Target::Window,
+        Target::D2d,
+        Target::Messages,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::Button => "button",
+            Target::TabBar => "tab_bar",
+            Target::Window => "window",
+            Target::D2d => "d2d",
+            Target::Messages => "messages",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Target> {
+        Target::ALL.into_iter().find(|target| target.name() == s)
+    }
+}
+
+// Decides which records get through. `None` turns a target off entirely.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    default: Option<Level>,
+    targets: HashMap<Target, Option<Level>>,
+}
+
+impl Default for Filter {
+    // Message tracing is noisy, so it has to be asked for explicitly.
+    fn default() -> Self {
+        Filter::new(Some(Level::Info)).with_target(Target::Messages, None)
+    }
+}
+
+impl Filter {
+    pub fn new(default: Option<Level>) -> Self {
+        Self {
+            default,
+            targets: HashMap::new(),
+        }
+    }
+
+    pub fn with_target(mut self, target: Target, max: Option<Level>) -> Self {
+        self.targets.insert(target, max);
+        self
+    }
+
+    // Parses specs like `warn,tab_bar=debug,messages=trace`: an optional default level
+    // followed by per-target overrides.
+    pub fn parse(spec: &str) -> Result<Filter, String> {
+        let mut filter = Filter::default();
+
+        for part in spec
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    let target = Target::parse(target.trim())
+                        .ok_or_else(|| format!("unknown log target `{}`", target))?;
+                    let level = Level::parse(level.trim())
+                        .ok_or_else(|| format!("unknown log level `{}`", level))?;
+                    filter.targets.insert(target, level);
+                }
+                None => {
+                    filter.default = Level::parse(part)
+                        .ok_or_else(|| format!("unknown log level `{}`", part))?;
+                }
+            }
+        }
+
+        Ok(filter)
+    }
+
+    pub fn enabled(&self, target: Target, level: Level) -> bool {
+        let max = self.targets.get(&target).copied().unwrap_or(self.default);
+        max.is_some_and(|max| level <= max)
+    }
+}
+
+pub struct Record<'a> {
+    pub level: Level,
+    pub target: Target,
+    // Time since the logger was set up
+    pub elapsed: Duration,
+    pub args: fmt::Arguments<'a>,
+}
+
+impl fmt::Display for Record<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "[{:>4}.{:03} {:<5} {}] {}",
+            self.elapsed.as_secs(),
+            self.elapsed.subsec_millis(),
+            self.level.name(),
+            self.target.name(),
+            self.args
+        )
+    }
+}
+
+pub trait Sink: Send {
+    fn write(&mut self, record: &Record);
+}
+
+pub struct StderrSink;
+
+impl Sink for StderrSink {
+    fn write(&mut self, record: &Record) {
+        eprintln!("{}", record);
+    }
+}
+
+pub struct FileSink {
+    writer: BufWriter<File>,
+}
+
+impl FileSink {
+    pub fn append(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+}
+
+impl Sink for FileSink {
+    // Flushed every line, so the log is complete even when the process goes down
+    fn write(&mut self, record: &Record) {
+        let _ = writeln!(self.writer, "{}", record).and_then(|_| self.writer.flush());
+    }
+}
+
+pub struct Logger {
+    filter: Filter,
+    sinks: Vec<Box<dyn Sink>>,
+    start: Instant,
+}
+
+impl Logger {
+    pub fn new(filter: Filter) -> Self {
+        Self {
+            filter,
+            sinks: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+
+    pub fn with_sink(mut self, sink: Box<dyn Sink>) -> Self {
+        self.sinks.push(sink);
+        self
+    }
+
+    pub fn enabled(&self, target: Target, level: Level) -> bool {
+        !self.sinks.is_empty() && self.filter.enabled(target, level)
+    }
+
+    pub fn log(&mut self, level: Level, target: Target, args: fmt::Arguments) {
+        if !self.enabled(target, level) {
+            return;
+        }
+
+        let record = Record {
+            level,
+            target,
+            elapsed: self.start.elapsed(),
+            args,
+        };
+
+        for sink in &mut self.sinks {
+            sink.write(&record);
+        }
+    }
+}
+
+static LOGGER: Mutex<Option<Logger>> = Mutex::new(None);
+
+// Until this is called, everything logged is dropped.
+pub fn init(logger: Logger) {
+    if let Ok(mut guard) = LOGGER.lock() {
+        *guard = Some(logger);
+    }
+}
+
+pub fn enabled(target: Target, level: Level) -> bool {
+    match LOGGER.lock() {
+        Ok(guard) => guard
+            .as_ref()
+            .is_some_and(|logger| logger.enabled(target, level)),
+        Err(_) => false,
+    }
+}
+
+pub fn log(level: Level, target: Target, args: fmt::Arguments) {
+    if let Ok(mut guard) = LOGGER.lock() {
+        if let Some(logger) = guard.as_mut() {
+            logger.log(level, target, args);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! log {
+    ( $level:expr, $target:expr, $($arg:tt)+ ) => {
+        $crate::logging::log($level, $target, format_args!($($arg)+))
+    };
+}
+
+#[macro_export]
+macro_rules! log_error {
+    ( $target:expr, $($arg:tt)+ ) => {
+        $crate::log!($crate::logging::Level::Error, $target, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! log_warn {
+    ( $target:expr, $($arg:tt)+ ) => {
+        $crate::log!($crate::logging::Level::Warn, $target, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! log_info {
+    ( $target:expr, $($arg:tt)+ ) => {
+        $crate::log!($crate::logging::Level::Info, $target, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! log_debug {
+    ( $target:expr, $($arg:tt)+ ) => {
+        $crate::log!($crate::logging::Level::Debug, $target, $($arg)+)
+    };
+}
+
+#[macro_export]
+macro_rules! log_trace {
+    ( $target:expr, $($arg:tt)+ ) => {
+        $crate::log!($crate::logging::Level::Trace, $target, $($arg)+)
+    };
+}
+
+// Logs a window message under `Target::Messages`, with its name when it is a known one.
+pub fn trace_message(component: &str, message: u32, wparam: usize, lparam: isize) {
+    if !enabled(Target::Messages, Level::Trace) {
+        return;
+    }
+
+    log_trace!(
+        Target::Messages,
+        "{} {} wparam={:#x} lparam={:#x}",
+        component,
+        MessageName(message),
+        wparam,
+        lparam
+    );
+}
+
+// Formats as the `WM_*` name when known and the hex id otherwise.
+pub struct MessageName(pub u32);
+
+impl fmt::Display for MessageName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match message_name(self.0) {
+            Some(name) => f.write_str(name),
+            None if self.0 >= WM_APP => write!(f, "WM_APP+{:#x}", self.0 - WM_APP),
+            None if self.0 >= WM_USER => write!(f, "WM_USER+{:#x}", self.0 - WM_USER),
+            None => write!(f, "{:#06x}", self.0),
+        }
+    }
+}
+
+const WM_USER: u32 = 0x0400;
+const WM_APP: u32 = 0x8000;
+
+// The ids are spelled out instead of taken from winapi so the table works everywhere. Kept
+// sorted by id, `message_name` searches it.
+pub const MESSAGE_NAMES: &[(u32, &str)] = &[
+    (0x0000, "WM_NULL"),
+    (0x0001, "WM_CREATE"),
+    (0x0002, "WM_DESTROY"),
+    (0x0003, "WM_MOVE"),
+    (0x0005, "WM_SIZE"),
+    (0x0006, "WM_ACTIVATE"),
+    (0x0007, "WM_SETFOCUS"),
+    (0x0008, "WM_KILLFOCUS"),
+    (0x000A, "WM_ENABLE"),
+    (0x000B, "WM_SETREDRAW"),
+    (0x000C, "WM_SETTEXT"),
+    (0x000D, "WM_GETTEXT"),
+    (0x000E, "WM_GETTEXTLENGTH"),
+    (0x000F, "WM_PAINT"),
+    (0x0010, "WM_CLOSE"),
+    (0x0011, "WM_QUERYENDSESSION"),
+    (0x0012, "WM_QUIT"),
+    (0x0014, "WM_ERASEBKGND"),
+    (0x0018, "WM_SHOWWINDOW"),
+    (0x001A, "WM_SETTINGCHANGE"),
+    (0x001C, "WM_ACTIVATEAPP"),
+    (0x0020, "WM_SETCURSOR"),
+    (0x0021, "WM_MOUSEACTIVATE"),
+    (0x0024, "WM_GETMINMAXINFO"),
+    (0x0046, "WM_WINDOWPOSCHANGING"),
+    (0x0047, "WM_WINDOWPOSCHANGED"),
+    (0x007B, "WM_CONTEXTMENU"),
+    (0x007F, "WM_GETICON"),
+    (0x0080, "WM_SETICON"),
+    (0x0081, "WM_NCCREATE"),
+    (0x0082, "WM_NCDESTROY"),
+    (0x0083, "WM_NCCALCSIZE"),
+    (0x0084, "WM_NCHITTEST"),
+    (0x0085, "WM_NCPAINT"),
+    (0x0086, "WM_NCACTIVATE"),
+    (0x0087, "WM_GETDLGCODE"),
+    (0x00A0, "WM_NCMOUSEMOVE"),
+    (0x00A1, "WM_NCLBUTTONDOWN"),
+    (0x00A2, "WM_NCLBUTTONUP"),
+    (0x00A3, "WM_NCLBUTTONDBLCLK"),
+    (0x00A4, "WM_NCRBUTTONDOWN"),
+    (0x00A5, "WM_NCRBUTTONUP"),
+    (0x0100, "WM_KEYDOWN"),
+    (0x0101, "WM_KEYUP"),
+    (0x0102, "WM_CHAR"),
+    (0x0104, "WM_SYSKEYDOWN"),
+    (0x0105, "WM_SYSKEYUP"),
+    (0x0106, "WM_SYSCHAR"),
+    (0x0111, "WM_COMMAND"),
+    (0x0112, "WM_SYSCOMMAND"),
+    (0x0113, "WM_TIMER"),
+    (0x0116, "WM_INITMENU"),
+    (0x0117, "WM_INITMENUPOPUP"),
+    (0x011F, "WM_MENUSELECT"),
+    (0x0120, "WM_MENUCHAR"),
+    (0x0121, "WM_ENTERIDLE"),
+    (0x0125, "WM_UNINITMENUPOPUP"),
+    (0x0200, "WM_MOUSEMOVE"),
+    (0x0201, "WM_LBUTTONDOWN"),
+    (0x0202, "WM_LBUTTONUP"),
+    (0x0203, "WM_LBUTTONDBLCLK"),
+    (0x0204, "WM_RBUTTONDOWN"),
+    (0x0205, "WM_RBUTTONUP"),
+    (0x0206, "WM_RBUTTONDBLCLK"),
+    (0x0207, "WM_MBUTTONDOWN"),
+    (0x0208, "WM_MBUTTONUP"),
+    (0x0209, "WM_MBUTTONDBLCLK"),
+    (0x020A, "WM_MOUSEWHEEL"),
+    (0x020E, "WM_MOUSEHWHEEL"),
+    (0x0210, "WM_PARENTNOTIFY"),
+    (0x0211, "WM_ENTERMENULOOP"),
+    (0x0212, "WM_EXITMENULOOP"),
+    (0x0214, "WM_SIZING"),
+    (0x0215, "WM_CAPTURECHANGED"),
+    (0x0216, "WM_MOVING"),
+    (0x0231, "WM_ENTERSIZEMOVE"),
+    (0x0232, "WM_EXITSIZEMOVE"),
+    (0x0281, "WM_IME_SETCONTEXT"),
+    (0x0282, "WM_IME_NOTIFY"),
+    (0x02A1, "WM_MOUSEHOVER"),
+    (0x02A2, "WM_NCMOUSELEAVE"),
+    (0x02A3, "WM_MOUSELEAVE"),
+    (0x02E0, "WM_DPICHANGED"),
+    (0x031A, "WM_THEMECHANGED"),
+    (0x031F, "WM_DWMNCRENDERINGCHANGED"),
+];
+
+pub fn message_name(message: u32) -> Option<&'static str> {
+    MESSAGE_NAMES
+        .binary_search_by_key(&message, |(id, _)| *id)
+        .ok()
+        .map(|idx| MESSAGE_NAMES[idx].1)
+}
@@ -1,82 +1,50 @@
-#[macro_export]
-macro_rules! wpanic_ifeq {
-    ( $code:expr, $compared:expr ) => {{
-        let res = unsafe { $code };
+// Each of these runs a Win32 call and turns the failure value into an `Err` carrying the
+// name of the call and the last OS error.
+macro_rules! werr_ifeq {
+    ( $api:ident ( $($arg:expr),* $(,)? ), $compared:expr ) => {{
+        let res = unsafe { $api($($arg),*) };
         if res == $compared {
-            std::panic::panic_any(std::io::Error::last_os_error());
+            Err($crate::error::Error::last_os_error(stringify!($api)))
+        } else {
+            Ok(res)
         }
-        res
     }};
 }
 
-#[macro_export]
-macro_rules! wpanic_ifne {
-    ( $code:expr, $compared:expr ) => {{
-        let res = unsafe { $code };
+macro_rules! werr_ifne {
+    ( $api:ident ( $($arg:expr),* $(,)? ), $compared:expr ) => {{
+        let res = unsafe { $api($($arg),*) };
         if res != $compared {
-            std::panic::panic_any(std::io::Error::last_os_error());
+            Err($crate::error::Error::last_os_error(stringify!($api)))
+        } else {
+            Ok(res)
         }
-        res
     }};
 }
 
-#[macro_export]
-macro_rules! wpanic_ifnull {
-    ( $code:expr ) => {{
-        let res = unsafe { $code };
+macro_rules! werr_ifnull {
+    ( $api:ident ( $($arg:expr),* $(,)? ) ) => {{
+        let res = unsafe { $api($($arg),*) };
         if res as winapi::shared::minwindef::LPVOID == winapi::shared::ntdef::NULL {
-            std::panic::panic_any(std::io::Error::last_os_error());
+            Err($crate::error::Error::last_os_error(stringify!($api)))
+        } else {
+            Ok(res)
         }
-        res
     }};
 }
 
-#[macro_export]
-macro_rules! wpanic_ifisnull {
-    ( $code:expr ) => {{
-        let res = unsafe { $code };
+macro_rules! werr_ifisnull {
+    ( $api:ident ( $($arg:expr),* $(,)? ) ) => {{
+        let res = unsafe { $api($($arg),*) };
         if res.is_null() {
-            std::panic::panic_any(std::io::Error::last_os_error());
+            Err($crate::error::Error::last_os_error(stringify!($api)))
+        } else {
+            Ok(res)
         }
-        res
     }};
 }
 
-#[macro_export]
-macro_rules! wnd_proc_gen {
-    ( $component_class:ident, $fn_name:ident ) => {
-        extern "system" fn $fn_name(
-            hwnd: winapi::shared::windef::HWND,
-            message: winapi::shared::minwindef::UINT,
-            wparam: winapi::shared::minwindef::WPARAM,
-            lparam: winapi::shared::minwindef::LPARAM,
-        ) -> winapi::shared::minwindef::LRESULT {
-            let component;
-
-            if message == winapi::um::winuser::WM_NCCREATE
-                || message == winapi::um::winuser::WM_CREATE
-            {
-                let cs = lparam as *const winapi::um::winuser::CREATESTRUCTW;
-                unsafe {
-                    component = (*cs).lpCreateParams as *mut $component_class;
-                    (*component).hwnd = hwnd;
-                    winapi::um::winuser::SetWindowLongPtrW(
-                        hwnd,
-                        winapi::um::winuser::GWLP_USERDATA,
-                        component as _,
-                    );
-                }
-            } else {
-                component = unsafe {
-                    winapi::um::winuser::GetWindowLongPtrW(hwnd, winapi::um::winuser::GWLP_USERDATA)
-                } as *mut $component_class;
-            }
-
-            if let Some(component) = unsafe { component.as_mut() } {
-                return component.handle_message(message, wparam, lparam);
-            }
-
-            unsafe { winapi::um::winuser::DefWindowProcW(hwnd, message, wparam, lparam) }
-        }
-    };
-}
+pub(crate) use werr_ifeq;
+pub(crate) use werr_ifisnull;
+pub(crate) use werr_ifne;
+pub(crate) use werr_ifnull;
@@ -0,0 +1,171 @@
+// What a popup menu shows. `A` stands for whatever picking an item does, the menu only hands
+// it back.
+//
+// Labels mark the key that picks an entry with a `&` in front of it, e.g. `"&Close"` is
+// picked with C. `&&` stands for a plain `&`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuEntry<A> {
+    Item(MenuItem<A>),
+    Submenu(Submenu<A>),
+    Separator,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuItem<A> {
+    pub action: A,
+    pub label: String,
+    // Disabled items show but can't be picked
+    pub enabled: bool,
+    pub checked: bool,
+    // The shortcut that does the same, only shown as a hint
+    pub accelerator: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Submenu<A> {
+    pub label: String,
+    pub enabled: bool,
+    pub entries: Vec<MenuEntry<A>>,
+}
+
+impl<A> MenuItem<A> {
+    pub fn new(action: A, label: &str) -> Self {
+        Self {
+            action,
+            label: String::from(label),
+            enabled: true,
+            checked: false,
+            accelerator: None,
+        }
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    pub fn with_checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    pub fn with_accelerator(mut self, accelerator: &str) -> Self {
+        self.accelerator = Some(String::from(accelerator));
+        self
+    }
+}
+
+impl<A> Submenu<A> {
+    pub fn new(label: &str, entries: Vec<MenuEntry<A>>) -> Self {
+        Self {
+            label: String::from(label),
+            enabled: true,
+            entries,
+        }
+    }
+
+    pub fn with_enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+}
+
+impl<A> MenuEntry<A> {
+    pub fn item(action: A, label: &str) -> Self {
+        Self::Item(MenuItem::new(action, label))
+    }
+
+    pub fn submenu(label: &str, entries: Vec<MenuEntry<A>>) -> Self {
+        Self::Submenu(Submenu::new(label, entries))
+    }
+
+    // The label as written, with its `&` marks.
+    pub fn label(&self) -> Option<&str> {
+        match self {
+            Self::Item(item) => Some(&item.label),
+            Self::Submenu(submenu) => Some(&submenu.label),
+            Self::Separator => None,
+        }
+    }
+
+    // The label as shown, see `parse_label`.
+    pub fn text(&self) -> Option<String> {
+        self.label().map(|label| parse_label(label).0)
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        match self {
+            Self::Item(item) => item.enabled,
+            Self::Submenu(submenu) => submenu.enabled,
+            Self::Separator => false,
+        }
+    }
+
+    // What picking the entry does, `None` for separators, submenus and disabled items.
+    pub fn action(&self) -> Option<&A> {
+        match self {
+            Self::Item(item) if item.enabled => Some(&item.action),
+            _ => None,
+        }
+    }
+
+    // The key that picks the entry, in lower case.
+    pub fn mnemonic(&self) -> Option<char> {
+        let (text, index) = parse_label(self.label()?);
+        text.chars()
+            .nth(index?)
+            .map(|mnemonic| mnemonic.to_ascii_lowercase())
+    }
+
+    // The same entry, and everything in it, with `f` applied to the actions.
+    pub fn map<B>(self, f: &impl Fn(A) -> B) -> MenuEntry<B> {
+        match self {
+            Self::Item(item) => MenuEntry::Item(MenuItem {
+                action: f(item.action),
+                label: item.label,
+                enabled: item.enabled,
+                checked: item.checked,
+                accelerator: item.accelerator,
+            }),
+            Self::Submenu(submenu) => MenuEntry::Submenu(Submenu {
+                label: submenu.label,
+                enabled: submenu.enabled,
+                entries: submenu
+                    .entries
+                    .into_iter()
+                    .map(|entry| entry.map(f))
+                    .collect(),
+            }),
+            Self::Separator => MenuEntry::Separator,
+        }
+    }
+}
+
+// Splits a label into the text to show and the position of the marked character in it, in
+// characters. Only the first mark counts.
+pub fn parse_label(label: &str) -> (String, Option<usize>) {
+    let mut text = String::with_capacity(label.len());
+    let mut mark = None;
+    let mut chars = label.chars();
+
+    while let Some(ch) = chars.next() {
+        if ch != '&' {
+            text.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            Some('&') => text.push('&'),
+            Some(marked) => {
+                if mark.is_none() {
+                    mark = Some(text.chars().count());
+                }
+                text.push(marked);
+            }
+            // A trailing `&` marks nothing
+            None => {}
+        }
+    }
+
+    (text, mark)
+}
@@ -0,0 +1,163 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::error::Error;
+use crate::event::{Event, EventHandler, HitZone, Reply};
+use crate::geometry::{Point, Rect};
+use crate::host::Hosted;
+use crate::platform::{Platform, Popup, WindowId, WindowParams};
+use crate::popup_menu::PopupMenu;
+use crate::render_resources::{DeviceError, RenderResources};
+use crate::widget::{Context, Widget};
+
+const MENU_CLASS_NAME: &str = "testwindowtabs.Menu";
+
+// One open level of a menu, shown on a popup of its own so the menu can reach past the window
+// it belongs to. That window owns the menu and places the popups, a popup only paints its
+// level and hands the menu the mouse.
+pub struct MenuWindow<P: Platform, A> {
+    platform: Rc<P>,
+    window: WindowId,
+    menu: Rc<RefCell<PopupMenu<A>>>,
+    level: usize,
+    // Where the popup is in the owner's client coordinates, which the menu is laid out in
+    origin: Point,
+    // Runs once the mouse changed the menu
+    changed: Rc<dyn Fn()>,
+    renderer: Option<RenderResources<P::DeviceFactory>>,
+}
+
+impl<P: Platform + 'static, A: Clone + 'static> MenuWindow<P, A> {
+    // Opens a popup over `owner` for `level` of the menu, where the menu has it.
+    pub fn open(
+        platform: Rc<P>,
+        owner: WindowId,
+        menu: Rc<RefCell<PopupMenu<A>>>,
+        level: usize,
+        changed: Rc<dyn Fn()>,
+    ) -> Result<Hosted<Self>, Error> {
+        let bounds = menu
+            .borrow()
+            .level_bounds()
+            .get(level)
+            .copied()
+            .unwrap_or_default();
+        let me = Self {
+            platform: platform.clone(),
+            window: WindowId(0),
+            menu,
+            level,
+            origin: Point::new(bounds.left, bounds.top),
+            changed,
+            renderer: None,
+        };
+
+        let params = WindowParams {
+            class_name: MENU_CLASS_NAME,
+            title: String::new(),
+            size: bounds.size(),
+            custom_frame: false,
+            popup: Some(Popup { owner, bounds }),
+        };
+        platform.create_window(&params, me)
+    }
+
+    fn client_rect(&self) -> Result<Rect, Error> {
+        self.platform.client_rect(self.window)
+    }
+
+    fn paint(&mut self) -> Result<(), Error> {
+        let mut renderer = match self.renderer.take() {
+            Some(renderer) => renderer,
+            None => return Ok(()),
+        };
+
+        let menu = self.menu.borrow();
+        let result = renderer.draw(|canvas| menu.paint_level(canvas, self.level, self.origin));
+        drop(menu);
+        self.renderer = Some(renderer);
+
+        match result {
+            Ok(()) => Ok(()),
+            // Painted again on a new device
+            Err(DeviceError::Lost) => self.platform.invalidate(self.window, self.client_rect()?),
+            Err(DeviceError::Failed(hr)) => Err(Error::Hresult {
+                api: "Direct2D",
+                hr,
+            }),
+        }
+    }
+
+    // Hands the menu a mouse event at `pos` in the popup's client coordinates.
+    fn mouse(
+        &mut self,
+        pos: Point,
+        f: impl FnOnce(&mut PopupMenu<A>, i32, i32, &mut Context),
+    ) -> Result<(), Error> {
+        let mut menu = self.menu.borrow_mut();
+        let mut ctx = Context::new(self.platform.clock().now(), menu.dpi());
+        f(
+            &mut menu,
+            pos.x + self.origin.x,
+            pos.y + self.origin.y,
+            &mut ctx,
+        );
+        let changed = !ctx.dirty().rects().is_empty() || menu.is_done();
+        drop(menu);
+
+        // The owner may close this popup right away
+        if changed {
+            (self.changed)();
+        }
+        Ok(())
+    }
+}
+
+impl<P: Platform + 'static, A: Clone + 'static> EventHandler for MenuWindow<P, A> {
+    type Custom = ();
+
+    fn attach(&mut self, window: WindowId) {
+        self.window = window;
+    }
+
+    fn handle_event(&mut self, event: Event<()>) -> Result<Reply, Error> {
+        match event {
+            Event::Created => {
+                let dpi = self.menu.borrow().dpi();
+                self.renderer = Some(RenderResources::new(
+                    self.platform.device_factory(self.window),
+                    self.client_rect()?.size(),
+                    dpi,
+                ));
+            }
+            Event::Resize(size) => {
+                if let Some(Err(DeviceError::Lost)) = self.renderer.as_mut().map(|r| r.resize(size))
+                {
+                    self.platform.invalidate(self.window, self.client_rect()?)?;
+                }
+            }
+            Event::Paint { .. } => {
+                self.paint()?;
+                return Ok(Reply::Handled);
+            }
+            Event::HitTest { .. } => return Ok(Reply::HitTest(HitZone::Client)),
+            Event::MouseMove { pos, buttons } => self.mouse(pos, |menu, x, y, ctx| {
+                menu.on_mouse_move(x, y, buttons.left, ctx)
+            })?,
+            Event::MouseDown { pos, .. } => self.mouse(pos, |menu, x, y, ctx| {
+                menu.on_mouse_down(x, y, ctx);
+            })?,
+            Event::MouseUp { pos, .. } => {
+                self.mouse(pos, |menu, x, y, ctx| menu.on_mouse_up(x, y, ctx))?
+            }
+            Event::CloseRequested => {
+                self.menu.borrow_mut().dismiss();
+                (self.changed)();
+                return Ok(Reply::Handled);
+            }
+            _ => {}
+        }
+
+        Ok(Reply::Default)
+    }
+}
@@ -0,0 +1,153 @@
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::animation::Clock;
+use crate::dpi::Dpi;
+use crate::error::Error;
+use crate::event::{EventHandler, HitZone};
+use crate::geometry::{Point, Rect, Size};
+use crate::host::Hosted;
+use crate::menu::{MenuEntry, MenuItem};
+use crate::render_resources::DeviceFactory;
+
+// Identifies a window of the platform it came from, e.g. the HWND on Win32.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowId(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SystemCommand {
+    Minimize,
+    Maximize,
+    Restore,
+    Close,
+    // Moving and sizing with the keyboard, where the system supports it
+    Move,
+    Size,
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowParams {
+    // For platforms that register window classes
+    pub class_name: &'static str,
+    pub title: String,
+    // In pixels, at whatever DPI the window ends up on
+    pub size: Size,
+    // Draws its own title bar in the client area, the system only keeps the resize borders
+    pub custom_frame: bool,
+    pub popup: Option<Popup>,
+}
+
+// A window shown over its owner without a frame and without taking the focus, e.g. a menu.
+// It may reach past the owner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Popup {
+    pub owner: WindowId,
+    // In client coordinates of the owner, `WindowParams::size` is ignored
+    pub bounds: Rect,
+}
+
+// Everything the app needs from the system it runs on. Positions are in client coordinates
+// of `window` unless said otherwise, sizes in physical pixels.
+pub trait Platform {
+    type DeviceFactory: DeviceFactory;
+
+    fn create_window<C: EventHandler + 'static>(
+        &self,
+        params: &WindowParams,
+        component: C,
+    ) -> Result<Hosted<C>, Error>;
+    // Hands `event` to the component later, as `Event::Custom`
+    fn post_event<C: EventHandler + 'static>(
+        &self,
+        window: &Hosted<C>,
+        event: C::Custom,
+    ) -> Result<(), Error>;
+    // Runs `command` once the current event is handled
+    fn post_command(&self, window: WindowId, command: SystemCommand) -> Result<(), Error>;
+    fn quit(&self);
+
+    fn device_factory(&self, window: WindowId) -> Self::DeviceFactory;
+    fn clock(&self) -> Rc<dyn Clock>;
+
+    fn dpi(&self, window: WindowId) -> Result<Dpi, Error>;
+    fn client_rect(&self, window: WindowId) -> Result<Rect, Error>;
+    // Including the frame, in screen coordinates
+    fn window_bounds(&self, window: WindowId) -> Result<Rect, Error>;
+    fn set_window_bounds(&self, window: WindowId, bounds: Rect) -> Result<(), Error>;
+    // Keeps the position, and makes the system pick up a changed frame
+    fn resize_window(&self, window: WindowId, size: Size) -> Result<(), Error>;
+    // Where the window goes when it is restored, also while it is maximized. In screen
+    // coordinates.
+    fn restored_bounds(&self, window: WindowId) -> Result<Rect, Error>;
+    fn is_maximized(&self, window: WindowId) -> Result<bool, Error>;
+    fn is_active(&self, window: WindowId) -> bool;
+    // Height of the system's caption at `dpi`, what a custom title bar should match
+    fn caption_height(&self, window: WindowId, dpi: Dpi) -> Result<i32, Error>;
+    // Thickness of the top resize border at `dpi`
+    fn resize_border(&self, window: WindowId, dpi: Dpi) -> Result<i32, Error>;
+
+    fn invalidate(&self, window: WindowId, rect: Rect) -> Result<(), Error>;
+    fn set_capture(&self, window: WindowId);
+    fn release_capture(&self, window: WindowId) -> Result<(), Error>;
+    // Asks for an `Event::MouseLeave` once the mouse leaves the window
+    fn track_mouse_leave(&self, window: WindowId) -> Result<(), Error>;
+    fn set_timer(&self, window: WindowId, id: usize, interval: Duration) -> Result<(), Error>;
+    fn kill_timer(&self, window: WindowId, id: usize);
+    // In screen coordinates
+    fn cursor_position(&self) -> Result<Point, Error>;
+    fn client_to_screen(&self, window: WindowId, point: Point) -> Result<Point, Error>;
+    fn animations_enabled(&self) -> bool;
+    // Work areas of all monitors, in screen coordinates. Empty where windows can't tell where
+    // they are.
+    fn monitors(&self) -> Result<Vec<Rect>, Error>;
+    // Work area of the monitor the window is on
+    fn monitor(&self, window: WindowId) -> Result<Option<Rect>, Error>;
+
+    // Asks the user a yes or no question in a dialog over `window`, and waits for the answer.
+    // `None` where there is no dialog to ask with, see `Window::ask`.
+    fn confirm(&self, window: WindowId, title: &str, question: &str)
+        -> Result<Option<bool>, Error>;
+}
+
+// What the title bar's menu offers, the commands of the system's window menu. A maximized
+// window can only be restored, not moved or sized.
+pub fn system_menu(maximized: bool) -> Vec<MenuEntry<SystemCommand>> {
+    let item = |command, label, enabled| {
+        MenuEntry::Item(MenuItem::new(command, label).with_enabled(enabled))
+    };
+
+    vec![
+        item(SystemCommand::Restore, "&Restore", maximized),
+        item(SystemCommand::Move, "&Move", !maximized),
+        item(SystemCommand::Size, "&Size", !maximized),
+        item(SystemCommand::Minimize, "Mi&nimize", true),
+        item(SystemCommand::Maximize, "Ma&ximize", !maximized),
+        MenuEntry::Separator,
+        MenuEntry::Item(MenuItem::new(SystemCommand::Close, "&Close").with_accelerator("Alt+F4")),
+    ]
+}
+
+// Where `pos` is on a window that draws its own frame, with resize borders of `border`
+// pixels inside `client`. For platforms without a system frame to ask.
+pub fn frame_hit_test(client: Rect, pos: Point, border: i32) -> HitZone {
+    if !client.contains_point(pos.x, pos.y) {
+        return HitZone::Nowhere;
+    }
+
+    let left = pos.x < client.left + border;
+    let right = pos.x >= client.right - border;
+    let top = pos.y < client.top + border;
+    let bottom = pos.y >= client.bottom - border;
+
+    match (left, right, top, bottom) {
+        (true, _, true, _) => HitZone::TopLeft,
+        (_, true, true, _) => HitZone::TopRight,
+        (true, _, _, true) => HitZone::BottomLeft,
+        (_, true, _, true) => HitZone::BottomRight,
+        (true, ..) => HitZone::Left,
+        (_, true, ..) => HitZone::Right,
+        (_, _, true, _) => HitZone::Top,
+        (.., true) => HitZone::Bottom,
+        _ => HitZone::Client,
+    }
+}
@@ -0,0 +1,615 @@
+use crate::canvas::Canvas;
+use crate::dpi::Dpi;
+use crate::event::Key;
+use crate::font::Font;
+use crate::geometry::{Insets, Point, PointF, Rect, Size};
+use crate::menu::{parse_label, MenuEntry};
+use crate::theme::MenuColors;
+use crate::units::Dip;
+use crate::widget::{Context, Widget};
+
+const BORDER: Dip = Dip(1.0);
+// Between the border and the entries
+const PADDING: Dip = Dip(3.0);
+const ITEM_HEIGHT: Dip = Dip(22.0);
+const SEPARATOR_HEIGHT: Dip = Dip(7.0);
+// Left of the labels, where check marks go
+const CHECK_WIDTH: Dip = Dip(24.0);
+// Between the longest label and the accelerator hints
+const ACCELERATOR_GAP: Dip = Dip(24.0);
+// Right of everything else, where submenu arrows go
+const ARROW_WIDTH: Dip = Dip(20.0);
+const MIN_WIDTH: Dip = Dip(120.0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MenuOutcome<A> {
+    Picked(A),
+    Dismissed,
+}
+
+// Which submenus of a menu are open and which entry of each open level is highlighted, the
+// top level first. The submenu a level shows is the entry highlighted in the level before it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MenuState {
+    path: Vec<Option<usize>>,
+}
+
+impl Default for MenuState {
+    fn default() -> Self {
+        Self { path: vec![None] }
+    }
+}
+
+impl MenuState {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // How many levels are open, the top one included
+    pub fn depth(&self) -> usize {
+        self.path.len()
+    }
+
+    pub fn highlighted(&self, level: usize) -> Option<usize> {
+        self.path.get(level).copied().flatten()
+    }
+
+    // What `level` shows, empty when it isn't open.
+    pub fn entries<'a, A>(&self, root: &'a [MenuEntry<A>], level: usize) -> &'a [MenuEntry<A>] {
+        let mut entries = root;
+        for index in self.path.iter().take(level) {
+            match index.and_then(|index| entries.get(index)) {
+                Some(MenuEntry::Submenu(submenu)) => entries = &submenu.entries,
+                _ => return &[],
+            }
+        }
+        entries
+    }
+
+    // Highlights an entry of `level`, which closes whatever the previous one had open.
+    pub fn highlight(&mut self, level: usize, index: Option<usize>) {
+        if level < self.path.len() && self.path[level] != index {
+            self.path.truncate(level + 1);
+            self.path[level] = index;
+        }
+    }
+
+    // Opens the submenu highlighted in `level`, if there is one. From the keyboard its first
+    // entry gets highlighted as well.
+    pub fn open_submenu<A>(
+        &mut self,
+        root: &[MenuEntry<A>],
+        level: usize,
+        highlight_first: bool,
+    ) -> bool {
+        let entries = self.entries(root, level);
+        let submenu = match self.highlighted(level).and_then(|index| entries.get(index)) {
+            Some(MenuEntry::Submenu(submenu)) if submenu.enabled => submenu,
+            _ => return false,
+        };
+
+        let first = step(&submenu.entries, None, 1).filter(|_| highlight_first);
+        if self.path.len() > level + 1 {
+            // Already open
+            if self.path[level + 1].is_none() {
+                self.path[level + 1] = first;
+            }
+        } else {
+            self.path.push(first);
+        }
+        true
+    }
+
+    pub fn close_submenu(&mut self) -> bool {
+        if self.path.len() > 1 {
+            self.path.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Does what picking an entry does: an item ends the menu, a submenu opens.
+    pub fn activate<A: Clone>(
+        &mut self,
+        root: &[MenuEntry<A>],
+        level: usize,
+        index: usize,
+        from_keyboard: bool,
+    ) -> Option<MenuOutcome<A>> {
+        let entry = self
+            .entries(root, level)
+            .get(index)
+            .filter(|entry| entry.is_enabled())?;
+        self.highlight(level, Some(index));
+
+        match entry {
+            MenuEntry::Item(item) => Some(MenuOutcome::Picked(item.action.clone())),
+            _ => {
+                self.open_submenu(root, level, from_keyboard);
+                None
+            }
+        }
+    }
+
+    // Moves through the innermost open level. Up and down skip what can't be picked and wrap
+    // around, right and left open and close submenus, and a character picks the entry it
+    // marks.
+    pub fn key<A: Clone>(&mut self, root: &[MenuEntry<A>], key: Key) -> Option<MenuOutcome<A>> {
+        let level = self.path.len() - 1;
+        let entries = self.entries(root, level);
+        let current = self.highlighted(level);
+
+        match key {
+            Key::Down => self.highlight(level, step(entries, current, 1)),
+            Key::Up => self.highlight(level, step(entries, current, -1)),
+            Key::Home => self.highlight(level, step(entries, None, 1)),
+            Key::End => self.highlight(level, step(entries, None, -1)),
+            Key::Right => {
+                self.open_submenu(root, level, true);
+            }
+            Key::Left => {
+                self.close_submenu();
+            }
+            Key::Escape => {
+                if !self.close_submenu() {
+                    return Some(MenuOutcome::Dismissed);
+                }
+            }
+            Key::Enter | Key::Space => {
+                return current.and_then(|index| self.activate(root, level, index, true));
+            }
+            Key::Char(ch) => return self.mnemonic(root, level, ch),
+            Key::Tab | Key::Backspace | Key::Other => {}
+        }
+
+        None
+    }
+
+    // Picks the only entry of `level` marked with `ch`. When more share it, they get
+    // highlighted in turn instead.
+    fn mnemonic<A: Clone>(
+        &mut self,
+        root: &[MenuEntry<A>],
+        level: usize,
+        ch: char,
+    ) -> Option<MenuOutcome<A>> {
+        let ch = ch.to_ascii_lowercase();
+        let marked = self
+            .entries(root, level)
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.is_enabled() && entry.mnemonic() == Some(ch))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        match marked[..] {
+            [] => None,
+            [index] => self.activate(root, level, index, true),
+            _ => {
+                let current = self.highlighted(level);
+                let next = marked
+                    .iter()
+                    .find(|index| Some(**index) > current)
+                    .or(marked.first())
+                    .copied();
+                self.highlight(level, next);
+                None
+            }
+        }
+    }
+}
+
+// The next entry that can be picked going `direction` from `from`, wrapping around. Without a
+// start that is the first one going down and the last one going up.
+fn step<A>(entries: &[MenuEntry<A>], from: Option<usize>, direction: isize) -> Option<usize> {
+    let len = entries.len() as isize;
+    let start = match from {
+        Some(from) => from as isize,
+        None if direction > 0 => -1,
+        None => len,
+    };
+
+    (1..=len)
+        .map(|n| (start + n * direction).rem_euclid(len) as usize)
+        .find(|index| entries[*index].is_enabled())
+}
+
+// Size of a menu showing `entries`, and where each entry goes relative to its top left corner.
+pub fn measure_menu<A>(entries: &[MenuEntry<A>], dpi: Dpi) -> (Size, Vec<Rect>) {
+    let font = Font::new(dpi);
+    let inset = dpi.px(BORDER).get() + dpi.px(PADDING).get();
+
+    let label_width = entries
+        .iter()
+        .filter_map(|entry| entry.text())
+        .map(|text| font.text_width(&text))
+        .max()
+        .unwrap_or(0);
+    let accelerator_width = entries
+        .iter()
+        .filter_map(|entry| match entry {
+            MenuEntry::Item(item) => item.accelerator.as_deref(),
+            _ => None,
+        })
+        .map(|accelerator| font.text_width(accelerator))
+        .max()
+        .map_or(0, |width| dpi.px(ACCELERATOR_GAP).get() + width);
+
+    let content =
+        dpi.px(CHECK_WIDTH).get() + label_width + accelerator_width + dpi.px(ARROW_WIDTH).get();
+    let width = (content + 2 * inset).max(dpi.px(MIN_WIDTH).get());
+
+    let mut top = inset;
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            let height = match entry {
+                MenuEntry::Separator => dpi.px(SEPARATOR_HEIGHT).get(),
+                _ => dpi.px(ITEM_HEIGHT).get(),
+            };
+            let row = Rect::new(inset, top, width - inset, top + height);
+            top += height;
+            row
+        })
+        .collect();
+
+    (Size::new(width, top + inset), rows)
+}
+
+// Where a menu of `size` opened at `anchor` goes: below and to the right of it, or on the other
+// side of the anchor where that would leave `area`. Fitting neither way, it is pushed back
+// inside.
+pub fn place_menu(anchor: Point, size: Size, area: Rect) -> Rect {
+    let left = flip(anchor.x, anchor.x, size.width, area.left, area.right);
+    let top = flip(anchor.y, anchor.y, size.height, area.top, area.bottom);
+    Rect::from_size(left, top, size.width, size.height)
+}
+
+// Where a submenu of `size` goes next to `parent`, the menu it opened from: to the right of it
+// starting level with `item`, or to the left and ending level with `item` where it wouldn't
+// fit in `area`.
+pub fn place_submenu(parent: Rect, item: Rect, size: Size, area: Rect) -> Rect {
+    let left = flip(parent.right, parent.left, size.width, area.left, area.right);
+    let top = flip(item.top, item.bottom, size.height, area.top, area.bottom);
+    Rect::from_size(left, top, size.width, size.height)
+}
+
+// Start of a span of `len` starting at `start`, or ending at `end` when it doesn't fit before
+// `max`. Fitting neither way it ends at `max`, but never starts before `min`.
+fn flip(start: i32, end: i32, len: i32, min: i32, max: i32) -> i32 {
+    if start + len <= max {
+        start
+    } else if end - len >= min {
+        end - len
+    } else {
+        (max - len).max(min)
+    }
+}
+
+struct Level {
+    bounds: Rect,
+    rows: Vec<Rect>,
+}
+
+// A menu with its submenus opening beside it, laid out in the window's client coordinates.
+// The window hands it the keys while it is open, the popups showing its levels the mouse,
+// and takes how it ended afterwards.
+pub struct PopupMenu<A> {
+    entries: Vec<MenuEntry<A>>,
+    state: MenuState,
+    anchor: Point,
+    area: Rect,
+    colors: MenuColors,
+    dpi: Dpi,
+    levels: Vec<Level>,
+    outcome: Option<MenuOutcome<A>>,
+}
+
+impl<A: Clone + 'static> PopupMenu<A> {
+    // Opens at `anchor`, and it and its submenus stay inside `area`.
+    pub fn new(
+        entries: Vec<MenuEntry<A>>,
+        anchor: Point,
+        area: Rect,
+        colors: MenuColors,
+        dpi: Dpi,
+    ) -> Self {
+        let mut menu = Self {
+            entries,
+            state: MenuState::new(),
+            anchor,
+            area,
+            colors,
+            dpi,
+            levels: Vec::new(),
+            outcome: None,
+        };
+        menu.layout();
+        menu
+    }
+
+    pub fn entries(&self) -> &[MenuEntry<A>] {
+        &self.entries
+    }
+
+    pub fn state(&self) -> &MenuState {
+        &self.state
+    }
+
+    // Bounds of the open levels, the top one first.
+    pub fn level_bounds(&self) -> Vec<Rect> {
+        self.levels.iter().map(|level| level.bounds).collect()
+    }
+
+    pub fn entry_bounds(&self, level: usize, index: usize) -> Option<Rect> {
+        self.levels.get(level)?.rows.get(index).copied()
+    }
+
+    // What the open levels show, separators as "-".
+    pub fn labels(&self) -> Vec<Vec<String>> {
+        (0..self.state.depth())
+            .map(|level| {
+                self.state
+                    .entries(&self.entries, level)
+                    .iter()
+                    .map(|entry| entry.text().unwrap_or_else(|| String::from("-")))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn dpi(&self) -> Dpi {
+        self.dpi
+    }
+
+    // Whether the menu ended and the outcome waits to be taken.
+    pub fn is_done(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    pub fn take_outcome(&mut self) -> Option<MenuOutcome<A>> {
+        self.outcome.take()
+    }
+
+    pub fn dismiss(&mut self) {
+        self.outcome.get_or_insert(MenuOutcome::Dismissed);
+    }
+
+    pub fn on_key(&mut self, key: Key, ctx: &mut Context) {
+        self.change(ctx, |state, entries| state.key(entries, key));
+    }
+
+    // Applies `f` to the state, repaints whatever it changed and keeps the outcome.
+    fn change(
+        &mut self,
+        ctx: &mut Context,
+        f: impl FnOnce(&mut MenuState, &[MenuEntry<A>]) -> Option<MenuOutcome<A>>,
+    ) {
+        if self.outcome.is_some() {
+            return;
+        }
+
+        let before = self.state.clone();
+        let outcome = f(&mut self.state, &self.entries);
+        if self.state != before {
+            ctx.invalidate(self.bounds());
+            self.layout();
+            ctx.invalidate(self.bounds());
+        }
+        self.outcome = outcome;
+    }
+
+    fn layout(&mut self) {
+        let inset = self.dpi.px(BORDER).get() + self.dpi.px(PADDING).get();
+
+        self.levels.clear();
+        for level in 0..self.state.depth() {
+            let entries = self.state.entries(&self.entries, level);
+            let (size, rows) = measure_menu(entries, self.dpi);
+
+            let bounds = match self.levels.last() {
+                Some(parent) => {
+                    // The submenu's first entry lines up with the entry it opened from
+                    let item = self
+                        .state
+                        .highlighted(level - 1)
+                        .and_then(|index| parent.rows.get(index))
+                        .map_or(parent.bounds, |row| {
+                            row.inset(&Insets::new(0, -inset, 0, -inset))
+                        });
+                    place_submenu(parent.bounds, item, size, self.area)
+                }
+                None => place_menu(self.anchor, size, self.area),
+            };
+
+            let rows = rows
+                .into_iter()
+                .map(|row| row.offset(bounds.left, bounds.top))
+                .collect();
+            self.levels.push(Level { bounds, rows });
+        }
+    }
+
+    // Paints one open level with `origin` at the canvas' top left corner, e.g. on a popup of
+    // its own.
+    pub fn paint_level(&self, canvas: &mut dyn Canvas, level: usize, origin: Point) {
+        let Level { bounds, rows } = match self.levels.get(level) {
+            Some(level) => level,
+            None => return,
+        };
+        let bounds = bounds.offset(-origin.x, -origin.y);
+        let border = self.dpi.px(BORDER).get();
+
+        canvas.fill_rect(bounds.into(), self.colors.border);
+        canvas.fill_rect(
+            bounds.inset(&Insets::uniform(border)).into(),
+            self.colors.background,
+        );
+
+        let entries = self.state.entries(&self.entries, level);
+        for (index, (entry, row)) in entries.iter().zip(rows).enumerate() {
+            let lit = entry.is_enabled() && self.state.highlighted(level) == Some(index);
+            let row = row.offset(-origin.x, -origin.y);
+            self.paint_entry(canvas, entry, row, lit);
+        }
+    }
+
+    // The innermost level under the point, and the entry under it if any.
+    fn hit(&self, x: i32, y: i32) -> Option<(usize, Option<usize>)> {
+        let level = self
+            .levels
+            .iter()
+            .rposition(|level| level.bounds.contains_point(x, y))?;
+        let index = self.levels[level]
+            .rows
+            .iter()
+            .position(|row| row.contains_point(x, y));
+        Some((level, index))
+    }
+
+    fn paint_entry(&self, canvas: &mut dyn Canvas, entry: &MenuEntry<A>, row: Rect, lit: bool) {
+        let font = Font::new(self.dpi);
+        let colors = &self.colors;
+
+        if let MenuEntry::Separator = entry {
+            let top = row.top + row.height() / 2;
+            let line = Rect::new(row.left, top, row.right, top + font.scale());
+            canvas.fill_rect(line.into(), colors.separator);
+            return;
+        }
+
+        if lit {
+            canvas.fill_rect(row.into(), colors.highlight);
+        }
+        let color = match (entry.is_enabled(), lit) {
+            (false, _) => colors.disabled_text,
+            (true, true) => colors.highlight_text,
+            (true, false) => colors.text,
+        };
+
+        let (text, mark) = parse_label(entry.label().unwrap_or_default());
+        let top = row.top + (row.height() - font.height()) / 2;
+        let left = row.left + self.dpi.px(CHECK_WIDTH).get();
+        canvas.draw_text(&text, Point::new(left, top), color);
+
+        if let Some((start, end)) = mark.map(|mark| font.char_span(&text, mark)) {
+            let underline_top = top + font.height() + font.scale();
+            let underline = Rect::new(
+                left + start,
+                underline_top,
+                left + end,
+                underline_top + font.scale(),
+            );
+            canvas.fill_rect(underline.into(), color);
+        }
+
+        let middle = (row.top + row.bottom) as f32 / 2.0;
+        let size = font.height() as f32;
+        let width = font.scale() as f32 * 1.5;
+        match entry {
+            MenuEntry::Item(item) => {
+                if item.checked {
+                    let center = (row.left + self.dpi.px(CHECK_WIDTH).get() / 2) as f32;
+                    let corner = PointF::new(center - size / 6.0, middle + size / 3.0);
+                    canvas.draw_line(
+                        PointF::new(center - size / 2.0, middle),
+                        corner,
+                        color,
+                        width,
+                    );
+                    canvas.draw_line(
+                        corner,
+                        PointF::new(center + size / 2.0, middle - size / 3.0),
+                        color,
+                        width,
+                    );
+                }
+
+                if let Some(accelerator) = item.accelerator.as_deref() {
+                    let right = row.right - self.dpi.px(ARROW_WIDTH).get();
+                    let origin = Point::new(right - font.text_width(accelerator), top);
+                    canvas.draw_text(accelerator, origin, color);
+                }
+            }
+            MenuEntry::Submenu(_) => {
+                let center = (row.right - self.dpi.px(ARROW_WIDTH).get() / 2) as f32;
+                let tip = PointF::new(center + size / 4.0, middle);
+                canvas.draw_line(
+                    PointF::new(center - size / 4.0, middle - size / 2.0),
+                    tip,
+                    color,
+                    width,
+                );
+                canvas.draw_line(
+                    tip,
+                    PointF::new(center - size / 4.0, middle + size / 2.0),
+                    color,
+                    width,
+                );
+            }
+            MenuEntry::Separator => {}
+        }
+    }
+}
+
+impl<A: Clone + 'static> Widget for PopupMenu<A> {
+    fn bounds(&self) -> Rect {
+        self.levels
+            .iter()
+            .fold(Rect::default(), |bounds, level| bounds.union(&level.bounds))
+    }
+
+    // The menu places itself next to its anchor
+    fn set_bounds(&mut self, _bounds: Rect) {}
+
+    fn paint(&self, canvas: &mut dyn Canvas) {
+        for level in 0..self.levels.len() {
+            self.paint_level(canvas, level, Point::new(0, 0));
+        }
+    }
+
+    fn hit_test(&self, x: i32, y: i32) -> bool {
+        self.hit(x, y).is_some()
+    }
+
+    fn on_dpi_changed(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+        self.layout();
+    }
+
+    // Hovering an entry highlights it, and opens it if it's a submenu.
+    fn on_mouse_move(&mut self, x: i32, y: i32, _left_down: bool, ctx: &mut Context) {
+        let (level, index) = match self.hit(x, y) {
+            Some((level, Some(index))) => (level, index),
+            _ => return,
+        };
+
+        self.change(ctx, |state, entries| {
+            let enabled = state
+                .entries(entries, level)
+                .get(index)
+                .is_some_and(|entry| entry.is_enabled());
+            state.highlight(level, Some(index).filter(|_| enabled));
+            state.open_submenu(entries, level, false);
+            None
+        });
+    }
+
+    // Pressing anywhere outside of the menu dismisses it.
+    fn on_mouse_down(&mut self, x: i32, y: i32, ctx: &mut Context) -> bool {
+        if self.hit(x, y).is_none() {
+            self.change(ctx, |_, _| Some(MenuOutcome::Dismissed));
+        }
+        false
+    }
+
+    // Releasing any button over an entry picks it, like the system's menus.
+    fn on_mouse_up(&mut self, x: i32, y: i32, ctx: &mut Context) {
+        if let Some((level, Some(index))) = self.hit(x, y) {
+            self.change(ctx, |state, entries| {
+                state.activate(entries, level, index, false)
+            });
+        }
+    }
+}
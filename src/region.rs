@@ -0,0 +1,100 @@
+use crate::geometry::Rect;
+
+// Past this many separate rects painting them one by one costs more than repainting their
+// bounding box.
+const MAX_RECTS: usize = 8;
+
+// Accumulates the damaged areas of a window between two paints. Overlapping and touching rects
+// are merged as long as the merged rect doesn't cover much more than the rects themselves, so
+// hovering two neighbouring tabs repaints one strip while distant changes stay separate.
+#[derive(Debug, Default, Clone)]
+pub struct DirtyRegion {
+    rects: Vec<Rect>,
+}
+
+impl DirtyRegion {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rects.is_empty()
+    }
+
+    pub fn rects(&self) -> &[Rect] {
+        &self.rects
+    }
+
+    pub fn bounds(&self) -> Rect {
+        self.rects
+            .iter()
+            .fold(Rect::default(), |bounds, rect| bounds.union(rect))
+    }
+
+    pub fn intersects(&self, rect: &Rect) -> bool {
+        self.rects.iter().any(|dirty| dirty.intersects(rect))
+    }
+
+    // The parts of `rect` that need repainting.
+    pub fn clip(&self, rect: &Rect) -> Vec<Rect> {
+        self.rects
+            .iter()
+            .map(|dirty| dirty.intersection(rect))
+            .filter(|clipped| !clipped.is_empty())
+            .collect()
+    }
+
+    pub fn add(&mut self, rect: Rect) {
+        if rect.is_empty() || self.rects.iter().any(|dirty| dirty.contains(&rect)) {
+            return;
+        }
+
+        self.rects.retain(|dirty| !rect.contains(dirty));
+
+        let mut pending = rect;
+        // Merging can make the new rect reach others, so keep going until nothing changes.
+        while let Some(idx) = self
+            .rects
+            .iter()
+            .position(|dirty| should_merge(dirty, &pending))
+        {
+            pending = pending.union(&self.rects.swap_remove(idx));
+        }
+
+        self.rects.push(pending);
+
+        if self.rects.len() > MAX_RECTS {
+            let bounds = self.bounds();
+            self.rects.clear();
+            self.rects.push(bounds);
+        }
+    }
+
+    pub fn add_region(&mut self, other: &DirtyRegion) {
+        for rect in &other.rects {
+            self.add(*rect);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.rects.clear();
+    }
+
+    // Hands out everything accumulated for the current frame and starts over.
+    pub fn take(&mut self) -> DirtyRegion {
+        std::mem::take(self)
+    }
+}
+
+fn should_merge(a: &Rect, b: &Rect) -> bool {
+    if !a.touches(b) {
+        return false;
+    }
+
+    let union = a.union(b).area();
+    let overlap = a.intersection(b).area();
+    let covered = a.area() + b.area() - overlap;
+
+    // Accept up to 25% of wasted area in the merged rect.
+    union * 4 <= covered * 5
+}
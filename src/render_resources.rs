@@ -0,0 +1,145 @@
+use std::time::Duration;
+
+use crate::canvas::Canvas;
+use crate::dpi::Dpi;
+use crate::geometry::Size;
+use crate::logging::Target;
+use crate::{log_error, log_warn};
+
+// How long to wait before trying to create a device again after creating one failed. The
+// wait doubles with every failure in a row, up to the maximum.
+const RETRY_DELAY: Duration = Duration::from_millis(100);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceError {
+    // The device went away (driver update or reset, remote desktop reconnect, ...). Everything
+    // created from it has to be recreated.
+    Lost,
+    Failed(i32),
+}
+
+// A render target together with everything created from it (brushes, bitmaps). All of it is
+// tied to the underlying device and dropped as a whole when the device is lost.
+pub trait RenderDevice: Canvas {
+    fn begin_draw(&mut self);
+    fn end_draw(&mut self) -> Result<(), DeviceError>;
+    fn resize(&mut self, size: Size) -> Result<(), DeviceError>;
+    fn set_dpi(&mut self, dpi: Dpi);
+}
+
+pub trait DeviceFactory {
+    type Device: RenderDevice;
+
+    fn create_device(&mut self, size: Size, dpi: Dpi) -> Result<Self::Device, DeviceError>;
+}
+
+// Owns the device of a window and recreates it whenever it is lost. The device is created
+// lazily on the first draw after that, so nothing is recreated for a minimized window.
+pub struct RenderResources<F: DeviceFactory> {
+    factory: F,
+    device: Option<F::Device>,
+    size: Size,
+    dpi: Dpi,
+    lost_count: u32,
+    // Attempts to create a device that failed since the last one that worked
+    failed_creations: u32,
+}
+
+impl<F: DeviceFactory> RenderResources<F> {
+    pub fn new(factory: F, size: Size, dpi: Dpi) -> Self {
+        Self {
+            factory,
+            device: None,
+            size,
+            dpi,
+            lost_count: 0,
+            failed_creations: 0,
+        }
+    }
+
+    pub fn has_device(&self) -> bool {
+        self.device.is_some()
+    }
+
+    // How often the device was lost so far.
+    pub fn lost_count(&self) -> u32 {
+        self.lost_count
+    }
+
+    // After a lost device the next draw creates a new one right away. If creating it is what
+    // failed, the GPU is likely still resetting and the caller should wait this long first.
+    pub fn retry_delay(&self) -> Option<Duration> {
+        match self.failed_creations {
+            0 => None,
+            failed => Some(
+                RETRY_DELAY
+                    .saturating_mul(1 << (failed - 1).min(16))
+                    .min(MAX_RETRY_DELAY),
+            ),
+        }
+    }
+
+    pub fn resize(&mut self, size: Size) -> Result<(), DeviceError> {
+        self.size = size;
+
+        let result = match self.device.as_mut() {
+            Some(device) => device.resize(size),
+            None => Ok(()),
+        };
+
+        self.handle_result(result)
+    }
+
+    pub fn set_dpi(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+
+        if let Some(device) = self.device.as_mut() {
+            device.set_dpi(dpi);
+        }
+    }
+
+    // Drops the device and everything created from it.
+    pub fn release(&mut self) {
+        self.device = None;
+    }
+
+    // Paints a frame. `Err(DeviceError::Lost)` means nothing made it to the screen and the
+    // device was dropped, so the caller has to repaint everything.
+    pub fn draw<R>(&mut self, f: impl FnOnce(&mut dyn Canvas) -> R) -> Result<R, DeviceError> {
+        let device = match self.device.take() {
+            Some(device) => device,
+            None => {
+                let device = self.factory.create_device(self.size, self.dpi);
+                self.failed_creations = match device {
+                    Ok(_) => 0,
+                    Err(_) => self.failed_creations.saturating_add(1),
+                };
+                self.handle_result(device)?
+            }
+        };
+        let device = self.device.insert(device);
+
+        device.begin_draw();
+        let value = f(device);
+        let result = device.end_draw().map(|_| value);
+
+        self.handle_result(result)
+    }
+
+    fn handle_result<T>(&mut self, result: Result<T, DeviceError>) -> Result<T, DeviceError> {
+        match result {
+            Err(DeviceError::Lost) => {
+                self.lost_count += 1;
+                log_warn!(Target::D2d, "device lost ({} so far)", self.lost_count);
+                self.release();
+            }
+            Err(DeviceError::Failed(hr)) => {
+                log_error!(Target::D2d, "device failed with HRESULT {:#010x}", hr);
+            }
+            Ok(_) => {}
+        }
+
+        result
+    }
+}
@@ -0,0 +1,485 @@
+use std::path::Path;
+use std::{error, fmt, fs, io};
+
+use crate::autosave;
+use crate::closed_tabs::ClosedTab;
+use crate::geometry::Rect;
+
+// What is left of the app's windows when it quits, to bring them back on the next start.
+//
+// The file is plain text, one record per line, starting with a header that carries the
+// version:
+//
+//     testwindowtabs session 5
+//     window
+//     monitor 0 0 1920 1040
+//     bounds 100 100 600 600
+//     maximized true
+//     active 1
+//     tab "Notes" "opaque content state" true
+//     tab "Other" "" false
+//     tab "Third" "" false
+//     group "Research" 1 false 1 2
+//     end
+//     closed 0 2 "Closed tab" "its content state" false
+//
+// Files of older versions are brought up to date by `MIGRATIONS` before they are read.
+const HEADER: &str = "testwindowtabs session";
+pub const SESSION_VERSION: u32 = 5;
+
+// Far beyond any screen, and far enough from `i32::MAX` that sizes and sums of coordinates
+// can't overflow
+const MAX_COORDINATE: i32 = 1 << 24;
+
+// `MIGRATIONS[n]` turns the records of version `n + 1` into those of version `n + 2`.
+const MIGRATIONS: [fn(Vec<Record>) -> Vec<Record>; SESSION_VERSION as usize - 1] = [
+    // Version 2 added the monitor and the content state of tabs. Tabs of version 1 start
+    // out with empty content, and the window goes to whatever monitor it overlaps.
+    |records| {
+        records
+            .into_iter()
+            .map(|mut record| {
+                if record.key == "tab" {
+                    record.values.push(Value::Text(String::new()));
+                }
+                record
+            })
+            .collect()
+    },
+    // Version 3 added the closed tabs, which older files have none of.
+    |records| records,
+    // Version 4 added pinned tabs. None of the tabs of older files are.
+    |records| {
+        records
+            .into_iter()
+            .map(|mut record| {
+                if record.key == "tab" || record.key == "closed" {
+                    record.values.push(Value::Word(String::from("false")));
+                }
+                record
+            })
+            .collect()
+    },
+    // Version 5 added tab groups, which older files have none of.
+    |records| records,
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Session {
+    pub windows: Vec<WindowSession>,
+    // Oldest first, see `ClosedTabs`
+    pub closed_tabs: Vec<ClosedTab>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WindowSession {
+    pub placement: Placement,
+    pub tabs: Vec<TabSession>,
+    // Index into `tabs`
+    pub active: Option<usize>,
+    // In the order they show in
+    pub groups: Vec<GroupSession>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GroupSession {
+    pub name: String,
+    // See `TabGroup::color`
+    pub color: usize,
+    pub collapsed: bool,
+    // Indices into the window's `tabs`
+    pub tabs: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Placement {
+    // Work area of the monitor the window was on, in screen coordinates
+    pub monitor: Option<Rect>,
+    // Where the window goes when it isn't maximized, kept while it is
+    pub restored: Rect,
+    pub maximized: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabSession {
+    pub title: String,
+    // Whatever the tab's content wants back, the session doesn't look into it
+    pub state: String,
+    pub pinned: bool,
+}
+
+#[derive(Debug)]
+pub enum SessionError {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+    // Written by a newer version of the app
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Io(err) => write!(f, "can't access the session: {}", err),
+            SessionError::Parse { line, message } => {
+                write!(f, "session line {}: {}", line, message)
+            }
+            SessionError::UnsupportedVersion(version) => {
+                write!(f, "session version {} is newer than this app", version)
+            }
+        }
+    }
+}
+
+impl error::Error for SessionError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            SessionError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SessionError {
+    fn from(err: io::Error) -> Self {
+        SessionError::Io(err)
+    }
+}
+
+impl Session {
+    // A missing file is an empty session, like on the first start.
+    pub fn load(path: &Path) -> Result<Self, SessionError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // Written atomically, an autosave cut short by a crash leaves the last complete session.
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        autosave::write_atomic(path, self.to_text().as_bytes())?;
+        Ok(())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{} {}\n", HEADER, SESSION_VERSION);
+
+        for window in &self.windows {
+            let placement = &window.placement;
+            text.push_str("window\n");
+            if let Some(monitor) = placement.monitor {
+                text.push_str(&format!("monitor {}\n", rect_text(monitor)));
+            }
+            text.push_str(&format!("bounds {}\n", rect_text(placement.restored)));
+            text.push_str(&format!("maximized {}\n", placement.maximized));
+            if let Some(active) = window.active {
+                text.push_str(&format!("active {}\n", active));
+            }
+            for tab in &window.tabs {
+                text.push_str(&format!(
+                    "tab {} {} {}\n",
+                    quote(&tab.title),
+                    quote(&tab.state),
+                    tab.pinned
+                ));
+            }
+            for group in &window.groups {
+                text.push_str(&format!(
+                    "group {} {} {}",
+                    quote(&group.name),
+                    group.color,
+                    group.collapsed
+                ));
+                for tab in &group.tabs {
+                    text.push_str(&format!(" {}", tab));
+                }
+                text.push('\n');
+            }
+            text.push_str("end\n");
+        }
+
+        for closed in &self.closed_tabs {
+            text.push_str(&format!(
+                "closed {} {} {} {} {}\n",
+                closed.window,
+                closed.index,
+                quote(&closed.tab.title),
+                quote(&closed.tab.state),
+                closed.tab.pinned
+            ));
+        }
+
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, SessionError> {
+        let mut lines = text.lines().enumerate();
+        let version = match lines.next() {
+            Some((_, header)) => header
+                .strip_prefix(HEADER)
+                .and_then(|version| version.trim().parse::<u32>().ok())
+                .filter(|version| *version > 0)
+                .ok_or_else(|| parse_error(1, "not a session file"))?,
+            None => return Ok(Self::default()),
+        };
+        if version > SESSION_VERSION {
+            return Err(SessionError::UnsupportedVersion(version));
+        }
+
+        let mut records = lines
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| Record::parse(index, line))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for migrate in &MIGRATIONS[version as usize - 1..] {
+            records = migrate(records);
+        }
+
+        Self::from_records(records, text.lines().count())
+    }
+
+    fn from_records(records: Vec<Record>, last_line: usize) -> Result<Self, SessionError> {
+        let mut session = Self::default();
+        let mut window: Option<WindowSession> = None;
+
+        for record in records {
+            let line = record.line;
+            let current = match (record.key.as_str(), window.as_mut()) {
+                ("closed", None) => {
+                    session.closed_tabs.push(ClosedTab {
+                        window: record.index(0)?,
+                        index: record.index(1)?,
+                        tab: TabSession {
+                            title: record.text(2)?,
+                            state: record.text(3)?,
+                            pinned: record.bool(4)?,
+                        },
+                    });
+                    continue;
+                }
+                ("window", None) => {
+                    window = Some(WindowSession::default());
+                    continue;
+                }
+                ("window", Some(_)) => return Err(parse_error(line, "window inside a window")),
+                (_, None) => return Err(parse_error(line, "record outside of a window")),
+                (_, Some(current)) => current,
+            };
+
+            match record.key.as_str() {
+                "monitor" => current.placement.monitor = Some(record.rect()?),
+                "bounds" => current.placement.restored = record.rect()?,
+                "maximized" => current.placement.maximized = record.bool(0)?,
+                "active" => current.active = Some(record.int(0)? as usize),
+                "tab" => current.tabs.push(TabSession {
+                    title: record.text(0)?,
+                    state: record.text(1)?,
+                    pinned: record.bool(2)?,
+                }),
+                "group" => current.groups.push(GroupSession {
+                    name: record.text(0)?,
+                    color: record.index(1)?,
+                    collapsed: record.bool(2)?,
+                    tabs: (3..record.values.len())
+                        .map(|index| record.index(index))
+                        .collect::<Result<_, _>>()?,
+                }),
+                "end" => {
+                    let mut done = window.take().unwrap_or_default();
+                    if done.active.is_some_and(|active| active >= done.tabs.len()) {
+                        done.active = None;
+                    }
+                    let len = done.tabs.len();
+                    for group in &mut done.groups {
+                        group.tabs.retain(|tab| *tab < len);
+                    }
+                    session.windows.push(done);
+                }
+                // Records a later version may add are skipped
+                _ => {}
+            }
+        }
+
+        match window {
+            Some(_) => Err(parse_error(last_line, "the last window has no end")),
+            None => Ok(session),
+        }
+    }
+}
+
+impl Placement {
+    // Puts the window on one of `monitors` (work areas in screen coordinates). It stays on
+    // the monitor it overlaps the most, or goes back to the one it was on, and otherwise to
+    // the first. It is moved and shrunk as far as needed to be fully visible there. Without
+    // any monitors to go by it stays where it was.
+    pub fn clamp_to(&self, monitors: &[Rect]) -> Placement {
+        let overlapping = monitors
+            .iter()
+            .map(|monitor| (monitor, monitor.intersection(&self.restored).area()))
+            .filter(|(_, area)| *area > 0)
+            .max_by_key(|(_, area)| *area)
+            .map(|(monitor, _)| monitor);
+
+        let target = overlapping
+            .or_else(|| {
+                monitors
+                    .iter()
+                    .find(|monitor| Some(**monitor) == self.monitor)
+            })
+            .or_else(|| monitors.first());
+
+        let target = match target {
+            Some(target) => *target,
+            None => return *self,
+        };
+
+        let restored = self.restored;
+        let width = restored.width().min(target.width());
+        let height = restored.height().min(target.height());
+        let left = restored.left.clamp(target.left, target.right - width);
+        let top = restored.top.clamp(target.top, target.bottom - height);
+
+        Placement {
+            monitor: Some(target),
+            restored: Rect::from_size(left, top, width, height),
+            maximized: self.maximized,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Word(String),
+    Text(String),
+}
+
+// A line of the file: a key and what follows it.
+#[derive(Debug, Clone)]
+struct Record {
+    line: usize,
+    key: String,
+    values: Vec<Value>,
+}
+
+impl Record {
+    fn parse(index: usize, text: &str) -> Result<Self, SessionError> {
+        let line = index + 1;
+        let mut values = Vec::new();
+        let mut chars = text.trim().chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                ' ' | '\t' => {}
+                '"' => {
+                    let mut value = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => match chars.next() {
+                                Some('n') => value.push('\n'),
+                                Some(c @ ('\\' | '"')) => value.push(c),
+                                _ => return Err(parse_error(line, "bad escape")),
+                            },
+                            Some(c) => value.push(c),
+                            None => return Err(parse_error(line, "unterminated text")),
+                        }
+                    }
+                    values.push(Value::Text(value));
+                }
+                c => {
+                    let mut word = String::from(c);
+                    while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                        word.push(c);
+                    }
+                    values.push(Value::Word(word));
+                }
+            }
+        }
+
+        let key = match values.first() {
+            Some(Value::Word(key)) => key.clone(),
+            _ => return Err(parse_error(line, "missing key")),
+        };
+        values.remove(0);
+
+        Ok(Self { line, key, values })
+    }
+
+    fn word(&self, index: usize) -> Result<&str, SessionError> {
+        match self.values.get(index) {
+            Some(Value::Word(word)) => Ok(word),
+            _ => Err(self.error(index, "a value")),
+        }
+    }
+
+    fn int(&self, index: usize) -> Result<i32, SessionError> {
+        self.word(index)?
+            .parse()
+            .map_err(|_| self.error(index, "a number"))
+    }
+
+    fn index(&self, index: usize) -> Result<usize, SessionError> {
+        self.word(index)?
+            .parse()
+            .map_err(|_| self.error(index, "a position"))
+    }
+
+    fn bool(&self, index: usize) -> Result<bool, SessionError> {
+        self.word(index)?
+            .parse()
+            .map_err(|_| self.error(index, "true or false"))
+    }
+
+    fn text(&self, index: usize) -> Result<String, SessionError> {
+        match self.values.get(index) {
+            Some(Value::Text(text)) => Ok(text.clone()),
+            _ => Err(self.error(index, "quoted text")),
+        }
+    }
+
+    // Only rects that could be on a screen. Anything else would overflow when sized or
+    // moved around, so the file must have been mangled.
+    fn rect(&self) -> Result<Rect, SessionError> {
+        let rect = Rect::new(self.int(0)?, self.int(1)?, self.int(2)?, self.int(3)?);
+
+        let in_range = [rect.left, rect.top, rect.right, rect.bottom]
+            .iter()
+            .all(|value| (-MAX_COORDINATE..=MAX_COORDINATE).contains(value));
+        if !in_range || rect.left > rect.right || rect.top > rect.bottom {
+            let message = format!("{} is not a rect on a screen", self.key);
+            return Err(parse_error(self.line, &message));
+        }
+        Ok(rect)
+    }
+
+    fn error(&self, index: usize, expected: &str) -> SessionError {
+        let message = format!("{} expects {} at {}", self.key, expected, index + 1);
+        parse_error(self.line, &message)
+    }
+}
+
+fn parse_error(line: usize, message: &str) -> SessionError {
+    SessionError::Parse {
+        line,
+        message: String::from(message),
+    }
+}
+
+fn rect_text(rect: Rect) -> String {
+    format!("{} {} {} {}", rect.left, rect.top, rect.right, rect.bottom)
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
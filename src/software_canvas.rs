@@ -0,0 +1,401 @@
+use crate::canvas::{Canvas, Color};
+use crate::dpi::Dpi;
+use crate::font::Font;
+use crate::geometry::{Point, PointF, Rect, RectF, Size};
+use crate::image::Image;
+use crate::render_resources::{DeviceError, RenderDevice};
+
+// Samples per axis for shapes without an exact coverage formula.
+const ELLIPSE_SAMPLES: usize = 4;
+
+// Premultiplied 0xAARRGGBB pixels, which is also the byte order X11 and Wayland take 32-bit
+// images in on little endian machines.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pixmap {
+    width: i32,
+    height: i32,
+    pixels: Vec<u32>,
+}
+
+impl Pixmap {
+    pub fn new(size: Size) -> Self {
+        let width = size.width.max(0);
+        let height = size.height.max(0);
+
+        Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize],
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        Size::new(self.width, self.height)
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::from_size(0, 0, self.width, self.height)
+    }
+
+    pub fn pixels(&self) -> &[u32] {
+        &self.pixels
+    }
+
+    pub fn row(&self, y: i32) -> &[u32] {
+        let start = (y * self.width) as usize;
+        &self.pixels[start..start + self.width as usize]
+    }
+
+    pub fn pixel(&self, x: i32, y: i32) -> Option<u32> {
+        if self.bounds().contains_point(x, y) {
+            Some(self.pixels[(y * self.width + x) as usize])
+        } else {
+            None
+        }
+    }
+
+    fn blend(&mut self, x: i32, y: i32, color: [f32; 4], coverage: f32) {
+        if coverage <= 0.0 {
+            return;
+        }
+
+        let index = (y * self.width + x) as usize;
+        let dst = unpack(self.pixels[index]);
+        let src_alpha = color[0] * coverage;
+
+        let mut out = [0.0; 4];
+        for channel in 0..4 {
+            out[channel] = color[channel] * coverage + dst[channel] * (1.0 - src_alpha);
+        }
+        self.pixels[index] = pack(out);
+    }
+}
+
+// Hands finished frames to whatever shows them.
+pub trait Present {
+    // `damage` is the part of `pixmap` that changed since the last frame.
+    fn present(&mut self, pixmap: &Pixmap, damage: Rect) -> Result<(), DeviceError>;
+}
+
+// Draws into memory, for platforms without a GPU canvas. Antialiasing computes how much of
+// each pixel a shape covers, aliased drawing only looks at the pixel centers like Direct2D.
+pub struct SoftwareCanvas<P: Present> {
+    pixmap: Pixmap,
+    presenter: P,
+    dpi: Dpi,
+    antialias: bool,
+    clips: Vec<Rect>,
+    damage: Rect,
+}
+
+impl<P: Present> SoftwareCanvas<P> {
+    pub fn new(presenter: P, size: Size, dpi: Dpi) -> Self {
+        Self {
+            pixmap: Pixmap::new(size),
+            presenter,
+            dpi,
+            antialias: true,
+            clips: Vec::new(),
+            damage: Rect::default(),
+        }
+    }
+
+    pub fn pixmap(&self) -> &Pixmap {
+        &self.pixmap
+    }
+
+    pub fn presenter(&self) -> &P {
+        &self.presenter
+    }
+
+    fn clip(&self) -> Rect {
+        self.clips.last().copied().unwrap_or(self.pixmap.bounds())
+    }
+
+    // The pixels `area` touches that may be drawn to, which become part of the damage.
+    fn target(&mut self, area: RectF) -> Rect {
+        let target = area.round_out().intersection(&self.clip());
+        if !target.is_empty() {
+            self.damage = if self.damage.is_empty() {
+                target
+            } else {
+                self.damage.union(&target)
+            };
+        }
+        target
+    }
+
+    fn fill(&mut self, area: RectF, color: Color, coverage: impl Fn(f32, f32) -> f32) {
+        let target = self.target(area);
+        let color = premultiplied(color);
+
+        for y in target.top..target.bottom {
+            for x in target.left..target.right {
+                self.pixmap.blend(x, y, color, coverage(x as f32, y as f32));
+            }
+        }
+    }
+}
+
+impl<P: Present> Canvas for SoftwareCanvas<P> {
+    fn dpi(&self) -> Dpi {
+        self.dpi
+    }
+
+    fn set_antialias(&mut self, enabled: bool) {
+        self.antialias = enabled;
+    }
+
+    fn clear(&mut self, color: Color) {
+        let target = self.target(self.clip().into());
+        let color = pack(premultiplied(color));
+
+        for y in target.top..target.bottom {
+            let start = (y * self.pixmap.width) as usize;
+            self.pixmap.pixels[start + target.left as usize..start + target.right as usize]
+                .fill(color);
+        }
+    }
+
+    fn fill_rect(&mut self, rect: RectF, color: Color) {
+        let antialias = self.antialias;
+        self.fill(rect, color, |x, y| rect_coverage(&rect, x, y, antialias));
+    }
+
+    // Like Direct2D the stroke is centered on the edges of `rect`.
+    fn stroke_rect(&mut self, rect: RectF, color: Color, width: f32) {
+        let half = width / 2.0;
+        let outer = RectF::new(
+            rect.left - half,
+            rect.top - half,
+            rect.right + half,
+            rect.bottom + half,
+        );
+        let inner = RectF::new(
+            rect.left + half,
+            rect.top + half,
+            rect.right - half,
+            rect.bottom - half,
+        );
+
+        let antialias = self.antialias;
+        self.fill(outer, color, |x, y| {
+            let inside = if inner.width() > 0.0 && inner.height() > 0.0 {
+                rect_coverage(&inner, x, y, antialias)
+            } else {
+                0.0
+            };
+            rect_coverage(&outer, x, y, antialias) - inside
+        });
+    }
+
+    // Flat caps, the line ends exactly at `from` and `to`.
+    fn draw_line(&mut self, from: PointF, to: PointF, color: Color, width: f32) {
+        let half = width / 2.0;
+        let area = RectF::new(
+            from.x.min(to.x) - half - 1.0,
+            from.y.min(to.y) - half - 1.0,
+            from.x.max(to.x) + half + 1.0,
+            from.y.max(to.y) + half + 1.0,
+        );
+
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+        let length = (dx * dx + dy * dy).sqrt();
+        if length == 0.0 {
+            return;
+        }
+        let (ux, uy) = (dx / length, dy / length);
+
+        let antialias = self.antialias;
+        self.fill(area, color, |x, y| {
+            let (px, py) = (x + 0.5 - from.x, y + 0.5 - from.y);
+            // Distance along the line and away from it
+            let along = px * ux + py * uy;
+            let across = (px * uy - py * ux).abs();
+            let overshoot = (-along).max(along - length);
+
+            if antialias {
+                let side = (half + 0.5 - across).clamp(0.0, 1.0);
+                let end = (0.5 - overshoot).clamp(0.0, 1.0);
+                side.min(end)
+            } else if across <= half && overshoot <= 0.0 {
+                1.0
+            } else {
+                0.0
+            }
+        });
+    }
+
+    fn fill_ellipse(&mut self, center: PointF, radius_x: f32, radius_y: f32, color: Color) {
+        if radius_x <= 0.0 || radius_y <= 0.0 {
+            return;
+        }
+
+        let area = RectF::new(
+            center.x - radius_x,
+            center.y - radius_y,
+            center.x + radius_x,
+            center.y + radius_y,
+        );
+        let inside = |x: f32, y: f32| {
+            let (nx, ny) = ((x - center.x) / radius_x, (y - center.y) / radius_y);
+            nx * nx + ny * ny <= 1.0
+        };
+
+        let antialias = self.antialias;
+        self.fill(area, color, |x, y| {
+            if !antialias {
+                return if inside(x + 0.5, y + 0.5) { 1.0 } else { 0.0 };
+            }
+
+            let step = 1.0 / ELLIPSE_SAMPLES as f32;
+            let mut hits = 0;
+            for sy in 0..ELLIPSE_SAMPLES {
+                for sx in 0..ELLIPSE_SAMPLES {
+                    let sample_x = x + (sx as f32 + 0.5) * step;
+                    let sample_y = y + (sy as f32 + 0.5) * step;
+                    if inside(sample_x, sample_y) {
+                        hits += 1;
+                    }
+                }
+            }
+            hits as f32 / (ELLIPSE_SAMPLES * ELLIPSE_SAMPLES) as f32
+        });
+    }
+
+    // Scaled with bilinear filtering, `rect` gets the whole image.
+    fn draw_image(&mut self, image: &Image, rect: RectF) {
+        if rect.width() <= 0.0 || rect.height() <= 0.0 {
+            return;
+        }
+
+        let target = self.target(rect);
+        let scale_x = image.width() as f32 / rect.width();
+        let scale_y = image.height() as f32 / rect.height();
+
+        for y in target.top..target.bottom {
+            for x in target.left..target.right {
+                let coverage = rect_coverage(&rect, x as f32, y as f32, self.antialias);
+                if coverage <= 0.0 {
+                    continue;
+                }
+
+                let src_x = (x as f32 + 0.5 - rect.left) * scale_x - 0.5;
+                let src_y = (y as f32 + 0.5 - rect.top) * scale_y - 0.5;
+                let color = sample(image, src_x, src_y);
+                self.pixmap.blend(x, y, color, coverage);
+            }
+        }
+    }
+
+    // There is no font rasterizer here, so text is drawn in the pixel font.
+    fn draw_text(&mut self, text: &str, origin: Point, color: Color) {
+        Font::new(self.dpi).draw(self, text, origin, color);
+    }
+
+    // Aliased like Direct2D's axis aligned clips.
+    fn push_clip(&mut self, rect: RectF) {
+        let clip = rect.round().intersection(&self.clip());
+        self.clips.push(clip);
+    }
+
+    fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+}
+
+impl<P: Present> RenderDevice for SoftwareCanvas<P> {
+    fn begin_draw(&mut self) {
+        self.clips.clear();
+        self.damage = Rect::default();
+    }
+
+    fn end_draw(&mut self) -> Result<(), DeviceError> {
+        let damage = std::mem::take(&mut self.damage);
+        if damage.is_empty() {
+            return Ok(());
+        }
+
+        self.presenter.present(&self.pixmap, damage)
+    }
+
+    // The content is gone afterwards, the platform repaints all of a resized window.
+    fn resize(&mut self, size: Size) -> Result<(), DeviceError> {
+        if self.pixmap.size() != size {
+            self.pixmap = Pixmap::new(size);
+        }
+        Ok(())
+    }
+
+    fn set_dpi(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+    }
+}
+
+// How much of the pixel at `x`, `y` is inside `rect`.
+fn rect_coverage(rect: &RectF, x: f32, y: f32, antialias: bool) -> f32 {
+    if !antialias {
+        let (cx, cy) = (x + 0.5, y + 0.5);
+        let inside = cx >= rect.left && cx < rect.right && cy >= rect.top && cy < rect.bottom;
+        return if inside { 1.0 } else { 0.0 };
+    }
+
+    let horizontal = (rect.right.min(x + 1.0) - rect.left.max(x)).clamp(0.0, 1.0);
+    let vertical = (rect.bottom.min(y + 1.0) - rect.top.max(y)).clamp(0.0, 1.0);
+    horizontal * vertical
+}
+
+fn sample(image: &Image, x: f32, y: f32) -> [f32; 4] {
+    let max_x = image.width() as i32 - 1;
+    let max_y = image.height() as i32 - 1;
+    let (x0, y0) = (x.floor(), y.floor());
+    let (fx, fy) = (x - x0, y - y0);
+
+    let texel = |x: i32, y: i32| {
+        let (x, y) = (x.clamp(0, max_x), y.clamp(0, max_y));
+        let offset = (y as usize * image.width() as usize + x as usize) * 4;
+        let rgba = &image.pixels()[offset..offset + 4];
+        [
+            rgba[3] as f32 / 255.0,
+            rgba[0] as f32 / 255.0,
+            rgba[1] as f32 / 255.0,
+            rgba[2] as f32 / 255.0,
+        ]
+    };
+
+    let (x0, y0) = (x0 as i32, y0 as i32);
+    let corners = [
+        (texel(x0, y0), (1.0 - fx) * (1.0 - fy)),
+        (texel(x0 + 1, y0), fx * (1.0 - fy)),
+        (texel(x0, y0 + 1), (1.0 - fx) * fy),
+        (texel(x0 + 1, y0 + 1), fx * fy),
+    ];
+
+    let mut out = [0.0; 4];
+    for (texel, weight) in corners {
+        for channel in 0..4 {
+            out[channel] += texel[channel] * weight;
+        }
+    }
+    out
+}
+
+// As [a, r, g, b] with the color channels multiplied by alpha.
+fn premultiplied(color: Color) -> [f32; 4] {
+    let a = color.a.clamp(0.0, 1.0);
+    [a, color.r * a, color.g * a, color.b * a]
+}
+
+fn unpack(pixel: u32) -> [f32; 4] {
+    [
+        (pixel >> 24) as f32 / 255.0,
+        ((pixel >> 16) & 0xff) as f32 / 255.0,
+        ((pixel >> 8) & 0xff) as f32 / 255.0,
+        (pixel & 0xff) as f32 / 255.0,
+    ]
+}
+
+fn pack(color: [f32; 4]) -> u32 {
+    let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+    channel(color[0]) << 24 | channel(color[1]) << 16 | channel(color[2]) << 8 | channel(color[3])
+}
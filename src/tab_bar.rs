@@ -1,203 +1,667 @@
 use std::collections::HashMap;
-use std::io;
-use std::ptr::*;
+use std::time::Duration;
 
-use winapi::shared::minwindef::*;
-use winapi::shared::windef::*;
-use winapi::um::wingdi::*;
-use winapi::um::winuser::*;
+use crate::animation::{Animated, Easing};
+use crate::button::{BaseButton, Button, Icon, ToggleButton};
+use crate::canvas::{Canvas, Color};
+use crate::dpi::Dpi;
+use crate::font::Font;
+use crate::geometry::{Point, Rect, Size};
+use crate::layout::{Align, Flex, FlexItem};
+use crate::log_debug;
+use crate::logging::Target;
+use crate::tab_model::{GroupId, TabEvent, TabGroup, TabId, TabModel};
+use crate::theme::Theme;
+use crate::units::{Dip, DipInsets};
+use crate::widget::{Context, InputRouter, Widget};
 
-use crate::button::{BaseButton, Button, ToggleButton};
-use crate::wutils::{Component, Error};
-use crate::{wnd_proc_gen, wpanic_ifeq, wpanic_ifnull, wutils};
+const TAB_MOVE_DURATION: Duration = Duration::from_millis(180);
+const TAB_WIDTH: Dip = Dip(10.0);
+// Pinned tabs only show their icon
+const PINNED_TAB_WIDTH: Dip = Dip(28.0);
+const TAB_HEIGHT: Dip = Dip(40.0);
+const TAB_GAP: Dip = Dip(2.0);
+const TAB_BAR_PADDING: DipInsets = DipInsets::new(4.0, 0.0, 0.0, 0.0);
+const ADD_BUTTON_WIDTH: Dip = Dip(40.0);
+// The label in front of a group's tabs, sized to the group's name, and the line under them
+// in the group's color
+const GROUP_CHIP_MIN_WIDTH: Dip = Dip(16.0);
+const GROUP_CHIP_PADDING: Dip = Dip(6.0);
+const GROUP_LINE_WIDTH: Dip = Dip(3.0);
+// How far a group's label has to be dragged before the group moves, short of that it's a
+// click that collapses or expands the group
+const GROUP_DRAG_THRESHOLD: Dip = Dip(4.0);
 
-const CLASS_NAME: &str = "TAB_BAR";
-const UM_ADDTAB: u32 = WM_USER + 1;
-const UM_CLICKTAB: u32 = WM_USER + 2;
+// A group's label being pressed, and maybe dragged.
+#[derive(Debug, Clone, Copy)]
+struct GroupDrag {
+    group: GroupId,
+    start_x: i32,
+    moved: bool,
+}
+
+// What goes in the tab bar, in order, before the add button.
+enum Slot {
+    Chip(GroupId),
+    Tab(TabId),
+}
+
+// New tabs and group labels grow from a collapsed rect at their slot.
+fn appearing(rect: Rect) -> Animated<Rect> {
+    let mut collapsed = rect;
+    collapsed.right = collapsed.left;
+    Animated::new(collapsed, TAB_MOVE_DURATION, Easing::EaseOut)
+}
 
 pub struct TabBar {
-    hwnd: HWND,
-    h_inst: HINSTANCE,
-    add_button: Option<Box<Button>>,
-    tab_count: u32,
-    tab_order: Vec<u32>,
-    tab_buttons: HashMap<u32, Box<ToggleButton>>,
+    bounds: Rect,
+    now: Duration,
+    dpi: Dpi,
+    add_button: Button,
+    model: TabModel,
+    // What happened to the tabs since the window last looked, see `take_events`
+    events: Vec<TabEvent>,
+    tab_buttons: HashMap<TabId, ToggleButton>,
+    tab_rects: HashMap<TabId, Animated<Rect>>,
+    add_button_rect: Animated<Rect>,
+    group_chips: HashMap<GroupId, Animated<Rect>>,
+    drag: Option<GroupDrag>,
+    theme: Theme,
+    router: InputRouter,
 }
 
-impl Drop for TabBar {
-    fn drop(&mut self) {
-        unsafe {
-            DestroyWindow(self.hwnd);
-        }
+impl Widget for TabBar {
+    fn bounds(&self) -> Rect {
+        self.bounds
     }
-}
 
-impl Component for TabBar {
-    fn hwnd(&self) -> HWND {
-        self.hwnd
-    }
-
-    fn register_class(h_inst: HINSTANCE) -> Result<(), Error> {
-        if let Ok(_) = wutils::component_registry().set_registered(h_inst as isize, CLASS_NAME) {
-            let class = WNDCLASSW {
-                style: CS_HREDRAW | CS_VREDRAW | CS_OWNDC,
-                lpfnWndProc: Some(wnd_proc),
-                cbClsExtra: 0,
-                cbWndExtra: 0,
-                hInstance: h_inst,
-                hIcon: null_mut(),
-                hCursor: unsafe { LoadCursorW(null_mut(), IDC_ARROW) },
-                hbrBackground: null_mut(),
-                lpszMenuName: null(),
-                lpszClassName: wutils::wide_string(CLASS_NAME).as_ptr(),
-            };
-
-            if unsafe { RegisterClassW(&class) } == 0 {
-                return Err(Error::WindowsInternal(io::Error::last_os_error()));
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+        self.reposition_components(false);
+    }
+
+    // Only the tabs themselves take input, the empty part of the bar still drags the window
+    fn hit_test(&self, x: i32, y: i32) -> bool {
+        self.add_button.hit_test(x, y)
+            || self.group_chip_at(x, y).is_some()
+            || self
+                .tab_buttons
+                .values()
+                .any(|button| button.hit_test(x, y))
+    }
+
+    fn paint(&self, canvas: &mut dyn Canvas) {
+        // Paint Background
+        canvas.fill_rect(self.bounds.into(), Color::from_rgb(0xff, 0xff, 0xff));
+
+        let font = Font::new(self.dpi);
+        let padding = self.dpi.px(GROUP_CHIP_PADDING).get();
+        for (group, rect) in &self.group_chips {
+            if let Some(info) = self.model.group(*group) {
+                let color = self.theme.group_color(info.color);
+                let color = if info.collapsed {
+                    color.with_alpha(0.5)
+                } else {
+                    color
+                };
+                let rect = rect.value(self.now);
+                canvas.fill_rect(rect.into(), color);
+
+                // Left out while the label grows, until the name fits
+                if font.text_width(&info.name) + 2 * padding <= rect.width() {
+                    let origin = Point::new(
+                        rect.left + padding,
+                        rect.top + (rect.height() - font.height()) / 2,
+                    );
+                    canvas.draw_text(&info.name, origin, self.theme.group_text);
+                }
+            }
+        }
+
+        let line = self.dpi.px(GROUP_LINE_WIDTH).get();
+        for idx in self.model.tabs() {
+            if let Some(button) = self.tab_buttons.get(idx) {
+                button.paint(canvas);
+
+                let info = self
+                    .model
+                    .group_of(*idx)
+                    .and_then(|group| self.model.group(group));
+                if let Some(info) = info.filter(|info| !info.collapsed) {
+                    let mut rect = button.bounds();
+                    rect.top = rect.bottom - line;
+                    canvas.fill_rect(rect.into(), self.theme.group_color(info.color));
+                }
             }
         }
 
-        Ok(())
+        self.add_button.paint(canvas);
     }
-}
 
-impl TabBar {
-    pub fn new(
-        x: i32,
-        y: i32,
-        width: i32,
-        height: i32,
-        parent_hwnd: HWND,
-        h_inst: HINSTANCE,
-    ) -> Result<Box<Self>, Error> {
-        Self::register_class(h_inst)?;
-
-        let me = Box::new(Self {
-            hwnd: null_mut(),
-            h_inst,
-            add_button: None,
-            tab_count: 0,
-            tab_order: Vec::with_capacity(100),
-            tab_buttons: HashMap::with_capacity(100),
-        });
-
-        let hwnd = unsafe {
-            CreateWindowExW(
-                0,
-                wutils::wide_string(CLASS_NAME).as_ptr(),
-                wutils::wide_string("").as_ptr(),
-                WS_CHILD | WS_OVERLAPPED | WS_VISIBLE,
-                x,
-                y,
-                width,
-                height,
-                parent_hwnd,
-                null_mut(),
-                h_inst,
-                me.as_ref() as *const _ as _,
-            )
-        };
+    fn on_dpi_changed(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+        self.add_button.on_dpi_changed(dpi);
+        for button in self.tab_buttons.values_mut() {
+            button.on_dpi_changed(dpi);
+        }
+        self.reposition_components(false);
+    }
 
-        if hwnd.is_null() {
-            return Err(Error::WindowsInternal(io::Error::last_os_error()));
+    fn on_mouse_move(&mut self, x: i32, y: i32, left_down: bool, ctx: &mut Context) {
+        self.now = ctx.now();
+        if let Some(drag) = &mut self.drag {
+            let threshold = self.dpi.px(GROUP_DRAG_THRESHOLD).get();
+            drag.moved |= left_down && (x - drag.start_x).abs() >= threshold;
+
+            if drag.moved {
+                let group = drag.group;
+                self.drag_group(group, x, ctx);
+            }
+            return;
         }
 
-        Ok(me)
+        self.route(|router, children| router.mouse_move(children, x, y, left_down, ctx));
     }
 
-    pub fn get_client_rect(&self) -> RECT {
-        wutils::get_client_rect(self.hwnd).unwrap()
+    fn on_mouse_leave(&mut self, ctx: &mut Context) {
+        self.now = ctx.now();
+        self.route(|router, children| router.mouse_leave(children, ctx));
     }
 
-    fn on_created(&mut self) {
-        let mut add_button = Button::new(self.hwnd, self.h_inst, 0, 0, 0, 0, None).unwrap();
+    fn on_mouse_down(&mut self, x: i32, y: i32, ctx: &mut Context) -> bool {
+        self.now = ctx.now();
+        if let Some(group) = self.group_chip_at(x, y) {
+            self.drag = Some(GroupDrag {
+                group,
+                start_x: x,
+                moved: false,
+            });
+            return true;
+        }
+
+        self.route(|router, children| router.mouse_down(children, x, y, ctx))
+    }
+
+    fn on_mouse_up(&mut self, x: i32, y: i32, ctx: &mut Context) {
+        self.now = ctx.now();
+        if let Some(drag) = self.drag.take() {
+            if !drag.moved {
+                let collapsed = self
+                    .model
+                    .group(drag.group)
+                    .is_some_and(|info| info.collapsed);
+                self.set_group_collapsed(drag.group, !collapsed, ctx);
+            }
+            return;
+        }
+
+        self.route(|router, children| router.mouse_up(children, x, y, ctx));
 
-        let hwnd = self.hwnd;
-        add_button.on_click(Box::new(move |_| {
-            wpanic_ifeq!(PostMessageW(hwnd, UM_ADDTAB, 0, 0), FALSE);
-        }));
+        let clicked = self
+            .model
+            .tabs()
+            .iter()
+            .copied()
+            .filter(|idx| {
+                self.tab_buttons
+                    .get_mut(idx)
+                    .is_some_and(|button| button.take_click())
+            })
+            .collect::<Vec<_>>();
 
-        self.add_button = Some(add_button);
+        for idx in clicked {
+            log_debug!(Target::TabBar, "tab {} clicked", idx);
+            self.activate(idx, ctx);
+        }
+
+        if self.add_button.take_click() {
+            self.add_item(ctx);
+        }
     }
 
-    fn reposition_component<T: Component>(&self, button_ref: Option<&Box<T>>, rect: RECT) {
-        if let Some(button) = button_ref {
-            wpanic_ifeq!(
-                MoveWindow(
-                    button.hwnd(),
-                    rect.left,
-                    rect.top,
-                    rect.right - rect.left,
-                    rect.bottom - rect.top,
-                    TRUE
-                ),
-                0
-            );
+    fn on_frame(&mut self, ctx: &mut Context) -> bool {
+        self.now = ctx.now();
+
+        let is_animating = self.is_animating();
+        if self.move_components() {
+            ctx.invalidate(self.bounds);
         }
+
+        let children_animating = self.route(|_, children| InputRouter::frame(children, ctx));
+
+        is_animating || children_animating
     }
+}
 
-    fn reposition_components(&self) {
-        let my_rect = self.get_client_rect();
+impl TabBar {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        let mut me = Self {
+            bounds: Rect::from_size(x, y, width, height),
+            now: Duration::ZERO,
+            dpi: Dpi::DEFAULT,
+            add_button: Button::new(0, 0, 0, 0, None),
+            model: TabModel::new(),
+            events: Vec::new(),
+            tab_buttons: HashMap::with_capacity(100),
+            tab_rects: HashMap::with_capacity(100),
+            add_button_rect: Animated::new(Rect::default(), TAB_MOVE_DURATION, Easing::EaseOut),
+            group_chips: HashMap::new(),
+            drag: None,
+            theme: Theme::default(),
+            router: InputRouter::new(),
+        };
 
-        let mut btn_rect = my_rect;
-        btn_rect.top = btn_rect.bottom - 40;
-        btn_rect.left = 4;
-        btn_rect.right = btn_rect.left + 10;
+        me.reposition_components(false);
+        me
+    }
 
-        for idx in &self.tab_order {
-            self.reposition_component(self.tab_buttons.get(idx), btn_rect);
-            btn_rect.left += 12;
-            btn_rect.right = btn_rect.left + 10;
-        }
+    // Hands the router the children in paint order: the tabs, then the add button.
+    fn route<R>(&mut self, f: impl FnOnce(&mut InputRouter, &mut [&mut dyn Widget]) -> R) -> R {
+        let order = self.model.tabs();
+        let mut tabs: Vec<(&TabId, &mut ToggleButton)> = self.tab_buttons.iter_mut().collect();
+        tabs.sort_by_key(|(idx, _)| order.iter().position(|other| other == *idx));
 
-        btn_rect.right += 30;
-        self.reposition_component(self.add_button.as_ref(), btn_rect);
+        let mut children: Vec<&mut dyn Widget> = tabs
+            .into_iter()
+            .map(|(_, button)| button as &mut dyn Widget)
+            .collect();
+        children.push(&mut self.add_button);
+
+        f(&mut self.router, &mut children)
     }
 
-    fn handle_message(&mut self, message: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT {
-        match message {
-            UM_ADDTAB => {
-                self.add_item();
+    // Computes where every tab should be. With `animate` the tabs slide there over the next
+    // frames, otherwise (e.g. when resizing) they are moved right away.
+    fn reposition_components(&mut self, animate: bool) {
+        let now = self.now;
+        let dpi = self.dpi;
+        let height = dpi.px(TAB_HEIGHT).get();
+
+        // The pinned tabs come first and keep their width, the others give way when the bar
+        // runs out of room. Every group starts with its label, the tabs of a collapsed group
+        // take no room.
+        let mut slots = Vec::with_capacity(self.model.tabs().len());
+        let mut items = Vec::with_capacity(self.model.tabs().len() + 1);
+        let mut last_group = None;
+        for (index, idx) in self.model.tabs().iter().enumerate() {
+            let group = self.model.group_of(*idx);
+            if let Some(group) = group.filter(|group| last_group != Some(*group)) {
+                slots.push(Slot::Chip(group));
+                items.push(FlexItem::fixed(self.group_chip_width(group)));
             }
-            UM_CLICKTAB => {
-                dbg!(wparam);
+            last_group = group;
+
+            if self.model.is_hidden(*idx) {
+                continue;
+            }
+            slots.push(Slot::Tab(*idx));
+            items.push(if index < self.model.pinned().len() {
+                FlexItem::fixed(dpi.px(PINNED_TAB_WIDTH).get())
+            } else {
+                FlexItem::fixed(dpi.px(TAB_WIDTH).get()).with_shrink(1.0)
+            });
+        }
+        items.push(FlexItem::fixed(dpi.px(ADD_BUTTON_WIDTH).get()));
+        let items = items
+            .into_iter()
+            .map(|item| item.with_size(Size::new(0, height)))
+            .collect::<Vec<_>>();
+
+        let rects = Flex::row()
+            .with_padding(TAB_BAR_PADDING.to_px(dpi))
+            .with_gap(dpi.px(TAB_GAP).get())
+            .with_align(Align::End)
+            .layout(self.bounds, &items);
+
+        // Hidden tabs shrink into their group's label
+        let mut targets = HashMap::with_capacity(slots.len());
+        let mut chips = HashMap::new();
+        for (slot, rect) in slots.iter().zip(&rects) {
+            match slot {
+                Slot::Chip(group) => {
+                    chips.insert(*group, *rect);
+                }
+                Slot::Tab(idx) => {
+                    targets.insert(*idx, *rect);
+                }
             }
-            WM_SIZE => {
-                self.reposition_components();
+        }
+        for idx in self.model.tabs() {
+            if let Some(chip) = self
+                .model
+                .group_of(*idx)
+                .and_then(|group| chips.get(&group))
+            {
+                targets.entry(*idx).or_insert_with(|| {
+                    let mut hidden = *chip;
+                    hidden.left = hidden.right;
+                    hidden
+                });
             }
-            WM_PAINT => {
-                let mut ps = PAINTSTRUCT::default();
-                let hdc = wpanic_ifnull!(BeginPaint(self.hwnd, &mut ps));
-
-                // Paint Background
-                let bg_color = RGB(0xff, 0xff, 0xff);
-                let bg_brush = wpanic_ifnull!(CreateSolidBrush(bg_color));
-                wpanic_ifeq!(FillRect(hdc, &ps.rcPaint, bg_brush), 0);
-                wpanic_ifeq!(DeleteObject(bg_brush as _), FALSE);
+        }
+
+        let place = |rect: &mut Animated<Rect>, target: Rect| {
+            if animate {
+                rect.animate_to(target, now);
+            } else {
+                rect.set(target);
             }
-            WM_CREATE => {
-                self.on_created();
-                self.reposition_components();
+        };
+        self.group_chips
+            .retain(|group, _| chips.contains_key(group));
+        for (group, target) in chips {
+            place(
+                self.group_chips
+                    .entry(group)
+                    .or_insert_with(|| appearing(target)),
+                target,
+            );
+        }
+        for (idx, target) in targets {
+            place(
+                self.tab_rects
+                    .entry(idx)
+                    .or_insert_with(|| appearing(target)),
+                target,
+            );
+        }
+
+        place(&mut self.add_button_rect, rects[rects.len() - 1]);
+
+        self.move_components();
+    }
+
+    // Returns whether anything actually moved.
+    fn move_components(&mut self) -> bool {
+        let now = self.now;
+        let mut moved = false;
+
+        for idx in self.model.tabs() {
+            if let (Some(rect), Some(button)) =
+                (self.tab_rects.get(idx), self.tab_buttons.get_mut(idx))
+            {
+                moved |= button.bounds() != rect.value(now);
+                button.set_bounds(rect.value(now));
             }
-            _ => {}
         }
-        unsafe { DefWindowProcW(self.hwnd, message, wparam, lparam) }
+
+        moved |= self.add_button.bounds() != self.add_button_rect.value(now);
+        self.add_button.set_bounds(self.add_button_rect.value(now));
+
+        // Group labels are painted straight from their rects
+        moved || self.group_chips.values().any(|rect| rect.is_animating(now))
+    }
+
+    fn is_animating(&self) -> bool {
+        let now = self.now;
+
+        self.add_button_rect.is_animating(now)
+            || self.tab_rects.values().any(|rect| rect.is_animating(now))
+            || self.group_chips.values().any(|rect| rect.is_animating(now))
     }
 
-    pub fn add_item(&mut self) {
-        let hwnd = self.hwnd;
-        let idx = self.tab_count;
-        self.tab_count += 1;
-        let mut button = ToggleButton::new(self.hwnd, self.h_inst, 0, 0, 0, 0, None, None).unwrap();
+    pub fn add_item(&mut self, ctx: &mut Context) -> TabId {
+        self.insert_item(self.model.tabs().len(), ctx)
+    }
 
-        button.on_click(Box::new(move |_| {
-            wpanic_ifeq!(PostMessageW(hwnd, UM_CLICKTAB, idx as usize, 0), FALSE);
-        }));
+    // Opens a tab at `index`, e.g. where a closed tab used to be, or at the end when there
+    // aren't that many tabs anymore.
+    pub fn insert_item(&mut self, index: usize, ctx: &mut Context) -> TabId {
+        self.now = ctx.now();
+        let (idx, events) = self.model.insert(index);
 
-        self.tab_order.push(idx);
+        let mut button = ToggleButton::new(0, 0, 0, 0, None, None);
+        button.on_dpi_changed(self.dpi);
         self.tab_buttons.insert(idx, button);
-        self.reposition_components();
+
+        self.rearrange(events, ctx);
+        idx
+    }
+
+    pub fn close_tab(&mut self, idx: TabId, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = self.model.close(idx);
+        if events.is_empty() {
+            return;
+        }
+
+        self.tab_buttons.remove(&idx);
+        self.tab_rects.remove(&idx);
+        self.rearrange(events, ctx);
+    }
+
+    // Moves the tab to `index`, see `TabModel::move_tab`.
+    pub fn move_tab(&mut self, idx: TabId, index: usize, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = self.model.move_tab(idx, index);
+        self.rearrange(events, ctx);
+    }
+
+    pub fn set_tab_pinned(&mut self, idx: TabId, pinned: bool, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = if pinned {
+            self.model.pin(idx)
+        } else {
+            self.model.unpin(idx)
+        };
+        self.rearrange(events, ctx);
+    }
+
+    // Queues the events and slides the tabs to where they go now.
+    fn rearrange(&mut self, events: Vec<TabEvent>, ctx: &mut Context) {
+        if events.is_empty() {
+            return;
+        }
+
+        self.push_events(events, ctx);
+        self.relayout(ctx);
+    }
+
+    fn relayout(&mut self, ctx: &mut Context) {
+        self.reposition_components(true);
+
+        if self.is_animating() {
+            ctx.request_frames();
+        }
+        ctx.invalidate(self.bounds);
+    }
+
+    pub fn close_all(&mut self, ctx: &mut Context) {
+        let events = self.model.close_all();
+        self.tab_buttons.clear();
+        self.tab_rects.clear();
+        self.drag = None;
+        self.push_events(events, ctx);
+        self.reposition_components(false);
+        ctx.invalidate(self.bounds);
+    }
+
+    pub fn activate(&mut self, idx: TabId, ctx: &mut Context) {
+        let events = self.model.activate(idx);
+        self.push_events(events, ctx);
+    }
+
+    pub fn model(&self) -> &TabModel {
+        &self.model
+    }
+
+    pub fn tabs(&self) -> &[TabId] {
+        self.model.tabs()
+    }
+
+    pub fn tab_index(&self, idx: TabId) -> Option<usize> {
+        self.model.index_of(idx)
+    }
+
+    pub fn is_tab_pinned(&self, idx: TabId) -> bool {
+        self.model.is_pinned(idx)
+    }
+
+    pub fn other_tabs(&self, idx: TabId) -> Vec<TabId> {
+        self.model.others(idx)
+    }
+
+    // Groups the tabs, see `TabModel::create_group`.
+    pub fn create_group(
+        &mut self,
+        tabs: &[TabId],
+        name: &str,
+        ctx: &mut Context,
+    ) -> Option<GroupId> {
+        self.now = ctx.now();
+        let (group, events) = self.model.create_group(tabs, name)?;
+        self.rearrange(events, ctx);
+        Some(group)
+    }
+
+    pub fn add_to_group(&mut self, idx: TabId, group: GroupId, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = self.model.add_to_group(idx, group);
+        self.rearrange(events, ctx);
+    }
+
+    pub fn remove_from_group(&mut self, idx: TabId, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = self.model.remove_from_group(idx);
+        self.rearrange(events, ctx);
+    }
+
+    pub fn ungroup(&mut self, group: GroupId, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = self.model.ungroup(group);
+        self.rearrange(events, ctx);
+    }
+
+    pub fn move_group(&mut self, group: GroupId, index: usize, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = self.model.move_group(group, index);
+        self.rearrange(events, ctx);
+    }
+
+    // The label takes the new name's width, the tabs after it make room.
+    pub fn rename_group(&mut self, group: GroupId, name: &str, ctx: &mut Context) {
+        self.now = ctx.now();
+        if self.model.rename_group(group, name) {
+            self.relayout(ctx);
+        }
+    }
+
+    pub fn set_group_color(&mut self, group: GroupId, color: usize, ctx: &mut Context) {
+        if self.model.set_group_color(group, color) {
+            ctx.invalidate(self.bounds);
+        }
     }
-}
 
-wnd_proc_gen!(TabBar, wnd_proc);
+    pub fn set_group_collapsed(&mut self, group: GroupId, collapsed: bool, ctx: &mut Context) {
+        self.now = ctx.now();
+        let events = self.model.set_group_collapsed(group, collapsed);
+        self.push_events(events, ctx);
+        self.relayout(ctx);
+    }
+
+    // Moves the dragged group to where the pointer is among the other tabs.
+    fn drag_group(&mut self, group: GroupId, x: i32, ctx: &mut Context) {
+        let index = self
+            .model
+            .tabs()
+            .iter()
+            .filter(|idx| self.model.group_of(**idx) != Some(group))
+            .filter_map(|idx| self.tab_buttons.get(idx))
+            .filter(|button| {
+                let bounds = button.bounds();
+                (bounds.left + bounds.right) / 2 < x
+            })
+            .count();
+
+        self.move_group(group, index, ctx);
+    }
+
+    pub fn group_of(&self, idx: TabId) -> Option<GroupId> {
+        self.model.group_of(idx)
+    }
+
+    pub fn group(&self, group: GroupId) -> Option<&TabGroup> {
+        self.model.group(group)
+    }
+
+    pub fn groups(&self) -> Vec<GroupId> {
+        self.model.groups()
+    }
+
+    // Wide enough for the group's name, but never narrower than a short one would be.
+    fn group_chip_width(&self, group: GroupId) -> i32 {
+        let name = self
+            .model
+            .group(group)
+            .map_or(0, |info| Font::new(self.dpi).text_width(&info.name));
+        let padding = self.dpi.px(GROUP_CHIP_PADDING).get();
+        (name + 2 * padding).max(self.dpi.px(GROUP_CHIP_MIN_WIDTH).get())
+    }
+
+    // The group whose label is at the point.
+    pub fn group_chip_at(&self, x: i32, y: i32) -> Option<GroupId> {
+        self.group_chips
+            .iter()
+            .find(|(_, rect)| rect.value(self.now).contains_point(x, y))
+            .map(|(group, _)| *group)
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme, ctx: &mut Context) {
+        self.theme = theme;
+        ctx.invalidate(self.bounds);
+    }
+
+    pub fn tab_at(&self, x: i32, y: i32) -> Option<TabId> {
+        self.model.tabs().iter().copied().find(|idx| {
+            self.tab_buttons
+                .get(idx)
+                .is_some_and(|button| button.hit_test(x, y))
+        })
+    }
+
+    pub fn tab_bounds(&self, idx: TabId) -> Option<Rect> {
+        self.tab_buttons.get(&idx).map(|button| button.bounds())
+    }
+
+    pub fn active_tab(&self) -> Option<TabId> {
+        self.model.active()
+    }
+
+    pub fn tab_title(&self, idx: TabId) -> &str {
+        self.model.title(idx)
+    }
+
+    pub fn set_tab_title(&mut self, idx: TabId, title: &str) {
+        self.model.set_title(idx, title);
+    }
+
+    // Hands over what happened to the tabs since the last call, oldest first.
+    pub fn take_events(&mut self) -> Vec<TabEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    // Queues the events for the window and keeps only the active tab's button toggled. A
+    // click on the active tab would otherwise untoggle it.
+    fn push_events(&mut self, events: Vec<TabEvent>, ctx: &mut Context) {
+        let active = self.model.active();
+        for (idx, button) in &mut self.tab_buttons {
+            button.set_toggled(active == Some(*idx), ctx);
+        }
+
+        self.events.extend(events);
+    }
+
+    pub fn tab_icon(&self, idx: TabId) -> Option<&Icon> {
+        self.tab_buttons.get(&idx).and_then(|button| button.icon())
+    }
+
+    pub fn set_tab_icon(&mut self, idx: TabId, icon: Option<Icon>, ctx: &mut Context) {
+        if let Some(button) = self.tab_buttons.get_mut(&idx) {
+            button.set_icon(icon, ctx);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use crate::canvas::Canvas;
+use crate::close::CloseReply;
+use crate::dpi::Dpi;
+use crate::geometry::Rect;
+use crate::widget::{Context, Widget};
+
+// What a tab shows below the title bar. Content of inactive tabs stays alive while hidden,
+// the hooks let it pause whatever it is doing in the meantime.
+pub trait TabContent: Widget {
+    fn on_activated(&mut self, _ctx: &mut Context) {}
+
+    fn on_deactivated(&mut self, _ctx: &mut Context) {}
+
+    // Asks whether the tab may close. Content with unsaved work can keep `reply` and answer
+    // once the user decided, the default closes right away.
+    fn on_close_requested(&mut self, reply: CloseReply, _ctx: &mut Context) {
+        reply.close();
+    }
+
+    // The tab is closing, the content still gets painted until `on_closed`
+    fn on_closing(&mut self, _ctx: &mut Context) {}
+
+    // Last call before the content is dropped
+    fn on_closed(&mut self) {}
+
+    // Kept in the session, the content gets it back when the tab is restored
+    fn save_state(&self) -> String {
+        String::new()
+    }
+}
+
+// Puts a tab's content into the widget tree, which only knows about plain widgets, and
+// keeps the hooks reachable through a downcast.
+pub struct TabPanel {
+    content: Box<dyn TabContent>,
+}
+
+impl TabPanel {
+    pub fn new(content: Box<dyn TabContent>) -> Self {
+        Self { content }
+    }
+
+    pub fn content(&self) -> &dyn TabContent {
+        self.content.as_ref()
+    }
+
+    pub fn content_mut(&mut self) -> &mut dyn TabContent {
+        self.content.as_mut()
+    }
+}
+
+impl Widget for TabPanel {
+    fn bounds(&self) -> Rect {
+        self.content.bounds()
+    }
+
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.content.set_bounds(bounds);
+    }
+
+    fn paint(&self, canvas: &mut dyn Canvas) {
+        self.content.paint(canvas);
+    }
+
+    fn hit_test(&self, x: i32, y: i32) -> bool {
+        self.content.hit_test(x, y)
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.content.is_focusable()
+    }
+
+    fn on_focus_changed(&mut self, focused: bool, ctx: &mut Context) {
+        self.content.on_focus_changed(focused, ctx);
+    }
+
+    fn on_dpi_changed(&mut self, dpi: Dpi) {
+        self.content.on_dpi_changed(dpi);
+    }
+
+    fn on_mouse_move(&mut self, x: i32, y: i32, left_down: bool, ctx: &mut Context) {
+        self.content.on_mouse_move(x, y, left_down, ctx);
+    }
+
+    fn on_mouse_leave(&mut self, ctx: &mut Context) {
+        self.content.on_mouse_leave(ctx);
+    }
+
+    fn on_mouse_down(&mut self, x: i32, y: i32, ctx: &mut Context) -> bool {
+        self.content.on_mouse_down(x, y, ctx)
+    }
+
+    fn on_mouse_up(&mut self, x: i32, y: i32, ctx: &mut Context) {
+        self.content.on_mouse_up(x, y, ctx);
+    }
+
+    fn on_frame(&mut self, ctx: &mut Context) -> bool {
+        self.content.on_frame(ctx)
+    }
+}
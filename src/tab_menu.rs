@@ -0,0 +1,59 @@
+use crate::menu::{MenuEntry, MenuItem};
+use crate::tab_model::{TabId, TabModel};
+
+// What the menu of a tab can do to it. `App` items are the app's own, see
+// `Window::set_tab_menu_items`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TabAction {
+    Close,
+    CloseOthers,
+    CloseToTheRight,
+    Duplicate,
+    Pin,
+    Unpin,
+    Rename,
+    MoveToNewWindow,
+    ReopenClosed,
+    App(u32),
+}
+
+// The menu a right-click on `tab` shows, with only what applies to the tab enabled.
+// `can_reopen` tells whether any closed tab is left to reopen.
+pub fn tab_menu(model: &TabModel, tab: TabId, can_reopen: bool) -> Vec<MenuEntry<TabAction>> {
+    let item = |action, label| MenuEntry::Item(MenuItem::new(action, label));
+    let enabled = |action, label, enabled| {
+        MenuEntry::Item(MenuItem::new(action, label).with_enabled(enabled))
+    };
+
+    let pin = if model.is_pinned(tab) {
+        item(TabAction::Unpin, "Un&pin")
+    } else {
+        item(TabAction::Pin, "&Pin")
+    };
+
+    vec![
+        item(TabAction::Close, "&Close"),
+        enabled(
+            TabAction::CloseOthers,
+            "Close &others",
+            !model.others(tab).is_empty(),
+        ),
+        enabled(
+            TabAction::CloseToTheRight,
+            "Close to the &right",
+            !model.to_the_right(tab).is_empty(),
+        ),
+        MenuEntry::Separator,
+        item(TabAction::Duplicate, "&Duplicate"),
+        pin,
+        item(TabAction::Rename, "Re&name"),
+        // The only tab would leave an empty window behind
+        enabled(
+            TabAction::MoveToNewWindow,
+            "&Move to new window",
+            model.tabs().len() > 1,
+        ),
+        MenuEntry::Separator,
+        enabled(TabAction::ReopenClosed, "Reopen closed &tab", can_reopen),
+    ]
+}
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+// Which tabs exist, in what order, and which one is active. Every change returns what
+// happened to the tabs in the order their content should hear about it, so the window can
+// replay it against the content panels. Kept free of widgets so it can be tested on its own.
+//
+// Pinned tabs always come first. Moving a tab keeps it on its side of them, only pinning and
+// unpinning crosses over.
+//
+// The other tabs can be put in groups. A group's tabs always sit next to each other: a tab
+// dropped in the middle of a group joins it, one dragged away from its group leaves it, and
+// a group moves as a whole. Pinned tabs are never in a group, and a group is gone once its
+// last tab is.
+
+pub type TabId = u32;
+pub type GroupId = u32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TabEvent {
+    // A tab was added, its content gets created hidden
+    Opened(TabId),
+    Activated(TabId),
+    Deactivated(TabId),
+    // The tab is about to go, its content is still around
+    Closing(TabId),
+    // The tab is gone, its content gets dropped
+    Closed(TabId),
+    // The tab went somewhere else in the tab order
+    Moved(TabId),
+    // The tab joined the pinned tabs, or left them again
+    Pinned(TabId),
+    Unpinned(TabId),
+    // The tab joined a group, maybe leaving another one, or left its group for none
+    Grouped(TabId),
+    Ungrouped(TabId),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TabGroup {
+    pub name: String,
+    // Index into the theme's group colors, see `Theme::group_color`
+    pub color: usize,
+    // Only the group's label shows in the tab bar
+    pub collapsed: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct TabModel {
+    tabs: Vec<TabId>,
+    titles: HashMap<TabId, String>,
+    active: Option<TabId>,
+    next_id: TabId,
+    // How many of the first tabs are pinned
+    pinned: usize,
+    groups: HashMap<GroupId, TabGroup>,
+    tab_groups: HashMap<TabId, GroupId>,
+    next_group_id: GroupId,
+}
+
+impl TabModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tabs(&self) -> &[TabId] {
+        &self.tabs
+    }
+
+    pub fn active(&self) -> Option<TabId> {
+        self.active
+    }
+
+    pub fn contains(&self, id: TabId) -> bool {
+        self.tabs.contains(&id)
+    }
+
+    pub fn index_of(&self, id: TabId) -> Option<usize> {
+        self.tabs.iter().position(|tab| *tab == id)
+    }
+
+    pub fn pinned(&self) -> &[TabId] {
+        &self.tabs[..self.pinned]
+    }
+
+    pub fn is_pinned(&self, id: TabId) -> bool {
+        self.index_of(id).is_some_and(|index| index < self.pinned)
+    }
+
+    // What "close other tabs" closes when asked on `id`: everything but it and the pinned
+    // tabs.
+    pub fn others(&self, id: TabId) -> Vec<TabId> {
+        self.tabs[self.pinned..]
+            .iter()
+            .copied()
+            .filter(|tab| *tab != id)
+            .collect()
+    }
+
+    pub fn group_of(&self, id: TabId) -> Option<GroupId> {
+        self.tab_groups.get(&id).copied()
+    }
+
+    pub fn group(&self, group: GroupId) -> Option<&TabGroup> {
+        self.groups.get(&group)
+    }
+
+    // The groups in the order they appear in.
+    pub fn groups(&self) -> Vec<GroupId> {
+        let mut groups = Vec::with_capacity(self.groups.len());
+        for tab in &self.tabs {
+            if let Some(group) = self.group_of(*tab) {
+                if groups.last() != Some(&group) {
+                    groups.push(group);
+                }
+            }
+        }
+        groups
+    }
+
+    pub fn group_tabs(&self, group: GroupId) -> &[TabId] {
+        match self.group_range(group) {
+            Some(range) => &self.tabs[range],
+            None => &[],
+        }
+    }
+
+    // Whether the tab is in a collapsed group, so only the group's label shows.
+    pub fn is_hidden(&self, id: TabId) -> bool {
+        self.group_of(id)
+            .and_then(|group| self.group(group))
+            .is_some_and(|group| group.collapsed)
+    }
+
+    // What "close tabs to the right" closes when asked on `id`, which spares the pinned tabs
+    // as well.
+    pub fn to_the_right(&self, id: TabId) -> Vec<TabId> {
+        match self.index_of(id) {
+            Some(index) => self.tabs[(index + 1).max(self.pinned)..].to_vec(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn title(&self, id: TabId) -> &str {
+        self.titles.get(&id).map_or("", String::as_str)
+    }
+
+    pub fn set_title(&mut self, id: TabId, title: &str) {
+        if self.contains(id) {
+            self.titles.insert(id, String::from(title));
+        }
+    }
+
+    // Adds a tab at the end and activates it.
+    pub fn add(&mut self) -> (TabId, Vec<TabEvent>) {
+        self.insert(self.tabs.len())
+    }
+
+    // Adds a tab at `index`, or at the end when there aren't that many tabs, and activates it.
+    // New tabs aren't pinned, so they go after the pinned ones. One opened in the middle of a
+    // group joins it.
+    pub fn insert(&mut self, index: usize) -> (TabId, Vec<TabEvent>) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let index = index.clamp(self.pinned, self.tabs.len());
+        let group = self.group_around(index);
+        self.tabs.insert(index, id);
+
+        let mut events = vec![TabEvent::Opened(id)];
+        self.set_group(id, group, &mut events);
+        events.extend(self.activate(id));
+        (id, events)
+    }
+
+    // Activating the active tab or one that doesn't exist changes nothing. A tab in a
+    // collapsed group expands it.
+    pub fn activate(&mut self, id: TabId) -> Vec<TabEvent> {
+        if !self.contains(id) || self.active == Some(id) {
+            return Vec::new();
+        }
+
+        if let Some(group) = self
+            .group_of(id)
+            .and_then(|group| self.groups.get_mut(&group))
+        {
+            group.collapsed = false;
+        }
+
+        let mut events = Vec::with_capacity(2);
+        if let Some(previous) = self.active.replace(id) {
+            events.push(TabEvent::Deactivated(previous));
+        }
+        events.push(TabEvent::Activated(id));
+        events
+    }
+
+    // Closing the active tab hands activation to its right neighbour, or the left one for the
+    // last tab, before the closed tab's content is dropped.
+    pub fn close(&mut self, id: TabId) -> Vec<TabEvent> {
+        let index = match self.index_of(id) {
+            Some(index) => index,
+            None => return Vec::new(),
+        };
+
+        let mut events = vec![TabEvent::Closing(id)];
+        self.tabs.remove(index);
+        self.titles.remove(&id);
+        if index < self.pinned {
+            self.pinned -= 1;
+        }
+        if let Some(group) = self.tab_groups.remove(&id) {
+            self.forget_if_empty(group);
+        }
+
+        if self.active == Some(id) {
+            self.active = None;
+            events.push(TabEvent::Deactivated(id));
+
+            let next = self.tabs.get(index).or_else(|| self.tabs.last()).copied();
+            if let Some(next) = next {
+                events.extend(self.activate(next));
+            }
+        }
+
+        events.push(TabEvent::Closed(id));
+        events
+    }
+
+    // Closes every tab from the left. Unlike closing them one by one, no other tab gets
+    // activated just to be closed right after.
+    pub fn close_all(&mut self) -> Vec<TabEvent> {
+        let mut events = Vec::with_capacity(self.tabs.len() * 2 + 1);
+        self.titles.clear();
+        self.pinned = 0;
+        self.groups.clear();
+        self.tab_groups.clear();
+
+        for id in std::mem::take(&mut self.tabs) {
+            events.push(TabEvent::Closing(id));
+            if self.active == Some(id) {
+                self.active = None;
+                events.push(TabEvent::Deactivated(id));
+            }
+            events.push(TabEvent::Closed(id));
+        }
+        events
+    }
+
+    // Moves the tab to `index`, as far as it can go without leaving the pinned or the other
+    // tabs. Where it lands decides its group: the one it landed in the middle of, its own if
+    // it is still next to it, or none.
+    pub fn move_tab(&mut self, id: TabId, index: usize) -> Vec<TabEvent> {
+        let from = match self.index_of(id) {
+            Some(from) => from,
+            None => return Vec::new(),
+        };
+
+        let to = if from < self.pinned {
+            index.min(self.pinned - 1)
+        } else {
+            index.clamp(self.pinned, self.tabs.len() - 1)
+        };
+        if to == from {
+            return Vec::new();
+        }
+
+        self.tabs.remove(from);
+        self.tabs.insert(to, id);
+
+        let mut events = vec![TabEvent::Moved(id)];
+        if to >= self.pinned {
+            let own = self.group_of(id);
+            let left = to.checked_sub(1).and_then(|index| self.group_at(index));
+            let right = self.group_at(to + 1);
+
+            let group = if left.is_some() && left == right {
+                left
+            } else if own.is_some() && (left == own || right == own) {
+                own
+            } else {
+                None
+            };
+            self.set_group(id, group, &mut events);
+        }
+        events
+    }
+
+    // Pins the tab at the end of the pinned tabs, taking it out of its group.
+    pub fn pin(&mut self, id: TabId) -> Vec<TabEvent> {
+        let from = match self.index_of(id) {
+            Some(from) if from >= self.pinned => from,
+            _ => return Vec::new(),
+        };
+
+        let mut events = Vec::with_capacity(2);
+        self.set_group(id, None, &mut events);
+
+        self.tabs.remove(from);
+        self.tabs.insert(self.pinned, id);
+        self.pinned += 1;
+        events.push(TabEvent::Pinned(id));
+        events
+    }
+
+    // Unpins the tab, it goes first among the other tabs.
+    pub fn unpin(&mut self, id: TabId) -> Vec<TabEvent> {
+        let from = match self.index_of(id) {
+            Some(from) if from < self.pinned => from,
+            _ => return Vec::new(),
+        };
+
+        self.pinned -= 1;
+        self.tabs.remove(from);
+        self.tabs.insert(self.pinned, id);
+        vec![TabEvent::Unpinned(id)]
+    }
+
+    // Puts the tabs in a new group named `name`, gathered where the first of them is. Pinned
+    // tabs are left out, and `None` means there was no tab left to group.
+    pub fn create_group(&mut self, tabs: &[TabId], name: &str) -> Option<(GroupId, Vec<TabEvent>)> {
+        let members = self.tabs[self.pinned..]
+            .iter()
+            .copied()
+            .filter(|tab| tabs.contains(tab))
+            .collect::<Vec<_>>();
+        let first = self.index_of(*members.first()?)?;
+        let before = members
+            .iter()
+            .filter_map(|tab| self.index_of(*tab))
+            .collect::<Vec<_>>();
+
+        let id = self.next_group_id;
+        self.next_group_id += 1;
+        self.groups.insert(
+            id,
+            TabGroup {
+                name: String::from(name),
+                // New groups go through the palette in turn
+                color: id as usize,
+                collapsed: false,
+            },
+        );
+
+        // Nothing before the first member moves, so it is where the group goes, unless that
+        // is now the middle of another group
+        self.tabs.retain(|tab| !members.contains(tab));
+        let at = match self
+            .group_around(first)
+            .and_then(|other| self.group_range(other))
+        {
+            Some(other) => other.end,
+            None => first,
+        };
+        self.tabs.splice(at..at, members.iter().copied());
+
+        let mut events = members
+            .iter()
+            .zip(before)
+            .filter(|(tab, from)| self.index_of(**tab) != Some(*from))
+            .map(|(tab, _)| TabEvent::Moved(*tab))
+            .collect::<Vec<_>>();
+        for tab in &members {
+            self.set_group(*tab, Some(id), &mut events);
+        }
+        Some((id, events))
+    }
+
+    // Moves the tab to the end of `group` and makes it a member. Pinned tabs have to be
+    // unpinned first.
+    pub fn add_to_group(&mut self, id: TabId, group: GroupId) -> Vec<TabEvent> {
+        let from = match self.index_of(id) {
+            Some(from) if from >= self.pinned => from,
+            _ => return Vec::new(),
+        };
+        if !self.groups.contains_key(&group) || self.group_of(id) == Some(group) {
+            return Vec::new();
+        }
+
+        self.tabs.remove(from);
+        let to = self.group_range(group).map_or(from, |range| range.end);
+        self.tabs.insert(to, id);
+
+        let mut events = Vec::with_capacity(2);
+        if to != from {
+            events.push(TabEvent::Moved(id));
+        }
+        self.set_group(id, Some(group), &mut events);
+        if self.active == Some(id) {
+            self.set_group_collapsed(group, false);
+        }
+        events
+    }
+
+    // Takes the tab out of its group. One from the middle of the group goes right after it.
+    pub fn remove_from_group(&mut self, id: TabId) -> Vec<TabEvent> {
+        let (group, from) = match (self.group_of(id), self.index_of(id)) {
+            (Some(group), Some(from)) => (group, from),
+            _ => return Vec::new(),
+        };
+        let range = self.group_range(group).unwrap_or(from..from + 1);
+
+        let mut events = Vec::with_capacity(2);
+        if from != range.start && from + 1 != range.end {
+            self.tabs.remove(from);
+            self.tabs.insert(range.end - 1, id);
+            events.push(TabEvent::Moved(id));
+        }
+        self.set_group(id, None, &mut events);
+        events
+    }
+
+    // Drops the group, its tabs stay where they are.
+    pub fn ungroup(&mut self, group: GroupId) -> Vec<TabEvent> {
+        let mut events = Vec::new();
+        for tab in self.group_tabs(group).to_vec() {
+            self.set_group(tab, None, &mut events);
+        }
+        events
+    }
+
+    // Moves the whole group so its first tab ends up at `index`, or as close as it gets
+    // without landing among the pinned tabs or in the middle of another group.
+    pub fn move_group(&mut self, group: GroupId, index: usize) -> Vec<TabEvent> {
+        let range = match self.group_range(group) {
+            Some(range) => range,
+            None => return Vec::new(),
+        };
+        let from = range.start;
+        let members = self.tabs.drain(range).collect::<Vec<_>>();
+
+        let at = index.clamp(self.pinned, self.tabs.len());
+        let at = match self
+            .group_around(at)
+            .and_then(|other| self.group_range(other))
+        {
+            // Whichever end of the other group is closer
+            Some(other) if at - other.start <= other.end - at => other.start,
+            Some(other) => other.end,
+            None => at,
+        };
+        self.tabs.splice(at..at, members.iter().copied());
+
+        if at == from {
+            return Vec::new();
+        }
+        members.into_iter().map(TabEvent::Moved).collect()
+    }
+
+    pub fn rename_group(&mut self, group: GroupId, name: &str) -> bool {
+        match self.groups.get_mut(&group) {
+            Some(group) => {
+                group.name = String::from(name);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn set_group_color(&mut self, group: GroupId, color: usize) -> bool {
+        match self.groups.get_mut(&group) {
+            Some(group) => {
+                group.color = color;
+                true
+            }
+            None => false,
+        }
+    }
+
+    // Collapsing the group of the active tab activates the closest tab that is still shown.
+    // Without one the group stays expanded.
+    pub fn set_group_collapsed(&mut self, group: GroupId, collapsed: bool) -> Vec<TabEvent> {
+        let range = match self.group_range(group) {
+            Some(range) => range,
+            None => return Vec::new(),
+        };
+
+        let mut events = Vec::new();
+        if collapsed
+            && self
+                .active
+                .is_some_and(|active| self.group_of(active) == Some(group))
+        {
+            let shown = |tab: &&TabId| !self.is_hidden(**tab);
+            let next = self.tabs[range.end..]
+                .iter()
+                .find(shown)
+                .or_else(|| self.tabs[..range.start].iter().rev().find(shown))
+                .copied();
+
+            match next {
+                Some(next) => events = self.activate(next),
+                None => return Vec::new(),
+            }
+        }
+
+        if let Some(group) = self.groups.get_mut(&group) {
+            group.collapsed = collapsed;
+        }
+        events
+    }
+
+    fn group_at(&self, index: usize) -> Option<GroupId> {
+        self.tabs.get(index).and_then(|tab| self.group_of(*tab))
+    }
+
+    // The group a tab put at `index` would land in the middle of.
+    fn group_around(&self, index: usize) -> Option<GroupId> {
+        let left = index
+            .checked_sub(1)
+            .and_then(|index| self.group_at(index))?;
+        (self.group_at(index) == Some(left)).then_some(left)
+    }
+
+    fn group_range(&self, group: GroupId) -> Option<Range<usize>> {
+        let start = self
+            .tabs
+            .iter()
+            .position(|tab| self.group_of(*tab) == Some(group))?;
+        let len = self.tabs[start..]
+            .iter()
+            .take_while(|tab| self.group_of(**tab) == Some(group))
+            .count();
+        Some(start..start + len)
+    }
+
+    fn set_group(&mut self, id: TabId, group: Option<GroupId>, events: &mut Vec<TabEvent>) {
+        let old = match group {
+            Some(group) => self.tab_groups.insert(id, group),
+            None => self.tab_groups.remove(&id),
+        };
+        if old == group {
+            return;
+        }
+
+        events.push(match group {
+            Some(_) => TabEvent::Grouped(id),
+            None => TabEvent::Ungrouped(id),
+        });
+        if let Some(old) = old {
+            self.forget_if_empty(old);
+        }
+    }
+
+    fn forget_if_empty(&mut self, group: GroupId) {
+        if !self.tab_groups.values().any(|other| *other == group) {
+            self.groups.remove(&group);
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use crate::canvas::Color;
+
+// Colors of the parts of the window we draw ourselves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    // What tab groups get to pick from, see `TabGroup::color`
+    pub group_colors: Vec<Color>,
+    // The names on the groups' labels, over any of the group colors
+    pub group_text: Color,
+    pub menu: MenuColors,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MenuColors {
+    pub background: Color,
+    pub border: Color,
+    pub text: Color,
+    pub disabled_text: Color,
+    // Behind the entry the mouse or the keyboard is on, and its text
+    pub highlight: Color,
+    pub highlight_text: Color,
+    pub separator: Color,
+}
+
+impl Default for MenuColors {
+    fn default() -> Self {
+        Self {
+            background: Color::from_rgb(0xf9, 0xf9, 0xf9),
+            border: Color::from_rgb(0xa0, 0xa0, 0xa0),
+            text: Color::from_rgb(0x21, 0x21, 0x21),
+            disabled_text: Color::from_rgb(0xa0, 0xa0, 0xa0),
+            highlight: Color::from_rgb(150, 200, 180),
+            highlight_text: Color::from_rgb(0x21, 0x21, 0x21),
+            separator: Color::from_rgb(0xd7, 0xd7, 0xd7),
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            group_colors: vec![
+                Color::from_rgb(0x5f, 0x63, 0x68), // grey
+                Color::from_rgb(0x1a, 0x73, 0xe8), // blue
+                Color::from_rgb(0xd9, 0x30, 0x25), // red
+                Color::from_rgb(0xf9, 0xab, 0x00), // yellow
+                Color::from_rgb(0x1e, 0x8e, 0x3e), // green
+                Color::from_rgb(0xd0, 0x18, 0x84), // pink
+                Color::from_rgb(0xa1, 0x42, 0xf4), // purple
+                Color::from_rgb(0x00, 0x7b, 0x83), // cyan
+                Color::from_rgb(0xfa, 0x90, 0x3e), // orange
+            ],
+            group_text: Color::from_rgb(0xff, 0xff, 0xff),
+            menu: MenuColors::default(),
+        }
+    }
+}
+
+impl Theme {
+    // Colors are picked by index, so a palette with fewer colors wraps around.
+    pub fn group_color(&self, index: usize) -> Color {
+        match self.group_colors.len() {
+            0 => Color::from_rgb(0x80, 0x80, 0x80),
+            len => self.group_colors[index % len],
+        }
+    }
+}
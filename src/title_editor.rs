@@ -0,0 +1,151 @@
+use crate::canvas::Canvas;
+use crate::dpi::Dpi;
+use crate::event::Key;
+use crate::font::Font;
+use crate::geometry::{Insets, Point, Rect, Size};
+use crate::popup_menu::place_menu;
+use crate::theme::MenuColors;
+use crate::units::Dip;
+use crate::widget::{Context, Widget};
+
+const BORDER: Dip = Dip(1.0);
+// Between the border and the text
+const PADDING: Dip = Dip(4.0);
+const WIDTH: Dip = Dip(160.0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EditOutcome {
+    Done(String),
+    Cancelled,
+}
+
+// A box to type a new title into, painted over the window like a menu. The window hands it
+// the keys while it is open, and takes how it ended afterwards.
+pub struct TitleEditor {
+    text: String,
+    anchor: Point,
+    area: Rect,
+    colors: MenuColors,
+    dpi: Dpi,
+    bounds: Rect,
+    outcome: Option<EditOutcome>,
+    // Text only counts once a key was pressed here, the one that opened the editor typed
+    // its text before
+    typing: bool,
+}
+
+impl TitleEditor {
+    // Opens at `anchor` with `text` to edit, and stays inside `area`.
+    pub fn new(text: &str, anchor: Point, area: Rect, colors: MenuColors, dpi: Dpi) -> Self {
+        let mut editor = Self {
+            text: String::from(text),
+            anchor,
+            area,
+            colors,
+            dpi,
+            bounds: Rect::default(),
+            outcome: None,
+            typing: false,
+        };
+        editor.layout();
+        editor
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn take_outcome(&mut self) -> Option<EditOutcome> {
+        self.outcome.take()
+    }
+
+    // What gets typed comes with `on_text`.
+    pub fn on_key(&mut self, key: Key, ctx: &mut Context) {
+        self.typing = true;
+        if self.outcome.is_some() {
+            return;
+        }
+
+        match key {
+            Key::Backspace => {
+                self.text.pop();
+            }
+            Key::Enter => self.outcome = Some(EditOutcome::Done(self.text.clone())),
+            Key::Escape => self.outcome = Some(EditOutcome::Cancelled),
+            _ => return,
+        }
+        ctx.invalidate(self.bounds);
+    }
+
+    pub fn on_text(&mut self, ch: char, ctx: &mut Context) {
+        if self.outcome.is_some() || !self.typing || ch.is_control() {
+            return;
+        }
+
+        self.text.push(ch);
+        ctx.invalidate(self.bounds);
+    }
+
+    fn inset(&self) -> i32 {
+        self.dpi.px(BORDER).get() + self.dpi.px(PADDING).get()
+    }
+
+    fn layout(&mut self) {
+        let size = Size::new(
+            self.dpi.px(WIDTH).get(),
+            Font::new(self.dpi).height() + 2 * self.inset(),
+        );
+        self.bounds = place_menu(self.anchor, size, self.area);
+    }
+}
+
+impl Widget for TitleEditor {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    // The editor places itself next to its anchor
+    fn set_bounds(&mut self, _bounds: Rect) {}
+
+    fn paint(&self, canvas: &mut dyn Canvas) {
+        let font = Font::new(self.dpi);
+        let border = self.dpi.px(BORDER).get();
+        canvas.fill_rect(self.bounds.into(), self.colors.border);
+        canvas.fill_rect(
+            self.bounds.inset(&Insets::uniform(border)).into(),
+            self.colors.background,
+        );
+
+        // A title too long for the box shows its end, where the caret is
+        let inner = self.bounds.inset(&Insets::uniform(self.inset()));
+        let room = inner.width() - 2 * font.scale();
+        let mut shown = self.text.as_str();
+        while !shown.is_empty() && font.text_width(shown) > room {
+            let mut chars = shown.chars();
+            chars.next();
+            shown = chars.as_str();
+        }
+        canvas.draw_text(shown, Point::new(inner.left, inner.top), self.colors.text);
+
+        let caret = inner.left + font.text_width(shown) + font.scale();
+        let caret = Rect::new(caret, inner.top, caret + font.scale(), inner.bottom);
+        canvas.fill_rect(caret.into(), self.colors.text);
+    }
+
+    fn hit_test(&self, x: i32, y: i32) -> bool {
+        self.bounds.contains_point(x, y)
+    }
+
+    fn on_dpi_changed(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+        self.layout();
+    }
+
+    // Pressing anywhere else keeps what was typed, like moving the focus away.
+    fn on_mouse_down(&mut self, x: i32, y: i32, _ctx: &mut Context) -> bool {
+        if self.outcome.is_none() && !self.hit_test(x, y) {
+            self.outcome = Some(EditOutcome::Done(self.text.clone()));
+        }
+        false
+    }
+}
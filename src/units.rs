@@ -0,0 +1,191 @@
+use crate::dpi::Dpi;
+use crate::geometry::{Insets, PointF, Rect, RectF, Size};
+
+// How a fractional pixel value becomes a whole one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rounding {
+    Round,
+    Floor,
+    Ceil,
+}
+
+impl Rounding {
+    pub fn apply(self, value: f32) -> i32 {
+        match self {
+            Rounding::Round => value.round() as _,
+            Rounding::Floor => value.floor() as _,
+            Rounding::Ceil => value.ceil() as _,
+        }
+    }
+}
+
+// A device independent pixel, 1/96 of an inch. Layout values are written in these.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct Dip(pub f32);
+
+// A physical pixel on the monitor the window is on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Px(pub i32);
+
+impl Dip {
+    pub fn to_px(self, dpi: Dpi, rounding: Rounding) -> Px {
+        Px(rounding.apply(self.to_px_f32(dpi)))
+    }
+
+    // Exact, for values that are handed to the canvas as is.
+    pub fn to_px_f32(self, dpi: Dpi) -> f32 {
+        self.0 * dpi.factor()
+    }
+}
+
+impl Px {
+    pub fn get(self) -> i32 {
+        self.0
+    }
+
+    pub fn to_dip(self, dpi: Dpi) -> Dip {
+        Dip(self.0 as f32 / dpi.factor())
+    }
+}
+
+// Centers a stroke of `width` pixels on `coord` so that it covers whole pixels. Odd widths
+// need to sit on a pixel center, even widths on a pixel edge.
+pub fn snap_to_pixel(coord: f32, width: f32) -> f32 {
+    if width.round() as i32 % 2 == 1 {
+        coord.floor() + 0.5
+    } else {
+        coord.round()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DipPoint {
+    pub x: Dip,
+    pub y: Dip,
+}
+
+impl DipPoint {
+    pub const fn new(x: f32, y: f32) -> Self {
+        Self {
+            x: Dip(x),
+            y: Dip(y),
+        }
+    }
+
+    pub fn to_px_f32(self, dpi: Dpi) -> PointF {
+        PointF::new(self.x.to_px_f32(dpi), self.y.to_px_f32(dpi))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DipSize {
+    pub width: Dip,
+    pub height: Dip,
+}
+
+impl DipSize {
+    pub const fn new(width: f32, height: f32) -> Self {
+        Self {
+            width: Dip(width),
+            height: Dip(height),
+        }
+    }
+
+    pub fn to_px(self, dpi: Dpi, rounding: Rounding) -> Size {
+        Size::new(
+            self.width.to_px(dpi, rounding).get(),
+            self.height.to_px(dpi, rounding).get(),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DipRect {
+    pub left: Dip,
+    pub top: Dip,
+    pub right: Dip,
+    pub bottom: Dip,
+}
+
+impl DipRect {
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left: Dip(left),
+            top: Dip(top),
+            right: Dip(right),
+            bottom: Dip(bottom),
+        }
+    }
+
+    // Rounds the edges rather than the size, so rects sharing an edge still do in pixels.
+    pub fn to_px(self, dpi: Dpi) -> Rect {
+        RectF::new(
+            self.left.to_px_f32(dpi),
+            self.top.to_px_f32(dpi),
+            self.right.to_px_f32(dpi),
+            self.bottom.to_px_f32(dpi),
+        )
+        .round()
+    }
+
+    pub fn width(&self) -> Dip {
+        Dip(self.right.0 - self.left.0)
+    }
+
+    pub fn height(&self) -> Dip {
+        Dip(self.bottom.0 - self.top.0)
+    }
+
+    // A rect of `size` in the middle of this one, e.g. a glyph inside its button.
+    pub fn centered(&self, size: DipSize) -> DipRect {
+        let left = self.left.0 + (self.width().0 - size.width.0) / 2.0;
+        let top = self.top.0 + (self.height().0 - size.height.0) / 2.0;
+        DipRect::new(left, top, left + size.width.0, top + size.height.0)
+    }
+
+    pub fn inset(&self, insets: DipInsets) -> DipRect {
+        DipRect::new(
+            self.left.0 + insets.left.0,
+            self.top.0 + insets.top.0,
+            self.right.0 - insets.right.0,
+            self.bottom.0 - insets.bottom.0,
+        )
+    }
+
+    pub fn from_px(rect: Rect, dpi: Dpi) -> Self {
+        Self {
+            left: Px(rect.left).to_dip(dpi),
+            top: Px(rect.top).to_dip(dpi),
+            right: Px(rect.right).to_dip(dpi),
+            bottom: Px(rect.bottom).to_dip(dpi),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DipInsets {
+    pub left: Dip,
+    pub top: Dip,
+    pub right: Dip,
+    pub bottom: Dip,
+}
+
+impl DipInsets {
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Self {
+        Self {
+            left: Dip(left),
+            top: Dip(top),
+            right: Dip(right),
+            bottom: Dip(bottom),
+        }
+    }
+
+    pub fn to_px(self, dpi: Dpi) -> Insets {
+        Insets::new(
+            dpi.px(self.left).get(),
+            dpi.px(self.top).get(),
+            dpi.px(self.right).get(),
+            dpi.px(self.bottom).get(),
+        )
+    }
+}
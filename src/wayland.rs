@@ -0,0 +1,1710 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::os::fd::{AsFd, OwnedFd};
+use std::ptr::{self, null_mut};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustix::event::{poll, PollFd, PollFlags, Timespec};
+use rustix::fs::{ftruncate, memfd_create, MemfdFlags};
+use rustix::mm::{mmap, munmap, MapFlags, ProtFlags};
+use wayland_client::backend::WaylandError;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::wl_buffer::{self, WlBuffer};
+use wayland_client::protocol::wl_callback::{self, WlCallback};
+use wayland_client::protocol::wl_compositor::WlCompositor;
+use wayland_client::protocol::wl_keyboard::{self, WlKeyboard};
+use wayland_client::protocol::wl_pointer::{self, WlPointer};
+use wayland_client::protocol::wl_registry::{self, WlRegistry};
+use wayland_client::protocol::wl_seat::{self, WlSeat};
+use wayland_client::protocol::wl_shm::{self, WlShm};
+use wayland_client::protocol::wl_shm_pool::WlShmPool;
+use wayland_client::protocol::wl_surface::{self, WlSurface};
+use wayland_client::{delegate_noop, Connection, Dispatch, EventQueue, Proxy, QueueHandle, WEnum};
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_device_v1::{
+    Shape, WpCursorShapeDeviceV1,
+};
+use wayland_protocols::wp::cursor_shape::v1::client::wp_cursor_shape_manager_v1::WpCursorShapeManagerV1;
+use wayland_protocols::xdg::shell::client::xdg_popup::{self, XdgPopup};
+use wayland_protocols::xdg::shell::client::xdg_positioner::{
+    Anchor, ConstraintAdjustment, Gravity, XdgPositioner,
+};
+use wayland_protocols::xdg::shell::client::xdg_surface::{self, XdgSurface};
+use wayland_protocols::xdg::shell::client::xdg_toplevel::{self, ResizeEdge, XdgToplevel};
+use wayland_protocols::xdg::shell::client::xdg_wm_base::{self, XdgWmBase};
+
+use crate::animation::{Clock, SystemClock};
+use crate::dpi::Dpi;
+use crate::error::{self, DisplayResultExt, Error};
+use crate::event::{Event, EventHandler, HitZone, Key, MouseButton, MouseButtons, Reply};
+use crate::geometry::{Point, Rect, Size};
+use crate::host::{Dispatch as HostDispatch, Host, Hosted};
+use crate::keymap::Keymap;
+use crate::log_info;
+use crate::logging::Target;
+use crate::platform::{frame_hit_test, Platform, SystemCommand, WindowId, WindowParams};
+use crate::region::DirtyRegion;
+use crate::render_resources::{DeviceError, DeviceFactory};
+use crate::software_canvas::{Pixmap, Present, SoftwareCanvas};
+
+// There is no system caption to match, this is what a custom title bar gets at 96 DPI.
+const CAPTION_HEIGHT: i32 = 30;
+const RESIZE_BORDER: i32 = 8;
+// Two presses on the caption within this many milliseconds toggle maximizing.
+const DOUBLE_CLICK_TIME: u32 = 400;
+
+// From linux/input-event-codes.h
+const BTN_LEFT: u32 = 0x110;
+const BTN_RIGHT: u32 = 0x111;
+const BTN_MIDDLE: u32 = 0x112;
+const KEY_ESC: u32 = 1;
+const KEY_BACKSPACE: u32 = 14;
+const KEY_TAB: u32 = 15;
+const KEY_ENTER: u32 = 28;
+const KEY_SPACE: u32 = 57;
+const KEY_KPENTER: u32 = 96;
+const KEY_HOME: u32 = 102;
+const KEY_UP: u32 = 103;
+const KEY_LEFT: u32 = 105;
+const KEY_RIGHT: u32 = 106;
+const KEY_END: u32 = 107;
+const KEY_DOWN: u32 = 108;
+// xkb keycodes are evdev codes shifted by this
+const XKB_KEYCODE_OFFSET: u32 = 8;
+
+type SendFn = Rc<dyn Fn(Event<()>) -> Option<Reply>>;
+
+enum Posted {
+    Command(WindowId, SystemCommand),
+    Custom(Box<dyn FnOnce()>),
+}
+
+// What an `xdg_toplevel.configure` asked for, applied with the `xdg_surface.configure` after it.
+#[derive(Debug, Clone, Copy, Default)]
+struct Toplevel {
+    // In surface coordinates, none when the window picks its size
+    size: Option<(i32, i32)>,
+    maximized: bool,
+    activated: bool,
+}
+
+// What the event queue dispatched, handled once dispatching is done so no component runs
+// while the queue is borrowed.
+enum Incoming {
+    Configure {
+        window: WindowId,
+        serial: u32,
+        toplevel: Option<Toplevel>,
+    },
+    Close(WindowId),
+    Scale(WindowId, i32),
+    FrameDone(WindowId),
+    PointerEnter {
+        window: WindowId,
+        serial: u32,
+        pos: (f64, f64),
+    },
+    PointerLeave(WindowId),
+    PointerMotion((f64, f64)),
+    PointerButton {
+        serial: u32,
+        time: u32,
+        button: u32,
+        pressed: bool,
+    },
+    KeyboardEnter(WindowId),
+    KeyboardLeave(WindowId),
+    Key {
+        key: Key,
+        text: Option<char>,
+    },
+}
+
+// The state the event queue dispatches into.
+struct Protocol {
+    seat: Option<WlSeat>,
+    pointer: Option<WlPointer>,
+    keyboard: Option<WlKeyboard>,
+    cursor_shape: Option<WpCursorShapeManagerV1>,
+    cursor_device: Option<WpCursorShapeDeviceV1>,
+    configures: HashMap<WindowId, Toplevel>,
+    incoming: VecDeque<Incoming>,
+    // The compositor's, and the modifiers and the group in effect
+    keymap: Option<Keymap>,
+    modifiers: u32,
+    group: usize,
+}
+
+// What a window's `xdg_surface` is shown as.
+enum Role {
+    Toplevel(XdgToplevel),
+    Popup(XdgPopup),
+}
+
+struct WaylandWindow {
+    surface: WlSurface,
+    xdg_surface: XdgSurface,
+    role: Role,
+    // In buffer pixels, which are `scale` times the surface coordinates
+    size: Size,
+    // The size from before the window was maximized
+    restored: Size,
+    scale: i32,
+    configured: bool,
+    maximized: bool,
+    invalid: DirtyRegion,
+    frame_pending: Rc<Cell<bool>>,
+    // Interval and when the timer fires next
+    timers: HashMap<usize, (Duration, Duration)>,
+    tracking_leave: bool,
+    cursor_zone: HitZone,
+    last_caption_press: Option<u32>,
+    send: SendFn,
+    destroy: Rc<dyn Fn()>,
+}
+
+struct PointerFocus {
+    window: WindowId,
+    // Of the enter event, which setting the cursor needs
+    serial: u32,
+    pos: Point,
+}
+
+struct State {
+    windows: HashMap<WindowId, WaylandWindow>,
+    next_id: usize,
+    active: Option<WindowId>,
+    captured: Option<WindowId>,
+    pointer: Option<PointerFocus>,
+    // The window with the keyboard focus
+    keyboard: Option<WindowId>,
+    buttons: MouseButtons,
+    // Of the last button event, which moving, resizing and the window menu need
+    button_serial: u32,
+    posted: VecDeque<Posted>,
+    quit: bool,
+}
+
+// Runs windows on a Wayland compositor. Windows always draw their own frame, caption drags
+// and border presses are handed to the compositor with `xdg_toplevel.move` and `resize`.
+// Painting goes through a software canvas into shared memory buffers.
+pub struct WaylandPlatform {
+    conn: Connection,
+    queue: RefCell<EventQueue<Protocol>>,
+    protocol: RefCell<Protocol>,
+    qh: QueueHandle<Protocol>,
+    compositor: WlCompositor,
+    shm: WlShm,
+    wm_base: XdgWmBase,
+    clock: Rc<SystemClock>,
+    state: Rc<RefCell<State>>,
+}
+
+impl WaylandPlatform {
+    // Connects to the compositor in `WAYLAND_DISPLAY`.
+    pub fn connect() -> Result<Self, Error> {
+        let conn = Connection::connect_to_env().or_display("connect")?;
+        let (globals, mut queue) = registry_queue_init::<Protocol>(&conn).or_display("registry")?;
+        let qh = queue.handle();
+
+        let compositor = globals
+            .bind::<WlCompositor, _, _>(&qh, 4..=6, ())
+            .or_display("wl_compositor")?;
+        let shm = globals
+            .bind::<WlShm, _, _>(&qh, 1..=1, ())
+            .or_display("wl_shm")?;
+        let wm_base = globals
+            .bind::<XdgWmBase, _, _>(&qh, 1..=5, ())
+            .or_display("xdg_wm_base")?;
+
+        let mut protocol = Protocol {
+            seat: globals.bind::<WlSeat, _, _>(&qh, 1..=7, ()).ok(),
+            pointer: None,
+            keyboard: None,
+            // Without it the compositor picks the cursor
+            cursor_shape: globals.bind(&qh, 1..=1, ()).ok(),
+            cursor_device: None,
+            configures: HashMap::new(),
+            incoming: VecDeque::new(),
+            keymap: None,
+            modifiers: 0,
+            group: 0,
+        };
+
+        // Gets the seat's capabilities, and with them the pointer
+        queue.roundtrip(&mut protocol).or_display("roundtrip")?;
+
+        Ok(Self {
+            conn,
+            queue: RefCell::new(queue),
+            protocol: RefCell::new(protocol),
+            qh,
+            compositor,
+            shm,
+            wm_base,
+            clock: Rc::new(SystemClock::new()),
+            state: Rc::new(RefCell::new(State {
+                windows: HashMap::new(),
+                next_id: 0,
+                active: None,
+                captured: None,
+                pointer: None,
+                keyboard: None,
+                buttons: MouseButtons::default(),
+                button_serial: 0,
+                posted: VecDeque::new(),
+                quit: false,
+            })),
+        })
+    }
+
+    // Handles events, timers and painting until the app quits.
+    pub fn run(&self) -> Result<(), Error> {
+        while !self.state.borrow().quit {
+            self.run_pending();
+            self.fire_timers();
+            self.paint_invalid();
+            self.flush()?;
+
+            self.queue
+                .borrow_mut()
+                .dispatch_pending(&mut self.protocol.borrow_mut())
+                .or_display("dispatch")?;
+
+            if !self.handle_incoming() && !self.state.borrow().quit {
+                self.wait()?;
+            }
+        }
+
+        self.flush()
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        match self.conn.flush() {
+            // The rest goes out with the next flush
+            Err(WaylandError::Io(err)) if err.kind() == std::io::ErrorKind::WouldBlock => Ok(()),
+            result => result.or_display("flush"),
+        }
+    }
+
+    // Sleeps until the compositor sends something or the next timer is due.
+    fn wait(&self) -> Result<(), Error> {
+        let guard = match self.queue.borrow().prepare_read() {
+            Some(guard) => guard,
+            // Events are queued already
+            None => return Ok(()),
+        };
+
+        let timeout = self.next_timer().map(|due| {
+            let wait = due.saturating_sub(self.clock.now());
+            Timespec {
+                tv_sec: wait.as_secs() as _,
+                tv_nsec: wait.subsec_nanos() as _,
+            }
+        });
+
+        let fd = guard.connection_fd();
+        let mut fds = [PollFd::new(&fd, PollFlags::IN)];
+        let ready = match poll(&mut fds, timeout.as_ref()) {
+            Ok(ready) => ready > 0,
+            Err(rustix::io::Errno::INTR) => false,
+            Err(err) => return Err(Error::display("poll", err)),
+        };
+
+        if !ready {
+            return Ok(());
+        }
+
+        match guard.read() {
+            Err(WaylandError::Io(err)) if err.kind() == std::io::ErrorKind::WouldBlock => Ok(()),
+            result => result.map(|_| ()).or_display("read"),
+        }
+    }
+
+    fn run_pending(&self) {
+        loop {
+            let posted = self.state.borrow_mut().posted.pop_front();
+            match posted {
+                Some(Posted::Command(window, command)) => self.run_command(window, command),
+                Some(Posted::Custom(deliver)) => deliver(),
+                None => break,
+            }
+        }
+    }
+
+    fn run_command(&self, window: WindowId, command: SystemCommand) {
+        if command == SystemCommand::Close {
+            close_window(&self.state, window);
+            return;
+        }
+
+        let state = self.state.borrow();
+        let toplevel = match state.windows.get(&window).map(|data| &data.role) {
+            Some(Role::Toplevel(toplevel)) => toplevel,
+            _ => return,
+        };
+
+        match command {
+            SystemCommand::Minimize => toplevel.set_minimized(),
+            SystemCommand::Maximize => toplevel.set_maximized(),
+            SystemCommand::Restore => toplevel.unset_maximized(),
+            // xdg-shell only moves and sizes with the pointer
+            SystemCommand::Move | SystemCommand::Size => {
+                log_info!(Target::Window, "no keyboard {:?}", command);
+            }
+            SystemCommand::Close => {}
+        }
+    }
+
+    fn next_timer(&self) -> Option<Duration> {
+        let state = self.state.borrow();
+        state
+            .windows
+            .values()
+            .flat_map(|data| data.timers.values().map(|(_, due)| *due))
+            .min()
+    }
+
+    fn fire_timers(&self) {
+        let now = self.clock.now();
+        let due = {
+            let mut state = self.state.borrow_mut();
+            let mut due = Vec::new();
+            for (window, data) in state.windows.iter_mut() {
+                for (id, (interval, next)) in data.timers.iter_mut() {
+                    if *next <= now {
+                        // Late timers fire once, like WM_TIMER
+                        *next = now + *interval;
+                        due.push((*window, *id));
+                    }
+                }
+            }
+            due
+        };
+
+        for (window, id) in due {
+            self.send(window, Event::Timer(id));
+        }
+    }
+
+    // Paints whatever was invalidated, once the compositor is ready for another frame.
+    fn paint_invalid(&self) {
+        let dirty = {
+            let mut state = self.state.borrow_mut();
+            state
+                .windows
+                .iter_mut()
+                .filter(|(_, data)| {
+                    data.configured && !data.frame_pending.get() && !data.invalid.is_empty()
+                })
+                .map(|(window, data)| (*window, data.invalid.take().bounds()))
+                .collect::<Vec<_>>()
+        };
+
+        for (window, dirty) in dirty {
+            self.send(window, Event::Paint { dirty });
+        }
+    }
+
+    fn send(&self, window: WindowId, event: Event<()>) -> Option<Reply> {
+        let send = self.state.borrow().windows.get(&window)?.send.clone();
+        send(event)
+    }
+
+    fn handle_incoming(&self) -> bool {
+        let incoming = std::mem::take(&mut self.protocol.borrow_mut().incoming);
+        let handled = !incoming.is_empty();
+
+        for incoming in incoming {
+            let result = match incoming {
+                Incoming::Configure {
+                    window,
+                    serial,
+                    toplevel,
+                } => self.on_configure(window, serial, toplevel),
+                Incoming::Close(window) => {
+                    close_window(&self.state, window);
+                    Ok(())
+                }
+                Incoming::Scale(window, scale) => self.on_scale_changed(window, scale),
+                Incoming::FrameDone(window) => {
+                    if let Some(data) = self.state.borrow().windows.get(&window) {
+                        data.frame_pending.set(false);
+                    }
+                    Ok(())
+                }
+                Incoming::PointerEnter {
+                    window,
+                    serial,
+                    pos,
+                } => {
+                    let pos = self.to_buffer(window, pos);
+                    self.state.borrow_mut().pointer = Some(PointerFocus {
+                        window,
+                        serial,
+                        pos,
+                    });
+                    // Whatever the cursor was, it's the compositor's default now
+                    self.with_window(window, |data| data.cursor_zone = HitZone::Nowhere)
+                        .and_then(|_| self.on_motion(window, pos))
+                }
+                Incoming::PointerLeave(window) => {
+                    {
+                        let mut state = self.state.borrow_mut();
+                        state.pointer = None;
+                        // A move or resize the compositor took over ends without a release
+                        state.buttons = MouseButtons::default();
+                    }
+                    self.leave(window);
+                    Ok(())
+                }
+                Incoming::PointerMotion(pos) => match self.pointer_window() {
+                    Some(window) => {
+                        let pos = self.to_buffer(window, pos);
+                        if let Some(pointer) = self.state.borrow_mut().pointer.as_mut() {
+                            pointer.pos = pos;
+                        }
+                        self.on_motion(window, pos)
+                    }
+                    None => Ok(()),
+                },
+                Incoming::PointerButton {
+                    serial,
+                    time,
+                    button,
+                    pressed,
+                } => self.on_button(serial, time, button, pressed),
+                Incoming::KeyboardEnter(window) => {
+                    self.state.borrow_mut().keyboard = Some(window);
+                    Ok(())
+                }
+                Incoming::KeyboardLeave(window) => {
+                    let mut state = self.state.borrow_mut();
+                    if state.keyboard == Some(window) {
+                        state.keyboard = None;
+                    }
+                    Ok(())
+                }
+                Incoming::Key { key, text } => {
+                    let window = self.state.borrow().keyboard;
+                    if let Some(window) = window {
+                        self.send(window, Event::KeyDown(key));
+                        if let Some(ch) = text {
+                            self.send(window, Event::Text(ch));
+                        }
+                    }
+                    Ok(())
+                }
+            };
+
+            if let Err(err) = result {
+                error::report_error(&err);
+            }
+        }
+
+        handled
+    }
+
+    fn on_configure(
+        &self,
+        window: WindowId,
+        serial: u32,
+        toplevel: Option<Toplevel>,
+    ) -> Result<(), Error> {
+        let (resized, activated) = {
+            let mut state = self.state.borrow_mut();
+            let data = match state.windows.get_mut(&window) {
+                Some(data) => data,
+                None => return Ok(()),
+            };
+            data.xdg_surface.ack_configure(serial);
+            data.configured = true;
+
+            let toplevel = toplevel.unwrap_or(Toplevel {
+                size: None,
+                maximized: data.maximized,
+                activated: false,
+            });
+            data.maximized = toplevel.maximized;
+
+            let size = match toplevel.size {
+                Some((width, height)) => Size::new(width * data.scale, height * data.scale),
+                None => data.size,
+            };
+            let resized = data.size != size;
+            data.size = size;
+            if !data.maximized {
+                data.restored = size;
+            }
+
+            (resized.then_some(size), toplevel.activated)
+        };
+
+        if let Some(size) = resized {
+            self.send(window, Event::Resize(size));
+        }
+        self.set_active(window, activated);
+
+        // The first configure needs a buffer, and the caption buttons show whether the window
+        // is maximized
+        self.invalidate(window, self.client_rect(window)?)
+    }
+
+    fn on_scale_changed(&self, window: WindowId, scale: i32) -> Result<(), Error> {
+        let suggested = {
+            let mut state = self.state.borrow_mut();
+            let data = match state.windows.get_mut(&window) {
+                Some(data) if data.scale != scale => data,
+                _ => return Ok(()),
+            };
+
+            let size = Size::new(
+                data.size.width / data.scale * scale,
+                data.size.height / data.scale * scale,
+            );
+            data.scale = scale;
+            data.surface.set_buffer_scale(scale);
+            Rect::from_size(0, 0, size.width, size.height)
+        };
+
+        let dpi = self.dpi(window)?;
+        self.send(window, Event::Dpi { dpi, suggested });
+        Ok(())
+    }
+
+    // Shows the surface at `bounds` in the parent's buffer pixels. Where that is off the
+    // screen the compositor flips or slides the popup back onto it.
+    fn get_popup(
+        &self,
+        xdg_surface: &XdgSurface,
+        parent: &XdgSurface,
+        bounds: Rect,
+        scale: i32,
+        window: WindowId,
+    ) -> XdgPopup {
+        let positioner = self.wm_base.create_positioner(&self.qh, ());
+        positioner.set_size(
+            (bounds.width() / scale).max(1),
+            (bounds.height() / scale).max(1),
+        );
+        positioner.set_anchor_rect(bounds.left / scale, bounds.top / scale, 1, 1);
+        positioner.set_anchor(Anchor::TopLeft);
+        positioner.set_gravity(Gravity::BottomRight);
+        positioner.set_constraint_adjustment(
+            ConstraintAdjustment::FlipX
+                | ConstraintAdjustment::FlipY
+                | ConstraintAdjustment::SlideX
+                | ConstraintAdjustment::SlideY,
+        );
+
+        let popup = xdg_surface.get_popup(Some(parent), &positioner, &self.qh, window);
+        positioner.destroy();
+        popup
+    }
+
+    fn to_buffer(&self, window: WindowId, (x, y): (f64, f64)) -> Point {
+        let scale = self.scale(window) as f64;
+        Point::new((x * scale) as i32, (y * scale) as i32)
+    }
+
+    fn scale(&self, window: WindowId) -> i32 {
+        self.with_window(window, |data| data.scale).unwrap_or(1)
+    }
+
+    fn pointer_window(&self) -> Option<WindowId> {
+        self.state
+            .borrow()
+            .pointer
+            .as_ref()
+            .map(|pointer| pointer.window)
+    }
+
+    // The compositor draws no frame, so the window's borders are the resize borders.
+    fn system_hit_test(&self, window: WindowId, pos: Point) -> Result<HitZone, Error> {
+        let rect = self.client_rect(window)?;
+        if self.is_maximized(window)? {
+            return Ok(frame_hit_test(rect, pos, 0));
+        }
+
+        let border = self.resize_border(window, self.dpi(window)?)?;
+        Ok(frame_hit_test(rect, pos, border))
+    }
+
+    fn hit_test(&self, window: WindowId, pos: Point) -> Result<HitZone, Error> {
+        let system = self.system_hit_test(window, pos)?;
+
+        match self.send(window, Event::HitTest { pos, system }) {
+            Some(Reply::HitTest(zone)) => Ok(zone),
+            _ => Ok(system),
+        }
+    }
+
+    fn on_button(&self, serial: u32, time: u32, code: u32, pressed: bool) -> Result<(), Error> {
+        let button = match code {
+            BTN_LEFT => MouseButton::Left,
+            BTN_RIGHT => MouseButton::Right,
+            BTN_MIDDLE => MouseButton::Middle,
+            _ => return Ok(()),
+        };
+
+        let (window, pos, buttons, captured) = {
+            let mut state = self.state.borrow_mut();
+            let (window, pos) = match state.pointer.as_ref() {
+                Some(pointer) => (pointer.window, pointer.pos),
+                None => return Ok(()),
+            };
+            state.button_serial = serial;
+            set_button(&mut state.buttons, button, pressed);
+            (window, pos, state.buttons, state.captured == Some(window))
+        };
+
+        if !pressed {
+            self.send(
+                window,
+                Event::MouseUp {
+                    pos,
+                    button,
+                    buttons,
+                },
+            );
+            return Ok(());
+        }
+
+        let zone = if captured {
+            HitZone::Client
+        } else {
+            self.hit_test(window, pos)?
+        };
+
+        match zone {
+            HitZone::Client | HitZone::Other => {
+                self.send(
+                    window,
+                    Event::MouseDown {
+                        pos,
+                        button,
+                        buttons,
+                    },
+                );
+            }
+            HitZone::Nowhere => {}
+            HitZone::Caption if button == MouseButton::Left => {
+                if self.is_double_click(window, time) {
+                    let command = if self.is_maximized(window)? {
+                        SystemCommand::Restore
+                    } else {
+                        SystemCommand::Maximize
+                    };
+                    self.post_command(window, command)?;
+                } else {
+                    self.move_resize(window, serial, None);
+                }
+            }
+            zone if button == MouseButton::Left => {
+                if let Some(edge) = resize_edge(zone) {
+                    self.move_resize(window, serial, Some(edge));
+                }
+            }
+            _ => {
+                self.send(
+                    window,
+                    Event::NonClientMouseDown {
+                        pos: self.client_to_screen(window, pos)?,
+                        button,
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_double_click(&self, window: WindowId, time: u32) -> bool {
+        let mut state = self.state.borrow_mut();
+        let data = match state.windows.get_mut(&window) {
+            Some(data) => data,
+            None => return false,
+        };
+
+        let previous = data.last_caption_press.replace(time);
+        match previous {
+            Some(previous) if time.wrapping_sub(previous) <= DOUBLE_CLICK_TIME => {
+                data.last_caption_press = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Hands the pointer to the compositor, which moves or resizes until it is released.
+    fn move_resize(&self, window: WindowId, serial: u32, edge: Option<ResizeEdge>) {
+        let seat = match self.protocol.borrow().seat.clone() {
+            Some(seat) => seat,
+            None => return,
+        };
+
+        let mut state = self.state.borrow_mut();
+        state.captured = None;
+        if let Some(Role::Toplevel(toplevel)) = state.windows.get(&window).map(|data| &data.role) {
+            match edge {
+                Some(edge) => toplevel.resize(&seat, serial, edge),
+                None => toplevel._move(&seat, serial),
+            }
+        }
+    }
+
+    fn on_motion(&self, window: WindowId, pos: Point) -> Result<(), Error> {
+        let (captured, buttons) = {
+            let state = self.state.borrow();
+            (state.captured == Some(window), state.buttons)
+        };
+        let pressed = buttons.left || buttons.right || buttons.middle;
+
+        // The zone only changes while nothing is pressed, like the cursor on Windows
+        let zone = if captured || pressed {
+            HitZone::Client
+        } else {
+            self.hit_test(window, pos)?
+        };
+        self.set_cursor(window, zone);
+
+        if matches!(zone, HitZone::Client | HitZone::Other) {
+            self.send(window, Event::MouseMove { pos, buttons });
+        } else {
+            // Over the caption or the borders the window doesn't get mouse moves
+            self.leave(window);
+        }
+
+        Ok(())
+    }
+
+    fn set_cursor(&self, window: WindowId, zone: HitZone) {
+        let zone = if zone.is_border() {
+            zone
+        } else {
+            HitZone::Client
+        };
+
+        let serial = {
+            let mut state = self.state.borrow_mut();
+            let serial = match state.pointer.as_ref() {
+                Some(pointer) => pointer.serial,
+                None => return,
+            };
+            match state.windows.get_mut(&window) {
+                Some(data) if data.cursor_zone != zone => data.cursor_zone = zone,
+                _ => return,
+            }
+            serial
+        };
+
+        if let Some(device) = self.protocol.borrow().cursor_device.as_ref() {
+            device.set_shape(serial, cursor_shape(zone));
+        }
+    }
+
+    fn leave(&self, window: WindowId) {
+        let tracking = match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => std::mem::replace(&mut data.tracking_leave, false),
+            None => false,
+        };
+
+        if tracking {
+            self.send(window, Event::MouseLeave);
+        }
+    }
+
+    fn set_active(&self, window: WindowId, active: bool) {
+        {
+            let mut state = self.state.borrow_mut();
+            if active && state.active != Some(window) {
+                state.active = Some(window);
+            } else if !active && state.active == Some(window) {
+                state.active = None;
+            } else {
+                return;
+            }
+        }
+
+        self.send(window, Event::Activate(active));
+        self.send(window, Event::Focus(active));
+    }
+
+    fn with_window<R>(
+        &self,
+        window: WindowId,
+        f: impl FnOnce(&mut WaylandWindow) -> R,
+    ) -> Result<R, Error> {
+        match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => Ok(f(data)),
+            None => Err(Error::Generic(format!("no window {:?}", window))),
+        }
+    }
+}
+
+// Destroys the window unless its component wants to keep it open.
+fn close_window(state: &Rc<RefCell<State>>, window: WindowId) {
+    let send = match state.borrow().windows.get(&window) {
+        Some(data) => data.send.clone(),
+        None => return,
+    };
+
+    if send(Event::CloseRequested) != Some(Reply::Handled) {
+        destroy_window(state, window);
+    }
+}
+
+// Forgets the window and destroys it, which makes it drop its component after telling it.
+fn destroy_window(state: &Rc<RefCell<State>>, window: WindowId) {
+    let data = {
+        let mut state = state.borrow_mut();
+        if state.captured == Some(window) {
+            state.captured = None;
+        }
+        if state.active == Some(window) {
+            state.active = None;
+        }
+        match state.windows.remove(&window) {
+            Some(data) => data,
+            None => return,
+        }
+    };
+
+    (data.send)(Event::Destroyed);
+    (data.destroy)();
+
+    match &data.role {
+        Role::Toplevel(toplevel) => toplevel.destroy(),
+        Role::Popup(popup) => popup.destroy(),
+    }
+    data.xdg_surface.destroy();
+    data.surface.destroy();
+}
+
+impl Platform for WaylandPlatform {
+    type DeviceFactory = WaylandDeviceFactory;
+
+    // Without the decoration protocol every window draws its own frame, `custom_frame` or not.
+    fn create_window<C: EventHandler + 'static>(
+        &self,
+        params: &WindowParams,
+        component: C,
+    ) -> Result<Hosted<C>, Error> {
+        let window = {
+            let mut state = self.state.borrow_mut();
+            state.next_id += 1;
+            WindowId(state.next_id)
+        };
+
+        let surface = self.compositor.create_surface(&self.qh, window);
+        let xdg_surface = self.wm_base.get_xdg_surface(&surface, &self.qh, window);
+        let (role, size, scale) = match params.popup {
+            Some(popup) => {
+                let (parent, scale) = self.with_window(popup.owner, |owner| {
+                    (owner.xdg_surface.clone(), owner.scale)
+                })?;
+                let xdg_popup = self.get_popup(&xdg_surface, &parent, popup.bounds, scale, window);
+                surface.set_buffer_scale(scale);
+                (Role::Popup(xdg_popup), popup.bounds.size(), scale)
+            }
+            None => {
+                let toplevel = xdg_surface.get_toplevel(&self.qh, window);
+                toplevel.set_title(params.title.clone());
+                toplevel.set_app_id(params.class_name.to_owned());
+                (Role::Toplevel(toplevel), params.size, 1)
+            }
+        };
+        // Asks for the first configure
+        surface.commit();
+
+        let host = Rc::new(Host::new(component));
+        host.with(|component| component.attach(window));
+
+        let name = params.class_name;
+        let send: SendFn = {
+            let host = host.clone();
+            Rc::new(move |event| {
+                let event = event.cast().ok()?;
+                let handle = |component: &mut C, event| match component.handle_event(event) {
+                    Ok(reply) => reply,
+                    Err(err) => {
+                        error::report_error(&err.in_component(name));
+                        Reply::Default
+                    }
+                };
+
+                match host.dispatch(event, handle) {
+                    HostDispatch::Handled(reply) => Some(reply),
+                    HostDispatch::Deferred | HostDispatch::Busy | HostDispatch::Gone => None,
+                }
+            })
+        };
+
+        let destroy: Rc<dyn Fn()> = {
+            let host = host.clone();
+            Rc::new(move || host.destroy())
+        };
+
+        self.state.borrow_mut().windows.insert(
+            window,
+            WaylandWindow {
+                surface,
+                xdg_surface,
+                role,
+                size,
+                restored: size,
+                scale,
+                configured: false,
+                maximized: false,
+                invalid: DirtyRegion::new(),
+                frame_pending: Rc::new(Cell::new(false)),
+                timers: HashMap::new(),
+                tracking_leave: false,
+                cursor_zone: HitZone::Nowhere,
+                last_caption_press: None,
+                send: send.clone(),
+                destroy,
+            },
+        );
+
+        send(Event::Created);
+        send(Event::Resize(self.client_rect(window)?.size()));
+        self.flush()?;
+
+        let state = self.state.clone();
+        Ok(Hosted::new(host, window, move || {
+            destroy_window(&state, window)
+        }))
+    }
+
+    fn post_event<C: EventHandler + 'static>(
+        &self,
+        window: &Hosted<C>,
+        event: C::Custom,
+    ) -> Result<(), Error> {
+        let host = window.host().clone();
+        let deliver = move || {
+            host.dispatch(Event::Custom(event), |component, event| {
+                if let Err(err) = component.handle_event(event) {
+                    error::report_error(&err);
+                }
+            });
+        };
+
+        let mut state = self.state.borrow_mut();
+        state.posted.push_back(Posted::Custom(Box::new(deliver)));
+        Ok(())
+    }
+
+    fn post_command(&self, window: WindowId, command: SystemCommand) -> Result<(), Error> {
+        let mut state = self.state.borrow_mut();
+        state.posted.push_back(Posted::Command(window, command));
+        Ok(())
+    }
+
+    fn quit(&self) {
+        self.state.borrow_mut().quit = true;
+    }
+
+    fn device_factory(&self, window: WindowId) -> WaylandDeviceFactory {
+        let state = self.state.borrow();
+        let data = state.windows.get(&window);
+
+        WaylandDeviceFactory {
+            window,
+            surface: data.map(|data| data.surface.clone()),
+            frame_pending: data
+                .map(|data| data.frame_pending.clone())
+                .unwrap_or_default(),
+            shm: self.shm.clone(),
+            qh: self.qh.clone(),
+        }
+    }
+
+    fn clock(&self) -> Rc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    fn dpi(&self, window: WindowId) -> Result<Dpi, Error> {
+        let scale = self.with_window(window, |data| data.scale)?;
+        Ok(Dpi::new(Dpi::DEFAULT.value() * scale as u32))
+    }
+
+    fn client_rect(&self, window: WindowId) -> Result<Rect, Error> {
+        self.with_window(window, |data| {
+            Rect::from_size(0, 0, data.size.width, data.size.height)
+        })
+    }
+
+    // Windows don't know where they are on Wayland, as far as they can tell it's the origin.
+    fn window_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        self.client_rect(window)
+    }
+
+    fn set_window_bounds(&self, window: WindowId, bounds: Rect) -> Result<(), Error> {
+        self.resize_window(window, bounds.size())
+    }
+
+    // The window picks its own size unless the compositor configured one, like when maximized.
+    fn resize_window(&self, window: WindowId, size: Size) -> Result<(), Error> {
+        let resized = self.with_window(window, |data| {
+            if data.maximized || data.size == size {
+                return false;
+            }
+            data.size = size;
+            data.restored = size;
+            true
+        })?;
+
+        if resized {
+            self.send(window, Event::Resize(size));
+            self.invalidate(window, self.client_rect(window)?)?;
+        }
+        Ok(())
+    }
+
+    // Windows don't know where they are, only their size is worth keeping.
+    fn restored_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        let size = self.with_window(window, |data| data.restored)?;
+        Ok(Rect::from_size(0, 0, size.width, size.height))
+    }
+
+    fn is_maximized(&self, window: WindowId) -> Result<bool, Error> {
+        self.with_window(window, |data| data.maximized)
+    }
+
+    fn is_active(&self, window: WindowId) -> bool {
+        self.state.borrow().active == Some(window)
+    }
+
+    fn caption_height(&self, _window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        Ok(dpi.scale(CAPTION_HEIGHT))
+    }
+
+    fn resize_border(&self, _window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        Ok(dpi.scale(RESIZE_BORDER))
+    }
+
+    fn invalidate(&self, window: WindowId, rect: Rect) -> Result<(), Error> {
+        self.with_window(window, |data| data.invalid.add(rect))
+    }
+
+    // Pressing a button grabs the pointer for the surface until the release anyway.
+    fn set_capture(&self, window: WindowId) {
+        self.state.borrow_mut().captured = Some(window);
+    }
+
+    fn release_capture(&self, window: WindowId) -> Result<(), Error> {
+        let mut state = self.state.borrow_mut();
+        if state.captured == Some(window) {
+            state.captured = None;
+        }
+        Ok(())
+    }
+
+    fn track_mouse_leave(&self, window: WindowId) -> Result<(), Error> {
+        self.with_window(window, |data| data.tracking_leave = true)
+    }
+
+    fn set_timer(&self, window: WindowId, id: usize, interval: Duration) -> Result<(), Error> {
+        let now = self.clock.now();
+        self.with_window(window, |data| {
+            data.timers.insert(id, (interval, now + interval));
+        })
+    }
+
+    fn kill_timer(&self, window: WindowId, id: usize) {
+        let _ = self.with_window(window, |data| data.timers.remove(&id));
+    }
+
+    // Only known while the pointer is over one of the windows.
+    fn cursor_position(&self) -> Result<Point, Error> {
+        let state = self.state.borrow();
+        Ok(state
+            .pointer
+            .as_ref()
+            .map_or(Point::new(0, 0), |pointer| pointer.pos))
+    }
+
+    fn client_to_screen(&self, _window: WindowId, point: Point) -> Result<Point, Error> {
+        Ok(point)
+    }
+
+    fn animations_enabled(&self) -> bool {
+        true
+    }
+
+    fn monitors(&self) -> Result<Vec<Rect>, Error> {
+        Ok(Vec::new())
+    }
+
+    fn monitor(&self, _window: WindowId) -> Result<Option<Rect>, Error> {
+        Ok(None)
+    }
+
+    // There is no toolkit to show a dialog with, the window asks in a menu of its own.
+    fn confirm(
+        &self,
+        _window: WindowId,
+        _title: &str,
+        _question: &str,
+    ) -> Result<Option<bool>, Error> {
+        Ok(None)
+    }
+}
+
+pub struct WaylandDeviceFactory {
+    window: WindowId,
+    surface: Option<WlSurface>,
+    frame_pending: Rc<Cell<bool>>,
+    shm: WlShm,
+    qh: QueueHandle<Protocol>,
+}
+
+impl DeviceFactory for WaylandDeviceFactory {
+    type Device = SoftwareCanvas<ShmPresenter>;
+
+    fn create_device(&mut self, size: Size, dpi: Dpi) -> Result<Self::Device, DeviceError> {
+        // The window is gone
+        let surface = self.surface.clone().ok_or(DeviceError::Failed(0))?;
+
+        let presenter = ShmPresenter {
+            window: self.window,
+            surface,
+            frame_pending: self.frame_pending.clone(),
+            shm: self.shm.clone(),
+            qh: self.qh.clone(),
+            buffers: Vec::new(),
+        };
+
+        Ok(SoftwareCanvas::new(presenter, size, dpi))
+    }
+}
+
+// Copies frames into shared memory buffers the compositor reads from. While the compositor
+// holds on to one buffer the next frame goes into another.
+pub struct ShmPresenter {
+    window: WindowId,
+    surface: WlSurface,
+    frame_pending: Rc<Cell<bool>>,
+    shm: WlShm,
+    qh: QueueHandle<Protocol>,
+    buffers: Vec<ShmBuffer>,
+}
+
+impl ShmPresenter {
+    fn free_buffer(&mut self, size: Size) -> Result<usize, Error> {
+        // Buffers of an old size go once the compositor is done with them
+        self.buffers
+            .retain(|buffer| buffer.is_busy() || buffer.size == size);
+
+        let free = self
+            .buffers
+            .iter()
+            .position(|buffer| !buffer.is_busy() && buffer.size == size);
+
+        match free {
+            Some(index) => Ok(index),
+            None => {
+                self.buffers
+                    .push(ShmBuffer::new(&self.shm, &self.qh, size)?);
+                Ok(self.buffers.len() - 1)
+            }
+        }
+    }
+}
+
+impl Present for ShmPresenter {
+    fn present(&mut self, pixmap: &Pixmap, damage: Rect) -> Result<(), DeviceError> {
+        let damage = damage.intersection(&pixmap.bounds());
+        if damage.is_empty() {
+            return Ok(());
+        }
+
+        // The other buffers have older frames, so the whole frame is copied
+        let buffer = match self.free_buffer(pixmap.size()) {
+            Ok(index) => &mut self.buffers[index],
+            Err(err) => {
+                error::report_error(&err);
+                return Err(DeviceError::Failed(0));
+            }
+        };
+        buffer.write(pixmap.pixels());
+
+        self.surface.attach(Some(&buffer.buffer), 0, 0);
+        self.surface
+            .damage_buffer(damage.left, damage.top, damage.width(), damage.height());
+        self.surface.frame(&self.qh, self.window);
+        self.surface.commit();
+        self.frame_pending.set(true);
+        Ok(())
+    }
+}
+
+struct ShmBuffer {
+    pool: WlShmPool,
+    buffer: WlBuffer,
+    size: Size,
+    map: *mut u8,
+    len: usize,
+    busy: Arc<AtomicBool>,
+}
+
+impl ShmBuffer {
+    fn new(shm: &WlShm, qh: &QueueHandle<Protocol>, size: Size) -> Result<Self, Error> {
+        let stride = size.width * 4;
+        let len = (stride * size.height) as usize;
+
+        let fd = memfd_create("testwindowtabs", MemfdFlags::CLOEXEC).or_display("memfd_create")?;
+        ftruncate(&fd, len as u64).or_display("ftruncate")?;
+        let map = unsafe {
+            mmap(
+                null_mut(),
+                len,
+                ProtFlags::READ | ProtFlags::WRITE,
+                MapFlags::SHARED,
+                &fd,
+                0,
+            )
+        }
+        .or_display("mmap")?;
+
+        let pool = shm.create_pool(fd.as_fd(), len as i32, qh, ());
+        let busy = Arc::new(AtomicBool::new(false));
+        let buffer = pool.create_buffer(
+            0,
+            size.width,
+            size.height,
+            stride,
+            wl_shm::Format::Argb8888,
+            qh,
+            busy.clone(),
+        );
+
+        Ok(Self {
+            pool,
+            buffer,
+            size,
+            map: map as *mut u8,
+            len,
+            busy,
+        })
+    }
+
+    fn is_busy(&self) -> bool {
+        self.busy.load(Ordering::Acquire)
+    }
+
+    // The pixmap's premultiplied 0xAARRGGBB pixels are what ARGB8888 is on little endian.
+    fn write(&mut self, pixels: &[u32]) {
+        let len = self.len.min(pixels.len() * 4);
+        unsafe { ptr::copy_nonoverlapping(pixels.as_ptr() as *const u8, self.map, len) };
+        self.busy.store(true, Ordering::Release);
+    }
+}
+
+impl Drop for ShmBuffer {
+    fn drop(&mut self) {
+        self.buffer.destroy();
+        self.pool.destroy();
+        unsafe {
+            let _ = munmap(self.map as _, self.len);
+        }
+    }
+}
+
+impl Dispatch<WlRegistry, GlobalListContents> for Protocol {
+    // Globals that come and go later aren't used
+    fn event(
+        _: &mut Self,
+        _: &WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<WlSeat, ()> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        seat: &WlSeat,
+        event: wl_seat::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        let capabilities = match event {
+            wl_seat::Event::Capabilities {
+                capabilities: WEnum::Value(capabilities),
+            } => capabilities,
+            _ => return,
+        };
+
+        let has_keyboard = capabilities.contains(wl_seat::Capability::Keyboard);
+        if has_keyboard && protocol.keyboard.is_none() {
+            protocol.keyboard = Some(seat.get_keyboard(qh, ()));
+        } else if !has_keyboard {
+            if let Some(keyboard) = protocol.keyboard.take() {
+                if keyboard.version() >= 3 {
+                    keyboard.release();
+                }
+            }
+        }
+
+        let has_pointer = capabilities.contains(wl_seat::Capability::Pointer);
+        if has_pointer && protocol.pointer.is_none() {
+            let pointer = seat.get_pointer(qh, ());
+            protocol.cursor_device = protocol
+                .cursor_shape
+                .as_ref()
+                .map(|manager| manager.get_pointer(&pointer, qh, ()));
+            protocol.pointer = Some(pointer);
+        } else if !has_pointer {
+            if let Some(device) = protocol.cursor_device.take() {
+                device.destroy();
+            }
+            if let Some(pointer) = protocol.pointer.take() {
+                if pointer.version() >= 3 {
+                    pointer.release();
+                }
+            }
+        }
+    }
+}
+
+impl Dispatch<WlPointer, ()> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        _: &WlPointer,
+        event: wl_pointer::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let incoming = match event {
+            wl_pointer::Event::Enter {
+                serial,
+                surface,
+                surface_x,
+                surface_y,
+            } => match surface.data::<WindowId>() {
+                Some(window) => Incoming::PointerEnter {
+                    window: *window,
+                    serial,
+                    pos: (surface_x, surface_y),
+                },
+                None => return,
+            },
+            wl_pointer::Event::Leave { surface, .. } => match surface.data::<WindowId>() {
+                Some(window) => Incoming::PointerLeave(*window),
+                None => return,
+            },
+            wl_pointer::Event::Motion {
+                surface_x,
+                surface_y,
+                ..
+            } => Incoming::PointerMotion((surface_x, surface_y)),
+            wl_pointer::Event::Button {
+                serial,
+                time,
+                button,
+                state,
+            } => Incoming::PointerButton {
+                serial,
+                time,
+                button,
+                pressed: state == WEnum::Value(wl_pointer::ButtonState::Pressed),
+            },
+            _ => return,
+        };
+
+        protocol.incoming.push_back(incoming);
+    }
+}
+
+// Keys are read with the compositor's keymap. Until it came, only those that don't type
+// characters are told apart.
+impl Dispatch<WlKeyboard, ()> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        _: &WlKeyboard,
+        event: wl_keyboard::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let incoming = match event {
+            wl_keyboard::Event::Enter { surface, .. } => match surface.data::<WindowId>() {
+                Some(window) => Incoming::KeyboardEnter(*window),
+                None => return,
+            },
+            wl_keyboard::Event::Leave { surface, .. } => match surface.data::<WindowId>() {
+                Some(window) => Incoming::KeyboardLeave(*window),
+                None => return,
+            },
+            wl_keyboard::Event::Keymap {
+                format: WEnum::Value(wl_keyboard::KeymapFormat::XkbV1),
+                fd,
+                size,
+            } => {
+                match read_keymap(fd, size) {
+                    Ok(keymap) => protocol.keymap = Some(keymap),
+                    Err(err) => error::report_error(&err),
+                }
+                return;
+            }
+            wl_keyboard::Event::Modifiers {
+                mods_depressed,
+                mods_latched,
+                mods_locked,
+                group,
+                ..
+            } => {
+                protocol.modifiers = mods_depressed | mods_latched | mods_locked;
+                protocol.group = group as usize;
+                return;
+            }
+            wl_keyboard::Event::Key {
+                key,
+                state: WEnum::Value(wl_keyboard::KeyState::Pressed),
+                ..
+            } => {
+                let symbols = protocol
+                    .keymap
+                    .as_ref()
+                    .and_then(|keymap| keymap.symbols(key + XKB_KEYCODE_OFFSET));
+                match symbols {
+                    Some(symbols) => Incoming::Key {
+                        key: symbols.key(),
+                        text: symbols.text(protocol.group, protocol.modifiers),
+                    },
+                    None => Incoming::Key {
+                        key: key_from_code(key),
+                        text: None,
+                    },
+                }
+            }
+            _ => return,
+        };
+
+        protocol.incoming.push_back(incoming);
+    }
+}
+
+impl Dispatch<WlSurface, WindowId> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        _: &WlSurface,
+        event: wl_surface::Event,
+        window: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_surface::Event::PreferredBufferScale { factor } = event {
+            protocol
+                .incoming
+                .push_back(Incoming::Scale(*window, factor.max(1)));
+        }
+    }
+}
+
+impl Dispatch<WlCallback, WindowId> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        _: &WlCallback,
+        event: wl_callback::Event,
+        window: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_callback::Event::Done { .. } = event {
+            protocol.incoming.push_back(Incoming::FrameDone(*window));
+        }
+    }
+}
+
+impl Dispatch<WlBuffer, Arc<AtomicBool>> for Protocol {
+    fn event(
+        _: &mut Self,
+        _: &WlBuffer,
+        event: wl_buffer::Event,
+        busy: &Arc<AtomicBool>,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_buffer::Event::Release = event {
+            busy.store(false, Ordering::Release);
+        }
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for Protocol {
+    fn event(
+        _: &mut Self,
+        wm_base: &XdgWmBase,
+        event: xdg_wm_base::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_wm_base::Event::Ping { serial } = event {
+            wm_base.pong(serial);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, WindowId> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        _: &XdgSurface,
+        event: xdg_surface::Event,
+        window: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_surface::Event::Configure { serial } = event {
+            let toplevel = protocol.configures.remove(window);
+            protocol.incoming.push_back(Incoming::Configure {
+                window: *window,
+                serial,
+                toplevel,
+            });
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, WindowId> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        _: &XdgToplevel,
+        event: xdg_toplevel::Event,
+        window: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            xdg_toplevel::Event::Configure {
+                width,
+                height,
+                states,
+            } => {
+                let states = states
+                    .chunks_exact(4)
+                    .filter_map(|state| {
+                        let state = u32::from_ne_bytes(state.try_into().ok()?);
+                        xdg_toplevel::State::try_from(state).ok()
+                    })
+                    .collect::<Vec<_>>();
+
+                let toplevel = Toplevel {
+                    size: (width > 0 && height > 0).then_some((width, height)),
+                    maximized: states.contains(&xdg_toplevel::State::Maximized),
+                    activated: states.contains(&xdg_toplevel::State::Activated),
+                };
+                protocol.configures.insert(*window, toplevel);
+            }
+            xdg_toplevel::Event::Close => protocol.incoming.push_back(Incoming::Close(*window)),
+            _ => {}
+        }
+    }
+}
+
+// Where the compositor actually put a popup is of no interest, the menu keeps its layout. Once
+// the compositor dismisses it, e.g. for a click elsewhere, it gets a close request.
+impl Dispatch<XdgPopup, WindowId> for Protocol {
+    fn event(
+        protocol: &mut Self,
+        _: &XdgPopup,
+        event: xdg_popup::Event,
+        window: &WindowId,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_popup::Event::PopupDone = event {
+            protocol.incoming.push_back(Incoming::Close(*window));
+        }
+    }
+}
+
+delegate_noop!(Protocol: WlCompositor);
+delegate_noop!(Protocol: XdgPositioner);
+delegate_noop!(Protocol: WlShmPool);
+delegate_noop!(Protocol: ignore WlShm);
+delegate_noop!(Protocol: WpCursorShapeManagerV1);
+delegate_noop!(Protocol: WpCursorShapeDeviceV1);
+
+// `code` is an evdev key code.
+fn key_from_code(code: u32) -> Key {
+    match code {
+        KEY_UP => Key::Up,
+        KEY_DOWN => Key::Down,
+        KEY_LEFT => Key::Left,
+        KEY_RIGHT => Key::Right,
+        KEY_HOME => Key::Home,
+        KEY_END => Key::End,
+        KEY_ENTER | KEY_KPENTER => Key::Enter,
+        KEY_ESC => Key::Escape,
+        KEY_SPACE => Key::Space,
+        KEY_TAB => Key::Tab,
+        KEY_BACKSPACE => Key::Backspace,
+        _ => Key::Other,
+    }
+}
+
+// The keymap is mapped rather than read, the compositor may hand the same file to every
+// client. It ends with a nul.
+fn read_keymap(fd: OwnedFd, size: u32) -> Result<Keymap, Error> {
+    let len = size as usize;
+    let map = unsafe { mmap(null_mut(), len, ProtFlags::READ, MapFlags::PRIVATE, &fd, 0) }
+        .or_display("mmap")?;
+    let bytes = unsafe { std::slice::from_raw_parts(map as *const u8, len) };
+    let text =
+        String::from_utf8_lossy(bytes.split(|byte| *byte == 0).next().unwrap_or(&[])).into_owned();
+    unsafe {
+        let _ = munmap(map, len);
+    }
+
+    Keymap::parse(&text)
+        .ok_or_else(|| Error::display("wl_keyboard.keymap", "no keys in the keymap"))
+}
+
+fn set_button(buttons: &mut MouseButtons, button: MouseButton, down: bool) {
+    match button {
+        MouseButton::Left => buttons.left = down,
+        MouseButton::Right => buttons.right = down,
+        MouseButton::Middle => buttons.middle = down,
+    }
+}
+
+fn resize_edge(zone: HitZone) -> Option<ResizeEdge> {
+    let edge = match zone {
+        HitZone::TopLeft => ResizeEdge::TopLeft,
+        HitZone::Top => ResizeEdge::Top,
+        HitZone::TopRight => ResizeEdge::TopRight,
+        HitZone::Right => ResizeEdge::Right,
+        HitZone::BottomRight => ResizeEdge::BottomRight,
+        HitZone::Bottom => ResizeEdge::Bottom,
+        HitZone::BottomLeft => ResizeEdge::BottomLeft,
+        HitZone::Left => ResizeEdge::Left,
+        _ => return None,
+    };
+    Some(edge)
+}
+
+fn cursor_shape(zone: HitZone) -> Shape {
+    match zone {
+        HitZone::TopLeft => Shape::NwResize,
+        HitZone::Top => Shape::NResize,
+        HitZone::TopRight => Shape::NeResize,
+        HitZone::Right => Shape::EResize,
+        HitZone::BottomRight => Shape::SeResize,
+        HitZone::Bottom => Shape::SResize,
+        HitZone::BottomLeft => Shape::SwResize,
+        HitZone::Left => Shape::WResize,
+        _ => Shape::Default,
+    }
+}
@@ -0,0 +1,236 @@
+use std::any::Any;
+use std::time::Duration;
+
+use crate::canvas::Canvas;
+use crate::dpi::Dpi;
+use crate::geometry::{Rect, Size};
+use crate::layout::FlexItem;
+use crate::region::DirtyRegion;
+
+// Collects what widgets ask of the window hosting them while handling an event. The host
+// applies it afterwards: invalidating the damaged areas and running the frame timer.
+#[derive(Default)]
+pub struct Context {
+    now: Duration,
+    dpi: Dpi,
+    dirty: DirtyRegion,
+    wants_frames: bool,
+}
+
+impl Context {
+    pub fn new(now: Duration, dpi: Dpi) -> Self {
+        Self {
+            now,
+            dpi,
+            ..Default::default()
+        }
+    }
+
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    pub fn dpi(&self) -> Dpi {
+        self.dpi
+    }
+
+    pub fn invalidate(&mut self, rect: Rect) {
+        self.dirty.add(rect);
+    }
+
+    pub fn request_frames(&mut self) {
+        self.wants_frames = true;
+    }
+
+    pub fn dirty(&self) -> &DirtyRegion {
+        &self.dirty
+    }
+
+    pub fn wants_frames(&self) -> bool {
+        self.wants_frames
+    }
+}
+
+// Lets the owner of a `dyn Widget` get back to the concrete type.
+pub trait AsAny {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: Any> AsAny for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+// A windowless component. It lives inside a window (or another widget), paints into the
+// window's canvas and gets its input routed from the parent. Coordinates are client
+// coordinates of the hosting window.
+pub trait Widget: AsAny {
+    fn bounds(&self) -> Rect;
+    fn set_bounds(&mut self, bounds: Rect);
+    fn paint(&self, canvas: &mut dyn Canvas);
+
+    fn hit_test(&self, x: i32, y: i32) -> bool {
+        self.bounds().contains_point(x, y)
+    }
+
+    // Size the widget would like to have given the space available and its children, each
+    // with the size it measured.
+    fn measure(&self, _available: Size, _children: &[FlexItem]) -> Size {
+        self.bounds().size()
+    }
+
+    // Where the children go once the widget was given `bounds`. `None` leaves them where
+    // they are.
+    fn arrange(&self, _bounds: Rect, _children: &[FlexItem]) -> Option<Vec<Rect>> {
+        None
+    }
+
+    fn is_focusable(&self) -> bool {
+        false
+    }
+
+    fn on_focus_changed(&mut self, _focused: bool, _ctx: &mut Context) {}
+
+    // Called when the widget is added to a tree and whenever the window moves to a monitor with
+    // a different DPI. A layout pass follows, so only the scaled values need updating.
+    fn on_dpi_changed(&mut self, _dpi: Dpi) {}
+
+    fn on_mouse_move(&mut self, _x: i32, _y: i32, _left_down: bool, _ctx: &mut Context) {}
+
+    fn on_mouse_leave(&mut self, _ctx: &mut Context) {}
+
+    // Returning true captures the mouse for this widget until the button is released.
+    fn on_mouse_down(&mut self, _x: i32, _y: i32, _ctx: &mut Context) -> bool {
+        false
+    }
+
+    fn on_mouse_up(&mut self, _x: i32, _y: i32, _ctx: &mut Context) {}
+
+    // Called on every tick of the frame timer, returns whether it still needs more frames.
+    fn on_frame(&mut self, _ctx: &mut Context) -> bool {
+        false
+    }
+}
+
+// Hover and capture bookkeeping for a parent routing mouse input to a list of children.
+// Children are passed in paint order, so later ones are on top.
+#[derive(Debug, Default)]
+pub struct InputRouter {
+    hover: Option<usize>,
+    capture: Option<usize>,
+}
+
+impl InputRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hover(&self) -> Option<usize> {
+        self.hover
+    }
+
+    pub fn capture(&self) -> Option<usize> {
+        self.capture
+    }
+
+    pub fn hit(widgets: &[&mut dyn Widget], x: i32, y: i32) -> Option<usize> {
+        widgets.iter().rposition(|widget| widget.hit_test(x, y))
+    }
+
+    fn set_hover(
+        &mut self,
+        widgets: &mut [&mut dyn Widget],
+        hover: Option<usize>,
+        ctx: &mut Context,
+    ) {
+        if self.hover == hover {
+            return;
+        }
+
+        if let Some(widget) = self.hover.and_then(|idx| widgets.get_mut(idx)) {
+            widget.on_mouse_leave(ctx);
+        }
+
+        self.hover = hover;
+    }
+
+    pub fn mouse_move(
+        &mut self,
+        widgets: &mut [&mut dyn Widget],
+        x: i32,
+        y: i32,
+        left_down: bool,
+        ctx: &mut Context,
+    ) {
+        let target = match self.capture {
+            Some(capture) => Some(capture),
+            None => {
+                let hit = Self::hit(widgets, x, y);
+                self.set_hover(widgets, hit, ctx);
+                hit
+            }
+        };
+
+        if let Some(widget) = target.and_then(|idx| widgets.get_mut(idx)) {
+            widget.on_mouse_move(x, y, left_down, ctx);
+        }
+    }
+
+    pub fn mouse_leave(&mut self, widgets: &mut [&mut dyn Widget], ctx: &mut Context) {
+        if self.capture.is_none() {
+            self.set_hover(widgets, None, ctx);
+        }
+    }
+
+    pub fn mouse_down(
+        &mut self,
+        widgets: &mut [&mut dyn Widget],
+        x: i32,
+        y: i32,
+        ctx: &mut Context,
+    ) -> bool {
+        let hit = Self::hit(widgets, x, y);
+        self.set_hover(widgets, hit, ctx);
+
+        if let Some(idx) = hit {
+            if widgets[idx].on_mouse_down(x, y, ctx) {
+                self.capture = Some(idx);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    pub fn mouse_up(&mut self, widgets: &mut [&mut dyn Widget], x: i32, y: i32, ctx: &mut Context) {
+        let target = self.capture.take().or_else(|| Self::hit(widgets, x, y));
+
+        if let Some(widget) = target.and_then(|idx| widgets.get_mut(idx)) {
+            widget.on_mouse_up(x, y, ctx);
+        }
+
+        // The pointer may have been released over a different widget than the captured one
+        let hit = Self::hit(widgets, x, y);
+        self.set_hover(widgets, hit, ctx);
+    }
+
+    pub fn is_captured(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    pub fn frame(widgets: &mut [&mut dyn Widget], ctx: &mut Context) -> bool {
+        let mut is_animating = false;
+
+        for widget in widgets.iter_mut() {
+            is_animating |= widget.on_frame(ctx);
+        }
+
+        is_animating
+    }
+}
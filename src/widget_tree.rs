@@ -0,0 +1,621 @@
+use crate::canvas::Canvas;
+use crate::dpi::Dpi;
+use crate::geometry::{Rect, Size};
+use crate::layout::FlexItem;
+use crate::widget::{Context, Widget};
+
+// The slot of the widget and which of the widgets that lived in that slot it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WidgetId {
+    index: usize,
+    generation: u32,
+}
+
+struct Slot {
+    generation: u32,
+    node: Option<Node>,
+}
+
+struct Node {
+    widget: Box<dyn Widget>,
+    parent: Option<WidgetId>,
+    children: Vec<WidgetId>,
+    visible: bool,
+    z_index: i32,
+    // Layout parameters for the parent, `size` holds the result of the last measure pass.
+    item: FlexItem,
+}
+
+// A widget that only groups its children. It paints nothing and lets input through to
+// whatever is behind it.
+pub struct Panel {
+    bounds: Rect,
+}
+
+impl Panel {
+    pub fn new(bounds: Rect) -> Self {
+        Self { bounds }
+    }
+}
+
+impl Widget for Panel {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    fn paint(&self, _canvas: &mut dyn Canvas) {}
+
+    fn hit_test(&self, _x: i32, _y: i32) -> bool {
+        false
+    }
+}
+
+// Owns all widgets of a window. Slots of removed widgets are reused, but under a new
+// generation, so a stale id just stops resolving instead of pointing at some other widget.
+pub struct WidgetTree {
+    slots: Vec<Slot>,
+    free: Vec<usize>,
+    root: WidgetId,
+    hover: Option<WidgetId>,
+    capture: Option<WidgetId>,
+    focus: Option<WidgetId>,
+    dpi: Dpi,
+}
+
+impl WidgetTree {
+    pub fn new(bounds: Rect) -> Self {
+        let root = Node {
+            widget: Box::new(Panel::new(bounds)),
+            parent: None,
+            children: Vec::new(),
+            visible: true,
+            z_index: 0,
+            item: FlexItem::new(bounds.size()),
+        };
+
+        Self {
+            slots: vec![Slot {
+                generation: 0,
+                node: Some(root),
+            }],
+            free: Vec::new(),
+            root: WidgetId {
+                index: 0,
+                generation: 0,
+            },
+            hover: None,
+            capture: None,
+            focus: None,
+            dpi: Dpi::DEFAULT,
+        }
+    }
+
+    pub fn root(&self) -> WidgetId {
+        self.root
+    }
+
+    fn node(&self, id: WidgetId) -> Option<&Node> {
+        self.slots
+            .get(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_ref())
+    }
+
+    fn node_mut(&mut self, id: WidgetId) -> Option<&mut Node> {
+        self.slots
+            .get_mut(id.index)
+            .filter(|slot| slot.generation == id.generation)
+            .and_then(|slot| slot.node.as_mut())
+    }
+
+    fn nodes_mut(&mut self) -> impl Iterator<Item = &mut Node> {
+        self.slots.iter_mut().filter_map(|slot| slot.node.as_mut())
+    }
+
+    // The number of slots in use, live widgets and free ones.
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn contains(&self, id: WidgetId) -> bool {
+        self.node(id).is_some()
+    }
+
+    pub fn add_child(&mut self, parent: WidgetId, widget: Box<dyn Widget>) -> Option<WidgetId> {
+        let index = self.node(parent)?.children.len();
+        self.insert_child(parent, index, widget)
+    }
+
+    pub fn insert_child(
+        &mut self,
+        parent: WidgetId,
+        index: usize,
+        mut widget: Box<dyn Widget>,
+    ) -> Option<WidgetId> {
+        self.node(parent)?;
+        widget.on_dpi_changed(self.dpi);
+
+        let node = Node {
+            item: FlexItem::new(widget.bounds().size()),
+            widget,
+            parent: Some(parent),
+            children: Vec::new(),
+            visible: true,
+            z_index: 0,
+        };
+
+        let id = match self.free.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index];
+                slot.node = Some(node);
+                WidgetId {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                self.slots.push(Slot {
+                    generation: 0,
+                    node: Some(node),
+                });
+                WidgetId {
+                    index: self.slots.len() - 1,
+                    generation: 0,
+                }
+            }
+        };
+
+        let children = &mut self.node_mut(parent)?.children;
+        children.insert(index.min(children.len()), id);
+
+        Some(id)
+    }
+
+    // Removes the widget with its whole subtree and hands the widget back. The root can't be
+    // removed.
+    pub fn remove(&mut self, id: WidgetId) -> Option<Box<dyn Widget>> {
+        let parent = self.node(id)?.parent?;
+
+        if let Some(parent) = self.node_mut(parent) {
+            parent.children.retain(|child| *child != id);
+        }
+
+        let mut removed = Vec::new();
+        self.collect_subtree(id, &mut removed);
+
+        for other in &removed {
+            if self.hover == Some(*other) {
+                self.hover = None;
+            }
+            if self.capture == Some(*other) {
+                self.capture = None;
+            }
+            if self.focus == Some(*other) {
+                self.focus = None;
+            }
+        }
+
+        let mut widget = None;
+        for other in removed {
+            let slot = &mut self.slots[other.index];
+            let node = slot.node.take();
+            slot.generation = slot.generation.wrapping_add(1);
+            self.free.push(other.index);
+
+            if other == id {
+                widget = node.map(|node| node.widget);
+            }
+        }
+
+        widget
+    }
+
+    fn collect_subtree(&self, id: WidgetId, out: &mut Vec<WidgetId>) {
+        if let Some(node) = self.node(id) {
+            out.push(id);
+            for child in &node.children {
+                self.collect_subtree(*child, out);
+            }
+        }
+    }
+
+    pub fn parent(&self, id: WidgetId) -> Option<WidgetId> {
+        self.node(id)?.parent
+    }
+
+    pub fn children(&self, id: WidgetId) -> &[WidgetId] {
+        match self.node(id) {
+            Some(node) => &node.children,
+            None => &[],
+        }
+    }
+
+    pub fn widget(&self, id: WidgetId) -> Option<&dyn Widget> {
+        self.node(id).map(|node| node.widget.as_ref())
+    }
+
+    pub fn widget_mut(&mut self, id: WidgetId) -> Option<&mut (dyn Widget + 'static)> {
+        self.node_mut(id).map(|node| node.widget.as_mut())
+    }
+
+    pub fn get<T: Widget + 'static>(&self, id: WidgetId) -> Option<&T> {
+        self.widget(id)?.as_any().downcast_ref()
+    }
+
+    pub fn get_mut<T: Widget + 'static>(&mut self, id: WidgetId) -> Option<&mut T> {
+        self.widget_mut(id)?.as_any_mut().downcast_mut()
+    }
+
+    pub fn is_visible(&self, id: WidgetId) -> bool {
+        self.node(id).is_some_and(|node| node.visible)
+    }
+
+    // Whether the widget and all of its ancestors are visible.
+    pub fn is_shown(&self, id: WidgetId) -> bool {
+        let mut current = Some(id);
+
+        while let Some(id) = current {
+            match self.node(id) {
+                Some(node) if node.visible => current = node.parent,
+                _ => return false,
+            }
+        }
+
+        true
+    }
+
+    pub fn set_visible(&mut self, id: WidgetId, visible: bool) {
+        if let Some(node) = self.node_mut(id) {
+            node.visible = visible;
+        }
+
+        if !visible {
+            // Hidden widgets lose hover, capture and focus, including their descendants
+            for other in [self.hover, self.capture, self.focus].into_iter().flatten() {
+                if !self.is_shown(other) {
+                    if self.hover == Some(other) {
+                        self.hover = None;
+                    }
+                    if self.capture == Some(other) {
+                        self.capture = None;
+                    }
+                    if self.focus == Some(other) {
+                        self.focus = None;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn flex(&self, id: WidgetId) -> FlexItem {
+        self.node(id)
+            .map_or_else(FlexItem::default, |node| node.item)
+    }
+
+    // How the widget is sized and placed by a layout container parent.
+    pub fn set_flex(&mut self, id: WidgetId, item: FlexItem) {
+        if let Some(node) = self.node_mut(id) {
+            node.item = FlexItem {
+                size: node.item.size,
+                ..item
+            };
+        }
+    }
+
+    pub fn z_index(&self, id: WidgetId) -> i32 {
+        self.node(id).map_or(0, |node| node.z_index)
+    }
+
+    // Siblings with a higher z-index paint on top. Siblings with the same one paint in the
+    // order they were added.
+    pub fn set_z_index(&mut self, id: WidgetId, z_index: i32) {
+        if let Some(node) = self.node_mut(id) {
+            node.z_index = z_index;
+        }
+    }
+
+    // Raises the widget above all of its siblings. Only the z-index changes, so the layout
+    // order stays the same.
+    pub fn bring_to_front(&mut self, id: WidgetId) {
+        let top = self
+            .siblings(id)
+            .map(|sibling| self.z_index(sibling))
+            .max()
+            .unwrap_or(0);
+        self.set_z_index(id, top + 1);
+    }
+
+    pub fn send_to_back(&mut self, id: WidgetId) {
+        let bottom = self
+            .siblings(id)
+            .map(|sibling| self.z_index(sibling))
+            .min()
+            .unwrap_or(0);
+        self.set_z_index(id, bottom - 1);
+    }
+
+    fn siblings(&self, id: WidgetId) -> impl Iterator<Item = WidgetId> + '_ {
+        let parent = self.parent(id);
+        parent
+            .map(|parent| self.children(parent))
+            .unwrap_or(&[])
+            .iter()
+            .copied()
+            .filter(move |sibling| *sibling != id)
+    }
+
+    // Visible children in the order they were added, which is the order layouts use.
+    fn visible_children(&self, id: WidgetId) -> Vec<WidgetId> {
+        self.children(id)
+            .iter()
+            .copied()
+            .filter(|child| self.is_visible(*child))
+            .collect()
+    }
+
+    // Visible children in paint order, bottom first.
+    fn ordered_children(&self, id: WidgetId) -> Vec<WidgetId> {
+        let mut children = self.visible_children(id);
+        children.sort_by_key(|child| self.z_index(*child));
+        children
+    }
+
+    // Every shown widget, parents before their children and bottom before top.
+    pub fn paint_order(&self) -> Vec<WidgetId> {
+        let mut order = Vec::new();
+        if self.is_visible(self.root) {
+            self.collect_paint_order(self.root, &mut order);
+        }
+        order
+    }
+
+    fn collect_paint_order(&self, id: WidgetId, out: &mut Vec<WidgetId>) {
+        out.push(id);
+        for child in self.ordered_children(id) {
+            self.collect_paint_order(child, out);
+        }
+    }
+
+    // Paints the widgets that overlap `rect`.
+    pub fn paint(&self, canvas: &mut dyn Canvas, rect: &Rect) {
+        for id in self.paint_order() {
+            if let Some(node) = self.node(id) {
+                if node.widget.bounds().intersects(rect) {
+                    node.widget.paint(canvas);
+                }
+            }
+        }
+    }
+
+    // The topmost shown widget under the point.
+    pub fn hit_test(&self, x: i32, y: i32) -> Option<WidgetId> {
+        self.paint_order().into_iter().rev().find(|id| {
+            self.node(*id)
+                .is_some_and(|node| node.widget.hit_test(x, y))
+        })
+    }
+
+    pub fn dpi(&self) -> Dpi {
+        self.dpi
+    }
+
+    // Tells every widget about the new DPI. The caller is expected to run a layout pass after.
+    pub fn set_dpi(&mut self, dpi: Dpi) {
+        if self.dpi == dpi {
+            return;
+        }
+
+        self.dpi = dpi;
+        for node in self.nodes_mut() {
+            node.widget.on_dpi_changed(dpi);
+        }
+    }
+
+    // Runs the measure pass bottom up and then places everything inside `bounds`.
+    pub fn layout(&mut self, bounds: Rect) {
+        let root = self.root;
+        self.measure(root, bounds.size());
+        self.arrange(root, bounds);
+    }
+
+    fn measure(&mut self, id: WidgetId, available: Size) -> Size {
+        let children = self.visible_children(id);
+        for child in &children {
+            self.measure(*child, available);
+        }
+        let items = self.items(&children);
+
+        match self.node_mut(id) {
+            Some(node) => {
+                node.item.size = node.widget.measure(available, &items);
+                node.item.size
+            }
+            None => Size::default(),
+        }
+    }
+
+    fn items(&self, children: &[WidgetId]) -> Vec<FlexItem> {
+        children
+            .iter()
+            .filter_map(|child| self.node(*child).map(|node| node.item))
+            .collect()
+    }
+
+    fn arrange(&mut self, id: WidgetId, bounds: Rect) {
+        let children = self.visible_children(id);
+        let items = self.items(&children);
+
+        let rects = match self.node_mut(id) {
+            Some(node) => {
+                if node.widget.bounds() != bounds {
+                    node.widget.set_bounds(bounds);
+                }
+                node.widget.arrange(bounds, &items)
+            }
+            None => return,
+        };
+
+        for (idx, child) in children.into_iter().enumerate() {
+            let rect = match rects.as_ref().and_then(|rects| rects.get(idx)) {
+                Some(rect) => *rect,
+                None => match self.widget(child) {
+                    Some(widget) => widget.bounds(),
+                    None => continue,
+                },
+            };
+
+            self.arrange(child, rect);
+        }
+    }
+
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.focus
+    }
+
+    // Focusable shown widgets in tree order, which is also the tab order.
+    pub fn focus_order(&self) -> Vec<WidgetId> {
+        self.paint_order()
+            .into_iter()
+            .filter(|id| self.widget(*id).is_some_and(|widget| widget.is_focusable()))
+            .collect()
+    }
+
+    pub fn set_focus(&mut self, id: Option<WidgetId>, ctx: &mut Context) {
+        let id = id.filter(|id| {
+            self.is_shown(*id) && self.widget(*id).is_some_and(|widget| widget.is_focusable())
+        });
+
+        if self.focus == id {
+            return;
+        }
+
+        if let Some(widget) = self.focus.and_then(|old| self.widget_mut(old)) {
+            widget.on_focus_changed(false, ctx);
+        }
+
+        self.focus = id;
+
+        if let Some(widget) = id.and_then(|new| self.widget_mut(new)) {
+            widget.on_focus_changed(true, ctx);
+        }
+    }
+
+    pub fn focus_next(&mut self, ctx: &mut Context) {
+        self.move_focus(1, ctx);
+    }
+
+    pub fn focus_previous(&mut self, ctx: &mut Context) {
+        self.move_focus(-1, ctx);
+    }
+
+    fn move_focus(&mut self, step: isize, ctx: &mut Context) {
+        let order = self.focus_order();
+        if order.is_empty() {
+            return;
+        }
+
+        let len = order.len() as isize;
+        let next = match self
+            .focus
+            .and_then(|focus| order.iter().position(|id| *id == focus))
+        {
+            Some(current) => (current as isize + step).rem_euclid(len),
+            None if step > 0 => 0,
+            None => len - 1,
+        };
+
+        self.set_focus(Some(order[next as usize]), ctx);
+    }
+
+    pub fn hover(&self) -> Option<WidgetId> {
+        self.hover
+    }
+
+    pub fn is_captured(&self) -> bool {
+        self.capture.is_some()
+    }
+
+    fn set_hover(&mut self, hover: Option<WidgetId>, ctx: &mut Context) {
+        if self.hover == hover {
+            return;
+        }
+
+        if let Some(widget) = self.hover.and_then(|old| self.widget_mut(old)) {
+            widget.on_mouse_leave(ctx);
+        }
+
+        self.hover = hover;
+    }
+
+    pub fn mouse_move(&mut self, x: i32, y: i32, left_down: bool, ctx: &mut Context) {
+        let target = match self.capture {
+            Some(capture) => Some(capture),
+            None => {
+                let hit = self.hit_test(x, y);
+                self.set_hover(hit, ctx);
+                hit
+            }
+        };
+
+        if let Some(widget) = target.and_then(|id| self.widget_mut(id)) {
+            widget.on_mouse_move(x, y, left_down, ctx);
+        }
+    }
+
+    pub fn mouse_leave(&mut self, ctx: &mut Context) {
+        if self.capture.is_none() {
+            self.set_hover(None, ctx);
+        }
+    }
+
+    // Returns whether the widget under the point captured the mouse.
+    pub fn mouse_down(&mut self, x: i32, y: i32, ctx: &mut Context) -> bool {
+        let hit = self.hit_test(x, y);
+        self.set_hover(hit, ctx);
+
+        let id = match hit {
+            Some(id) => id,
+            None => return false,
+        };
+
+        if self.widget(id).is_some_and(|widget| widget.is_focusable()) {
+            self.set_focus(Some(id), ctx);
+        }
+
+        let captured = self
+            .widget_mut(id)
+            .is_some_and(|widget| widget.on_mouse_down(x, y, ctx));
+        if captured {
+            self.capture = Some(id);
+        }
+
+        captured
+    }
+
+    pub fn mouse_up(&mut self, x: i32, y: i32, ctx: &mut Context) {
+        let target = self.capture.take().or_else(|| self.hit_test(x, y));
+
+        if let Some(widget) = target.and_then(|id| self.widget_mut(id)) {
+            widget.on_mouse_up(x, y, ctx);
+        }
+
+        // The pointer may have been released over a different widget than the captured one
+        let hit = self.hit_test(x, y);
+        self.set_hover(hit, ctx);
+    }
+
+    // Steps every widget, hidden ones included, so their animations stay on time.
+    pub fn frame(&mut self, ctx: &mut Context) -> bool {
+        let mut is_animating = false;
+
+        for node in self.nodes_mut() {
+            is_animating |= node.widget.on_frame(ctx);
+        }
+
+        is_animating
+    }
+}
@@ -0,0 +1,348 @@
+use std::cell::Cell;
+use std::mem;
+use std::ptr::{null, null_mut};
+use std::rc::Rc;
+use std::time::Duration;
+
+use winapi::shared::minwindef::{FALSE, HINSTANCE, TRUE};
+use winapi::shared::windef::{HWND, POINT};
+use winapi::um::d2d1::ID2D1Factory;
+use winapi::um::libloaderapi::GetModuleHandleW;
+use winapi::um::winuser::*;
+
+use crate::animation::{Clock, SystemClock};
+use crate::component::{self, WindowData};
+use crate::d2d_canvas::D2DDeviceFactory;
+use crate::dpi::Dpi;
+use crate::error::Error;
+use crate::event::EventHandler;
+use crate::geometry::{Point, Rect, Size};
+use crate::host::{Host, Hosted};
+use crate::macros::{werr_ifeq, werr_ifisnull};
+use crate::platform::{Platform, SystemCommand, WindowId, WindowParams};
+use crate::wutils;
+
+pub struct Win32Platform {
+    h_inst: HINSTANCE,
+    d2d_factory: &'static ID2D1Factory,
+    clock: Rc<SystemClock>,
+}
+
+impl Win32Platform {
+    pub fn new() -> Result<Self, Error> {
+        Ok(Self {
+            h_inst: werr_ifisnull!(GetModuleHandleW(null()))?,
+            d2d_factory: wutils::create_d2d_factory()?,
+            clock: Rc::new(SystemClock::new()),
+        })
+    }
+
+    pub fn h_inst(&self) -> HINSTANCE {
+        self.h_inst
+    }
+
+    pub fn hwnd(window: WindowId) -> HWND {
+        window.0 as HWND
+    }
+
+    // Runs the message loop until the app quits.
+    pub fn run(&self) {
+        let mut msg: MSG = unsafe { mem::zeroed() };
+        unsafe {
+            while GetMessageW(&mut msg, null_mut(), 0, 0) == TRUE {
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+        }
+    }
+}
+
+impl Platform for Win32Platform {
+    type DeviceFactory = D2DDeviceFactory<'static>;
+
+    fn create_window<C: EventHandler + 'static>(
+        &self,
+        params: &WindowParams,
+        component: C,
+    ) -> Result<Hosted<C>, Error> {
+        wutils::register_class(self.h_inst, params.class_name, component::wnd_proc::<C>)?;
+
+        let window_style = WS_THICKFRAME   // required for a standard resizeable window
+        | WS_SYSMENU      // Explicitly ask for the titlebar to support snapping via Win + ← / Win + →
+        | WS_MAXIMIZEBOX  // Add maximize button to support maximizing via mouse dragging
+                        // to the top of the screen
+        | WS_MINIMIZEBOX  // Add minimize button to support minimizing by clicking on the taskbar icon
+        | WS_VISIBLE; // Make window visible after it is created (not important)
+
+        // Popups go where the app puts them, and are shown without taking the activation
+        let (style, ex_style, owner, origin, size) = match params.popup {
+            Some(popup) => (
+                WS_POPUP,
+                WS_EX_TOOLWINDOW | WS_EX_NOACTIVATE | WS_EX_TOPMOST,
+                Self::hwnd(popup.owner),
+                self.client_to_screen(
+                    popup.owner,
+                    Point::new(popup.bounds.left, popup.bounds.top),
+                )?,
+                popup.bounds.size(),
+            ),
+            None => (
+                window_style,
+                0,
+                null_mut(),
+                Point::new(CW_USEDEFAULT, CW_USEDEFAULT),
+                params.size,
+            ),
+        };
+
+        let host = Rc::new(Host::new(component));
+        let data = Box::into_raw(Box::new(WindowData {
+            host: host.clone(),
+            name: params.class_name,
+            custom_frame: params.custom_frame,
+            high_surrogate: Cell::new(None),
+        }));
+
+        let hwnd = werr_ifisnull!(CreateWindowExW(
+            ex_style,
+            wutils::wide_string(params.class_name).as_ptr(),
+            wutils::wide_string(&params.title).as_ptr(),
+            style | WS_CLIPCHILDREN,
+            origin.x,
+            origin.y,
+            size.width,
+            size.height,
+            owner,
+            null_mut(),
+            self.h_inst,
+            data as _,
+        ));
+
+        let hwnd = match hwnd {
+            Ok(hwnd) => hwnd,
+            Err(err) => {
+                // Without a WM_NCCREATE the window never took the data over, otherwise
+                // WM_NCDESTROY already released it
+                if Rc::strong_count(&host) == 2 {
+                    drop(unsafe { Box::from_raw(data) });
+                }
+                return Err(err);
+            }
+        };
+        if params.popup.is_some() {
+            unsafe {
+                ShowWindow(hwnd, SW_SHOWNOACTIVATE);
+            }
+        }
+
+        Ok(Hosted::new(host, WindowId(hwnd as _), move || unsafe {
+            DestroyWindow(hwnd);
+        }))
+    }
+
+    fn post_event<C: EventHandler + 'static>(
+        &self,
+        window: &Hosted<C>,
+        event: C::Custom,
+    ) -> Result<(), Error> {
+        let hwnd = Self::hwnd(window.window());
+        let event = Box::into_raw(Box::new(event));
+
+        let result = werr_ifeq!(
+            PostMessageW(hwnd, component::custom_event_message(), 0, event as _),
+            FALSE
+        );
+        if result.is_err() {
+            drop(unsafe { Box::from_raw(event) });
+        }
+
+        result.map(|_| ())
+    }
+
+    fn post_command(&self, window: WindowId, command: SystemCommand) -> Result<(), Error> {
+        let hwnd = Self::hwnd(window);
+        let (message, wparam) = match command {
+            SystemCommand::Minimize => (WM_SYSCOMMAND, SC_MINIMIZE),
+            SystemCommand::Maximize => (WM_SYSCOMMAND, SC_MAXIMIZE),
+            SystemCommand::Restore => (WM_SYSCOMMAND, SC_RESTORE),
+            SystemCommand::Close => (WM_CLOSE, 0),
+            SystemCommand::Move => (WM_SYSCOMMAND, SC_MOVE),
+            SystemCommand::Size => (WM_SYSCOMMAND, SC_SIZE),
+        };
+
+        werr_ifeq!(PostMessageW(hwnd, message, wparam, 0), FALSE)?;
+        Ok(())
+    }
+
+    fn quit(&self) {
+        unsafe { PostQuitMessage(0) };
+    }
+
+    fn device_factory(&self, window: WindowId) -> Self::DeviceFactory {
+        D2DDeviceFactory::new(self.d2d_factory, Self::hwnd(window))
+    }
+
+    fn clock(&self) -> Rc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    fn dpi(&self, window: WindowId) -> Result<Dpi, Error> {
+        Ok(Dpi::new(wutils::get_dpi_for_window(Self::hwnd(window))?))
+    }
+
+    fn client_rect(&self, window: WindowId) -> Result<Rect, Error> {
+        Ok(wutils::get_client_rect(Self::hwnd(window))?.into())
+    }
+
+    fn window_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        let mut rect = Default::default();
+        werr_ifeq!(GetWindowRect(Self::hwnd(window), &mut rect), FALSE)?;
+        Ok(rect.into())
+    }
+
+    fn set_window_bounds(&self, window: WindowId, bounds: Rect) -> Result<(), Error> {
+        werr_ifeq!(
+            SetWindowPos(
+                Self::hwnd(window),
+                null_mut(),
+                bounds.left,
+                bounds.top,
+                bounds.width(),
+                bounds.height(),
+                SWP_NOZORDER | SWP_NOACTIVATE,
+            ),
+            FALSE
+        )?;
+        Ok(())
+    }
+
+    fn resize_window(&self, window: WindowId, size: Size) -> Result<(), Error> {
+        werr_ifeq!(
+            SetWindowPos(
+                Self::hwnd(window),
+                null_mut(),
+                0,
+                0,
+                size.width,
+                size.height,
+                SWP_FRAMECHANGED | SWP_NOMOVE | SWP_NOZORDER,
+            ),
+            FALSE
+        )?;
+        Ok(())
+    }
+
+    fn restored_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        Ok(wutils::get_restored_rect(Self::hwnd(window))?.into())
+    }
+
+    fn is_maximized(&self, window: WindowId) -> Result<bool, Error> {
+        wutils::window_is_maximized(Self::hwnd(window))
+    }
+
+    fn is_active(&self, _window: WindowId) -> bool {
+        !unsafe { GetFocus() }.is_null()
+    }
+
+    fn caption_height(&self, window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        wutils::get_caption_height(Self::hwnd(window), dpi.value())
+    }
+
+    fn resize_border(&self, _window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        let frame_y = wutils::get_system_metrics_for_dpi(SM_CYFRAME, dpi.value())?;
+        let padding = wutils::get_system_metrics_for_dpi(SM_CXPADDEDBORDER, dpi.value())?;
+        Ok(frame_y + padding)
+    }
+
+    fn invalidate(&self, window: WindowId, rect: Rect) -> Result<(), Error> {
+        werr_ifeq!(
+            InvalidateRect(Self::hwnd(window), &rect.into(), FALSE),
+            FALSE
+        )?;
+        Ok(())
+    }
+
+    fn set_capture(&self, window: WindowId) {
+        unsafe { SetCapture(Self::hwnd(window)) };
+    }
+
+    fn release_capture(&self, _window: WindowId) -> Result<(), Error> {
+        werr_ifeq!(ReleaseCapture(), FALSE)?;
+        Ok(())
+    }
+
+    fn track_mouse_leave(&self, window: WindowId) -> Result<(), Error> {
+        let mut trk = TRACKMOUSEEVENT {
+            cbSize: mem::size_of::<TRACKMOUSEEVENT>() as u32,
+            dwFlags: TME_LEAVE,
+            hwndTrack: Self::hwnd(window),
+            dwHoverTime: 0,
+        };
+
+        werr_ifeq!(TrackMouseEvent(&mut trk), FALSE)?;
+        Ok(())
+    }
+
+    fn set_timer(&self, window: WindowId, id: usize, interval: Duration) -> Result<(), Error> {
+        werr_ifeq!(
+            SetTimer(Self::hwnd(window), id, interval.as_millis() as _, None),
+            0
+        )?;
+        Ok(())
+    }
+
+    fn kill_timer(&self, window: WindowId, id: usize) {
+        unsafe { KillTimer(Self::hwnd(window), id) };
+    }
+
+    fn cursor_position(&self) -> Result<Point, Error> {
+        let mut point = POINT::default();
+        werr_ifeq!(GetCursorPos(&mut point), FALSE)?;
+        Ok(Point::new(point.x, point.y))
+    }
+
+    fn client_to_screen(&self, window: WindowId, point: Point) -> Result<Point, Error> {
+        let mut point = POINT {
+            x: point.x,
+            y: point.y,
+        };
+        werr_ifeq!(ClientToScreen(Self::hwnd(window), &mut point), FALSE)?;
+        Ok(Point::new(point.x, point.y))
+    }
+
+    fn animations_enabled(&self) -> bool {
+        wutils::client_area_animation_enabled().unwrap_or(true)
+    }
+
+    fn monitors(&self) -> Result<Vec<Rect>, Error> {
+        let monitors = wutils::get_monitors()?;
+        Ok(monitors
+            .into_iter()
+            .map(|info| info.rcWork.into())
+            .collect())
+    }
+
+    fn monitor(&self, window: WindowId) -> Result<Option<Rect>, Error> {
+        let info = wutils::get_window_monitor(Self::hwnd(window))?;
+        Ok(Some(info.rcWork.into()))
+    }
+
+    fn confirm(
+        &self,
+        window: WindowId,
+        title: &str,
+        question: &str,
+    ) -> Result<Option<bool>, Error> {
+        let answer = werr_ifeq!(
+            MessageBoxW(
+                Self::hwnd(window),
+                wutils::wide_string(question).as_ptr(),
+                wutils::wide_string(title).as_ptr(),
+                MB_YESNO | MB_ICONQUESTION,
+            ),
+            0
+        )?;
+
+        Ok(Some(answer == IDYES))
+    }
+}
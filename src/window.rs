@@ -0,0 +1,2128 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::{Rc, Weak};
+use std::time::Duration;
+
+use crate::animation::{self, FrameScheduler, TimerAction};
+use crate::autosave::Autosave;
+use crate::button::{BaseButton, Button, Colors as ButtonColors, State as ButtonState};
+use crate::canvas::{Canvas, Color};
+use crate::close::{CloseProtocol, CloseReply, CloseStep, Party};
+use crate::closed_tabs::{ClosedTab, ClosedTabs};
+use crate::dpi::Dpi;
+use crate::error::{self, Error};
+use crate::event::{Event, EventHandler, HitZone, MouseButton, Reply};
+use crate::geometry::{Insets, Point, PointF, Rect, RectF};
+use crate::host::{ComponentHost, Hosted};
+use crate::layout::{Align, Flex, FlexBox, FlexItem, Spacer, Stack};
+use crate::log_debug;
+use crate::logging::Target;
+use crate::menu::{MenuEntry, MenuItem};
+use crate::menu_window::MenuWindow;
+use crate::platform::{self, Platform, SystemCommand, WindowId, WindowParams};
+use crate::popup_menu::{self, MenuOutcome, PopupMenu};
+use crate::region::DirtyRegion;
+use crate::render_resources::{DeviceError, RenderResources};
+use crate::session::{GroupSession, Placement, Session, TabSession, WindowSession};
+use crate::tab_bar::TabBar;
+use crate::tab_content::{TabContent, TabPanel};
+use crate::tab_menu::{self, TabAction};
+use crate::tab_model::{GroupId, TabEvent, TabGroup, TabId, TabModel};
+use crate::theme::Theme;
+use crate::title_editor::{EditOutcome, TitleEditor};
+use crate::units::{Dip, DipInsets, DipRect, DipSize, Rounding};
+use crate::widget::{Context, Widget};
+use crate::widget_tree::{WidgetId, WidgetTree};
+
+const WINDOW_CLASS_NAME: &str = "testwindowtabs.Window";
+const WINDOW_TITLE: &str = "the testwindowtabs application";
+const TITLE_BG_COLOR: (u8, u8, u8) = (150, 200, 180);
+const TITLE_HOVER_COLOR: (u8, u8, u8) = (130, 180, 160);
+const TITLE_DOWN_COLOR: (u8, u8, u8) = (120, 167, 148);
+const TITLE_ITEM_COLOR: (u8, u8, u8) = (33, 33, 33);
+const TITLE_ITEM_BLUR_COLOR: (u8, u8, u8) = (127, 127, 127);
+pub const ICON_DIMENSION: Dip = Dip(9.0);
+const ICON_SIZE: DipSize = DipSize {
+    width: ICON_DIMENSION,
+    height: ICON_DIMENSION,
+};
+const RESTORE_BACK_INSETS: DipInsets = DipInsets::new(2.0, 0.0, 0.0, 2.0);
+const RESTORE_FRONT_INSETS: DipInsets = DipInsets::new(0.0, 2.0, 2.0, 0.0);
+const WINDOW_SIZE: DipSize = DipSize::new(500.0, 500.0);
+const DRAG_AREA_WIDTH: Dip = Dip(100.0);
+const TAB_BAR_MARGIN: DipInsets = DipInsets::new(0.0, 2.0, 0.0, 0.0);
+const ANIMATION_TIMER_ID: usize = 1;
+const DEVICE_RETRY_TIMER_ID: usize = 2;
+const AUTOSAVE_TIMER_ID: usize = 3;
+const TOP_AND_BOTTOM_BORDERS: i32 = 2;
+const FAKE_SHADOW_HEIGHT: i32 = 1;
+// Sadly SM_CXSIZE does not result in the right size buttons for Win10
+const TITLEBAR_BUTTON_WIDTH: Dip = Dip(47.0);
+
+type ContentFn = Rc<dyn Fn(TabId, &str) -> Box<dyn TabContent>>;
+type CloseFn = Box<dyn FnMut(CloseReply)>;
+type SessionFn = Box<dyn FnMut(Session)>;
+type TabMenuItemsFn = Rc<dyn Fn(&TabModel, TabId) -> Vec<MenuEntry<TabAction>>>;
+type TabActionFn = Rc<RefCell<dyn FnMut(TabId, TabAction, &TabSession)>>;
+type AnswerFn<P> = Box<dyn FnOnce(&mut Window<P>, bool) -> Result<(), Error>>;
+
+// What picking from one of the window's menus does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MenuPick {
+    System(SystemCommand),
+    Tab(TabId, TabAction),
+    // The answer to the question asked with `Window::ask`
+    Answer(bool),
+}
+
+// A menu that is open, and the popups showing its open levels where they were placed, the
+// top one first.
+struct OpenMenu<P: Platform + 'static> {
+    menu: Rc<RefCell<PopupMenu<MenuPick>>>,
+    popups: Vec<(Rect, Hosted<MenuWindow<P, MenuPick>>)>,
+}
+
+// What the app's windows have in common. Windows opened for a tab moved out or for a session
+// being restored are kept by the others, and the app quits once the last of them is gone.
+// They are saved together, in the order they opened.
+struct AppWindows<P: Platform + 'static> {
+    moved: RefCell<Vec<Hosted<Window<P>>>>,
+    windows: RefCell<Vec<Weak<ComponentHost<Window<P>>>>>,
+    open: Cell<usize>,
+    // Window indices handed out so far, see `Window::set_closed_tabs`
+    indices: Cell<usize>,
+    session_handler: RefCell<Option<SessionFn>>,
+    // See `Window::set_autosave`
+    autosave: Cell<Option<(Duration, Duration)>>,
+}
+
+pub struct Window<P: Platform + 'static> {
+    platform: Rc<P>,
+    window: WindowId,
+    is_down: bool,
+    track_mouse_leave: bool,
+    minimize_button: Option<WidgetId>,
+    maximize_button: Option<WidgetId>,
+    close_button: Option<WidgetId>,
+    tab_bar: Option<WidgetId>,
+    title_bar: Option<WidgetId>,
+    drag_area: Option<WidgetId>,
+    content: Option<WidgetId>,
+    tab_panels: HashMap<TabId, WidgetId>,
+    tab_content: Option<ContentFn>,
+    // Saved state for the content of the tab being opened
+    opening_state: Option<String>,
+    close: CloseProtocol,
+    close_handler: Option<CloseFn>,
+    tab_menu_items: Option<TabMenuItemsFn>,
+    tab_action_handler: Option<TabActionFn>,
+    menu: Option<OpenMenu<P>>,
+    // What runs once the question the open menu asks is answered
+    question: Option<AnswerFn<P>>,
+    // The tab being renamed and its `TitleEditor`
+    editor: Option<(TabId, WidgetId)>,
+    app: Rc<AppWindows<P>>,
+    // This window's own host, which its menu popups tell about what they changed
+    this: Weak<ComponentHost<Self>>,
+    theme: Theme,
+    autosave: Option<Autosave>,
+    closed_tabs: Rc<RefCell<ClosedTabs>>,
+    // This window's place among the app's windows, which the closed tabs remember
+    window_index: usize,
+    // Everyone agreed to close, the next close request goes through
+    may_close: bool,
+    // Some platforms forget the window before telling it, nothing can be painted anymore
+    destroyed: bool,
+    widgets: WidgetTree,
+    scheduler: FrameScheduler,
+    renderer: Option<RenderResources<P::DeviceFactory>>,
+    dirty: DirtyRegion,
+    dpi: Dpi,
+}
+
+impl<P: Platform + 'static> Window<P> {
+    pub fn new(platform: Rc<P>) -> Result<Hosted<Self>, Error> {
+        let me = Self {
+            platform: platform.clone(),
+            window: WindowId(0),
+            is_down: false,
+            track_mouse_leave: false,
+            minimize_button: None,
+            maximize_button: None,
+            close_button: None,
+            tab_bar: None,
+            title_bar: None,
+            drag_area: None,
+            content: None,
+            tab_panels: HashMap::new(),
+            tab_content: None,
+            opening_state: None,
+            close: CloseProtocol::new(),
+            close_handler: None,
+            tab_menu_items: None,
+            tab_action_handler: None,
+            menu: None,
+            question: None,
+            editor: None,
+            app: Rc::new(AppWindows {
+                moved: RefCell::default(),
+                windows: RefCell::default(),
+                open: Cell::new(1),
+                indices: Cell::new(0),
+                session_handler: RefCell::default(),
+                autosave: Cell::new(None),
+            }),
+            this: Weak::new(),
+            theme: Theme::default(),
+            autosave: None,
+            closed_tabs: Rc::default(),
+            window_index: 0,
+            may_close: false,
+            destroyed: false,
+            widgets: WidgetTree::new(Rect::default()),
+            scheduler: FrameScheduler::new(platform.clock()),
+            renderer: None,
+            dirty: DirtyRegion::new(),
+            dpi: Dpi::DEFAULT,
+        };
+
+        let params = WindowParams {
+            class_name: WINDOW_CLASS_NAME,
+            title: String::from(WINDOW_TITLE),
+            // Sized for the right monitor once it is known in `Event::Created`
+            size: WINDOW_SIZE.to_px(Dpi::DEFAULT, Rounding::Round),
+            custom_frame: true,
+            popup: None,
+        };
+
+        let window = platform.create_window(&params, me)?;
+        let this = Rc::downgrade(window.host());
+        window.with(|window| {
+            window.app.windows.borrow_mut().push(this.clone());
+            window.this = this;
+        })?;
+        Ok(window)
+    }
+
+    fn on_created(&mut self) {
+        let title_bg = Color::from_rgb(TITLE_BG_COLOR.0, TITLE_BG_COLOR.1, TITLE_BG_COLOR.2);
+        let btn_hover = Color::from_rgb(
+            TITLE_HOVER_COLOR.0,
+            TITLE_HOVER_COLOR.1,
+            TITLE_HOVER_COLOR.2,
+        );
+        let btn_down = Color::from_rgb(TITLE_DOWN_COLOR.0, TITLE_DOWN_COLOR.1, TITLE_DOWN_COLOR.2);
+
+        let mut minimize_button = Button::new(
+            0,
+            0,
+            0,
+            0,
+            Some(ButtonColors::new(title_bg, btn_hover, btn_down)),
+        );
+
+        let mut maximize_button = Button::new(
+            0,
+            0,
+            0,
+            0,
+            Some(ButtonColors::new(title_bg, btn_hover, btn_down)),
+        );
+
+        let mut close_button = Button::new(
+            0,
+            0,
+            0,
+            0,
+            Some(ButtonColors::new(
+                title_bg,
+                Color::from_rgb(232, 17, 35),
+                Color::from_rgb(232, 73, 76),
+            )),
+        );
+
+        let window = self.window;
+
+        // Clicks are delivered while the window is handling an event, so anything that makes
+        // the system call back into the window gets posted instead.
+        let platform = self.platform.clone();
+        minimize_button.on_click(Box::new(move |_| {
+            let result = platform.post_command(window, SystemCommand::Minimize);
+            report_window_error(result);
+        }));
+
+        let platform = self.platform.clone();
+        maximize_button.on_click(Box::new(move |_| {
+            let result = platform.is_maximized(window).and_then(|maximized| {
+                let command = if maximized {
+                    SystemCommand::Restore
+                } else {
+                    SystemCommand::Maximize
+                };
+                platform.post_command(window, command)
+            });
+            report_window_error(result);
+        }));
+
+        let platform = self.platform.clone();
+        close_button.on_click(Box::new(move |_| {
+            let result = platform.post_command(window, SystemCommand::Close);
+            report_window_error(result);
+        }));
+
+        let platform = self.platform.clone();
+        minimize_button.on_paint_last(Box::new(move |button, canvas| {
+            let has_focus = platform.is_active(window);
+
+            let title_bar_item_color = if has_focus || button.state() != ButtonState::None {
+                Color::from_rgb(TITLE_ITEM_COLOR.0, TITLE_ITEM_COLOR.1, TITLE_ITEM_COLOR.2)
+            } else {
+                Color::from_rgb(
+                    TITLE_ITEM_BLUR_COLOR.0,
+                    TITLE_ITEM_BLUR_COLOR.1,
+                    TITLE_ITEM_BLUR_COLOR.2,
+                )
+            };
+
+            let dpi = canvas.dpi();
+            let icon_rect = DipRect::from_px(button.bounds(), dpi)
+                .centered(DipSize {
+                    width: ICON_DIMENSION,
+                    height: Dip(1.0),
+                })
+                .to_px(dpi);
+
+            canvas.fill_rect(icon_rect.into(), title_bar_item_color);
+        }));
+
+        let platform = self.platform.clone();
+        maximize_button.on_paint_last(Box::new(move |button, canvas| {
+            let has_focus = platform.is_active(window);
+
+            let title_bar_item_color = if has_focus || button.state() != ButtonState::None {
+                Color::from_rgb(TITLE_ITEM_COLOR.0, TITLE_ITEM_COLOR.1, TITLE_ITEM_COLOR.2)
+            } else {
+                Color::from_rgb(
+                    TITLE_ITEM_BLUR_COLOR.0,
+                    TITLE_ITEM_BLUR_COLOR.1,
+                    TITLE_ITEM_BLUR_COLOR.2,
+                )
+            };
+
+            let bg_color = button.background();
+
+            let dpi = canvas.dpi();
+            let icon = DipRect::from_px(button.bounds(), dpi).centered(ICON_SIZE);
+            let stroke_rect = |rect: DipRect| RectF::from(rect.to_px(dpi)).snap_to_pixel(1.0);
+            let icon_rect = stroke_rect(icon);
+
+            if platform.is_maximized(window).unwrap_or(false) {
+                // Two overlapping windows, the one in front is down and to the left
+                canvas.stroke_rect(
+                    stroke_rect(icon.inset(RESTORE_BACK_INSETS)),
+                    title_bar_item_color,
+                    1.0,
+                );
+
+                let restore_rect = stroke_rect(icon.inset(RESTORE_FRONT_INSETS));
+                canvas.fill_rect(restore_rect, bg_color);
+                canvas.stroke_rect(restore_rect, title_bar_item_color, 1.0);
+            } else {
+                canvas.stroke_rect(icon_rect, title_bar_item_color, 1.0);
+            }
+        }));
+
+        let platform = self.platform.clone();
+        close_button.on_paint_last(Box::new(move |button, canvas| {
+            let has_focus = platform.is_active(window);
+
+            let title_bar_item_color = if has_focus {
+                Color::from_rgb(TITLE_ITEM_COLOR.0, TITLE_ITEM_COLOR.1, TITLE_ITEM_COLOR.2)
+            } else {
+                Color::from_rgb(
+                    TITLE_ITEM_BLUR_COLOR.0,
+                    TITLE_ITEM_BLUR_COLOR.1,
+                    TITLE_ITEM_BLUR_COLOR.2,
+                )
+            };
+
+            let dpi = canvas.dpi();
+            let icon_rect = RectF::from(
+                DipRect::from_px(button.bounds(), dpi)
+                    .centered(ICON_SIZE)
+                    .to_px(dpi),
+            );
+
+            let button_icon_color = if button.state() == ButtonState::None {
+                title_bar_item_color
+            } else {
+                Color::from_argb(0xffffffff)
+            };
+
+            canvas.draw_line(
+                PointF::new(icon_rect.left, icon_rect.top),
+                PointF::new(icon_rect.right, icon_rect.bottom),
+                button_icon_color,
+                1.2,
+            );
+            canvas.draw_line(
+                PointF::new(icon_rect.left, icon_rect.bottom),
+                PointF::new(icon_rect.right, icon_rect.top),
+                button_icon_color,
+                1.2,
+            );
+        }));
+
+        // The content of the tabs goes below everything else
+        let root = self.widgets.root();
+        self.content = self.widgets.add_child(
+            root,
+            Box::new(Stack::new(Insets::default(), Align::Stretch)),
+        );
+
+        // Tabs, then an empty area to drag the window by, then the caption buttons
+        let title_bar = self.widgets.add_child(
+            root,
+            Box::new(FlexBox::new(Flex::row().with_padding(Insets::new(
+                0,
+                FAKE_SHADOW_HEIGHT,
+                0,
+                0,
+            )))),
+        );
+
+        if let Some(title_bar) = title_bar {
+            self.tab_bar = self
+                .widgets
+                .add_child(title_bar, Box::new(TabBar::new(0, 0, 0, 0)));
+            self.drag_area = self.widgets.add_child(title_bar, Box::new(Spacer::new()));
+            self.minimize_button = self.widgets.add_child(title_bar, Box::new(minimize_button));
+            self.maximize_button = self.widgets.add_child(title_bar, Box::new(maximize_button));
+            self.close_button = self.widgets.add_child(title_bar, Box::new(close_button));
+        }
+        self.title_bar = title_bar;
+    }
+
+    fn invalidate(&mut self, rect: Rect) -> Result<(), Error> {
+        self.dirty.add(rect);
+        self.platform.invalidate(self.window, rect)
+    }
+
+    pub fn context(&self) -> Context {
+        Context::new(self.scheduler.now(), self.dpi)
+    }
+
+    // Carries out what the widgets asked for while handling an event.
+    pub fn apply_context(&mut self, ctx: Context) -> Result<(), Error> {
+        if self.destroyed {
+            return Ok(());
+        }
+
+        for rect in ctx.dirty().rects() {
+            self.invalidate(*rect)?;
+        }
+
+        if ctx.wants_frames() {
+            let action = self.scheduler.request_frames();
+            self.apply_timer_action(action)?;
+        }
+
+        Ok(())
+    }
+
+    fn apply_timer_action(&self, action: TimerAction) -> Result<(), Error> {
+        match action {
+            TimerAction::Start(interval) => {
+                self.platform
+                    .set_timer(self.window, ANIMATION_TIMER_ID, interval)?;
+            }
+            TimerAction::Stop => self.platform.kill_timer(self.window, ANIMATION_TIMER_ID),
+            TimerAction::None => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn add_widget(&mut self, widget: Box<dyn Widget>) -> Result<Option<WidgetId>, Error> {
+        let bounds = widget.bounds();
+        let root = self.widgets.root();
+        let id = self.widgets.add_child(root, widget);
+        self.invalidate(bounds)?;
+
+        // Give the new widget a frame in case it starts out animated
+        let action = self.scheduler.request_frames();
+        self.apply_timer_action(action)?;
+        Ok(id)
+    }
+
+    pub fn remove_widget(&mut self, id: WidgetId) -> Result<Option<Box<dyn Widget>>, Error> {
+        let widget = match self.widgets.remove(id) {
+            Some(widget) => widget,
+            None => return Ok(None),
+        };
+
+        self.invalidate(widget.bounds())?;
+        Ok(Some(widget))
+    }
+
+    // Runs `f` against the widget tree and then applies what the widgets asked for.
+    fn with_widgets<R>(
+        &mut self,
+        f: impl FnOnce(&mut WidgetTree, &mut Context) -> R,
+    ) -> Result<R, Error> {
+        let mut ctx = self.context();
+        let result = f(&mut self.widgets, &mut ctx);
+        self.apply_context(ctx)?;
+
+        // Clicks in the tab bar may have opened, closed or switched tabs, and content may
+        // have answered a close request
+        self.apply_tab_events()?;
+        self.run_close(Vec::new())?;
+        Ok(result)
+    }
+
+    fn with_tab_bar<R>(
+        &mut self,
+        f: impl FnOnce(&mut TabBar, &mut Context) -> R,
+    ) -> Result<Option<R>, Error> {
+        let mut ctx = self.context();
+        let result = self
+            .tab_bar
+            .and_then(|id| self.widgets.get_mut::<TabBar>(id))
+            .map(|tab_bar| f(tab_bar, &mut ctx));
+        self.apply_context(ctx)?;
+
+        self.apply_tab_events()?;
+        Ok(result)
+    }
+
+    // Creates the content of every tab opened from now on, from the state it saved in an
+    // earlier session or from an empty one.
+    pub fn set_tab_content(
+        &mut self,
+        content: impl Fn(TabId, &str) -> Box<dyn TabContent> + 'static,
+    ) {
+        self.tab_content = Some(Rc::new(content));
+    }
+
+    pub fn add_tab(&mut self) -> Result<Option<TabId>, Error> {
+        self.open_tab("", "")
+    }
+
+    pub fn open_tab(&mut self, title: &str, state: &str) -> Result<Option<TabId>, Error> {
+        self.insert_tab(usize::MAX, title, state)
+    }
+
+    // Opens a tab at `index`, or at the end when there aren't that many tabs.
+    pub fn insert_tab(
+        &mut self,
+        index: usize,
+        title: &str,
+        state: &str,
+    ) -> Result<Option<TabId>, Error> {
+        self.opening_state = Some(String::from(state));
+        let result = self.with_tab_bar(|tab_bar, ctx| {
+            let tab = tab_bar.insert_item(index, ctx);
+            tab_bar.set_tab_title(tab, title);
+            tab
+        });
+        self.opening_state = None;
+        result
+    }
+
+    pub fn set_tab_title(&mut self, tab: TabId, title: &str) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, _| tab_bar.set_tab_title(tab, title))?;
+        self.session_changed()
+    }
+
+    pub fn tabs(&self) -> Vec<TabId> {
+        self.tab_bar
+            .and_then(|id| self.widgets.get::<TabBar>(id))
+            .map(|tab_bar| tab_bar.tabs().to_vec())
+            .unwrap_or_default()
+    }
+
+    pub fn active_tab(&self) -> Option<TabId> {
+        self.tab_bar
+            .and_then(|id| self.widgets.get::<TabBar>(id))
+            .and_then(|tab_bar| tab_bar.active_tab())
+    }
+
+    pub fn activate_tab(&mut self, tab: TabId) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.activate(tab, ctx))
+            .map(|_| ())
+    }
+
+    // Moves the tab to `index`, but never past the pinned tabs or into them.
+    pub fn move_tab(&mut self, tab: TabId, index: usize) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.move_tab(tab, index, ctx))
+            .map(|_| ())
+    }
+
+    pub fn is_tab_pinned(&self, tab: TabId) -> bool {
+        self.tab_bar
+            .and_then(|id| self.widgets.get::<TabBar>(id))
+            .is_some_and(|tab_bar| tab_bar.is_tab_pinned(tab))
+    }
+
+    pub fn set_tab_pinned(&mut self, tab: TabId, pinned: bool) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.set_tab_pinned(tab, pinned, ctx))
+            .map(|_| ())
+    }
+
+    // Puts the tabs in a new group, gathered where the first of them is. Pinned tabs stay out
+    // of groups.
+    pub fn create_tab_group(
+        &mut self,
+        tabs: &[TabId],
+        name: &str,
+    ) -> Result<Option<GroupId>, Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.create_group(tabs, name, ctx))
+            .map(Option::flatten)
+    }
+
+    pub fn add_tab_to_group(&mut self, tab: TabId, group: GroupId) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.add_to_group(tab, group, ctx))
+            .map(|_| ())
+    }
+
+    pub fn remove_tab_from_group(&mut self, tab: TabId) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.remove_from_group(tab, ctx))
+            .map(|_| ())
+    }
+
+    pub fn ungroup_tabs(&mut self, group: GroupId) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.ungroup(group, ctx))
+            .map(|_| ())
+    }
+
+    // Moves the whole group, see `TabModel::move_group`.
+    pub fn move_tab_group(&mut self, group: GroupId, index: usize) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.move_group(group, index, ctx))
+            .map(|_| ())
+    }
+
+    pub fn rename_tab_group(&mut self, group: GroupId, name: &str) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.rename_group(group, name, ctx))
+            .map(|_| ())
+    }
+
+    // `color` picks one of the theme's group colors.
+    pub fn set_tab_group_color(&mut self, group: GroupId, color: usize) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.set_group_color(group, color, ctx))
+            .map(|_| ())
+    }
+
+    pub fn set_tab_group_collapsed(
+        &mut self,
+        group: GroupId,
+        collapsed: bool,
+    ) -> Result<(), Error> {
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.set_group_collapsed(group, collapsed, ctx))
+            .map(|_| ())
+    }
+
+    pub fn tab_group_of(&self, tab: TabId) -> Option<GroupId> {
+        self.tab_bar_widget()
+            .and_then(|tab_bar| tab_bar.group_of(tab))
+    }
+
+    pub fn tab_group(&self, group: GroupId) -> Option<TabGroup> {
+        self.tab_bar_widget()
+            .and_then(|tab_bar| tab_bar.group(group).cloned())
+    }
+
+    pub fn tab_groups(&self) -> Vec<GroupId> {
+        self.tab_bar_widget()
+            .map(TabBar::groups)
+            .unwrap_or_default()
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) -> Result<(), Error> {
+        self.theme = theme.clone();
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.set_theme(theme, ctx))
+            .map(|_| ())
+    }
+
+    fn tab_bar_widget(&self) -> Option<&TabBar> {
+        self.tab_bar.and_then(|id| self.widgets.get::<TabBar>(id))
+    }
+
+    // Closes the tabs their content agrees to close, see `TabContent::on_close_requested`.
+    pub fn close_tabs(&mut self, tabs: &[TabId]) -> Result<(), Error> {
+        let step = self.close.close_tabs(tabs);
+        self.run_close(step.into_iter().collect())
+    }
+
+    pub fn close_tab(&mut self, tab: TabId) -> Result<(), Error> {
+        self.close_tabs(&[tab])
+    }
+
+    // Closes every tab but `tab` and the pinned ones.
+    pub fn close_other_tabs(&mut self, tab: TabId) -> Result<(), Error> {
+        let others = self
+            .tab_bar
+            .and_then(|id| self.widgets.get::<TabBar>(id))
+            .map(|tab_bar| tab_bar.other_tabs(tab))
+            .unwrap_or_default();
+        self.close_tabs(&others)
+    }
+
+    // Closes the tabs after `tab`, except for pinned ones.
+    pub fn close_tabs_to_the_right(&mut self, tab: TabId) -> Result<(), Error> {
+        let tabs = self
+            .tab_bar_widget()
+            .map(|tab_bar| tab_bar.model().to_the_right(tab))
+            .unwrap_or_default();
+        self.close_tabs(&tabs)
+    }
+
+    // Opens a copy of the tab right after it, with the state its content has now.
+    pub fn duplicate_tab(&mut self, tab: TabId) -> Result<Option<TabId>, Error> {
+        let copy = self
+            .tab_bar_widget()
+            .and_then(|tab_bar| Some((tab_bar.tab_index(tab)?, self.tab_session(tab_bar, tab))));
+
+        match copy {
+            Some((index, saved)) => self.open_saved_tab(index + 1, &saved),
+            None => Ok(None),
+        }
+    }
+
+    // Takes the tab out of the window to be opened somewhere else. Its content is asked for
+    // its state and dropped without being asked whether it may close, and the tab isn't kept
+    // among the closed ones.
+    pub fn detach_tab(&mut self, tab: TabId) -> Result<Option<TabSession>, Error> {
+        let saved = match self.tab_bar_widget() {
+            Some(tab_bar) if tab_bar.tab_index(tab).is_some() => self.tab_session(tab_bar, tab),
+            _ => return Ok(None),
+        };
+
+        self.with_tab_bar(|tab_bar, ctx| tab_bar.close_tab(tab, ctx))?;
+        Ok(Some(saved))
+    }
+
+    // Adds the app's own items to every tab's menu, after the standard ones. Picking one goes
+    // to the tab action handler.
+    pub fn set_tab_menu_items(
+        &mut self,
+        items: impl Fn(&TabModel, TabId) -> Vec<MenuEntry<TabAction>> + 'static,
+    ) {
+        self.tab_menu_items = Some(Rc::new(items));
+    }
+
+    // Gets the app's own items picked from the tab menu, along with what the tab looks like.
+    pub fn set_tab_action_handler(
+        &mut self,
+        handler: impl FnMut(TabId, TabAction, &TabSession) + 'static,
+    ) {
+        self.tab_action_handler = Some(Rc::new(RefCell::new(handler)));
+    }
+
+    // What right-clicking the tab offers.
+    pub fn tab_menu(&self, tab: TabId) -> Vec<MenuEntry<TabAction>> {
+        let tab_bar = match self.tab_bar_widget() {
+            Some(tab_bar) if tab_bar.tab_index(tab).is_some() => tab_bar,
+            _ => return Vec::new(),
+        };
+
+        let mut menu = tab_menu::tab_menu(tab_bar.model(), tab, self.can_reopen_tab());
+        let extra = self
+            .tab_menu_items
+            .as_ref()
+            .map(|items| items(tab_bar.model(), tab))
+            .unwrap_or_default();
+        if !extra.is_empty() {
+            menu.push(MenuEntry::Separator);
+            menu.extend(extra);
+        }
+        menu
+    }
+
+    // Does what picking `action` from the tab's menu does.
+    pub fn run_tab_action(&mut self, tab: TabId, action: TabAction) -> Result<(), Error> {
+        log_debug!(Target::TabBar, "tab {}: {:?}", tab, action);
+
+        match action {
+            TabAction::Close => self.close_tab(tab),
+            TabAction::CloseOthers => self.close_other_tabs(tab),
+            TabAction::CloseToTheRight => self.close_tabs_to_the_right(tab),
+            TabAction::Duplicate => self.duplicate_tab(tab).map(|_| ()),
+            TabAction::Pin => self.set_tab_pinned(tab, true),
+            TabAction::Unpin => self.set_tab_pinned(tab, false),
+            TabAction::ReopenClosed => self.reopen_closed_tab().map(|_| ()),
+            TabAction::Rename => self.rename_tab(tab),
+            TabAction::MoveToNewWindow => self.move_tab_to_new_window(tab).map(|_| ()),
+            TabAction::App(_) => {
+                let saved = self
+                    .tab_bar_widget()
+                    .filter(|tab_bar| tab_bar.tab_index(tab).is_some())
+                    .map(|tab_bar| self.tab_session(tab_bar, tab));
+                let handler = self.tab_action_handler.clone();
+                if let Some((saved, handler)) = saved.zip(handler) {
+                    // Taken already if the handler got here from another of the app's windows
+                    if let Ok(mut handler) = handler.try_borrow_mut() {
+                        handler(tab, action, &saved);
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Opens a box under the tab to type its new title into. Enter or pressing elsewhere
+    // renames it, Escape leaves the title as it was.
+    pub fn rename_tab(&mut self, tab: TabId) -> Result<(), Error> {
+        let (title, bounds) = match self.tab_bar_widget() {
+            Some(tab_bar) if tab_bar.tab_index(tab).is_some() => (
+                String::from(tab_bar.tab_title(tab)),
+                tab_bar.tab_bounds(tab).unwrap_or_default(),
+            ),
+            _ => return Ok(()),
+        };
+        self.close_menu()?;
+        self.close_editor()?;
+
+        // The editor is painted over the window, unlike menus it can't reach past it
+        let anchor = Point::new(bounds.left, bounds.bottom);
+        let area = self.platform.client_rect(self.window)?;
+        let editor = TitleEditor::new(&title, anchor, area, self.theme.menu, self.dpi);
+        let bounds = editor.bounds();
+        let root = self.widgets.root();
+        self.editor = self
+            .widgets
+            .add_child(root, Box::new(editor))
+            .map(|id| (tab, id));
+        if let Some((_, id)) = self.editor {
+            self.widgets.bring_to_front(id);
+        }
+        self.invalidate(bounds)
+    }
+
+    // The tab whose title is being typed, and what was typed so far.
+    pub fn renaming_tab(&self) -> Option<(TabId, String)> {
+        let (tab, id) = self.editor?;
+        let editor = self.widgets.get::<TitleEditor>(id)?;
+        Some((tab, String::from(editor.text())))
+    }
+
+    fn close_editor(&mut self) -> Result<(), Error> {
+        match self.editor.take() {
+            Some((_, id)) => self.remove_widget(id).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    // Opens a window of the app's own with the tab in it, the way `detach_tab` left it.
+    pub fn move_tab_to_new_window(&mut self, tab: TabId) -> Result<Option<WindowId>, Error> {
+        if !self.tabs().contains(&tab) {
+            return Ok(None);
+        }
+
+        // Opened before the tab is taken out, so it isn't lost if the window can't be
+        let window = Window::new(self.platform.clone())?;
+        let saved = match self.detach_tab(tab)? {
+            Some(saved) => saved,
+            None => return Ok(None),
+        };
+
+        let index = self.app.indices.get() + 1;
+        self.adopt_window(&window, index)?;
+        window.with(|moved| moved.open_saved_tab(0, &saved))??;
+        Ok(Some(self.keep_window(window)))
+    }
+
+    // Makes `window` one of the app's windows, at `index` among them. It shares the closed
+    // tabs, the tab content, the tab menu and the session handling with this one.
+    fn adopt_window(&mut self, window: &Hosted<Window<P>>, index: usize) -> Result<(), Error> {
+        self.app.indices.set(self.app.indices.get().max(index));
+        self.app.open.set(self.app.open.get() + 1);
+        let mut windows = self.app.windows.borrow_mut();
+        windows.retain(|window| window.strong_count() > 0);
+        windows.push(Rc::downgrade(window.host()));
+        drop(windows);
+
+        let theme = self.theme.clone();
+        window.with(|other| -> Result<(), Error> {
+            other.app = self.app.clone();
+            other.tab_content = self.tab_content.clone();
+            other.tab_menu_items = self.tab_menu_items.clone();
+            other.tab_action_handler = self.tab_action_handler.clone();
+            other.set_closed_tabs(self.closed_tabs.clone(), index);
+            other.autosave =
+                self.app.autosave.get().map(|(delay, max_delay)| {
+                    Autosave::new(other.platform.clock(), delay, max_delay)
+                });
+            other.set_theme(theme)
+        })?
+    }
+
+    fn keep_window(&self, window: Hosted<Window<P>>) -> WindowId {
+        let id = window.window();
+        let mut moved = self.app.moved.borrow_mut();
+        // Windows closed since are let go of
+        moved.retain(|window| !window.host().is_destroyed());
+        moved.push(window);
+        id
+    }
+
+    // The app's other windows that are still open, in the order they opened.
+    fn other_windows(&self) -> Vec<Rc<ComponentHost<Self>>> {
+        self.app
+            .windows
+            .borrow()
+            .iter()
+            .filter(|window| !window.ptr_eq(&self.this))
+            .filter_map(Weak::upgrade)
+            .filter(|window| !window.is_destroyed())
+            .collect()
+    }
+
+    // Runs `f` on one of the windows tabs were moved to while it is open.
+    pub fn with_moved_window<R>(
+        &self,
+        window: WindowId,
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> Option<R> {
+        let host = self
+            .app
+            .moved
+            .borrow()
+            .iter()
+            .find(|moved| moved.window() == window)?
+            .host()
+            .clone();
+        host.with(f)
+    }
+
+    // Right-clicking a tab shows its menu.
+    fn tab_context_menu(&mut self, pos: Point) -> Result<(), Error> {
+        let tab = match self
+            .tab_bar_widget()
+            .and_then(|tab_bar| tab_bar.tab_at(pos.x, pos.y))
+        {
+            Some(tab) => tab,
+            None => return Ok(()),
+        };
+
+        let menu = self
+            .tab_menu(tab)
+            .into_iter()
+            .map(|entry| entry.map(&|action| MenuPick::Tab(tab, action)))
+            .collect();
+        self.open_menu(menu, pos)
+    }
+
+    pub fn is_menu_open(&self) -> bool {
+        self.menu.is_some()
+    }
+
+    // What the open menu and its open submenus show, separators as "-".
+    pub fn menu_labels(&self) -> Vec<Vec<String>> {
+        self.menu
+            .as_ref()
+            .map(|open| open.menu.borrow().labels())
+            .unwrap_or_default()
+    }
+
+    // Where the open menu and its open submenus are in client coordinates, the top one first.
+    pub fn menu_bounds(&self) -> Vec<Rect> {
+        self.menu
+            .as_ref()
+            .map(|open| open.menu.borrow().level_bounds())
+            .unwrap_or_default()
+    }
+
+    pub fn menu_entry_bounds(&self, level: usize, index: usize) -> Option<Rect> {
+        let open = self.menu.as_ref()?;
+        let bounds = open.menu.borrow().entry_bounds(level, index);
+        bounds
+    }
+
+    // The popups showing the open menu's levels, the top one first.
+    pub fn menu_windows(&self) -> Vec<WindowId> {
+        self.menu
+            .as_ref()
+            .map(|open| {
+                open.popups
+                    .iter()
+                    .map(|(_, popup)| popup.window())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // Shows `entries` at `pos`, in place of any menu that was open.
+    fn open_menu(&mut self, entries: Vec<MenuEntry<MenuPick>>, pos: Point) -> Result<(), Error> {
+        self.close_menu()?;
+
+        let menu = PopupMenu::new(entries, pos, self.menu_area()?, self.theme.menu, self.dpi);
+        self.menu = Some(OpenMenu {
+            menu: Rc::new(RefCell::new(menu)),
+            popups: Vec::new(),
+        });
+        self.sync_menu()
+    }
+
+    // A question the menu asked is answered no.
+    fn close_menu(&mut self) -> Result<(), Error> {
+        self.menu = None;
+        self.answer(false)
+    }
+
+    // Takes the menu's outcome if it ended, or else gives each open level a popup where the
+    // menu placed it and repaints them.
+    fn menu_changed(&mut self) -> Result<(), Error> {
+        let outcome = match self.menu.as_ref() {
+            Some(open) => open.menu.borrow_mut().take_outcome(),
+            None => return Ok(()),
+        };
+
+        match outcome {
+            Some(outcome) => {
+                self.menu = None;
+                match outcome {
+                    MenuOutcome::Picked(pick) => self.run_menu_pick(pick),
+                    MenuOutcome::Dismissed => self.answer(false),
+                }
+            }
+            None => self.sync_menu(),
+        }
+    }
+
+    fn sync_menu(&mut self) -> Result<(), Error> {
+        let changed = self.menu_changed_fn();
+        let open = match self.menu.as_mut() {
+            Some(open) => open,
+            None => return Ok(()),
+        };
+
+        // Levels that closed or moved lose their popups, the ones opened since get new ones
+        let levels = open.menu.borrow().level_bounds();
+        let kept = open
+            .popups
+            .iter()
+            .zip(&levels)
+            .take_while(|((placed, _), bounds)| placed == *bounds)
+            .count();
+        open.popups.truncate(kept);
+        for (level, bounds) in levels.iter().enumerate().skip(kept) {
+            let popup = MenuWindow::open(
+                self.platform.clone(),
+                self.window,
+                open.menu.clone(),
+                level,
+                changed.clone(),
+            )?;
+            open.popups.push((*bounds, popup));
+        }
+
+        // The ones kept may show other entries or highlight another one
+        for (bounds, popup) in &open.popups {
+            let client_rect = Rect::from_size(0, 0, bounds.width(), bounds.height());
+            self.platform.invalidate(popup.window(), client_rect)?;
+        }
+        Ok(())
+    }
+
+    // What the menu's popups run after changing it. The window may be busy with the event
+    // that led to that, then it looks at the menu right after.
+    fn menu_changed_fn(&self) -> Rc<dyn Fn()> {
+        let this = self.this.clone();
+        Rc::new(move || {
+            if let Some(host) = this.upgrade() {
+                host.dispatch(Event::Custom(()), |window, event| {
+                    report_window_error(window.handle_event(event))
+                });
+            }
+        })
+    }
+
+    // Menus open on popups that may reach past the window, as far as its monitor's work area
+    // goes. Where a window can't tell where it is, they stay inside the client area.
+    fn menu_area(&self) -> Result<Rect, Error> {
+        let monitor = match self.platform.monitor(self.window)? {
+            Some(monitor) => monitor,
+            None => return self.platform.client_rect(self.window),
+        };
+
+        let origin = self
+            .platform
+            .client_to_screen(self.window, Point::new(0, 0))?;
+        Ok(monitor.offset(-origin.x, -origin.y))
+    }
+
+    // While a menu is open it takes the keys and the mouse that isn't over one of its popups,
+    // and anything that changes the window under it dismisses it. Returns `None` for events
+    // the window handles as usual.
+    fn menu_event(&mut self, event: &Event<()>) -> Result<Option<Reply>, Error> {
+        let menu = match self.menu.as_ref() {
+            Some(open) => open.menu.clone(),
+            None => return Ok(None),
+        };
+
+        // The menu only repaints its popups, which `menu_changed` takes care of
+        let mut ctx = self.context();
+        let reply = match *event {
+            // Pressing anywhere only dismisses the menu, the resize borders included
+            Event::HitTest {
+                system: HitZone::Nowhere,
+                ..
+            } => return Ok(None),
+            Event::HitTest { .. } => return Ok(Some(Reply::HitTest(HitZone::Client))),
+            Event::MouseMove { .. } | Event::MouseUp { .. } => Reply::Default,
+            Event::MouseDown { pos, .. } => {
+                menu.borrow_mut().on_mouse_down(pos.x, pos.y, &mut ctx);
+                Reply::Default
+            }
+            Event::KeyDown(key) => {
+                menu.borrow_mut().on_key(key, &mut ctx);
+                Reply::Handled
+            }
+            Event::NonClientMouseDown { .. } => {
+                self.close_menu()?;
+                return Ok(Some(Reply::Handled));
+            }
+            // A question stays until it is answered, e.g. while the window only just opens
+            Event::Activate(false) | Event::Resize(_) | Event::Dpi { .. }
+                if self.question.is_some() =>
+            {
+                return Ok(None)
+            }
+            // Nobody is left to answer for
+            Event::Destroyed => {
+                self.question = None;
+                self.close_menu()?;
+                return Ok(None);
+            }
+            Event::Activate(false) | Event::Resize(_) | Event::Dpi { .. } => {
+                self.close_menu()?;
+                return Ok(None);
+            }
+            Event::Custom(()) => Reply::Handled,
+            _ => return Ok(None),
+        };
+
+        self.menu_changed()?;
+        Ok(Some(reply))
+    }
+
+    // While a tab is being renamed the editor takes the keys, and pressing anywhere or
+    // anything that changes the window under it ends the editing with what was typed.
+    fn editor_event(&mut self, event: &Event<()>) -> Result<Option<Reply>, Error> {
+        let (tab, id) = match self.editor {
+            Some(editor) => editor,
+            None => return Ok(None),
+        };
+
+        let mut ctx = self.context();
+        let editor = match self.widgets.get_mut::<TitleEditor>(id) {
+            Some(editor) => editor,
+            None => return Ok(None),
+        };
+        let (reply, outcome) = match *event {
+            Event::KeyDown(key) => {
+                editor.on_key(key, &mut ctx);
+                (Some(Reply::Handled), editor.take_outcome())
+            }
+            Event::Text(ch) => {
+                editor.on_text(ch, &mut ctx);
+                (Some(Reply::Handled), None)
+            }
+            Event::MouseDown { pos, .. } => {
+                editor.on_mouse_down(pos.x, pos.y, &mut ctx);
+                (Some(Reply::Default), editor.take_outcome())
+            }
+            Event::NonClientMouseDown { .. }
+            | Event::Activate(false)
+            | Event::Resize(_)
+            | Event::Dpi { .. } => (None, Some(EditOutcome::Done(String::from(editor.text())))),
+            _ => (None, None),
+        };
+        self.apply_context(ctx)?;
+
+        if let Some(outcome) = outcome {
+            self.close_editor()?;
+            if let EditOutcome::Done(title) = outcome {
+                self.set_tab_title(tab, &title)?;
+            }
+        }
+        Ok(reply)
+    }
+
+    fn run_menu_pick(&mut self, pick: MenuPick) -> Result<(), Error> {
+        log_debug!(Target::Window, "picked {:?} from a menu", pick);
+
+        match pick {
+            // Posted like a press of the caption buttons, the system may call back into the
+            // window
+            MenuPick::System(command) => self.platform.post_command(self.window, command),
+            MenuPick::Tab(tab, action) => self.run_tab_action(tab, action),
+            MenuPick::Answer(yes) => self.answer(yes),
+        }
+    }
+
+    // Asks the user a yes or no question, in a dialog of the system where it has one and in a
+    // menu over the window otherwise. Dismissing the menu answers no. `answer` runs once the
+    // user answered, which may be after this returns.
+    pub fn ask(
+        &mut self,
+        title: &str,
+        question: &str,
+        answer: impl FnOnce(&mut Self, bool) -> Result<(), Error> + 'static,
+    ) -> Result<(), Error> {
+        if let Some(yes) = self.platform.confirm(self.window, title, question)? {
+            return answer(self, yes);
+        }
+
+        let entries = vec![
+            MenuEntry::Item(
+                MenuItem::new(MenuPick::Answer(false), &question.replace('&', "&&"))
+                    .with_enabled(false),
+            ),
+            MenuEntry::Separator,
+            MenuEntry::item(MenuPick::Answer(true), "&Yes"),
+            MenuEntry::item(MenuPick::Answer(false), "&No"),
+        ];
+
+        // Centered over the window, where the user looks at
+        let (size, _) = popup_menu::measure_menu(&entries, self.dpi);
+        let client_rect = self.platform.client_rect(self.window)?;
+        let pos = Point::new(
+            (client_rect.left + client_rect.right - size.width) / 2,
+            (client_rect.top + client_rect.bottom - size.height) / 2,
+        );
+        self.open_menu(entries, pos)?;
+        self.question = Some(Box::new(answer));
+        Ok(())
+    }
+
+    pub fn is_asking(&self) -> bool {
+        self.question.is_some()
+    }
+
+    fn answer(&mut self, yes: bool) -> Result<(), Error> {
+        match self.question.take() {
+            Some(answer) => answer(self, yes),
+            None => Ok(()),
+        }
+    }
+
+    // Middle-clicking a tab closes it, unless it is pinned.
+    fn middle_click(&mut self, pos: Point) -> Result<(), Error> {
+        let tab = self
+            .tab_bar
+            .and_then(|id| self.widgets.get::<TabBar>(id))
+            .and_then(|tab_bar| tab_bar.tab_at(pos.x, pos.y))
+            .filter(|tab| !self.is_tab_pinned(*tab));
+
+        match tab {
+            Some(tab) => self.close_tab(tab),
+            None => Ok(()),
+        }
+    }
+
+    // Shares the closed tabs with the app's other windows, e.g. to reopen tabs of a window
+    // that is gone. `index` is this window's place among them, as in `Session::windows`.
+    pub fn set_closed_tabs(&mut self, closed_tabs: Rc<RefCell<ClosedTabs>>, index: usize) {
+        self.closed_tabs = closed_tabs;
+        self.window_index = index;
+    }
+
+    pub fn closed_tabs(&self) -> &Rc<RefCell<ClosedTabs>> {
+        &self.closed_tabs
+    }
+
+    pub fn can_reopen_tab(&self) -> bool {
+        !self.closed_tabs.borrow().is_empty()
+    }
+
+    // Brings back the tab this window closed last, where it was as far as the tabs open now
+    // allow, with the state its content saved on closing.
+    pub fn reopen_closed_tab(&mut self) -> Result<Option<TabId>, Error> {
+        let closed = match self.closed_tabs.borrow_mut().pop(self.window_index) {
+            Some(closed) => closed,
+            None => return Ok(None),
+        };
+
+        log_debug!(Target::TabBar, "reopening a tab at {}", closed.index);
+        self.open_saved_tab(closed.index, &closed.tab)
+    }
+
+    // Opens a tab the way it was saved. A pinned one is pinned again before it goes to
+    // `index`, which wouldn't be among the pinned tabs otherwise.
+    fn open_saved_tab(&mut self, index: usize, saved: &TabSession) -> Result<Option<TabId>, Error> {
+        let tab = self.insert_tab(index, &saved.title, &saved.state)?;
+
+        if let Some(tab) = tab.filter(|_| saved.pinned) {
+            self.set_tab_pinned(tab, true)?;
+            self.move_tab(tab, index)?;
+        }
+        Ok(tab)
+    }
+
+    // Asked last when the window is about to close, once all tabs agreed.
+    pub fn set_close_handler(&mut self, handler: impl FnMut(CloseReply) + 'static) {
+        self.close_handler = Some(Box::new(handler));
+    }
+
+    // Gets the session of all the app's windows right before the last of them closes, with
+    // its tabs still open. Shared by the app's windows, see `app_session`.
+    pub fn set_session_handler(&mut self, handler: impl FnMut(Session) + 'static) {
+        *self.app.session_handler.borrow_mut() = Some(Box::new(handler));
+    }
+
+    // Also hands the session to the session handler a while after the tabs of any of the
+    // app's windows changed, so there is something to restore should the app not get to
+    // close them.
+    pub fn set_autosave(&mut self, delay: Duration, max_delay: Duration) {
+        self.app.autosave.set(Some((delay, max_delay)));
+        self.autosave = Some(Autosave::new(self.platform.clock(), delay, max_delay));
+        for window in self.other_windows() {
+            window.with(|other| {
+                other.autosave = Some(Autosave::new(other.platform.clock(), delay, max_delay));
+            });
+        }
+    }
+
+    fn session_changed(&mut self) -> Result<(), Error> {
+        if self.destroyed {
+            return Ok(());
+        }
+
+        match self.autosave.as_mut().map(|autosave| autosave.changed()) {
+            Some(action) => self.apply_autosave_action(action),
+            None => Ok(()),
+        }
+    }
+
+    fn autosave_tick(&mut self) -> Result<(), Error> {
+        let (save, action) = match self.autosave.as_mut() {
+            Some(autosave) => autosave.tick(),
+            None => (false, TimerAction::Stop),
+        };
+
+        self.apply_autosave_action(action)?;
+        if save {
+            log_debug!(Target::Window, "autosaving the session");
+            self.hand_over_session(false)?;
+        }
+        Ok(())
+    }
+
+    fn apply_autosave_action(&self, action: TimerAction) -> Result<(), Error> {
+        match action {
+            TimerAction::Start(interval) => {
+                self.platform
+                    .set_timer(self.window, AUTOSAVE_TIMER_ID, interval)
+            }
+            TimerAction::Stop => {
+                self.platform.kill_timer(self.window, AUTOSAVE_TIMER_ID);
+                Ok(())
+            }
+            TimerAction::None => Ok(()),
+        }
+    }
+
+    // `leave_out` this window, which is about to close.
+    fn hand_over_session(&mut self, leave_out: bool) -> Result<(), Error> {
+        if self.app.session_handler.borrow().is_none() {
+            return Ok(());
+        }
+
+        let session = self.collect_session(leave_out)?;
+        if let Some(handler) = self.app.session_handler.borrow_mut().as_mut() {
+            handler(session);
+        }
+        Ok(())
+    }
+
+    fn is_last_window(&self) -> bool {
+        self.app.open.get() <= 1
+    }
+
+    // The sessions of all the app's open windows, in the order they opened, and the tabs
+    // closed so far. Closed tabs remember their window by its place in `Session::windows`,
+    // the ones of windows that are gone by a place past them.
+    pub fn app_session(&self) -> Result<Session, Error> {
+        self.collect_session(false)
+    }
+
+    fn collect_session(&self, leave_out: bool) -> Result<Session, Error> {
+        let mut windows = Vec::new();
+        let mut indices = Vec::new();
+        for window in self.app.windows.borrow().clone() {
+            let saved = if window.ptr_eq(&self.this) {
+                if leave_out {
+                    continue;
+                }
+                Some(self.session().map(|saved| (self.window_index, saved)))
+            } else {
+                let window = window.upgrade();
+                window
+                    .and_then(|window| {
+                        window.with(|other| {
+                            let index = other.window_index;
+                            (!other.destroyed).then(|| other.session().map(|saved| (index, saved)))
+                        })
+                    })
+                    .flatten()
+            };
+
+            if let Some((index, saved)) = saved.transpose()? {
+                indices.push(index);
+                windows.push(saved);
+            }
+        }
+
+        let closed_tabs = self
+            .closed_tabs
+            .borrow()
+            .tabs()
+            .iter()
+            .map(|closed| ClosedTab {
+                window: indices
+                    .iter()
+                    .position(|index| *index == closed.window)
+                    .unwrap_or(windows.len()),
+                ..closed.clone()
+            })
+            .collect();
+
+        Ok(Session {
+            windows,
+            closed_tabs,
+        })
+    }
+
+    // Brings back all the windows of `session`, this one as the first of them, and the tabs
+    // they closed. A window that comes back without tabs gets a new one.
+    pub fn restore_app_session(&mut self, session: &Session) -> Result<(), Error> {
+        {
+            let mut closed_tabs = self.closed_tabs.borrow_mut();
+            closed_tabs.clear();
+            for closed in &session.closed_tabs {
+                closed_tabs.push(closed.clone());
+            }
+        }
+
+        let mut saved = session.windows.iter();
+        if let Some(first) = saved.next() {
+            self.restore_session(first)?;
+        }
+        if self.tabs().is_empty() {
+            self.add_tab()?;
+        }
+
+        for (index, saved) in saved.enumerate() {
+            let window = Window::new(self.platform.clone())?;
+            self.adopt_window(&window, index + 1)?;
+            window.with(|other| -> Result<(), Error> {
+                other.restore_session(saved)?;
+                if other.tabs().is_empty() {
+                    other.add_tab()?;
+                }
+                Ok(())
+            })??;
+            self.keep_window(window);
+        }
+
+        // Windows opened from now on don't take over the closed tabs of gone ones
+        let gone = session.closed_tabs.iter().map(|closed| closed.window).max();
+        if let Some(gone) = gone {
+            self.app.indices.set(self.app.indices.get().max(gone));
+        }
+        Ok(())
+    }
+
+    pub fn session(&self) -> Result<WindowSession, Error> {
+        let placement = Placement {
+            monitor: self.platform.monitor(self.window)?,
+            restored: self.platform.restored_bounds(self.window)?,
+            maximized: self.platform.is_maximized(self.window)?,
+        };
+
+        let tab_bar = match self.tab_bar.and_then(|id| self.widgets.get::<TabBar>(id)) {
+            Some(tab_bar) => tab_bar,
+            None => {
+                return Ok(WindowSession {
+                    placement,
+                    ..WindowSession::default()
+                })
+            }
+        };
+
+        let tabs = tab_bar
+            .tabs()
+            .iter()
+            .map(|tab| self.tab_session(tab_bar, *tab))
+            .collect();
+        let position = |tab| tab_bar.tabs().iter().position(|open| *open == tab);
+        let active = tab_bar.active_tab().and_then(position);
+        let groups = tab_bar
+            .groups()
+            .into_iter()
+            .filter_map(|group| {
+                let info = tab_bar.group(group)?;
+                Some(GroupSession {
+                    name: info.name.clone(),
+                    color: info.color,
+                    collapsed: info.collapsed,
+                    tabs: tab_bar
+                        .tabs()
+                        .iter()
+                        .filter(|tab| tab_bar.group_of(**tab) == Some(group))
+                        .filter_map(|tab| position(*tab))
+                        .collect(),
+                })
+            })
+            .collect();
+
+        Ok(WindowSession {
+            placement,
+            tabs,
+            active,
+            groups,
+        })
+    }
+
+    fn tab_session(&self, tab_bar: &TabBar, tab: TabId) -> TabSession {
+        TabSession {
+            pinned: tab_bar.is_tab_pinned(tab),
+            title: String::from(tab_bar.tab_title(tab)),
+            state: self
+                .tab_panels
+                .get(&tab)
+                .and_then(|id| self.widgets.get::<TabPanel>(*id))
+                .map(|panel| panel.content().save_state())
+                .unwrap_or_default(),
+        }
+    }
+
+    // What it takes to reopen `tab` once it is closed.
+    fn closed_tab(&self, tab: TabId) -> Option<ClosedTab> {
+        let tab_bar = self.tab_bar.and_then(|id| self.widgets.get::<TabBar>(id))?;
+
+        Some(ClosedTab {
+            tab: self.tab_session(tab_bar, tab),
+            index: tab_bar.tab_index(tab)?,
+            window: self.window_index,
+        })
+    }
+
+    // Puts the window back where it was, as far as the monitors connected now allow, and
+    // reopens its tabs and their groups.
+    pub fn restore_session(&mut self, session: &WindowSession) -> Result<(), Error> {
+        let placement = session.placement.clamp_to(&self.platform.monitors()?);
+        self.platform
+            .set_window_bounds(self.window, placement.restored)?;
+        if placement.maximized {
+            self.platform
+                .post_command(self.window, SystemCommand::Maximize)?;
+        }
+
+        let mut tabs = Vec::with_capacity(session.tabs.len());
+        for tab in &session.tabs {
+            tabs.push(self.open_saved_tab(usize::MAX, tab)?);
+        }
+
+        // Collapsed only once the active tab is back, which might be in one of them
+        let mut collapsed = Vec::new();
+        for saved in &session.groups {
+            let members = saved
+                .tabs
+                .iter()
+                .filter_map(|index| tabs.get(*index).copied().flatten())
+                .collect::<Vec<_>>();
+            if let Some(group) = self.create_tab_group(&members, &saved.name)? {
+                self.set_tab_group_color(group, saved.color)?;
+                if saved.collapsed {
+                    collapsed.push(group);
+                }
+            }
+        }
+
+        if let Some(tab) = session
+            .active
+            .and_then(|active| tabs.get(active).copied().flatten())
+        {
+            self.activate_tab(tab)?;
+        }
+        for group in collapsed {
+            self.set_tab_group_collapsed(group, true)?;
+        }
+        Ok(())
+    }
+
+    // Carries the close protocol on until it waits for an answer that wasn't given yet.
+    fn run_close(&mut self, steps: Vec<CloseStep>) -> Result<(), Error> {
+        let mut steps = VecDeque::from(steps);
+        steps.extend(self.close.take_answers());
+
+        while let Some(step) = steps.pop_front() {
+            match step {
+                CloseStep::Ask(party) => {
+                    let reply = self.close.reply(party);
+                    self.ask_to_close(reply)?;
+                }
+                CloseStep::Close { tabs, window } => {
+                    // Tabs closing along with the last window are kept by the session
+                    // instead of the closed tabs. Any other window becomes closed tabs, and
+                    // the session goes on without it.
+                    let last = window && self.is_last_window();
+                    if last {
+                        self.hand_over_session(false)?;
+                        self.with_tab_bar(|tab_bar, ctx| tab_bar.close_all(ctx))?;
+                    } else {
+                        for tab in tabs {
+                            let closed = self.closed_tab(tab);
+                            self.with_tab_bar(|tab_bar, ctx| tab_bar.close_tab(tab, ctx))?;
+                            if let Some(closed) = closed {
+                                self.closed_tabs.borrow_mut().push(closed);
+                            }
+                        }
+                    }
+
+                    if window {
+                        if !last {
+                            self.hand_over_session(true)?;
+                        }
+                        // The session was just handed over
+                        if let Some(action) = self.autosave.as_mut().map(Autosave::cancel) {
+                            self.apply_autosave_action(action)?;
+                        }
+                        self.may_close = true;
+                        self.platform
+                            .post_command(self.window, SystemCommand::Close)?;
+                    }
+                }
+                CloseStep::Cancelled => log_debug!(Target::Window, "closing cancelled"),
+            }
+
+            steps.extend(self.close.take_answers());
+        }
+
+        Ok(())
+    }
+
+    fn ask_to_close(&mut self, reply: CloseReply) -> Result<(), Error> {
+        match reply.party() {
+            Party::Tab(tab) => {
+                let mut ctx = self.context();
+                let panel = self
+                    .tab_panels
+                    .get(&tab)
+                    .and_then(|id| self.widgets.get_mut::<TabPanel>(*id));
+
+                match panel {
+                    Some(panel) => panel.content_mut().on_close_requested(reply, &mut ctx),
+                    None => reply.close(),
+                }
+                self.apply_context(ctx)
+            }
+            Party::Window => {
+                match self.close_handler.as_mut() {
+                    Some(handler) => handler(reply),
+                    None => reply.close(),
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Replays what happened in the tab bar against the content panels: only the active
+    // tab's panel is shown, the others stay in the tree hidden.
+    fn apply_tab_events(&mut self) -> Result<(), Error> {
+        let events = match self
+            .tab_bar
+            .and_then(|id| self.widgets.get_mut::<TabBar>(id))
+        {
+            Some(tab_bar) => tab_bar.take_events(),
+            None => return Ok(()),
+        };
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.session_changed()?;
+
+        let mut ctx = self.context();
+        for event in events {
+            let tab = match event {
+                TabEvent::Opened(tab) => {
+                    self.open_tab_panel(tab);
+                    continue;
+                }
+                // Only the tab bar and the session care where a tab is
+                TabEvent::Moved(_)
+                | TabEvent::Pinned(_)
+                | TabEvent::Unpinned(_)
+                | TabEvent::Grouped(_)
+                | TabEvent::Ungrouped(_) => continue,
+                TabEvent::Activated(tab)
+                | TabEvent::Deactivated(tab)
+                | TabEvent::Closing(tab)
+                | TabEvent::Closed(tab) => tab,
+            };
+            let id = match self.tab_panels.get(&tab) {
+                Some(id) => *id,
+                None => continue,
+            };
+
+            match event {
+                TabEvent::Activated(_) => {
+                    // Hidden panels are skipped by the layout, this one has to catch up on
+                    // resizes before it hears about the activation
+                    self.widgets.set_visible(id, true);
+                    self.reposition_components()?;
+                }
+                TabEvent::Deactivated(_) => self.widgets.set_visible(id, false),
+                _ => {}
+            }
+
+            let panel = match self.widgets.get_mut::<TabPanel>(id) {
+                Some(panel) => panel,
+                None => continue,
+            };
+            ctx.invalidate(panel.bounds());
+
+            let content = panel.content_mut();
+            match event {
+                TabEvent::Activated(_) => content.on_activated(&mut ctx),
+                TabEvent::Deactivated(_) => content.on_deactivated(&mut ctx),
+                TabEvent::Closing(_) => content.on_closing(&mut ctx),
+                TabEvent::Closed(_) => {
+                    content.on_closed();
+                    self.widgets.remove(id);
+                    self.tab_panels.remove(&tab);
+                }
+                TabEvent::Opened(_)
+                | TabEvent::Moved(_)
+                | TabEvent::Pinned(_)
+                | TabEvent::Unpinned(_)
+                | TabEvent::Grouped(_)
+                | TabEvent::Ungrouped(_) => {}
+            }
+        }
+
+        self.apply_context(ctx)
+    }
+
+    fn open_tab_panel(&mut self, tab: TabId) {
+        let state = self.opening_state.take().unwrap_or_default();
+        let content = match self.tab_content.as_ref() {
+            Some(create) => create(tab, &state),
+            None => return,
+        };
+
+        let panel = self.content.and_then(|parent| {
+            self.widgets
+                .add_child(parent, Box::new(TabPanel::new(content)))
+        });
+
+        if let Some(panel) = panel {
+            // Shown once the tab gets activated
+            self.widgets.set_visible(panel, false);
+            self.tab_panels.insert(tab, panel);
+        }
+    }
+
+    fn paint(&self, canvas: &mut dyn Canvas, rect: &Rect, has_focus: bool) -> Result<(), Error> {
+        // Paint Background
+        canvas.fill_rect((*rect).into(), Color::from_rgb(200, 250, 230));
+
+        // Paint Title Bar
+        let title_bar_rect = self.title_bar_rect()?;
+        canvas.fill_rect(
+            title_bar_rect.intersection(rect).into(),
+            Color::from_rgb(150, 200, 180),
+        );
+
+        let demo_color = Color::new(1.0, 0.0, 0.0, 0.5);
+        canvas.draw_line(
+            PointF::new(0.0, 0.0),
+            PointF::new(300.0, 300.0),
+            demo_color,
+            2.0,
+        );
+        canvas.fill_rect(RectF::new(0.0, 0.0, 100.0, 100.0), demo_color);
+
+        // Paint fake top shadow. Original is missing because of the client rect extension.
+        let fake_top_shadow_color = if has_focus {
+            Color::from_rgb(112, 112, 112)
+        } else {
+            Color::from_rgb(170, 170, 170)
+        };
+        let fake_top_shadow_rect = self.fake_shadow_rect()?;
+        canvas.fill_rect(
+            fake_top_shadow_rect.intersection(rect).into(),
+            fake_top_shadow_color,
+        );
+
+        self.widgets.paint(canvas, rect);
+        Ok(())
+    }
+
+    fn paint_dirty(&mut self, system_dirty: Rect) -> Result<(), Error> {
+        let has_focus = self.platform.is_active(self.window);
+
+        // Invalidations coming from the system (e.g. uncovering the window) only show
+        // up in the paint struct.
+        self.dirty.add(system_dirty);
+        let dirty = self.dirty.take();
+
+        let mut renderer = match self.renderer.take() {
+            Some(renderer) => renderer,
+            None => return Ok(()),
+        };
+
+        let result = renderer.draw(|canvas| {
+            dirty.rects().iter().try_for_each(|rect| {
+                canvas.push_clip((*rect).into());
+                let result = self.paint(canvas, rect, has_focus);
+                canvas.pop_clip();
+                result
+            })
+        });
+        self.renderer = Some(renderer);
+
+        match result {
+            Ok(painted) => painted,
+            Err(err) => self.handle_device_error(err),
+        }
+    }
+
+    fn set_dpi(&mut self, dpi: Dpi) {
+        self.dpi = dpi;
+        self.widgets.set_dpi(dpi);
+
+        if let Some(renderer) = self.renderer.as_mut() {
+            renderer.set_dpi(dpi);
+        }
+    }
+
+    fn handle_device_error(&mut self, err: DeviceError) -> Result<(), Error> {
+        match err {
+            // The renderer dropped the device, everything gets painted again on a new one. When
+            // not even a new one could be created, painting right away would just fail again.
+            DeviceError::Lost => match self.renderer.as_ref().and_then(|r| r.retry_delay()) {
+                Some(delay) => self
+                    .platform
+                    .set_timer(self.window, DEVICE_RETRY_TIMER_ID, delay),
+                None => {
+                    let client_rect = self.platform.client_rect(self.window)?;
+                    self.invalidate(client_rect)
+                }
+            },
+            DeviceError::Failed(hr) => Err(Error::Hresult {
+                api: "Direct2D",
+                hr,
+            }),
+        }
+    }
+
+    fn reposition_components(&mut self) -> Result<(), Error> {
+        let dpi = self.dpi;
+        let title_bar_rect = self.title_bar_rect()?;
+
+        let button_item = FlexItem::fixed(dpi.px(TITLEBAR_BUTTON_WIDTH).get());
+        let items = [
+            (
+                self.tab_bar,
+                FlexItem::flexible(1.0).with_margin(TAB_BAR_MARGIN.to_px(dpi)),
+            ),
+            (
+                self.drag_area,
+                FlexItem::fixed(dpi.px(DRAG_AREA_WIDTH).get()),
+            ),
+            (self.minimize_button, button_item),
+            (self.maximize_button, button_item),
+            (self.close_button, button_item),
+        ];
+
+        for (id, item) in items {
+            if let Some(id) = id {
+                self.widgets.set_flex(id, item);
+            }
+        }
+
+        if let Some(title_bar) = self.title_bar.and_then(|id| self.widgets.widget_mut(id)) {
+            title_bar.set_bounds(title_bar_rect);
+        }
+
+        let client_rect = self.platform.client_rect(self.window)?;
+        if let Some(content) = self.content.and_then(|id| self.widgets.widget_mut(id)) {
+            let mut content_rect = client_rect;
+            content_rect.top = title_bar_rect.bottom;
+            content.set_bounds(content_rect);
+        }
+
+        self.widgets.layout(client_rect);
+        Ok(())
+    }
+
+    fn handle_hit_test(&self, pos: Point, system: HitZone) -> Result<Reply, Error> {
+        // Let the default procedure handle resizing areas
+        if system == HitZone::Nowhere || system.is_border() {
+            return Ok(Reply::HitTest(system));
+        }
+
+        // Extending the client area over the title bar also took the top resize border, so
+        // that is detected here.
+        let resize_border = self.platform.resize_border(self.window, self.dpi)?;
+
+        // check if in resize area
+        if pos.y > 0 && pos.y < resize_border {
+            return Ok(Reply::HitTest(HitZone::Top));
+        }
+
+        // Widgets in the title bar take their own input
+        if self.widgets.hit_test(pos.x, pos.y).is_some() {
+            return Ok(Reply::HitTest(HitZone::Client));
+        }
+
+        // check if in window title area
+        if pos.y < self.title_bar_rect()?.bottom {
+            return Ok(Reply::HitTest(HitZone::Caption));
+        }
+
+        Ok(Reply::HitTest(HitZone::Client))
+    }
+
+    fn on_window_created(&mut self) -> Result<(), Error> {
+        self.dpi = self.platform.dpi(self.window)?;
+
+        let client_rect = self.platform.client_rect(self.window)?;
+        self.renderer = Some(RenderResources::new(
+            self.platform.device_factory(self.window),
+            client_rect.size(),
+            self.dpi,
+        ));
+
+        // Makes the system pick up the client area that is extended into the title bar. The
+        // window was created on whatever monitor the system picked, so the size is scaled to
+        // its DPI as well.
+        let window_size = WINDOW_SIZE.to_px(self.dpi, Rounding::Round);
+        self.platform.resize_window(self.window, window_size)?;
+
+        self.on_created();
+        self.widgets.set_dpi(self.dpi);
+        self.reposition_components()
+    }
+
+    fn on_dpi_changed(&mut self, dpi: Dpi, suggested: Rect) -> Result<(), Error> {
+        self.set_dpi(dpi);
+
+        self.platform.set_window_bounds(self.window, suggested)?;
+
+        // The size might not have changed, which leaves the layout to us
+        self.reposition_components()?;
+        let client_rect = self.platform.client_rect(self.window)?;
+        self.invalidate(client_rect)
+    }
+
+    // Our own take on the system's window menu, which wouldn't follow the theme.
+    fn show_system_menu(&mut self, pos: Point) -> Result<(), Error> {
+        if !self.title_bar_rect()?.contains_point(pos.x, pos.y) {
+            return Ok(());
+        }
+
+        let maximized = self.platform.is_maximized(self.window)?;
+        let menu = platform::system_menu(maximized)
+            .into_iter()
+            .map(|entry| entry.map(&MenuPick::System))
+            .collect();
+        self.open_menu(menu, pos)
+    }
+
+    // The title bar is as high as the system's would be, over the top of the client area.
+    fn title_bar_rect(&self) -> Result<Rect, Error> {
+        let mut rect = self.platform.client_rect(self.window)?;
+        let caption_height = self.platform.caption_height(self.window, self.dpi)?;
+
+        rect.bottom = rect.top + caption_height + TOP_AND_BOTTOM_BORDERS;
+        Ok(rect)
+    }
+
+    fn fake_shadow_rect(&self) -> Result<Rect, Error> {
+        let mut rect = self.platform.client_rect(self.window)?;
+
+        rect.bottom = rect.top + FAKE_SHADOW_HEIGHT;
+        Ok(rect)
+    }
+}
+
+impl<P: Platform + 'static> EventHandler for Window<P> {
+    type Custom = ();
+
+    fn attach(&mut self, window: WindowId) {
+        self.window = window;
+    }
+
+    fn handle_event(&mut self, event: Event<()>) -> Result<Reply, Error> {
+        if let Some(reply) = self.menu_event(&event)? {
+            return Ok(reply);
+        }
+        if let Some(reply) = self.editor_event(&event)? {
+            return Ok(reply);
+        }
+
+        match event {
+            Event::Activate(_) => {
+                let buttons = [
+                    self.minimize_button,
+                    self.maximize_button,
+                    self.close_button,
+                ];
+                let bounds = buttons
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|id| self.widgets.widget(id).map(|button| button.bounds()))
+                    .collect::<Vec<_>>();
+
+                for bounds in bounds {
+                    self.invalidate(bounds)?;
+                }
+
+                // The fake shadow changes color with the focus
+                self.invalidate(self.fake_shadow_rect()?)?;
+            }
+            Event::Resize(size) => {
+                self.reposition_components()?;
+                if let Some(Err(err)) = self.renderer.as_mut().map(|r| r.resize(size)) {
+                    self.handle_device_error(err)?;
+                }
+            }
+            Event::HitTest { pos, system } => return self.handle_hit_test(pos, system),
+            Event::NonClientMouseDown {
+                button: MouseButton::Right,
+                ..
+            } => {
+                self.is_down = true;
+                self.platform.set_capture(self.window);
+                return Ok(Reply::Handled);
+            }
+            Event::MouseUp {
+                pos,
+                button: MouseButton::Right,
+                ..
+            } if self.is_down => {
+                // The capture has to go even if showing the menu failed
+                let result = self.show_system_menu(pos);
+                self.is_down = false;
+                self.platform.release_capture(self.window)?;
+                result?;
+            }
+            Event::MouseUp {
+                pos,
+                button: MouseButton::Right,
+                ..
+            } => self.tab_context_menu(pos)?,
+            Event::MouseUp {
+                pos,
+                button: MouseButton::Middle,
+                ..
+            } => self.middle_click(pos)?,
+            Event::EraseBackground => return Ok(Reply::Handled),
+            Event::Paint { dirty } => {
+                self.paint_dirty(dirty)?;
+                return Ok(Reply::Handled);
+            }
+            Event::Timer(DEVICE_RETRY_TIMER_ID) => {
+                self.platform.kill_timer(self.window, DEVICE_RETRY_TIMER_ID);
+                let client_rect = self.platform.client_rect(self.window)?;
+                self.invalidate(client_rect)?;
+                return Ok(Reply::Handled);
+            }
+            Event::Timer(AUTOSAVE_TIMER_ID) => {
+                self.autosave_tick()?;
+                return Ok(Reply::Handled);
+            }
+            Event::Timer(ANIMATION_TIMER_ID) => {
+                let is_animating = self.with_widgets(|widgets, ctx| widgets.frame(ctx))?;
+                let action = self.scheduler.frame(is_animating);
+                self.apply_timer_action(action)?;
+                return Ok(Reply::Handled);
+            }
+            Event::MouseMove { pos, buttons } => {
+                if !self.track_mouse_leave {
+                    self.track_mouse_leave = true;
+                    self.platform.track_mouse_leave(self.window)?;
+                }
+
+                self.with_widgets(|widgets, ctx| {
+                    widgets.mouse_move(pos.x, pos.y, buttons.left, ctx)
+                })?;
+            }
+            Event::MouseLeave => {
+                self.track_mouse_leave = false;
+                self.with_widgets(|widgets, ctx| widgets.mouse_leave(ctx))?;
+            }
+            Event::MouseDown {
+                pos,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let captured =
+                    self.with_widgets(|widgets, ctx| widgets.mouse_down(pos.x, pos.y, ctx))?;
+
+                if captured {
+                    self.platform.set_capture(self.window);
+                }
+            }
+            Event::MouseUp {
+                pos,
+                button: MouseButton::Left,
+                ..
+            } => {
+                let was_captured = self.widgets.is_captured();
+                let result = self.with_widgets(|widgets, ctx| widgets.mouse_up(pos.x, pos.y, ctx));
+
+                if was_captured {
+                    self.platform.release_capture(self.window)?;
+                }
+                result?;
+            }
+            Event::Created => self.on_window_created()?,
+            Event::Dpi { dpi, suggested } => {
+                self.on_dpi_changed(dpi, suggested)?;
+                return Ok(Reply::Handled);
+            }
+            Event::SettingsChanged => {
+                animation::set_animations_enabled(self.platform.animations_enabled());
+            }
+            Event::CloseRequested => {
+                // Once everyone agreed the close gets posted again, and this time goes through
+                if std::mem::take(&mut self.may_close) {
+                    return Ok(Reply::Default);
+                }
+
+                let step = self.close.close_window(&self.tabs());
+                self.run_close(step.into_iter().collect())?;
+                return Ok(Reply::Handled);
+            }
+            Event::Destroyed => {
+                // Tab content hears about the window going away like about its tab closing.
+                // The app quits with its last window.
+                self.destroyed = true;
+                report_window_error(self.with_tab_bar(|tab_bar, ctx| tab_bar.close_all(ctx)));
+                let open = self.app.open.get().saturating_sub(1);
+                self.app.open.set(open);
+                if open == 0 {
+                    self.platform.quit();
+                }
+                return Ok(Reply::Handled);
+            }
+            _ => {}
+        }
+
+        Ok(Reply::Default)
+    }
+}
+
+// Errors can't leave an event handler or a click callback, so they are reported and the
+// window carries on.
+fn report_window_error<T>(result: Result<T, Error>) {
+    if let Err(err) = result {
+        error::report_error(&err.in_component("Window"));
+    }
+}
@@ -1,52 +1,38 @@
-use std::borrow::BorrowMut;
+// Window handles are opaque to Rust code, they are only ever handed back to Win32
+#![allow(clippy::not_unsafe_ptr_arg_deref)]
+
 use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::io;
 use std::mem::MaybeUninit;
 use std::os::windows::prelude::OsStrExt;
 use std::ptr::{null, null_mut};
-use std::sync::{Mutex, Once};
+use std::sync::{Mutex, OnceLock};
 
-use winapi::shared::d3d9types::{D3DCOLORVALUE, D3DCOLOR_COLORVALUE};
+use winapi::shared::d3d9types::D3DCOLORVALUE;
 use winapi::shared::minwindef::*;
 use winapi::shared::windef::*;
-use winapi::shared::winerror::{HRESULT, S_OK};
+use winapi::shared::winerror::S_OK;
 use winapi::um::d2d1::{
     D2D1CreateFactory, ID2D1Factory, D2D1_FACTORY_OPTIONS, D2D1_FACTORY_TYPE_SINGLE_THREADED,
-    D2D1_RECT_F,
 };
+use winapi::um::dwrite::{DWriteCreateFactory, IDWriteFactory, DWRITE_FACTORY_TYPE_SHARED};
 use winapi::um::uxtheme::*;
 use winapi::um::winuser::*;
 use winapi::Interface;
 
+use crate::dpi::Dpi;
+use crate::error::Error;
+use crate::macros::{werr_ifeq, werr_ifisnull, werr_ifne};
+
 pub const CS_ACTIVE: i32 = 1;
 pub const DC_BRUSH: i32 = 18;
 pub const DC_PEN: i32 = 19;
 pub const TMT_CAPTIONFONT: i32 = 801;
 pub const WP_CAPTION: i32 = 1;
 
-pub const TOP_AND_BOTTOM_BORDERS: i32 = 2;
-pub const FAKE_SHADOW_HEIGHT: i32 = 1;
-
 type WndProc =
     unsafe extern "system" fn(hwnd: HWND, message: UINT, wparam: WPARAM, lparam: LPARAM) -> LRESULT;
 
-#[derive(Debug)]
-pub enum Error {
-    Generic(String),
-    WindowsInternal(io::Error),
-    Hresult(HRESULT),
-    ComponentRegistryError,
-    ComponentAlreadyRegistered,
-}
-
-#[derive(Debug)]
-pub struct TitleBarButtonRects {
-    pub close: RECT,
-    pub maximize: RECT,
-    pub minimize: RECT,
-}
-
 pub struct ComponentRegistry {
     registry: Mutex<HashMap<isize, HashMap<&'static str, bool>>>,
 }
@@ -61,15 +47,12 @@ impl ComponentRegistry {
     pub fn set_registered(&self, h_inst: isize, class_name: &'static str) -> Result<(), Error> {
         let mut guard = match self.registry.lock() {
             Ok(ok) => ok,
-            Err(_) => return Err(Error::ComponentRegistryError),
+            Err(_) => return Err(Error::ComponentRegistryPoisoned),
         };
 
-        let registry = guard.borrow_mut();
-        if !registry.contains_key(&h_inst) {
-            registry.insert(h_inst, HashMap::with_capacity(10));
-        }
-
-        let hregistry = registry.get_mut(&h_inst).unwrap();
+        let hregistry = guard
+            .entry(h_inst)
+            .or_insert_with(|| HashMap::with_capacity(10));
         if hregistry.contains_key(class_name) {
             return Err(Error::ComponentAlreadyRegistered);
         }
@@ -81,16 +64,8 @@ impl ComponentRegistry {
 }
 
 pub fn component_registry() -> &'static ComponentRegistry {
-    static mut SINGLETON: MaybeUninit<ComponentRegistry> = MaybeUninit::uninit();
-    static ONCE: Once = Once::new();
-
-    unsafe {
-        ONCE.call_once(|| {
-            SINGLETON.write(ComponentRegistry::new());
-        });
-
-        SINGLETON.assume_init_ref()
-    }
+    static SINGLETON: OnceLock<ComponentRegistry> = OnceLock::new();
+    SINGLETON.get_or_init(ComponentRegistry::new)
 }
 
 pub fn register_class(
@@ -100,6 +75,8 @@ pub fn register_class(
 ) -> Result<(), Error> {
     match component_registry().set_registered(h_inst as isize, class_name) {
         Ok(_) => {
+            // Has to outlive the call to RegisterClassW
+            let wide_class_name = wide_string(class_name);
             let class = WNDCLASSW {
                 style: CS_HREDRAW | CS_VREDRAW | CS_OWNDC,
                 lpfnWndProc: Some(wnd_proc),
@@ -110,14 +87,11 @@ pub fn register_class(
                 hCursor: unsafe { LoadCursorW(null_mut(), IDC_ARROW) },
                 hbrBackground: null_mut(),
                 lpszMenuName: null(),
-                lpszClassName: wide_string(class_name).as_ptr(),
+                lpszClassName: wide_class_name.as_ptr(),
             };
 
-            if unsafe { RegisterClassW(&class) } == 0 {
-                Err(Error::WindowsInternal(io::Error::last_os_error()))
-            } else {
-                Ok(())
-            }
+            werr_ifeq!(RegisterClassW(&class), 0)?;
+            Ok(())
         }
         Err(Error::ComponentAlreadyRegistered) => Ok(()),
         Err(err) => Err(err),
@@ -132,41 +106,32 @@ pub fn wide_string(s: &str) -> Vec<u16> {
 }
 
 pub fn dpi_scale(value: i32, dpi: u32) -> i32 {
-    (value as f32 * dpi as f32 / 96f32) as _
+    Dpi::new(dpi).scale(value)
 }
 
 pub fn get_client_rect(handle: HWND) -> Result<RECT, Error> {
     let mut rect = RECT::default();
 
-    if unsafe { GetClientRect(handle, &mut rect) } != TRUE {
-        return Err(Error::WindowsInternal(io::Error::last_os_error()));
-    }
+    werr_ifne!(GetClientRect(handle, &mut rect), TRUE)?;
 
     Ok(rect)
 }
 
 pub fn get_dpi_for_window(handle: HWND) -> Result<u32, Error> {
-    let dpi = unsafe { GetDpiForWindow(handle) };
-    if dpi == 0 {
-        return Err(Error::Generic(String::from("Failed to get DPI")));
+    // GetDpiForWindow only fails for invalid windows and doesn't set the last error
+    match unsafe { GetDpiForWindow(handle) } {
+        0 => Err(Error::Generic(String::from("GetDpiForWindow failed"))),
+        dpi => Ok(dpi),
     }
-
-    Ok(dpi)
 }
 
 pub fn get_system_metrics_for_dpi(n_index: i32, dpi: u32) -> Result<i32, Error> {
-    let res = unsafe { GetSystemMetricsForDpi(n_index, dpi) };
-    if res == 0 {
-        return Err(Error::WindowsInternal(io::Error::last_os_error()));
-    }
-    Ok(res)
+    werr_ifeq!(GetSystemMetricsForDpi(n_index, dpi), 0)
 }
 
-pub fn get_titlebar_rect(handle: HWND) -> Result<RECT, Error> {
-    let theme = unsafe { OpenThemeData(handle, wide_string("WINDOW").as_ptr()) };
-    if theme.is_null() {
-        return Err(Error::WindowsInternal(io::Error::last_os_error()));
-    }
+// Height of the themed caption, which is what Windows' own title bars use.
+pub fn get_caption_height(handle: HWND, dpi: u32) -> Result<i32, Error> {
+    let theme = werr_ifisnull!(OpenThemeData(handle, wide_string("WINDOW").as_ptr()))?;
 
     let rect = RECT::default();
     let mut size = SIZE::default();
@@ -184,126 +149,125 @@ pub fn get_titlebar_rect(handle: HWND) -> Result<RECT, Error> {
     };
 
     if res != S_OK {
-        return Err(Error::Hresult(res));
+        unsafe { CloseThemeData(theme) };
+        return Err(Error::Hresult {
+            api: "GetThemePartSize",
+            hr: res,
+        });
     }
 
     let res = unsafe { CloseThemeData(theme) };
     if res != S_OK {
-        return Err(Error::Hresult(res));
+        return Err(Error::Hresult {
+            api: "CloseThemeData",
+            hr: res,
+        });
     }
 
-    // if window_is_maximized(handle).unwrap_or(false) {
-    //     size.cy -= 4;
-    // }
+    Ok(dpi_scale(size.cy, dpi))
+}
 
-    let dpi = get_dpi_for_window(handle)?;
+pub fn window_is_maximized(handle: HWND) -> Result<bool, Error> {
+    let mut placement = WINDOWPLACEMENT {
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as _,
+        ..Default::default()
+    };
 
-    let height = dpi_scale(size.cy, dpi) + TOP_AND_BOTTOM_BORDERS;
+    werr_ifne!(GetWindowPlacement(handle, &mut placement), TRUE)?;
 
-    let mut rect = get_client_rect(handle)?;
+    Ok(placement.showCmd == SW_SHOWMAXIMIZED as _)
+}
 
-    rect.bottom = rect.top + height;
+// Where the window goes when restored, in screen coordinates. The placement itself is in
+// workspace coordinates, which leave out a taskbar at the top or left of the monitor.
+pub fn get_restored_rect(handle: HWND) -> Result<RECT, Error> {
+    let mut placement = WINDOWPLACEMENT {
+        length: std::mem::size_of::<WINDOWPLACEMENT>() as _,
+        ..Default::default()
+    };
 
-    Ok(rect)
-}
+    werr_ifne!(GetWindowPlacement(handle, &mut placement), TRUE)?;
 
-pub fn fake_shadow_rect(handle: HWND) -> Result<RECT, Error> {
-    let mut rect = get_client_rect(handle)?;
+    let monitor = get_window_monitor(handle)?;
+    let dx = monitor.rcWork.left - monitor.rcMonitor.left;
+    let dy = monitor.rcWork.top - monitor.rcMonitor.top;
 
-    rect.bottom = rect.top + FAKE_SHADOW_HEIGHT;
+    let mut rect = placement.rcNormalPosition;
+    rect.left += dx;
+    rect.right += dx;
+    rect.top += dy;
+    rect.bottom += dy;
     Ok(rect)
 }
 
-pub fn get_titlebar_button_rects(
-    handle: HWND,
-    title_bar_rect: &RECT,
-) -> Result<TitleBarButtonRects, Error> {
-    let dpi = unsafe { GetDpiForWindow(handle) };
-    if dpi == 0 {
-        return Err(Error::Generic(String::from("Failed to get DPI")));
-    }
+pub fn get_window_monitor(handle: HWND) -> Result<MONITORINFO, Error> {
+    let monitor = unsafe { MonitorFromWindow(handle, MONITOR_DEFAULTTONEAREST) };
+    get_monitor_info(monitor)
+}
 
-    let mut button_rects = TitleBarButtonRects {
-        close: RECT::default(),
-        maximize: RECT::default(),
-        minimize: RECT::default(),
+pub fn get_monitor_info(monitor: HMONITOR) -> Result<MONITORINFO, Error> {
+    let mut info = MONITORINFO {
+        cbSize: std::mem::size_of::<MONITORINFO>() as _,
+        ..Default::default()
     };
-    // Sadly SM_CXSIZE does not result in the right size buttons for Win10
-    let button_width = dpi_scale(47, dpi);
-
-    button_rects.close = *title_bar_rect;
-    button_rects.close.top += FAKE_SHADOW_HEIGHT;
-    // if window_is_maximized(handle).unwrap_or(false) {
-    //     button_rects.close.right -= 2;
-    // }
 
-    button_rects.close.left = button_rects.close.right - button_width;
+    werr_ifne!(GetMonitorInfoW(monitor, &mut info), TRUE)?;
 
-    button_rects.maximize = button_rects.close;
-    button_rects.maximize.left -= button_width;
-    button_rects.maximize.right -= button_width;
+    Ok(info)
+}
 
-    button_rects.minimize = button_rects.maximize;
-    button_rects.minimize.left -= button_width;
-    button_rects.minimize.right -= button_width;
+pub fn get_monitors() -> Result<Vec<MONITORINFO>, Error> {
+    unsafe extern "system" fn collect(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _rect: LPRECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let monitors = &mut *(data as *mut Vec<HMONITOR>);
+        monitors.push(monitor);
+        TRUE
+    }
 
-    Ok(button_rects)
+    let mut monitors: Vec<HMONITOR> = Vec::new();
+    werr_ifne!(
+        EnumDisplayMonitors(
+            null_mut(),
+            null(),
+            Some(collect),
+            &mut monitors as *mut _ as LPARAM,
+        ),
+        TRUE
+    )?;
+
+    monitors.into_iter().map(get_monitor_info).collect()
 }
 
-pub fn window_is_maximized(handle: HWND) -> Result<bool, Error> {
-    let mut placement = WINDOWPLACEMENT {
-        length: std::mem::size_of::<WINDOWPLACEMENT>() as _,
-        ..Default::default()
-    };
+pub fn client_area_animation_enabled() -> Result<bool, Error> {
+    let mut enabled: BOOL = FALSE;
 
-    if unsafe { GetWindowPlacement(handle, &mut placement) } != TRUE {
-        return Err(Error::WindowsInternal(io::Error::last_os_error()));
-    }
+    werr_ifne!(
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            &mut enabled as *mut _ as _,
+            0,
+        ),
+        TRUE
+    )?;
 
-    Ok(placement.showCmd == SW_SHOWMAXIMIZED as _)
+    Ok(enabled == TRUE)
 }
 
 pub fn is_mouse_over(handle: HWND) -> Result<bool, Error> {
     let mut cursor_point = POINT::default();
-    if unsafe { GetCursorPos(&mut cursor_point) } != TRUE {
-        return Err(Error::WindowsInternal(io::Error::last_os_error()));
-    }
-
-    if unsafe { ScreenToClient(handle, &mut cursor_point) } != TRUE {
-        return Err(Error::WindowsInternal(io::Error::last_os_error()));
-    }
+    werr_ifne!(GetCursorPos(&mut cursor_point), TRUE)?;
+    werr_ifne!(ScreenToClient(handle, &mut cursor_point), TRUE)?;
 
     let rect = get_client_rect(handle)?;
 
     Ok(unsafe { PtInRect(&rect, cursor_point) } == TRUE)
 }
 
-pub fn center_rect_in_rect(to_center: &mut RECT, outer_rect: &RECT) {
-    let to_width = to_center.right - to_center.left;
-    let to_height = to_center.bottom - to_center.top;
-    let outer_width = outer_rect.right - outer_rect.left;
-    let outer_height = outer_rect.bottom - outer_rect.top;
-    let padding_x = (outer_width - to_width) / 2;
-    let padding_y = (outer_height - to_height) / 2;
-    to_center.left = outer_rect.left + padding_x;
-    to_center.top = outer_rect.top + padding_y;
-    to_center.right = to_center.left + to_width;
-    to_center.bottom = to_center.top + to_height;
-}
-
-pub fn center_d2drect_in_rect(to_center: &mut D2D1_RECT_F, outer_rect: &D2D1_RECT_F) {
-    let to_width = to_center.right - to_center.left;
-    let to_height = to_center.bottom - to_center.top;
-    let outer_width = outer_rect.right - outer_rect.left;
-    let outer_height = outer_rect.bottom - outer_rect.top;
-    let padding_x = (outer_width - to_width) / 2.0;
-    let padding_y = (outer_height - to_height) / 2.0;
-    to_center.left = outer_rect.left + padding_x;
-    to_center.top = outer_rect.top + padding_y;
-    to_center.right = to_center.left + to_width;
-    to_center.bottom = to_center.top + to_height;
-}
-
 pub fn color_from_argb(color: u32) -> D3DCOLORVALUE {
     D3DCOLORVALUE {
         a: ((color >> 24) & 0xff) as f32 / 255.0,
@@ -340,6 +304,28 @@ pub fn create_d2d_factory<'a>() -> Result<&'a ID2D1Factory, Error> {
     if res == 0 {
         Ok(unsafe { &*d2d_factory.assume_init() })
     } else {
-        Err(Error::Hresult(res))
+        Err(Error::Hresult {
+            api: "D2D1CreateFactory",
+            hr: res,
+        })
+    }
+}
+
+pub fn create_dwrite_factory<'a>() -> Result<&'a IDWriteFactory, Error> {
+    let mut dwrite_factory = MaybeUninit::<*mut IDWriteFactory>::uninit();
+    let res = unsafe {
+        DWriteCreateFactory(
+            DWRITE_FACTORY_TYPE_SHARED,
+            &IDWriteFactory::uuidof(),
+            dwrite_factory.as_mut_ptr() as _,
+        )
+    };
+    if res == 0 {
+        Ok(unsafe { &*dwrite_factory.assume_init() })
+    } else {
+        Err(Error::Hresult {
+            api: "DWriteCreateFactory",
+            hr: res,
+        })
     }
 }
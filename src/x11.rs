@@ -0,0 +1,1337 @@
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+use rustix::event::{poll, PollFd, PollFlags, Timespec};
+use x11rb::connection::Connection;
+use x11rb::cursor::Handle as CursorHandle;
+use x11rb::image::{BitsPerPixel, Image as XImage, ImageOrder, ScanlinePad};
+use x11rb::protocol::xproto::{
+    self, AtomEnum, ChangeWindowAttributesAux, ClientMessageEvent, ConfigureWindowAux,
+    ConnectionExt as _, CreateGCAux, CreateWindowAux, EventMask, KeyButMask, NotifyDetail,
+    NotifyMode, PropMode, WindowClass,
+};
+use x11rb::protocol::Event as XEvent;
+use x11rb::resource_manager::{self, Database};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::CURRENT_TIME;
+
+use crate::animation::{Clock, SystemClock};
+use crate::dpi::Dpi;
+use crate::error::{self, DisplayResultExt, Error};
+use crate::event::{Event, EventHandler, HitZone, Key, MouseButton, MouseButtons, Reply};
+use crate::keymap::Keymap;
+use crate::geometry::{Point, Rect, Size};
+use crate::host::{Dispatch, Host, Hosted};
+use crate::log_warn;
+use crate::logging::Target;
+use crate::platform::{frame_hit_test, Platform, SystemCommand, WindowId, WindowParams};
+use crate::region::DirtyRegion;
+use crate::render_resources::{DeviceError, DeviceFactory};
+use crate::software_canvas::{Pixmap, Present, SoftwareCanvas};
+
+// Without a system caption this is what a custom title bar gets, in pixels at 96 DPI.
+const CAPTION_HEIGHT: i32 = 30;
+const RESIZE_BORDER: i32 = 8;
+// Two presses on the caption within this many milliseconds toggle maximizing.
+const DOUBLE_CLICK_TIME: u32 = 400;
+
+// From the Motif window manager, what `_MOTIF_WM_HINTS` is read as.
+const MWM_HINTS_DECORATIONS: u32 = 1 << 1;
+// `_NET_WM_MOVERESIZE` directions, the source is a normal application
+const MOVERESIZE_SIZE_TOPLEFT: u32 = 0;
+const MOVERESIZE_SIZE_TOP: u32 = 1;
+const MOVERESIZE_SIZE_TOPRIGHT: u32 = 2;
+const MOVERESIZE_SIZE_RIGHT: u32 = 3;
+const MOVERESIZE_SIZE_BOTTOMRIGHT: u32 = 4;
+const MOVERESIZE_SIZE_BOTTOM: u32 = 5;
+const MOVERESIZE_SIZE_BOTTOMLEFT: u32 = 6;
+const MOVERESIZE_SIZE_LEFT: u32 = 7;
+const MOVERESIZE_MOVE: u32 = 8;
+const MOVERESIZE_SIZE_KEYBOARD: u32 = 9;
+const MOVERESIZE_MOVE_KEYBOARD: u32 = 10;
+const SOURCE_APPLICATION: u32 = 1;
+const NET_WM_STATE_REMOVE: u32 = 0;
+const NET_WM_STATE_ADD: u32 = 1;
+const ICONIC_STATE: u32 = 3;
+
+x11rb::atom_manager! {
+    Atoms: AtomsCookie {
+        WM_PROTOCOLS,
+        WM_DELETE_WINDOW,
+        WM_CHANGE_STATE,
+        UTF8_STRING,
+        RESOURCE_MANAGER,
+        _MOTIF_WM_HINTS,
+        _NET_WM_NAME,
+        _NET_WM_MOVERESIZE,
+        _NET_WM_STATE,
+        _NET_WM_STATE_MAXIMIZED_VERT,
+        _NET_WM_STATE_MAXIMIZED_HORZ,
+        _NET_WM_STATE_HIDDEN,
+    }
+}
+
+type SendFn = Rc<dyn Fn(Event<()>) -> Option<Reply>>;
+
+enum Posted {
+    Command(WindowId, SystemCommand),
+    Custom(Box<dyn FnOnce()>),
+}
+
+struct X11Window {
+    size: Size,
+    maximized: bool,
+    // Bounds from before the app maximized the window
+    restored: Option<Rect>,
+    minimized: bool,
+    invalid: DirtyRegion,
+    // Interval and when the timer fires next
+    timers: HashMap<usize, (Duration, Duration)>,
+    tracking_leave: bool,
+    cursor_zone: HitZone,
+    last_caption_press: Option<u32>,
+    gc: xproto::Gcontext,
+    send: SendFn,
+    destroy: Rc<dyn Fn()>,
+}
+
+struct State {
+    windows: HashMap<WindowId, X11Window>,
+    dpi: Dpi,
+    active: Option<WindowId>,
+    captured: Option<WindowId>,
+    posted: VecDeque<Posted>,
+    quit: bool,
+}
+
+// Runs windows on an X server. Windows ask the window manager to leave out its decorations
+// and hand caption drags and border presses back to it with `_NET_WM_MOVERESIZE`, so moving,
+// resizing and snapping stay the window manager's. Painting goes through a software canvas.
+pub struct X11Platform {
+    conn: Rc<RustConnection>,
+    screen: usize,
+    atoms: Atoms,
+    cursors: HashMap<HitZone, xproto::Cursor>,
+    keymap: Keymap,
+    clock: Rc<SystemClock>,
+    state: Rc<RefCell<State>>,
+}
+
+impl X11Platform {
+    // Connects to the display in `DISPLAY`.
+    pub fn connect() -> Result<Self, Error> {
+        let (conn, screen) = RustConnection::connect(None).or_display("connect")?;
+        let atoms = Atoms::new(&conn)
+            .or_display("InternAtom")?
+            .reply()
+            .or_display("InternAtom")?;
+
+        let database = resource_manager::new_from_default(&conn).or_display("GetProperty")?;
+        let cursor_handle = CursorHandle::new(&conn, screen, &database)
+            .or_display("cursor")?
+            .reply()
+            .or_display("cursor")?;
+
+        let cursor_names = [
+            (HitZone::Client, "left_ptr"),
+            (HitZone::Left, "left_side"),
+            (HitZone::Right, "right_side"),
+            (HitZone::Top, "top_side"),
+            (HitZone::Bottom, "bottom_side"),
+            (HitZone::TopLeft, "top_left_corner"),
+            (HitZone::TopRight, "top_right_corner"),
+            (HitZone::BottomLeft, "bottom_left_corner"),
+            (HitZone::BottomRight, "bottom_right_corner"),
+        ];
+        let mut cursors = HashMap::new();
+        for (zone, name) in cursor_names {
+            let cursor = cursor_handle
+                .load_cursor(&conn, name)
+                .or_display("CreateCursor")?;
+            cursors.insert(zone, cursor);
+        }
+
+        let (min_keycode, max_keycode) = (conn.setup().min_keycode, conn.setup().max_keycode);
+        let mapping = conn
+            .get_keyboard_mapping(min_keycode, max_keycode - min_keycode + 1)
+            .or_display("GetKeyboardMapping")?
+            .reply()
+            .or_display("GetKeyboardMapping")?;
+        let keymap =
+            Keymap::from_x11(min_keycode, mapping.keysyms_per_keycode, &mapping.keysyms);
+
+        // DPI changes show up as a new resource database on the root window
+        let root = conn.setup().roots[screen].root;
+        conn.change_window_attributes(
+            root,
+            &ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+        )
+        .or_display("ChangeWindowAttributes")?;
+
+        Ok(Self {
+            conn: Rc::new(conn),
+            screen,
+            atoms,
+            cursors,
+            keymap,
+            clock: Rc::new(SystemClock::new()),
+            state: Rc::new(RefCell::new(State {
+                windows: HashMap::new(),
+                dpi: database_dpi(&database),
+                active: None,
+                captured: None,
+                posted: VecDeque::new(),
+                quit: false,
+            })),
+        })
+    }
+
+    fn root(&self) -> xproto::Window {
+        self.conn.setup().roots[self.screen].root
+    }
+
+    fn xid(window: WindowId) -> xproto::Window {
+        window.0 as _
+    }
+
+    // Handles events, timers and painting until the app quits.
+    pub fn run(&self) -> Result<(), Error> {
+        while !self.state.borrow().quit {
+            self.run_pending();
+            self.fire_timers();
+            self.paint_invalid();
+            self.conn.flush().or_display("flush")?;
+
+            let mut handled = false;
+            while let Some(event) = self.conn.poll_for_event().or_display("poll_for_event")? {
+                self.handle_event(event);
+                handled = true;
+            }
+
+            if !handled && !self.state.borrow().quit {
+                self.wait()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Sleeps until the server sends something or the next timer is due.
+    fn wait(&self) -> Result<(), Error> {
+        let timeout = self.next_timer().map(|due| {
+            let wait = due.saturating_sub(self.clock.now());
+            Timespec {
+                tv_sec: wait.as_secs() as _,
+                tv_nsec: wait.subsec_nanos() as _,
+            }
+        });
+
+        let stream = self.conn.stream();
+        let mut fds = [PollFd::new(stream, PollFlags::IN)];
+        match poll(&mut fds, timeout.as_ref()) {
+            Ok(_) | Err(rustix::io::Errno::INTR) => Ok(()),
+            Err(err) => Err(Error::display("poll", err)),
+        }
+    }
+
+    fn run_pending(&self) {
+        loop {
+            let posted = self.state.borrow_mut().posted.pop_front();
+            match posted {
+                Some(Posted::Command(window, command)) => {
+                    if let Err(err) = self.run_command(window, command) {
+                        error::report_error(&err);
+                    }
+                }
+                Some(Posted::Custom(deliver)) => deliver(),
+                None => break,
+            }
+        }
+    }
+
+    fn run_command(&self, window: WindowId, command: SystemCommand) -> Result<(), Error> {
+        let xid = Self::xid(window);
+        let atoms = &self.atoms;
+
+        let (message_type, data) = match command {
+            SystemCommand::Close => {
+                close_window(&self.conn, &self.state, window);
+                return Ok(());
+            }
+            SystemCommand::Minimize => (atoms.WM_CHANGE_STATE, [ICONIC_STATE, 0, 0, 0, 0]),
+            SystemCommand::Maximize | SystemCommand::Restore => {
+                if command == SystemCommand::Maximize && !self.is_maximized(window)? {
+                    let bounds = self.window_bounds(window)?;
+                    self.with_window(window, |data| data.restored = Some(bounds))?;
+                }
+
+                let action = if command == SystemCommand::Maximize {
+                    NET_WM_STATE_ADD
+                } else {
+                    NET_WM_STATE_REMOVE
+                };
+                let data = [
+                    action,
+                    atoms._NET_WM_STATE_MAXIMIZED_VERT,
+                    atoms._NET_WM_STATE_MAXIMIZED_HORZ,
+                    SOURCE_APPLICATION,
+                    0,
+                ];
+                (atoms._NET_WM_STATE, data)
+            }
+            SystemCommand::Move | SystemCommand::Size => {
+                let direction = if command == SystemCommand::Move {
+                    MOVERESIZE_MOVE_KEYBOARD
+                } else {
+                    MOVERESIZE_SIZE_KEYBOARD
+                };
+                let data = [0, 0, direction, 0, SOURCE_APPLICATION];
+                (atoms._NET_WM_MOVERESIZE, data)
+            }
+        };
+
+        self.send_to_window_manager(ClientMessageEvent::new(32, xid, message_type, data))
+    }
+
+    fn send_to_window_manager(&self, event: ClientMessageEvent) -> Result<(), Error> {
+        self.conn
+            .send_event(
+                false,
+                self.root(),
+                EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+                event,
+            )
+            .or_display("SendEvent")?;
+        Ok(())
+    }
+
+    fn next_timer(&self) -> Option<Duration> {
+        let state = self.state.borrow();
+        state
+            .windows
+            .values()
+            .flat_map(|data| data.timers.values().map(|(_, due)| *due))
+            .min()
+    }
+
+    fn fire_timers(&self) {
+        let now = self.clock.now();
+        let due = {
+            let mut state = self.state.borrow_mut();
+            let mut due = Vec::new();
+            for (window, data) in state.windows.iter_mut() {
+                for (id, (interval, next)) in data.timers.iter_mut() {
+                    if *next <= now {
+                        // Late timers fire once, like WM_TIMER
+                        *next = now + *interval;
+                        due.push((*window, *id));
+                    }
+                }
+            }
+            due
+        };
+
+        for (window, id) in due {
+            self.send(window, Event::Timer(id));
+        }
+    }
+
+    // Paints whatever was invalidated, once there is nothing else to do like the system does.
+    fn paint_invalid(&self) {
+        let dirty = {
+            let mut state = self.state.borrow_mut();
+            state
+                .windows
+                .iter_mut()
+                .filter(|(_, data)| !data.invalid.is_empty() && !data.minimized)
+                .map(|(window, data)| (*window, data.invalid.take().bounds()))
+                .collect::<Vec<_>>()
+        };
+
+        for (window, dirty) in dirty {
+            self.send(window, Event::Paint { dirty });
+        }
+    }
+
+    fn send(&self, window: WindowId, event: Event<()>) -> Option<Reply> {
+        let send = self.state.borrow().windows.get(&window)?.send.clone();
+        send(event)
+    }
+
+    fn window_id(&self, xid: xproto::Window) -> Option<WindowId> {
+        let window = WindowId(xid as _);
+        self.state
+            .borrow()
+            .windows
+            .contains_key(&window)
+            .then_some(window)
+    }
+
+    fn handle_event(&self, event: XEvent) {
+        let result = match event {
+            XEvent::Expose(event) => self.window_id(event.window).map_or(Ok(()), |window| {
+                let rect = Rect::from_size(
+                    event.x as _,
+                    event.y as _,
+                    event.width as _,
+                    event.height as _,
+                );
+                self.invalidate(window, rect)
+            }),
+            XEvent::ConfigureNotify(event) => {
+                if let Some(window) = self.window_id(event.window) {
+                    self.on_configure(window, Size::new(event.width as _, event.height as _));
+                }
+                Ok(())
+            }
+            XEvent::ButtonPress(event) => self
+                .window_id(event.event)
+                .map_or(Ok(()), |window| self.on_button_press(window, &event)),
+            XEvent::ButtonRelease(event) => {
+                if let Some(window) = self.window_id(event.event) {
+                    self.on_button_release(window, &event);
+                }
+                Ok(())
+            }
+            XEvent::KeyPress(event) => {
+                if let Some(window) = self.window_id(event.event) {
+                    self.on_key_press(window, &event);
+                }
+                Ok(())
+            }
+            XEvent::MotionNotify(event) => self.window_id(event.event).map_or(Ok(()), |window| {
+                let pos = Point::new(event.event_x as _, event.event_y as _);
+                self.on_motion(window, pos, buttons_from_state(event.state))
+            }),
+            XEvent::LeaveNotify(event) => {
+                // The pointer moving into a child or a grab starting isn't leaving
+                if event.mode == NotifyMode::NORMAL && event.detail != NotifyDetail::INFERIOR {
+                    if let Some(window) = self.window_id(event.event) {
+                        self.leave(window);
+                    }
+                }
+                Ok(())
+            }
+            XEvent::FocusIn(event) if event.detail != NotifyDetail::POINTER => {
+                if let Some(window) = self.window_id(event.event) {
+                    self.set_active(window, true);
+                }
+                Ok(())
+            }
+            XEvent::FocusOut(event) if event.detail != NotifyDetail::POINTER => {
+                // Moving or resizing grabs the keyboard for a moment, which doesn't deactivate
+                if event.mode != NotifyMode::GRAB {
+                    if let Some(window) = self.window_id(event.event) {
+                        self.set_active(window, false);
+                    }
+                }
+                Ok(())
+            }
+            XEvent::ClientMessage(event) => {
+                let deleted = event.type_ == self.atoms.WM_PROTOCOLS
+                    && event.data.as_data32()[0] == self.atoms.WM_DELETE_WINDOW;
+
+                if let Some(window) = self.window_id(event.window).filter(|_| deleted) {
+                    close_window(&self.conn, &self.state, window);
+                }
+                Ok(())
+            }
+            XEvent::PropertyNotify(event) => self.on_property_changed(event.window, event.atom),
+            XEvent::DestroyNotify(event) => {
+                // Destroyed by someone else
+                if let Some(window) = self.window_id(event.window) {
+                    destroy_window(&self.conn, &self.state, window);
+                }
+                Ok(())
+            }
+            XEvent::Error(err) => {
+                log_warn!(Target::Window, "X11 request failed: {:?}", err);
+                Ok(())
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(err) = result {
+            error::report_error(&err);
+        }
+    }
+
+    fn on_configure(&self, window: WindowId, size: Size) {
+        let resized = match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) if data.size != size => {
+                data.size = size;
+                true
+            }
+            _ => false,
+        };
+
+        if resized {
+            self.send(window, Event::Resize(size));
+        }
+    }
+
+    // What the window manager would do with a press at `pos`, which is nothing since it was
+    // told to leave the frame to the window.
+    fn system_hit_test(&self, window: WindowId, pos: Point) -> Result<HitZone, Error> {
+        let rect = self.client_rect(window)?;
+        if self.is_maximized(window)? {
+            return Ok(frame_hit_test(rect, pos, 0));
+        }
+
+        let border = self.resize_border(window, self.dpi(window)?)?;
+        Ok(frame_hit_test(rect, pos, border))
+    }
+
+    fn hit_test(&self, window: WindowId, pos: Point) -> Result<HitZone, Error> {
+        let system = self.system_hit_test(window, pos)?;
+
+        match self.send(window, Event::HitTest { pos, system }) {
+            Some(Reply::HitTest(zone)) => Ok(zone),
+            _ => Ok(system),
+        }
+    }
+
+    fn on_button_press(
+        &self,
+        window: WindowId,
+        event: &xproto::ButtonPressEvent,
+    ) -> Result<(), Error> {
+        let button = match button_from_detail(event.detail) {
+            Some(button) => button,
+            // Wheel
+            None => return Ok(()),
+        };
+
+        let pos = Point::new(event.event_x as _, event.event_y as _);
+        let screen_pos = Point::new(event.root_x as _, event.root_y as _);
+        let mut buttons = buttons_from_state(event.state);
+        set_button(&mut buttons, button, true);
+
+        let captured = self.state.borrow().captured == Some(window);
+        let zone = if captured {
+            HitZone::Client
+        } else {
+            self.hit_test(window, pos)?
+        };
+
+        match zone {
+            HitZone::Client | HitZone::Other => {
+                self.send(
+                    window,
+                    Event::MouseDown {
+                        pos,
+                        button,
+                        buttons,
+                    },
+                );
+            }
+            HitZone::Nowhere => {}
+            HitZone::Caption if button == MouseButton::Left => {
+                if self.is_double_click(window, event.time) {
+                    let command = if self.is_maximized(window)? {
+                        SystemCommand::Restore
+                    } else {
+                        SystemCommand::Maximize
+                    };
+                    self.post_command(window, command)?;
+                } else {
+                    self.move_resize(window, screen_pos, MOVERESIZE_MOVE)?;
+                }
+            }
+            zone if button == MouseButton::Left => {
+                if let Some(direction) = resize_direction(zone) {
+                    self.move_resize(window, screen_pos, direction)?;
+                }
+            }
+            _ => {
+                self.send(
+                    window,
+                    Event::NonClientMouseDown {
+                        pos: screen_pos,
+                        button,
+                    },
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_double_click(&self, window: WindowId, time: u32) -> bool {
+        let mut state = self.state.borrow_mut();
+        let data = match state.windows.get_mut(&window) {
+            Some(data) => data,
+            None => return false,
+        };
+
+        let previous = data.last_caption_press.replace(time);
+        match previous {
+            Some(previous) if time.wrapping_sub(previous) <= DOUBLE_CLICK_TIME => {
+                data.last_caption_press = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn on_button_release(&self, window: WindowId, event: &xproto::ButtonReleaseEvent) {
+        let button = match button_from_detail(event.detail) {
+            Some(button) => button,
+            None => return,
+        };
+
+        let mut buttons = buttons_from_state(event.state);
+        set_button(&mut buttons, button, false);
+
+        self.send(
+            window,
+            Event::MouseUp {
+                pos: Point::new(event.event_x as _, event.event_y as _),
+                button,
+                buttons,
+            },
+        );
+    }
+
+    // The key goes by what it is without modifiers, the text by the shift level and the
+    // group the state selects.
+    fn on_key_press(&self, window: WindowId, event: &xproto::KeyPressEvent) {
+        let symbols = match self.keymap.symbols(event.detail as u32) {
+            Some(symbols) => symbols,
+            None => {
+                self.send(window, Event::KeyDown(Key::Other));
+                return;
+            }
+        };
+
+        self.send(window, Event::KeyDown(symbols.key()));
+        let state = u16::from(event.state) as u32;
+        let group = (state >> 13) as usize & 3;
+        if let Some(ch) = symbols.text(group, state) {
+            self.send(window, Event::Text(ch));
+        }
+    }
+
+    fn on_motion(&self, window: WindowId, pos: Point, buttons: MouseButtons) -> Result<(), Error> {
+        let captured = self.state.borrow().captured == Some(window);
+        let pressed = buttons.left || buttons.right || buttons.middle;
+
+        // The zone only changes while nothing is pressed, like the cursor on Windows
+        let zone = if captured || pressed {
+            HitZone::Client
+        } else {
+            self.hit_test(window, pos)?
+        };
+        self.set_cursor(window, zone)?;
+
+        if matches!(zone, HitZone::Client | HitZone::Other) {
+            self.send(window, Event::MouseMove { pos, buttons });
+        } else {
+            // Over the caption or the borders the window doesn't get mouse moves
+            self.leave(window);
+        }
+
+        Ok(())
+    }
+
+    fn set_cursor(&self, window: WindowId, zone: HitZone) -> Result<(), Error> {
+        let zone = if zone.is_border() {
+            zone
+        } else {
+            HitZone::Client
+        };
+
+        let changed = match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => std::mem::replace(&mut data.cursor_zone, zone) != zone,
+            None => false,
+        };
+
+        if let Some(cursor) = self.cursors.get(&zone).filter(|_| changed) {
+            self.conn
+                .change_window_attributes(
+                    Self::xid(window),
+                    &ChangeWindowAttributesAux::new().cursor(*cursor),
+                )
+                .or_display("ChangeWindowAttributes")?;
+        }
+        Ok(())
+    }
+
+    fn leave(&self, window: WindowId) {
+        let tracking = match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => std::mem::replace(&mut data.tracking_leave, false),
+            None => false,
+        };
+
+        if tracking {
+            self.send(window, Event::MouseLeave);
+        }
+    }
+
+    fn set_active(&self, window: WindowId, active: bool) {
+        {
+            let mut state = self.state.borrow_mut();
+            if active {
+                state.active = Some(window);
+            } else if state.active == Some(window) {
+                state.active = None;
+            } else {
+                return;
+            }
+        }
+
+        self.send(window, Event::Activate(active));
+        self.send(window, Event::Focus(active));
+    }
+
+    // Hands the pointer to the window manager, which moves or resizes until it is released.
+    fn move_resize(
+        &self,
+        window: WindowId,
+        screen_pos: Point,
+        direction: u32,
+    ) -> Result<(), Error> {
+        self.state.borrow_mut().captured = None;
+        self.conn
+            .ungrab_pointer(CURRENT_TIME)
+            .or_display("UngrabPointer")?;
+
+        let data = [
+            screen_pos.x as u32,
+            screen_pos.y as u32,
+            direction,
+            1,
+            SOURCE_APPLICATION,
+        ];
+        let event =
+            ClientMessageEvent::new(32, Self::xid(window), self.atoms._NET_WM_MOVERESIZE, data);
+        self.send_to_window_manager(event)
+    }
+
+    fn on_property_changed(&self, xid: xproto::Window, atom: xproto::Atom) -> Result<(), Error> {
+        if xid == self.root() && atom == self.atoms.RESOURCE_MANAGER {
+            return self.on_resources_changed();
+        }
+
+        let window = match self.window_id(xid) {
+            Some(window) if atom == self.atoms._NET_WM_STATE => window,
+            _ => return Ok(()),
+        };
+
+        let reply = self
+            .conn
+            .get_property(false, xid, atom, AtomEnum::ATOM, 0, 64)
+            .or_display("GetProperty")?
+            .reply()
+            .or_display("GetProperty")?;
+        let states = reply.value32().into_iter().flatten().collect::<Vec<_>>();
+
+        let maximized = states.contains(&self.atoms._NET_WM_STATE_MAXIMIZED_VERT)
+            && states.contains(&self.atoms._NET_WM_STATE_MAXIMIZED_HORZ);
+        let minimized = states.contains(&self.atoms._NET_WM_STATE_HIDDEN);
+
+        let changed = match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => {
+                let changed = data.maximized != maximized;
+                data.maximized = maximized;
+                data.minimized = minimized;
+                changed
+            }
+            None => false,
+        };
+
+        // The caption buttons show whether the window is maximized
+        if changed {
+            self.invalidate(window, self.client_rect(window)?)?;
+        }
+        Ok(())
+    }
+
+    fn on_resources_changed(&self) -> Result<(), Error> {
+        let database = resource_manager::new_from_default(&*self.conn).or_display("GetProperty")?;
+        let dpi = database_dpi(&database);
+
+        let (previous, windows) = {
+            let mut state = self.state.borrow_mut();
+            let previous = std::mem::replace(&mut state.dpi, dpi);
+            (previous, state.windows.keys().copied().collect::<Vec<_>>())
+        };
+
+        if previous == dpi {
+            return Ok(());
+        }
+
+        for window in windows {
+            let bounds = self.window_bounds(window)?;
+            let factor = dpi.factor() / previous.factor();
+            let suggested = Rect::from_size(
+                bounds.left,
+                bounds.top,
+                (bounds.width() as f32 * factor).round() as _,
+                (bounds.height() as f32 * factor).round() as _,
+            );
+            self.send(window, Event::Dpi { dpi, suggested });
+        }
+        Ok(())
+    }
+
+    fn with_window<R>(
+        &self,
+        window: WindowId,
+        f: impl FnOnce(&mut X11Window) -> R,
+    ) -> Result<R, Error> {
+        match self.state.borrow_mut().windows.get_mut(&window) {
+            Some(data) => Ok(f(data)),
+            None => Err(Error::Generic(format!("no window {:?}", window))),
+        }
+    }
+}
+
+// Destroys the window unless its component wants to keep it open.
+fn close_window(conn: &RustConnection, state: &Rc<RefCell<State>>, window: WindowId) {
+    let send = match state.borrow().windows.get(&window) {
+        Some(data) => data.send.clone(),
+        None => return,
+    };
+
+    if send(Event::CloseRequested) != Some(Reply::Handled) {
+        destroy_window(conn, state, window);
+    }
+}
+
+// Forgets the window and destroys it, which makes it drop its component after telling it.
+fn destroy_window(conn: &RustConnection, state: &Rc<RefCell<State>>, window: WindowId) {
+    let data = match state.borrow_mut().windows.remove(&window) {
+        Some(data) => data,
+        None => return,
+    };
+
+    {
+        let mut state = state.borrow_mut();
+        if state.captured == Some(window) {
+            state.captured = None;
+        }
+        if state.active == Some(window) {
+            state.active = None;
+        }
+    }
+
+    (data.send)(Event::Destroyed);
+    (data.destroy)();
+
+    let xid = window.0 as _;
+    let result = conn
+        .free_gc(data.gc)
+        .and_then(|_| conn.destroy_window(xid))
+        .and_then(|_| conn.flush());
+    if let Err(err) = result {
+        error::report_error(&Error::display("DestroyWindow", err));
+    }
+}
+
+impl Platform for X11Platform {
+    type DeviceFactory = X11DeviceFactory;
+
+    fn create_window<C: EventHandler + 'static>(
+        &self,
+        params: &WindowParams,
+        component: C,
+    ) -> Result<Hosted<C>, Error> {
+        let conn = &*self.conn;
+        let screen = &conn.setup().roots[self.screen];
+        if screen.root_depth != 24 && screen.root_depth != 32 {
+            return Err(Error::Generic(format!(
+                "unsupported screen depth {}",
+                screen.root_depth
+            )));
+        }
+
+        let xid = conn.generate_id().or_display("generate_id")?;
+        let gc = conn.generate_id().or_display("generate_id")?;
+        // Popups are placed by the app, the window manager leaves them alone
+        let (origin, size) = match params.popup {
+            Some(popup) => (
+                self.client_to_screen(
+                    popup.owner,
+                    Point::new(popup.bounds.left, popup.bounds.top),
+                )?,
+                popup.bounds.size(),
+            ),
+            None => (Point::new(0, 0), params.size),
+        };
+
+        let event_mask = EventMask::EXPOSURE
+            | EventMask::STRUCTURE_NOTIFY
+            | EventMask::PROPERTY_CHANGE
+            | EventMask::FOCUS_CHANGE
+            | EventMask::KEY_PRESS
+            | EventMask::BUTTON_PRESS
+            | EventMask::BUTTON_RELEASE
+            | EventMask::POINTER_MOTION
+            | EventMask::LEAVE_WINDOW;
+
+        conn.create_window(
+            screen.root_depth,
+            xid,
+            screen.root,
+            origin.x as _,
+            origin.y as _,
+            size.width.max(1) as _,
+            size.height.max(1) as _,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            screen.root_visual,
+            &CreateWindowAux::new()
+                .event_mask(event_mask)
+                .override_redirect(params.popup.map(|_| 1))
+                .cursor(self.cursors.get(&HitZone::Client).copied()),
+        )
+        .or_display("CreateWindow")?;
+        conn.create_gc(gc, xid, &CreateGCAux::new())
+            .or_display("CreateGC")?;
+
+        let title = params.title.as_bytes();
+        let class = format!("{0}\0{0}\0", params.class_name);
+        conn.change_property8(
+            PropMode::REPLACE,
+            xid,
+            AtomEnum::WM_NAME,
+            AtomEnum::STRING,
+            title,
+        )
+        .or_display("ChangeProperty")?;
+        conn.change_property8(
+            PropMode::REPLACE,
+            xid,
+            self.atoms._NET_WM_NAME,
+            self.atoms.UTF8_STRING,
+            title,
+        )
+        .or_display("ChangeProperty")?;
+        conn.change_property8(
+            PropMode::REPLACE,
+            xid,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            class.as_bytes(),
+        )
+        .or_display("ChangeProperty")?;
+        conn.change_property32(
+            PropMode::REPLACE,
+            xid,
+            self.atoms.WM_PROTOCOLS,
+            AtomEnum::ATOM,
+            &[self.atoms.WM_DELETE_WINDOW],
+        )
+        .or_display("ChangeProperty")?;
+
+        if let Some(popup) = params.popup {
+            conn.change_property32(
+                PropMode::REPLACE,
+                xid,
+                AtomEnum::WM_TRANSIENT_FOR,
+                AtomEnum::WINDOW,
+                &[Self::xid(popup.owner)],
+            )
+            .or_display("ChangeProperty")?;
+        }
+
+        if params.custom_frame {
+            // Flags, functions, decorations, input mode and status. Only the decorations are
+            // set, to none.
+            let hints = [MWM_HINTS_DECORATIONS, 0, 0, 0, 0];
+            conn.change_property32(
+                PropMode::REPLACE,
+                xid,
+                self.atoms._MOTIF_WM_HINTS,
+                self.atoms._MOTIF_WM_HINTS,
+                &hints,
+            )
+            .or_display("ChangeProperty")?;
+        }
+
+        let window = WindowId(xid as _);
+        let host = Rc::new(Host::new(component));
+        host.with(|component| component.attach(window));
+
+        let name = params.class_name;
+        let send: SendFn = {
+            let host = host.clone();
+            Rc::new(move |event| {
+                let event = event.cast().ok()?;
+                let handle = |component: &mut C, event| match component.handle_event(event) {
+                    Ok(reply) => reply,
+                    Err(err) => {
+                        error::report_error(&err.in_component(name));
+                        Reply::Default
+                    }
+                };
+
+                match host.dispatch(event, handle) {
+                    Dispatch::Handled(reply) => Some(reply),
+                    Dispatch::Deferred | Dispatch::Busy | Dispatch::Gone => None,
+                }
+            })
+        };
+
+        let destroy: Rc<dyn Fn()> = {
+            let host = host.clone();
+            Rc::new(move || host.destroy())
+        };
+
+        self.state.borrow_mut().windows.insert(
+            window,
+            X11Window {
+                size,
+                maximized: false,
+                restored: None,
+                minimized: false,
+                invalid: DirtyRegion::new(),
+                timers: HashMap::new(),
+                tracking_leave: false,
+                cursor_zone: HitZone::Client,
+                last_caption_press: None,
+                gc,
+                send: send.clone(),
+                destroy,
+            },
+        );
+
+        send(Event::Created);
+        send(Event::Resize(self.client_rect(window)?.size()));
+
+        conn.map_window(xid).or_display("MapWindow")?;
+        conn.flush().or_display("flush")?;
+
+        let conn = self.conn.clone();
+        let state = self.state.clone();
+        Ok(Hosted::new(host, window, move || {
+            destroy_window(&conn, &state, window)
+        }))
+    }
+
+    fn post_event<C: EventHandler + 'static>(
+        &self,
+        window: &Hosted<C>,
+        event: C::Custom,
+    ) -> Result<(), Error> {
+        let host = window.host().clone();
+        let deliver = move || {
+            host.dispatch(Event::Custom(event), |component, event| {
+                if let Err(err) = component.handle_event(event) {
+                    error::report_error(&err);
+                }
+            });
+        };
+
+        let mut state = self.state.borrow_mut();
+        state.posted.push_back(Posted::Custom(Box::new(deliver)));
+        Ok(())
+    }
+
+    fn post_command(&self, window: WindowId, command: SystemCommand) -> Result<(), Error> {
+        let mut state = self.state.borrow_mut();
+        state.posted.push_back(Posted::Command(window, command));
+        Ok(())
+    }
+
+    fn quit(&self) {
+        self.state.borrow_mut().quit = true;
+    }
+
+    fn device_factory(&self, window: WindowId) -> X11DeviceFactory {
+        let gc = self
+            .state
+            .borrow()
+            .windows
+            .get(&window)
+            .map(|data| data.gc)
+            .unwrap_or_default();
+
+        X11DeviceFactory {
+            conn: self.conn.clone(),
+            window: Self::xid(window),
+            gc,
+            depth: self.conn.setup().roots[self.screen].root_depth,
+        }
+    }
+
+    fn clock(&self) -> Rc<dyn Clock> {
+        self.clock.clone()
+    }
+
+    // X11 has one DPI for all screens.
+    fn dpi(&self, _window: WindowId) -> Result<Dpi, Error> {
+        Ok(self.state.borrow().dpi)
+    }
+
+    fn client_rect(&self, window: WindowId) -> Result<Rect, Error> {
+        self.with_window(window, |data| {
+            Rect::from_size(0, 0, data.size.width, data.size.height)
+        })
+    }
+
+    fn window_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        let origin = self.client_to_screen(window, Point::new(0, 0))?;
+        let size = self.client_rect(window)?.size();
+        Ok(Rect::from_size(origin.x, origin.y, size.width, size.height))
+    }
+
+    fn set_window_bounds(&self, window: WindowId, bounds: Rect) -> Result<(), Error> {
+        let aux = ConfigureWindowAux::new()
+            .x(bounds.left)
+            .y(bounds.top)
+            .width(bounds.width().max(1) as u32)
+            .height(bounds.height().max(1) as u32);
+
+        self.conn
+            .configure_window(Self::xid(window), &aux)
+            .or_display("ConfigureWindow")?;
+        Ok(())
+    }
+
+    fn resize_window(&self, window: WindowId, size: Size) -> Result<(), Error> {
+        let aux = ConfigureWindowAux::new()
+            .width(size.width.max(1) as u32)
+            .height(size.height.max(1) as u32);
+
+        self.conn
+            .configure_window(Self::xid(window), &aux)
+            .or_display("ConfigureWindow")?;
+        Ok(())
+    }
+
+    // Only known when the app maximized the window itself, otherwise the maximized bounds are
+    // all there is.
+    fn restored_bounds(&self, window: WindowId) -> Result<Rect, Error> {
+        let restored = self.with_window(window, |data| data.restored.filter(|_| data.maximized))?;
+        match restored {
+            Some(restored) => Ok(restored),
+            None => self.window_bounds(window),
+        }
+    }
+
+    fn is_maximized(&self, window: WindowId) -> Result<bool, Error> {
+        self.with_window(window, |data| data.maximized)
+    }
+
+    fn is_active(&self, window: WindowId) -> bool {
+        self.state.borrow().active == Some(window)
+    }
+
+    fn caption_height(&self, _window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        Ok(dpi.scale(CAPTION_HEIGHT))
+    }
+
+    fn resize_border(&self, _window: WindowId, dpi: Dpi) -> Result<i32, Error> {
+        Ok(dpi.scale(RESIZE_BORDER))
+    }
+
+    fn invalidate(&self, window: WindowId, rect: Rect) -> Result<(), Error> {
+        self.with_window(window, |data| data.invalid.add(rect))
+    }
+
+    // The press already grabbed the pointer for the window until the release.
+    fn set_capture(&self, window: WindowId) {
+        self.state.borrow_mut().captured = Some(window);
+    }
+
+    fn release_capture(&self, window: WindowId) -> Result<(), Error> {
+        let mut state = self.state.borrow_mut();
+        if state.captured == Some(window) {
+            state.captured = None;
+        }
+        Ok(())
+    }
+
+    fn track_mouse_leave(&self, window: WindowId) -> Result<(), Error> {
+        self.with_window(window, |data| data.tracking_leave = true)
+    }
+
+    fn set_timer(&self, window: WindowId, id: usize, interval: Duration) -> Result<(), Error> {
+        let now = self.clock.now();
+        self.with_window(window, |data| {
+            data.timers.insert(id, (interval, now + interval));
+        })
+    }
+
+    fn kill_timer(&self, window: WindowId, id: usize) {
+        let _ = self.with_window(window, |data| data.timers.remove(&id));
+    }
+
+    fn cursor_position(&self) -> Result<Point, Error> {
+        let reply = self
+            .conn
+            .query_pointer(self.root())
+            .or_display("QueryPointer")?
+            .reply()
+            .or_display("QueryPointer")?;
+        Ok(Point::new(reply.root_x as _, reply.root_y as _))
+    }
+
+    fn client_to_screen(&self, window: WindowId, point: Point) -> Result<Point, Error> {
+        let reply = self
+            .conn
+            .translate_coordinates(Self::xid(window), self.root(), point.x as _, point.y as _)
+            .or_display("TranslateCoordinates")?
+            .reply()
+            .or_display("TranslateCoordinates")?;
+        Ok(Point::new(reply.dst_x as _, reply.dst_y as _))
+    }
+
+    fn animations_enabled(&self) -> bool {
+        true
+    }
+
+    // The whole screen, without RandR there is nothing finer to go by.
+    fn monitors(&self) -> Result<Vec<Rect>, Error> {
+        let screen = &self.conn.setup().roots[self.screen];
+        Ok(vec![Rect::from_size(
+            0,
+            0,
+            screen.width_in_pixels as _,
+            screen.height_in_pixels as _,
+        )])
+    }
+
+    fn monitor(&self, _window: WindowId) -> Result<Option<Rect>, Error> {
+        Ok(self.monitors()?.first().copied())
+    }
+
+    // There is no toolkit to show a dialog with, the window asks in a menu of its own.
+    fn confirm(
+        &self,
+        _window: WindowId,
+        _title: &str,
+        _question: &str,
+    ) -> Result<Option<bool>, Error> {
+        Ok(None)
+    }
+}
+
+pub struct X11DeviceFactory {
+    conn: Rc<RustConnection>,
+    window: xproto::Window,
+    gc: xproto::Gcontext,
+    depth: u8,
+}
+
+impl DeviceFactory for X11DeviceFactory {
+    type Device = SoftwareCanvas<X11Presenter>;
+
+    fn create_device(&mut self, size: Size, dpi: Dpi) -> Result<Self::Device, DeviceError> {
+        let presenter = X11Presenter {
+            conn: self.conn.clone(),
+            window: self.window,
+            gc: self.gc,
+            depth: self.depth,
+        };
+
+        Ok(SoftwareCanvas::new(presenter, size, dpi))
+    }
+}
+
+// Uploads the changed part of a frame with PutImage.
+pub struct X11Presenter {
+    conn: Rc<RustConnection>,
+    window: xproto::Window,
+    gc: xproto::Gcontext,
+    depth: u8,
+}
+
+impl Present for X11Presenter {
+    fn present(&mut self, pixmap: &Pixmap, damage: Rect) -> Result<(), DeviceError> {
+        let damage = damage.intersection(&pixmap.bounds());
+        if damage.is_empty() {
+            return Ok(());
+        }
+
+        let mut data = Vec::with_capacity(damage.area() as usize * 4);
+        for y in damage.top..damage.bottom {
+            let row = &pixmap.row(y)[damage.left as usize..damage.right as usize];
+            for pixel in row {
+                data.extend_from_slice(&pixel.to_le_bytes());
+            }
+        }
+
+        let result = XImage::new(
+            damage.width() as _,
+            damage.height() as _,
+            ScanlinePad::Pad32,
+            self.depth,
+            BitsPerPixel::B32,
+            ImageOrder::LsbFirst,
+            Cow::Owned(data),
+        )
+        .map_err(|err| Error::display("PutImage", err))
+        .and_then(|image| {
+            image
+                .put(
+                    &*self.conn,
+                    self.window,
+                    self.gc,
+                    damage.left as _,
+                    damage.top as _,
+                )
+                .map_err(|err| Error::display("PutImage", err))
+        });
+
+        // A broken connection ends the app anyway, there is no device to get back
+        result.map(|_| ()).map_err(|err| {
+            error::report_error(&err);
+            DeviceError::Failed(0)
+        })
+    }
+}
+
+fn database_dpi(database: &Database) -> Dpi {
+    match database.get_value::<u32>("Xft.dpi", "") {
+        Ok(Some(dpi)) if dpi > 0 => Dpi::new(dpi),
+        _ => Dpi::DEFAULT,
+    }
+}
+
+fn button_from_detail(detail: xproto::Button) -> Option<MouseButton> {
+    match detail {
+        1 => Some(MouseButton::Left),
+        2 => Some(MouseButton::Middle),
+        3 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+// The buttons that were down before the event.
+fn buttons_from_state(state: KeyButMask) -> MouseButtons {
+    MouseButtons {
+        left: state.contains(KeyButMask::BUTTON1),
+        right: state.contains(KeyButMask::BUTTON3),
+        middle: state.contains(KeyButMask::BUTTON2),
+    }
+}
+
+fn set_button(buttons: &mut MouseButtons, button: MouseButton, down: bool) {
+    match button {
+        MouseButton::Left => buttons.left = down,
+        MouseButton::Right => buttons.right = down,
+        MouseButton::Middle => buttons.middle = down,
+    }
+}
+
+fn resize_direction(zone: HitZone) -> Option<u32> {
+    let direction = match zone {
+        HitZone::TopLeft => MOVERESIZE_SIZE_TOPLEFT,
+        HitZone::Top => MOVERESIZE_SIZE_TOP,
+        HitZone::TopRight => MOVERESIZE_SIZE_TOPRIGHT,
+        HitZone::Right => MOVERESIZE_SIZE_RIGHT,
+        HitZone::BottomRight => MOVERESIZE_SIZE_BOTTOMRIGHT,
+        HitZone::Bottom => MOVERESIZE_SIZE_BOTTOM,
+        HitZone::BottomLeft => MOVERESIZE_SIZE_BOTTOMLEFT,
+        HitZone::Left => MOVERESIZE_SIZE_LEFT,
+        _ => return None,
+    };
+    Some(direction)
+}
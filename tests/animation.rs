@@ -0,0 +1,213 @@
+// Steps easing curves, animated values and the frame scheduler on a virtual clock, so no test
+// has to sleep.
+#![cfg(target_os = "linux")]
+
+use std::rc::Rc;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use testwindowtabs::animation::{
+    set_animations_enabled, Animated, Clock, Easing, FrameScheduler, TimerAction, VirtualClock,
+    FRAME_INTERVAL,
+};
+use testwindowtabs::geometry::Rect;
+
+const DURATION: Duration = Duration::from_millis(100);
+
+const EASINGS: [Easing; 6] = [
+    Easing::Linear,
+    Easing::EaseIn,
+    Easing::EaseOut,
+    Easing::EaseInOut,
+    Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+    Easing::CubicBezier(0.42, 0.0, 0.58, 1.0),
+];
+
+// Whether animations run is process wide, so the tests that animate can't overlap with the one
+// turning them off.
+fn animations(enabled: bool) -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    let guard = LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    set_animations_enabled(enabled);
+    guard
+}
+
+fn ms(ms: u64) -> Duration {
+    Duration::from_millis(ms)
+}
+
+#[test]
+fn easings_start_at_0_and_end_at_1() {
+    for easing in EASINGS {
+        assert!(easing.apply(0.0).abs() < 1e-4, "{:?}", easing);
+        assert!((easing.apply(1.0) - 1.0).abs() < 1e-4, "{:?}", easing);
+
+        // Out of range progress is clamped
+        assert_eq!(easing.apply(-1.0), easing.apply(0.0), "{:?}", easing);
+        assert_eq!(easing.apply(2.0), easing.apply(1.0), "{:?}", easing);
+    }
+}
+
+#[test]
+fn easings_never_go_back() {
+    for easing in EASINGS {
+        let mut last = easing.apply(0.0);
+        for step in 1..=1000 {
+            let value = easing.apply(step as f32 / 1000.0);
+            assert!(value >= last - 1e-5, "{:?} at {}", easing, step);
+            last = value;
+        }
+    }
+}
+
+#[test]
+fn easings_have_their_shape() {
+    assert_eq!(Easing::Linear.apply(0.25), 0.25);
+    assert!(Easing::EaseIn.apply(0.5) < 0.5);
+    assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-6);
+
+    // A straight bezier is linear, and `ease` from CSS is 0.8024 halfway through
+    let linear = Easing::CubicBezier(0.0, 0.0, 1.0, 1.0);
+    for step in 0..=10 {
+        let t = step as f32 / 10.0;
+        assert!((linear.apply(t) - t).abs() < 1e-3, "at {}", t);
+    }
+    let ease = Easing::CubicBezier(0.25, 0.1, 0.25, 1.0);
+    assert!((ease.apply(0.5) - 0.8024).abs() < 1e-3);
+}
+
+#[test]
+fn values_move_from_start_to_target() {
+    let _animations = animations(true);
+    let clock = VirtualClock::new();
+    let mut value = Animated::new(0.0f32, DURATION, Easing::Linear);
+    assert!(!value.is_animating(clock.now()));
+
+    value.animate_to(100.0, clock.now());
+    assert_eq!(value.target(), 100.0);
+    assert_eq!(value.value(clock.now()), 0.0);
+    assert!(value.is_animating(clock.now()));
+
+    clock.advance(ms(25));
+    assert_eq!(value.value(clock.now()), 25.0);
+    assert_eq!(value.progress(clock.now()), 0.25);
+
+    clock.advance(ms(75));
+    assert_eq!(value.value(clock.now()), 100.0);
+    assert!(!value.is_animating(clock.now()));
+
+    clock.advance(ms(1000));
+    assert_eq!(value.value(clock.now()), 100.0);
+}
+
+#[test]
+fn retargeting_mid_flight_continues_from_the_current_value() {
+    let _animations = animations(true);
+    let clock = VirtualClock::new();
+    let mut value = Animated::new(0.0f32, DURATION, Easing::EaseOut);
+    value.animate_to(100.0, clock.now());
+
+    clock.advance(ms(40));
+    let before = value.value(clock.now());
+    value.animate_to(-50.0, clock.now());
+    assert_eq!(value.value(clock.now()), before);
+
+    // And from there it heads for the new target over a full duration
+    clock.advance(ms(1));
+    assert!(value.value(clock.now()) < before);
+    clock.advance(ms(98));
+    assert!(value.is_animating(clock.now()));
+    clock.advance(ms(1));
+    assert_eq!(value.value(clock.now()), -50.0);
+}
+
+#[test]
+fn rects_animate_edge_by_edge() {
+    let _animations = animations(true);
+    let clock = VirtualClock::new();
+    let mut rect = Animated::new(Rect::new(0, 0, 10, 10), DURATION, Easing::Linear);
+    rect.animate_to(Rect::new(100, 0, 110, 20), clock.now());
+
+    clock.advance(ms(50));
+    assert_eq!(rect.value(clock.now()), Rect::new(50, 0, 60, 15));
+
+    rect.animate_from_to(Rect::new(0, 0, 0, 0), Rect::new(0, 0, 40, 40), clock.now());
+    assert_eq!(rect.value(clock.now()), Rect::new(0, 0, 0, 0));
+    clock.advance(ms(50));
+    assert_eq!(rect.value(clock.now()), Rect::new(0, 0, 20, 20));
+}
+
+#[test]
+fn a_zero_duration_jumps_to_the_target() {
+    let _animations = animations(true);
+    let clock = VirtualClock::new();
+    let mut value = Animated::new(0i32, Duration::ZERO, Easing::Linear);
+
+    value.animate_to(7, clock.now());
+    assert_eq!(value.progress(clock.now()), 1.0);
+    assert_eq!(value.value(clock.now()), 7);
+    assert!(!value.is_animating(clock.now()));
+}
+
+#[test]
+fn set_stops_a_running_animation() {
+    let _animations = animations(true);
+    let clock = VirtualClock::new();
+    let mut value = Animated::new(0.0f32, DURATION, Easing::Linear);
+
+    value.animate_to(100.0, clock.now());
+    clock.advance(ms(50));
+    value.set(3.0);
+    assert!(!value.is_animating(clock.now()));
+    assert_eq!(value.value(clock.now()), 3.0);
+}
+
+#[test]
+fn without_animations_values_jump_to_their_target() {
+    let _animations = animations(false);
+    let clock = VirtualClock::new();
+    let mut value = Animated::new(0.0f32, DURATION, Easing::Linear);
+
+    value.animate_to(100.0, clock.now());
+    assert!(!value.is_animating(clock.now()));
+    assert_eq!(value.value(clock.now()), 100.0);
+
+    value.animate_from_to(10.0, 20.0, clock.now());
+    assert_eq!(value.value(clock.now()), 20.0);
+
+    set_animations_enabled(true);
+}
+
+#[test]
+fn the_frame_timer_starts_once_and_stops_when_nothing_animates() {
+    let clock = Rc::new(VirtualClock::new());
+    let mut scheduler = FrameScheduler::new(clock.clone());
+    assert!(!scheduler.is_running());
+    assert_eq!(scheduler.last_frame(), None);
+
+    assert_eq!(
+        scheduler.request_frames(),
+        TimerAction::Start(FRAME_INTERVAL)
+    );
+    assert!(scheduler.is_running());
+    // Already running, so nothing to do for further requests
+    assert_eq!(scheduler.request_frames(), TimerAction::None);
+
+    clock.advance(FRAME_INTERVAL);
+    assert_eq!(scheduler.frame(true), TimerAction::None);
+    assert_eq!(scheduler.last_frame(), Some(FRAME_INTERVAL));
+
+    clock.advance(FRAME_INTERVAL);
+    assert_eq!(scheduler.frame(false), TimerAction::Stop);
+    assert!(!scheduler.is_running());
+    assert_eq!(scheduler.last_frame(), Some(FRAME_INTERVAL * 2));
+    assert_eq!(scheduler.now(), FRAME_INTERVAL * 2);
+
+    // A late tick after stopping doesn't stop it twice, and frames can be asked for again
+    assert_eq!(scheduler.frame(false), TimerAction::None);
+    assert_eq!(
+        scheduler.request_frames(),
+        TimerAction::Start(FRAME_INTERVAL)
+    );
+}
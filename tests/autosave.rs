@@ -0,0 +1,235 @@
+// Debounces autosaves on a virtual clock, and writes sessions and run markers into a temp
+// directory the way a crash would find them.
+#![cfg(target_os = "linux")]
+
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use testwindowtabs::animation::{TimerAction, VirtualClock};
+use testwindowtabs::autosave::{self, Autosave, RunMarker};
+use testwindowtabs::session::{Session, TabSession, WindowSession};
+
+const DELAY: Duration = Duration::from_secs(2);
+const MAX_DELAY: Duration = Duration::from_secs(10);
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+fn autosave() -> (Autosave, Rc<VirtualClock>) {
+    let clock = Rc::new(VirtualClock::new());
+    (Autosave::new(clock.clone(), DELAY, MAX_DELAY), clock)
+}
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!(
+            "testwindowtabs-autosave-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    fn files(&self) -> Vec<String> {
+        let mut files = std::fs::read_dir(&self.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect::<Vec<_>>();
+        files.sort();
+        files
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+fn session(titles: &[&str]) -> Session {
+    Session {
+        windows: vec![WindowSession {
+            tabs: titles
+                .iter()
+                .map(|title| TabSession {
+                    title: String::from(*title),
+                    state: String::new(),
+                    pinned: false,
+                })
+                .collect(),
+            ..WindowSession::default()
+        }],
+        ..Session::default()
+    }
+}
+
+#[test]
+fn nothing_is_saved_without_changes() {
+    let (mut autosave, clock) = autosave();
+    assert!(!autosave.is_pending());
+    assert_eq!(autosave.due(), None);
+
+    clock.advance(secs(60));
+    assert_eq!(autosave.tick(), (false, TimerAction::Stop));
+}
+
+#[test]
+fn a_change_is_saved_once_things_calm_down() {
+    let (mut autosave, clock) = autosave();
+    clock.set(secs(5));
+
+    assert_eq!(autosave.changed(), TimerAction::Start(DELAY));
+    assert_eq!(autosave.due(), Some(secs(7)));
+
+    clock.set(secs(7));
+    assert_eq!(autosave.tick(), (true, TimerAction::Stop));
+    assert!(!autosave.is_pending());
+
+    // Saved already
+    assert_eq!(autosave.tick(), (false, TimerAction::Stop));
+}
+
+#[test]
+fn every_change_puts_the_save_off() {
+    let (mut autosave, clock) = autosave();
+
+    autosave.changed();
+    clock.advance(secs(1));
+    assert_eq!(autosave.changed(), TimerAction::Start(DELAY));
+    assert_eq!(autosave.due(), Some(secs(3)));
+}
+
+#[test]
+fn a_steady_stream_of_changes_is_saved_after_the_max_delay() {
+    let (mut autosave, clock) = autosave();
+    autosave.changed();
+
+    let mut started = Vec::new();
+    for _ in 0..12 {
+        clock.advance(secs(1));
+        if let TimerAction::Start(wait) = autosave.changed() {
+            started.push(wait.as_secs());
+        }
+    }
+
+    // The waits shrink once the first change is about to be 10 s old
+    assert_eq!(started, [2, 2, 2, 2, 2, 2, 2, 2, 1, 0, 0, 0]);
+    assert_eq!(autosave.tick(), (true, TimerAction::Stop));
+
+    // The next change starts over
+    assert_eq!(autosave.changed(), TimerAction::Start(DELAY));
+}
+
+#[test]
+fn a_timer_firing_early_is_started_again() {
+    let (mut autosave, clock) = autosave();
+    autosave.changed();
+
+    clock.advance(Duration::from_millis(1500));
+    assert_eq!(
+        autosave.tick(),
+        (false, TimerAction::Start(Duration::from_millis(500)))
+    );
+    assert!(autosave.is_pending());
+}
+
+#[test]
+fn cancelling_forgets_the_changes() {
+    let (mut autosave, clock) = autosave();
+    autosave.changed();
+
+    assert_eq!(autosave.cancel(), TimerAction::Stop);
+    clock.advance(secs(60));
+    assert_eq!(autosave.tick(), (false, TimerAction::Stop));
+}
+
+#[test]
+fn saving_replaces_the_file_and_leaves_nothing_else_behind() {
+    let dir = TempDir::new("replace");
+    let path = dir.0.join("session");
+
+    session(&["one"]).save(&path).unwrap();
+    session(&["one", "two"]).save(&path).unwrap();
+
+    assert_eq!(Session::load(&path).unwrap(), session(&["one", "two"]));
+    assert_eq!(dir.files(), ["session"]);
+}
+
+#[test]
+fn a_write_cut_short_leaves_the_last_session() {
+    let dir = TempDir::new("cut-short");
+    let path = dir.0.join("session");
+    session(&["kept"]).save(&path).unwrap();
+
+    // What a crash halfway through the next save leaves
+    let partial = session(&["lost", "tabs"]).to_text();
+    std::fs::write(dir.0.join("session.tmp"), &partial[..partial.len() / 2]).unwrap();
+    assert_eq!(Session::load(&path).unwrap(), session(&["kept"]));
+
+    // And the save after it goes through regardless
+    session(&["new"]).save(&path).unwrap();
+    assert_eq!(Session::load(&path).unwrap(), session(&["new"]));
+    assert_eq!(dir.files(), ["session"]);
+}
+
+#[test]
+fn a_failed_write_keeps_the_old_file() {
+    let dir = TempDir::new("failed");
+    let path = dir.0.join("session");
+    autosave::write_atomic(&path, b"old").unwrap();
+
+    // The temporary file can't be created where a directory is in the way
+    std::fs::create_dir(dir.0.join("session.tmp")).unwrap();
+    assert!(autosave::write_atomic(&path, b"new").is_err());
+    assert_eq!(std::fs::read(&path).unwrap(), b"old");
+}
+
+#[test]
+fn a_clean_exit_leaves_no_marker() {
+    let dir = TempDir::new("clean");
+    let path = dir.0.join("nested").join("session");
+
+    let (marker, unclean) = RunMarker::acquire(&path).unwrap();
+    assert!(!unclean);
+    assert_eq!(marker.path(), RunMarker::path_for(&path));
+    assert!(marker.path().exists());
+
+    marker.release().unwrap();
+    assert!(!RunMarker::path_for(&path).exists());
+
+    let (marker, unclean) = RunMarker::acquire(&path).unwrap();
+    assert!(!unclean);
+    marker.release().unwrap();
+}
+
+#[test]
+fn a_marker_left_behind_means_the_last_run_crashed() {
+    let dir = TempDir::new("crashed");
+    let path = dir.0.join("session");
+
+    // Never released, like when the process dies
+    let _crashed = RunMarker::acquire(&path).unwrap();
+
+    let (marker, unclean) = RunMarker::acquire(&path).unwrap();
+    assert!(unclean);
+
+    // This run exits cleanly, so the next one starts without a question
+    marker.release().unwrap();
+    let (_, unclean) = RunMarker::acquire(&path).unwrap();
+    assert!(!unclean);
+}
+
+#[test]
+fn releasing_a_marker_that_is_gone_is_fine() {
+    let dir = TempDir::new("gone");
+    let (marker, _) = RunMarker::acquire(&dir.0.join("session")).unwrap();
+
+    std::fs::remove_file(marker.path()).unwrap();
+    marker.release().unwrap();
+}
@@ -0,0 +1,191 @@
+// Walks the close protocol through the answers tabs and the window can give, right away and
+// later on.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::close::{CloseProtocol, CloseReply, CloseStep, Party};
+
+// Answers every question with what `answer` says, keeping the replies it returns for later.
+fn drive(
+    protocol: &mut CloseProtocol,
+    step: Option<CloseStep>,
+    mut answer: impl FnMut(CloseReply) -> Option<CloseReply>,
+) -> (Vec<CloseStep>, Vec<CloseReply>) {
+    let mut steps = Vec::new();
+    let mut kept = Vec::new();
+    let mut next: Vec<CloseStep> = step.into_iter().collect();
+
+    while let Some(step) = next.pop() {
+        if let CloseStep::Ask(party) = step {
+            kept.extend(answer(protocol.reply(party)));
+        }
+        steps.push(step);
+        next.extend(protocol.take_answers());
+    }
+
+    (steps, kept)
+}
+
+#[test]
+fn closing_the_window_asks_every_tab_in_order_and_then_the_window() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_window(&[3, 1, 2]);
+
+    let (steps, _) = drive(&mut protocol, step, |reply| {
+        reply.close();
+        None
+    });
+
+    assert_eq!(
+        steps,
+        [
+            CloseStep::Ask(Party::Tab(3)),
+            CloseStep::Ask(Party::Tab(1)),
+            CloseStep::Ask(Party::Tab(2)),
+            CloseStep::Ask(Party::Window),
+            CloseStep::Close {
+                tabs: vec![3, 1, 2],
+                window: true
+            },
+        ]
+    );
+    assert!(!protocol.is_closing());
+}
+
+#[test]
+fn a_cancel_stops_the_round_and_closes_nothing() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_window(&[0, 1, 2]);
+
+    let (steps, _) = drive(&mut protocol, step, |reply| {
+        if reply.party() == Party::Tab(1) {
+            reply.cancel();
+        } else {
+            reply.close();
+        }
+        None
+    });
+
+    assert_eq!(
+        steps,
+        [
+            CloseStep::Ask(Party::Tab(0)),
+            CloseStep::Ask(Party::Tab(1)),
+            CloseStep::Cancelled,
+        ]
+    );
+    assert!(!protocol.is_closing());
+}
+
+#[test]
+fn the_window_handler_can_cancel_after_all_tabs_agreed() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_window(&[0]);
+
+    let (steps, _) = drive(&mut protocol, step, |reply| {
+        if reply.party() == Party::Window {
+            reply.cancel();
+        } else {
+            reply.close();
+        }
+        None
+    });
+
+    assert_eq!(steps.last(), Some(&CloseStep::Cancelled));
+}
+
+#[test]
+fn closing_all_tabs_leaves_the_window_alone() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_tabs(&[0, 1]);
+
+    let (steps, _) = drive(&mut protocol, step, |reply| {
+        reply.close();
+        None
+    });
+
+    assert_eq!(
+        steps.last(),
+        Some(&CloseStep::Close {
+            tabs: vec![0, 1],
+            window: false
+        })
+    );
+    assert!(!steps.contains(&CloseStep::Ask(Party::Window)));
+}
+
+#[test]
+fn a_tab_can_close_after_saving() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_window(&[0, 1]);
+
+    // Tab 0 holds on to its reply while it saves
+    let (steps, mut kept) = drive(&mut protocol, step, |reply| match reply.party() {
+        Party::Tab(0) => Some(reply),
+        _ => {
+            reply.close();
+            None
+        }
+    });
+    assert_eq!(steps, [CloseStep::Ask(Party::Tab(0))]);
+    assert!(protocol.is_closing());
+
+    // Nobody else gets asked while waiting, and another request doesn't start over
+    assert!(protocol.take_answers().is_empty());
+    assert_eq!(protocol.close_window(&[0, 1]), None);
+
+    kept.pop().unwrap().close();
+    let steps = protocol.take_answers();
+    assert_eq!(steps, [CloseStep::Ask(Party::Tab(1))]);
+
+    let (steps, _) = drive(&mut protocol, steps.into_iter().next(), |reply| {
+        reply.close();
+        None
+    });
+    assert_eq!(
+        steps.last(),
+        Some(&CloseStep::Close {
+            tabs: vec![0, 1],
+            window: true
+        })
+    );
+}
+
+#[test]
+fn dropping_a_reply_unanswered_cancels() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_tabs(&[0]);
+
+    let (steps, _) = drive(&mut protocol, step, |_| None);
+    assert_eq!(steps, [CloseStep::Ask(Party::Tab(0)), CloseStep::Cancelled]);
+}
+
+#[test]
+fn answers_from_an_earlier_round_are_ignored() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_tabs(&[0]);
+    let (_, mut kept) = drive(&mut protocol, step, Some);
+
+    // The first round gets cancelled while tab 0 still holds on to its reply
+    let stale = kept.pop().unwrap();
+    protocol.reply(Party::Tab(0)).cancel();
+    assert_eq!(protocol.take_answers(), [CloseStep::Cancelled]);
+
+    let step = protocol.close_tabs(&[0]);
+    assert_eq!(step, Some(CloseStep::Ask(Party::Tab(0))));
+
+    stale.close();
+    assert!(protocol.take_answers().is_empty());
+    assert!(protocol.is_closing());
+}
+
+#[test]
+fn answers_from_parties_that_were_not_asked_are_ignored() {
+    let mut protocol = CloseProtocol::new();
+    let step = protocol.close_window(&[0, 1]);
+    let (_, _kept) = drive(&mut protocol, step, Some);
+
+    protocol.reply(Party::Window).close();
+    protocol.reply(Party::Tab(1)).close();
+    assert!(protocol.take_answers().is_empty());
+    assert!(protocol.is_closing());
+}
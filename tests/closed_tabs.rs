@@ -0,0 +1,65 @@
+// Keeps the tabs closed last in a bounded stack and hands them back per window.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::closed_tabs::{ClosedTab, ClosedTabs};
+use testwindowtabs::session::TabSession;
+
+fn closed(title: &str, window: usize) -> ClosedTab {
+    ClosedTab {
+        tab: TabSession {
+            title: String::from(title),
+            state: String::new(),
+            pinned: false,
+        },
+        index: 0,
+        window,
+    }
+}
+
+fn titles(tabs: &ClosedTabs) -> Vec<&str> {
+    tabs.tabs()
+        .iter()
+        .map(|closed| closed.tab.title.as_str())
+        .collect()
+}
+
+#[test]
+fn tabs_come_back_newest_first() {
+    let mut tabs = ClosedTabs::new(10);
+    for title in ["a", "b", "c"] {
+        tabs.push(closed(title, 0));
+    }
+
+    assert_eq!(tabs.pop(0).unwrap().tab.title, "c");
+    assert_eq!(tabs.pop(0).unwrap().tab.title, "b");
+    assert_eq!(tabs.pop(0).unwrap().tab.title, "a");
+    assert_eq!(tabs.pop(0), None);
+}
+
+#[test]
+fn the_oldest_tabs_are_forgotten_past_the_limit() {
+    let mut tabs = ClosedTabs::new(3);
+    for title in ["a", "b", "c", "d", "e"] {
+        tabs.push(closed(title, 0));
+    }
+    assert_eq!(titles(&tabs), ["c", "d", "e"]);
+
+    let restored = ClosedTabs::with_tabs(2, tabs.tabs().to_vec());
+    assert_eq!(titles(&restored), ["d", "e"]);
+
+    let mut none = ClosedTabs::new(0);
+    none.push(closed("a", 0));
+    assert!(none.is_empty());
+}
+
+#[test]
+fn a_window_gets_its_own_tabs_back_first() {
+    let mut tabs = ClosedTabs::new(10);
+    tabs.push(closed("first window", 0));
+    tabs.push(closed("second window", 1));
+
+    assert_eq!(tabs.pop(0).unwrap().tab.title, "first window");
+    // Nothing of its own left, so it takes what another window closed
+    assert_eq!(tabs.pop(0).unwrap().tab.title, "second window");
+    assert!(tabs.is_empty());
+}
@@ -0,0 +1,39 @@
+// Scales between DIPs and pixels at the DPIs Windows offers, and makes sure a DPI of 0 can't
+// turn sizes into nothing.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::dpi::Dpi;
+use testwindowtabs::units::{Dip, Px};
+
+#[test]
+fn the_factor_is_relative_to_96_dpi() {
+    let factors = [(96, 1.0), (120, 1.25), (144, 1.5), (168, 1.75), (192, 2.0)];
+    for (dpi, factor) in factors {
+        assert_eq!(Dpi::new(dpi).factor(), factor);
+        assert_eq!(Dpi::from(dpi), Dpi::new(dpi));
+    }
+    assert_eq!(Dpi::default(), Dpi::DEFAULT);
+    assert_eq!(Dpi::DEFAULT.value(), 96);
+}
+
+#[test]
+fn dips_scale_to_rounded_pixels() {
+    let dpi = Dpi::new(120);
+    assert_eq!(dpi.px(Dip(10.0)), Px(13)); // 12.5 rounds away from 0
+    assert_eq!(dpi.px(Dip(-10.0)), Px(-13));
+    assert_eq!(dpi.px(Dip(47.0)), Px(59));
+    assert_eq!(dpi.scale(47), 59);
+
+    assert_eq!(Dpi::new(168).px(Dip(1.0)), Px(2));
+    assert_eq!(Dpi::new(192).dip(Px(25)), Dip(12.5));
+}
+
+#[test]
+fn zero_dpi_stands_for_the_default() {
+    for dpi in [Dpi::new(0), Dpi::from(0)] {
+        assert_eq!(dpi, Dpi::DEFAULT);
+        assert_eq!(dpi.factor(), 1.0);
+        assert_eq!(dpi.px(Dip(10.0)), Px(10));
+        assert_eq!(dpi.dip(Px(10)), Dip(10.0));
+    }
+}
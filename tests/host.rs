@@ -0,0 +1,234 @@
+// Drives a host the way a window procedure does, with handlers that send more messages to
+// their own window before they return.
+#![cfg(target_os = "linux")]
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use testwindowtabs::event::{Event, HitZone, Reply};
+use testwindowtabs::geometry::{Point, Size};
+use testwindowtabs::host::{Dispatch, Host};
+
+type Message = Event<u32>;
+type TestHost = Host<Recorder, Message>;
+
+// What the fake pump does when the component handles `Custom(n)`.
+#[derive(Clone)]
+enum Step {
+    Send(Message),
+    Destroy,
+    With,
+}
+
+// Writes down every event it gets and what happened to what it sent meanwhile.
+struct Recorder {
+    log: Rc<RefCell<Vec<String>>>,
+    script: Vec<(u32, Vec<Step>)>,
+    dropped: Rc<Cell<bool>>,
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        self.log.borrow_mut().push(String::from("dropped"));
+        self.dropped.set(true);
+    }
+}
+
+struct Pump {
+    host: TestHost,
+    log: Rc<RefCell<Vec<String>>>,
+    dropped: Rc<Cell<bool>>,
+}
+
+fn pump(script: Vec<(u32, Vec<Step>)>) -> Pump {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let dropped = Rc::new(Cell::new(false));
+    let recorder = Recorder {
+        log: log.clone(),
+        script,
+        dropped: dropped.clone(),
+    };
+
+    Pump {
+        host: Host::new(recorder),
+        log,
+        dropped,
+    }
+}
+
+impl Pump {
+    // Like a synchronous `SendMessage` to the window.
+    fn send(&self, event: Message) -> Dispatch<Reply> {
+        self.host
+            .dispatch(event, |recorder, event| self.handle(recorder, event))
+    }
+
+    fn handle(&self, recorder: &mut Recorder, event: Message) -> Reply {
+        self.log.borrow_mut().push(format!("{:?}", event));
+
+        let steps = match event {
+            Event::Custom(n) => recorder
+                .script
+                .iter()
+                .find(|(id, _)| *id == n)
+                .map(|(_, steps)| steps.clone())
+                .unwrap_or_default(),
+            Event::HitTest { .. } => return Reply::HitTest(HitZone::Caption),
+            _ => Vec::new(),
+        };
+
+        for step in steps {
+            let note = match step {
+                Step::Send(event) => format!("sent {:?}: {:?}", event, self.send(event.clone())),
+                Step::Destroy => {
+                    self.host.destroy();
+                    format!("destroyed, dropped: {}", self.dropped.get())
+                }
+                Step::With => format!("with: {:?}", self.host.with(|_| ())),
+            };
+            self.log.borrow_mut().push(note);
+        }
+
+        Reply::Handled
+    }
+
+    fn take_log(&self) -> Vec<String> {
+        self.log.take()
+    }
+}
+
+fn resize(width: i32) -> Message {
+    Event::Resize(Size::new(width, 10))
+}
+
+fn hit_test() -> Message {
+    Event::HitTest {
+        pos: Point::new(1, 1),
+        system: HitZone::Client,
+    }
+}
+
+#[test]
+fn an_idle_host_hands_the_reply_back() {
+    let pump = pump(Vec::new());
+
+    assert_eq!(
+        pump.send(hit_test()),
+        Dispatch::Handled(Reply::HitTest(HitZone::Caption))
+    );
+    assert_eq!(pump.send(resize(1)), Dispatch::Handled(Reply::Handled));
+    assert!(!pump.host.is_busy());
+}
+
+#[test]
+fn nested_events_wait_for_the_handler_and_run_in_order() {
+    let pump = pump(vec![
+        (1, vec![Step::Send(Event::Custom(2)), Step::Send(resize(3))]),
+        // Sent while the queue is being worked through, it goes last
+        (2, vec![Step::Send(Event::Custom(4))]),
+    ]);
+
+    assert_eq!(
+        pump.send(Event::Custom(1)),
+        Dispatch::Handled(Reply::Handled)
+    );
+    assert_eq!(
+        pump.take_log(),
+        [
+            "Custom(1)",
+            "sent Custom(2): Deferred",
+            "sent Resize(Size { width: 3, height: 10 }): Deferred",
+            "Custom(2)",
+            "sent Custom(4): Deferred",
+            "Resize(Size { width: 3, height: 10 })",
+            "Custom(4)",
+        ]
+    );
+    assert!(!pump.host.is_busy());
+}
+
+#[test]
+fn a_busy_host_turns_down_events_waiting_for_a_reply() {
+    let pump = pump(vec![(
+        1,
+        vec![
+            Step::Send(hit_test()),
+            Step::Send(Event::CloseRequested),
+            Step::Send(Event::Custom(2)),
+        ],
+    )]);
+
+    pump.send(Event::Custom(1));
+    assert_eq!(
+        pump.take_log(),
+        [
+            "Custom(1)",
+            "sent HitTest { pos: Point { x: 1, y: 1 }, system: Client }: Busy",
+            "sent CloseRequested: Busy",
+            "sent Custom(2): Deferred",
+            "Custom(2)",
+        ]
+    );
+
+    // Once idle again they get through
+    assert_eq!(
+        pump.send(Event::CloseRequested),
+        Dispatch::Handled(Reply::Handled)
+    );
+}
+
+#[test]
+fn destroying_from_a_handler_drops_the_component_once_it_returns() {
+    let pump = pump(vec![(
+        1,
+        vec![
+            Step::Send(resize(2)),
+            Step::Destroy,
+            Step::Send(resize(3)),
+            Step::With,
+        ],
+    )]);
+
+    // The handler that was running still gets its reply through
+    assert_eq!(
+        pump.send(Event::Custom(1)),
+        Dispatch::Handled(Reply::Handled)
+    );
+    assert_eq!(
+        pump.take_log(),
+        [
+            "Custom(1)",
+            "sent Resize(Size { width: 2, height: 10 }): Deferred",
+            "destroyed, dropped: false",
+            "sent Resize(Size { width: 3, height: 10 }): Gone",
+            "with: None",
+            // What was queued before is dropped with it
+            "dropped",
+        ]
+    );
+
+    assert!(pump.dropped.get());
+    assert!(pump.host.is_destroyed());
+    assert_eq!(pump.send(resize(4)), Dispatch::Gone);
+    assert_eq!(pump.host.with(|_| ()), None);
+}
+
+#[test]
+fn destroying_an_idle_host_drops_the_component_right_away() {
+    let pump = pump(Vec::new());
+
+    pump.host.destroy();
+    assert!(pump.dropped.get());
+    assert_eq!(pump.send(Event::Custom(1)), Dispatch::Gone);
+    assert_eq!(pump.take_log(), ["dropped"]);
+}
+
+#[test]
+fn the_component_is_out_of_reach_while_a_handler_runs() {
+    let pump = pump(vec![(1, vec![Step::With])]);
+
+    pump.send(Event::Custom(1));
+    assert_eq!(pump.take_log(), ["Custom(1)", "with: None"]);
+
+    assert_eq!(pump.host.with(|recorder| recorder.script.len()), Some(1));
+}
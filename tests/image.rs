@@ -0,0 +1,427 @@
+// Decodes PNG and ICO files built byte by byte, so every color type and bit depth an icon can
+// come in is covered without shipping binary fixtures.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::dpi::Dpi;
+use testwindowtabs::image::{decode_ico, premultiply, select_variant, Error, Image, ImageSet};
+
+fn encode_png(
+    width: u32,
+    height: u32,
+    color: png::ColorType,
+    depth: png::BitDepth,
+    data: &[u8],
+    palette: Option<(&[u8], &[u8])>,
+) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width, height);
+    encoder.set_color(color);
+    encoder.set_depth(depth);
+    if let Some((palette, trns)) = palette {
+        encoder.set_palette(palette.to_vec());
+        encoder.set_trns(trns.to_vec());
+    }
+
+    let mut writer = encoder.write_header().unwrap();
+    writer.write_image_data(data).unwrap();
+    writer.finish().unwrap();
+    bytes
+}
+
+fn rgba_png(width: u32, height: u32, pixel: [u8; 4]) -> Vec<u8> {
+    let data = pixel.repeat((width * height) as usize);
+    encode_png(
+        width,
+        height,
+        png::ColorType::Rgba,
+        png::BitDepth::Eight,
+        &data,
+        None,
+    )
+}
+
+fn ico(entries: &[Vec<u8>]) -> Vec<u8> {
+    let mut bytes = vec![0, 0, 1, 0];
+    bytes.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+
+    let mut offset = 6 + entries.len() * 16;
+    for data in entries {
+        // Width, height, colors, reserved, planes and bit count aren't looked at
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += data.len();
+    }
+    for data in entries {
+        bytes.extend_from_slice(data);
+    }
+    bytes
+}
+
+// `rows` are top-down and already packed and padded, `mask` has one entry per pixel with
+// true for transparent.
+fn dib(
+    width: u32,
+    height: u32,
+    bit_count: u16,
+    palette: &[[u8; 4]],
+    rows: &[Vec<u8>],
+    mask: Option<&[bool]>,
+) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&40u32.to_le_bytes());
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&(height * 2).to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&bit_count.to_le_bytes());
+    bytes.extend_from_slice(&[0; 16]);
+    bytes.extend_from_slice(&(palette.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+
+    for color in palette {
+        bytes.extend_from_slice(color);
+    }
+    for row in rows.iter().rev() {
+        bytes.extend_from_slice(row);
+    }
+
+    if let Some(mask) = mask {
+        let stride = (width as usize).div_ceil(32) * 4;
+        for y in (0..height as usize).rev() {
+            let mut row = vec![0u8; stride];
+            for x in 0..width as usize {
+                if mask[y * width as usize + x] {
+                    row[x / 8] |= 0x80 >> (x % 8);
+                }
+            }
+            bytes.extend_from_slice(&row);
+        }
+    }
+    bytes
+}
+
+fn pixel(image: &Image, x: u32, y: u32) -> [u8; 4] {
+    let idx = ((y * image.width() + x) * 4) as usize;
+    image.pixels()[idx..idx + 4].try_into().unwrap()
+}
+
+const RED: [u8; 4] = [0, 0, 0xff, 0];
+const GREEN: [u8; 4] = [0, 0xff, 0, 0];
+const BLUE: [u8; 4] = [0xff, 0, 0, 0];
+const WHITE: [u8; 4] = [0xff, 0xff, 0xff, 0];
+
+#[test]
+fn pngs_of_every_color_type_decode_to_premultiplied_rgba() {
+    let rgba = Image::from_png(&rgba_png(1, 1, [200, 100, 50, 128])).unwrap();
+    assert_eq!(pixel(&rgba, 0, 0), [100, 50, 25, 128]);
+
+    let rgb = encode_png(
+        2,
+        1,
+        png::ColorType::Rgb,
+        png::BitDepth::Eight,
+        &[1, 2, 3, 4, 5, 6],
+        None,
+    );
+    let rgb = Image::from_png(&rgb).unwrap();
+    assert_eq!(rgb.pixels(), [1, 2, 3, 0xff, 4, 5, 6, 0xff]);
+
+    let gray = encode_png(
+        1,
+        1,
+        png::ColorType::Grayscale,
+        png::BitDepth::Eight,
+        &[77],
+        None,
+    );
+    assert_eq!(Image::from_png(&gray).unwrap().pixels(), [77, 77, 77, 0xff]);
+
+    let gray_alpha = encode_png(
+        1,
+        1,
+        png::ColorType::GrayscaleAlpha,
+        png::BitDepth::Eight,
+        &[255, 0],
+        None,
+    );
+    assert_eq!(Image::from_png(&gray_alpha).unwrap().pixels(), [0, 0, 0, 0]);
+
+    // Two pixels of a 1 bit palette, the second one half transparent
+    let indexed = encode_png(
+        2,
+        1,
+        png::ColorType::Indexed,
+        png::BitDepth::One,
+        &[0b0100_0000],
+        Some((&[10, 20, 30, 200, 100, 50], &[0xff, 128])),
+    );
+    let indexed = Image::from_png(&indexed).unwrap();
+    assert_eq!(indexed.pixels(), [10, 20, 30, 0xff, 100, 50, 25, 128]);
+
+    // 16 bit samples are cut down to their high byte
+    let deep = encode_png(
+        1,
+        1,
+        png::ColorType::Rgb,
+        png::BitDepth::Sixteen,
+        &[0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc],
+        None,
+    );
+    assert_eq!(
+        Image::from_png(&deep).unwrap().pixels(),
+        [0x12, 0x56, 0x9a, 0xff]
+    );
+}
+
+#[test]
+fn ico_files_can_hold_pngs() {
+    let bytes = ico(&[
+        rgba_png(16, 16, [0, 0, 0xff, 0xff]),
+        rgba_png(32, 32, [0; 4]),
+    ]);
+    let images = decode_ico(&bytes).unwrap();
+
+    assert_eq!(images.len(), 2);
+    assert_eq!((images[0].width(), images[0].height()), (16, 16));
+    assert_eq!(pixel(&images[0], 15, 15), [0, 0, 0xff, 0xff]);
+    assert_eq!((images[1].width(), images[1].height()), (32, 32));
+}
+
+#[test]
+fn palette_dibs_of_1_4_and_8_bits_per_pixel_decode() {
+    // 2x2, top row red/green and bottom row blue/white; rows are padded to 4 bytes
+    let palette = [RED, GREEN, BLUE, WHITE];
+    let cases = [
+        (
+            1,
+            palette[..2].to_vec(),
+            vec![vec![0b0100_0000, 0, 0, 0]; 2],
+        ),
+        (
+            4,
+            palette.to_vec(),
+            vec![vec![0x01, 0, 0, 0], vec![0x23, 0, 0, 0]],
+        ),
+        (
+            8,
+            palette.to_vec(),
+            vec![vec![0, 1, 0, 0], vec![2, 3, 0, 0]],
+        ),
+    ];
+
+    for (bit_count, palette, rows) in cases {
+        let image = decode_ico(&ico(&[dib(2, 2, bit_count, &palette, &rows, None)]))
+            .unwrap()
+            .remove(0);
+
+        assert_eq!(pixel(&image, 0, 0), [0xff, 0, 0, 0xff], "{bit_count}bpp");
+        assert_eq!(pixel(&image, 1, 0), [0, 0xff, 0, 0xff], "{bit_count}bpp");
+        if bit_count > 1 {
+            assert_eq!(pixel(&image, 0, 1), [0, 0, 0xff, 0xff], "{bit_count}bpp");
+            assert_eq!(
+                pixel(&image, 1, 1),
+                [0xff, 0xff, 0xff, 0xff],
+                "{bit_count}bpp"
+            );
+        }
+    }
+}
+
+#[test]
+fn the_mask_makes_pixels_of_dibs_without_alpha_transparent() {
+    let rows = vec![vec![0, 0, 0xff, 0xff, 0, 0, 0, 0]; 1];
+    let bytes = ico(&[dib(2, 1, 24, &[], &rows, Some(&[false, true]))]);
+    let image = decode_ico(&bytes).unwrap().remove(0);
+
+    assert_eq!(pixel(&image, 0, 0), [0xff, 0, 0, 0xff]);
+    assert_eq!(pixel(&image, 1, 0), [0, 0, 0, 0]);
+}
+
+#[test]
+fn the_alpha_channel_of_32bpp_dibs_wins_over_the_mask() {
+    // The mask would hide the first pixel, the alpha channel keeps it half visible
+    let rows = vec![vec![0, 0, 200, 128, 0xff, 0, 0, 0xff]];
+    let bytes = ico(&[dib(2, 1, 32, &[], &rows, Some(&[true, false]))]);
+    let image = decode_ico(&bytes).unwrap().remove(0);
+
+    assert_eq!(pixel(&image, 0, 0), [100, 0, 0, 128]);
+    assert_eq!(pixel(&image, 1, 0), [0, 0, 0xff, 0xff]);
+}
+
+#[test]
+fn dibs_with_an_empty_alpha_channel_fall_back_to_the_mask() {
+    let rows = vec![vec![0, 0, 0xff, 0, 0xff, 0, 0, 0]];
+    let bytes = ico(&[dib(2, 1, 32, &[], &rows, Some(&[true, false]))]);
+    let image = decode_ico(&bytes).unwrap().remove(0);
+    assert_eq!(pixel(&image, 0, 0), [0, 0, 0, 0]);
+    assert_eq!(pixel(&image, 1, 0), [0, 0, 0xff, 0xff]);
+
+    // Without a mask every pixel is opaque
+    let bytes = ico(&[dib(2, 1, 32, &[], &rows, None)]);
+    let image = decode_ico(&bytes).unwrap().remove(0);
+    assert_eq!(pixel(&image, 0, 0), [0xff, 0, 0, 0xff]);
+}
+
+#[test]
+fn truncated_files_are_reported() {
+    let png = rgba_png(4, 4, [0xff; 4]);
+    assert!(matches!(
+        Image::from_png(&png[..png.len() / 2]),
+        Err(Error::Png(_))
+    ));
+
+    // One entry announced, none there
+    assert!(matches!(
+        decode_ico(&[0, 0, 1, 0, 1, 0]),
+        Err(Error::Truncated)
+    ));
+
+    // The entry points past the end of the file
+    let mut bytes = ico(std::slice::from_ref(&png));
+    bytes.truncate(bytes.len() - 1);
+    assert!(matches!(decode_ico(&bytes), Err(Error::Truncated)));
+
+    // The color bits stop early
+    let rows = vec![vec![0; 8]; 2];
+    let mut entry = dib(2, 2, 24, &[], &rows, None);
+    entry.truncate(entry.len() - 4);
+    assert!(matches!(decode_ico(&ico(&[entry])), Err(Error::Truncated)));
+
+    // The palette stops early
+    let mut entry = dib(2, 1, 8, &[RED; 3], &[vec![0; 4]], None);
+    entry.truncate(40 + 8);
+    assert!(matches!(decode_ico(&ico(&[entry])), Err(Error::Truncated)));
+
+    // Sizes no file could hold, without overflowing on the way
+    let mut entry = dib(2, 1, 32, &[], &[vec![0; 8]], None);
+    entry[4..8].copy_from_slice(&(i32::MAX as u32).to_le_bytes());
+    entry[8..12].copy_from_slice(&(i32::MAX as u32 - 1).to_le_bytes());
+    assert!(matches!(decode_ico(&ico(&[entry])), Err(Error::Truncated)));
+
+    let mut entry = dib(2, 1, 8, &[RED; 3], &[vec![0; 4]], None);
+    entry[32..36].copy_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(decode_ico(&ico(&[entry])), Err(Error::Truncated)));
+}
+
+#[test]
+fn invalid_headers_are_rejected() {
+    assert!(matches!(
+        ImageSet::decode(b"GIF89a", 16),
+        Err(Error::UnknownFormat)
+    ));
+    assert!(matches!(
+        decode_ico(&[0, 0, 2, 0, 0, 0]),
+        Err(Error::UnknownFormat)
+    ));
+    assert!(matches!(decode_ico(&ico(&[])), Err(Error::Empty)));
+
+    let mut png = rgba_png(1, 1, [0; 4]);
+    png[12] = b'X';
+    assert!(matches!(Image::from_png(&png), Err(Error::Png(_))));
+
+    let rows = vec![vec![0; 4]];
+    let mut small_header = dib(1, 1, 24, &[], &rows, None);
+    small_header[0] = 12;
+    assert!(matches!(
+        decode_ico(&ico(&[small_header])),
+        Err(Error::Unsupported(_))
+    ));
+
+    let mut compressed = dib(1, 1, 24, &[], &rows, None);
+    compressed[16] = 1;
+    assert!(matches!(
+        decode_ico(&ico(&[compressed])),
+        Err(Error::Unsupported(_))
+    ));
+
+    let sixteen_bits = dib(1, 1, 16, &[], &rows, None);
+    assert!(matches!(
+        decode_ico(&ico(&[sixteen_bits])),
+        Err(Error::Unsupported(_))
+    ));
+
+    let empty = dib(0, 1, 24, &[], &rows, None);
+    assert!(matches!(
+        decode_ico(&ico(&[empty])),
+        Err(Error::InvalidDimensions(0, 1))
+    ));
+
+    assert!(matches!(
+        Image::from_rgba(2, 2, vec![0; 4]),
+        Err(Error::InvalidDimensions(2, 2))
+    ));
+}
+
+#[test]
+fn premultiplying_rounds_to_the_nearest_value() {
+    let mut pixels = vec![
+        255, 1, 1, 128, // 128 exactly, 0.502 rounds to 1
+        1, 200, 0, 127, // 0.498 rounds to 0, 99.6 to 100
+        200, 100, 50, 100, // 78.4, 39.2 and 19.6
+        9, 8, 7, 0, // nothing left without alpha
+        9, 8, 7, 255, // untouched when opaque
+    ];
+    premultiply(&mut pixels);
+
+    assert_eq!(
+        pixels,
+        [
+            128, 1, 1, 128, //
+            0, 100, 0, 127, //
+            78, 39, 20, 100, //
+            0, 0, 0, 0, //
+            9, 8, 7, 255,
+        ]
+    );
+}
+
+fn square(size: u32) -> Image {
+    Image::from_rgba(size, size, vec![0; (size * size * 4) as usize]).unwrap()
+}
+
+#[test]
+fn the_smallest_variant_that_is_big_enough_is_selected() {
+    let variants = [square(48), square(16), square(32)];
+
+    assert_eq!(select_variant(&variants, 16), 1);
+    assert_eq!(select_variant(&variants, 17), 2);
+    assert_eq!(select_variant(&variants, 40), 0);
+    // Nothing is big enough, the largest gets scaled up
+    assert_eq!(select_variant(&variants, 64), 0);
+}
+
+#[test]
+fn image_sets_pick_a_variant_per_dpi() {
+    let set = ImageSet::new(vec![square(32), square(16), square(20)], 16).unwrap();
+
+    let sizes = [(96, 16), (144, 24), (192, 32)];
+    for (dpi, size) in sizes {
+        assert_eq!(set.size_for_dpi(Dpi::new(dpi)), size);
+    }
+
+    assert_eq!(set.for_dpi(Dpi::new(96)).width(), 16);
+    assert_eq!(set.for_dpi(Dpi::new(144)).width(), 32);
+    assert_eq!(set.for_dpi(Dpi::new(192)).width(), 32);
+    // Answered from the cache the second time
+    assert_eq!(set.for_dpi(Dpi::new(144)).width(), 32);
+
+    let small = ImageSet::new(vec![square(16), square(20)], 16).unwrap();
+    assert_eq!(small.for_dpi(Dpi::new(144)).width(), 20);
+    assert_eq!(small.for_dpi(Dpi::new(192)).width(), 20);
+
+    assert!(matches!(ImageSet::new(Vec::new(), 16), Err(Error::Empty)));
+}
+
+#[test]
+fn image_sets_decode_both_formats() {
+    let png = ImageSet::decode(&rgba_png(24, 24, [0; 4]), 16).unwrap();
+    assert_eq!(png.variants().len(), 1);
+
+    let ico = ImageSet::decode(
+        &ico(&[rgba_png(32, 32, [0; 4]), rgba_png(16, 16, [0; 4])]),
+        16,
+    )
+    .unwrap();
+    let widths = ico.variants().iter().map(Image::width).collect::<Vec<_>>();
+    assert_eq!(widths, [16, 32]);
+}
@@ -0,0 +1,169 @@
+// Reads what keys type from X11 keyboard mappings and xkb keymaps.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::event::Key;
+use testwindowtabs::keymap::{
+    key_from_keysym, keysym_from_name, keysym_to_char, Keymap, ALT, CAPS_LOCK, CONTROL, LEVEL3,
+    NUM_LOCK, SHIFT,
+};
+
+// Cut down from what a compositor hands out for a German layout with a Russian second group
+const KEYMAP: &str = r#"xkb_keymap {
+xkb_keycodes "evdev+aliases(qwertz)" {
+	minimum = 8;
+	maximum = 255;
+	<ESC>                = 9;
+	<AE01>               = 10;
+	<AD01>               = 24;
+	<AD06>               = 29;
+	<AC01>               = 38;
+	<AC10>               = 47;
+	<KP1>                = 87;
+	<RTRN>               = 36;
+	alias <LatZ>         = <AD06>;
+	indicator 1 = "Caps Lock";
+};
+
+xkb_types "complete" {
+	virtual_modifiers NumLock,Alt,LevelThree;
+	type "ONE_LEVEL" {
+		modifiers= none;
+		level_name[Level1]= "Any";
+	};
+};
+
+xkb_compatibility "complete" {
+	interpret Shift_Lock+AnyOf(Shift+Lock) {
+		action= LockMods(modifiers=Shift);
+	};
+};
+
+xkb_symbols "pc+de+ru:2+inet(evdev)" {
+	name[group1]="German";
+	name[group2]="Russian";
+
+	key <ESC>                {	[          Escape ] };
+	key <AE01>               {	[               1,          exclam,     onesuperior,      exclamdown ] };
+	key <AD01>               {
+		type[group1]= "FOUR_LEVEL_SEMIALPHABETIC",
+		symbols[Group1]= [               q,               Q,              at,     Greek_OMEGA ],
+		symbols[Group2]= [     Cyrillic_shorti,     Cyrillic_SHORTI ]
+	};
+	key <LatZ>               {	[               z,               Z ], [ Cyrillic_en, Cyrillic_EN ] };
+	key <AC01>               {	[               a,               A,              ae,              AE ] };
+	key <AC10>               {	[      odiaeresis,      Odiaeresis,     dead_doubleacute ] };
+	key <KP1>                {	[          KP_End,            KP_1 ] };
+	key <RTRN>               {	[          Return ] };
+	key <AB09>               {	[ U20AC, { a, b } ] };
+	modifier_map Mod5 { <LVL3> };
+};
+
+};
+"#;
+
+#[test]
+fn an_xkb_keymap_types_by_group_and_level() {
+    let keymap = Keymap::parse(KEYMAP).unwrap();
+    let q = keymap.symbols(24).unwrap();
+    assert_eq!(q.key(), Key::Char('q'));
+    assert_eq!(q.text(0, 0), Some('q'));
+    assert_eq!(q.text(0, SHIFT), Some('Q'));
+    assert_eq!(q.text(0, LEVEL3), Some('@'));
+    assert_eq!(q.text(0, LEVEL3 | SHIFT), Some('Ω'));
+    assert_eq!(q.text(1, 0), Some('й'));
+    assert_eq!(q.text(1, SHIFT), Some('Й'));
+    // Shortcuts type nothing
+    assert_eq!(q.text(0, CONTROL), None);
+    assert_eq!(q.text(0, ALT | SHIFT), None);
+
+    // Keys named by an alias and with their groups one after the other
+    let z = keymap.symbols(29).unwrap();
+    assert_eq!(z.text(0, SHIFT), Some('Z'));
+    assert_eq!(z.text(1, 0), Some('н'));
+
+    let o = keymap.symbols(47).unwrap();
+    assert_eq!(o.key(), Key::Char('ö'));
+    assert_eq!(o.text(0, SHIFT), Some('Ö'));
+    // Dead keys don't type by themselves
+    assert_eq!(o.text(0, LEVEL3), None);
+    // A group the key doesn't have is the first one
+    assert_eq!(o.text(1, 0), Some('ö'));
+
+    assert_eq!(keymap.symbols(9).unwrap().key(), Key::Escape);
+    assert_eq!(keymap.symbols(9).unwrap().text(0, 0), None);
+    assert_eq!(keymap.symbols(36).unwrap().key(), Key::Enter);
+    assert_eq!(keymap.symbols(10).unwrap().text(0, SHIFT), Some('!'));
+    // Keys without a keycode are left out
+    assert_eq!(keymap.symbols(59), None);
+}
+
+#[test]
+fn caps_lock_shifts_letters_and_num_lock_the_keypad() {
+    let keymap = Keymap::parse(KEYMAP).unwrap();
+    let a = keymap.symbols(38).unwrap();
+    assert_eq!(a.text(0, CAPS_LOCK), Some('A'));
+    assert_eq!(a.text(0, CAPS_LOCK | SHIFT), Some('a'));
+    let one = keymap.symbols(10).unwrap();
+    assert_eq!(one.text(0, CAPS_LOCK), Some('1'));
+
+    let kp1 = keymap.symbols(87).unwrap();
+    assert_eq!(kp1.text(0, 0), None);
+    assert_eq!(kp1.key(), Key::End);
+    assert_eq!(kp1.text(0, NUM_LOCK), Some('1'));
+    assert_eq!(kp1.text(0, NUM_LOCK | SHIFT), None);
+}
+
+#[test]
+fn text_that_is_no_keymap_has_no_keys() {
+    assert_eq!(Keymap::parse(""), None);
+    assert_eq!(
+        Keymap::parse("xkb_keymap { xkb_symbols { key <AC01> { [ a ] }; }; };"),
+        None
+    );
+    assert_eq!(Keymap::parse("xkb_keycodes { <AC01> = 38"), None);
+}
+
+#[test]
+fn the_x11_mapping_has_two_groups_and_a_third_level() {
+    // Three keys from keycode 8, seven keysyms each
+    let q = [0x71, 0x51, 0x6ca, 0x6ea, 0x40, 0x7d9, 0];
+    let a = [0x61, 0, 0, 0, 0, 0, 0];
+    let escape = [0xff1b, 0, 0, 0, 0, 0, 0];
+    let keysyms = [q, a, escape].concat();
+    let keymap = Keymap::from_x11(8, 7, &keysyms);
+
+    let q = keymap.symbols(8).unwrap();
+    assert_eq!(q.text(0, SHIFT), Some('Q'));
+    assert_eq!(q.text(0, LEVEL3), Some('@'));
+    assert_eq!(q.text(0, LEVEL3 | SHIFT), Some('Ω'));
+    assert_eq!(q.text(1, 0), Some('й'));
+    assert_eq!(q.text(1, SHIFT), Some('Й'));
+
+    // Listed in lower case only
+    assert_eq!(keymap.symbols(9).unwrap().text(0, SHIFT), Some('A'));
+    assert_eq!(keymap.symbols(10).unwrap().key(), Key::Escape);
+    assert_eq!(keymap.symbols(11), None);
+}
+
+#[test]
+fn keysyms_are_named_and_type_characters() {
+    assert_eq!(keysym_from_name("a"), Some(0x61));
+    assert_eq!(keysym_from_name("1"), Some(0x31));
+    assert_eq!(keysym_from_name("EuroSign"), Some(0x20ac));
+    assert_eq!(keysym_from_name("U20AC"), Some(0x10020ac));
+    assert_eq!(keysym_from_name("U00E4"), Some(0xe4));
+    assert_eq!(keysym_from_name("0x100263a"), Some(0x100263a));
+    assert_eq!(keysym_from_name("Udiaeresis"), Some(0xdc));
+    assert_eq!(keysym_from_name("BackSpace"), Some(0xff08));
+    assert_eq!(keysym_from_name("NotAKeysym"), None);
+
+    assert_eq!(keysym_to_char(0x20ac), Some('€'));
+    assert_eq!(keysym_to_char(0x10020ac), Some('€'));
+    assert_eq!(keysym_to_char(0xe4), Some('ä'));
+    assert_eq!(keysym_to_char(0xffb7), Some('7'));
+    assert_eq!(keysym_to_char(0xff08), None);
+
+    assert_eq!(key_from_keysym(0x41), Key::Char('a'));
+    assert_eq!(key_from_keysym(0xff8d), Key::Enter);
+    assert_eq!(key_from_keysym(0xffe1), Key::Other);
+}
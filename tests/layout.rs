@@ -0,0 +1,388 @@
+// Lays out a few thousand random rows and columns and checks what has to hold for all of
+// them, plus the shrinking cases the properties don't pin down exactly. The generator is
+// seeded, so a failing case comes back on every run.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::geometry::{Insets, Rect, Size};
+use testwindowtabs::layout::{Align, Direction, Flex, FlexItem, Justify};
+
+const CASES: usize = 5000;
+
+// xorshift64*
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn range(&mut self, low: i32, high: i32) -> i32 {
+        low + (self.next() % (high - low + 1) as u64) as i32
+    }
+
+    fn pick<T: Copy>(&mut self, values: &[T]) -> T {
+        values[self.next() as usize % values.len()]
+    }
+
+    fn insets(&mut self, max: i32) -> Insets {
+        Insets::new(
+            self.range(0, max),
+            self.range(0, max),
+            self.range(0, max),
+            self.range(0, max),
+        )
+    }
+}
+
+struct Case {
+    flex: Flex,
+    bounds: Rect,
+    items: Vec<FlexItem>,
+}
+
+fn random_case(rng: &mut Rng) -> Case {
+    let flex = Flex::new(rng.pick(&[Direction::Row, Direction::Column]))
+        .with_padding(rng.insets(10))
+        .with_gap(rng.range(0, 8))
+        .with_align(rng.pick(&[Align::Start, Align::Center, Align::End, Align::Stretch]))
+        .with_justify(rng.pick(&[
+            Justify::Start,
+            Justify::Center,
+            Justify::End,
+            Justify::SpaceBetween,
+        ]));
+
+    let bounds = Rect::from_size(
+        rng.range(-50, 50),
+        rng.range(-50, 50),
+        rng.range(0, 400),
+        rng.range(0, 400),
+    );
+
+    let items = (0..rng.range(1, 7))
+        .map(|_| {
+            let mut item = FlexItem::new(Size::new(rng.range(0, 150), rng.range(0, 150)))
+                .with_grow(rng.pick(&[0.0, 0.0, 1.0, 2.0, 3.0]))
+                .with_shrink(rng.pick(&[0.0, 1.0, 1.0, 2.0]))
+                .with_margin(rng.insets(5));
+            if rng.range(0, 1) == 0 {
+                item = item.with_basis(rng.range(0, 150));
+            }
+            if rng.range(0, 3) == 0 {
+                item = item.with_align(rng.pick(&[Align::Start, Align::End, Align::Stretch]));
+            }
+            item
+        })
+        .collect();
+
+    Case {
+        flex,
+        bounds,
+        items,
+    }
+}
+
+// Splits rects, sizes and insets into (main, cross) for the direction of the case.
+impl Case {
+    fn inner(&self) -> Rect {
+        self.bounds.inset(&self.flex.padding)
+    }
+
+    fn main(&self, rect: &Rect) -> (i32, i32) {
+        match self.flex.direction {
+            Direction::Row => (rect.left, rect.right),
+            Direction::Column => (rect.top, rect.bottom),
+        }
+    }
+
+    fn cross(&self, rect: &Rect) -> (i32, i32) {
+        match self.flex.direction {
+            Direction::Row => (rect.top, rect.bottom),
+            Direction::Column => (rect.left, rect.right),
+        }
+    }
+
+    fn main_margin(&self, item: &FlexItem) -> (i32, i32) {
+        match self.flex.direction {
+            Direction::Row => (item.margin.left, item.margin.right),
+            Direction::Column => (item.margin.top, item.margin.bottom),
+        }
+    }
+
+    fn cross_margin(&self, item: &FlexItem) -> (i32, i32) {
+        match self.flex.direction {
+            Direction::Row => (item.margin.top, item.margin.bottom),
+            Direction::Column => (item.margin.left, item.margin.right),
+        }
+    }
+
+    fn basis(&self, item: &FlexItem) -> i32 {
+        let size = match self.flex.direction {
+            Direction::Row => item.size.width,
+            Direction::Column => item.size.height,
+        };
+        item.basis.unwrap_or(size).max(0)
+    }
+
+    fn cross_size(&self, item: &FlexItem) -> i32 {
+        match self.flex.direction {
+            Direction::Row => item.size.height,
+            Direction::Column => item.size.width,
+        }
+    }
+
+    // Main axis space that isn't taken by the items themselves.
+    fn spacing(&self) -> i32 {
+        let margins: i32 = self
+            .items
+            .iter()
+            .map(|item| self.main_margin(item).0 + self.main_margin(item).1)
+            .sum();
+        margins + self.flex.gap * (self.items.len() as i32 - 1)
+    }
+
+    fn available(&self) -> i32 {
+        let (start, end) = self.main(&self.inner());
+        end - start - self.spacing()
+    }
+
+    fn free(&self) -> i32 {
+        self.available() - self.items.iter().map(|item| self.basis(item)).sum::<i32>()
+    }
+
+    fn rigid(&self) -> i32 {
+        self.items
+            .iter()
+            .filter(|item| item.shrink <= 0.0)
+            .map(|item| self.basis(item))
+            .sum()
+    }
+
+    fn total_grow(&self) -> f32 {
+        self.items.iter().map(|item| item.grow).sum()
+    }
+
+    fn leading(&self, rects: &[Rect]) -> i32 {
+        self.main(&rects[0]).0 - self.main_margin(&self.items[0]).0 - self.main(&self.inner()).0
+    }
+
+    fn trailing(&self, rects: &[Rect]) -> i32 {
+        let last = self.items.len() - 1;
+        self.main(&self.inner()).1
+            - self.main(&rects[last]).1
+            - self.main_margin(&self.items[last]).1
+    }
+}
+
+fn for_each_case(check: impl Fn(usize, &Case, &[Rect])) {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    for n in 0..CASES {
+        let case = random_case(&mut rng);
+        let rects = case.flex.layout(case.bounds, &case.items);
+        assert_eq!(rects.len(), case.items.len());
+        check(n, &case, &rects);
+    }
+}
+
+#[test]
+fn rects_are_never_negative() {
+    for_each_case(|n, _, rects| {
+        for rect in rects {
+            assert!(
+                rect.width() >= 0 && rect.height() >= 0,
+                "case {}: {:?}",
+                n,
+                rect
+            );
+        }
+    });
+}
+
+#[test]
+fn rects_stay_inside_the_padding_when_they_can() {
+    for_each_case(|n, case, rects| {
+        let inner = case.inner();
+        let (main_start, main_end) = case.main(&inner);
+        let (cross_start, cross_end) = case.cross(&inner);
+        let fits = case.rigid() <= case.available();
+
+        for (item, rect) in case.items.iter().zip(rects) {
+            let (cross_before, cross_after) = case.cross_margin(item);
+            if cross_before + cross_after <= cross_end - cross_start {
+                let (start, end) = case.cross(rect);
+                assert!(start >= cross_start && end <= cross_end, "case {}", n);
+            }
+
+            if fits {
+                let (start, end) = case.main(rect);
+                assert!(start >= main_start && end <= main_end, "case {}", n);
+            }
+        }
+    });
+}
+
+#[test]
+fn neighbours_are_exactly_their_margins_and_the_gap_apart() {
+    for_each_case(|n, case, rects| {
+        if case.flex.justify == Justify::SpaceBetween {
+            return;
+        }
+
+        for idx in 1..rects.len() {
+            let spacing = case.main_margin(&case.items[idx - 1]).1
+                + case.flex.gap
+                + case.main_margin(&case.items[idx]).0;
+            assert_eq!(
+                case.main(&rects[idx]).0 - case.main(&rects[idx - 1]).1,
+                spacing,
+                "case {} item {}",
+                n,
+                idx
+            );
+        }
+    });
+}
+
+#[test]
+fn free_space_is_shared_by_grow_factor() {
+    for_each_case(|n, case, rects| {
+        let (free, total_grow) = (case.free(), case.total_grow());
+        if free <= 0 || total_grow <= 0.0 {
+            return;
+        }
+
+        for (item, rect) in case.items.iter().zip(rects) {
+            let (start, end) = case.main(rect);
+            let expected = case.basis(item) as f32 + free as f32 * item.grow / total_grow;
+            assert!(
+                ((end - start) as f32 - expected).abs() <= 1.0,
+                "case {}: {} instead of {}",
+                n,
+                end - start,
+                expected
+            );
+        }
+
+        // Growing items leave nothing over for justifying
+        assert_eq!(case.leading(rects), 0, "case {}", n);
+        assert_eq!(case.trailing(rects), 0, "case {}", n);
+    });
+}
+
+#[test]
+fn overflow_is_taken_from_the_items_that_shrink() {
+    for_each_case(|n, case, rects| {
+        if case.free() >= 0 || case.rigid() > case.available() {
+            return;
+        }
+        let shrinkable = case
+            .items
+            .iter()
+            .any(|item| item.shrink > 0.0 && case.basis(item) > 0);
+        if !shrinkable {
+            return;
+        }
+
+        // Exactly filled, nothing overflows
+        assert_eq!(case.leading(rects), 0, "case {}", n);
+        assert_eq!(case.trailing(rects), 0, "case {}", n);
+
+        for (item, rect) in case.items.iter().zip(rects) {
+            let (start, end) = case.main(rect);
+            if item.shrink <= 0.0 {
+                assert_eq!(end - start, case.basis(item), "case {}", n);
+            } else {
+                assert!(end - start <= case.basis(item), "case {}", n);
+            }
+        }
+    });
+}
+
+#[test]
+fn leftover_space_goes_where_justify_says() {
+    for_each_case(|n, case, rects| {
+        if case.free() <= 0 || case.total_grow() > 0.0 {
+            return;
+        }
+
+        let (leading, trailing) = (case.leading(rects), case.trailing(rects));
+        assert!(leading + trailing <= case.free(), "case {}", n);
+        match case.flex.justify {
+            Justify::Start => assert_eq!(leading, 0, "case {}", n),
+            Justify::End => assert_eq!(trailing, 0, "case {}", n),
+            Justify::Center => assert!((leading - trailing).abs() <= 1, "case {}", n),
+            Justify::SpaceBetween if rects.len() > 1 => {
+                assert_eq!((leading, trailing), (0, 0), "case {}", n)
+            }
+            Justify::SpaceBetween => assert_eq!(leading, 0, "case {}", n),
+        }
+    });
+}
+
+#[test]
+fn items_are_aligned_on_the_cross_axis() {
+    for_each_case(|n, case, rects| {
+        let (cross_start, cross_end) = case.cross(&case.inner());
+
+        for (item, rect) in case.items.iter().zip(rects) {
+            let (before, after) = case.cross_margin(item);
+            let available = cross_end - cross_start - before - after;
+            if available < case.cross_size(item) {
+                continue;
+            }
+
+            let (start, end) = case.cross(rect);
+            let (leading, trailing) = (start - cross_start - before, cross_end - after - end);
+            match item.align.unwrap_or(case.flex.align) {
+                Align::Start => assert_eq!(leading, 0, "case {}", n),
+                Align::End => assert_eq!(trailing, 0, "case {}", n),
+                Align::Center => assert!((leading - trailing).abs() <= 1, "case {}", n),
+                Align::Stretch => assert_eq!((leading, trailing), (0, 0), "case {}", n),
+            }
+            if item.align.unwrap_or(case.flex.align) != Align::Stretch {
+                assert_eq!(end - start, case.cross_size(item), "case {}", n);
+            }
+        }
+    });
+}
+
+fn widths(rects: &[Rect]) -> Vec<i32> {
+    rects.iter().map(Rect::width).collect()
+}
+
+#[test]
+fn bigger_items_shrink_more() {
+    let items = [
+        FlexItem::new(Size::new(100, 10)),
+        FlexItem::new(Size::new(300, 10)),
+    ];
+    let rects = Flex::row().layout(Rect::new(0, 0, 200, 10), &items);
+    assert_eq!(widths(&rects), [50, 150]);
+}
+
+#[test]
+fn what_an_item_cant_give_up_is_taken_from_the_others() {
+    // Both weigh 100, so each should give up 25, but the small one only has 10
+    let items = [
+        FlexItem::new(Size::new(10, 10)).with_shrink(10.0),
+        FlexItem::new(Size::new(100, 10)),
+    ];
+    let rects = Flex::row().layout(Rect::new(0, 0, 60, 10), &items);
+    assert_eq!(widths(&rects), [0, 60]);
+    assert_eq!(rects[1].right, 60);
+}
+
+#[test]
+fn items_that_cant_shrink_overflow() {
+    let items = [
+        FlexItem::fixed(80),
+        FlexItem::new(Size::new(50, 10)),
+        FlexItem::fixed(40),
+    ];
+    let rects = Flex::row().layout(Rect::new(0, 0, 100, 10), &items);
+    assert_eq!(widths(&rects), [80, 0, 40]);
+    assert_eq!(rects[2].right, 120);
+}
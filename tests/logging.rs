@@ -0,0 +1,126 @@
+// Which records the log filter lets through, and how window messages are named.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::logging::{message_name, Filter, Level, MessageName, Target, MESSAGE_NAMES};
+
+const LEVELS: [Level; 5] = [
+    Level::Error,
+    Level::Warn,
+    Level::Info,
+    Level::Debug,
+    Level::Trace,
+];
+
+// The most verbose level `target` gets through, `None` when it is off.
+fn max_level(filter: &Filter, target: Target) -> Option<Level> {
+    LEVELS
+        .into_iter()
+        .rev()
+        .find(|level| filter.enabled(target, *level))
+}
+
+#[test]
+fn by_default_everything_but_messages_logs_info() {
+    let filter = Filter::default();
+
+    for target in Target::ALL {
+        let expected = match target {
+            Target::Messages => None,
+            _ => Some(Level::Info),
+        };
+        assert_eq!(max_level(&filter, target), expected, "{:?}", target);
+    }
+    assert_eq!(Filter::parse("").unwrap(), filter);
+}
+
+#[test]
+fn a_spec_sets_the_default_and_overrides_targets() {
+    let filter = Filter::parse("warn, tab_bar=debug,messages=TRACE,d2d=off").unwrap();
+
+    assert_eq!(max_level(&filter, Target::Window), Some(Level::Warn));
+    assert_eq!(max_level(&filter, Target::Button), Some(Level::Warn));
+    assert_eq!(max_level(&filter, Target::TabBar), Some(Level::Debug));
+    assert_eq!(max_level(&filter, Target::Messages), Some(Level::Trace));
+    assert_eq!(max_level(&filter, Target::D2d), None);
+}
+
+#[test]
+fn messages_stay_off_unless_asked_for() {
+    let filter = Filter::parse("trace").unwrap();
+    assert_eq!(max_level(&filter, Target::Window), Some(Level::Trace));
+    assert_eq!(max_level(&filter, Target::Messages), None);
+}
+
+#[test]
+fn off_turns_everything_off() {
+    let filter = Filter::parse("off,window=error").unwrap();
+    assert_eq!(max_level(&filter, Target::Button), None);
+    assert_eq!(max_level(&filter, Target::Window), Some(Level::Error));
+}
+
+#[test]
+fn the_last_setting_wins() {
+    let filter = Filter::parse("debug,window=trace,error,window=warn").unwrap();
+    assert_eq!(max_level(&filter, Target::Button), Some(Level::Error));
+    assert_eq!(max_level(&filter, Target::Window), Some(Level::Warn));
+}
+
+#[test]
+fn builders_match_parsing() {
+    let built = Filter::new(Some(Level::Warn)).with_target(Target::TabBar, None);
+    assert_eq!(max_level(&built, Target::TabBar), None);
+    assert_eq!(max_level(&built, Target::Window), Some(Level::Warn));
+    // Unlike the default, a new filter traces messages like anything else
+    assert_eq!(max_level(&built, Target::Messages), Some(Level::Warn));
+}
+
+#[test]
+fn malformed_specs_are_rejected() {
+    for spec in [
+        "loud",
+        "window=loud",
+        "windows=debug",
+        "=debug",
+        "window=",
+        "window=debug=trace",
+        "Window=debug",
+    ] {
+        assert!(Filter::parse(spec).is_err(), "{}", spec);
+    }
+
+    assert_eq!(
+        Filter::parse("warn,nope=debug").unwrap_err(),
+        "unknown log target `nope`"
+    );
+    assert_eq!(
+        Filter::parse("window=loud").unwrap_err(),
+        "unknown log level `loud`"
+    );
+}
+
+#[test]
+fn message_names_are_sorted_by_id() {
+    for pair in MESSAGE_NAMES.windows(2) {
+        assert!(pair[0].0 < pair[1].0, "{:?} before {:?}", pair[0], pair[1]);
+    }
+}
+
+#[test]
+fn every_listed_message_is_found() {
+    for (id, name) in MESSAGE_NAMES {
+        assert_eq!(message_name(*id), Some(*name));
+        assert_eq!(MessageName(*id).to_string(), *name);
+    }
+    assert_eq!(message_name(0x0004), None);
+}
+
+#[test]
+fn unknown_messages_are_formatted_by_range() {
+    assert_eq!(MessageName(0x0004).to_string(), "0x0004");
+    assert_eq!(MessageName(0x03ff).to_string(), "0x03ff");
+    assert_eq!(MessageName(0x0400).to_string(), "WM_USER+0x0");
+    assert_eq!(MessageName(0x0401).to_string(), "WM_USER+0x1");
+    assert_eq!(MessageName(0x7fff).to_string(), "WM_USER+0x7bff");
+    assert_eq!(MessageName(0x8000).to_string(), "WM_APP+0x0");
+    assert_eq!(MessageName(0x8010).to_string(), "WM_APP+0x10");
+}
@@ -0,0 +1,365 @@
+// How the menus the window draws itself are navigated, and where they open.
+#![cfg(target_os = "linux")]
+
+use std::time::Duration;
+
+use testwindowtabs::canvas::{Canvas, Color};
+use testwindowtabs::dpi::Dpi;
+use testwindowtabs::event::Key;
+use testwindowtabs::font::Font;
+use testwindowtabs::geometry::{Point, PointF, Rect, RectF, Size};
+use testwindowtabs::image::Image;
+use testwindowtabs::menu::{MenuEntry, MenuItem};
+use testwindowtabs::popup_menu::{place_menu, place_submenu, MenuOutcome, MenuState, PopupMenu};
+use testwindowtabs::theme::MenuColors;
+use testwindowtabs::widget::{Context, Widget};
+
+const AREA: Rect = Rect::new(0, 0, 800, 600);
+
+// Open, a disabled Save, a separator, a Share submenu and two items sharing C.
+fn entries() -> Vec<MenuEntry<u32>> {
+    vec![
+        MenuEntry::item(1, "&Open"),
+        MenuEntry::Item(MenuItem::new(2, "&Save").with_enabled(false)),
+        MenuEntry::Separator,
+        MenuEntry::submenu(
+            "Sh&are",
+            vec![MenuEntry::item(10, "&Mail"), MenuEntry::item(11, "&Link")],
+        ),
+        MenuEntry::item(3, "&Copy"),
+        MenuEntry::item(4, "&Close"),
+    ]
+}
+
+fn press(state: &mut MenuState, keys: &[Key]) -> Option<MenuOutcome<u32>> {
+    keys.iter()
+        .map(|key| state.key(&entries(), *key))
+        .last()
+        .flatten()
+}
+
+fn center(rect: Rect) -> Point {
+    Point::new(rect.left + rect.width() / 2, rect.top + rect.height() / 2)
+}
+
+fn context() -> Context {
+    Context::new(Duration::ZERO, Dpi::DEFAULT)
+}
+
+#[test]
+fn up_and_down_skip_what_can_not_be_picked_and_wrap() {
+    let mut state = MenuState::new();
+    let mut highlighted = Vec::new();
+    for _ in 0..5 {
+        press(&mut state, &[Key::Down]);
+        highlighted.push(state.highlighted(0).unwrap());
+    }
+    assert_eq!(highlighted, [0, 3, 4, 5, 0]);
+
+    press(&mut state, &[Key::Up]);
+    assert_eq!(state.highlighted(0), Some(5));
+    press(&mut state, &[Key::Up, Key::Up, Key::Up]);
+    assert_eq!(state.highlighted(0), Some(0));
+}
+
+#[test]
+fn home_and_end_go_to_the_first_and_last_entries() {
+    let mut state = MenuState::new();
+    press(&mut state, &[Key::End]);
+    assert_eq!(state.highlighted(0), Some(5));
+    press(&mut state, &[Key::Home]);
+    assert_eq!(state.highlighted(0), Some(0));
+}
+
+#[test]
+fn right_and_left_open_and_close_submenus() {
+    let mut state = MenuState::new();
+
+    // Only a submenu opens
+    press(&mut state, &[Key::Home, Key::Right]);
+    assert_eq!(state.depth(), 1);
+
+    press(&mut state, &[Key::Down, Key::Right]);
+    assert_eq!(state.depth(), 2);
+    assert_eq!(state.highlighted(1), Some(0));
+    assert_eq!(state.entries(&entries(), 1).len(), 2);
+
+    press(&mut state, &[Key::Down]);
+    assert_eq!(state.highlighted(1), Some(1));
+
+    press(&mut state, &[Key::Left]);
+    assert_eq!(state.depth(), 1);
+    assert_eq!(state.highlighted(0), Some(3));
+
+    // Moving on in the parent closes the submenu
+    press(&mut state, &[Key::Right, Key::Left, Key::Right]);
+    assert_eq!(state.depth(), 2);
+    state.highlight(0, Some(4));
+    assert_eq!(state.depth(), 1);
+}
+
+#[test]
+fn escape_closes_a_submenu_before_the_menu() {
+    let mut state = MenuState::new();
+    press(&mut state, &[Key::Home, Key::Down, Key::Right]);
+
+    assert_eq!(press(&mut state, &[Key::Escape]), None);
+    assert_eq!(state.depth(), 1);
+    assert_eq!(
+        press(&mut state, &[Key::Escape]),
+        Some(MenuOutcome::Dismissed)
+    );
+}
+
+#[test]
+fn enter_picks_the_highlighted_item() {
+    let mut state = MenuState::new();
+    assert_eq!(press(&mut state, &[Key::Enter]), None);
+
+    assert_eq!(
+        press(&mut state, &[Key::End, Key::Enter]),
+        Some(MenuOutcome::Picked(4))
+    );
+
+    // On a submenu it opens it instead
+    let mut state = MenuState::new();
+    assert_eq!(press(&mut state, &[Key::Home, Key::Down, Key::Space]), None);
+    assert_eq!(state.depth(), 2);
+    assert_eq!(
+        press(&mut state, &[Key::Down, Key::Enter]),
+        Some(MenuOutcome::Picked(11))
+    );
+}
+
+#[test]
+fn a_marked_key_picks_its_entry() {
+    let mut state = MenuState::new();
+    assert_eq!(
+        press(&mut state, &[Key::Char('O')]),
+        Some(MenuOutcome::Picked(1))
+    );
+
+    // Disabled items and unmarked keys do nothing
+    let mut state = MenuState::new();
+    assert_eq!(press(&mut state, &[Key::Char('s')]), None);
+    assert_eq!(press(&mut state, &[Key::Char('z')]), None);
+    assert_eq!(state.highlighted(0), None);
+
+    // A submenu opens and its own keys apply
+    assert_eq!(press(&mut state, &[Key::Char('a')]), None);
+    assert_eq!(state.depth(), 2);
+    assert_eq!(
+        press(&mut state, &[Key::Char('l')]),
+        Some(MenuOutcome::Picked(11))
+    );
+}
+
+#[test]
+fn a_key_marking_several_entries_goes_through_them() {
+    let mut state = MenuState::new();
+    let mut highlighted = Vec::new();
+    for _ in 0..3 {
+        assert_eq!(press(&mut state, &[Key::Char('c')]), None);
+        highlighted.push(state.highlighted(0).unwrap());
+    }
+    assert_eq!(highlighted, [4, 5, 4]);
+}
+
+#[test]
+fn a_menu_opens_below_and_right_of_where_it_was_asked_for() {
+    let size = Size::new(150, 200);
+    assert_eq!(
+        place_menu(Point::new(100, 100), size, AREA),
+        Rect::from_size(100, 100, 150, 200)
+    );
+}
+
+#[test]
+fn a_menu_flips_to_stay_inside_the_area() {
+    let size = Size::new(150, 200);
+    assert_eq!(
+        place_menu(Point::new(750, 100), size, AREA),
+        Rect::from_size(600, 100, 150, 200)
+    );
+    assert_eq!(
+        place_menu(Point::new(100, 500), size, AREA),
+        Rect::from_size(100, 300, 150, 200)
+    );
+    assert_eq!(
+        place_menu(Point::new(750, 500), size, AREA),
+        Rect::from_size(600, 300, 150, 200)
+    );
+}
+
+#[test]
+fn a_menu_fitting_neither_way_is_pushed_inside() {
+    // Not enough room on either side of the anchor, but enough in the area
+    assert_eq!(
+        place_menu(Point::new(400, 300), Size::new(500, 400), AREA),
+        Rect::from_size(300, 200, 500, 400)
+    );
+    // Larger than the area, it keeps its top left corner in it
+    assert_eq!(
+        place_menu(Point::new(400, 300), Size::new(100, 700), AREA),
+        Rect::from_size(400, 0, 100, 700)
+    );
+}
+
+#[test]
+fn a_submenu_opens_beside_its_parent() {
+    let parent = Rect::new(100, 100, 250, 300);
+    let item = Rect::new(100, 150, 250, 172);
+    let size = Size::new(150, 100);
+
+    assert_eq!(
+        place_submenu(parent, item, size, AREA),
+        Rect::from_size(250, 150, 150, 100)
+    );
+
+    // Too close to the right, it goes left of the parent
+    let parent = Rect::new(700, 100, 790, 300);
+    let item = Rect::new(700, 150, 790, 172);
+    assert_eq!(
+        place_submenu(parent, item, size, AREA),
+        Rect::from_size(550, 150, 150, 100)
+    );
+
+    // Too close to the bottom, it ends level with the item
+    let parent = Rect::new(100, 300, 250, 580);
+    let item = Rect::new(100, 520, 250, 542);
+    assert_eq!(
+        place_submenu(parent, item, size, AREA),
+        Rect::from_size(250, 442, 150, 100)
+    );
+}
+
+#[test]
+fn hovering_a_submenu_opens_it_and_releasing_over_an_item_picks_it() {
+    let mut menu = PopupMenu::new(
+        entries(),
+        Point::new(10, 10),
+        AREA,
+        MenuColors::default(),
+        Dpi::DEFAULT,
+    );
+    let mut ctx = context();
+    assert_eq!(menu.level_bounds().len(), 1);
+
+    let share = center(menu.entry_bounds(0, 3).unwrap());
+    menu.on_mouse_move(share.x, share.y, false, &mut ctx);
+    let levels = menu.level_bounds();
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[1].left, levels[0].right);
+    assert_eq!(menu.labels()[1], ["Mail", "Link"]);
+    assert!(!ctx.dirty().is_empty());
+
+    // Releasing over what can't be picked does nothing
+    let save = center(menu.entry_bounds(0, 1).unwrap());
+    menu.on_mouse_up(save.x, save.y, &mut ctx);
+    assert_eq!(menu.take_outcome(), None);
+
+    let link = center(menu.entry_bounds(1, 1).unwrap());
+    menu.on_mouse_move(link.x, link.y, false, &mut ctx);
+    menu.on_mouse_up(link.x, link.y, &mut ctx);
+    assert_eq!(menu.take_outcome(), Some(MenuOutcome::Picked(11)));
+}
+
+#[test]
+fn pressing_outside_dismisses_the_menu() {
+    let mut menu = PopupMenu::new(
+        entries(),
+        Point::new(10, 10),
+        AREA,
+        MenuColors::default(),
+        Dpi::DEFAULT,
+    );
+    let mut ctx = context();
+
+    let open = center(menu.entry_bounds(0, 0).unwrap());
+    menu.on_mouse_down(open.x, open.y, &mut ctx);
+    assert_eq!(menu.take_outcome(), None);
+
+    menu.on_mouse_down(700, 500, &mut ctx);
+    assert_eq!(menu.take_outcome(), Some(MenuOutcome::Dismissed));
+}
+
+#[test]
+fn a_menu_opened_in_a_corner_stays_inside_the_area() {
+    let mut menu = PopupMenu::new(
+        entries(),
+        Point::new(790, 590),
+        AREA,
+        MenuColors::default(),
+        Dpi::new(192),
+    );
+    let mut ctx = context();
+    menu.on_key(Key::Char('a'), &mut ctx);
+
+    let levels = menu.level_bounds();
+    assert_eq!(levels.len(), 2);
+    for level in &levels {
+        assert_eq!(level.intersection(&AREA), *level);
+    }
+    assert!(levels[1].right <= levels[0].left);
+}
+
+// Keeps the text drawn and the rectangles filled in the text color.
+struct TextCanvas {
+    color: Color,
+    texts: Vec<(String, Point)>,
+    rects: Vec<Rect>,
+}
+
+impl Canvas for TextCanvas {
+    fn dpi(&self) -> Dpi {
+        Dpi::DEFAULT
+    }
+
+    fn set_antialias(&mut self, _enabled: bool) {}
+    fn clear(&mut self, _color: Color) {}
+    fn fill_rect(&mut self, rect: RectF, color: Color) {
+        if color == self.color {
+            self.rects.push(rect.round());
+        }
+    }
+    fn stroke_rect(&mut self, _rect: RectF, _color: Color, _width: f32) {}
+    fn draw_line(&mut self, _from: PointF, _to: PointF, _color: Color, _width: f32) {}
+    fn fill_ellipse(&mut self, _center: PointF, _rx: f32, _ry: f32, _color: Color) {}
+    fn draw_image(&mut self, _image: &Image, _rect: RectF) {}
+    fn draw_text(&mut self, text: &str, origin: Point, _color: Color) {
+        self.texts.push((text.to_string(), origin));
+    }
+    fn push_clip(&mut self, _rect: RectF) {}
+    fn pop_clip(&mut self) {}
+}
+
+#[test]
+fn labels_are_drawn_as_text_with_the_marked_character_underlined() {
+    let entries = vec![
+        MenuEntry::Item(MenuItem::new(1, "Ü&bersicht").with_accelerator("Strg+Ü")),
+        MenuEntry::item(2, "Закрыть"),
+    ];
+    let colors = MenuColors::default();
+    let menu = PopupMenu::new(entries, Point::new(0, 0), AREA, colors, Dpi::DEFAULT);
+    let mut canvas = TextCanvas {
+        color: colors.text,
+        texts: Vec::new(),
+        rects: Vec::new(),
+    };
+    menu.paint(&mut canvas);
+
+    let texts = canvas
+        .texts
+        .iter()
+        .map(|(text, _)| text.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(texts, ["Übersicht", "Strg+Ü", "Закрыть"]);
+
+    // Under the b, one character in
+    let font = Font::new(Dpi::DEFAULT);
+    let (start, end) = font.char_span("Übersicht", 1);
+    let origin = canvas.texts[0].1;
+    assert!(canvas.rects.iter().any(|rect| rect.left == origin.x + start
+        && rect.right == origin.x + end
+        && rect.top > origin.y + font.height()));
+}
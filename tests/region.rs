@@ -0,0 +1,134 @@
+// Collects damage the way a window does between paints and checks which rects it ends up
+// repainting.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::geometry::Rect;
+use testwindowtabs::region::DirtyRegion;
+
+fn region(rects: &[Rect]) -> DirtyRegion {
+    let mut region = DirtyRegion::new();
+    for rect in rects {
+        region.add(*rect);
+    }
+    region
+}
+
+fn sorted(region: &DirtyRegion) -> Vec<Rect> {
+    let mut rects = region.rects().to_vec();
+    rects.sort_by_key(|rect| (rect.left, rect.top));
+    rects
+}
+
+#[test]
+fn empty_rects_add_nothing() {
+    let region = region(&[Rect::new(5, 5, 5, 10), Rect::default()]);
+    assert!(region.is_empty());
+    assert_eq!(region.bounds(), Rect::default());
+}
+
+#[test]
+fn contained_rects_are_dropped_either_way() {
+    let outer = Rect::new(0, 0, 100, 100);
+
+    let inner_last = region(&[outer, Rect::new(10, 10, 20, 20)]);
+    assert_eq!(inner_last.rects(), [outer]);
+
+    // Added first, the inner rects get swallowed by the bigger one
+    let inner_first = region(&[Rect::new(10, 10, 20, 20), Rect::new(50, 50, 60, 60), outer]);
+    assert_eq!(inner_first.rects(), [outer]);
+}
+
+#[test]
+fn touching_rects_merge() {
+    // Neighbouring tabs share an edge
+    let region = region(&[Rect::new(0, 0, 50, 40), Rect::new(50, 0, 100, 40)]);
+    assert_eq!(region.rects(), [Rect::new(0, 0, 100, 40)]);
+}
+
+#[test]
+fn distant_rects_stay_separate() {
+    let left = Rect::new(0, 0, 10, 10);
+    let right = Rect::new(200, 0, 210, 10);
+    let region = region(&[left, right]);
+
+    assert_eq!(sorted(&region), [left, right]);
+    assert_eq!(region.bounds(), Rect::new(0, 0, 210, 10));
+}
+
+#[test]
+fn merges_waste_at_most_a_quarter_of_the_painted_area() {
+    // 200 + 200 covered in a 500 union is exactly 25% more than needed
+    let tall = Rect::new(0, 0, 10, 20);
+    let at_limit = region(&[tall, Rect::new(10, 5, 20, 25)]);
+    assert_eq!(at_limit.rects(), [Rect::new(0, 0, 20, 25)]);
+
+    // One row lower the union needs 520, which is too much
+    let past_limit = region(&[tall, Rect::new(10, 6, 20, 26)]);
+    assert_eq!(past_limit.rects().len(), 2);
+
+    let square = Rect::new(0, 0, 10, 10);
+
+    // Overlap only counts once
+    let overlapping = region(&[square, Rect::new(5, 5, 15, 15)]);
+    assert_eq!(overlapping.rects().len(), 2);
+    let overlapping = region(&[square, Rect::new(5, 0, 15, 10)]);
+    assert_eq!(overlapping.rects(), [Rect::new(0, 0, 15, 10)]);
+}
+
+#[test]
+fn a_merge_can_pull_in_more_rects() {
+    // Neither outer rect touches the other, the one in the middle bridges them
+    let left = Rect::new(0, 0, 10, 10);
+    let right = Rect::new(20, 0, 30, 10);
+    let mut region = region(&[left, right]);
+    assert_eq!(region.rects().len(), 2);
+
+    region.add(Rect::new(10, 0, 20, 10));
+    assert_eq!(region.rects(), [Rect::new(0, 0, 30, 10)]);
+}
+
+#[test]
+fn too_many_rects_collapse_into_their_bounds() {
+    let mut region = DirtyRegion::new();
+    for n in 0..8 {
+        region.add(Rect::from_size(n * 100, n * 100, 10, 10));
+    }
+    assert_eq!(region.rects().len(), 8);
+
+    region.add(Rect::from_size(800, 800, 10, 10));
+    assert_eq!(region.rects(), [Rect::new(0, 0, 810, 810)]);
+}
+
+#[test]
+fn clip_keeps_the_dirty_parts_of_a_rect() {
+    let region = region(&[Rect::new(0, 0, 10, 10), Rect::new(100, 0, 110, 10)]);
+
+    let mut clipped = region.clip(&Rect::new(5, 5, 105, 50));
+    clipped.sort_by_key(|rect| rect.left);
+    assert_eq!(
+        clipped,
+        [Rect::new(5, 5, 10, 10), Rect::new(100, 5, 105, 10)]
+    );
+
+    assert!(region.clip(&Rect::new(20, 0, 90, 10)).is_empty());
+    assert!(region.intersects(&Rect::new(9, 9, 20, 20)));
+    assert!(!region.intersects(&Rect::new(10, 10, 20, 20)));
+}
+
+#[test]
+fn take_hands_out_the_region_and_starts_over() {
+    let mut region = region(&[Rect::new(0, 0, 10, 10)]);
+
+    let taken = region.take();
+    assert_eq!(taken.rects(), [Rect::new(0, 0, 10, 10)]);
+    assert!(region.is_empty());
+
+    region.add(Rect::new(50, 50, 60, 60));
+    let mut other = DirtyRegion::new();
+    other.add_region(&region);
+    other.add_region(&taken);
+    assert_eq!(other.rects().len(), 2);
+
+    other.clear();
+    assert!(other.is_empty());
+}
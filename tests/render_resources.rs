@@ -0,0 +1,224 @@
+// Loses the device at every step a real GPU can lose it, through a fake factory that fails on
+// cue, and checks what the window's renderer drops, recreates and reports.
+#![cfg(target_os = "linux")]
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Duration;
+
+use testwindowtabs::canvas::{Canvas, Color};
+use testwindowtabs::dpi::Dpi;
+use testwindowtabs::geometry::{Point, PointF, RectF, Size};
+use testwindowtabs::image::Image;
+use testwindowtabs::render_resources::{DeviceError, DeviceFactory, RenderDevice, RenderResources};
+
+const LOST: DeviceError = DeviceError::Lost;
+// DXGI_ERROR_DEVICE_HUNG, not something recreating the device fixes
+const FAILED: DeviceError = DeviceError::Failed(0x887a0006_u32 as i32);
+
+// What the next calls fail with, and what happened so far
+#[derive(Default)]
+struct Script {
+    create: VecDeque<DeviceError>,
+    end_draw: VecDeque<DeviceError>,
+    resize: VecDeque<DeviceError>,
+    created: Vec<(Size, Dpi)>,
+    alive: u32,
+    draws: u32,
+    dpi: Option<Dpi>,
+}
+
+type Shared = Rc<RefCell<Script>>;
+
+struct FakeFactory(Shared);
+
+impl DeviceFactory for FakeFactory {
+    type Device = FakeDevice;
+
+    fn create_device(&mut self, size: Size, dpi: Dpi) -> Result<FakeDevice, DeviceError> {
+        let mut script = self.0.borrow_mut();
+        if let Some(err) = script.create.pop_front() {
+            return Err(err);
+        }
+
+        script.created.push((size, dpi));
+        script.alive += 1;
+        Ok(FakeDevice(self.0.clone()))
+    }
+}
+
+struct FakeDevice(Shared);
+
+impl Drop for FakeDevice {
+    fn drop(&mut self) {
+        self.0.borrow_mut().alive -= 1;
+    }
+}
+
+impl RenderDevice for FakeDevice {
+    fn begin_draw(&mut self) {}
+
+    fn end_draw(&mut self) -> Result<(), DeviceError> {
+        let mut script = self.0.borrow_mut();
+        script.draws += 1;
+        script.end_draw.pop_front().map_or(Ok(()), Err)
+    }
+
+    fn resize(&mut self, _size: Size) -> Result<(), DeviceError> {
+        self.0.borrow_mut().resize.pop_front().map_or(Ok(()), Err)
+    }
+
+    fn set_dpi(&mut self, dpi: Dpi) {
+        self.0.borrow_mut().dpi = Some(dpi);
+    }
+}
+
+impl Canvas for FakeDevice {
+    fn dpi(&self) -> Dpi {
+        Dpi::DEFAULT
+    }
+
+    fn set_antialias(&mut self, _enabled: bool) {}
+    fn clear(&mut self, _color: Color) {}
+    fn fill_rect(&mut self, _rect: RectF, _color: Color) {}
+    fn stroke_rect(&mut self, _rect: RectF, _color: Color, _width: f32) {}
+    fn draw_line(&mut self, _from: PointF, _to: PointF, _color: Color, _width: f32) {}
+    fn fill_ellipse(&mut self, _center: PointF, _rx: f32, _ry: f32, _color: Color) {}
+    fn draw_image(&mut self, _image: &Image, _rect: RectF) {}
+    fn draw_text(&mut self, _text: &str, _origin: Point, _color: Color) {}
+    fn push_clip(&mut self, _rect: RectF) {}
+    fn pop_clip(&mut self) {}
+}
+
+const SIZE: Size = Size::new(500, 500);
+
+fn resources() -> (RenderResources<FakeFactory>, Shared) {
+    let script = Shared::default();
+    let resources = RenderResources::new(FakeFactory(script.clone()), SIZE, Dpi::DEFAULT);
+    (resources, script)
+}
+
+fn draw(resources: &mut RenderResources<FakeFactory>) -> Result<(), DeviceError> {
+    resources.draw(|_| ())
+}
+
+#[test]
+fn the_device_is_created_on_the_first_draw_and_kept() {
+    let (mut resources, script) = resources();
+    assert!(!resources.has_device());
+    assert!(script.borrow().created.is_empty());
+
+    draw(&mut resources).unwrap();
+    draw(&mut resources).unwrap();
+    assert!(resources.has_device());
+    assert_eq!(script.borrow().created, [(SIZE, Dpi::DEFAULT)]);
+    assert_eq!(script.borrow().draws, 2);
+
+    // Whatever the closure returns comes back out
+    assert_eq!(resources.draw(|canvas| canvas.dpi()), Ok(Dpi::DEFAULT));
+}
+
+#[test]
+fn a_device_lost_while_drawing_is_dropped_and_recreated_on_the_next_draw() {
+    let (mut resources, script) = resources();
+    draw(&mut resources).unwrap();
+
+    script.borrow_mut().end_draw.push_back(LOST);
+    assert_eq!(draw(&mut resources), Err(LOST));
+    assert!(!resources.has_device());
+    assert_eq!(script.borrow().alive, 0);
+    assert_eq!(resources.lost_count(), 1);
+    // A device existed, so the next draw can go ahead right away
+    assert_eq!(resources.retry_delay(), None);
+
+    draw(&mut resources).unwrap();
+    assert_eq!(script.borrow().created.len(), 2);
+    assert_eq!(script.borrow().alive, 1);
+    assert_eq!(resources.lost_count(), 1);
+}
+
+#[test]
+fn a_device_lost_while_resizing_is_recreated_at_the_new_size() {
+    let (mut resources, script) = resources();
+    draw(&mut resources).unwrap();
+
+    let bigger = Size::new(800, 600);
+    script.borrow_mut().resize.push_back(LOST);
+    assert_eq!(resources.resize(bigger), Err(LOST));
+    assert!(!resources.has_device());
+    assert_eq!(resources.lost_count(), 1);
+
+    // Resizing without a device doesn't create one
+    let biggest = Size::new(1024, 768);
+    resources.resize(biggest).unwrap();
+    assert_eq!(script.borrow().created.len(), 1);
+
+    draw(&mut resources).unwrap();
+    assert_eq!(script.borrow().created[1], (biggest, Dpi::DEFAULT));
+}
+
+#[test]
+fn failing_to_create_a_device_backs_off() {
+    let (mut resources, script) = resources();
+    script.borrow_mut().create.extend([LOST; 8]);
+
+    let mut delays = Vec::new();
+    for _ in 0..8 {
+        assert_eq!(draw(&mut resources), Err(LOST));
+        delays.push(resources.retry_delay().unwrap().as_millis());
+    }
+    assert_eq!(delays, [100, 200, 400, 800, 1600, 3200, 5000, 5000]);
+    assert_eq!(resources.lost_count(), 8);
+    assert_eq!(script.borrow().draws, 0);
+
+    // One that works starts over
+    draw(&mut resources).unwrap();
+    assert_eq!(resources.retry_delay(), None);
+    assert!(resources.has_device());
+}
+
+#[test]
+fn other_failures_keep_the_device() {
+    let (mut resources, script) = resources();
+    draw(&mut resources).unwrap();
+
+    script.borrow_mut().end_draw.push_back(FAILED);
+    assert_eq!(draw(&mut resources), Err(FAILED));
+    assert!(resources.has_device());
+    assert_eq!(resources.lost_count(), 0);
+    assert_eq!(resources.retry_delay(), None);
+
+    draw(&mut resources).unwrap();
+    assert_eq!(script.borrow().created.len(), 1);
+}
+
+#[test]
+fn the_dpi_reaches_the_current_and_the_next_device() {
+    let (mut resources, script) = resources();
+    let dpi = Dpi::new(144);
+
+    // No device yet, the one created next starts out at the new DPI
+    resources.set_dpi(dpi);
+    assert_eq!(script.borrow().dpi, None);
+    draw(&mut resources).unwrap();
+    assert_eq!(script.borrow().created[0], (SIZE, dpi));
+
+    resources.set_dpi(Dpi::new(192));
+    assert_eq!(script.borrow().dpi, Some(Dpi::new(192)));
+}
+
+#[test]
+fn release_drops_the_device_until_the_next_draw() {
+    let (mut resources, script) = resources();
+    draw(&mut resources).unwrap();
+
+    resources.release();
+    assert!(!resources.has_device());
+    assert_eq!(script.borrow().alive, 0);
+    assert_eq!(resources.lost_count(), 0);
+
+    draw(&mut resources).unwrap();
+    assert_eq!(script.borrow().alive, 1);
+    assert_eq!(resources.retry_delay(), None::<Duration>);
+}
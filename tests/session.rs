@@ -0,0 +1,403 @@
+// Reads and writes session files, including ones of older versions, and puts saved windows
+// back on the monitors there are now.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::closed_tabs::ClosedTab;
+use testwindowtabs::geometry::Rect;
+use testwindowtabs::session::{
+    GroupSession, Placement, Session, SessionError, TabSession, WindowSession, SESSION_VERSION,
+};
+
+const LEFT_MONITOR: Rect = Rect::new(0, 0, 1920, 1040);
+const RIGHT_MONITOR: Rect = Rect::new(1920, 0, 3840, 1040);
+
+fn tab(title: &str, state: &str) -> TabSession {
+    TabSession {
+        title: String::from(title),
+        state: String::from(state),
+        pinned: false,
+    }
+}
+
+fn placement(restored: Rect) -> Placement {
+    Placement {
+        monitor: Some(LEFT_MONITOR),
+        restored,
+        maximized: false,
+    }
+}
+
+fn sample() -> Session {
+    Session {
+        windows: vec![
+            WindowSession {
+                placement: Placement {
+                    monitor: Some(RIGHT_MONITOR),
+                    restored: Rect::from_size(2000, 100, 800, 600),
+                    maximized: true,
+                },
+                tabs: vec![tab("Notes", "3"), tab("", ""), tab("Third", "")],
+                active: Some(1),
+                groups: vec![
+                    GroupSession {
+                        name: String::from("Research \"2\""),
+                        color: 3,
+                        collapsed: true,
+                        tabs: vec![1, 2],
+                    },
+                    GroupSession {
+                        name: String::new(),
+                        color: 0,
+                        collapsed: false,
+                        tabs: vec![],
+                    },
+                ],
+            },
+            WindowSession {
+                placement: placement(Rect::from_size(100, 100, 600, 400)),
+                tabs: vec![],
+                active: None,
+                groups: vec![],
+            },
+        ],
+        closed_tabs: vec![
+            ClosedTab {
+                tab: tab("Gone", "7"),
+                index: 2,
+                window: 0,
+            },
+            ClosedTab {
+                tab: tab("also \"gone\"", ""),
+                index: 0,
+                window: 1,
+            },
+        ],
+    }
+}
+
+fn error_line(text: &str) -> usize {
+    match Session::parse(text) {
+        Err(SessionError::Parse { line, .. }) => line,
+        other => panic!("expected a parse error, got {:?}", other),
+    }
+}
+
+#[test]
+fn a_session_survives_a_round_trip() {
+    let session = sample();
+    assert_eq!(Session::parse(&session.to_text()).unwrap(), session);
+}
+
+#[test]
+fn titles_and_states_keep_quotes_backslashes_and_newlines() {
+    let session = Session {
+        windows: vec![WindowSession {
+            tabs: vec![
+                tab("say \"hi\"", "C:\\Users\\me"),
+                tab("two\nlines", "{\"scroll\": 12}\n"),
+            ],
+            active: Some(0),
+            ..WindowSession::default()
+        }],
+        ..Session::default()
+    };
+
+    let text = session.to_text();
+    assert_eq!(
+        text.lines().filter(|line| line.starts_with("tab")).count(),
+        2
+    );
+    assert_eq!(Session::parse(&text).unwrap(), session);
+}
+
+#[test]
+fn saving_and_loading_goes_through_the_file() {
+    let dir = std::env::temp_dir().join(format!("testwindowtabs-session-{}", std::process::id()));
+    let path = dir.join("nested").join("session");
+
+    assert_eq!(Session::load(&path).unwrap(), Session::default());
+
+    sample().save(&path).unwrap();
+    let loaded = Session::load(&path);
+    let _ = std::fs::remove_dir_all(&dir);
+    assert_eq!(loaded.unwrap(), sample());
+}
+
+#[test]
+fn the_file_starts_with_the_current_version() {
+    let text = Session::default().to_text();
+    assert_eq!(
+        text,
+        format!("testwindowtabs session {}\n", SESSION_VERSION)
+    );
+}
+
+#[test]
+fn version_1_files_are_migrated() {
+    let text = "testwindowtabs session 1\n\
+                window\n\
+                bounds 10 20 610 420\n\
+                maximized false\n\
+                active 0\n\
+                tab \"Old\"\n\
+                end\n";
+
+    let session = Session::parse(text).unwrap();
+    assert_eq!(
+        session.windows,
+        [WindowSession {
+            placement: Placement {
+                monitor: None,
+                restored: Rect::new(10, 20, 610, 420),
+                maximized: false,
+            },
+            tabs: vec![tab("Old", "")],
+            active: Some(0),
+            groups: vec![],
+        }]
+    );
+}
+
+#[test]
+fn version_2_files_have_no_closed_tabs() {
+    let text = "testwindowtabs session 2\nwindow\ntab \"a\" \"b\"\nend\n";
+    let session = Session::parse(text).unwrap();
+
+    assert_eq!(session.windows[0].tabs, [tab("a", "b")]);
+    assert!(session.closed_tabs.is_empty());
+}
+
+#[test]
+fn closed_tabs_sit_outside_of_the_windows() {
+    let text = "testwindowtabs session 3\n\
+                closed 0 1 \"first\" \"\"\n\
+                window\n\
+                end\n\
+                closed 1 4 \"second\" \"state\"\n";
+
+    let session = Session::parse(text).unwrap();
+    assert_eq!(session.windows.len(), 1);
+    assert_eq!(
+        session.closed_tabs,
+        [
+            ClosedTab {
+                tab: tab("first", ""),
+                index: 1,
+                window: 0,
+            },
+            ClosedTab {
+                tab: tab("second", "state"),
+                index: 4,
+                window: 1,
+            },
+        ]
+    );
+}
+
+#[test]
+fn version_3_tabs_are_not_pinned() {
+    let text = "testwindowtabs session 3\n\
+                window\n\
+                tab \"a\" \"\"\n\
+                end\n\
+                closed 0 1 \"b\" \"\"\n";
+
+    let session = Session::parse(text).unwrap();
+    assert_eq!(session.windows[0].tabs, [tab("a", "")]);
+    assert_eq!(session.closed_tabs[0].tab, tab("b", ""));
+}
+
+#[test]
+fn pinned_tabs_stay_pinned() {
+    let mut session = sample();
+    session.windows[0].tabs[0].pinned = true;
+    session.closed_tabs[1].tab.pinned = true;
+
+    assert_eq!(Session::parse(&session.to_text()).unwrap(), session);
+}
+
+#[test]
+fn version_4_files_have_no_groups() {
+    let text = "testwindowtabs session 4\nwindow\ntab \"a\" \"\" true\nend\n";
+    let session = Session::parse(text).unwrap();
+
+    assert!(session.windows[0].tabs[0].pinned);
+    assert!(session.windows[0].groups.is_empty());
+}
+
+#[test]
+fn groups_lose_tabs_past_the_end() {
+    let text = "testwindowtabs session 5\n\
+                window\n\
+                tab \"a\" \"\" false\n\
+                tab \"b\" \"\" false\n\
+                group \"g\" 2 false 1 2 7\n\
+                end\n";
+    let session = Session::parse(text).unwrap();
+
+    assert_eq!(
+        session.windows[0].groups,
+        [GroupSession {
+            name: String::from("g"),
+            color: 2,
+            collapsed: false,
+            tabs: vec![1],
+        }]
+    );
+    assert_eq!(
+        error_line("testwindowtabs session 5\nwindow\ngroup \"g\" 0 false x\nend\n"),
+        3
+    );
+}
+
+#[test]
+fn newer_versions_are_refused() {
+    let text = format!("testwindowtabs session {}\n", SESSION_VERSION + 1);
+    assert!(matches!(
+        Session::parse(&text),
+        Err(SessionError::UnsupportedVersion(version)) if version == SESSION_VERSION + 1
+    ));
+}
+
+#[test]
+fn unknown_records_are_skipped() {
+    let text = "testwindowtabs session 2\nwindow\nzoom 150\ntab \"a\" \"b\"\nend\n";
+    let session = Session::parse(text).unwrap();
+    assert_eq!(session.windows[0].tabs, [tab("a", "b")]);
+}
+
+#[test]
+fn an_active_index_past_the_tabs_is_dropped() {
+    let text = "testwindowtabs session 2\nwindow\nactive 4\ntab \"a\" \"\"\nend\n";
+    assert_eq!(Session::parse(text).unwrap().windows[0].active, None);
+}
+
+#[test]
+fn broken_files_report_the_line() {
+    assert_eq!(error_line("some other file\n"), 1);
+    assert_eq!(error_line("testwindowtabs session 0\n"), 1);
+    assert_eq!(
+        error_line("testwindowtabs session 2\nwindow\nbounds 1 2 x 4\nend\n"),
+        3
+    );
+    assert_eq!(
+        error_line("testwindowtabs session 2\nwindow\ntab \"open\nend\n"),
+        3
+    );
+    assert_eq!(error_line("testwindowtabs session 2\nend\n"), 2);
+    assert_eq!(error_line("testwindowtabs session 2\nwindow\nwindow\n"), 3);
+    assert_eq!(
+        error_line("testwindowtabs session 2\nwindow\nbounds 0 0 1 1\n"),
+        3
+    );
+    assert_eq!(
+        error_line("testwindowtabs session 3\nclosed -1 0 \"a\" \"\"\n"),
+        2
+    );
+}
+
+#[test]
+fn rects_that_could_not_be_on_a_screen_are_refused() {
+    for rect in [
+        "0 0 2147483647 100",
+        "-2147483648 0 100 100",
+        "0 0 100 99999999",
+        "100 0 50 100",
+        "0 100 100 50",
+    ] {
+        let text = format!("testwindowtabs session 2\nwindow\nbounds {}\nend\n", rect);
+        assert_eq!(error_line(&text), 3, "{}", rect);
+
+        let text = format!("testwindowtabs session 2\nwindow\nmonitor {}\nend\n", rect);
+        assert_eq!(error_line(&text), 3, "{}", rect);
+    }
+
+    // Empty and off screen is fine, the window is put back on a monitor
+    let text = "testwindowtabs session 2\nwindow\nbounds -5000 9000 -5000 9000\nend\n";
+    let placement = Session::parse(text).unwrap().windows[0].placement;
+    assert_eq!(
+        placement.clamp_to(&[LEFT_MONITOR]).monitor,
+        Some(LEFT_MONITOR)
+    );
+}
+
+#[test]
+fn a_mangled_file_loads_as_an_error() {
+    let dir = std::env::temp_dir().join(format!("testwindowtabs-mangled-{}", std::process::id()));
+    let path = dir.join("session");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        &path,
+        "testwindowtabs session 2\nwindow\nmonitor 0 0 1920 1040\nbounds 2147483647 2147483647 -1 -1\nend\n",
+    )
+    .unwrap();
+
+    let loaded = Session::load(&path);
+    let _ = std::fs::remove_dir_all(&dir);
+    match loaded {
+        Err(SessionError::Parse { line: 4, .. }) => {}
+        other => panic!("expected a parse error on line 4, got {:?}", other),
+    }
+}
+
+#[test]
+fn an_empty_file_is_an_empty_session() {
+    assert_eq!(Session::parse("").unwrap(), Session::default());
+}
+
+#[test]
+fn a_visible_window_stays_where_it_was() {
+    let saved = placement(Rect::from_size(100, 100, 600, 400));
+    assert_eq!(saved.clamp_to(&[LEFT_MONITOR, RIGHT_MONITOR]), saved);
+}
+
+#[test]
+fn a_window_goes_to_the_monitor_it_overlaps_the_most() {
+    let saved = placement(Rect::from_size(1800, 100, 600, 400));
+    let clamped = saved.clamp_to(&[LEFT_MONITOR, RIGHT_MONITOR]);
+
+    assert_eq!(clamped.monitor, Some(RIGHT_MONITOR));
+    assert_eq!(clamped.restored, Rect::from_size(1920, 100, 600, 400));
+}
+
+#[test]
+fn a_window_off_screen_goes_back_to_its_monitor() {
+    let saved = Placement {
+        monitor: Some(RIGHT_MONITOR),
+        restored: Rect::from_size(5000, 2000, 600, 400),
+        maximized: true,
+    };
+    let clamped = saved.clamp_to(&[LEFT_MONITOR, RIGHT_MONITOR]);
+
+    assert_eq!(clamped.monitor, Some(RIGHT_MONITOR));
+    assert_eq!(clamped.restored, Rect::from_size(3240, 640, 600, 400));
+    assert!(clamped.maximized);
+}
+
+#[test]
+fn a_window_whose_monitor_is_gone_goes_to_the_first_one() {
+    let saved = Placement {
+        monitor: Some(RIGHT_MONITOR),
+        restored: Rect::from_size(2000, 100, 600, 400),
+        maximized: false,
+    };
+    let clamped = saved.clamp_to(&[LEFT_MONITOR]);
+
+    assert_eq!(clamped.monitor, Some(LEFT_MONITOR));
+    assert_eq!(clamped.restored, Rect::from_size(1320, 100, 600, 400));
+}
+
+#[test]
+fn a_window_larger_than_the_monitor_is_shrunk() {
+    let small = Rect::new(0, 0, 1024, 728);
+    let saved = placement(Rect::from_size(-50, -50, 1920, 1040));
+    let clamped = saved.clamp_to(&[small]);
+
+    assert_eq!(clamped.restored, small);
+}
+
+#[test]
+fn without_monitors_the_window_is_left_alone() {
+    let saved = placement(Rect::from_size(5000, 5000, 600, 400));
+    assert_eq!(saved.clamp_to(&[]), saved);
+}
@@ -0,0 +1,114 @@
+// Which items a tab's menu has, and which of them apply to the tab.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::menu::MenuEntry;
+use testwindowtabs::tab_menu::{tab_menu, TabAction};
+use testwindowtabs::tab_model::{TabId, TabModel};
+
+fn open(count: usize) -> (TabModel, Vec<TabId>) {
+    let mut model = TabModel::new();
+    let tabs = (0..count).map(|_| model.add().0).collect();
+    (model, tabs)
+}
+
+// The actions of the enabled items, in order.
+fn enabled(menu: &[MenuEntry<TabAction>]) -> Vec<TabAction> {
+    menu.iter()
+        .filter_map(|entry| entry.action())
+        .copied()
+        .collect()
+}
+
+#[test]
+fn the_menu_has_the_standard_items_in_order() {
+    let (model, tabs) = open(3);
+    let labels = tab_menu(&model, tabs[1], false)
+        .iter()
+        .map(|entry| entry.text().unwrap_or_else(|| String::from("-")))
+        .collect::<Vec<_>>();
+
+    assert_eq!(
+        labels,
+        [
+            "Close",
+            "Close others",
+            "Close to the right",
+            "-",
+            "Duplicate",
+            "Pin",
+            "Rename",
+            "Move to new window",
+            "-",
+            "Reopen closed tab",
+        ]
+    );
+}
+
+#[test]
+fn every_item_has_its_own_key() {
+    let (model, tabs) = open(3);
+    let mut keys = tab_menu(&model, tabs[1], true)
+        .iter()
+        .filter(|entry| entry.label().is_some())
+        .map(|entry| entry.mnemonic().unwrap())
+        .collect::<Vec<_>>();
+    let len = keys.len();
+    keys.sort();
+    keys.dedup();
+
+    assert_eq!(keys.len(), len);
+}
+
+#[test]
+fn a_lone_tab_has_nothing_to_close_besides_itself() {
+    let (model, tabs) = open(1);
+
+    assert_eq!(
+        enabled(&tab_menu(&model, tabs[0], false)),
+        [
+            TabAction::Close,
+            TabAction::Duplicate,
+            TabAction::Pin,
+            TabAction::Rename,
+        ]
+    );
+}
+
+#[test]
+fn the_last_tab_has_nothing_to_its_right() {
+    let (model, tabs) = open(3);
+    let menu = enabled(&tab_menu(&model, tabs[2], false));
+
+    assert!(menu.contains(&TabAction::CloseOthers));
+    assert!(!menu.contains(&TabAction::CloseToTheRight));
+    assert!(enabled(&tab_menu(&model, tabs[1], false)).contains(&TabAction::CloseToTheRight));
+}
+
+#[test]
+fn pinned_tabs_offer_to_unpin_and_are_spared_by_the_others() {
+    let (mut model, tabs) = open(3);
+    model.pin(tabs[0]);
+    model.pin(tabs[1]);
+
+    let menu = enabled(&tab_menu(&model, tabs[0], false));
+    assert!(menu.contains(&TabAction::Unpin));
+    assert!(!menu.contains(&TabAction::Pin));
+
+    // Only the unpinned tab would close, either way
+    assert_eq!(model.others(tabs[0]), [tabs[2]]);
+    assert_eq!(model.to_the_right(tabs[0]), [tabs[2]]);
+
+    // With just pinned tabs around there is nothing to close to the right
+    model.pin(tabs[2]);
+    let menu = enabled(&tab_menu(&model, tabs[0], false));
+    assert!(!menu.contains(&TabAction::CloseOthers));
+    assert!(!menu.contains(&TabAction::CloseToTheRight));
+}
+
+#[test]
+fn reopening_needs_a_closed_tab() {
+    let (model, tabs) = open(2);
+
+    assert!(!enabled(&tab_menu(&model, tabs[0], false)).contains(&TabAction::ReopenClosed));
+    assert!(enabled(&tab_menu(&model, tabs[0], true)).contains(&TabAction::ReopenClosed));
+}
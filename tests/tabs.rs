@@ -0,0 +1,673 @@
+// Replays the tab model's events the way the window does against its content panels, and
+// checks which content is shown and what it got told, in order.
+#![cfg(target_os = "linux")]
+
+use std::collections::BTreeMap;
+
+use testwindowtabs::tab_model::{GroupId, TabEvent, TabId, TabModel};
+
+#[derive(Default)]
+struct Panel {
+    visible: bool,
+    // Stands in for whatever state the content keeps while hidden
+    activations: u32,
+}
+
+#[derive(Default)]
+struct Panels {
+    panels: BTreeMap<TabId, Panel>,
+    hooks: Vec<String>,
+}
+
+impl Panels {
+    fn apply(&mut self, events: Vec<TabEvent>) {
+        for event in events {
+            match event {
+                TabEvent::Opened(tab) => {
+                    self.panels.insert(tab, Panel::default());
+                }
+                TabEvent::Activated(tab) => {
+                    let panel = self.panels.get_mut(&tab).unwrap();
+                    panel.visible = true;
+                    panel.activations += 1;
+                    self.hooks.push(format!("activated {}", tab));
+                }
+                TabEvent::Deactivated(tab) => {
+                    self.panels.get_mut(&tab).unwrap().visible = false;
+                    self.hooks.push(format!("deactivated {}", tab));
+                }
+                TabEvent::Closing(tab) => {
+                    assert!(self.panels.contains_key(&tab));
+                    self.hooks.push(format!("closing {}", tab));
+                }
+                TabEvent::Closed(tab) => {
+                    self.panels.remove(&tab).unwrap();
+                    self.hooks.push(format!("closed {}", tab));
+                }
+                // Where a tab is doesn't concern its content
+                TabEvent::Moved(tab)
+                | TabEvent::Pinned(tab)
+                | TabEvent::Unpinned(tab)
+                | TabEvent::Grouped(tab)
+                | TabEvent::Ungrouped(tab) => {
+                    assert!(self.panels.contains_key(&tab));
+                }
+            }
+        }
+    }
+
+    fn visible(&self) -> Vec<TabId> {
+        self.panels
+            .iter()
+            .filter(|(_, panel)| panel.visible)
+            .map(|(tab, _)| *tab)
+            .collect()
+    }
+
+    fn take_hooks(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.hooks)
+    }
+}
+
+fn open(model: &mut TabModel, panels: &mut Panels, count: usize) -> Vec<TabId> {
+    let tabs = (0..count)
+        .map(|_| {
+            let (tab, events) = model.add();
+            panels.apply(events);
+            tab
+        })
+        .collect();
+    panels.take_hooks();
+    tabs
+}
+
+// Xorshift, so the property tests go through the same changes on every run.
+struct Rng(u32);
+
+impl Rng {
+    // Something in `0..bound`, or 0 when that is empty.
+    fn below(&mut self, bound: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 17;
+        self.0 ^= self.0 << 5;
+        self.0 as usize % bound.max(1)
+    }
+}
+
+#[test]
+fn new_tabs_take_over_activation() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+
+    let (first, events) = model.add();
+    assert_eq!(
+        events,
+        [TabEvent::Opened(first), TabEvent::Activated(first)]
+    );
+    panels.apply(events);
+
+    let (second, events) = model.add();
+    panels.apply(events);
+
+    assert_eq!(model.active(), Some(second));
+    assert_eq!(panels.visible(), [second]);
+    assert_eq!(
+        panels.take_hooks(),
+        ["activated 0", "deactivated 0", "activated 1"]
+    );
+}
+
+#[test]
+fn hidden_content_keeps_its_state() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 2);
+
+    panels.apply(model.activate(tabs[0]));
+    assert_eq!(panels.visible(), [tabs[0]]);
+    assert_eq!(panels.take_hooks(), ["deactivated 1", "activated 0"]);
+
+    panels.apply(model.activate(tabs[1]));
+    assert_eq!(panels.visible(), [tabs[1]]);
+    assert_eq!(panels.panels[&tabs[0]].activations, 2);
+    assert_eq!(panels.panels[&tabs[1]].activations, 2);
+}
+
+#[test]
+fn activating_the_active_tab_changes_nothing() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 2);
+
+    assert!(model.activate(tabs[1]).is_empty());
+    assert!(model.activate(42).is_empty());
+    assert_eq!(model.active(), Some(tabs[1]));
+}
+
+#[test]
+fn closing_the_active_tab_activates_its_right_neighbour() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+
+    panels.apply(model.activate(tabs[1]));
+    panels.take_hooks();
+
+    panels.apply(model.close(tabs[1]));
+    assert_eq!(
+        panels.take_hooks(),
+        ["closing 1", "deactivated 1", "activated 2", "closed 1"]
+    );
+    assert_eq!(panels.visible(), [tabs[2]]);
+    assert_eq!(model.tabs(), [tabs[0], tabs[2]]);
+}
+
+#[test]
+fn closing_the_last_tab_activates_its_left_neighbour() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+
+    panels.apply(model.close(tabs[2]));
+    assert_eq!(
+        panels.take_hooks(),
+        ["closing 2", "deactivated 2", "activated 1", "closed 2"]
+    );
+    assert_eq!(panels.visible(), [tabs[1]]);
+}
+
+#[test]
+fn closing_an_inactive_tab_leaves_the_active_one_alone() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+
+    panels.apply(model.close(tabs[0]));
+    assert_eq!(panels.take_hooks(), ["closing 0", "closed 0"]);
+    assert_eq!(panels.visible(), [tabs[2]]);
+    assert_eq!(model.active(), Some(tabs[2]));
+
+    assert!(model.close(tabs[0]).is_empty());
+}
+
+#[test]
+fn closing_the_only_tab_leaves_nothing_active() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 1);
+
+    panels.apply(model.close(tabs[0]));
+    assert_eq!(
+        panels.take_hooks(),
+        ["closing 0", "deactivated 0", "closed 0"]
+    );
+    assert_eq!(model.active(), None);
+    assert!(panels.panels.is_empty());
+}
+
+#[test]
+fn closing_all_tabs_activates_none_of_them() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+
+    panels.apply(model.activate(tabs[0]));
+    panels.take_hooks();
+
+    panels.apply(model.close_all());
+    assert_eq!(
+        panels.take_hooks(),
+        [
+            "closing 0",
+            "deactivated 0",
+            "closed 0",
+            "closing 1",
+            "closed 1",
+            "closing 2",
+            "closed 2"
+        ]
+    );
+    assert!(model.tabs().is_empty());
+    assert_eq!(model.active(), None);
+}
+
+#[test]
+fn inserted_tabs_go_where_they_were_asked_to() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+
+    let (middle, events) = model.insert(1);
+    panels.apply(events);
+    assert_eq!(model.tabs(), [tabs[0], middle, tabs[1], tabs[2]]);
+    assert_eq!(model.index_of(middle), Some(1));
+    assert_eq!(panels.visible(), [middle]);
+
+    // Past the end they go last
+    let (last, _) = model.insert(100);
+    assert_eq!(model.index_of(last), Some(4));
+    assert_eq!(model.index_of(99), None);
+}
+
+// Pinned tabs lead the tab order, whatever happens to the others.
+fn assert_pinned_first(model: &TabModel) {
+    let pinned = model.pinned().len();
+    assert_eq!(&model.tabs()[..pinned], model.pinned());
+    for (index, tab) in model.tabs().iter().enumerate() {
+        assert_eq!(model.is_pinned(*tab), index < pinned);
+    }
+}
+
+#[test]
+fn pinning_moves_a_tab_to_the_end_of_the_pinned_ones() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 4);
+
+    assert_eq!(model.pin(tabs[2]), [TabEvent::Pinned(tabs[2])]);
+    assert_eq!(model.pin(tabs[3]), [TabEvent::Pinned(tabs[3])]);
+    assert_eq!(model.tabs(), [tabs[2], tabs[3], tabs[0], tabs[1]]);
+    assert_eq!(model.pinned(), [tabs[2], tabs[3]]);
+    assert_pinned_first(&model);
+
+    // Pinning twice changes nothing, and neither does pinning a stranger
+    assert!(model.pin(tabs[2]).is_empty());
+    assert!(model.pin(42).is_empty());
+}
+
+#[test]
+fn unpinning_moves_a_tab_to_the_start_of_the_others() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+    model.pin(tabs[1]);
+    model.pin(tabs[2]);
+
+    assert_eq!(model.unpin(tabs[1]), [TabEvent::Unpinned(tabs[1])]);
+    assert_eq!(model.tabs(), [tabs[2], tabs[1], tabs[0]]);
+    assert_eq!(model.pinned(), [tabs[2]]);
+    assert!(model.unpin(tabs[1]).is_empty());
+    assert_pinned_first(&model);
+}
+
+#[test]
+fn moving_keeps_a_tab_on_its_side_of_the_pinned_tabs() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 5);
+    model.pin(tabs[0]);
+    model.pin(tabs[1]);
+
+    // An unpinned tab stops right after the pinned ones
+    assert_eq!(model.move_tab(tabs[4], 0), [TabEvent::Moved(tabs[4])]);
+    assert_eq!(model.tabs(), [tabs[0], tabs[1], tabs[4], tabs[2], tabs[3]]);
+
+    // And a pinned one right before the others
+    assert_eq!(model.move_tab(tabs[0], 10), [TabEvent::Moved(tabs[0])]);
+    assert_eq!(model.tabs(), [tabs[1], tabs[0], tabs[4], tabs[2], tabs[3]]);
+
+    // Already as far as it goes
+    assert!(model.move_tab(tabs[0], 4).is_empty());
+    assert!(model.move_tab(tabs[3], 100).is_empty());
+    assert_pinned_first(&model);
+}
+
+#[test]
+fn new_tabs_never_go_among_the_pinned_ones() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 2);
+    model.pin(tabs[0]);
+    model.pin(tabs[1]);
+
+    let (tab, _) = model.insert(0);
+    assert_eq!(model.tabs(), [tabs[0], tabs[1], tab]);
+    assert!(!model.is_pinned(tab));
+    assert_pinned_first(&model);
+}
+
+#[test]
+fn other_tabs_leave_out_the_pinned_ones() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 4);
+    model.pin(tabs[0]);
+
+    assert_eq!(model.others(tabs[2]), [tabs[1], tabs[3]]);
+    // Asked on a pinned tab it still spares the pinned ones
+    assert_eq!(model.others(tabs[0]), [tabs[1], tabs[2], tabs[3]]);
+}
+
+#[test]
+fn closing_pinned_tabs_keeps_the_rest_in_place() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 4);
+    model.pin(tabs[0]);
+    model.pin(tabs[1]);
+
+    panels.apply(model.close(tabs[0]));
+    assert_eq!(model.pinned(), [tabs[1]]);
+    assert_pinned_first(&model);
+
+    panels.apply(model.close_all());
+    assert!(model.pinned().is_empty());
+    let (tab, _) = model.add();
+    assert!(!model.is_pinned(tab));
+}
+
+#[test]
+fn any_sequence_of_changes_keeps_the_pinned_tabs_first() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let mut rng = Rng(0x2545_f491);
+
+    for _ in 0..2000 {
+        let tabs = model.tabs().to_vec();
+        let tab = tabs.get(rng.below(tabs.len() + 1)).copied().unwrap_or(99);
+        let events = match rng.below(6) {
+            0 => model.insert(rng.below(8)).1,
+            1 => model.pin(tab),
+            2 => model.unpin(tab),
+            3 => model.move_tab(tab, rng.below(8)),
+            4 if tabs.len() > 3 => model.close(tab),
+            _ => model.activate(tab),
+        };
+        panels.apply(events);
+        assert_pinned_first(&model);
+    }
+}
+
+// A group's tabs sit next to each other, pinned tabs stay out of groups and a group is gone
+// with its last tab.
+fn assert_groups_hold(model: &TabModel, created: GroupId) {
+    let tabs = model.tabs();
+    for group in 0..created {
+        let members = tabs
+            .iter()
+            .enumerate()
+            .filter(|(_, tab)| model.group_of(**tab) == Some(group))
+            .map(|(index, _)| index)
+            .collect::<Vec<_>>();
+
+        if let (Some(first), Some(last)) = (members.first(), members.last()) {
+            assert_eq!(last - first + 1, members.len(), "group {} is split", group);
+            assert!(*first >= model.pinned().len());
+            assert!(model.group(group).is_some());
+            assert_eq!(model.group_tabs(group), &tabs[*first..=*last]);
+        } else {
+            assert!(model.group(group).is_none(), "group {} is empty", group);
+        }
+    }
+
+    let mut groups = model.groups();
+    groups.dedup();
+    assert_eq!(groups, model.groups());
+}
+
+#[test]
+fn a_group_gathers_its_tabs_where_the_first_one_is() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 5);
+
+    let (group, events) = model.create_group(&[tabs[3], tabs[1]], "work").unwrap();
+    assert_eq!(
+        events,
+        [
+            TabEvent::Moved(tabs[3]),
+            TabEvent::Grouped(tabs[1]),
+            TabEvent::Grouped(tabs[3]),
+        ]
+    );
+    assert_eq!(model.tabs(), [tabs[0], tabs[1], tabs[3], tabs[2], tabs[4]]);
+    assert_eq!(model.group_tabs(group), [tabs[1], tabs[3]]);
+    assert_eq!(model.group(group).unwrap().name, "work");
+    assert_groups_hold(&model, group + 1);
+}
+
+#[test]
+fn new_groups_take_the_next_color() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 2);
+
+    let (first, _) = model.create_group(&[tabs[0]], "a").unwrap();
+    let (second, _) = model.create_group(&[tabs[1]], "b").unwrap();
+    assert_ne!(
+        model.group(first).unwrap().color,
+        model.group(second).unwrap().color
+    );
+
+    assert!(model.set_group_color(first, 7));
+    assert_eq!(model.group(first).unwrap().color, 7);
+    assert!(model.rename_group(first, "c"));
+    assert_eq!(model.group(first).unwrap().name, "c");
+    assert!(!model.rename_group(42, "nope"));
+}
+
+#[test]
+fn pinned_tabs_stay_out_of_groups() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+    model.pin(tabs[0]);
+
+    assert!(model.create_group(&[tabs[0]], "none").is_none());
+    let (group, _) = model.create_group(&[tabs[0], tabs[1]], "some").unwrap();
+    assert_eq!(model.group_tabs(group), [tabs[1]]);
+    assert!(model.add_to_group(tabs[0], group).is_empty());
+
+    // Pinning a grouped tab takes it out, and its group with it once empty
+    assert_eq!(
+        model.pin(tabs[1]),
+        [TabEvent::Ungrouped(tabs[1]), TabEvent::Pinned(tabs[1])]
+    );
+    assert!(model.group(group).is_none());
+    assert!(model.groups().is_empty());
+}
+
+#[test]
+fn a_tab_opened_inside_a_group_joins_it() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+    let (group, _) = model.create_group(&[tabs[0], tabs[1]], "g").unwrap();
+
+    let (inside, events) = model.insert(1);
+    assert!(events.contains(&TabEvent::Grouped(inside)));
+    assert_eq!(model.group_of(inside), Some(group));
+
+    // At either end it stays out
+    let (after, _) = model.insert(3);
+    assert_eq!(model.group_of(after), None);
+    let (before, _) = model.insert(0);
+    assert_eq!(model.group_of(before), None);
+    assert_groups_hold(&model, group + 1);
+}
+
+#[test]
+fn moving_a_tab_in_or_out_of_a_group_changes_its_group() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 5);
+    let (group, _) = model
+        .create_group(&[tabs[1], tabs[2], tabs[3]], "g")
+        .unwrap();
+
+    // Within the group it stays
+    assert_eq!(model.move_tab(tabs[1], 3), [TabEvent::Moved(tabs[1])]);
+    assert_eq!(model.group_of(tabs[1]), Some(group));
+
+    // Into the middle of it joins
+    assert_eq!(
+        model.move_tab(tabs[4], 2),
+        [TabEvent::Moved(tabs[4]), TabEvent::Grouped(tabs[4])]
+    );
+
+    // Away from it leaves
+    assert_eq!(
+        model.move_tab(tabs[2], 0),
+        [TabEvent::Moved(tabs[2]), TabEvent::Ungrouped(tabs[2])]
+    );
+    assert_eq!(model.group_tabs(group), [tabs[4], tabs[3], tabs[1]]);
+    assert_groups_hold(&model, group + 1);
+}
+
+#[test]
+fn a_tab_taken_out_of_the_middle_goes_after_its_group() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 4);
+    let (group, _) = model.create_group(&tabs[..3], "g").unwrap();
+
+    assert_eq!(
+        model.remove_from_group(tabs[1]),
+        [TabEvent::Moved(tabs[1]), TabEvent::Ungrouped(tabs[1])]
+    );
+    assert_eq!(model.tabs(), [tabs[0], tabs[2], tabs[1], tabs[3]]);
+
+    // From the end it stays put
+    assert_eq!(
+        model.remove_from_group(tabs[2]),
+        [TabEvent::Ungrouped(tabs[2])]
+    );
+    assert_eq!(
+        model.add_to_group(tabs[3], group),
+        [TabEvent::Moved(tabs[3]), TabEvent::Grouped(tabs[3]),]
+    );
+    assert_eq!(model.group_tabs(group), [tabs[0], tabs[3]]);
+    assert_groups_hold(&model, group + 1);
+
+    model.ungroup(group);
+    assert!(model.group(group).is_none());
+    assert_eq!(model.tabs(), [tabs[0], tabs[3], tabs[2], tabs[1]]);
+}
+
+#[test]
+fn a_group_moves_as_a_whole_and_never_into_another() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 6);
+    let (first, _) = model.create_group(&tabs[0..2], "first").unwrap();
+    let (second, _) = model.create_group(&tabs[3..6], "second").unwrap();
+
+    assert_eq!(
+        model.move_group(first, 1),
+        [TabEvent::Moved(tabs[0]), TabEvent::Moved(tabs[1])]
+    );
+    assert_eq!(
+        model.tabs(),
+        [tabs[2], tabs[0], tabs[1], tabs[3], tabs[4], tabs[5]]
+    );
+
+    // The middle of the other group snaps to its closer end
+    model.move_group(first, 3);
+    assert_eq!(
+        model.tabs(),
+        [tabs[2], tabs[3], tabs[4], tabs[5], tabs[0], tabs[1]]
+    );
+    model.move_group(first, 2);
+    assert_eq!(
+        model.tabs(),
+        [tabs[2], tabs[0], tabs[1], tabs[3], tabs[4], tabs[5]]
+    );
+
+    assert!(model.move_group(second, 100).is_empty());
+    assert_groups_hold(&model, second + 1);
+}
+
+#[test]
+fn collapsing_the_active_group_activates_the_next_tab_shown() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+    let (group, _) = model.create_group(&tabs[1..], "g").unwrap();
+
+    assert_eq!(
+        model.set_group_collapsed(group, true),
+        [TabEvent::Deactivated(tabs[2]), TabEvent::Activated(tabs[0])]
+    );
+    assert!(model.group(group).unwrap().collapsed);
+    assert!(model.is_hidden(tabs[1]));
+    assert!(!model.is_hidden(tabs[0]));
+
+    // Activating a hidden tab brings its group back
+    model.activate(tabs[2]);
+    assert!(!model.group(group).unwrap().collapsed);
+}
+
+#[test]
+fn a_group_with_every_tab_stays_expanded() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 2);
+    let (group, _) = model.create_group(&tabs, "all").unwrap();
+
+    assert!(model.set_group_collapsed(group, true).is_empty());
+    assert!(!model.group(group).unwrap().collapsed);
+}
+
+#[test]
+fn groups_go_with_their_last_tab() {
+    let mut model = TabModel::new();
+    let mut panels = Panels::default();
+    let tabs = open(&mut model, &mut panels, 3);
+    let (group, _) = model.create_group(&tabs[..2], "g").unwrap();
+
+    panels.apply(model.close(tabs[0]));
+    assert!(model.group(group).is_some());
+    panels.apply(model.close(tabs[1]));
+    assert!(model.group(group).is_none());
+
+    let (group, _) = model.create_group(&[tabs[2]], "h").unwrap();
+    panels.apply(model.close_all());
+    assert!(model.group(group).is_none());
+}
+
+#[test]
+fn any_sequence_of_changes_keeps_groups_together() {
+    for seed in [0x2545_f491, 0x9e37_79b9, 0x85eb_ca6b, 0xc2b2_ae35] {
+        let mut model = TabModel::new();
+        let mut panels = Panels::default();
+        let mut created = 0;
+        let mut rng = Rng(seed);
+
+        for _ in 0..1000 {
+            let tabs = model.tabs().to_vec();
+            let tab = tabs.get(rng.below(tabs.len() + 1)).copied().unwrap_or(999);
+            let group = rng.below(created as usize + 1) as GroupId;
+            let events = match rng.below(12) {
+                0 | 1 => model.insert(rng.below(tabs.len() + 2)).1,
+                2 | 3 => model.move_tab(tab, rng.below(tabs.len() + 2)),
+                4 | 5 if tabs.len() > 4 => model.close(tab),
+                6 => {
+                    let picked = tabs
+                        .iter()
+                        .copied()
+                        .filter(|_| rng.below(3) == 0)
+                        .collect::<Vec<_>>();
+                    match model.create_group(&picked, "g") {
+                        Some((group, events)) => {
+                            created = group + 1;
+                            events
+                        }
+                        None => Vec::new(),
+                    }
+                }
+                7 => model.add_to_group(tab, group),
+                8 => model.remove_from_group(tab),
+                9 => model.move_group(group, rng.below(tabs.len() + 2)),
+                10 => match rng.below(3) {
+                    0 => model.pin(tab),
+                    1 => model.unpin(tab),
+                    _ => model.ungroup(group),
+                },
+                _ => model.set_group_collapsed(group, rng.below(2) == 0),
+            };
+            panels.apply(events);
+            assert_groups_hold(&model, created);
+            assert_pinned_first(&model);
+        }
+    }
+}
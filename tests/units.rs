@@ -0,0 +1,159 @@
+// Converts DIPs to pixels and back at every scaling step Windows offers, from 100% to 200%.
+#![cfg(target_os = "linux")]
+
+use testwindowtabs::dpi::Dpi;
+use testwindowtabs::geometry::{Insets, Rect, Size};
+use testwindowtabs::units::{snap_to_pixel, Dip, DipInsets, DipRect, DipSize, Px, Rounding};
+
+const DPIS: [u32; 5] = [96, 120, 144, 168, 192];
+
+// Every quarter DIP from -100 to 300
+fn dips() -> impl Iterator<Item = Dip> {
+    (-400..=1200).map(|quarter| Dip(quarter as f32 / 4.0))
+}
+
+#[test]
+fn rounding_picks_the_whole_pixel_it_says() {
+    for dpi in DPIS.map(Dpi::new) {
+        for dip in dips() {
+            let exact = dip.to_px_f32(dpi);
+            let round = dip.to_px(dpi, Rounding::Round).get();
+            let floor = dip.to_px(dpi, Rounding::Floor).get();
+            let ceil = dip.to_px(dpi, Rounding::Ceil).get();
+
+            assert!(
+                (round as f32 - exact).abs() <= 0.5,
+                "{:?} at {:?}",
+                dip,
+                dpi
+            );
+            assert!(
+                floor as f32 <= exact && exact <= ceil as f32,
+                "{:?} at {:?}",
+                dip,
+                dpi
+            );
+            if exact.fract() == 0.0 {
+                assert_eq!((floor, ceil), (round, round), "{:?} at {:?}", dip, dpi);
+            } else {
+                assert_eq!(ceil - floor, 1, "{:?} at {:?}", dip, dpi);
+            }
+        }
+    }
+}
+
+#[test]
+fn rounding_at_each_dpi() {
+    let rounded = |dpi, rounding| Dip(10.5).to_px(Dpi::new(dpi), rounding).get();
+
+    // 10.5, 13.125, 15.75, 18.375 and 21 pixels
+    let expected = [
+        (11, 10, 11),
+        (13, 13, 14),
+        (16, 15, 16),
+        (18, 18, 19),
+        (21, 21, 21),
+    ];
+    for (dpi, (round, floor, ceil)) in DPIS.into_iter().zip(expected) {
+        assert_eq!(rounded(dpi, Rounding::Round), round, "{}", dpi);
+        assert_eq!(rounded(dpi, Rounding::Floor), floor, "{}", dpi);
+        assert_eq!(rounded(dpi, Rounding::Ceil), ceil, "{}", dpi);
+    }
+}
+
+#[test]
+fn rects_sharing_an_edge_still_do_in_pixels() {
+    for dpi in DPIS.map(Dpi::new) {
+        for width in dips().filter(|dip| dip.0 > 0.0).take(200) {
+            // A row of rects like tabs, each starting where the last one ends
+            let mut left = 3.25;
+            let mut previous: Option<Rect> = None;
+
+            for _ in 0..10 {
+                let rect = DipRect::new(left, 0.0, left + width.0, 40.0).to_px(dpi);
+                if let Some(previous) = previous {
+                    assert_eq!(previous.right, rect.left, "{:?} wide at {:?}", width, dpi);
+                }
+                previous = Some(rect);
+                left += width.0;
+            }
+
+            // Together they cover exactly what one rect across all of them would
+            let row = DipRect::new(3.25, 0.0, left, 40.0).to_px(dpi);
+            assert_eq!(previous.unwrap().right, row.right);
+        }
+    }
+}
+
+#[test]
+fn pixels_survive_a_round_trip_through_dips() {
+    for dpi in DPIS.map(Dpi::new) {
+        for px in -500..=500 {
+            let dip = Px(px).to_dip(dpi);
+            assert_eq!(
+                dip.to_px(dpi, Rounding::Round),
+                Px(px),
+                "{} at {:?}",
+                px,
+                dpi
+            );
+            assert_eq!(dpi.dip(Px(px)), dip);
+        }
+
+        let rect = Rect::new(-13, 7, 501, 999);
+        assert_eq!(DipRect::from_px(rect, dpi).to_px(dpi), rect, "{:?}", dpi);
+    }
+}
+
+#[test]
+fn strokes_are_snapped_to_cover_whole_pixels() {
+    for coord in (-40..=40).map(|tenth| tenth as f32 / 10.0) {
+        for width in [1.0, 3.0, 5.0, 1.2] {
+            // Odd widths sit on the center of the pixel the coordinate falls into
+            let snapped = snap_to_pixel(coord, width);
+            assert_eq!(snapped, coord.floor() + 0.5, "{} wide at {}", width, coord);
+        }
+        for width in [2.0, 4.0, 2.2] {
+            // Even widths sit on the nearest edge
+            let snapped = snap_to_pixel(coord, width);
+            assert_eq!(snapped, coord.round(), "{} wide at {}", width, coord);
+        }
+    }
+}
+
+#[test]
+fn glyphs_are_centered_in_dips() {
+    let bounds = DipRect::new(0.0, 0.0, 47.0, 32.0);
+    let glyph = bounds.centered(DipSize::new(9.0, 9.0));
+    assert_eq!(glyph, DipRect::new(19.0, 11.5, 28.0, 20.5));
+    assert_eq!((glyph.width(), glyph.height()), (Dip(9.0), Dip(9.0)));
+
+    let inset = glyph.inset(DipInsets::new(2.0, 0.0, 0.0, 2.0));
+    assert_eq!(inset, DipRect::new(21.0, 11.5, 28.0, 18.5));
+
+    // The glyph keeps its size in pixels wherever the button ends up
+    for dpi in DPIS.map(Dpi::new) {
+        let size = Dip(9.0).to_px(dpi, Rounding::Round).get();
+        for left in 0..20 {
+            let button = Rect::from_size(left, 0, 59, 40);
+            let glyph = DipRect::from_px(button, dpi)
+                .centered(DipSize::new(9.0, 9.0))
+                .to_px(dpi);
+            assert!((glyph.width() - size).abs() <= 1, "at {} {:?}", left, dpi);
+            assert!(button.contains(&glyph));
+        }
+    }
+}
+
+#[test]
+fn sizes_and_insets_scale_per_value() {
+    let dpi = Dpi::new(144);
+    assert_eq!(
+        DipSize::new(10.0, 3.0).to_px(dpi, Rounding::Floor),
+        Size::new(15, 4)
+    );
+    assert_eq!(
+        DipInsets::new(1.0, 2.0, 3.0, 5.0).to_px(dpi),
+        Insets::new(2, 3, 5, 8)
+    );
+}
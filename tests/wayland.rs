@@ -0,0 +1,582 @@
+// Runs the app against a minimal headless compositor in the test, which records what the
+// app asks of it and feeds it pointer input.
+#![cfg(target_os = "linux")]
+
+use std::collections::HashMap;
+use std::os::fd::OwnedFd;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use wayland_protocols::xdg::shell::server::xdg_surface::{self, XdgSurface};
+use wayland_protocols::xdg::shell::server::xdg_toplevel::{self, ResizeEdge, XdgToplevel};
+use wayland_protocols::xdg::shell::server::xdg_wm_base::{self, XdgWmBase};
+use wayland_server::backend::{ClientData, ObjectId};
+use wayland_server::protocol::wl_buffer::WlBuffer;
+use wayland_server::protocol::wl_callback::WlCallback;
+use wayland_server::protocol::wl_compositor::{self, WlCompositor};
+use wayland_server::protocol::wl_pointer::{self, WlPointer};
+use wayland_server::protocol::wl_seat::{self, WlSeat};
+use wayland_server::protocol::wl_shm::{self, WlShm};
+use wayland_server::protocol::wl_shm_pool::{self, WlShmPool};
+use wayland_server::protocol::wl_surface::{self, WlSurface};
+use wayland_server::{
+    Client, DataInit, Dispatch, Display, DisplayHandle, GlobalDispatch, ListeningSocket, New,
+    Resource,
+};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+const TITLE_BG_PIXEL: u32 = 0xff96c8b4;
+const BTN_LEFT: u32 = 0x110;
+
+// Kills the app when a test fails half way.
+struct Kill(Child);
+
+impl Drop for Kill {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Requested {
+    Move,
+    Resize(ResizeEdge),
+    Maximize,
+    Minimize,
+}
+
+struct Buffer {
+    pool: Arc<OwnedFd>,
+    offset: i32,
+    width: i32,
+    height: i32,
+    stride: i32,
+}
+
+#[derive(Default)]
+struct Surface {
+    pending: Option<WlBuffer>,
+    callbacks: Vec<WlCallback>,
+}
+
+struct Frame {
+    width: i32,
+    pixels: Vec<u32>,
+}
+
+#[derive(Default)]
+struct Compositor {
+    surfaces: HashMap<ObjectId, Surface>,
+    toplevel: Option<(WlSurface, XdgSurface, XdgToplevel)>,
+    title: Option<String>,
+    pointer: Option<WlPointer>,
+    frame: Option<Frame>,
+    requested: Vec<Requested>,
+    serial: u32,
+    time: u32,
+}
+
+impl Compositor {
+    fn next_serial(&mut self) -> u32 {
+        self.serial += 1;
+        self.serial
+    }
+
+    fn pixel(&self, x: i32, y: i32) -> Option<u32> {
+        let frame = self.frame.as_ref()?;
+        frame.pixels.get((y * frame.width + x) as usize).copied()
+    }
+}
+
+struct Session {
+    display: Display<Compositor>,
+    socket: ListeningSocket,
+    compositor: Compositor,
+    app: Kill,
+    _dir: TempDir,
+}
+
+impl Session {
+    fn start(name: &str) -> Self {
+        let display = Display::<Compositor>::new().unwrap();
+        let handle = display.handle();
+        handle.create_global::<Compositor, WlCompositor, ()>(6, ());
+        handle.create_global::<Compositor, WlShm, ()>(1, ());
+        handle.create_global::<Compositor, XdgWmBase, ()>(5, ());
+        handle.create_global::<Compositor, WlSeat, ()>(7, ());
+
+        let dir = TempDir::new(name);
+        let path = dir.0.join("wayland-0");
+        let socket = ListeningSocket::bind_absolute(path.clone()).unwrap();
+
+        let app = Kill(
+            Command::new(env!("CARGO_BIN_EXE_testwindowtabs"))
+                .env("WAYLAND_DISPLAY", &path)
+                .env_remove("DISPLAY")
+                .env("TESTWINDOWTABS_SESSION", dir.0.join("session"))
+                .spawn()
+                .unwrap(),
+        );
+
+        Self {
+            display,
+            socket,
+            compositor: Compositor::default(),
+            app,
+            _dir: dir,
+        }
+    }
+
+    fn dispatch(&mut self) {
+        if let Some(stream) = self.socket.accept().unwrap() {
+            self.display
+                .handle()
+                .insert_client(stream, Arc::new(ClientState))
+                .unwrap();
+        }
+
+        self.display.dispatch_clients(&mut self.compositor).unwrap();
+        self.display.flush_clients().unwrap();
+    }
+
+    fn wait_until(&mut self, what: &str, mut condition: impl FnMut(&Compositor) -> bool) {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            self.dispatch();
+            if condition(&self.compositor) {
+                return;
+            }
+
+            assert!(Instant::now() < deadline, "timed out waiting for {}", what);
+            if let Some(status) = self.app.0.try_wait().unwrap() {
+                panic!("the app exited with {} waiting for {}", status, what);
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    fn configure(&mut self) {
+        let serial = self.compositor.next_serial();
+        let (_, xdg_surface, toplevel) = self.compositor.toplevel.as_ref().unwrap();
+
+        let activated = (xdg_toplevel::State::Activated as u32)
+            .to_ne_bytes()
+            .to_vec();
+        toplevel.configure(0, 0, activated);
+        xdg_surface.configure(serial);
+    }
+
+    fn surface(&self) -> WlSurface {
+        self.compositor.toplevel.as_ref().unwrap().0.clone()
+    }
+
+    fn enter(&mut self, x: f64, y: f64) {
+        let serial = self.compositor.next_serial();
+        let surface = self.surface();
+        let pointer = self.compositor.pointer.as_ref().unwrap();
+        pointer.enter(serial, &surface, x, y);
+        pointer.frame();
+        self.dispatch();
+    }
+
+    fn leave(&mut self) {
+        let serial = self.compositor.next_serial();
+        let surface = self.surface();
+        let pointer = self.compositor.pointer.as_ref().unwrap();
+        pointer.leave(serial, &surface);
+        pointer.frame();
+        self.dispatch();
+    }
+
+    // `after` milliseconds after the last one
+    fn button(&mut self, state: wl_pointer::ButtonState, after: u32) {
+        let serial = self.compositor.next_serial();
+        self.compositor.time += after;
+        let pointer = self.compositor.pointer.as_ref().unwrap();
+        pointer.button(serial, self.compositor.time, BTN_LEFT, state);
+        pointer.frame();
+        self.dispatch();
+    }
+
+    fn press(&mut self, x: f64, y: f64) {
+        self.enter(x, y);
+        // Far enough apart to never be a double click
+        self.button(wl_pointer::ButtonState::Pressed, 1000);
+    }
+
+    fn click(&mut self, x: f64, y: f64) {
+        self.press(x, y);
+        self.button(wl_pointer::ButtonState::Released, 50);
+        self.leave();
+    }
+
+    fn next_request(&mut self, what: &str) -> Requested {
+        self.wait_until(what, |compositor| !compositor.requested.is_empty());
+        self.compositor.requested.remove(0)
+    }
+}
+
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let path =
+            std::env::temp_dir().join(format!("testwindowtabs-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+#[test]
+fn client_side_decorations_hand_moving_and_resizing_to_the_compositor() {
+    let mut session = Session::start("decorations");
+
+    session.wait_until("the toplevel", |compositor| {
+        compositor.toplevel.is_some() && compositor.pointer.is_some()
+    });
+    assert_eq!(
+        session.compositor.title.as_deref(),
+        Some("the testwindowtabs application")
+    );
+
+    session.configure();
+
+    // The window is 500 pixels wide and the drag area sits left of the three caption buttons
+    let (drag_x, drag_y) = (500 - 3 * 47 - 50, 10);
+    session.wait_until("the title bar", |compositor| {
+        compositor.pixel(drag_x, drag_y) == Some(TITLE_BG_PIXEL)
+    });
+
+    session.press(drag_x as f64, drag_y as f64);
+    assert_eq!(session.next_request("a move"), Requested::Move);
+    // The compositor took the pointer
+    session.leave();
+
+    session.press(498.0, 250.0);
+    assert_eq!(
+        session.next_request("a resize"),
+        Requested::Resize(ResizeEdge::Right)
+    );
+    session.leave();
+
+    // A second press on the caption right after the first maximizes instead
+    session.press(drag_x as f64, drag_y as f64);
+    assert_eq!(session.next_request("a move"), Requested::Move);
+    session.button(wl_pointer::ButtonState::Released, 50);
+    session.button(wl_pointer::ButtonState::Pressed, 50);
+    assert_eq!(session.next_request("maximizing"), Requested::Maximize);
+    session.button(wl_pointer::ButtonState::Released, 50);
+    session.leave();
+
+    session.click(500.0 - 47.0 * 2.0 - 20.0, 15.0);
+    assert_eq!(session.next_request("minimizing"), Requested::Minimize);
+
+    // The close button ends the app
+    session.click(500.0 - 20.0, 15.0);
+
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        session.dispatch();
+        if let Some(status) = session.app.0.try_wait().unwrap() {
+            break status;
+        }
+        assert!(Instant::now() < deadline, "the app didn't exit");
+        thread::sleep(Duration::from_millis(5));
+    };
+    assert!(status.success());
+}
+
+struct ClientState;
+
+impl ClientData for ClientState {}
+
+impl GlobalDispatch<WlCompositor, ()> for Compositor {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WlCompositor>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<WlCompositor, ()> for Compositor {
+    fn request(
+        compositor: &mut Self,
+        _: &Client,
+        _: &WlCompositor,
+        request: wl_compositor::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_compositor::Request::CreateSurface { id } = request {
+            let surface = data_init.init(id, ());
+            compositor.surfaces.insert(surface.id(), Surface::default());
+        }
+    }
+}
+
+impl Dispatch<WlSurface, ()> for Compositor {
+    fn request(
+        compositor: &mut Self,
+        _: &Client,
+        surface: &WlSurface,
+        request: wl_surface::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let state = compositor.surfaces.entry(surface.id()).or_default();
+        match request {
+            wl_surface::Request::Attach { buffer, .. } => state.pending = buffer,
+            wl_surface::Request::Frame { callback } => {
+                state.callbacks.push(data_init.init(callback, ()));
+            }
+            wl_surface::Request::Commit => {
+                let callbacks = std::mem::take(&mut state.callbacks);
+                if let Some(buffer) = state.pending.take() {
+                    compositor.frame = Some(read_buffer(&buffer));
+                    buffer.release();
+                }
+                for callback in callbacks {
+                    callback.done(compositor.time);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn read_buffer(buffer: &WlBuffer) -> Frame {
+    let data = buffer.data::<Buffer>().unwrap();
+    let mut bytes = vec![0; (data.stride * data.height) as usize];
+    rustix::io::pread(&*data.pool, &mut bytes, data.offset as u64).unwrap();
+
+    let pixels = bytes
+        .chunks_exact(4)
+        .map(|pixel| u32::from_le_bytes(pixel.try_into().unwrap()))
+        .collect();
+    Frame {
+        width: data.width,
+        pixels,
+    }
+}
+
+impl Dispatch<WlCallback, ()> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlCallback,
+        _: <WlCallback as Resource>::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<WlShm, ()> for Compositor {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WlShm>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let shm = data_init.init(resource, ());
+        shm.format(wl_shm::Format::Argb8888);
+        shm.format(wl_shm::Format::Xrgb8888);
+    }
+}
+
+impl Dispatch<WlShm, ()> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlShm,
+        request: wl_shm::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_shm::Request::CreatePool { id, fd, .. } = request {
+            data_init.init(id, Arc::new(fd));
+        }
+    }
+}
+
+impl Dispatch<WlShmPool, Arc<OwnedFd>> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlShmPool,
+        request: wl_shm_pool::Request,
+        pool: &Arc<OwnedFd>,
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_shm_pool::Request::CreateBuffer {
+            id,
+            offset,
+            width,
+            height,
+            stride,
+            ..
+        } = request
+        {
+            let buffer = Buffer {
+                pool: pool.clone(),
+                offset,
+                width,
+                height,
+                stride,
+            };
+            data_init.init(id, buffer);
+        }
+    }
+}
+
+impl Dispatch<WlBuffer, Buffer> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlBuffer,
+        _: <WlBuffer as Resource>::Request,
+        _: &Buffer,
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<WlSeat, ()> for Compositor {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<WlSeat>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        let seat = data_init.init(resource, ());
+        seat.capabilities(wl_seat::Capability::Pointer);
+    }
+}
+
+impl Dispatch<WlSeat, ()> for Compositor {
+    fn request(
+        compositor: &mut Self,
+        _: &Client,
+        _: &WlSeat,
+        request: wl_seat::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let wl_seat::Request::GetPointer { id } = request {
+            compositor.pointer = Some(data_init.init(id, ()));
+        }
+    }
+}
+
+impl Dispatch<WlPointer, ()> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &WlPointer,
+        _: wl_pointer::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+    }
+}
+
+impl GlobalDispatch<XdgWmBase, ()> for Compositor {
+    fn bind(
+        _: &mut Self,
+        _: &DisplayHandle,
+        _: &Client,
+        resource: New<XdgWmBase>,
+        _: &(),
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        data_init.init(resource, ());
+    }
+}
+
+impl Dispatch<XdgWmBase, ()> for Compositor {
+    fn request(
+        _: &mut Self,
+        _: &Client,
+        _: &XdgWmBase,
+        request: xdg_wm_base::Request,
+        _: &(),
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let xdg_wm_base::Request::GetXdgSurface { id, surface } = request {
+            data_init.init(id, surface);
+        }
+    }
+}
+
+impl Dispatch<XdgSurface, WlSurface> for Compositor {
+    fn request(
+        compositor: &mut Self,
+        _: &Client,
+        xdg_surface: &XdgSurface,
+        request: xdg_surface::Request,
+        surface: &WlSurface,
+        _: &DisplayHandle,
+        data_init: &mut DataInit<'_, Self>,
+    ) {
+        if let xdg_surface::Request::GetToplevel { id } = request {
+            let toplevel = data_init.init(id, ());
+            compositor.toplevel = Some((surface.clone(), xdg_surface.clone(), toplevel));
+        }
+    }
+}
+
+impl Dispatch<XdgToplevel, ()> for Compositor {
+    fn request(
+        compositor: &mut Self,
+        _: &Client,
+        _: &XdgToplevel,
+        request: xdg_toplevel::Request,
+        _: &(),
+        _: &DisplayHandle,
+        _: &mut DataInit<'_, Self>,
+    ) {
+        let requested = match request {
+            xdg_toplevel::Request::SetTitle { title } => {
+                compositor.title = Some(title);
+                return;
+            }
+            xdg_toplevel::Request::Move { .. } => Requested::Move,
+            xdg_toplevel::Request::Resize {
+                edges: wayland_server::WEnum::Value(edge),
+                ..
+            } => Requested::Resize(edge),
+            xdg_toplevel::Request::SetMaximized => Requested::Maximize,
+            xdg_toplevel::Request::SetMinimized => Requested::Minimize,
+            _ => return,
+        };
+        compositor.requested.push(requested);
+    }
+}
@@ -0,0 +1,408 @@
+// Builds widget trees out of probes that log what happens to them, and checks the order the
+// tree paints, hit tests and moves focus in, and what it forgets when widgets go away.
+#![cfg(target_os = "linux")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use testwindowtabs::canvas::{Canvas, Color};
+use testwindowtabs::dpi::Dpi;
+use testwindowtabs::geometry::{Point, PointF, Rect, RectF};
+use testwindowtabs::image::Image;
+use testwindowtabs::widget::{Context, Widget};
+use testwindowtabs::widget_tree::{WidgetId, WidgetTree};
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+struct Probe {
+    name: &'static str,
+    bounds: Rect,
+    focusable: bool,
+    log: Log,
+}
+
+impl Probe {
+    fn new(name: &'static str, bounds: Rect, log: &Log) -> Box<Self> {
+        Box::new(Self {
+            name,
+            bounds,
+            focusable: false,
+            log: log.clone(),
+        })
+    }
+
+    fn focusable(name: &'static str, bounds: Rect, log: &Log) -> Box<Self> {
+        let mut probe = Self::new(name, bounds, log);
+        probe.focusable = true;
+        probe
+    }
+
+    fn record(&self, what: &str) {
+        self.log
+            .borrow_mut()
+            .push(format!("{} {}", what, self.name));
+    }
+}
+
+impl Widget for Probe {
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    fn set_bounds(&mut self, bounds: Rect) {
+        self.bounds = bounds;
+    }
+
+    fn paint(&self, _canvas: &mut dyn Canvas) {
+        self.record("paint");
+    }
+
+    fn is_focusable(&self) -> bool {
+        self.focusable
+    }
+
+    fn on_focus_changed(&mut self, focused: bool, _ctx: &mut Context) {
+        self.record(if focused { "focus" } else { "blur" });
+    }
+
+    fn on_mouse_move(&mut self, _x: i32, _y: i32, _left_down: bool, _ctx: &mut Context) {
+        self.record("move");
+    }
+
+    fn on_mouse_leave(&mut self, _ctx: &mut Context) {
+        self.record("leave");
+    }
+
+    // Focusable probes capture the mouse like buttons do
+    fn on_mouse_down(&mut self, _x: i32, _y: i32, _ctx: &mut Context) -> bool {
+        self.record("down");
+        self.focusable
+    }
+
+    fn on_mouse_up(&mut self, _x: i32, _y: i32, _ctx: &mut Context) {
+        self.record("up");
+    }
+}
+
+struct NullCanvas;
+
+impl Canvas for NullCanvas {
+    fn dpi(&self) -> Dpi {
+        Dpi::DEFAULT
+    }
+
+    fn set_antialias(&mut self, _enabled: bool) {}
+    fn clear(&mut self, _color: Color) {}
+    fn fill_rect(&mut self, _rect: RectF, _color: Color) {}
+    fn stroke_rect(&mut self, _rect: RectF, _color: Color, _width: f32) {}
+    fn draw_line(&mut self, _from: PointF, _to: PointF, _color: Color, _width: f32) {}
+    fn fill_ellipse(&mut self, _center: PointF, _rx: f32, _ry: f32, _color: Color) {}
+    fn draw_image(&mut self, _image: &Image, _rect: RectF) {}
+    fn draw_text(&mut self, _text: &str, _origin: Point, _color: Color) {}
+    fn push_clip(&mut self, _rect: RectF) {}
+    fn pop_clip(&mut self) {}
+}
+
+const BOUNDS: Rect = Rect::new(0, 0, 100, 100);
+
+fn ctx() -> Context {
+    Context::new(Duration::ZERO, Dpi::DEFAULT)
+}
+
+fn take(log: &Log) -> Vec<String> {
+    std::mem::take(&mut *log.borrow_mut())
+}
+
+fn name(tree: &WidgetTree, id: WidgetId) -> &'static str {
+    tree.get::<Probe>(id).map_or("-", |probe| probe.name)
+}
+
+fn names(tree: &WidgetTree, ids: &[WidgetId]) -> Vec<&'static str> {
+    ids.iter().map(|id| name(tree, *id)).collect()
+}
+
+#[test]
+fn children_go_where_they_are_inserted() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let root = tree.root();
+
+    let a = tree.add_child(root, Probe::new("a", BOUNDS, &log)).unwrap();
+    tree.add_child(root, Probe::new("b", BOUNDS, &log)).unwrap();
+    tree.insert_child(root, 0, Probe::new("c", BOUNDS, &log))
+        .unwrap();
+    // Past the end just appends
+    tree.insert_child(root, 99, Probe::new("d", BOUNDS, &log))
+        .unwrap();
+    let a1 = tree.add_child(a, Probe::new("a1", BOUNDS, &log)).unwrap();
+
+    assert_eq!(names(&tree, tree.children(root)), ["c", "a", "b", "d"]);
+    assert_eq!(tree.parent(a1), Some(a));
+    assert_eq!(tree.parent(a), Some(root));
+    assert_eq!(tree.parent(root), None);
+}
+
+#[test]
+fn removing_a_widget_removes_its_subtree() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let root = tree.root();
+
+    let a = tree.add_child(root, Probe::new("a", BOUNDS, &log)).unwrap();
+    let b = tree.add_child(root, Probe::new("b", BOUNDS, &log)).unwrap();
+    let a1 = tree.add_child(a, Probe::new("a1", BOUNDS, &log)).unwrap();
+    let a11 = tree.add_child(a1, Probe::new("a11", BOUNDS, &log)).unwrap();
+
+    let removed = tree.remove(a).unwrap();
+    assert_eq!(removed.as_any().downcast_ref::<Probe>().unwrap().name, "a");
+
+    for gone in [a, a1, a11] {
+        assert!(!tree.contains(gone));
+        assert!(tree.widget(gone).is_none());
+        assert!(tree.children(gone).is_empty());
+    }
+    assert_eq!(tree.children(root), [b]);
+    assert!(tree.remove(a).is_none());
+    assert!(tree
+        .add_child(a, Probe::new("late", BOUNDS, &log))
+        .is_none());
+
+    // The root stays
+    assert!(tree.remove(root).is_none());
+    assert!(tree.contains(root));
+}
+
+#[test]
+fn removed_widgets_lose_hover_capture_and_focus() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let mut ctx = ctx();
+
+    let a = tree
+        .add_child(tree.root(), Probe::new("a", BOUNDS, &log))
+        .unwrap();
+    let a1 = tree
+        .add_child(a, Probe::focusable("a1", BOUNDS, &log))
+        .unwrap();
+
+    assert!(tree.mouse_down(50, 50, &mut ctx));
+    assert_eq!(tree.hover(), Some(a1));
+    assert_eq!(tree.focused(), Some(a1));
+    assert!(tree.is_captured());
+
+    tree.remove(a);
+    assert_eq!(tree.hover(), None);
+    assert_eq!(tree.focused(), None);
+    assert!(!tree.is_captured());
+
+    // Nothing is left to route the release to
+    take(&log);
+    tree.mouse_up(50, 50, &mut ctx);
+    assert!(take(&log).is_empty());
+}
+
+#[test]
+fn slots_of_removed_widgets_are_reused_under_new_ids() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let root = tree.root();
+
+    let old = tree
+        .add_child(root, Probe::new("old", BOUNDS, &log))
+        .unwrap();
+    let slots = tree.slot_count();
+    tree.remove(old);
+
+    let new = tree
+        .add_child(root, Probe::new("new", BOUNDS, &log))
+        .unwrap();
+    assert_eq!(tree.slot_count(), slots);
+    assert_ne!(old, new);
+    assert!(!tree.contains(old));
+    assert_eq!(name(&tree, new), "new");
+
+    // Tabs opening and closing all day don't grow the tree
+    for _ in 0..100 {
+        let tab = tree
+            .add_child(root, Probe::new("tab", BOUNDS, &log))
+            .unwrap();
+        tree.add_child(tab, Probe::new("content", BOUNDS, &log));
+        tree.remove(tab);
+    }
+    assert_eq!(tree.slot_count(), slots + 2);
+}
+
+#[test]
+fn higher_z_indices_paint_later_and_get_hit_first() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let root = tree.root();
+
+    let a = tree.add_child(root, Probe::new("a", BOUNDS, &log)).unwrap();
+    let b = tree.add_child(root, Probe::new("b", BOUNDS, &log)).unwrap();
+    let c = tree.add_child(root, Probe::new("c", BOUNDS, &log)).unwrap();
+    let a1 = tree.add_child(a, Probe::new("a1", BOUNDS, &log)).unwrap();
+
+    // Same z-index, so the order they were added in, children right after their parent
+    assert_eq!(
+        names(&tree, &tree.paint_order()),
+        ["-", "a", "a1", "b", "c"]
+    );
+    assert_eq!(tree.hit_test(50, 50), Some(c));
+
+    tree.set_z_index(a, 1);
+    assert_eq!(
+        names(&tree, &tree.paint_order()),
+        ["-", "b", "c", "a", "a1"]
+    );
+    assert_eq!(tree.hit_test(50, 50), Some(a1));
+
+    tree.bring_to_front(b);
+    assert_eq!(tree.z_index(b), 2);
+    assert_eq!(tree.hit_test(50, 50), Some(b));
+
+    tree.send_to_back(b);
+    assert_eq!(
+        names(&tree, &tree.paint_order()),
+        ["-", "b", "c", "a", "a1"]
+    );
+
+    // Layout order doesn't care about z-indices
+    assert_eq!(tree.children(root), [a, b, c]);
+
+    tree.paint(&mut NullCanvas, &BOUNDS);
+    assert_eq!(take(&log), ["paint b", "paint c", "paint a", "paint a1"]);
+}
+
+#[test]
+fn only_widgets_in_the_painted_rect_paint() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let root = tree.root();
+
+    tree.add_child(root, Probe::new("left", Rect::new(0, 0, 50, 100), &log));
+    tree.add_child(root, Probe::new("right", Rect::new(50, 0, 100, 100), &log));
+
+    tree.paint(&mut NullCanvas, &Rect::new(60, 10, 70, 20));
+    assert_eq!(take(&log), ["paint right"]);
+    assert_eq!(
+        tree.hit_test(10, 10).map(|id| name(&tree, id)),
+        Some("left")
+    );
+    assert_eq!(tree.hit_test(200, 10), None);
+}
+
+#[test]
+fn hidden_widgets_and_their_children_are_skipped() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let mut ctx = ctx();
+    let root = tree.root();
+
+    let a = tree.add_child(root, Probe::new("a", BOUNDS, &log)).unwrap();
+    let b = tree.add_child(root, Probe::new("b", BOUNDS, &log)).unwrap();
+    let b1 = tree
+        .add_child(b, Probe::focusable("b1", BOUNDS, &log))
+        .unwrap();
+
+    tree.set_focus(Some(b1), &mut ctx);
+    tree.mouse_move(50, 50, false, &mut ctx);
+    assert_eq!(tree.hover(), Some(b1));
+
+    tree.set_visible(b, false);
+    assert!(tree.is_visible(b1));
+    assert!(!tree.is_shown(b1));
+    assert_eq!(tree.hover(), None);
+    assert_eq!(tree.focused(), None);
+
+    assert_eq!(names(&tree, &tree.paint_order()), ["-", "a"]);
+    assert_eq!(tree.hit_test(50, 50), Some(a));
+    assert!(tree.focus_order().is_empty());
+
+    // Hidden widgets can't take focus either
+    tree.set_focus(Some(b1), &mut ctx);
+    assert_eq!(tree.focused(), None);
+
+    tree.set_visible(b, true);
+    assert_eq!(tree.hit_test(50, 50), Some(b1));
+}
+
+#[test]
+fn focus_moves_through_focusable_widgets_and_wraps_around() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let mut ctx = ctx();
+    let root = tree.root();
+
+    let a = tree
+        .add_child(root, Probe::focusable("a", BOUNDS, &log))
+        .unwrap();
+    tree.add_child(root, Probe::new("label", BOUNDS, &log));
+    let b = tree
+        .add_child(root, Probe::focusable("b", BOUNDS, &log))
+        .unwrap();
+    let c = tree
+        .add_child(root, Probe::focusable("c", BOUNDS, &log))
+        .unwrap();
+    assert_eq!(tree.focus_order(), [a, b, c]);
+
+    // Without focus, forward starts at the first widget and backward at the last
+    tree.focus_previous(&mut ctx);
+    assert_eq!(tree.focused(), Some(c));
+    tree.set_focus(None, &mut ctx);
+    tree.focus_next(&mut ctx);
+    assert_eq!(tree.focused(), Some(a));
+    take(&log);
+
+    tree.focus_next(&mut ctx);
+    tree.focus_next(&mut ctx);
+    tree.focus_next(&mut ctx);
+    assert_eq!(tree.focused(), Some(a));
+    assert_eq!(
+        take(&log),
+        ["blur a", "focus b", "blur b", "focus c", "blur c", "focus a"]
+    );
+
+    tree.focus_previous(&mut ctx);
+    assert_eq!(tree.focused(), Some(c));
+
+    // Focusing the focused widget again doesn't notify it
+    take(&log);
+    tree.set_focus(Some(c), &mut ctx);
+    assert!(take(&log).is_empty());
+}
+
+#[test]
+fn captured_widgets_get_the_mouse_until_it_is_released() {
+    let log = Log::default();
+    let mut tree = WidgetTree::new(BOUNDS);
+    let mut ctx = ctx();
+    let root = tree.root();
+
+    let button = tree
+        .add_child(
+            root,
+            Probe::focusable("button", Rect::new(0, 0, 50, 50), &log),
+        )
+        .unwrap();
+    tree.add_child(root, Probe::new("other", Rect::new(50, 0, 100, 50), &log));
+
+    assert!(tree.mouse_down(10, 10, &mut ctx));
+    tree.mouse_move(70, 10, true, &mut ctx);
+    assert_eq!(tree.hover(), Some(button));
+
+    tree.mouse_up(70, 10, &mut ctx);
+    assert!(!tree.is_captured());
+    assert_eq!(
+        take(&log),
+        [
+            "focus button",
+            "down button",
+            "move button",
+            "up button",
+            "leave button"
+        ]
+    );
+    assert_eq!(tree.hover().map(|id| name(&tree, id)), Some("other"));
+}
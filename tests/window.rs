@@ -0,0 +1,1237 @@
+// Runs the app's window on the headless platform and drives it the way a user would: through
+// the caption buttons, the tab bar and resizing.
+#![cfg(target_os = "linux")]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+use testwindowtabs::closed_tabs::ClosedTab;
+use testwindowtabs::dpi::Dpi;
+use testwindowtabs::event::{Event, HitZone, Key, MouseButton};
+use testwindowtabs::font::Font;
+use testwindowtabs::geometry::{Point, Rect};
+use testwindowtabs::headless::{DrawOp, HeadlessPlatform};
+use testwindowtabs::host::Hosted;
+use testwindowtabs::menu::MenuEntry;
+use testwindowtabs::platform::{Platform, SystemCommand, WindowId};
+use testwindowtabs::session::{GroupSession, Session, TabSession, WindowSession};
+use testwindowtabs::tab_menu::TabAction;
+use testwindowtabs::tab_model::{TabGroup, TabId};
+use testwindowtabs::theme::Theme;
+use testwindowtabs::window::Window;
+
+// Caption buttons are 47 px wide at 96 DPI, lined up at the right edge of the title bar
+const BUTTON_WIDTH: i32 = 47;
+const CAPTION_Y: i32 = 15;
+
+fn open() -> (Rc<HeadlessPlatform>, Hosted<Window<HeadlessPlatform>>) {
+    let platform = Rc::new(HeadlessPlatform::new());
+    let window = Window::new(platform.clone()).unwrap();
+    (platform, window)
+}
+
+// The `n`th caption button from the right, starting with the close button at 0.
+fn caption_button(
+    platform: &HeadlessPlatform,
+    window: &Hosted<Window<HeadlessPlatform>>,
+    n: i32,
+) -> Point {
+    let client = platform.client_rect(window.window()).unwrap();
+    Point::new(
+        client.right - BUTTON_WIDTH * n - BUTTON_WIDTH / 2,
+        CAPTION_Y,
+    )
+}
+
+#[test]
+fn the_window_opens_at_its_default_size() {
+    let (platform, window) = open();
+    let id = window.window();
+
+    assert_eq!(platform.client_rect(id).unwrap(), Rect::new(0, 0, 500, 500));
+    assert!(!platform.is_maximized(id).unwrap());
+
+    platform.paint(id);
+    let frames = platform.frames(id);
+    assert_eq!(frames.len(), 1);
+    assert!(frames[0].contains(&DrawOp::PushClip(Rect::new(0, 0, 500, 500).into())));
+}
+
+#[test]
+fn the_maximize_button_maximizes_and_restores() {
+    let (platform, window) = open();
+    let id = window.window();
+
+    platform.click(id, caption_button(&platform, &window, 1));
+    platform.run_pending();
+    assert!(platform.is_maximized(id).unwrap());
+    assert_eq!(
+        platform.client_rect(id).unwrap(),
+        Rect::new(0, 0, 1920, 1080)
+    );
+
+    // The buttons moved along with the right edge
+    platform.click(id, caption_button(&platform, &window, 1));
+    platform.run_pending();
+    assert!(!platform.is_maximized(id).unwrap());
+    assert_eq!(platform.client_rect(id).unwrap(), Rect::new(0, 0, 500, 500));
+}
+
+#[test]
+fn the_minimize_button_minimizes() {
+    let (platform, window) = open();
+    let id = window.window();
+
+    platform.click(id, caption_button(&platform, &window, 2));
+    assert!(!platform.is_minimized(id));
+    platform.run_pending();
+    assert!(platform.is_minimized(id));
+}
+
+#[test]
+fn the_close_button_closes_the_window_and_quits() {
+    let (platform, window) = open();
+    let id = window.window();
+    window.with(|window| window.add_tab()).unwrap().unwrap();
+
+    platform.click(id, caption_button(&platform, &window, 0));
+    platform.run_pending();
+
+    assert!(!platform.is_open(id));
+    assert!(platform.has_quit());
+}
+
+#[test]
+fn the_add_button_opens_a_tab_and_activates_it() {
+    let (platform, window) = open();
+    let id = window.window();
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), []);
+
+    // With no tabs yet the add button sits at the left of the tab bar
+    platform.click(id, Point::new(20, 20));
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), [0]);
+
+    platform.click(id, Point::new(20, 20));
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), [0, 1]);
+    assert_eq!(window.with(|window| window.active_tab()).unwrap(), Some(1));
+}
+
+#[test]
+fn hit_testing_tells_the_caption_from_buttons_and_borders() {
+    let (platform, window) = open();
+    let id = window.window();
+
+    assert_eq!(platform.hit_test(id, Point::new(250, 2)), HitZone::Top);
+    assert_eq!(
+        platform.hit_test(id, Point::new(250, CAPTION_Y)),
+        HitZone::Caption
+    );
+    assert_eq!(
+        platform.hit_test(id, caption_button(&platform, &window, 0)),
+        HitZone::Client
+    );
+    assert_eq!(platform.hit_test(id, Point::new(250, 300)), HitZone::Client);
+    assert_eq!(platform.hit_test(id, Point::new(2, 300)), HitZone::Left);
+}
+
+#[test]
+fn resizing_lays_out_the_title_bar_again() {
+    let (platform, window) = open();
+    let id = window.window();
+
+    // Right of the old close button, the caption once the window is wider
+    let old_close = caption_button(&platform, &window, 0);
+    platform
+        .set_window_bounds(id, Rect::from_size(0, 0, 800, 600))
+        .unwrap();
+
+    assert_eq!(platform.hit_test(id, old_close), HitZone::Caption);
+    assert_eq!(
+        platform.hit_test(id, caption_button(&platform, &window, 0)),
+        HitZone::Client
+    );
+
+    platform.click(id, caption_button(&platform, &window, 0));
+    platform.run_pending();
+    assert!(!platform.is_open(id));
+}
+
+#[test]
+fn only_the_invalid_part_gets_painted_again() {
+    let (platform, window) = open();
+    let id = window.window();
+    platform.paint(id);
+
+    // Hovering a caption button repaints that button and nothing else
+    let close = caption_button(&platform, &window, 0);
+    platform.move_mouse(id, close);
+    platform.paint(id);
+
+    let frames = platform.frames(id);
+    assert_eq!(frames.len(), 2);
+    let clips = frames[1]
+        .iter()
+        .filter_map(|op| match op {
+            DrawOp::PushClip(rect) => Some(*rect),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(clips.len(), 1);
+    let clip = clips[0].round();
+    assert!(clip.width() <= BUTTON_WIDTH);
+    assert!(clip.contains_point(close.x, close.y));
+}
+
+#[test]
+fn tab_changes_are_autosaved_once_they_calm_down() {
+    let (platform, window) = open();
+    let saved = Rc::new(RefCell::new(Vec::new()));
+    window
+        .with(|window| {
+            let saved = saved.clone();
+            window.set_session_handler(move |session| saved.borrow_mut().push(session));
+            window.set_autosave(Duration::from_secs(2), Duration::from_secs(30));
+        })
+        .unwrap();
+
+    let tab = window.with(|window| window.add_tab()).unwrap().unwrap();
+    platform.advance(Duration::from_secs(1));
+    window.with(|window| window.add_tab()).unwrap().unwrap();
+    platform.advance(Duration::from_secs(1));
+    window
+        .with(|window| window.set_tab_title(tab.unwrap(), "renamed"))
+        .unwrap()
+        .unwrap();
+    assert!(saved.borrow().is_empty());
+
+    platform.advance(Duration::from_secs(2));
+    let sessions = saved.borrow().clone();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].windows[0].tabs.len(), 2);
+    assert_eq!(sessions[0].windows[0].tabs[0].title, "renamed");
+    assert_eq!(sessions[0].windows[0].active, Some(1));
+
+    // Nothing changed since
+    platform.advance(Duration::from_secs(60));
+    assert_eq!(saved.borrow().len(), 1);
+}
+
+#[test]
+fn closing_saves_the_session_once_and_drops_the_pending_autosave() {
+    let (platform, window) = open();
+    let id = window.window();
+    let saved = Rc::new(RefCell::new(Vec::new()));
+    window
+        .with(|window| {
+            let saved = saved.clone();
+            window.set_session_handler(move |session| saved.borrow_mut().push(session));
+            window.set_autosave(Duration::from_secs(2), Duration::from_secs(30));
+        })
+        .unwrap();
+
+    window.with(|window| window.add_tab()).unwrap().unwrap();
+    platform.click(id, caption_button(&platform, &window, 0));
+    platform.run_pending();
+    assert!(!platform.is_open(id));
+
+    let sessions = saved.borrow().clone();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].windows[0].tabs.len(), 1);
+}
+
+#[test]
+fn closed_tabs_reopen_where_they_were() {
+    let (platform, window) = open();
+    let titles = ["a", "b", "c", "d"];
+    let tabs = window
+        .with(|window| {
+            titles
+                .iter()
+                .map(|title| window.open_tab(title, "").unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+    let open_titles = |window: &Hosted<Window<HeadlessPlatform>>| {
+        window
+            .with(|window| {
+                let session = window.session().unwrap();
+                session
+                    .tabs
+                    .into_iter()
+                    .map(|tab| tab.title)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap()
+    };
+
+    window
+        .with(|window| window.close_tab(tabs[1]))
+        .unwrap()
+        .unwrap();
+    window
+        .with(|window| window.close_tab(tabs[3]))
+        .unwrap()
+        .unwrap();
+    platform.run_pending();
+    assert_eq!(open_titles(&window), ["a", "c"]);
+    assert!(window.with(|window| window.can_reopen_tab()).unwrap());
+
+    let reopened = window
+        .with(|window| window.reopen_closed_tab())
+        .unwrap()
+        .unwrap();
+    assert_eq!(open_titles(&window), ["a", "c", "d"]);
+    assert_eq!(window.with(|window| window.active_tab()).unwrap(), reopened);
+
+    window
+        .with(|window| window.reopen_closed_tab())
+        .unwrap()
+        .unwrap();
+    assert_eq!(open_titles(&window), ["a", "b", "c", "d"]);
+    assert!(!window.with(|window| window.can_reopen_tab()).unwrap());
+    assert_eq!(
+        window
+            .with(|window| window.reopen_closed_tab())
+            .unwrap()
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn a_closed_tab_goes_last_once_its_place_is_gone() {
+    let (_platform, window) = open();
+    let tabs = window
+        .with(|window| {
+            (0..3)
+                .map(|_| window.add_tab().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+
+    window
+        .with(|window| window.close_tab(tabs[2]))
+        .unwrap()
+        .unwrap();
+    window
+        .with(|window| window.close_tab(tabs[1]))
+        .unwrap()
+        .unwrap();
+
+    // Forget the second tab, which leaves the third one with only the first tab to go after
+    let forgotten = window
+        .with(|window| window.closed_tabs().borrow_mut().pop(0))
+        .unwrap()
+        .unwrap();
+    assert_eq!(forgotten.index, 1);
+    let reopened = window
+        .with(|window| window.reopen_closed_tab())
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        window.with(|window| window.tabs()).unwrap(),
+        [tabs[0], reopened]
+    );
+}
+
+#[test]
+fn middle_clicking_closes_a_tab_unless_it_is_pinned() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = window
+        .with(|window| {
+            (0..2)
+                .map(|_| window.add_tab().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+    window
+        .with(|window| window.set_tab_pinned(tabs[1], true))
+        .unwrap()
+        .unwrap();
+    platform.advance(Duration::from_secs(1));
+    assert_eq!(
+        window.with(|window| window.tabs()).unwrap(),
+        [tabs[1], tabs[0]]
+    );
+
+    // The pinned tab is the first one, the other follows it
+    platform.release(id, Point::new(10, 20), MouseButton::Middle);
+    assert_eq!(
+        window.with(|window| window.tabs()).unwrap(),
+        [tabs[1], tabs[0]]
+    );
+
+    platform.release(id, Point::new(37, 20), MouseButton::Middle);
+    platform.run_pending();
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), [tabs[1]]);
+}
+
+#[test]
+fn closing_the_other_tabs_spares_the_pinned_ones() {
+    let (platform, window) = open();
+    let tabs = window
+        .with(|window| {
+            (0..4)
+                .map(|_| window.add_tab().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+    window
+        .with(|window| window.set_tab_pinned(tabs[0], true))
+        .unwrap()
+        .unwrap();
+
+    window
+        .with(|window| window.close_other_tabs(tabs[2]))
+        .unwrap()
+        .unwrap();
+    platform.run_pending();
+    assert_eq!(
+        window.with(|window| window.tabs()).unwrap(),
+        [tabs[0], tabs[2]]
+    );
+}
+
+#[test]
+fn a_pinned_tab_reopens_pinned() {
+    let (platform, window) = open();
+    let tabs = window
+        .with(|window| {
+            (0..3)
+                .map(|_| window.add_tab().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+    window
+        .with(|window| window.set_tab_pinned(tabs[2], true))
+        .unwrap()
+        .unwrap();
+    window
+        .with(|window| window.close_tab(tabs[2]))
+        .unwrap()
+        .unwrap();
+    platform.run_pending();
+
+    let reopened = window
+        .with(|window| window.reopen_closed_tab())
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert!(window
+        .with(|window| window.is_tab_pinned(reopened))
+        .unwrap());
+    assert_eq!(
+        window.with(|window| window.tabs()).unwrap(),
+        [reopened, tabs[0], tabs[1]]
+    );
+    assert!(
+        window
+            .with(|window| window.session())
+            .unwrap()
+            .unwrap()
+            .tabs[0]
+            .pinned
+    );
+}
+
+#[test]
+fn clicking_a_group_label_collapses_and_expands_the_group() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = window
+        .with(|window| {
+            (0..3)
+                .map(|_| window.add_tab().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+    window
+        .with(|window| window.activate_tab(tabs[0]))
+        .unwrap()
+        .unwrap();
+    let group = window
+        .with(|window| window.create_tab_group(&tabs[1..], "group"))
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    platform.advance(Duration::from_secs(1));
+    let collapsed = |window: &Hosted<Window<HeadlessPlatform>>| {
+        window
+            .with(|window| window.tab_group(group).unwrap().collapsed)
+            .unwrap()
+    };
+
+    // The label sits between the first tab and the group's tabs
+    platform.click(id, Point::new(24, 20));
+    assert!(collapsed(&window));
+
+    platform.advance(Duration::from_secs(1));
+    platform.click(id, Point::new(24, 20));
+    assert!(!collapsed(&window));
+}
+
+#[test]
+fn a_group_label_shows_the_name_and_is_as_wide_as_it() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = open_tabs(&window, 3);
+    let group = window
+        .with(|window| window.create_tab_group(&tabs[1..], "group"))
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    platform.advance(Duration::from_secs(1));
+
+    // The name is 29 px wide at 96 DPI, with the padding the label spans 16 to 57
+    platform.paint(id);
+    let text = Theme::default().group_text;
+    let label = platform
+        .frames(id)
+        .last()
+        .unwrap()
+        .iter()
+        .find_map(|op| match op {
+            DrawOp::Text(name, origin, color) if *color == text => Some((name.clone(), *origin)),
+            _ => None,
+        });
+    let (name, origin) = label.unwrap();
+    assert_eq!(name, "group");
+    assert_eq!(origin.x, 22);
+    assert_eq!(Font::new(Dpi::DEFAULT).text_width(&name), 29);
+
+    let collapsed = |window: &Hosted<Window<HeadlessPlatform>>| {
+        window
+            .with(|window| window.tab_group(group).unwrap().collapsed)
+            .unwrap()
+    };
+    platform.click(id, Point::new(50, 20));
+    assert!(collapsed(&window));
+
+    // Without a name it keeps a small label
+    window
+        .with(|window| {
+            window.rename_tab_group(group, "").unwrap();
+            window.set_tab_group_collapsed(group, false).unwrap();
+        })
+        .unwrap();
+    platform.advance(Duration::from_secs(1));
+    platform.click(id, Point::new(24, 20));
+    assert!(collapsed(&window));
+    platform.advance(Duration::from_secs(1));
+    platform.click(id, Point::new(24, 20));
+    platform.advance(Duration::from_secs(1));
+    platform.click(id, Point::new(50, 20));
+    assert!(!collapsed(&window));
+}
+
+#[test]
+fn groups_come_back_with_the_session() {
+    let (_platform, window) = open();
+    let tabs = open_tabs(&window, 4);
+    let saved = window
+        .with(|window| {
+            let group = window
+                .create_tab_group(&tabs[1..3], "work")
+                .unwrap()
+                .unwrap();
+            window.set_tab_group_color(group, 4).unwrap();
+            window.set_tab_group_collapsed(group, true).unwrap();
+            window.activate_tab(tabs[3]).unwrap();
+            window.session().unwrap()
+        })
+        .unwrap();
+    assert_eq!(
+        saved.groups,
+        [GroupSession {
+            name: String::from("work"),
+            color: 4,
+            collapsed: true,
+            tabs: vec![1, 2],
+        }]
+    );
+
+    let (_platform, restored) = open();
+    let again = restored
+        .with(|window| {
+            window.restore_session(&saved).unwrap();
+            window.session().unwrap()
+        })
+        .unwrap();
+    assert_eq!(again.groups, saved.groups);
+    assert_eq!(again.active, Some(3));
+
+    let tabs = restored.with(|window| window.tabs()).unwrap();
+    let group = restored
+        .with(|window| window.tab_group_of(tabs[1]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        restored
+            .with(|window| window.tab_group(group).unwrap())
+            .unwrap(),
+        TabGroup {
+            name: String::from("work"),
+            color: 4,
+            collapsed: true,
+        }
+    );
+    assert_eq!(
+        restored
+            .with(|window| window.tab_group_of(tabs[0]))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn dragging_a_group_label_moves_the_whole_group() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = window
+        .with(|window| {
+            (0..3)
+                .map(|_| window.add_tab().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap();
+    let group = window
+        .with(|window| window.create_tab_group(&tabs[1..], "group"))
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    platform.advance(Duration::from_secs(1));
+
+    platform.move_mouse(id, Point::new(24, 20));
+    platform.press(id, Point::new(24, 20), MouseButton::Left);
+    platform.move_mouse(id, Point::new(2, 20));
+    platform.release(id, Point::new(2, 20), MouseButton::Left);
+
+    assert_eq!(
+        window.with(|window| window.tabs()).unwrap(),
+        [tabs[1], tabs[2], tabs[0]]
+    );
+    assert!(!window
+        .with(|window| window.tab_group(group).unwrap().collapsed)
+        .unwrap());
+}
+
+fn open_tabs(window: &Hosted<Window<HeadlessPlatform>>, count: usize) -> Vec<TabId> {
+    window
+        .with(|window| {
+            (0..count)
+                .map(|_| window.add_tab().unwrap().unwrap())
+                .collect::<Vec<_>>()
+        })
+        .unwrap()
+}
+
+// Where the `n`th of a few unpinned tabs is, they are 10 px wide with 2 px between them.
+fn tab_pos(n: i32) -> Point {
+    Point::new(9 + n * 12, 20)
+}
+
+#[test]
+fn right_clicking_a_tab_shows_its_menu_and_runs_the_pick() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = open_tabs(&window, 3);
+    platform.advance(Duration::from_secs(1));
+
+    platform.release(id, tab_pos(0), MouseButton::Right);
+    assert!(window.with(|window| window.is_menu_open()).unwrap());
+    let labels = window.with(|window| window.menu_labels()).unwrap();
+    assert_eq!(labels.len(), 1);
+    assert_eq!(labels[0][0], "Close");
+
+    platform.press_key(id, Key::Char('r'));
+    platform.run_pending();
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), [tabs[0]]);
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+
+    // Dismissing the menu does nothing
+    platform.release(id, tab_pos(0), MouseButton::Right);
+    platform.press_key(id, Key::Escape);
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), [tabs[0]]);
+
+    // Away from the tabs there is no menu
+    platform.release(id, Point::new(300, 300), MouseButton::Right);
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+}
+
+#[test]
+fn clicking_outside_the_menu_dismisses_it() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = open_tabs(&window, 2);
+    platform.advance(Duration::from_secs(1));
+
+    platform.release(id, tab_pos(0), MouseButton::Right);
+    let bounds = window.with(|window| window.menu_bounds()).unwrap();
+    assert_eq!(bounds.len(), 1);
+    assert_eq!(
+        platform.popups(id),
+        window.with(|window| window.menu_windows()).unwrap()
+    );
+
+    platform.press(id, Point::new(300, 300), MouseButton::Left);
+    platform.release(id, Point::new(300, 300), MouseButton::Left);
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), tabs);
+}
+
+// The screen in the window's client coordinates.
+fn screen_in_client(platform: &HeadlessPlatform, id: WindowId) -> Rect {
+    let origin = platform.client_to_screen(id, Point::new(0, 0)).unwrap();
+    platform
+        .monitor(id)
+        .unwrap()
+        .unwrap()
+        .offset(-origin.x, -origin.y)
+}
+
+// Right-clicks the caption at `pos`, which opens the window menu there.
+fn open_window_menu(platform: &HeadlessPlatform, id: WindowId, pos: Point) {
+    platform.send(
+        id,
+        Event::NonClientMouseDown {
+            pos,
+            button: MouseButton::Right,
+        },
+    );
+    platform.release(id, pos, MouseButton::Right);
+}
+
+#[test]
+fn menus_reach_past_a_window_smaller_than_them() {
+    let (platform, window) = open();
+    let id = window.window();
+    platform
+        .set_window_bounds(id, Rect::new(600, 400, 700, 440))
+        .unwrap();
+
+    open_window_menu(&platform, id, Point::new(50, CAPTION_Y));
+    let bounds = window.with(|window| window.menu_bounds()).unwrap();
+    assert_eq!(bounds.len(), 1);
+    let client = platform.client_rect(id).unwrap();
+    assert!(bounds[0].height() > client.height());
+    assert!(bounds[0].bottom > client.bottom);
+    let screen = screen_in_client(&platform, id);
+    assert_eq!(bounds[0].intersection(&screen), bounds[0]);
+
+    // The popup is where the menu placed it
+    let popups = platform.popups(id);
+    assert_eq!(popups, window.with(|window| window.menu_windows()).unwrap());
+    assert_eq!(
+        platform.window_bounds(popups[0]).unwrap(),
+        bounds[0].offset(600, 400)
+    );
+
+    // Clicking an item on the popup picks it for the window
+    let minimize = window
+        .with(|window| window.menu_entry_bounds(0, 3))
+        .unwrap()
+        .unwrap();
+    let pos = Point::new(
+        (minimize.left + minimize.right) / 2 - bounds[0].left,
+        (minimize.top + minimize.bottom) / 2 - bounds[0].top,
+    );
+    platform.move_mouse(popups[0], pos);
+    platform.press(popups[0], pos, MouseButton::Left);
+    platform.release(popups[0], pos, MouseButton::Left);
+    platform.run_pending();
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+    assert!(platform.popups(id).is_empty());
+    assert_eq!(platform.commands(id), [SystemCommand::Minimize]);
+}
+
+#[test]
+fn menus_near_the_monitor_edge_flip_instead_of_leaving_it() {
+    let (platform, window) = open();
+    let id = window.window();
+    platform
+        .set_window_bounds(id, Rect::new(1840, 1030, 1920, 1080))
+        .unwrap();
+
+    let pos = Point::new(60, CAPTION_Y);
+    open_window_menu(&platform, id, pos);
+    let bounds = window.with(|window| window.menu_bounds()).unwrap();
+    assert_eq!(bounds.len(), 1);
+    let screen = screen_in_client(&platform, id);
+    assert_eq!(bounds[0].intersection(&screen), bounds[0]);
+    assert!(bounds[0].bottom <= pos.y);
+    assert!(bounds[0].right <= pos.x);
+    assert!(bounds[0].top < 0 && bounds[0].left < 0);
+}
+
+#[test]
+fn submenus_open_on_popups_of_their_own() {
+    let (platform, window) = open();
+    let id = window.window();
+    open_tabs(&window, 2);
+    platform.advance(Duration::from_secs(1));
+    window
+        .with(|window| {
+            window.set_tab_menu_items(|_, _| {
+                vec![MenuEntry::submenu(
+                    "Share",
+                    vec![
+                        MenuEntry::item(TabAction::App(1), "Mail"),
+                        MenuEntry::item(TabAction::App(2), "Link"),
+                    ],
+                )]
+            })
+        })
+        .unwrap();
+    platform
+        .set_window_bounds(id, Rect::new(600, 400, 1100, 440))
+        .unwrap();
+
+    platform.release(id, tab_pos(0), MouseButton::Right);
+    let labels = window.with(|window| window.menu_labels()).unwrap();
+    let share = labels[0].iter().position(|label| label == "Share").unwrap();
+    let bounds = window.with(|window| window.menu_bounds()).unwrap();
+    let entry = window
+        .with(|window| window.menu_entry_bounds(0, share))
+        .unwrap()
+        .unwrap();
+    let popup = platform.popups(id)[0];
+    platform.move_mouse(
+        popup,
+        Point::new(
+            entry.left + 2 - bounds[0].left,
+            entry.top + 2 - bounds[0].top,
+        ),
+    );
+
+    let bounds = window.with(|window| window.menu_bounds()).unwrap();
+    assert_eq!(bounds.len(), 2);
+    let popups = platform.popups(id);
+    assert_eq!(popups, window.with(|window| window.menu_windows()).unwrap());
+    for (popup, bounds) in popups.iter().zip(&bounds) {
+        assert_eq!(
+            platform.window_bounds(*popup).unwrap(),
+            bounds.offset(600, 400)
+        );
+    }
+    let client = platform.client_rect(id).unwrap();
+    assert!(bounds[1].bottom > client.bottom);
+}
+
+#[test]
+fn right_clicking_the_title_bar_shows_the_window_menu() {
+    let (platform, window) = open();
+    let id = window.window();
+    let pos = Point::new(300, CAPTION_Y);
+
+    platform.send(
+        id,
+        Event::NonClientMouseDown {
+            pos,
+            button: MouseButton::Right,
+        },
+    );
+    platform.release(id, pos, MouseButton::Right);
+
+    let labels = window.with(|window| window.menu_labels()).unwrap();
+    assert_eq!(
+        labels,
+        [["Restore", "Move", "Size", "Minimize", "Maximize", "-", "Close"]]
+    );
+
+    // The picked command goes to the system like a caption button press
+    platform.press_key(id, Key::Char('n'));
+    platform.run_pending();
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+    assert_eq!(platform.commands(id), [SystemCommand::Minimize]);
+    assert!(platform.is_minimized(id));
+}
+
+#[test]
+fn escape_closes_the_window_menu() {
+    let (platform, window) = open();
+    let id = window.window();
+    let pos = Point::new(300, CAPTION_Y);
+
+    platform.send(
+        id,
+        Event::NonClientMouseDown {
+            pos,
+            button: MouseButton::Right,
+        },
+    );
+    platform.release(id, pos, MouseButton::Right);
+    assert!(window.with(|window| window.is_menu_open()).unwrap());
+
+    platform.press_key(id, Key::Escape);
+    platform.run_pending();
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+    assert!(platform.commands(id).is_empty());
+}
+
+#[test]
+fn duplicating_opens_a_copy_next_to_the_tab() {
+    let (_platform, window) = open();
+    let tabs = open_tabs(&window, 2);
+    window
+        .with(|window| window.set_tab_title(tabs[0], "first"))
+        .unwrap()
+        .unwrap();
+
+    window
+        .with(|window| window.run_tab_action(tabs[0], TabAction::Duplicate))
+        .unwrap()
+        .unwrap();
+    let open = window.with(|window| window.tabs()).unwrap();
+    assert_eq!(open.len(), 3);
+    assert_eq!(open[0], tabs[0]);
+    assert_eq!(open[2], tabs[1]);
+    assert_eq!(
+        window.with(|window| window.active_tab()).unwrap(),
+        Some(open[1])
+    );
+    assert_eq!(
+        window
+            .with(|window| window.session())
+            .unwrap()
+            .unwrap()
+            .tabs[1]
+            .title,
+        "first"
+    );
+}
+
+#[test]
+fn renaming_a_tab_types_its_new_title() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = open_tabs(&window, 2);
+    platform.advance(Duration::from_secs(1));
+    window
+        .with(|window| window.set_tab_title(tabs[0], "ab"))
+        .unwrap()
+        .unwrap();
+
+    platform.release(id, tab_pos(0), MouseButton::Right);
+    platform.press_key(id, Key::Char('n'));
+    assert_eq!(
+        window.with(|window| window.renaming_tab()).unwrap(),
+        Some((tabs[0], String::from("ab")))
+    );
+
+    // The text the menu's key typed doesn't go to the editor it opened
+    platform.send(id, Event::Text('n'));
+    platform.press_key(id, Key::Backspace);
+    platform.type_text(id, "X 1é€");
+    platform.paint(id);
+    assert_eq!(
+        window.with(|window| window.renaming_tab()).unwrap(),
+        Some((tabs[0], String::from("aX 1é€")))
+    );
+    // Keys go to the editor, not the tabs
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), tabs);
+
+    platform.press_key(id, Key::Enter);
+    assert_eq!(window.with(|window| window.renaming_tab()).unwrap(), None);
+    let session = window.with(|window| window.session()).unwrap().unwrap();
+    assert_eq!(session.tabs[0].title, "aX 1é€");
+
+    // Escape leaves the title as it was
+    platform.release(id, tab_pos(1), MouseButton::Right);
+    platform.press_key(id, Key::Char('n'));
+    platform.type_text(id, "z");
+    platform.press_key(id, Key::Escape);
+    let session = window.with(|window| window.session()).unwrap().unwrap();
+    assert_eq!(session.tabs[1].title, "");
+
+    // Pressing elsewhere keeps what was typed
+    platform.release(id, tab_pos(1), MouseButton::Right);
+    platform.press_key(id, Key::Char('n'));
+    platform.type_text(id, "z");
+    platform.press(id, Point::new(300, 300), MouseButton::Left);
+    platform.release(id, Point::new(300, 300), MouseButton::Left);
+    assert_eq!(window.with(|window| window.renaming_tab()).unwrap(), None);
+    let session = window.with(|window| window.session()).unwrap().unwrap();
+    assert_eq!(session.tabs[1].title, "z");
+}
+
+#[test]
+fn moving_a_tab_opens_a_window_with_it() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = open_tabs(&window, 2);
+    platform.advance(Duration::from_secs(1));
+    window
+        .with(|window| {
+            window.set_tab_title(tabs[1], "moving").unwrap();
+            window.set_tab_pinned(tabs[1], true).unwrap();
+        })
+        .unwrap();
+    platform.advance(Duration::from_secs(1));
+
+    // Pinned, the tab went first
+    platform.release(id, tab_pos(0), MouseButton::Right);
+    platform.press_key(id, Key::Char('m'));
+    platform.run_pending();
+
+    let windows = platform.windows();
+    assert_eq!(windows.len(), 2);
+    let moved = windows[1];
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), [tabs[0]]);
+    // It went elsewhere rather than closed, there is nothing to reopen
+    assert!(!window.with(|window| window.can_reopen_tab()).unwrap());
+
+    let session = window
+        .with(|window| window.with_moved_window(moved, |moved| moved.session()))
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(session.tabs.len(), 1);
+    assert_eq!(session.tabs[0].title, "moving");
+    assert!(session.tabs[0].pinned);
+    assert_eq!(session.active, Some(0));
+
+    // The only tab of a window stays where it is
+    let menu = window.with(|window| window.tab_menu(tabs[0])).unwrap();
+    assert!(menu.iter().any(
+        |entry| entry.text().as_deref() == Some("Move to new window") && entry.action().is_none()
+    ));
+
+    // The app goes on until its last window is closed
+    platform.click(moved, caption_button(&platform, &window, 0));
+    platform.run_pending();
+    assert!(!platform.is_open(moved));
+    assert!(!platform.has_quit());
+
+    platform.click(id, caption_button(&platform, &window, 0));
+    platform.run_pending();
+    assert!(platform.has_quit());
+}
+
+#[test]
+fn the_app_handles_its_own_tab_menu_items() {
+    let (platform, window) = open();
+    let id = window.window();
+    let tabs = open_tabs(&window, 2);
+    platform.advance(Duration::from_secs(1));
+
+    // Renaming and moving need nothing from the app
+    let disabled = window
+        .with(|window| window.tab_menu(tabs[0]))
+        .unwrap()
+        .into_iter()
+        .filter(|entry| entry.label().is_some() && entry.action().is_none())
+        .filter_map(|entry| entry.text())
+        .collect::<Vec<_>>();
+    assert!(!disabled.contains(&String::from("Rename")));
+    assert!(!disabled.contains(&String::from("Move to new window")));
+
+    let handled = Rc::new(RefCell::new(Vec::new()));
+    window
+        .with(|window| {
+            let handled = handled.clone();
+            window.set_tab_action_handler(move |tab, action, saved| {
+                handled
+                    .borrow_mut()
+                    .push((tab, action, saved.title.clone()));
+            });
+            window.set_tab_menu_items(|_, _| vec![MenuEntry::item(TabAction::App(7), "Share")]);
+            window.set_tab_title(tabs[1], "shared").unwrap();
+        })
+        .unwrap();
+
+    platform.release(id, tab_pos(1), MouseButton::Right);
+    let labels = window.with(|window| window.menu_labels()).unwrap();
+    assert_eq!(labels[0].last().unwrap(), "Share");
+    platform.press_key(id, Key::End);
+    platform.press_key(id, Key::Enter);
+    platform.run_pending();
+
+    assert_eq!(
+        *handled.borrow(),
+        [(tabs[1], TabAction::App(7), String::from("shared"))]
+    );
+    assert_eq!(window.with(|window| window.tabs()).unwrap(), tabs);
+
+    // A window a tab moved to takes the app's items along
+    let moved = window
+        .with(|window| window.move_tab_to_new_window(tabs[1]))
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(platform.windows(), [id, moved]);
+    // The tab is a new one there
+    let menu = window
+        .with(|window| window.with_moved_window(moved, |moved| moved.tab_menu(moved.tabs()[0])))
+        .unwrap()
+        .unwrap();
+    assert!(menu
+        .iter()
+        .any(|entry| entry.text().as_deref() == Some("Share")));
+}
+
+#[test]
+fn all_windows_are_saved_together_until_they_close() {
+    let (platform, window) = open();
+    let saved = Rc::new(RefCell::new(Vec::new()));
+    window
+        .with(|window| {
+            let saved = saved.clone();
+            window.set_session_handler(move |session| saved.borrow_mut().push(session));
+            window.set_autosave(Duration::from_secs(2), Duration::from_secs(30));
+        })
+        .unwrap();
+    let tabs = open_tabs(&window, 3);
+    window
+        .with(|window| {
+            window.set_tab_title(tabs[1], "moving").unwrap();
+            window.close_tab(tabs[2]).unwrap();
+        })
+        .unwrap();
+    let moved = window
+        .with(|window| window.move_tab_to_new_window(tabs[1]))
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    window
+        .with(|window| {
+            window.with_moved_window(moved, |moved| {
+                let tab = moved.add_tab().unwrap().unwrap();
+                moved.close_tab(tab).unwrap();
+            })
+        })
+        .unwrap()
+        .unwrap();
+
+    // Either window autosaves both
+    platform.advance(Duration::from_secs(3));
+    let session = saved.borrow().last().unwrap().clone();
+    assert_eq!(session.windows.len(), 2);
+    assert_eq!(session.windows[0].tabs.len(), 1);
+    assert_eq!(session.windows[1].tabs[0].title, "moving");
+    let closed = |session: &Session| {
+        session
+            .closed_tabs
+            .iter()
+            .map(|closed| closed.window)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(closed(&session), [0, 1]);
+    assert_eq!(
+        window.with(|window| window.app_session()).unwrap().unwrap(),
+        session
+    );
+
+    // Once a window closes its tabs are closed tabs of a window the session doesn't have
+    platform.send(moved, Event::CloseRequested);
+    platform.run_pending();
+    assert!(!platform.is_open(moved));
+    let session = saved.borrow().last().unwrap().clone();
+    assert_eq!(session.windows.len(), 1);
+    assert_eq!(closed(&session), [0, 1, 1]);
+    assert_eq!(session.closed_tabs[2].tab.title, "moving");
+}
+
+#[test]
+fn restoring_a_session_opens_all_its_windows() {
+    let (platform, window) = open();
+    let id = window.window();
+    let saved_window = |titles: &[&str]| WindowSession {
+        tabs: titles
+            .iter()
+            .map(|title| TabSession {
+                title: String::from(*title),
+                ..TabSession::default()
+            })
+            .collect(),
+        active: Some(0),
+        ..WindowSession::default()
+    };
+    let closed = |window| ClosedTab {
+        tab: TabSession::default(),
+        index: 0,
+        window,
+    };
+    let session = Session {
+        windows: vec![saved_window(&["a", "b"]), saved_window(&["c"])],
+        closed_tabs: vec![closed(1), closed(5)],
+    };
+
+    window
+        .with(|window| window.restore_app_session(&session))
+        .unwrap()
+        .unwrap();
+    let windows = platform.windows();
+    assert_eq!(windows.len(), 2);
+    let titles = |session: &WindowSession| {
+        session
+            .tabs
+            .iter()
+            .map(|tab| tab.title.clone())
+            .collect::<Vec<_>>()
+    };
+    let restored = window.with(|window| window.app_session()).unwrap().unwrap();
+    assert_eq!(restored.windows.len(), 2);
+    assert_eq!(titles(&restored.windows[0]), ["a", "b"]);
+    assert_eq!(titles(&restored.windows[1]), ["c"]);
+    assert_eq!(restored.closed_tabs, [closed(1), closed(2)]);
+
+    // A window opened later isn't taken for the one that is gone
+    let tab = window.with(|window| window.tabs()[1]).unwrap();
+    window
+        .with(|window| window.move_tab_to_new_window(tab))
+        .unwrap()
+        .unwrap();
+    assert_eq!(platform.windows().len(), 3);
+    let restored = window.with(|window| window.app_session()).unwrap().unwrap();
+    assert_eq!(restored.closed_tabs, [closed(1), closed(3)]);
+    assert_eq!(platform.windows()[0], id);
+}
+
+#[test]
+fn a_question_is_asked_in_a_menu_where_there_is_no_dialog() {
+    let (platform, window) = open();
+    let id = window.window();
+    let answers = Rc::new(RefCell::new(Vec::new()));
+    let ask = |answers: &Rc<RefCell<Vec<bool>>>| {
+        let answers = answers.clone();
+        window
+            .with(|window| {
+                window.ask("Tabs", "Go on?", move |_, yes| {
+                    answers.borrow_mut().push(yes);
+                    Ok(())
+                })
+            })
+            .unwrap()
+            .unwrap();
+    };
+
+    ask(&answers);
+    assert!(window.with(|window| window.is_asking()).unwrap());
+    let labels = window.with(|window| window.menu_labels()).unwrap();
+    assert_eq!(labels, [["Go on?", "-", "Yes", "No"]]);
+
+    // It stays open until it is answered
+    platform.send(id, Event::Activate(false));
+    assert!(window.with(|window| window.is_menu_open()).unwrap());
+    platform.press_key(id, Key::Char('y'));
+    platform.run_pending();
+    assert!(!window.with(|window| window.is_asking()).unwrap());
+    assert_eq!(*answers.borrow(), [true]);
+
+    // Dismissing the menu answers no
+    ask(&answers);
+    platform.press_key(id, Key::Escape);
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+    assert_eq!(*answers.borrow(), [true, false]);
+
+    // Where there is a dialog it answers instead
+    platform.answer_next(true);
+    ask(&answers);
+    assert!(!window.with(|window| window.is_menu_open()).unwrap());
+    assert_eq!(*answers.borrow(), [true, false, true]);
+    assert_eq!(platform.questions(), ["Go on?", "Go on?", "Go on?"]);
+}
@@ -0,0 +1,244 @@
+// Runs the app against Xvfb with the test acting as the window manager. Skipped when Xvfb
+// isn't installed.
+#![cfg(target_os = "linux")]
+
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use testwindowtabs::autosave::RunMarker;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ChangeWindowAttributesAux, ConfigureWindowAux, ConnectionExt as _, EventMask,
+    ImageFormat, Window,
+};
+use x11rb::protocol::xtest::ConnectionExt as _;
+use x11rb::protocol::Event;
+use x11rb::rust_connection::RustConnection;
+
+const DISPLAY: u32 = 73;
+const TIMEOUT: Duration = Duration::from_secs(10);
+const WINDOW_TITLE: &str = "the testwindowtabs application";
+const TITLE_BG_PIXEL: u32 = 0x96c8b4;
+
+// Kills whatever is still running when a test fails half way.
+struct Kill(Child);
+
+impl Drop for Kill {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+struct Session {
+    conn: RustConnection,
+    root: Window,
+    app: Kill,
+    _server: Kill,
+    session: PathBuf,
+}
+
+impl Session {
+    fn start(display: u32) -> Option<Self> {
+        let server = match Command::new("Xvfb")
+            .arg(format!(":{}", display))
+            .args(["-screen", "0", "1024x768x24", "-nolisten", "tcp"])
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(server) => Kill(server),
+            Err(_) => {
+                eprintln!("Xvfb isn't installed, skipping");
+                return None;
+            }
+        };
+
+        let socket = format!("/tmp/.X11-unix/X{}", display);
+        wait_until(|| Path::new(&socket).exists());
+
+        let name = format!(":{}", display);
+        let (conn, screen) = RustConnection::connect(Some(&name)).unwrap();
+        let root = conn.setup().roots[screen].root;
+
+        // Be the window manager, so client messages to the root window come here
+        let mask = EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY;
+        conn.change_window_attributes(root, &ChangeWindowAttributesAux::new().event_mask(mask))
+            .unwrap()
+            .check()
+            .unwrap();
+
+        // Keeps the user's own session out of it
+        let session =
+            std::env::temp_dir().join(format!("testwindowtabs-x11-{}.session", std::process::id()));
+        let app = Kill(
+            Command::new(env!("CARGO_BIN_EXE_testwindowtabs"))
+                .env("DISPLAY", &name)
+                .env("TESTWINDOWTABS_SESSION", &session)
+                .spawn()
+                .unwrap(),
+        );
+
+        Some(Self {
+            conn,
+            root,
+            app,
+            _server: server,
+            session,
+        })
+    }
+
+    fn atom(&self, name: &str) -> Atom {
+        self.conn
+            .intern_atom(false, name.as_bytes())
+            .unwrap()
+            .reply()
+            .unwrap()
+            .atom
+    }
+
+    // Maps and configures windows like a window manager that doesn't reparent, until the
+    // app's window is up.
+    fn wait_for_window(&self) -> Window {
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            assert!(Instant::now() < deadline, "the window never showed up");
+
+            match self.conn.wait_for_event().unwrap() {
+                Event::MapRequest(event) => {
+                    self.conn.map_window(event.window).unwrap();
+                    self.conn.flush().unwrap();
+
+                    if self.title(event.window) == WINDOW_TITLE {
+                        return event.window;
+                    }
+                }
+                Event::ConfigureRequest(event) => {
+                    let aux = ConfigureWindowAux::from_configure_request(&event);
+                    self.conn.configure_window(event.window, &aux).unwrap();
+                    self.conn.flush().unwrap();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn title(&self, window: Window) -> String {
+        let reply = self
+            .conn
+            .get_property(false, window, AtomEnum::WM_NAME, AtomEnum::STRING, 0, 256)
+            .unwrap()
+            .reply()
+            .unwrap();
+        String::from_utf8_lossy(&reply.value).into_owned()
+    }
+
+    fn pixel(&self, x: i16, y: i16) -> u32 {
+        let reply = self
+            .conn
+            .get_image(ImageFormat::Z_PIXMAP, self.root, x, y, 1, 1, !0)
+            .unwrap()
+            .reply()
+            .unwrap();
+        u32::from_le_bytes(reply.data[..4].try_into().unwrap()) & 0xffffff
+    }
+
+    fn press(&self, x: i16, y: i16) {
+        let conn = &self.conn;
+        conn.xtest_fake_input(6, 0, 0, self.root, x, y, 0).unwrap();
+        conn.xtest_fake_input(4, 1, 0, self.root, 0, 0, 0).unwrap();
+        conn.flush().unwrap();
+    }
+
+    fn release(&self) {
+        self.conn
+            .xtest_fake_input(5, 1, 0, self.root, 0, 0, 0)
+            .unwrap();
+        self.conn.flush().unwrap();
+    }
+
+    // The next `_NET_WM_MOVERESIZE` direction the app asks for.
+    fn wait_for_move_resize(&self, window: Window) -> u32 {
+        let move_resize = self.atom("_NET_WM_MOVERESIZE");
+        let deadline = Instant::now() + TIMEOUT;
+        loop {
+            assert!(Instant::now() < deadline, "no _NET_WM_MOVERESIZE");
+
+            match self.conn.poll_for_event().unwrap() {
+                Some(Event::ClientMessage(event))
+                    if event.window == window && event.type_ == move_resize =>
+                {
+                    return event.data.as_data32()[2];
+                }
+                Some(_) => {}
+                None => thread::sleep(Duration::from_millis(10)),
+            }
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.session);
+        let _ = std::fs::remove_file(RunMarker::path_for(&self.session));
+    }
+}
+
+fn wait_until(mut condition: impl FnMut() -> bool) {
+    let deadline = Instant::now() + TIMEOUT;
+    while !condition() {
+        assert!(Instant::now() < deadline, "timed out");
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn frameless_window_hands_moving_and_resizing_to_the_window_manager() {
+    let mut session = match Session::start(DISPLAY) {
+        Some(session) => session,
+        None => return,
+    };
+    let window = session.wait_for_window();
+
+    // No decorations from the window manager
+    let hints_atom = session.atom("_MOTIF_WM_HINTS");
+    let hints = session
+        .conn
+        .get_property(false, window, hints_atom, hints_atom, 0, 5)
+        .unwrap()
+        .reply()
+        .unwrap();
+    let hints = hints.value32().unwrap().collect::<Vec<_>>();
+    assert_eq!(hints[0] & 2, 2);
+    assert_eq!(hints[2], 0);
+
+    // The window is 500 pixels wide and the drag area sits left of the three caption buttons
+    let (drag_x, drag_y) = (500 - 3 * 47 - 50, 10);
+    wait_until(|| session.pixel(drag_x, drag_y) == TITLE_BG_PIXEL);
+
+    session.press(drag_x, drag_y);
+    assert_eq!(session.wait_for_move_resize(window), 8);
+    session.release();
+
+    // Past the double click time, so this is a new drag
+    thread::sleep(Duration::from_millis(500));
+
+    session.press(498, 250);
+    assert_eq!(session.wait_for_move_resize(window), 3);
+    session.release();
+
+    // The close button ends the app
+    session.press(500 - 20, 15);
+    session.release();
+
+    let deadline = Instant::now() + TIMEOUT;
+    let status = loop {
+        if let Some(status) = session.app.0.try_wait().unwrap() {
+            break status;
+        }
+        assert!(Instant::now() < deadline, "the app didn't exit");
+        thread::sleep(Duration::from_millis(20));
+    };
+    assert!(status.success());
+}